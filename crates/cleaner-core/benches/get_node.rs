@@ -0,0 +1,72 @@
+//! Lookups in trees of a million nodes, one folder holding all of them and
+//! a thousand folders of a thousand files. Before the children were indexed
+//! by name a lookup went through every sibling on the way, run it against
+//! an older checkout to compare. Building the trees is measured too, every
+//! node went into an allocation of its own before the nodes of a tree
+//! shared an arena.
+
+use std::{ffi::OsString, hint::black_box, path::PathBuf};
+
+use cleaner_core::tree::{Tree, node::Node};
+use criterion::{Criterion, criterion_group, criterion_main};
+
+const NODES: usize = 1_000_000;
+
+fn file(i: usize) -> Node {
+    Node::new(OsString::from(format!("file{}", i)), false, false)
+}
+
+fn wide() -> Tree {
+    let root = PathBuf::from("/wide");
+    let mut tree = Tree::from_node(Node::new(root.clone().into_os_string(), true, false));
+    for i in 0..NODES {
+        let _ = tree.insert(&root, file(i));
+    }
+    tree
+}
+
+fn nested() -> Tree {
+    let root = PathBuf::from("/nested");
+    let mut tree = Tree::from_node(Node::new(root.clone().into_os_string(), true, false));
+    for i in 0..1000 {
+        let name = format!("folder{}", i);
+        let _ = tree.insert(&root, Node::new(OsString::from(&name), true, false));
+        let folder = root.join(name);
+        for j in 0..NODES / 1000 {
+            let _ = tree.insert(&folder, file(j));
+        }
+    }
+    tree
+}
+
+fn get_node(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_node");
+
+    let tree = wide();
+    // the last sibling, the worst case of a scan through the children
+    let last = PathBuf::from(format!("/wide/file{}", NODES - 1));
+    group.bench_function("wide folder", |b| {
+        b.iter(|| tree.get_node(black_box(&last)))
+    });
+    let missing = PathBuf::from("/wide/missing");
+    group.bench_function("wide folder, missing", |b| {
+        b.iter(|| tree.get_node(black_box(&missing)))
+    });
+
+    let tree = nested();
+    let last = PathBuf::from(format!("/nested/folder999/file{}", NODES / 1000 - 1));
+    group.bench_function("nested folders", |b| {
+        b.iter(|| tree.get_node(black_box(&last)))
+    });
+    group.finish();
+
+    // every node of a scan goes into the arena of its tree one by one
+    let mut group = c.benchmark_group("insert");
+    group.sample_size(10);
+    group.bench_function("wide folder", |b| b.iter(wide));
+    group.bench_function("nested folders", |b| b.iter(nested));
+    group.finish();
+}
+
+criterion_group!(benches, get_node);
+criterion_main!(benches);
@@ -0,0 +1,324 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    database::{Database, to_io},
+    models::now_secs,
+};
+
+/**
+ * entries the feed keeps, older ones are dropped as new ones come in
+ */
+pub const KEEP_ENTRIES: i64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivityKind {
+    /// a scan finished, with the bytes and entries it found
+    Scan,
+    /// files were removed or moved away, with the bytes freed
+    Cleanup,
+    /// a dashboard alert was raised
+    Alert,
+    /// a root took more or less space than at its scan before
+    SpaceTrend,
+}
+
+impl ActivityKind {
+    pub const ALL: [ActivityKind; 4] = [
+        ActivityKind::Scan,
+        ActivityKind::Cleanup,
+        ActivityKind::Alert,
+        ActivityKind::SpaceTrend,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ActivityKind::Scan => "scan",
+            ActivityKind::Cleanup => "cleanup",
+            ActivityKind::Alert => "alert",
+            ActivityKind::SpaceTrend => "spaceTrend",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/**
+ * one thing which happened, as the history screen lists it
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityEntry {
+    pub id: i64,
+    pub kind: ActivityKind,
+    /// unix seconds
+    pub at: u64,
+    /// the root, the volume or the first path it is about
+    pub path: Option<PathBuf>,
+    pub summary: String,
+    /// bytes found, freed or left free, the growth for a trend, which is below zero when it shrank
+    pub bytes: Option<i64>,
+    /// entries found or removed
+    pub count: Option<u64>,
+}
+
+/**
+ * one page of the feed, the latest first
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityPage {
+    pub entries: Vec<ActivityEntry>,
+    /// the entries of the kinds asked for over all pages
+    pub total: usize,
+    /// where the next page starts, none after the last one
+    pub next_offset: Option<usize>,
+}
+
+/**
+ * Scans, cleanups, alerts and space trends in one feed, in the app database
+ * next to the audit log. The audit log tells what each step of an operation
+ * did, the feed is the history the user browses.
+ */
+#[derive(Clone)]
+pub struct ActivityFeed {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl ActivityFeed {
+    pub fn new(database: &Database) -> io::Result<Self> {
+        let shared = database.connection();
+        let conn = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS activity (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                at INTEGER NOT NULL,
+                path TEXT,
+                summary TEXT NOT NULL,
+                bytes INTEGER,
+                count INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS activity_kind ON activity (kind, id);",
+        )
+        .map_err(to_io)?;
+
+        Ok(Self {
+            conn: Arc::clone(&shared),
+        })
+    }
+
+    pub fn in_memory() -> io::Result<Self> {
+        Self::new(&Database::in_memory()?)
+    }
+
+    /**
+     * add what just happened to the feed, the id it got
+     */
+    pub fn record(
+        &self,
+        kind: ActivityKind,
+        path: Option<&Path>,
+        summary: &str,
+        bytes: Option<i64>,
+        count: Option<u64>,
+    ) -> io::Result<i64> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "INSERT INTO activity (kind, at, path, summary, bytes, count)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                kind.name(),
+                now_secs() as i64,
+                path.map(|path| path.to_string_lossy()),
+                summary,
+                bytes,
+                count.map(|count| count as i64)
+            ],
+        )
+        .map_err(to_io)?;
+        let id = conn.last_insert_rowid();
+        conn.execute(
+            "DELETE FROM activity WHERE id <= ?1",
+            params![id - KEEP_ENTRIES],
+        )
+        .map_err(to_io)?;
+        Ok(id)
+    }
+
+    /**
+     * `limit` entries from `offset` on, the latest first, only those of
+     * `kinds` unless it is empty
+     */
+    pub fn page(
+        &self,
+        offset: usize,
+        limit: usize,
+        kinds: &[ActivityKind],
+    ) -> io::Result<ActivityPage> {
+        let kinds = (!kinds.is_empty())
+            .then(|| {
+                serde_json::to_string(&kinds.iter().map(|kind| kind.name()).collect::<Vec<_>>())
+            })
+            .transpose()?;
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let total: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM activity
+                    WHERE ?1 IS NULL OR kind IN (SELECT value FROM json_each(?1))",
+                params![kinds],
+                |row| row.get(0),
+            )
+            .map_err(to_io)?;
+        let mut statement = conn
+            .prepare(
+                "SELECT id, kind, at, path, summary, bytes, count FROM activity
+                    WHERE ?1 IS NULL OR kind IN (SELECT value FROM json_each(?1))
+                    ORDER BY id DESC LIMIT ?2 OFFSET ?3",
+            )
+            .map_err(to_io)?;
+        let rows = statement
+            .query_map(params![kinds, limit as i64, offset as i64], row)
+            .map_err(to_io)?;
+
+        let entries = collect(rows)?;
+        let total = total as usize;
+        let end = offset.saturating_add(limit);
+        Ok(ActivityPage {
+            entries,
+            total,
+            next_offset: (end < total).then_some(end),
+        })
+    }
+
+    /**
+     * the entries of `kinds` recorded from `from` until before `to`, in unix
+     * seconds, oldest first
+     */
+    pub fn between(
+        &self,
+        from: u64,
+        to: u64,
+        kinds: &[ActivityKind],
+    ) -> io::Result<Vec<ActivityEntry>> {
+        let kinds =
+            serde_json::to_string(&kinds.iter().map(|kind| kind.name()).collect::<Vec<_>>())?;
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut statement = conn
+            .prepare(
+                "SELECT id, kind, at, path, summary, bytes, count FROM activity
+                    WHERE at >= ?1 AND at < ?2
+                        AND kind IN (SELECT value FROM json_each(?3))
+                    ORDER BY id",
+            )
+            .map_err(to_io)?;
+        let rows = statement
+            .query_map(params![from as i64, to as i64, kinds], row)
+            .map_err(to_io)?;
+        collect(rows)
+    }
+}
+
+type Row = (
+    i64,
+    String,
+    i64,
+    Option<String>,
+    String,
+    Option<i64>,
+    Option<i64>,
+);
+
+fn row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Row> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+    ))
+}
+
+fn collect(rows: impl Iterator<Item = rusqlite::Result<Row>>) -> io::Result<Vec<ActivityEntry>> {
+    let mut entries = Vec::new();
+    for row in rows {
+        let (id, kind, at, path, summary, bytes, count) = row.map_err(to_io)?;
+        // a kind recorded by a newer version is left out
+        let Some(kind) = ActivityKind::parse(&kind) else {
+            continue;
+        };
+        entries.push(ActivityEntry {
+            id,
+            kind,
+            at: at as u64,
+            path: path.map(PathBuf::from),
+            summary,
+            bytes,
+            count: count.map(|count| count as u64),
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed_paged_by_kind() {
+        let feed = ActivityFeed::in_memory().unwrap();
+        let root = PathBuf::from("/data");
+        feed.record(
+            ActivityKind::Scan,
+            Some(&root),
+            "scanned /data",
+            Some(300),
+            Some(2),
+        )
+        .unwrap();
+        feed.record(
+            ActivityKind::SpaceTrend,
+            Some(&root),
+            "/data shrank",
+            Some(-50),
+            None,
+        )
+        .unwrap();
+        let cleanup = feed
+            .record(
+                ActivityKind::Cleanup,
+                None,
+                "run_cleanup removed 2 entries",
+                Some(120),
+                Some(2),
+            )
+            .unwrap();
+
+        let all = feed.page(0, 2, &[]).unwrap();
+        assert_eq!(all.total, 3);
+        assert_eq!(all.next_offset, Some(2));
+        assert_eq!(all.entries[0].id, cleanup);
+        assert_eq!(all.entries[0].path, None);
+        assert_eq!(all.entries[1].bytes, Some(-50));
+        assert_eq!(feed.page(2, 2, &[]).unwrap().next_offset, None);
+
+        let scans = feed
+            .page(0, 10, &[ActivityKind::Scan, ActivityKind::Alert])
+            .unwrap();
+        assert_eq!(scans.total, 1);
+        assert_eq!(scans.entries[0].path, Some(root));
+        assert_eq!(scans.entries[0].count, Some(2));
+    }
+}
@@ -0,0 +1,63 @@
+use std::{fs::Metadata, path::Path};
+
+/**
+ * Bytes `path` takes on disk, its allocated 512 byte blocks. Less than its
+ * length for a sparse file, more for a small one filling a whole block.
+ */
+#[cfg(unix)]
+pub fn size_on_disk(_path: &Path, metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.blocks() * 512
+}
+
+/**
+ * Bytes `path` takes on disk. Compressed and sparse files report what they
+ * really take, a directory or a file which can't be opened counts its length.
+ */
+#[cfg(windows)]
+pub fn size_on_disk(path: &Path, metadata: &Metadata) -> u64 {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::{
+        Foundation::{GetLastError, NO_ERROR},
+        Storage::FileSystem::{GetCompressedFileSizeW, INVALID_FILE_SIZE},
+    };
+
+    if metadata.is_dir() {
+        return metadata.len();
+    }
+    let wide: Vec<u16> = crate::long_path::extended(path)
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let mut high = 0u32;
+    let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+    // the low word of a size can be all ones, only an error code tells a failure
+    if low == INVALID_FILE_SIZE && unsafe { GetLastError() } != NO_ERROR {
+        return metadata.len();
+    }
+    ((high as u64) << 32) | low as u64
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn size_on_disk(_path: &Path, metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sparse_file_takes_less_on_disk() {
+        let path = temp_path("sparse.bin");
+        let file = std::fs::File::create(&path).unwrap();
+        file.set_len(64 * 1024 * 1024).unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert!(size_on_disk(&path, &metadata) < metadata.len());
+        let _ = std::fs::remove_file(&path);
+    }
+}
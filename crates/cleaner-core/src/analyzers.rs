@@ -0,0 +1,435 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use wasmi::{
+    Config, Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+};
+
+use crate::{lossless, tree::Tree};
+
+/**
+ * extension of the modules an analyzer directory is searched for
+ */
+pub const EXTENSION: &str = "wasm";
+
+/**
+ * entries handed to an analyzer per call
+ */
+pub const BATCH_SIZE: usize = 1000;
+
+/**
+ * instructions one call may run, a module looping forever is stopped there
+ */
+pub const FUEL_PER_CALL: u64 = 100_000_000;
+
+/**
+ * the linear memory one analyzer may grow to
+ */
+pub const MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/**
+ * findings kept of one analyzer, the rest of a run is dropped
+ */
+pub const FINDING_LIMIT: usize = 10_000;
+
+/**
+ * bytes of findings one call may return
+ */
+const OUTPUT_LIMIT: usize = 16 * 1024 * 1024;
+
+/**
+ * what an analyzer is told about one entry below the analyzed folder
+ */
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryRecord {
+    /// lossy where it isn't valid UTF-8
+    pub path: String,
+    pub size: usize,
+    pub allocated_size: usize,
+    pub is_directory: bool,
+    pub is_link: bool,
+    pub modified: Option<u64>,
+    pub created: Option<u64>,
+}
+
+/**
+ * one finding as an analyzer reports it
+ */
+#[derive(Debug, Deserialize)]
+struct Reported {
+    path: String,
+    message: String,
+    #[serde(default)]
+    size: Option<u64>,
+}
+
+/**
+ * something an analyzer found, for the user to review
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub analyzer: String,
+    pub path: PathBuf,
+    pub message: String,
+    /// bytes the finding is about, when the analyzer tells
+    pub size: Option<u64>,
+}
+
+/**
+ * an analyzer module found on disk, with why it can't run if it can't
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzerInfo {
+    pub name: String,
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    pub error: Option<String>,
+}
+
+/**
+ * what one analyzer reported on a folder, or why it stopped
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzerReport {
+    pub name: String,
+    pub findings: Vec<Finding>,
+    pub error: Option<String>,
+}
+
+struct HostState {
+    limits: StoreLimits,
+}
+
+/**
+ * A custom detector shipped as a WebAssembly module, experimental. The
+ * module imports nothing, so it can't reach the filesystem, the network or
+ * the clock, and runs with bounded memory and instructions. It exports:
+ *
+ * - `memory`, its linear memory
+ * - `alloc(len: i32) -> i32`, room for `len` bytes of input
+ * - `analyze(ptr: i32, len: i32) -> i64`, given a JSON array of entries,
+ *   returns where its findings are, the pointer in the high and the length
+ *   in the low 32 bits, 0 for none
+ * - optionally `finish() -> i64`, after the last batch, the same way
+ *
+ * Findings are a JSON array of `{ "path", "message", "size"? }`.
+ */
+#[derive(Debug, Clone)]
+pub struct Analyzer {
+    name: String,
+    engine: Engine,
+    module: Module,
+}
+
+impl Analyzer {
+    /**
+     * compile `bytes`, a module importing anything or missing an export is refused
+     */
+    pub fn new(name: &str, bytes: &[u8]) -> Result<Self, String> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, bytes).map_err(|err| err.to_string())?;
+        if let Some(import) = module.imports().next() {
+            return Err(format!(
+                "imports {}::{}, analyzers may import nothing",
+                import.module(),
+                import.name()
+            ));
+        }
+        for export in ["memory", "alloc", "analyze"] {
+            if !module.exports().any(|exported| exported.name() == export) {
+                return Err(format!("exports no {}", export));
+            }
+        }
+        Ok(Self {
+            name: name.to_string(),
+            engine,
+            module,
+        })
+    }
+
+    /**
+     * the module at `path`, named after its file
+     */
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let bytes = fs::read(path).map_err(|err| err.to_string())?;
+        Self::new(&name, &bytes)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /**
+     * Stream the entries below `root` through the analyzer and collect what
+     * it reports. A fresh instance per run, so a run starts from nothing.
+     * Blocking.
+     */
+    pub fn run(&self, tree: &Tree, root: &Path) -> Result<Vec<Finding>, String> {
+        let mut run = Run::start(self)?;
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut failed = None;
+        tree.walk(root, |path, node| {
+            if failed.is_some() {
+                return;
+            }
+            batch.push(EntryRecord {
+                path: path.to_string_lossy().into_owned(),
+                size: node.size(),
+                allocated_size: node.allocated_size(),
+                is_directory: node.is_directory,
+                is_link: node.is_link,
+                modified: node.modified,
+                created: node.created,
+            });
+            if batch.len() == BATCH_SIZE {
+                failed = run.analyze(&batch).err();
+                batch.clear();
+            }
+        });
+        if let Some(err) = failed {
+            return Err(err);
+        }
+        if !batch.is_empty() {
+            run.analyze(&batch)?;
+        }
+        run.finish()
+    }
+}
+
+/**
+ * one instance of an analyzer going through one folder
+ */
+struct Run<'a> {
+    analyzer: &'a Analyzer,
+    store: Store<HostState>,
+    instance: Instance,
+    memory: Memory,
+    findings: Vec<Finding>,
+}
+
+impl<'a> Run<'a> {
+    fn start(analyzer: &'a Analyzer) -> Result<Self, String> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MEMORY_LIMIT)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&analyzer.engine, HostState { limits });
+        store.limiter(|state| &mut state.limits);
+        store
+            .set_fuel(FUEL_PER_CALL)
+            .map_err(|err| err.to_string())?;
+        let instance = Linker::<HostState>::new(&analyzer.engine)
+            .instantiate(&mut store, &analyzer.module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|err| err.to_string())?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| "exports no memory".to_string())?;
+        Ok(Self {
+            analyzer,
+            store,
+            instance,
+            memory,
+            findings: Vec::new(),
+        })
+    }
+
+    fn analyze(&mut self, batch: &[EntryRecord]) -> Result<(), String> {
+        let input = serde_json::to_vec(batch).map_err(|err| err.to_string())?;
+        self.store
+            .set_fuel(FUEL_PER_CALL)
+            .map_err(|err| err.to_string())?;
+        let alloc = self
+            .instance
+            .get_typed_func::<i32, i32>(&self.store, "alloc")
+            .map_err(|err| err.to_string())?;
+        let ptr = alloc
+            .call(&mut self.store, input.len() as i32)
+            .map_err(|err| err.to_string())?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, &input)
+            .map_err(|err| err.to_string())?;
+
+        let analyze = self
+            .instance
+            .get_typed_func::<(i32, i32), i64>(&self.store, "analyze")
+            .map_err(|err| err.to_string())?;
+        let found = analyze
+            .call(&mut self.store, (ptr, input.len() as i32))
+            .map_err(|err| err.to_string())?;
+        self.collect(found)
+    }
+
+    /**
+     * read the findings at the packed location an export returned
+     */
+    fn collect(&mut self, found: i64) -> Result<(), String> {
+        let (ptr, len) = ((found as u64 >> 32) as usize, found as u32 as usize);
+        if len == 0 {
+            return Ok(());
+        }
+        if len > OUTPUT_LIMIT {
+            return Err(format!("returned {} bytes of findings", len));
+        }
+        let mut output = vec![0; len];
+        self.memory
+            .read(&self.store, ptr, &mut output)
+            .map_err(|err| err.to_string())?;
+        let reported: Vec<Reported> =
+            serde_json::from_slice(&output).map_err(|err| format!("invalid findings, {}", err))?;
+
+        let room = FINDING_LIMIT.saturating_sub(self.findings.len());
+        self.findings
+            .extend(reported.into_iter().take(room).map(|reported| Finding {
+                analyzer: self.analyzer.name.clone(),
+                path: PathBuf::from(reported.path),
+                message: reported.message,
+                size: reported.size,
+            }));
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<Finding>, String> {
+        if let Ok(finish) = self
+            .instance
+            .get_typed_func::<(), i64>(&self.store, "finish")
+        {
+            self.store
+                .set_fuel(FUEL_PER_CALL)
+                .map_err(|err| err.to_string())?;
+            let found = finish
+                .call(&mut self.store, ())
+                .map_err(|err| err.to_string())?;
+            self.collect(found)?;
+        }
+        Ok(self.findings)
+    }
+}
+
+/**
+ * run each analyzer through the entries below `root`, one failing leaves the others running
+ */
+pub fn run_all(analyzers: &[Analyzer], tree: &Tree, root: &Path) -> Vec<AnalyzerReport> {
+    analyzers
+        .iter()
+        .map(|analyzer| {
+            let (findings, error) = match analyzer.run(tree, root) {
+                Ok(findings) => (findings, None),
+                Err(err) => (Vec::new(), Some(err)),
+            };
+            AnalyzerReport {
+                name: analyzer.name.clone(),
+                findings,
+                error,
+            }
+        })
+        .collect()
+}
+
+/**
+ * every module in `dir` by name, the ones which don't load with their error
+ */
+pub fn discover(dir: &Path) -> Vec<(AnalyzerInfo, Option<Analyzer>)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(AnalyzerInfo, Option<Analyzer>)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == EXTENSION)
+        })
+        .map(|path| {
+            let loaded = Analyzer::load(&path);
+            let info = AnalyzerInfo {
+                name: path
+                    .file_stem()
+                    .map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+                error: loaded.as_ref().err().cloned(),
+                path,
+            };
+            (info, loaded.ok())
+        })
+        .collect();
+    found.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::node::Node;
+    use std::ffi::OsString;
+
+    /**
+     * reports the same finding for every batch that isn't empty, from a data segment
+     */
+    const RENDERS: &str = r#"(module
+        (memory (export "memory") 2)
+        (global $next (mut i32) (i32.const 1024))
+        (data (i32.const 0) "[{\"path\":\"/media/render.fcp\",\"message\":\"old render\",\"size\":9}]")
+        (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+        (func (export "analyze") (param $ptr i32) (param $len i32) (result i64)
+            (if (result i64) (i32.gt_u (local.get $len) (i32.const 2))
+                (then (i64.const 62))
+                (else (i64.const 0)))))"#;
+
+    fn tree() -> (Tree, PathBuf) {
+        let root = PathBuf::from("/");
+        let mut tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let _ = tree.insert(&root, Node::new(OsString::from("render.fcp"), false, false));
+        (tree, root)
+    }
+
+    #[test]
+    fn test_findings_tagged_with_the_analyzer() {
+        let analyzer = Analyzer::new("renders", &wat::parse_str(RENDERS).unwrap()).unwrap();
+        let (tree, root) = tree();
+        let findings = analyzer.run(&tree, &root).unwrap();
+        assert_eq!(
+            findings,
+            vec![Finding {
+                analyzer: "renders".to_string(),
+                path: PathBuf::from("/media/render.fcp"),
+                message: "old render".to_string(),
+                size: Some(9),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_sandbox_refuses_imports_and_stops_loops() {
+        let importing = wat::parse_str(
+            r#"(module (import "wasi_snapshot_preview1" "path_open" (func (param i32))))"#,
+        )
+        .unwrap();
+        let err = Analyzer::new("importing", &importing).unwrap_err();
+        assert!(err.contains("path_open"));
+
+        let looping = RENDERS.replace(
+            "(if (result i64)",
+            "(loop $forever (br $forever)) (if (result i64)",
+        );
+        let analyzer = Analyzer::new("looping", &wat::parse_str(&looping).unwrap()).unwrap();
+        let (tree, root) = tree();
+        assert!(analyzer.run(&tree, &root).is_err());
+    }
+}
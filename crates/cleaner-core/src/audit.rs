@@ -0,0 +1,233 @@
+use std::{
+    io,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use specta::Type;
+
+use crate::{
+    database::{Database, to_io},
+    models::now_secs,
+    transaction::{JournalEntry, TransactionState},
+};
+
+/**
+ * one operation that changed files, as it ended or as far as it got
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    pub operation: String,
+    /// unix seconds
+    pub started: u64,
+    pub finished: Option<u64>,
+    /// none while it runs, when the app quit in the middle of it, or for a failed hook
+    pub state: Option<TransactionState>,
+    pub journal: Vec<JournalEntry>,
+    pub error: Option<String>,
+    /// what a hook printed
+    pub output: Option<String>,
+}
+
+/**
+ * The operations run on the files of the user, in the app database next to
+ * the hash index. The journal of a step is written as soon as the step is
+ * done, an entry left without a state tells what an interrupted operation
+ * already changed.
+ */
+#[derive(Clone)]
+pub struct AuditLog {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl AuditLog {
+    pub fn new(database: &Database) -> io::Result<Self> {
+        let shared = database.connection();
+        let conn = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                operation TEXT NOT NULL,
+                started INTEGER NOT NULL,
+                finished INTEGER,
+                state TEXT,
+                journal TEXT NOT NULL,
+                error TEXT
+            );",
+        )
+        .map_err(to_io)?;
+        Self::add_output(&conn).map_err(to_io)?;
+
+        Ok(Self {
+            conn: Arc::clone(&shared),
+        })
+    }
+
+    pub fn in_memory() -> io::Result<Self> {
+        Self::new(&Database::in_memory()?)
+    }
+
+    /**
+     * the output of hooks, null for the entries recorded before hooks were kept
+     */
+    fn add_output(conn: &Connection) -> rusqlite::Result<()> {
+        let kept: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('audit_log') WHERE name = 'output'",
+            [],
+            |row| row.get(0),
+        )?;
+        if kept == 0 {
+            conn.execute_batch("ALTER TABLE audit_log ADD COLUMN output TEXT;")?;
+        }
+        Ok(())
+    }
+
+    /**
+     * record that `operation` starts, the id its journal is written under
+     */
+    pub fn begin(&self, operation: &str) -> io::Result<i64> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "INSERT INTO audit_log (operation, started, journal) VALUES (?1, ?2, '[]')",
+            params![operation, now_secs() as i64],
+        )
+        .map_err(to_io)?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn journal(&self, id: i64, journal: &[JournalEntry]) -> io::Result<()> {
+        let journal = serde_json::to_string(journal)?;
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "UPDATE audit_log SET journal = ?2 WHERE id = ?1",
+            params![id, journal],
+        )
+        .map_err(to_io)?;
+        Ok(())
+    }
+
+    pub fn finish(
+        &self,
+        id: i64,
+        state: TransactionState,
+        journal: &[JournalEntry],
+        error: Option<&str>,
+    ) -> io::Result<()> {
+        let journal = serde_json::to_string(journal)?;
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "UPDATE audit_log SET finished = ?2, state = ?3, journal = ?4, error = ?5
+                WHERE id = ?1",
+            params![id, now_secs() as i64, state.name(), journal, error],
+        )
+        .map_err(to_io)?;
+        Ok(())
+    }
+
+    /**
+     * record an operation which already ended and changed no files, e.g. a hook
+     */
+    pub fn record(
+        &self,
+        operation: &str,
+        state: Option<TransactionState>,
+        output: Option<&str>,
+        error: Option<&str>,
+    ) -> io::Result<i64> {
+        let now = now_secs() as i64;
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "INSERT INTO audit_log (operation, started, finished, state, journal, error, output)
+                VALUES (?1, ?2, ?2, ?3, '[]', ?4, ?5)",
+            params![
+                operation,
+                now,
+                state.map(TransactionState::name),
+                error,
+                output
+            ],
+        )
+        .map_err(to_io)?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /**
+     * the last `limit` operations, the latest first
+     */
+    pub fn recent(&self, limit: usize) -> io::Result<Vec<AuditEntry>> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut statement = conn
+            .prepare(
+                "SELECT id, operation, started, finished, state, journal, error, output
+                    FROM audit_log ORDER BY id DESC LIMIT ?1",
+            )
+            .map_err(to_io)?;
+        let rows = statement
+            .query_map(params![limit as i64], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                ))
+            })
+            .map_err(to_io)?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (id, operation, started, finished, state, journal, error, output) =
+                row.map_err(to_io)?;
+            entries.push(AuditEntry {
+                id,
+                operation,
+                started: started as u64,
+                finished: finished.map(|finished| finished as u64),
+                state: state.as_deref().and_then(TransactionState::parse),
+                // a journal written by a newer version is shown empty
+                journal: serde_json::from_str(&journal).unwrap_or_default(),
+                error,
+                output,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::{Step, StepState};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_interrupted_operation_kept() {
+        let log = AuditLog::in_memory().unwrap();
+        let done = log.begin("backup then clean").unwrap();
+        let journal = vec![JournalEntry {
+            step: Step::Delete {
+                path: PathBuf::from("/cleaner/audit/old"),
+            },
+            state: StepState::Done,
+            error: None,
+        }];
+        log.finish(done, TransactionState::Committed, &journal, None)
+            .unwrap();
+        let interrupted = log.begin("compress then delete").unwrap();
+        log.journal(interrupted, &journal).unwrap();
+
+        let entries = log.recent(10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, interrupted);
+        assert_eq!(entries[0].state, None);
+        assert_eq!(entries[0].journal, journal);
+        assert_eq!(entries[1].state, Some(TransactionState::Committed));
+        assert!(entries[1].finished.is_some());
+    }
+}
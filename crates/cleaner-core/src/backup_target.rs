@@ -0,0 +1,263 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{
+    lossless,
+    mover::{ensure_absent, numbered},
+    path_key::PathKey,
+};
+
+/**
+ * the folders a backup goes in below the destination the user picked,
+ * unless they set a template of their own
+ */
+pub const DEFAULT_TEMPLATE: &str = "{hostname}/{date}/{source-name}";
+
+/**
+ * what `{hostname}` stands for when the name of the machine can't be read
+ */
+const UNKNOWN_HOST: &str = "unknown-host";
+
+/**
+ * what the placeholders of a template stand for
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetFields {
+    pub hostname: String,
+    /// `YYYY-MM-DD` in UTC
+    pub date: String,
+    /// the file name of the source, `root` for the root of a volume
+    pub source_name: String,
+}
+
+impl TargetFields {
+    /**
+     * the fields of a backup of `source` made at `now`, in seconds since the epoch
+     */
+    pub fn of(source: &Path, now: u64) -> Self {
+        Self {
+            hostname: sysinfo::System::host_name().unwrap_or_else(|| UNKNOWN_HOST.to_string()),
+            date: date(now),
+            source_name: source.file_name().map_or_else(
+                || "root".to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            ),
+        }
+    }
+}
+
+/**
+ * the day `secs` since the epoch fall on, `YYYY-MM-DD` in UTC
+ */
+pub fn date(secs: u64) -> String {
+    // days to the civil calendar, counted in eras of 400 years from March 0000
+    let z = secs / 86_400 + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn invalid(template: &str, problem: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("backup template {} has {}", template, problem),
+    )
+}
+
+/**
+ * `value` as one file name on every platform, what a name can't hold is
+ * replaced with `_`
+ */
+fn sanitize(value: &str) -> String {
+    let name: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // Windows drops them
+    let name = name.trim_end_matches(['.', ' ']);
+    if name.is_empty() {
+        "_".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/**
+ * The relative path `template` stands for with `fields`. Its folders are
+ * separated by `/`, a placeholder never adds one: the separators in its
+ * value are replaced.
+ */
+pub fn render(template: &str, fields: &TargetFields) -> io::Result<PathBuf> {
+    let mut path = PathBuf::new();
+    for part in template.split(['/', '\\']) {
+        let mut component = String::new();
+        let mut rest = part;
+        while let Some(start) = rest.find('{') {
+            component.push_str(&rest[..start]);
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| invalid(template, "an unclosed placeholder".to_string()))?
+                + start;
+            let value = match &rest[start + 1..end] {
+                "hostname" => &fields.hostname,
+                "date" => &fields.date,
+                "source-name" => &fields.source_name,
+                other => {
+                    return Err(invalid(
+                        template,
+                        format!("the unknown placeholder {{{}}}", other),
+                    ));
+                }
+            };
+            component.push_str(&sanitize(value));
+            rest = &rest[end + 1..];
+        }
+        component.push_str(rest);
+
+        let mut components = Path::new(&component).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(invalid(
+                template,
+                format!("the folder name {:?}", component),
+            ));
+        }
+        path.push(component);
+    }
+    Ok(path)
+}
+
+fn is_free(path: &Path) -> io::Result<bool> {
+    match ensure_absent(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/**
+ * where one source is backed up, as the webview gets it
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupTarget {
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8
+    pub raw_path: Option<String>,
+}
+
+impl From<PathBuf> for BackupTarget {
+    fn from(path: PathBuf) -> Self {
+        Self {
+            raw_path: lossless::raw_if_lossy(&path),
+            path,
+        }
+    }
+}
+
+/**
+ * Where each of `sources` is backed up below `destination`, following
+ * `template`. A target which exists already, or which an earlier source
+ * got, is numbered `name (2)` and up. With `archive` the targets are zip
+ * archives. Nothing is created.
+ */
+pub fn resolve(
+    destination: &Path,
+    template: &str,
+    sources: &[PathBuf],
+    archive: bool,
+    now: u64,
+) -> io::Result<Vec<PathBuf>> {
+    let mut taken = HashSet::new();
+    let mut targets = Vec::with_capacity(sources.len());
+    for source in sources {
+        let mut target = destination.join(render(template, &TargetFields::of(source, now))?);
+        if archive {
+            target.as_mut_os_string().push(".zip");
+        }
+        let mut candidate = target.clone();
+        for n in 2.. {
+            if !taken.contains(&PathKey::new(&candidate)) && is_free(&candidate)? {
+                break;
+            }
+            candidate = numbered(&target, n);
+        }
+        taken.insert(PathKey::new(&candidate));
+        targets.push(candidate);
+    }
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+    use std::fs;
+
+    #[test]
+    fn test_targets_dated_and_numbered() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(1_760_000_000), "2025-10-09");
+
+        let fields = TargetFields {
+            hostname: "nas/01".to_string(),
+            date: date(1_760_000_000),
+            source_name: "photos".to_string(),
+        };
+        assert_eq!(
+            render(DEFAULT_TEMPLATE, &fields).unwrap(),
+            PathBuf::from("nas_01/2025-10-09/photos")
+        );
+        assert_eq!(
+            render("backups/{source-name} {date}", &fields).unwrap(),
+            PathBuf::from("backups/photos 2025-10-09")
+        );
+        for template in ["{user}/{date}", "{date", "../{date}", "/{date}", "a//b"] {
+            let err = render(template, &fields).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{}", template);
+        }
+
+        let root = temp_path("backup-target");
+        let _ = fs::remove_dir_all(&root);
+        let now = 1_760_000_000;
+        let sources = [
+            PathBuf::from("/home/a/photos"),
+            PathBuf::from("/home/b/photos"),
+        ];
+        let first = resolve(&root, DEFAULT_TEMPLATE, &sources, false, now).unwrap();
+        let dated =
+            root.join(render("{hostname}/{date}", &TargetFields::of(&sources[0], now)).unwrap());
+        assert_eq!(first, vec![dated.join("photos"), dated.join("photos (2)")]);
+
+        // a backup made earlier that day keeps its folder
+        fs::create_dir_all(dated.join("photos")).unwrap();
+        let again = resolve(&root, DEFAULT_TEMPLATE, &sources[..1], true, now).unwrap();
+        assert_eq!(again, vec![dated.join("photos.zip")]);
+        let again = resolve(&root, DEFAULT_TEMPLATE, &sources, false, now).unwrap();
+        assert_eq!(
+            again,
+            vec![dated.join("photos (2)"), dated.join("photos (3)")]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
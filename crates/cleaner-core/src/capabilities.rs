@@ -0,0 +1,144 @@
+use std::{
+    ffi::OsString,
+    fs::{self, Metadata},
+    io,
+    path::Path,
+};
+
+use crate::{
+    long_path,
+    models::{VolumeCapabilities, Volumn},
+    path_key,
+};
+
+/**
+ * entries of the mount point looked at before giving up on the case probe
+ */
+const CASE_PROBE_ENTRIES: usize = 32;
+
+/**
+ * what a filesystem supports by its type as sysinfo names it, case
+ * sensitivity is only the usual default and `probe` checks it on the disk
+ */
+pub fn of_file_system(file_system: &str) -> VolumeCapabilities {
+    let capabilities =
+        |case_sensitive, symlinks, hardlinks, reflinks, compression| VolumeCapabilities {
+            case_sensitive,
+            symlinks,
+            hardlinks,
+            reflinks,
+            compression,
+        };
+
+    match file_system.to_ascii_lowercase().as_str() {
+        "apfs" => capabilities(false, true, true, true, true),
+        "hfs" | "hfs+" => capabilities(false, true, true, false, true),
+        "ntfs" => capabilities(false, true, true, false, true),
+        "refs" => capabilities(false, true, false, true, false),
+        "btrfs" => capabilities(true, true, true, true, true),
+        "xfs" => capabilities(true, true, true, true, false),
+        "zfs" => capabilities(true, true, true, false, true),
+        "ext2" | "ext3" | "ext4" | "f2fs" | "tmpfs" => capabilities(true, true, true, false, false),
+        "vfat" | "fat" | "fat32" | "msdos" | "exfat" => {
+            capabilities(false, false, false, false, false)
+        }
+        _ => capabilities(
+            path_key::CASE_SENSITIVE,
+            cfg!(unix),
+            cfg!(unix),
+            false,
+            false,
+        ),
+    }
+}
+
+/**
+ * the capabilities of `volume` with its case sensitivity checked on the disk,
+ * blocking, it lists the mount point
+ */
+pub fn probe(volume: &Volumn) -> VolumeCapabilities {
+    let mut capabilities = of_file_system(&volume.file_system);
+    if let Some(case_sensitive) = probe_case_sensitive(&volume.path) {
+        capabilities.case_sensitive = case_sensitive;
+    }
+    capabilities
+}
+
+/**
+ * Look an entry of `dir` up again with its case flipped, without writing
+ * anything. Reaching the same file means the volume ignores case, none
+ * when `dir` has no entry with a cased name.
+ */
+pub fn probe_case_sensitive(dir: &Path) -> Option<bool> {
+    let entries = fs::read_dir(long_path::extended(dir)).ok()?;
+    for entry in entries.flatten().take(CASE_PROBE_ENTRIES) {
+        let name = entry.file_name();
+        let Some(flipped) = flip_case(&name) else {
+            continue;
+        };
+        let Ok(original) = entry.metadata() else {
+            continue;
+        };
+
+        return match fs::symlink_metadata(long_path::extended(&dir.join(flipped))) {
+            Ok(other) => Some(!same_file(&original, &other)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Some(true),
+            Err(_) => None,
+        };
+    }
+    None
+}
+
+fn flip_case(name: &OsString) -> Option<String> {
+    let name = name.to_str()?;
+    let flipped: String = name
+        .chars()
+        .map(|c| {
+            if c.is_uppercase() {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                c.to_uppercase().next().unwrap_or(c)
+            }
+        })
+        .collect();
+    (flipped != name).then_some(flipped)
+}
+
+#[cfg(unix)]
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    a.is_dir() == b.is_dir() && a.len() == b.len() && a.modified().ok() == b.modified().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[test]
+    fn test_known_file_systems() {
+        assert!(of_file_system("APFS").reflinks);
+        assert!(!of_file_system("apfs").case_sensitive);
+        assert!(of_file_system("ext4").case_sensitive);
+        assert!(!of_file_system("exfat").hardlinks);
+        assert!(of_file_system("btrfs").compression);
+    }
+
+    #[test]
+    fn test_probe_case_sensitive() {
+        let dir = temp_path("case-probe");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(probe_case_sensitive(&dir), None);
+
+        fs::write(dir.join("Probe.txt"), b"probe").unwrap();
+        let probed = probe_case_sensitive(&dir).unwrap();
+        assert_eq!(probed, !dir.join("pROBE.TXT").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs::{self, Metadata},
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{hash, long_path, lossless};
+
+/**
+ * one entry of a comparison, `path` relative to both compared folders
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffEntry {
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8
+    pub raw_path: Option<String>,
+    pub is_directory: bool,
+    /// bytes on the left side, everything below it for a directory
+    pub size_a: Option<u64>,
+    /// bytes on the right side, everything below it for a directory
+    pub size_b: Option<u64>,
+}
+
+/**
+ * Two live folders side by side. A directory found on one side only is
+ * listed once, not every entry below it. Directories on both sides are
+ * compared by their contents and never listed themselves.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderDiff {
+    pub only_in_a: Vec<DiffEntry>,
+    pub only_in_b: Vec<DiffEntry>,
+    /// another size or type, or another content when contents were compared
+    pub different: Vec<DiffEntry>,
+    pub same: Vec<DiffEntry>,
+    /// directories or files that couldn't be read on either side, left out of the buckets
+    pub unreadable: Vec<PathBuf>,
+    /// files of the same size were hashed, else they count as the same
+    pub compared_content: bool,
+}
+
+/**
+ * Compare the folders `a` and `b` by relative path and size, links by their
+ * target and not followed. With `compare_content` files of the same size are
+ * hashed too, slow but the only way to tell a backup is intact. Blocking.
+ */
+pub fn diff_paths(a: &Path, b: &Path, compare_content: bool) -> io::Result<FolderDiff> {
+    for root in [a, b] {
+        if !fs::metadata(long_path::extended(root))?.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("{} is not a directory", root.display()),
+            ));
+        }
+    }
+    let mut diff = FolderDiff {
+        compared_content: compare_content,
+        ..FolderDiff::default()
+    };
+    diff_directory(a, b, Path::new(""), compare_content, &mut diff);
+    Ok(diff)
+}
+
+fn diff_directory(a: &Path, b: &Path, relative: &Path, content: bool, diff: &mut FolderDiff) {
+    let (Ok(left), Ok(right)) = (list(a), list(b)) else {
+        diff.unreadable.push(relative.to_path_buf());
+        return;
+    };
+
+    let mut names: BTreeMap<OsString, (Option<Metadata>, Option<Metadata>)> = left
+        .into_iter()
+        .map(|(name, metadata)| (name, (Some(metadata), None)))
+        .collect();
+    for (name, metadata) in right {
+        names.entry(name).or_default().1 = Some(metadata);
+    }
+
+    for (name, sides) in names {
+        let path = relative.join(&name);
+        let (a, b) = (a.join(&name), b.join(&name));
+        match sides {
+            (Some(left), None) => diff.only_in_a.push(entry(path, Some((&a, &left)), None)),
+            (None, Some(right)) => diff.only_in_b.push(entry(path, None, Some((&b, &right)))),
+            (Some(left), Some(right)) if left.is_dir() && right.is_dir() => {
+                diff_directory(&a, &b, &path, content, diff)
+            }
+            (Some(left), Some(right)) => {
+                let entry = entry(path, Some((&a, &left)), Some((&b, &right)));
+                match same(&a, &left, &b, &right, content) {
+                    Ok(true) => diff.same.push(entry),
+                    Ok(false) => diff.different.push(entry),
+                    Err(_) => diff.unreadable.push(entry.path),
+                }
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/**
+ * the entries of `dir` by name, their metadata without following links
+ */
+fn list(dir: &Path) -> io::Result<BTreeMap<OsString, Metadata>> {
+    fs::read_dir(long_path::extended(dir))?
+        .map(|entry| {
+            let entry = entry?;
+            Ok((entry.file_name(), entry.metadata()?))
+        })
+        .collect()
+}
+
+fn same(a: &Path, left: &Metadata, b: &Path, right: &Metadata, content: bool) -> io::Result<bool> {
+    let (left_type, right_type) = (left.file_type(), right.file_type());
+    if left_type.is_symlink() || right_type.is_symlink() {
+        return Ok(left_type.is_symlink()
+            && right_type.is_symlink()
+            && fs::read_link(long_path::extended(a))? == fs::read_link(long_path::extended(b))?);
+    }
+    if left.is_dir() != right.is_dir() || left.len() != right.len() {
+        return Ok(false);
+    }
+    if !content {
+        return Ok(true);
+    }
+    Ok(hash::hash_file(a)? == hash::hash_file(b)?)
+}
+
+fn entry(path: PathBuf, a: Option<(&Path, &Metadata)>, b: Option<(&Path, &Metadata)>) -> DiffEntry {
+    let size = |(path, metadata): (&Path, &Metadata)| size_of(path, metadata);
+    DiffEntry {
+        raw_path: lossless::raw_if_lossy(&path),
+        path,
+        is_directory: a.or(b).is_some_and(|(_, metadata)| metadata.is_dir()),
+        size_a: a.map(size),
+        size_b: b.map(size),
+    }
+}
+
+/**
+ * the length of a file, the sum of the files below a directory
+ */
+fn size_of(path: &Path, metadata: &Metadata) -> u64 {
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    list(path).map_or(0, |entries| {
+        entries
+            .iter()
+            .map(|(name, metadata)| size_of(&path.join(name), metadata))
+            .sum()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn paths(entries: &[DiffEntry]) -> Vec<&Path> {
+        entries.iter().map(|entry| entry.path.as_path()).collect()
+    }
+
+    #[test]
+    fn test_backup_compared() {
+        let root = temp_path("compare");
+        let _ = fs::remove_dir_all(&root);
+        let (a, b) = (root.join("a"), root.join("b"));
+        for side in [&a, &b] {
+            write(side, "docs/same.txt", "hello");
+            let edited = if side == &a { "draft" } else { "final" };
+            write(side, "docs/edited.txt", edited);
+        }
+        write(&a, "photos/one.jpg", "12345678");
+        write(&a, "photos/two.jpg", "12");
+        write(&a, "resized.png", "1234");
+        write(&b, "resized.png", "12");
+        write(&b, "extra.log", "x");
+
+        let diff = diff_paths(&a, &b, false).unwrap();
+        assert_eq!(paths(&diff.only_in_a), vec![Path::new("photos")]);
+        assert_eq!(diff.only_in_a[0].size_a, Some(10));
+        assert!(diff.only_in_a[0].is_directory);
+        assert_eq!(paths(&diff.only_in_b), vec![Path::new("extra.log")]);
+        assert_eq!(paths(&diff.different), vec![Path::new("resized.png")]);
+        assert_eq!(diff.different[0].size_a, Some(4));
+        assert_eq!(diff.different[0].size_b, Some(2));
+        assert_eq!(
+            paths(&diff.same),
+            vec![Path::new("docs/edited.txt"), Path::new("docs/same.txt")]
+        );
+
+        // the same size, another content
+        let diff = diff_paths(&a, &b, true).unwrap();
+        assert_eq!(
+            paths(&diff.different),
+            vec![Path::new("docs/edited.txt"), Path::new("resized.png")]
+        );
+        assert_eq!(paths(&diff.same), vec![Path::new("docs/same.txt")]);
+
+        assert!(diff_paths(&a.join("resized.png"), &b, false).is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::{PoisonError, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    dumps,
+    models::{Volumn, now_secs},
+    tree::Tree,
+};
+
+/**
+ * how often volume usage and alerts are refreshed in the background
+ */
+pub const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/**
+ * how many scan roots the dashboard remembers
+ */
+const SCAN_HISTORY: usize = 10;
+
+/**
+ * free space below this share of a volume raises an alert
+ */
+const LOW_SPACE_RATIO: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum Category {
+    Video,
+    Image,
+    Audio,
+    Archive,
+    Document,
+    Code,
+    /// memory dumps, crash reports and swapfiles, see `dumps`
+    SystemDump,
+    Other,
+}
+
+impl Category {
+    pub fn of(name: &Path) -> Category {
+        if dumps::classify(name).is_some() {
+            return Category::SystemDump;
+        }
+        let Some(ext) = name.extension().and_then(OsStr::to_str) else {
+            return Category::Other;
+        };
+
+        match ext.to_ascii_lowercase().as_str() {
+            "mp4" | "mkv" | "mov" | "avi" | "webm" | "m4v" | "wmv" => Category::Video,
+            "jpg" | "jpeg" | "png" | "gif" | "heic" | "webp" | "raw" | "cr2" | "nef" | "tiff" => {
+                Category::Image
+            }
+            "mp3" | "flac" | "wav" | "aac" | "m4a" | "ogg" => Category::Audio,
+            "zip" | "rar" | "7z" | "tar" | "gz" | "bz2" | "xz" | "zst" | "dmg" | "iso" => {
+                Category::Archive
+            }
+            "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "txt" | "md" | "pages" => {
+                Category::Document
+            }
+            "rs" | "js" | "ts" | "py" | "c" | "cpp" | "h" | "java" | "go" | "swift" | "o" => {
+                Category::Code
+            }
+            _ => Category::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryUsage {
+    pub category: Category,
+    pub size: usize,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanDelta {
+    pub root: PathBuf,
+    pub size: usize,
+    pub files: usize,
+    /// size found by the scan before this one, none for a first scan
+    pub previous_size: Option<usize>,
+    pub finished_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertKind {
+    LowSpace,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/**
+ * everything the home screen shows, kept up to date in the background
+ */
+#[derive(Debug, Clone, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Dashboard {
+    pub volumes: Vec<Volumn>,
+    /// latest scan per root, most recent first
+    pub scans: Vec<ScanDelta>,
+    /// categories of the most recent scan, largest first
+    pub categories: Vec<CategoryUsage>,
+    pub alerts: Vec<Alert>,
+    pub refreshed_at: u64,
+}
+
+/**
+ * total size, file count and category breakdown of the files below `root`
+ */
+pub fn summarize(tree: &Tree, root: &Path) -> (usize, usize, Vec<CategoryUsage>) {
+    let mut totals: HashMap<Category, (usize, usize)> = HashMap::new();
+    tree.walk(root, |path, node| {
+        if node.is_directory {
+            return;
+        }
+
+        let entry = totals.entry(Category::of(path)).or_default();
+        entry.0 += node.size();
+        entry.1 += 1;
+    });
+
+    let mut categories: Vec<CategoryUsage> = totals
+        .into_iter()
+        .map(|(category, (size, files))| CategoryUsage {
+            category,
+            size,
+            files,
+        })
+        .collect();
+    categories.sort_by_key(|category| Reverse(category.size));
+
+    let size = categories.iter().map(|usage| usage.size).sum();
+    let files = categories.iter().map(|usage| usage.files).sum();
+    (size, files, categories)
+}
+
+pub fn low_space_alerts(volumes: &[Volumn]) -> Vec<Alert> {
+    volumes
+        .iter()
+        .filter(|volume| {
+            volume.total_size > 0
+                && (volume.available_size as f64) < volume.total_size as f64 * LOW_SPACE_RATIO
+        })
+        .map(|volume| Alert {
+            kind: AlertKind::LowSpace,
+            path: volume.path.clone(),
+            message: format!(
+                "{} has only {} MB free",
+                volume.name,
+                volume.available_size / 1024 / 1024
+            ),
+        })
+        .collect()
+}
+
+/**
+ * Cached dashboard payload. Writers are the volume refresh timer and the
+ * scan completion, so reading it never walks a tree or touches the disk.
+ */
+#[derive(Default)]
+pub struct DashboardCache {
+    dashboard: RwLock<Dashboard>,
+}
+
+impl DashboardCache {
+    pub fn snapshot(&self) -> Dashboard {
+        self.dashboard
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /**
+     * take in the volumes as they are now, the alerts which weren't raised
+     * at the refresh before
+     */
+    pub fn refresh_volumes(&self, volumes: Vec<Volumn>) -> Vec<Alert> {
+        let mut dashboard = self
+            .dashboard
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let alerts = low_space_alerts(&volumes);
+        let raised = alerts
+            .iter()
+            .filter(|alert| {
+                !dashboard
+                    .alerts
+                    .iter()
+                    .any(|before| before.kind == alert.kind && before.path == alert.path)
+            })
+            .cloned()
+            .collect();
+        dashboard.alerts = alerts;
+        dashboard.volumes = volumes;
+        dashboard.refreshed_at = now_secs();
+        raised
+    }
+
+    /**
+     * take in a finished scan of `root`, compared with the scan of it before
+     */
+    pub fn record_scan(
+        &self,
+        root: PathBuf,
+        size: usize,
+        files: usize,
+        categories: Vec<CategoryUsage>,
+    ) -> ScanDelta {
+        let mut dashboard = self
+            .dashboard
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let previous_size = dashboard
+            .scans
+            .iter()
+            .position(|scan| scan.root == root)
+            .map(|index| dashboard.scans.remove(index).size);
+
+        let scan = ScanDelta {
+            root,
+            size,
+            files,
+            previous_size,
+            finished_at: now_secs(),
+        };
+        dashboard.scans.insert(0, scan.clone());
+        dashboard.scans.truncate(SCAN_HISTORY);
+        dashboard.categories = categories;
+        dashboard.refreshed_at = now_secs();
+        scan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_scan_keeps_previous_size() {
+        let cache = DashboardCache::default();
+        cache.record_scan(PathBuf::from("/data"), 100, 1, vec![]);
+        cache.record_scan(PathBuf::from("/home"), 50, 1, vec![]);
+        cache.record_scan(PathBuf::from("/data"), 300, 2, vec![]);
+
+        let dashboard = cache.snapshot();
+        assert_eq!(dashboard.scans.len(), 2);
+        assert_eq!(dashboard.scans[0].root, PathBuf::from("/data"));
+        assert_eq!(dashboard.scans[0].previous_size, Some(100));
+        assert_eq!(dashboard.scans[1].previous_size, None);
+    }
+
+    #[test]
+    fn test_low_space_alerts() {
+        let volume = |path: &str, available_size: u64| Volumn {
+            name: path.to_string(),
+            display_name: path.to_string(),
+            path: PathBuf::from(path),
+            total_size: 1000,
+            available_size,
+            file_system: "apfs".to_string(),
+            capabilities: None,
+            shadow_storage: None,
+            inodes: None,
+        };
+
+        let alerts = low_space_alerts(&[volume("/", 50), volume("/data", 500)]);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].path, PathBuf::from("/"));
+
+        // an alert is raised once, not at every refresh while it lasts
+        let cache = DashboardCache::default();
+        assert_eq!(cache.refresh_volumes(vec![volume("/", 50)]).len(), 1);
+        assert!(cache.refresh_volumes(vec![volume("/", 40)]).is_empty());
+        assert!(cache.refresh_volumes(vec![volume("/", 500)]).is_empty());
+        assert_eq!(cache.refresh_volumes(vec![volume("/", 50)]).len(), 1);
+    }
+}
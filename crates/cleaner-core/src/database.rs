@@ -0,0 +1,92 @@
+use std::{
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rusqlite::Connection;
+
+/**
+ * how long a write waits for another one holding the database before it fails
+ */
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(crate) fn to_io(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err)
+}
+
+/**
+ * The app database, one connection shared by every store in it. The stores
+ * lock it for each statement, a write from one never fails another with a
+ * busy database. The journal is written ahead, so readers in other processes
+ * don't block the writer.
+ */
+#[derive(Clone)]
+pub struct Database {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Database {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let conn = Connection::open(path).map_err(to_io)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .map_err(to_io)?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(to_io)?;
+        Ok(Self::with_connection(conn))
+    }
+
+    pub fn in_memory() -> io::Result<Self> {
+        Ok(Self::with_connection(
+            Connection::open_in_memory().map_err(to_io)?,
+        ))
+    }
+
+    pub(crate) fn with_connection(conn: Connection) -> Self {
+        Self {
+            conn: Arc::new(Mutex::new(conn)),
+        }
+    }
+
+    pub(crate) fn connection(&self) -> Arc<Mutex<Connection>> {
+        Arc::clone(&self.conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+    use crate::labels::LabelStore;
+
+    #[test]
+    fn test_stores_share_connection() {
+        let dir = temp_path("database");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("index.sqlite");
+        let _ = std::fs::remove_file(&path);
+
+        let database = Database::open(&path).unwrap();
+        let mode: String = database
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        LabelStore::new(&database).unwrap();
+        let tables: i64 = database
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'folder_labels'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
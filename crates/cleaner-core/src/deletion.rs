@@ -0,0 +1,643 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Condvar, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{locks, long_path};
+
+/**
+ * how often the progress of a running deletion is reported
+ */
+pub const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/**
+ * threads removing entries at once, deleting is bound by the filesystem more than the CPU
+ */
+pub const WORKERS: usize = 8;
+
+/**
+ * failures kept per selected path, the rest are only counted
+ */
+pub const FAILURES_PER_SUBTREE: usize = 20;
+
+/**
+ * how long an idle worker waits before it checks for a cancellation again
+ */
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupFailure {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/**
+ * what a running deletion got through so far, over all selected paths
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupProgress {
+    pub files: u64,
+    pub directories: u64,
+    pub bytes: u64,
+    pub failures: u64,
+    pub elapsed_millis: u64,
+    /// the last report of a deletion, finished or cancelled
+    pub done: bool,
+}
+
+/**
+ * the outcome of deleting one selected path
+ */
+#[derive(Debug, Clone, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtreeDeletion {
+    pub path: PathBuf,
+    /// the path itself is gone, nothing below it failed
+    pub removed: bool,
+    pub files: u64,
+    pub directories: u64,
+    pub bytes: u64,
+    /// the first `FAILURES_PER_SUBTREE` entries which could not be removed
+    pub failures: Vec<CleanupFailure>,
+    pub failure_count: u64,
+    /// entries whose lock flags were cleared to remove them
+    pub unlocked: u64,
+}
+
+/**
+ * A directory being emptied. It is removed once it was listed and every
+ * subdirectory is gone, which then counts down its parent in turn.
+ */
+struct Directory {
+    path: PathBuf,
+    parent: Option<Arc<Directory>>,
+    subtree: usize,
+    /// the listing of the directory itself plus its subdirectories still around
+    pending: AtomicUsize,
+    /// something below could not be removed, so the directory stays too
+    failed: AtomicBool,
+}
+
+#[derive(Default)]
+struct Queue {
+    directories: Vec<Arc<Directory>>,
+    /// directories taken by a worker and not listed yet
+    listing: usize,
+}
+
+struct Deletion<'a> {
+    /// the selected path of every subtree and the form it is accessed by
+    roots: Vec<(PathBuf, PathBuf)>,
+    subtrees: Vec<Mutex<SubtreeDeletion>>,
+    queue: Mutex<Queue>,
+    ready: Condvar,
+    cancel: &'a AtomicBool,
+    /// lock flags may be cleared, otherwise a locked entry fails with an explanation
+    unlock: bool,
+    files: AtomicU64,
+    directories: AtomicU64,
+    bytes: AtomicU64,
+    failures: AtomicU64,
+}
+
+/**
+ * Permanently delete `paths` with `workers` threads, a symlink itself is
+ * removed, never its target. Files go as soon as their directory is listed,
+ * directories bottom up once they are empty, so a cancelled or failed
+ * deletion leaves a consistent tree behind. Setting `cancel` stops the
+ * workers after the entry at hand, `report` gets the progress every
+ * `PROGRESS_INTERVAL` and once more at the end. With `unlock` the flags
+ * which lock an entry, or the directory holding it, are cleared when they
+ * are what refused the removal.
+ */
+pub fn delete(
+    paths: &[PathBuf],
+    workers: usize,
+    unlock: bool,
+    cancel: &AtomicBool,
+    report: &mut dyn FnMut(&CleanupProgress),
+) -> Vec<SubtreeDeletion> {
+    let started = Instant::now();
+    let deletion = Deletion {
+        roots: paths
+            .iter()
+            .map(|path| (path.clone(), long_path::extended(path).into_owned()))
+            .collect(),
+        subtrees: paths
+            .iter()
+            .map(|path| {
+                Mutex::new(SubtreeDeletion {
+                    path: path.clone(),
+                    ..SubtreeDeletion::default()
+                })
+            })
+            .collect(),
+        queue: Mutex::new(Queue::default()),
+        ready: Condvar::new(),
+        cancel,
+        unlock,
+        files: AtomicU64::new(0),
+        directories: AtomicU64::new(0),
+        bytes: AtomicU64::new(0),
+        failures: AtomicU64::new(0),
+    };
+
+    for subtree in 0..paths.len() {
+        deletion.start(subtree);
+    }
+
+    thread::scope(|scope| {
+        for _ in 0..workers.max(1) {
+            scope.spawn(|| deletion.work());
+        }
+
+        let mut next_report = started + PROGRESS_INTERVAL;
+        let mut queue = deletion.lock();
+        while !deletion.is_done(&queue) {
+            let now = Instant::now();
+            if now >= next_report {
+                drop(queue);
+                report(&deletion.progress(started, false));
+                next_report += PROGRESS_INTERVAL;
+                queue = deletion.lock();
+                continue;
+            }
+            let wait = (next_report - now).min(CANCEL_POLL);
+            queue = deletion
+                .ready
+                .wait_timeout(queue, wait)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    });
+
+    report(&deletion.progress(started, true));
+    deletion
+        .subtrees
+        .into_iter()
+        .map(|subtree| subtree.into_inner().unwrap_or_else(PoisonError::into_inner))
+        .collect()
+}
+
+impl Deletion<'_> {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    fn is_done(&self, queue: &Queue) -> bool {
+        queue.listing == 0 && (queue.directories.is_empty() || self.is_cancelled())
+    }
+
+    /**
+     * remove a selected file right away, queue a selected directory for the workers
+     */
+    fn start(&self, subtree: usize) {
+        let path = &self.roots[subtree].1;
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => {
+                self.lock().directories.push(Arc::new(Directory {
+                    path: path.clone(),
+                    parent: None,
+                    subtree,
+                    pending: AtomicUsize::new(1),
+                    failed: AtomicBool::new(false),
+                }))
+            }
+            Ok(metadata) => {
+                match self.unlocking(subtree, path, || remove_file(path, metadata.is_symlink())) {
+                    Ok(()) => {
+                        self.tally(subtree, 1, metadata.len(), 0);
+                        self.lock_subtree(subtree).removed = true;
+                    }
+                    Err(err) => self.fail(subtree, path, err),
+                }
+            }
+            Err(err) => self.fail(subtree, path, err),
+        }
+    }
+
+    fn work(&self) {
+        while let Some(directory) = self.next() {
+            self.empty(directory);
+            let mut queue = self.lock();
+            queue.listing -= 1;
+            if self.is_done(&queue) {
+                self.ready.notify_all();
+            }
+        }
+    }
+
+    fn next(&self) -> Option<Arc<Directory>> {
+        let mut queue = self.lock();
+        loop {
+            if self.is_cancelled() {
+                return None;
+            }
+            if let Some(directory) = queue.directories.pop() {
+                queue.listing += 1;
+                return Some(directory);
+            }
+            if queue.listing == 0 {
+                return None;
+            }
+            queue = self
+                .ready
+                .wait_timeout(queue, CANCEL_POLL)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /**
+     * remove the files of `directory` and queue its subdirectories
+     */
+    fn empty(&self, directory: Arc<Directory>) {
+        let entries = match fs::read_dir(&directory.path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                directory.failed.store(true, Ordering::Relaxed);
+                self.fail(directory.subtree, &directory.path, err);
+                return self.finish(directory);
+            }
+        };
+
+        let (mut files, mut bytes) = (0, 0);
+        let mut subdirectories = vec![];
+        for entry in entries {
+            if self.is_cancelled() {
+                break;
+            }
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    directory.failed.store(true, Ordering::Relaxed);
+                    self.fail(directory.subtree, &directory.path, err);
+                    continue;
+                }
+            };
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(err) => {
+                    directory.failed.store(true, Ordering::Relaxed);
+                    self.fail(directory.subtree, &path, err);
+                    continue;
+                }
+            };
+            if file_type.is_dir() {
+                subdirectories.push(path);
+                continue;
+            }
+
+            let size = entry.metadata().map_or(0, |metadata| metadata.len());
+            match self.unlocking(directory.subtree, &path, || {
+                remove_file(&path, file_type.is_symlink())
+            }) {
+                Ok(()) => {
+                    files += 1;
+                    bytes += size;
+                }
+                Err(err) => {
+                    directory.failed.store(true, Ordering::Relaxed);
+                    self.fail(directory.subtree, &path, err);
+                }
+            }
+        }
+        self.tally(directory.subtree, files, bytes, 0);
+
+        if !subdirectories.is_empty() {
+            directory
+                .pending
+                .fetch_add(subdirectories.len(), Ordering::AcqRel);
+            let mut queue = self.lock();
+            queue
+                .directories
+                .extend(subdirectories.into_iter().map(|path| {
+                    Arc::new(Directory {
+                        path,
+                        parent: Some(directory.clone()),
+                        subtree: directory.subtree,
+                        pending: AtomicUsize::new(1),
+                        failed: AtomicBool::new(false),
+                    })
+                }));
+            self.ready.notify_all();
+        }
+        self.finish(directory);
+    }
+
+    /**
+     * count down `directory` and remove every ancestor left empty by it
+     */
+    fn finish(&self, directory: Arc<Directory>) {
+        let mut current = Some(directory);
+        while let Some(directory) = current {
+            if directory.pending.fetch_sub(1, Ordering::AcqRel) != 1 {
+                return;
+            }
+
+            // a cancelled deletion keeps the directories it emptied
+            let mut kept = directory.failed.load(Ordering::Relaxed) || self.is_cancelled();
+            if !kept {
+                match self.unlocking(directory.subtree, &directory.path, || {
+                    fs::remove_dir(&directory.path)
+                }) {
+                    Ok(()) => self.tally(directory.subtree, 0, 0, 1),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                    Err(err) => {
+                        kept = true;
+                        self.fail(directory.subtree, &directory.path, err);
+                    }
+                }
+            }
+
+            match directory.parent.as_ref() {
+                Some(parent) if kept => parent.failed.store(true, Ordering::Relaxed),
+                Some(_) => {}
+                None => self.lock_subtree(directory.subtree).removed = !kept,
+            }
+            current = directory.parent.clone();
+        }
+    }
+
+    fn lock_subtree(&self, subtree: usize) -> MutexGuard<'_, SubtreeDeletion> {
+        self.subtrees[subtree]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn tally(&self, subtree: usize, files: u64, bytes: u64, directories: u64) {
+        if files == 0 && directories == 0 {
+            return;
+        }
+        self.files.fetch_add(files, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.directories.fetch_add(directories, Ordering::Relaxed);
+
+        let mut subtree = self.lock_subtree(subtree);
+        subtree.files += files;
+        subtree.bytes += bytes;
+        subtree.directories += directories;
+    }
+
+    /**
+     * Run `remove` again when the entry at `path` or the directory holding it
+     * is locked. A directory outside of the selection is never unlocked, and
+     * without `unlock` the error says which flags are in the way.
+     */
+    fn unlocking(
+        &self,
+        subtree: usize,
+        path: &Path,
+        remove: impl Fn() -> io::Result<()>,
+    ) -> io::Result<()> {
+        let err = match remove() {
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => err,
+            result => return result,
+        };
+        let accessed = &self.roots[subtree].1;
+        let Some((locked, flags)) = [Some(path), path.parent()]
+            .into_iter()
+            .flatten()
+            .filter(|locked| locked.starts_with(accessed))
+            .map(|locked| (locked, locks::of_path(locked)))
+            .find(|(_, flags)| flags.is_locked())
+        else {
+            return Err(err);
+        };
+
+        if !self.unlock {
+            return Err(io::Error::new(
+                err.kind(),
+                format!(
+                    "{} is locked ({}), allow unlocking to remove it",
+                    self.reported(subtree, locked).display(),
+                    flags
+                ),
+            ));
+        }
+        locks::clear(locked, flags)?;
+        self.lock_subtree(subtree).unlocked += 1;
+        remove()
+    }
+
+    /**
+     * `path` the way it was selected, without the extended length prefix
+     */
+    fn reported(&self, subtree: usize, path: &Path) -> PathBuf {
+        let (selected, accessed) = &self.roots[subtree];
+        path.strip_prefix(accessed)
+            .map_or_else(|_| path.to_path_buf(), |rest| selected.join(rest))
+    }
+
+    fn fail(&self, subtree: usize, path: &Path, err: io::Error) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        let path = self.reported(subtree, path);
+
+        let mut subtree = self.lock_subtree(subtree);
+        subtree.failure_count += 1;
+        if subtree.failures.len() < FAILURES_PER_SUBTREE {
+            subtree.failures.push(CleanupFailure {
+                path,
+                reason: err.to_string(),
+            });
+        }
+    }
+
+    fn progress(&self, started: Instant, done: bool) -> CleanupProgress {
+        CleanupProgress {
+            files: self.files.load(Ordering::Relaxed),
+            directories: self.directories.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            elapsed_millis: started.elapsed().as_millis() as u64,
+            done,
+        }
+    }
+}
+
+/**
+ * the selection without duplicates and paths nested in another selected one
+ */
+pub fn outermost(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut selected = paths.to_vec();
+    // components sort parents right before their descendants
+    selected.sort();
+    selected.dedup();
+
+    let mut outermost: Vec<PathBuf> = vec![];
+    for path in selected {
+        if !outermost
+            .last()
+            .is_some_and(|parent| path.starts_with(parent))
+        {
+            outermost.push(path);
+        }
+    }
+    outermost
+}
+
+/**
+ * a file or a symlink, a symlink to a directory is a directory entry on windows
+ */
+fn remove_file(path: &Path, is_symlink: bool) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) if is_symlink && cfg!(windows) => fs::remove_dir(path).map_err(|_| err),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    fn populate(root: &Path, depth: usize, width: usize) {
+        fs::create_dir_all(root).unwrap();
+        for index in 0..width {
+            fs::write(root.join(format!("{}.bin", index)), b"junk").unwrap();
+            if depth > 0 {
+                populate(&root.join(format!("dir-{}", index)), depth - 1, width);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nested_selection_dropped() {
+        let paths = outermost(&[
+            PathBuf::from("/data/a/b"),
+            PathBuf::from("/data/a"),
+            PathBuf::from("/data/ab"),
+            PathBuf::from("/data/a"),
+        ]);
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("/data/a"), PathBuf::from("/data/ab")]
+        );
+    }
+
+    #[test]
+    fn test_delete_tree_bottom_up() {
+        let root = temp_path("deletion-tree");
+        let _ = fs::remove_dir_all(&root);
+        populate(&root.join("node_modules"), 3, 3);
+        fs::write(root.join("single.log"), b"log").unwrap();
+
+        let mut reports = vec![];
+        let subtrees = delete(
+            &[root.join("node_modules"), root.join("single.log")],
+            4,
+            false,
+            &AtomicBool::new(false),
+            &mut |progress| reports.push(progress.clone()),
+        );
+
+        // 3 + 9 + 27 + 81 files in 1 + 3 + 9 + 27 directories
+        assert!(subtrees[0].removed);
+        assert_eq!(subtrees[0].files, 120);
+        assert_eq!(subtrees[0].directories, 40);
+        assert_eq!(subtrees[0].bytes, 480);
+        assert!(subtrees[1].removed);
+        assert_eq!(subtrees[1].bytes, 3);
+
+        let last = reports.last().unwrap();
+        assert!(last.done);
+        assert_eq!(last.files, 121);
+        assert_eq!(last.failures, 0);
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 0);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_remove_past_max_path() {
+        let root = temp_path("deletion-long-paths");
+        let mut deepest = root.clone();
+        for index in 0..4 {
+            deepest.push(format!("{}-{}", index, "x".repeat(80)));
+        }
+        fs::create_dir_all(long_path::extended(&deepest)).unwrap();
+        fs::write(long_path::extended(&deepest.join("junk.bin")), b"junk").unwrap();
+        assert!(deepest.as_os_str().len() > 260);
+
+        let subtrees = delete(
+            std::slice::from_ref(&root),
+            2,
+            false,
+            &AtomicBool::new(false),
+            &mut |_| {},
+        );
+        assert!(subtrees[0].removed);
+        assert!(fs::symlink_metadata(long_path::extended(&root)).is_err());
+    }
+
+    #[test]
+    fn test_cancelled_deletion_keeps_directories() {
+        let root = temp_path("deletion-cancelled");
+        let _ = fs::remove_dir_all(&root);
+        populate(&root, 2, 2);
+
+        let subtrees = delete(
+            std::slice::from_ref(&root),
+            2,
+            false,
+            &AtomicBool::new(true),
+            &mut |_| {},
+        );
+        assert!(!subtrees[0].removed);
+        assert_eq!(subtrees[0].failure_count, 0);
+        assert!(root.exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failures_reported_per_subtree() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = temp_path("deletion-partial");
+        let _ = fs::remove_dir_all(&root);
+        populate(&root.join("kept"), 1, 2);
+        populate(&root.join("gone"), 1, 2);
+        let locked = root.join("kept/dir-0");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+        // root ignores the permission bits, there is nothing to fail then
+        if fs::remove_file(locked.join("0.bin")).is_ok() {
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+            let _ = fs::remove_dir_all(&root);
+            return;
+        }
+
+        let subtrees = delete(
+            &[root.join("kept"), root.join("gone")],
+            2,
+            false,
+            &AtomicBool::new(false),
+            &mut |_| {},
+        );
+        assert!(!subtrees[0].removed);
+        assert_eq!(subtrees[0].failure_count, 2);
+        assert!(subtrees[0].failures[0].path.starts_with(&locked));
+        assert!(subtrees[1].removed);
+        assert!(locked.exists());
+        assert!(!root.join("kept/dir-1").exists());
+
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        let _ = fs::remove_dir_all(&root);
+    }
+}
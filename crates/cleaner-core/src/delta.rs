@@ -0,0 +1,172 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::tree::{Tree, node::NodeRef};
+
+/**
+ * how often the size changes of a scan are sent as one batch
+ */
+pub const DELTA_INTERVAL: Duration = Duration::from_secs(1);
+
+/**
+ * a node which grew or shrank since the previous batch
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeDelta {
+    pub path: PathBuf,
+    pub old_size: u64,
+    /// zero once the node left the tree
+    pub new_size: u64,
+}
+
+/**
+ * the size changes of one interval, sorted by path so parents come first
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeDeltaBatch {
+    pub deltas: Vec<SizeDelta>,
+}
+
+/**
+ * Nodes whose size changed since the last flush, each with the size it had
+ * before its first change. However often a directory grows in between, it
+ * ends up as one delta in the batch.
+ */
+#[derive(Debug, Default)]
+pub struct PendingDeltas {
+    before: HashMap<PathBuf, u64>,
+}
+
+impl PendingDeltas {
+    /**
+     * remember the size of `node` at `path` and of its ancestors before they change,
+     * a node already pending keeps the size it had before its first change
+     */
+    pub fn touch(&mut self, path: &Path, node: &NodeRef) {
+        let nodes = std::iter::once(node.clone()).chain(Tree::ancestors(node));
+        for (path, node) in path.ancestors().zip(nodes) {
+            // a pending node has all of its ancestors pending too
+            if self.before.contains_key(path) {
+                break;
+            }
+            let size = node.read().map_or(0, |node| node.size() as u64);
+            self.before.insert(path.to_path_buf(), size);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.before.is_empty()
+    }
+
+    /**
+     * the pending changes against the sizes in `tree` now, nodes back at their
+     * old size are left out
+     */
+    pub fn flush(&mut self, tree: &Tree) -> SizeDeltaBatch {
+        let mut deltas: Vec<SizeDelta> = self
+            .before
+            .drain()
+            .filter_map(|(path, old_size)| {
+                let new_size = tree
+                    .get_node(&path)
+                    .and_then(|node| node.read().ok().map(|node| node.size() as u64))
+                    .unwrap_or(0);
+                (new_size != old_size).then_some(SizeDelta {
+                    path,
+                    old_size,
+                    new_size,
+                })
+            })
+            .collect();
+        deltas.sort_by(|a, b| a.path.cmp(&b.path));
+        SizeDeltaBatch { deltas }
+    }
+
+    pub fn clear(&mut self) {
+        self.before.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::AgeBuckets,
+        tree::{Growth, node::Node},
+    };
+    use std::ffi::OsString;
+
+    fn file(name: &str, size: usize) -> Node {
+        let node = Node::new(OsString::from(name), false, false);
+        node.set_size(size);
+        node
+    }
+
+    #[test]
+    fn test_deltas_coalesced_per_node() {
+        let root = PathBuf::from("/");
+        let docs = PathBuf::from("/docs");
+        let mut tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let node = tree
+            .insert(&root, Node::new(OsString::from("docs"), true, false))
+            .unwrap();
+        let _ = tree.insert(&root, file("notes.txt", 10));
+
+        let mut pending = PendingDeltas::default();
+        for size in [100, 50] {
+            pending.touch(&docs, &node);
+            let growth = Growth {
+                size,
+                ..Growth::default()
+            };
+            let _ = node
+                .read()
+                .map(|node| node.grow(growth, &AgeBuckets::default()));
+            Tree::bubble(&node, growth, &AgeBuckets::default());
+        }
+
+        let batch = pending.flush(&tree);
+        assert_eq!(
+            batch.deltas,
+            vec![
+                SizeDelta {
+                    path: root.clone(),
+                    old_size: 10,
+                    new_size: 160,
+                },
+                SizeDelta {
+                    path: docs.clone(),
+                    old_size: 0,
+                    new_size: 150,
+                },
+            ]
+        );
+        assert!(pending.is_empty());
+
+        pending.touch(&docs, &node);
+        let _ = tree.remove(&docs);
+        let batch = pending.flush(&tree);
+        assert_eq!(batch.deltas[0].new_size, 10);
+        assert_eq!(batch.deltas[1].new_size, 0);
+    }
+
+    #[test]
+    fn test_unchanged_nodes_left_out() {
+        let root = PathBuf::from("/");
+        let tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let node = tree.get_node(&root).unwrap();
+
+        let mut pending = PendingDeltas::default();
+        pending.touch(&root, &node);
+        assert!(!pending.is_empty());
+        assert!(pending.flush(&tree).deltas.is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+use std::{fs::Metadata, path::Path};
+
+use crate::file_id::FileId;
+
+/**
+ * the id of the filesystem `path` lives on, `st_dev` of its metadata
+ */
+#[cfg(unix)]
+pub fn id(_path: &Path, metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.dev())
+}
+
+/**
+ * the serial number of the volume `path` lives on, a folder a volume is
+ * mounted at is opened through to the mounted volume
+ */
+#[cfg(windows)]
+pub fn id(path: &Path, _metadata: &Metadata) -> Option<u64> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        BY_HANDLE_FILE_INFORMATION, GetFileInformationByHandle,
+    };
+
+    // std opens directories with backup semantics
+    let file = std::fs::File::open(crate::long_path::extended(path)).ok()?;
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    let read = unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) };
+    (read != 0).then_some(info.dwVolumeSerialNumber as u64)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn id(_path: &Path, _metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/**
+ * the identity of a file with more than one hard link, none for a file
+ * linked once as it can't be met twice
+ */
+#[cfg(unix)]
+pub fn hardlink(metadata: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    (metadata.nlink() > 1)
+        .then(|| FileId::from_metadata(metadata))
+        .flatten()
+}
+
+/**
+ * std can't tell the links of a file on Windows without opening it
+ */
+#[cfg(not(unix))]
+pub fn hardlink(_metadata: &Metadata) -> Option<FileId> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[test]
+    fn test_same_directory_same_device() {
+        let dir = std::env::temp_dir();
+        let metadata = std::fs::metadata(&dir).unwrap();
+        let device = id(&dir, &metadata);
+        assert!(device.is_some());
+        let child = temp_path("device");
+        std::fs::create_dir_all(&child).unwrap();
+        assert_eq!(id(&child, &std::fs::metadata(&child).unwrap()), device);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hardlinks_share_an_id() {
+        let dir = temp_path("hardlink");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.bin"), "data").unwrap();
+        assert_eq!(
+            hardlink(&std::fs::metadata(dir.join("a.bin")).unwrap()),
+            None
+        );
+
+        std::fs::hard_link(dir.join("a.bin"), dir.join("b.bin")).unwrap();
+        let a = hardlink(&std::fs::metadata(dir.join("a.bin")).unwrap());
+        assert!(a.is_some());
+        assert_eq!(hardlink(&std::fs::metadata(dir.join("b.bin")).unwrap()), a);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
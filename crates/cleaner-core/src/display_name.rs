@@ -0,0 +1,242 @@
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{lossless, path_key::PathKey};
+
+/**
+ * The names the file manager of the platform shows for locations whose
+ * folder is named otherwise, e.g. `com~apple~CloudDocs` is iCloud Drive.
+ * Names are only ever shown, the raw path next to them is what is acted on.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownLocation {
+    pub path: PathBuf,
+    pub name: String,
+}
+
+/**
+ * one step of the path bar, from the outermost shown location down
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Breadcrumb {
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8
+    pub raw_path: Option<String>,
+    pub name: String,
+}
+
+/**
+ * the known locations of the user running the app, looked up once
+ */
+pub fn known_locations() -> &'static [KnownLocation] {
+    static KNOWN: OnceLock<Vec<KnownLocation>> = OnceLock::new();
+    KNOWN.get_or_init(|| {
+        let home = std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(PathBuf::from);
+        discover(home.as_deref(), &|name| std::env::var_os(name))
+    })
+}
+
+/**
+ * the friendly name of `path` when it is a known location
+ */
+pub fn of(path: &Path) -> Option<String> {
+    name_in(known_locations(), path)
+}
+
+fn name_in(known: &[KnownLocation], path: &Path) -> Option<String> {
+    let key = PathKey::new(path);
+    known
+        .iter()
+        .find(|location| PathKey::new(&location.path) == key)
+        .map(|location| location.name.clone())
+}
+
+/**
+ * The name a volume is shown with. Windows shows the label with the drive
+ * letter and an unlabeled drive as a local disk, the others show the label.
+ */
+pub fn of_volume(label: &str, mount_point: &Path) -> String {
+    if let Some(name) = of(mount_point) {
+        return name;
+    }
+    let letter = mount_point
+        .to_str()
+        .filter(|path| cfg!(windows) && path.len() <= 3 && path.ends_with([':', '\\']))
+        .map(|path| path.trim_end_matches('\\'));
+    match (letter, label.is_empty()) {
+        (Some(letter), true) => format!("Local Disk ({})", letter),
+        (Some(letter), false) => format!("{} ({})", label, letter),
+        (None, false) => label.to_string(),
+        (None, true) => mount_point.file_name().map_or_else(
+            || mount_point.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        ),
+    }
+}
+
+/**
+ * the path bar of `path`, starting at the innermost known location above it
+ * the way Finder starts at iCloud Drive, or at the root
+ */
+pub fn breadcrumbs(path: &Path) -> Vec<Breadcrumb> {
+    breadcrumbs_in(known_locations(), path)
+}
+
+fn breadcrumbs_in(known: &[KnownLocation], path: &Path) -> Vec<Breadcrumb> {
+    let mut crumbs = Vec::new();
+    for ancestor in PathKey::new(path).as_path().ancestors() {
+        let known_name = name_in(known, ancestor);
+        let name = known_name.clone().or_else(|| {
+            ancestor
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        });
+        crumbs.push(Breadcrumb {
+            path: ancestor.to_path_buf(),
+            raw_path: lossless::raw_if_lossy(ancestor),
+            name: name.unwrap_or_else(|| ancestor.display().to_string()),
+        });
+        if known_name.is_some() {
+            break;
+        }
+    }
+    crumbs.reverse();
+    crumbs
+}
+
+fn discover(home: Option<&Path>, env: &dyn Fn(&str) -> Option<OsString>) -> Vec<KnownLocation> {
+    let mut known = Vec::new();
+    let mut add = |path: PathBuf, name: String| known.push(KnownLocation { path, name });
+
+    if let Some(home) = home {
+        add(home.to_path_buf(), "Home".to_string());
+
+        let icloud = home.join("Library/Mobile Documents/com~apple~CloudDocs");
+        if cfg!(target_os = "macos") && icloud.is_dir() {
+            add(icloud, "iCloud Drive".to_string());
+        }
+        // File Provider extensions mount their drives here as Provider-Account
+        if let Ok(entries) = fs::read_dir(home.join("Library/CloudStorage")) {
+            for entry in entries.flatten() {
+                let folder = entry.file_name();
+                add(entry.path(), cloud_storage_name(&folder.to_string_lossy()));
+            }
+        }
+    }
+
+    // set by the OneDrive client of windows for every signed in account
+    for (variable, fallback) in [
+        ("OneDriveConsumer", Some("Personal")),
+        ("OneDriveCommercial", None),
+    ] {
+        let Some(path) = env(variable).map(PathBuf::from) else {
+            continue;
+        };
+        // the folder of a business account is named after the organization
+        let account = path
+            .to_str()
+            .and_then(|path| path.rsplit(['\\', '/']).next())
+            .and_then(|folder| folder.strip_prefix("OneDrive - "))
+            .or(fallback);
+        let name = match account {
+            Some(account) => format!("OneDrive – {}", account),
+            None => "OneDrive".to_string(),
+        };
+        add(path, name);
+    }
+    known
+}
+
+/**
+ * `OneDrive-Contoso` is shown as `OneDrive – Contoso`, `GoogleDrive-me@example.com`
+ * as `Google Drive – me@example.com`
+ */
+fn cloud_storage_name(folder: &str) -> String {
+    let (provider, account) = match folder.split_once('-') {
+        Some((provider, account)) if !account.is_empty() => (provider, Some(account)),
+        _ => (folder, None),
+    };
+    let provider = match provider {
+        "GoogleDrive" => "Google Drive",
+        other => other,
+    };
+    match account {
+        Some(account) => format!("{} – {}", provider, account),
+        None => provider.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known() -> Vec<KnownLocation> {
+        vec![
+            KnownLocation {
+                path: PathBuf::from("/Users/me"),
+                name: "Home".to_string(),
+            },
+            KnownLocation {
+                path: PathBuf::from("/Users/me/Library/Mobile Documents/com~apple~CloudDocs"),
+                name: "iCloud Drive".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_breadcrumbs_start_at_known_location() {
+        let crumbs = breadcrumbs_in(
+            &known(),
+            Path::new("/Users/me/Library/Mobile Documents/com~apple~CloudDocs/Projects/"),
+        );
+        let names: Vec<&str> = crumbs.iter().map(|crumb| crumb.name.as_str()).collect();
+        assert_eq!(names, vec!["iCloud Drive", "Projects"]);
+
+        let crumbs = breadcrumbs_in(&known(), Path::new("/Users/other"));
+        let names: Vec<&str> = crumbs.iter().map(|crumb| crumb.name.as_str()).collect();
+        assert_eq!(names, vec!["/", "Users", "other"]);
+        assert_eq!(crumbs[0].path, PathBuf::from("/"));
+    }
+
+    #[test]
+    fn test_onedrive_accounts_named() {
+        let env = |name: &str| match name {
+            "OneDriveCommercial" => Some(OsString::from(r"C:\Users\me\OneDrive - Contoso")),
+            "OneDriveConsumer" => Some(OsString::from(r"C:\Users\me\OneDrive")),
+            _ => None,
+        };
+        let names: Vec<String> = discover(None, &env)
+            .into_iter()
+            .map(|location| location.name)
+            .collect();
+        assert_eq!(names, vec!["OneDrive – Personal", "OneDrive – Contoso"]);
+    }
+
+    #[test]
+    fn test_cloud_storage_named() {
+        assert_eq!(cloud_storage_name("OneDrive-Contoso"), "OneDrive – Contoso");
+        assert_eq!(
+            cloud_storage_name("GoogleDrive-me@example.com"),
+            "Google Drive – me@example.com"
+        );
+        assert_eq!(cloud_storage_name("Dropbox"), "Dropbox");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_volume_names() {
+        assert_eq!(of_volume("Backup", Path::new("/Volumes/Backup")), "Backup");
+        assert_eq!(of_volume("", Path::new("/mnt/usb")), "usb");
+    }
+}
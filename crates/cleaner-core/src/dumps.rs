@@ -0,0 +1,250 @@
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{long_path, tree::Tree};
+
+/**
+ * bytes read from a dump to tell a full memory dump from a kernel one
+ */
+const HEADER_BYTES: u64 = 0x1000;
+
+/**
+ * offset of the dump type in the header of a 64 bit and of a 32 bit Windows crash dump
+ */
+const DUMP_TYPE_OFFSET: (usize, usize) = (0xF98, 0xF88);
+
+/**
+ * where Linux keeps the reports of crashed programs
+ */
+const CRASH_DIRECTORY: &str = "/var/crash";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum DumpKind {
+    /// the whole memory at the time of a crash, as large as the RAM
+    FullMemoryDump,
+    /// the kernel memory at the time of a crash, MEMORY.DMP by default
+    KernelDump,
+    /// the small dump of a crashed system or application
+    Minidump,
+    /// a report of a crashed program, in /var/crash
+    CrashReport,
+    Swapfile,
+}
+
+/**
+ * a memory dump, crash report or swapfile found in the scanned tree
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemDump {
+    pub path: PathBuf,
+    pub kind: DumpKind,
+    pub size: u64,
+    pub modified: Option<u64>,
+    /// false for a swapfile the system still uses
+    pub deletable: bool,
+    /// why it can't be deleted
+    pub reason: Option<String>,
+}
+
+/**
+ * what `path` is by its name and location alone, none for a regular file
+ */
+pub fn classify(path: &Path) -> Option<DumpKind> {
+    let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+
+    if name == "memory.dmp" {
+        return Some(DumpKind::KernelDump);
+    }
+    if matches!(extension.as_deref(), Some("dmp" | "mdmp" | "hdmp")) {
+        return Some(DumpKind::Minidump);
+    }
+    if path.starts_with(CRASH_DIRECTORY) {
+        return Some(DumpKind::CrashReport);
+    }
+    if name.starts_with("swapfile") || name == "swap.img" || name == "pagefile.sys" {
+        return Some(DumpKind::Swapfile);
+    }
+    None
+}
+
+/**
+ * The kind of `path` and whether it may go. A swapfile the system swaps to
+ * is refused, everything else is a leftover of a crash. Blocking.
+ */
+pub fn check(path: &Path) -> Result<DumpKind, String> {
+    let kind =
+        classify(path).ok_or_else(|| "not a memory dump, crash report or swapfile".to_string())?;
+    if kind == DumpKind::Swapfile && swap_in_use(path) {
+        return Err("in use by the system as swap".to_string());
+    }
+    Ok(refine(path, kind))
+}
+
+/**
+ * a dump named like a kernel or minidump may hold the whole memory, its header tells
+ */
+fn refine(path: &Path, kind: DumpKind) -> DumpKind {
+    if !matches!(kind, DumpKind::KernelDump | DumpKind::Minidump) {
+        return kind;
+    }
+    let mut header = Vec::new();
+    let read = File::open(long_path::extended(path))
+        .and_then(|file| file.take(HEADER_BYTES).read_to_end(&mut header));
+    match read {
+        Ok(_) => kind_of_header(&header).unwrap_or(kind),
+        Err(_) => kind,
+    }
+}
+
+fn kind_of_header(header: &[u8]) -> Option<DumpKind> {
+    if header.starts_with(b"MDMP") {
+        return Some(DumpKind::Minidump);
+    }
+    let offset = if header.starts_with(b"PAGEDU64") {
+        DUMP_TYPE_OFFSET.0
+    } else if header.starts_with(b"PAGEDUMP") {
+        DUMP_TYPE_OFFSET.1
+    } else {
+        return None;
+    };
+    let dump_type = u32::from_le_bytes(header.get(offset..offset + 4)?.try_into().ok()?);
+    Some(match dump_type {
+        // full and bitmap full
+        1 | 5 => DumpKind::FullMemoryDump,
+        // triage
+        4 => DumpKind::Minidump,
+        _ => DumpKind::KernelDump,
+    })
+}
+
+/**
+ * whether the system swaps to `path`, an unknown answer counts as in use
+ */
+#[cfg(target_os = "linux")]
+fn swap_in_use(path: &Path) -> bool {
+    std::fs::read_to_string("/proc/swaps").map_or(true, |swaps| is_listed(&swaps, path))
+}
+
+/**
+ * a pagefile in use is held open without sharing
+ */
+#[cfg(windows)]
+fn swap_in_use(path: &Path) -> bool {
+    File::open(long_path::extended(path)).is_err()
+}
+
+/**
+ * the dynamic pager manages its swapfiles, none is ever left behind
+ */
+#[cfg(not(any(target_os = "linux", windows)))]
+fn swap_in_use(_path: &Path) -> bool {
+    true
+}
+
+/**
+ * whether `/proc/swaps` lists `path`, the first column after the header line
+ */
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn is_listed(swaps: &str, path: &Path) -> bool {
+    swaps
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .any(|swap| Path::new(swap) == path)
+}
+
+/**
+ * Every memory dump, crash report and swapfile below `root` in the tree, the
+ * largest first. Dumps are read for their header and swapfiles checked
+ * against the active swap, blocking.
+ */
+pub fn find(tree: &Tree, root: &Path) -> Vec<SystemDump> {
+    let mut found = vec![];
+    tree.walk(root, |path, node| {
+        if node.is_directory || node.is_link || classify(path).is_none() {
+            return;
+        }
+        found.push((path.clone(), node.size() as u64, node.modified));
+    });
+
+    let mut dumps: Vec<SystemDump> = found
+        .into_iter()
+        .filter_map(|(path, size, modified)| {
+            let kind = classify(&path)?;
+            let (kind, reason) = match check(&path) {
+                Ok(kind) => (kind, None),
+                Err(reason) => (kind, Some(reason)),
+            };
+            Some(SystemDump {
+                path,
+                kind,
+                size,
+                modified,
+                deletable: reason.is_none(),
+                reason,
+            })
+        })
+        .collect();
+    dumps.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    dumps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dumps_classified_by_name() {
+        let kind = |path: &str| classify(Path::new(path));
+        assert_eq!(
+            kind("/mnt/c/Windows/MEMORY.DMP"),
+            Some(DumpKind::KernelDump)
+        );
+        assert_eq!(
+            kind("/mnt/c/Windows/Minidump/101624-8718-01.dmp"),
+            Some(DumpKind::Minidump)
+        );
+        assert_eq!(
+            kind("/var/crash/_usr_bin_app.1000.crash"),
+            Some(DumpKind::CrashReport)
+        );
+        assert_eq!(kind("/swapfile"), Some(DumpKind::Swapfile));
+        assert_eq!(kind("/swap.img"), Some(DumpKind::Swapfile));
+        assert_eq!(kind("/home/me/notes.txt"), None);
+    }
+
+    #[test]
+    fn test_full_memory_dump_told_by_header() {
+        let mut header = vec![0u8; HEADER_BYTES as usize];
+        header[..8].copy_from_slice(b"PAGEDU64");
+        header[DUMP_TYPE_OFFSET.0..DUMP_TYPE_OFFSET.0 + 4].copy_from_slice(&1u32.to_le_bytes());
+        assert_eq!(kind_of_header(&header), Some(DumpKind::FullMemoryDump));
+
+        header[DUMP_TYPE_OFFSET.0..DUMP_TYPE_OFFSET.0 + 4].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(kind_of_header(&header), Some(DumpKind::KernelDump));
+        assert_eq!(kind_of_header(b"MDMP\x93\xa7"), Some(DumpKind::Minidump));
+        assert_eq!(kind_of_header(b"PAGEDU64"), None);
+        assert_eq!(kind_of_header(b"hello"), None);
+    }
+
+    #[test]
+    fn test_active_swap_listed() {
+        let swaps = "Filename\tType\tSize\tUsed\tPriority\n\
+            /swapfile                               file\t\t2097148\t\t0\t\t-2\n";
+        assert!(is_listed(swaps, Path::new("/swapfile")));
+        assert!(!is_listed(swaps, Path::new("/swap.img")));
+    }
+}
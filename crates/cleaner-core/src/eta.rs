@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/**
+ * share of the work to be done before a remaining time is worth showing,
+ * the first directories say little about the rest of the disk
+ */
+const MIN_FRACTION: f64 = 0.02;
+/**
+ * the estimate never reaches the end by itself, the target is only a guess
+ */
+const MAX_FRACTION: f64 = 0.99;
+
+/**
+ * what the progress of a scan is measured against
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum EstimateBasis {
+    /// bytes found against the used bytes of the volume
+    Size,
+    /// entries found against the entries of the previous scan
+    Entries,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanTarget {
+    pub basis: EstimateBasis,
+    pub total: u64,
+}
+
+impl ScanTarget {
+    pub fn new(basis: EstimateBasis, total: u64) -> Option<Self> {
+        (total > 0).then_some(Self { basis, total })
+    }
+
+    /**
+     * share of the target reached by `entries` and `size`, capped below one
+     */
+    pub fn fraction(&self, entries: usize, size: usize) -> f64 {
+        let done = match self.basis {
+            EstimateBasis::Size => size,
+            EstimateBasis::Entries => entries,
+        };
+        (done as f64 / self.total as f64).min(MAX_FRACTION)
+    }
+}
+
+/**
+ * time left at the average pace so far, none while too little is done to tell
+ */
+pub fn remaining(fraction: f64, elapsed: Duration) -> Option<Duration> {
+    (fraction >= MIN_FRACTION).then(|| elapsed.mul_f64((1.0 - fraction) / fraction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fraction_by_basis() {
+        let size = ScanTarget::new(EstimateBasis::Size, 1000).unwrap();
+        assert_eq!(size.fraction(10, 250), 0.25);
+        assert_eq!(size.fraction(10, 5000), MAX_FRACTION);
+
+        let entries = ScanTarget::new(EstimateBasis::Entries, 40).unwrap();
+        assert_eq!(entries.fraction(10, 5000), 0.25);
+        assert!(ScanTarget::new(EstimateBasis::Entries, 0).is_none());
+    }
+
+    #[test]
+    fn test_remaining_at_average_pace() {
+        let elapsed = Duration::from_secs(60);
+        assert_eq!(remaining(0.25, elapsed), Some(Duration::from_secs(180)));
+        assert_eq!(remaining(0.01, elapsed), None);
+    }
+}
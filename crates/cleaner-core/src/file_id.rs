@@ -0,0 +1,114 @@
+use std::{fs::Metadata, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/**
+ * What a file is, whatever its path: the volume it lives on and its number
+ * there. A rename keeps it, every hard link of a file shares it, and the
+ * number of a deleted file may be handed out again.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileId {
+    /// `st_dev` on unix, the volume serial number on Windows
+    pub volume: u64,
+    /// the inode on unix, the file id on Windows
+    pub file: u64,
+}
+
+impl FileId {
+    /**
+     * the identity `metadata` carries, only unix keeps it there
+     */
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &Metadata) -> Option<FileId> {
+        use std::os::unix::fs::MetadataExt;
+
+        Some(FileId {
+            volume: metadata.dev(),
+            file: metadata.ino(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn from_metadata(_metadata: &Metadata) -> Option<FileId> {
+        None
+    }
+
+    /**
+     * the identity of the file `metadata` describes, asked from a handle to
+     * `path` where the metadata doesn't carry it. The handle follows links
+     * the way `fs::metadata` does.
+     */
+    pub fn of(path: &Path, metadata: &Metadata) -> Option<FileId> {
+        Self::from_metadata(metadata).or_else(|| Self::open(path))
+    }
+
+    /**
+     * FILE_ID_INFO of a handle opened without read access, so a file another
+     * process holds open still tells its id. ReFS ids wider than 64 bits are
+     * folded, NTFS ids always fit.
+     */
+    #[cfg(windows)]
+    fn open(path: &Path) -> Option<FileId> {
+        use std::os::windows::{fs::OpenOptionsExt, io::AsRawHandle};
+        use windows_sys::Win32::Storage::FileSystem::{
+            FILE_FLAG_BACKUP_SEMANTICS, FILE_ID_INFO, FILE_READ_ATTRIBUTES, FileIdInfo,
+            GetFileInformationByHandleEx,
+        };
+
+        let file = std::fs::OpenOptions::new()
+            .access_mode(FILE_READ_ATTRIBUTES)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(crate::long_path::extended(path))
+            .ok()?;
+        let mut info: FILE_ID_INFO = unsafe { std::mem::zeroed() };
+        let read = unsafe {
+            GetFileInformationByHandleEx(
+                file.as_raw_handle() as _,
+                FileIdInfo,
+                &mut info as *mut FILE_ID_INFO as *mut _,
+                std::mem::size_of::<FILE_ID_INFO>() as u32,
+            )
+        };
+        let id = u128::from_le_bytes(info.FileId.Identifier);
+        (read != 0).then_some(FileId {
+            volume: info.VolumeSerialNumber,
+            file: (id as u64) ^ (id >> 64) as u64,
+        })
+    }
+
+    #[cfg(not(windows))]
+    fn open(_path: &Path) -> Option<FileId> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    fn id(path: &Path) -> Option<FileId> {
+        FileId::of(path, &std::fs::symlink_metadata(path).unwrap())
+    }
+
+    #[test]
+    fn test_rename_keeps_the_id() {
+        let dir = temp_path("file-id");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+        std::fs::write(dir.join("b.txt"), "b").unwrap();
+
+        let a = id(&dir.join("a.txt"));
+        assert!(a.is_some());
+        assert_ne!(id(&dir.join("b.txt")), a);
+        std::fs::rename(dir.join("a.txt"), dir.join("renamed.txt")).unwrap();
+        assert_eq!(id(&dir.join("renamed.txt")), a);
+
+        std::fs::hard_link(dir.join("renamed.txt"), dir.join("link.txt")).unwrap();
+        assert_eq!(id(&dir.join("link.txt")), a);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
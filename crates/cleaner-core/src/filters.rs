@@ -0,0 +1,117 @@
+use std::{io, path::Path};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/**
+ * paths never listed whatever the user configured, the data volume of macOS
+ * is mounted below the root a second time
+ */
+pub const SYSTEM_EXCLUDES: &[&str] = &["/System/Volumes/Data"];
+
+/**
+ * what a scan leaves out, as the user configured it
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ScanFilters {
+    /// globs matched against the whole path, e.g. `**/node_modules` or `*.iso`
+    pub exclude: Vec<String>,
+    /// files smaller than this get no node of their own, their bytes still count in their directory
+    pub min_file_size: Option<u64>,
+}
+
+/**
+ * The filters compiled into one matcher, the system excludes included. An
+ * excluded entry never enters the tree, neither its size nor, for a
+ * directory, anything below it.
+ */
+#[derive(Debug, Clone)]
+pub struct FilterMatcher {
+    filters: ScanFilters,
+    set: GlobSet,
+}
+
+impl FilterMatcher {
+    /**
+     * compile `filters`, an invalid glob is rejected with the pattern in the message
+     */
+    pub fn new(filters: ScanFilters) -> io::Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        let patterns = SYSTEM_EXCLUDES
+            .iter()
+            .copied()
+            .chain(filters.exclude.iter().map(String::as_str));
+        for pattern in patterns {
+            let glob = Glob::new(pattern).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid exclude pattern {}: {}", pattern, err),
+                )
+            })?;
+            builder.add(glob);
+        }
+        let set = builder.build().map_err(io::Error::other)?;
+        Ok(Self { filters, set })
+    }
+
+    pub fn filters(&self) -> &ScanFilters {
+        &self.filters
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.set.is_match(path)
+    }
+
+    /**
+     * whether a file of `size` bytes is only added to the size of its directory
+     */
+    pub fn folds(&self, size: u64) -> bool {
+        self.filters.min_file_size.is_some_and(|min| size < min)
+    }
+}
+
+impl Default for FilterMatcher {
+    fn default() -> Self {
+        // the system excludes are valid globs
+        Self::new(ScanFilters::default()).expect("system excludes compile")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclude_patterns_match_whole_path() {
+        let matcher = FilterMatcher::new(ScanFilters {
+            exclude: vec!["**/node_modules".to_string(), "*.iso".to_string()],
+            ..ScanFilters::default()
+        })
+        .unwrap();
+        assert!(matcher.is_excluded(Path::new("/home/me/app/node_modules")));
+        assert!(matcher.is_excluded(Path::new("/home/me/Downloads/ubuntu.iso")));
+        assert!(matcher.is_excluded(Path::new("/System/Volumes/Data")));
+        assert!(!matcher.is_excluded(Path::new("/home/me/app/node_modules.txt")));
+        assert!(!matcher.is_excluded(Path::new("/home/me/app/src")));
+
+        let invalid = FilterMatcher::new(ScanFilters {
+            exclude: vec!["[unclosed".to_string()],
+            ..ScanFilters::default()
+        });
+        assert_eq!(invalid.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_small_files_folded() {
+        assert!(!FilterMatcher::default().folds(0));
+        let matcher = FilterMatcher::new(ScanFilters {
+            min_file_size: Some(1024),
+            ..ScanFilters::default()
+        })
+        .unwrap();
+        assert!(matcher.folds(1023));
+        assert!(!matcher.folds(1024));
+    }
+}
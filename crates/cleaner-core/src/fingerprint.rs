@@ -0,0 +1,301 @@
+use std::{
+    cmp::Reverse,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{long_path, tree::Tree};
+
+const MAGIC: &[u8; 4] = b"CLBF";
+/**
+ * sized for about a million directories at a 1% false positive rate
+ */
+const DEFAULT_BITS: usize = 10 * 1024 * 1024;
+const DEFAULT_HASHES: u32 = 7;
+
+/**
+ * Bloom filter over `(directory path, mtime)` pairs seen by the last scan.
+ * A directory whose pair is missing has certainly changed, a present pair
+ * means it is probably unchanged, which is good enough for a startup
+ * freshness check without loading the previous snapshot.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryFingerprints {
+    hashes: u32,
+    bits: Vec<u64>,
+}
+
+impl Default for DirectoryFingerprints {
+    fn default() -> Self {
+        Self::with_size(DEFAULT_BITS, DEFAULT_HASHES)
+    }
+}
+
+impl DirectoryFingerprints {
+    pub fn with_size(bits: usize, hashes: u32) -> Self {
+        Self {
+            hashes,
+            bits: vec![0; bits.div_ceil(64).max(1)],
+        }
+    }
+
+    /**
+     * two independent 64 bit hashes from blake3, stable across runs and builds
+     */
+    fn seeds(path: &Path, modified: u64) -> (u64, u64) {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(path.as_os_str().as_encoded_bytes());
+        hasher.update(&modified.to_le_bytes());
+        let digest = hasher.finalize();
+        let bytes = digest.as_bytes();
+        (
+            u64::from_le_bytes(bytes[0..8].try_into().unwrap_or_default()),
+            u64::from_le_bytes(bytes[8..16].try_into().unwrap_or_default()),
+        )
+    }
+
+    fn positions(&self, path: &Path, modified: u64) -> impl Iterator<Item = usize> + '_ {
+        let (h1, h2) = Self::seeds(path, modified);
+        let len = (self.bits.len() * 64) as u64;
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    /**
+     * fingerprint the directories enumerated below `root`, a directory still waiting
+     * for its listing has no children yet and is left out so an interrupted scan only
+     * vouches for what it read. `None` when nothing below `root` was enumerated.
+     */
+    pub fn from_tree(tree: &Tree, root: &Path) -> Option<Self> {
+        let mut fingerprints = Self::default();
+        let mut inserted = 0;
+        tree.walk(root, |path, node| {
+            if !node.is_directory || node.is_link || node.children.is_empty() {
+                return;
+            }
+
+            // the scan root itself carries no metadata
+            let modified = node.modified.or_else(|| {
+                fs::symlink_metadata(path)
+                    .ok()
+                    .map(|metadata| mtime(&metadata))
+            });
+            if let Some(modified) = modified {
+                fingerprints.insert(path, modified);
+                inserted += 1;
+            }
+        });
+        (inserted > 0).then_some(fingerprints)
+    }
+
+    /**
+     * where the fingerprints of `root` are kept inside `dir`, one file per scan root
+     */
+    pub fn file_for(dir: &Path, root: &Path) -> PathBuf {
+        let digest = blake3::hash(root.as_os_str().as_encoded_bytes());
+        dir.join(format!("fingerprints-{}.bin", &digest.to_hex()[..16]))
+    }
+
+    /**
+     * Delete the fingerprint files in `dir` not written for `max_age`, then
+     * the oldest ones past `max_files`. Returns the files and bytes removed.
+     */
+    pub fn prune(dir: &Path, max_age: Duration, max_files: usize) -> io::Result<(usize, u64)> {
+        let mut files: Vec<(PathBuf, SystemTime, u64)> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.starts_with("fingerprints-") && name.ends_with(".bin")
+            })
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((entry.path(), metadata.modified().ok()?, metadata.len()))
+            })
+            .collect();
+        // newest first, whatever is past the cap is the oldest
+        files.sort_by_key(|(_, modified, _)| Reverse(*modified));
+
+        let now = SystemTime::now();
+        let (mut removed, mut bytes) = (0, 0);
+        for (index, (path, modified, size)) in files.into_iter().enumerate() {
+            let expired = now.duration_since(modified).is_ok_and(|age| age > max_age);
+            if (expired || index >= max_files) && fs::remove_file(&path).is_ok() {
+                removed += 1;
+                bytes += size;
+            }
+        }
+        Ok((removed, bytes))
+    }
+
+    pub fn insert(&mut self, path: &Path, modified: u64) {
+        let positions: Vec<usize> = self.positions(path, modified).collect();
+        for pos in positions {
+            self.bits[pos / 64] |= 1 << (pos % 64);
+        }
+    }
+
+    pub fn probably_unchanged(&self, path: &Path, modified: u64) -> bool {
+        self.positions(path, modified)
+            .all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(12 + self.bits.len() * 8);
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&self.hashes.to_le_bytes());
+        buf.extend_from_slice(&(self.bits.len() as u32).to_le_bytes());
+        for word in &self.bits {
+            buf.extend_from_slice(&word.to_le_bytes());
+        }
+        fs::write(path, buf)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let buf = fs::read(path)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid fingerprint file");
+        if buf.len() < 12 || &buf[0..4] != MAGIC {
+            return Err(invalid());
+        }
+
+        let hashes = u32::from_le_bytes(buf[4..8].try_into().map_err(|_| invalid())?);
+        let words = u32::from_le_bytes(buf[8..12].try_into().map_err(|_| invalid())?) as usize;
+        if buf.len() != 12 + words * 8 {
+            return Err(invalid());
+        }
+
+        let bits = buf[12..]
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap_or_default()))
+            .collect();
+        Ok(Self { hashes, bits })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Freshness {
+    pub checked: usize,
+    /// directories which certainly changed since the last scan, capped by the limit
+    pub changed: Vec<PathBuf>,
+    pub changed_count: usize,
+}
+
+pub(crate) fn mtime(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+/**
+ * walk the directories below `root` comparing only their mtimes against the fingerprints
+ */
+pub fn check_freshness(
+    fingerprints: &DirectoryFingerprints,
+    root: &Path,
+    limit: usize,
+) -> Freshness {
+    let mut freshness = Freshness {
+        checked: 0,
+        changed: vec![],
+        changed_count: 0,
+    };
+
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(metadata) = fs::symlink_metadata(long_path::extended(&dir)) else {
+            continue;
+        };
+
+        freshness.checked += 1;
+        if !fingerprints.probably_unchanged(&dir, mtime(&metadata)) {
+            freshness.changed_count += 1;
+            if freshness.changed.len() < limit {
+                freshness.changed.push(dir.clone());
+            }
+        }
+
+        if let Ok(entries) = fs::read_dir(long_path::extended(&dir)) {
+            for entry in entries.flatten() {
+                if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                    // the plain form, the fingerprints were keyed by it
+                    stack.push(dir.join(entry.file_name()));
+                }
+            }
+        }
+    }
+
+    freshness
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+    use crate::tree::node::Node;
+    use std::ffi::OsString;
+
+    #[test]
+    fn test_fingerprint_roundtrip() {
+        let mut fingerprints = DirectoryFingerprints::with_size(4096, 5);
+        fingerprints.insert(Path::new("/home/me"), 100);
+
+        assert!(fingerprints.probably_unchanged(Path::new("/home/me"), 100));
+        assert!(!fingerprints.probably_unchanged(Path::new("/home/me"), 101));
+
+        let file = temp_path("fingerprints.bin");
+        fingerprints.save(&file).unwrap();
+        assert_eq!(DirectoryFingerprints::load(&file).unwrap(), fingerprints);
+    }
+
+    #[test]
+    fn test_prune_keeps_newest_files() {
+        let dir = temp_path("fingerprints-prune");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for root in ["/a", "/b", "/c"] {
+            let file = DirectoryFingerprints::file_for(&dir, Path::new(root));
+            DirectoryFingerprints::with_size(64, 1).save(&file).unwrap();
+        }
+        fs::write(dir.join("unrelated.bin"), b"keep").unwrap();
+
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert_eq!(DirectoryFingerprints::prune(&dir, day, 3).unwrap().0, 0);
+        assert_eq!(DirectoryFingerprints::prune(&dir, day, 1).unwrap().0, 2);
+        assert_eq!(
+            DirectoryFingerprints::prune(&dir, Duration::ZERO, 1)
+                .unwrap()
+                .0,
+            1
+        );
+        assert!(dir.join("unrelated.bin").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_from_tree_skips_pending_directories() {
+        let root = PathBuf::from("/");
+        let mut tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+
+        let mut listed = Node::new(OsString::from("listed"), true, false);
+        listed.modified = Some(10);
+        let mut pending = Node::new(OsString::from("pending"), true, false);
+        pending.modified = Some(20);
+        let _ = tree.insert(&root, listed);
+        let _ = tree.insert(&root, pending);
+        let _ = tree.insert(
+            &PathBuf::from("/listed"),
+            Node::new(OsString::from("a.txt"), false, false),
+        );
+
+        let fingerprints = DirectoryFingerprints::from_tree(&tree, &root).unwrap();
+        assert!(fingerprints.probably_unchanged(Path::new("/listed"), 10));
+        assert!(!fingerprints.probably_unchanged(Path::new("/pending"), 20));
+        assert!(DirectoryFingerprints::from_tree(&tree, &PathBuf::from("/pending")).is_none());
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::long_path::extended;
+
+/**
+ * shape of a generated tree, the same spec and seed always produce the same files
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct FixtureSpec {
+    pub depth: usize,
+    pub directories: usize,
+    pub files: usize,
+    /// upper bound of a regular file, in bytes
+    pub max_file_size: usize,
+    /// extra characters in every directory name, deep trees then pass MAX_PATH
+    pub padding: usize,
+    pub seed: u64,
+}
+
+impl Default for FixtureSpec {
+    fn default() -> Self {
+        Self {
+            depth: 3,
+            directories: 3,
+            files: 4,
+            max_file_size: 16 * 1024,
+            padding: 0,
+            seed: 0x5eed,
+        }
+    }
+}
+
+/**
+ * A synthetic directory tree under the temp dir, with regular, sparse and
+ * hardlinked files plus symlinks on unix. Every size is recorded as it is
+ * created, so the totals are known without scanning. Removed on drop.
+ */
+pub struct Fixture {
+    pub root: PathBuf,
+    /// apparent bytes below each directory, directories weigh nothing of their own
+    pub sizes: HashMap<PathBuf, usize>,
+    /// bytes below each directory a hardlink shares with an entry counted before it
+    pub savings: HashMap<PathBuf, usize>,
+    /// entries below the root, directories and links included
+    pub entries: usize,
+    seed: u64,
+}
+
+/**
+ * a path under the temp dir only this test run uses, so concurrent runs of
+ * the suite don't trip over each other's files
+ */
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("cleaner-{}-{}", std::process::id(), name))
+}
+
+impl Fixture {
+    pub fn generate(name: &str, spec: FixtureSpec) -> io::Result<Self> {
+        let root = temp_path(&format!("fixture-{}", name));
+        if root.exists() {
+            fs::remove_dir_all(extended(&root))?;
+        }
+        fs::create_dir_all(&root)?;
+
+        let mut fixture = Fixture {
+            sizes: HashMap::from([(root.clone(), 0)]),
+            savings: HashMap::from([(root.clone(), 0)]),
+            root: root.clone(),
+            entries: 0,
+            seed: spec.seed,
+        };
+        fixture.fill(&root, spec, spec.depth)?;
+        Ok(fixture)
+    }
+
+    pub fn size_of(&self, dir: &Path) -> Option<usize> {
+        self.sizes.get(dir).copied()
+    }
+
+    fn fill(&mut self, dir: &Path, spec: FixtureSpec, depth: usize) -> io::Result<()> {
+        for index in 0..spec.files {
+            let path = dir.join(format!("file-{}.bin", index));
+            let size = self.next() as usize % spec.max_file_size;
+            File::create(extended(&path))?.write_all(&vec![index as u8; size])?;
+            self.record(&path)?;
+        }
+
+        // holes read back as zeros but still count towards the apparent size
+        let sparse = dir.join("sparse.img");
+        File::create(extended(&sparse))?.set_len(spec.max_file_size as u64 * 16)?;
+        self.record(&sparse)?;
+
+        fs::hard_link(
+            extended(&dir.join("file-0.bin")),
+            extended(&dir.join("hardlink.bin")),
+        )?;
+        self.record_link(&dir.join("hardlink.bin"))?;
+
+        #[cfg(unix)]
+        {
+            let link = dir.join("symlink");
+            std::os::unix::fs::symlink(&sparse, &link)?;
+            self.record(&link)?;
+        }
+
+        if depth == 0 {
+            return Ok(());
+        }
+
+        for index in 0..spec.directories {
+            let child = dir.join(format!("dir-{}{}", index, "x".repeat(spec.padding)));
+            fs::create_dir(extended(&child))?;
+            self.sizes.insert(child.clone(), 0);
+            self.savings.insert(child.clone(), 0);
+            self.record(&child)?;
+            self.fill(&child, spec, depth - 1)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * add the apparent size of a new entry to every directory above it, a
+     * directory only counts as an entry
+     */
+    fn record(&mut self, path: &Path) -> io::Result<()> {
+        let metadata = fs::symlink_metadata(extended(path))?;
+        let size = if metadata.is_dir() {
+            0
+        } else {
+            metadata.len() as usize
+        };
+        self.add(path, size, 0);
+        Ok(())
+    }
+
+    /**
+     * a second link to a file recorded before, it adds nothing but savings
+     */
+    fn record_link(&mut self, path: &Path) -> io::Result<()> {
+        let size = fs::symlink_metadata(extended(path))?.len() as usize;
+        self.add(path, 0, size);
+        Ok(())
+    }
+
+    fn add(&mut self, path: &Path, size: usize, saved: usize) {
+        self.entries += 1;
+        for ancestor in path.ancestors().skip(1) {
+            if let Some(total) = self.sizes.get_mut(ancestor) {
+                *total += size;
+            }
+            if let Some(total) = self.savings.get_mut(ancestor) {
+                *total += saved;
+            }
+            if ancestor == self.root {
+                break;
+            }
+        }
+    }
+
+    /**
+     * xorshift, good enough for varied file sizes and free of dependencies
+     */
+    fn next(&mut self) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(extended(&self.root));
+    }
+}
@@ -0,0 +1,941 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::Instant,
+};
+
+#[cfg(unix)]
+use memmap2::Advice;
+use memmap2::Mmap;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use specta::Type;
+use tracing::debug;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::{
+    database::{Database, to_io},
+    file_id::FileId,
+    long_path, lossless,
+    models::Volumn,
+};
+
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/**
+ * files from this size on are memory mapped where allowed, below it the
+ * buffered reads are as fast and need no map
+ */
+pub const MMAP_THRESHOLD: u64 = 1 << 30;
+
+/**
+ * Whether a file is safe from shrinking under its map. Windows refuses to
+ * truncate a file while a view of it is mapped. Elsewhere another process
+ * truncating a file while it is hashed raises SIGBUS and takes the whole
+ * app down, where a buffered read would just come back short, so files
+ * which may change under the hash are never mapped there.
+ */
+const MAP_KEEPS_LENGTH: bool = cfg!(windows);
+
+/**
+ * bytes hashed before the next chunk of a mapped file is prefetched
+ */
+const MMAP_CHUNK: usize = 16 << 20;
+
+/**
+ * filesystem types as the OS reports them which are reached over the network
+ */
+const NETWORK_FILE_SYSTEMS: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "smbfs",
+    "cifs",
+    "smb3",
+    "afpfs",
+    "webdav",
+    "davfs",
+    "fuse.sshfs",
+    "9p",
+];
+
+/**
+ * how file contents are hashed, every stored hash records its algorithm
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum HashAlgorithm {
+    /// cryptographic and fast with SIMD, the default for duplicates
+    #[default]
+    Blake3,
+    /// not cryptographic, a quick pre-filter before a full hash confirms a match
+    Xxh3,
+    /// the well known one, for manifests read by other tools
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /**
+     * the name stored next to the hash in the index
+     */
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Xxh3 => "xxh3",
+            HashAlgorithm::Sha256 => "sha256",
+        }
+    }
+
+    /**
+     * Blake3 gets most of its speed from SIMD. Without any, a CPU with SHA
+     * instructions hashes sha256 faster than the portable blake3.
+     */
+    pub fn preferred(acceleration: HashAcceleration) -> Self {
+        if !acceleration.simd && acceleration.sha_extensions {
+            HashAlgorithm::Sha256
+        } else {
+            HashAlgorithm::Blake3
+        }
+    }
+}
+
+/**
+ * instructions of the running CPU which speed up hashing, detected at runtime
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct HashAcceleration {
+    /// AVX2 or SSE4.1 on x86, NEON on ARM
+    pub simd: bool,
+    /// SHA-NI on x86, the SHA2 extension on ARM
+    pub sha_extensions: bool,
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn acceleration() -> HashAcceleration {
+    HashAcceleration {
+        simd: is_x86_feature_detected!("avx2") || is_x86_feature_detected!("sse4.1"),
+        sha_extensions: is_x86_feature_detected!("sha"),
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub fn acceleration() -> HashAcceleration {
+    HashAcceleration {
+        simd: std::arch::is_aarch64_feature_detected!("neon"),
+        sha_extensions: std::arch::is_aarch64_feature_detected!("sha2"),
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn acceleration() -> HashAcceleration {
+    HashAcceleration::default()
+}
+
+/**
+ * progress of hashing one file, reported after every chunk
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct HashProgress {
+    pub path: PathBuf,
+    pub algorithm: HashAlgorithm,
+    pub hashed: u64,
+    pub size: u64,
+    /// throughput of this file so far
+    pub bytes_per_sec: u64,
+    /// read through a memory map instead of buffered reads
+    pub mapped: bool,
+}
+
+/**
+ * Whether `file_system` is reached over the network. A page fault of a mapped
+ * file there waits on the network, and a dropped connection turns it into a
+ * crash instead of an I/O error.
+ */
+pub fn is_network_file_system(file_system: &str) -> bool {
+    NETWORK_FILE_SYSTEMS
+        .iter()
+        .any(|network| file_system.eq_ignore_ascii_case(network))
+}
+
+/**
+ * whether `path` may be memory mapped, only when its volume is known and local
+ */
+pub fn may_map(path: &Path, volumes: &[Volumn]) -> bool {
+    volumes
+        .iter()
+        .filter(|volume| path.starts_with(&volume.path))
+        .max_by_key(|volume| volume.path.components().count())
+        .is_some_and(|volume| !is_network_file_system(&volume.file_system))
+}
+
+/**
+ * blake3 content hash of a file as lowercase hex
+ */
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    hash_file_with(path, HashAlgorithm::Blake3)
+}
+
+/**
+ * content hash of a file with `algorithm` as lowercase hex, read buffered
+ */
+pub fn hash_file_with(path: &Path, algorithm: HashAlgorithm) -> io::Result<String> {
+    hash_file_observed(path, algorithm, false, &mut |_| {})
+}
+
+/**
+ * Content hash of a file with `algorithm`, `observe` sees the progress after
+ * every chunk. With `map` a file from `MMAP_THRESHOLD` on is read through a
+ * memory map, the next chunk prefetched while the current one is hashed,
+ * on the platforms where `MAP_KEEPS_LENGTH` holds.
+ */
+pub fn hash_file_observed(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    map: bool,
+    observe: &mut dyn FnMut(&HashProgress),
+) -> io::Result<String> {
+    let mut file = File::open(long_path::extended(path))?;
+    let size = file.metadata()?.len();
+    let mapped = map && MAP_KEEPS_LENGTH && size >= MMAP_THRESHOLD;
+    let mut hasher = Hasher::new(algorithm);
+    let started = Instant::now();
+    let mut progress = HashProgress {
+        path: path.to_path_buf(),
+        algorithm,
+        hashed: 0,
+        size,
+        bytes_per_sec: 0,
+        mapped,
+    };
+    let mut update = |chunk: &[u8]| {
+        hasher.update(chunk);
+        progress.hashed += chunk.len() as u64;
+        let elapsed = started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            progress.bytes_per_sec = (progress.hashed as f64 / elapsed) as u64;
+        }
+        observe(&progress);
+    };
+
+    if mapped {
+        read_mapped(&file, &mut update)?;
+    } else {
+        read_buffered(&mut file, &mut update)?;
+    }
+    Ok(hasher.finalize())
+}
+
+fn read_buffered(reader: &mut impl Read, update: &mut impl FnMut(&[u8])) -> io::Result<()> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(read) => update(&buffer[..read]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn read_mapped(file: &File, update: &mut impl FnMut(&[u8])) -> io::Result<()> {
+    // SAFETY: only reached where `MAP_KEEPS_LENGTH` holds, the file can't be truncated
+    // under the map and every page stays backed. A write racing the hash makes it as
+    // wrong as it would make a buffered read, and network filesystems where the map
+    // could vanish are never mapped.
+    let map = unsafe { Mmap::map(file)? };
+    #[cfg(unix)]
+    let _ = map.advise(Advice::Sequential);
+
+    for (index, chunk) in map.chunks(MMAP_CHUNK).enumerate() {
+        #[cfg(unix)]
+        {
+            // fault the next chunk in while this one is hashed
+            let next = (index + 1) * MMAP_CHUNK;
+            if next < map.len() {
+                let _ = map.advise_range(Advice::WillNeed, next, MMAP_CHUNK.min(map.len() - next));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = index;
+        update(chunk);
+    }
+    Ok(())
+}
+
+enum Hasher {
+    Blake3(Box<blake3::Hasher>),
+    Xxh3(Box<Xxh3>),
+    Sha256(Sha256),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgorithm::Xxh3 => Hasher::Xxh3(Box::new(Xxh3::new())),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+        }
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        match self {
+            Hasher::Blake3(hasher) => {
+                hasher.update(chunk);
+            }
+            Hasher::Xxh3(hasher) => hasher.update(chunk),
+            Hasher::Sha256(hasher) => hasher.update(chunk),
+        }
+    }
+
+    /**
+     * the hash as lowercase hex
+     */
+    fn finalize(self) -> String {
+        match self {
+            Hasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+            Hasher::Xxh3(hasher) => format!("{:016x}", hasher.digest()),
+            Hasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+        }
+    }
+}
+
+/**
+ * Completed file hashes persisted in SQLite, keyed by the exact bytes of the
+ * path and the algorithm and
+ * validated by size and mtime. Every file is recorded as soon as it is hashed,
+ * so an interrupted run resumes where it stopped instead of starting over.
+ * Hashes of another algorithm stay valid, they are used once it is chosen again.
+ * The identity of the file is recorded too, a file renamed or moved on its
+ * volume keeps its hash.
+ */
+#[derive(Clone)]
+pub struct HashIndex {
+    conn: Arc<Mutex<Connection>>,
+    /// what `hash` uses, shared by the clones
+    algorithm: Arc<Mutex<HashAlgorithm>>,
+    /// mounted volumes, a large file is only memory mapped on a known local one
+    volumes: Arc<Mutex<Vec<Volumn>>>,
+}
+
+impl HashIndex {
+    pub fn new(database: &Database) -> io::Result<Self> {
+        let shared = database.connection();
+        let conn = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        Self::migrate(&conn).map_err(to_io)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS file_hashes (
+                path BLOB NOT NULL,
+                algorithm TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                modified INTEGER NOT NULL,
+                hash TEXT NOT NULL,
+                PRIMARY KEY (path, algorithm)
+            );",
+        )
+        .map_err(to_io)?;
+        Self::add_identity(&conn).map_err(to_io)?;
+        Self::key_by_bytes(&conn).map_err(to_io)?;
+
+        Ok(Self {
+            conn: Arc::clone(&shared),
+            algorithm: Arc::new(Mutex::new(HashAlgorithm::default())),
+            volumes: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    pub fn in_memory() -> io::Result<Self> {
+        Self::new(&Database::in_memory()?)
+    }
+
+    /**
+     * an index written before the algorithm was recorded only holds blake3 hashes
+     */
+    fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+        let (columns, has_algorithm): (i64, i64) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(name = 'algorithm'), 0) FROM pragma_table_info('file_hashes')",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if columns == 0 || has_algorithm > 0 {
+            return Ok(());
+        }
+
+        debug!("record the algorithm of the stored hashes");
+        conn.execute_batch(
+            "BEGIN;
+            ALTER TABLE file_hashes RENAME TO file_hashes_unversioned;
+            CREATE TABLE file_hashes (
+                path TEXT NOT NULL,
+                algorithm TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                modified INTEGER NOT NULL,
+                hash TEXT NOT NULL,
+                PRIMARY KEY (path, algorithm)
+            );
+            INSERT INTO file_hashes (path, algorithm, size, modified, hash)
+                SELECT path, 'blake3', size, modified, hash FROM file_hashes_unversioned;
+            DROP TABLE file_hashes_unversioned;
+            COMMIT;",
+        )
+    }
+
+    /**
+     * the volume and file number of the hashed file, null for the hashes
+     * recorded before they were
+     */
+    fn add_identity(conn: &Connection) -> rusqlite::Result<()> {
+        let identified: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('file_hashes') WHERE name = 'file'",
+            [],
+            |row| row.get(0),
+        )?;
+        if identified == 0 {
+            conn.execute_batch(
+                "ALTER TABLE file_hashes ADD COLUMN volume INTEGER;
+                ALTER TABLE file_hashes ADD COLUMN file INTEGER;",
+            )?;
+        }
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS file_hashes_identity ON file_hashes (volume, file);",
+        )
+    }
+
+    /**
+     * An index written before the paths were kept as bytes keyed them by
+     * their lossy text, where names which aren't valid UTF-8 could share a
+     * key. The text of the others is the same bytes, the lossy ones are
+     * dropped and hashed again.
+     */
+    fn key_by_bytes(conn: &Connection) -> rusqlite::Result<()> {
+        let text: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('file_hashes') WHERE name = 'path' AND type = 'TEXT'",
+            [],
+            |row| row.get(0),
+        )?;
+        if text == 0 {
+            return Ok(());
+        }
+
+        debug!("key the stored hashes by the bytes of their path");
+        conn.execute_batch(
+            "BEGIN;
+            DROP INDEX IF EXISTS file_hashes_identity;
+            ALTER TABLE file_hashes RENAME TO file_hashes_text;
+            CREATE TABLE file_hashes (
+                path BLOB NOT NULL,
+                algorithm TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                modified INTEGER NOT NULL,
+                hash TEXT NOT NULL,
+                volume INTEGER,
+                file INTEGER,
+                PRIMARY KEY (path, algorithm)
+            );
+            INSERT INTO file_hashes (path, algorithm, size, modified, hash, volume, file)
+                SELECT CAST(path AS BLOB), algorithm, size, modified, hash, volume, file
+                FROM file_hashes_text WHERE instr(path, char(65533)) = 0;
+            DROP TABLE file_hashes_text;
+            CREATE INDEX file_hashes_identity ON file_hashes (volume, file);
+            COMMIT;",
+        )
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        *self
+            .algorithm
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /**
+     * the algorithm of `hash` from now on, the recorded hashes of the others are kept
+     */
+    pub fn set_algorithm(&self, algorithm: HashAlgorithm) {
+        *self
+            .algorithm
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = algorithm;
+    }
+
+    /**
+     * the mounted volumes, which decide whether a large file may be memory mapped
+     */
+    pub fn set_volumes(&self, volumes: Vec<Volumn>) {
+        *self.volumes.lock().unwrap_or_else(PoisonError::into_inner) = volumes;
+    }
+
+    fn lookup(
+        &self,
+        path: &[u8],
+        algorithm: HashAlgorithm,
+        size: u64,
+        modified: u64,
+    ) -> Option<String> {
+        let conn = self.conn.lock().ok()?;
+        conn.query_row(
+            "SELECT hash FROM file_hashes
+                WHERE path = ?1 AND algorithm = ?2 AND size = ?3 AND modified = ?4",
+            params![path, algorithm.name(), size as i64, modified as i64],
+            |row| row.get(0),
+        )
+        .optional()
+        .ok()
+        .flatten()
+    }
+
+    /**
+     * the hash recorded for the file `id` under another path, the same size
+     * and mtime tell its number wasn't handed to a new file since
+     */
+    fn lookup_moved(
+        &self,
+        id: FileId,
+        algorithm: HashAlgorithm,
+        size: u64,
+        modified: u64,
+    ) -> Option<String> {
+        let conn = self.conn.lock().ok()?;
+        conn.query_row(
+            "SELECT hash FROM file_hashes
+                WHERE volume = ?1 AND file = ?2 AND algorithm = ?3 AND size = ?4 AND modified = ?5",
+            params![
+                id.volume as i64,
+                id.file as i64,
+                algorithm.name(),
+                size as i64,
+                modified as i64
+            ],
+            |row| row.get(0),
+        )
+        .optional()
+        .ok()
+        .flatten()
+    }
+
+    fn record(
+        &self,
+        path: &[u8],
+        id: Option<FileId>,
+        algorithm: HashAlgorithm,
+        size: u64,
+        modified: u64,
+        hash: &str,
+    ) -> io::Result<()> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|_| io::Error::other("hash index poisoned"))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO file_hashes (path, algorithm, size, modified, hash, volume, file)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                path,
+                algorithm.name(),
+                size as i64,
+                modified as i64,
+                hash,
+                id.map(|id| id.volume as i64),
+                id.map(|id| id.file as i64)
+            ],
+        )
+        .map_err(to_io)?;
+        Ok(())
+    }
+
+    /**
+     * hash a file with the chosen algorithm, reusing the recorded hash while the file is unchanged
+     */
+    pub fn hash(&self, path: &Path) -> io::Result<String> {
+        self.hash_with(path, self.algorithm())
+    }
+
+    /**
+     * hash a file with `algorithm`, reusing the recorded hash while the file is unchanged
+     */
+    pub fn hash_with(&self, path: &Path, algorithm: HashAlgorithm) -> io::Result<String> {
+        self.hash_observed(path, algorithm, &mut |_| {})
+    }
+
+    /**
+     * `hash_with`, `observe` sees the progress of a file which is actually read
+     */
+    pub fn hash_observed(
+        &self,
+        path: &Path,
+        algorithm: HashAlgorithm,
+        observe: &mut dyn FnMut(&HashProgress),
+    ) -> io::Result<String> {
+        let metadata = std::fs::metadata(long_path::extended(path))?;
+        let size = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        let key = lossless::bytes(path);
+
+        if let Some(hash) = self.lookup(key, algorithm, size, modified) {
+            debug!(
+                "reuse recorded {} hash for {}",
+                algorithm.name(),
+                path.display()
+            );
+            return Ok(hash);
+        }
+        let id = FileId::of(path, &metadata);
+        if let Some(hash) = id.and_then(|id| self.lookup_moved(id, algorithm, size, modified)) {
+            debug!(
+                "reuse {} hash recorded before {} moved",
+                algorithm.name(),
+                path.display()
+            );
+            self.record(key, id, algorithm, size, modified, &hash)?;
+            return Ok(hash);
+        }
+
+        let map = may_map(
+            path,
+            &self.volumes.lock().unwrap_or_else(PoisonError::into_inner),
+        );
+        let hash = hash_file_observed(path, algorithm, map, observe)?;
+        self.record(key, id, algorithm, size, modified, &hash)?;
+        Ok(hash)
+    }
+
+    /**
+     * forget the hashes of files which no longer exist, returns how many were dropped
+     */
+    pub fn prune_missing(&self) -> io::Result<usize> {
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|_| io::Error::other("hash index poisoned"))?;
+
+        let keys: Vec<Vec<u8>> = conn
+            .prepare("SELECT DISTINCT path FROM file_hashes")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<Vec<u8>>>>()
+            })
+            .map_err(to_io)?;
+
+        let tx = conn.transaction().map_err(to_io)?;
+        let mut pruned = 0;
+        let missing = |key: &&Vec<u8>| lossless::from_bytes(key).is_some_and(|path| !path.exists());
+        for path in keys.iter().filter(missing) {
+            pruned += tx
+                .execute("DELETE FROM file_hashes WHERE path = ?1", params![path])
+                .map_err(to_io)?;
+        }
+        tx.commit().map_err(to_io)?;
+        Ok(pruned)
+    }
+
+    /**
+     * rebuild the database without its free pages, returns the size in bytes before and after
+     */
+    pub fn vacuum(&self) -> io::Result<(u64, u64)> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|_| io::Error::other("hash index poisoned"))?;
+        let size = |conn: &Connection| -> rusqlite::Result<u64> {
+            let pages: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+            let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+            Ok((pages * page_size) as u64)
+        };
+
+        let before = size(&conn).map_err(to_io)?;
+        conn.execute_batch("VACUUM;").map_err(to_io)?;
+        let after = size(&conn).map_err(to_io)?;
+        Ok((before, after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[test]
+    fn test_resume_from_recorded_hash() {
+        let path = temp_path("hash-index.bin");
+        std::fs::write(&path, b"cleaner").unwrap();
+
+        let index = HashIndex::in_memory().unwrap();
+        let hash = index.hash(&path).unwrap();
+        assert_eq!(hash, hash_file(&path).unwrap());
+
+        // a recorded entry is trusted while size and mtime are unchanged
+        let metadata = std::fs::metadata(&path).unwrap();
+        let modified = metadata
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        index
+            .record(
+                lossless::bytes(&path),
+                None,
+                HashAlgorithm::Blake3,
+                metadata.len(),
+                modified,
+                "checkpoint",
+            )
+            .unwrap();
+        assert_eq!(index.hash(&path).unwrap(), "checkpoint");
+    }
+
+    #[test]
+    fn test_algorithms_recorded_apart() {
+        let path = temp_path("hash-algorithms.bin");
+        std::fs::write(&path, b"cleaner").unwrap();
+
+        let index = HashIndex::in_memory().unwrap();
+        let blake3 = index.hash(&path).unwrap();
+        index.set_algorithm(HashAlgorithm::Sha256);
+        let sha256 = index.hash(&path).unwrap();
+        let xxh3 = index.hash_with(&path, HashAlgorithm::Xxh3).unwrap();
+
+        assert_eq!(blake3, hash_file(&path).unwrap());
+        assert_eq!(
+            sha256,
+            "11336bc0d91358ca2d117e81fc24e61304d90b7e86139e0fb2fb7ced2ff9535b"
+        );
+        assert_eq!(xxh3.len(), 16);
+
+        // switching back finds the blake3 hash still recorded
+        let key = lossless::bytes(&path);
+        let metadata = std::fs::metadata(&path).unwrap();
+        let modified = metadata
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert_eq!(
+            index.lookup(key, HashAlgorithm::Blake3, metadata.len(), modified),
+            Some(blake3)
+        );
+    }
+
+    #[test]
+    fn test_mapped_read_matches_buffered() {
+        let path = temp_path("hash-mapped.bin");
+        let contents: Vec<u8> = (0..MMAP_CHUNK * 2 + 17).map(|byte| byte as u8).collect();
+        std::fs::write(&path, &contents).unwrap();
+
+        let update = |algorithm| {
+            let mut mapped = Hasher::new(algorithm);
+            read_mapped(&File::open(&path).unwrap(), &mut |chunk| {
+                mapped.update(chunk)
+            })
+            .unwrap();
+            mapped.finalize()
+        };
+        for algorithm in [
+            HashAlgorithm::Blake3,
+            HashAlgorithm::Xxh3,
+            HashAlgorithm::Sha256,
+        ] {
+            assert_eq!(update(algorithm), hash_file_with(&path, algorithm).unwrap());
+        }
+
+        let mut seen = Vec::new();
+        hash_file_observed(&path, HashAlgorithm::Xxh3, true, &mut |progress| {
+            seen.push((progress.hashed, progress.mapped))
+        })
+        .unwrap();
+        // below the threshold the map is not worth it
+        assert!(seen.iter().all(|(_, mapped)| !mapped));
+        assert_eq!(seen.last().unwrap().0, contents.len() as u64);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_network_volumes_never_mapped() {
+        let volume = |path: &str, file_system: &str| Volumn {
+            name: path.to_string(),
+            display_name: path.to_string(),
+            path: PathBuf::from(path),
+            total_size: 0,
+            available_size: 0,
+            file_system: file_system.to_string(),
+            capabilities: None,
+            shadow_storage: None,
+            inodes: None,
+        };
+        let volumes = [volume("/", "apfs"), volume("/Volumes/share", "smbfs")];
+
+        assert!(may_map(Path::new("/Users/me/disk.img"), &volumes));
+        assert!(!may_map(Path::new("/Volumes/share/disk.img"), &volumes));
+        assert!(!may_map(Path::new("/Users/me/disk.img"), &[]));
+        assert!(is_network_file_system("NFS"));
+    }
+
+    #[test]
+    fn test_unversioned_index_migrated() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE file_hashes (
+                path TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                modified INTEGER NOT NULL,
+                hash TEXT NOT NULL
+            );
+            INSERT INTO file_hashes VALUES ('/cleaner/old.bin', 1, 1, 'old');",
+        )
+        .unwrap();
+
+        let index = HashIndex::new(&Database::with_connection(conn)).unwrap();
+        assert_eq!(
+            index.lookup(b"/cleaner/old.bin", HashAlgorithm::Blake3, 1, 1),
+            Some("old".to_string())
+        );
+        assert!(
+            index
+                .lookup(b"/cleaner/old.bin", HashAlgorithm::Sha256, 1, 1)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_moved_file_keeps_its_hash() {
+        let dir = temp_path("hash-moved");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let (before, after) = (dir.join("before.bin"), dir.join("after.bin"));
+        std::fs::write(&before, b"cleaner").unwrap();
+
+        let index = HashIndex::in_memory().unwrap();
+        let metadata = std::fs::metadata(&before).unwrap();
+        let modified = metadata
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        index
+            .record(
+                lossless::bytes(&before),
+                FileId::of(&before, &metadata),
+                HashAlgorithm::Blake3,
+                metadata.len(),
+                modified,
+                "checkpoint",
+            )
+            .unwrap();
+
+        std::fs::rename(&before, &after).unwrap();
+        assert_eq!(index.hash(&after).unwrap(), "checkpoint");
+        assert_eq!(
+            index.lookup(
+                lossless::bytes(&after),
+                HashAlgorithm::Blake3,
+                metadata.len(),
+                modified
+            ),
+            Some("checkpoint".to_string())
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_prune_missing_hashes() {
+        let index = HashIndex::in_memory().unwrap();
+        index
+            .record(
+                b"/cleaner/missing.bin",
+                None,
+                HashAlgorithm::Blake3,
+                1,
+                1,
+                "gone",
+            )
+            .unwrap();
+        index
+            .record(
+                b"/cleaner/missing.bin",
+                None,
+                HashAlgorithm::Xxh3,
+                1,
+                1,
+                "gone",
+            )
+            .unwrap();
+        assert_eq!(index.prune_missing().unwrap(), 2);
+        assert!(
+            index
+                .lookup(b"/cleaner/missing.bin", HashAlgorithm::Blake3, 1, 1)
+                .is_none()
+        );
+        assert!(index.vacuum().is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lossy_names_keyed_apart() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let index = HashIndex::in_memory().unwrap();
+        let first = Path::new(std::ffi::OsStr::from_bytes(b"/cleaner/a\xff.bin"));
+        let second = Path::new(std::ffi::OsStr::from_bytes(b"/cleaner/a\xfe.bin"));
+        assert_eq!(first.to_string_lossy(), second.to_string_lossy());
+
+        index
+            .record(
+                lossless::bytes(first),
+                None,
+                HashAlgorithm::Blake3,
+                1,
+                1,
+                "first",
+            )
+            .unwrap();
+        assert!(
+            index
+                .lookup(lossless::bytes(second), HashAlgorithm::Blake3, 1, 1)
+                .is_none()
+        );
+        assert_eq!(lossless::from_bytes(lossless::bytes(first)).unwrap(), first);
+    }
+
+    #[test]
+    fn test_text_keys_migrated_to_bytes() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE file_hashes (
+                path TEXT NOT NULL,
+                algorithm TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                modified INTEGER NOT NULL,
+                hash TEXT NOT NULL,
+                PRIMARY KEY (path, algorithm)
+            );
+            INSERT INTO file_hashes VALUES ('/cleaner/kept.bin', 'xxh3', 1, 1, 'kept');
+            INSERT INTO file_hashes VALUES ('/cleaner/a\u{FFFD}.bin', 'xxh3', 1, 1, 'lossy');",
+        )
+        .unwrap();
+
+        let index = HashIndex::new(&Database::with_connection(conn)).unwrap();
+        assert_eq!(
+            index.lookup(b"/cleaner/kept.bin", HashAlgorithm::Xxh3, 1, 1),
+            Some("kept".to_string())
+        );
+        assert!(
+            index
+                .lookup(
+                    "/cleaner/a\u{FFFD}.bin".as_bytes(),
+                    HashAlgorithm::Xxh3,
+                    1,
+                    1
+                )
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{audit::AuditLog, transaction::TransactionState};
+
+/**
+ * seconds a hook may run when its settings don't tell
+ */
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/**
+ * bytes kept of the output and of the errors of a hook, the rest is read and dropped
+ */
+pub const OUTPUT_LIMIT: usize = 64 * 1024;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/**
+ * how long the output is still read after a hook exited, a child it left
+ * running may hold its pipes open
+ */
+const DRAIN_GRACE: Duration = Duration::from_secs(1);
+
+/**
+ * when a hook runs
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum HookEvent {
+    /// before a scan starts, the scan waits for it
+    PreScan,
+    /// after files were removed, trashed or deleted
+    PostCleanup,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::PreScan => "preScan",
+            HookEvent::PostCleanup => "postCleanup",
+        }
+    }
+}
+
+fn default_timeout() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+/**
+ * A command the user wants run on `event`. The program and its arguments
+ * are given apart and no shell is involved, `["sh", "-c", "..."]` gets one.
+ * The summary of the event is written to its stdin as JSON.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Hook {
+    pub event: HookEvent,
+    /// the program first, then its arguments
+    pub command: Vec<String>,
+    /// the hook is killed once it runs longer
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u64,
+}
+
+/**
+ * what a hook is told about the event it runs on
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum HookSummary {
+    #[serde(rename_all = "camelCase")]
+    PreScan { path: PathBuf },
+    #[serde(rename_all = "camelCase")]
+    PostCleanup {
+        /// the command which removed the files, e.g. `run_cleanup`
+        operation: String,
+        removed: usize,
+        failed: usize,
+        freed_bytes: u64,
+        cancelled: bool,
+    },
+}
+
+impl HookSummary {
+    pub fn event(&self) -> HookEvent {
+        match self {
+            HookSummary::PreScan { .. } => HookEvent::PreScan,
+            HookSummary::PostCleanup { .. } => HookEvent::PostCleanup,
+        }
+    }
+}
+
+/**
+ * how a hook ended
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookOutcome {
+    /// none when it was killed, by the timeout or by a signal
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl HookOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /**
+     * the error of a hook which did not succeed, for the audit log
+     */
+    pub fn error(&self, timeout: Duration) -> Option<String> {
+        if self.timed_out {
+            Some(format!("killed after {} seconds", timeout.as_secs()))
+        } else {
+            match self.exit_code {
+                Some(0) => None,
+                Some(code) => Some(format!("exited with {}", code)),
+                None => Some("killed by a signal".to_string()),
+            }
+        }
+    }
+
+    /**
+     * stdout then stderr, each left out when empty
+     */
+    pub fn output(&self) -> String {
+        match (self.stdout.is_empty(), self.stderr.is_empty()) {
+            (_, true) => self.stdout.clone(),
+            (true, false) => format!("stderr:\n{}", self.stderr),
+            (false, false) => format!("{}\nstderr:\n{}", self.stdout, self.stderr),
+        }
+    }
+}
+
+/**
+ * read `pipe` to its end on a thread, keeping the first `OUTPUT_LIMIT` bytes
+ */
+fn capture(
+    pipe: Option<impl Read + Send + 'static>,
+) -> (Arc<Mutex<Vec<u8>>>, thread::JoinHandle<()>) {
+    let kept = Arc::new(Mutex::new(Vec::new()));
+    let buffer = kept.clone();
+    let reader = thread::spawn(move || {
+        let Some(mut pipe) = pipe else {
+            return;
+        };
+        let mut chunk = [0u8; 8192];
+        // never stop reading, a hook blocks once its pipe is full
+        while let Ok(read) = pipe.read(&mut chunk) {
+            if read == 0 {
+                break;
+            }
+            let mut buffer = buffer.lock().unwrap_or_else(PoisonError::into_inner);
+            let room = OUTPUT_LIMIT.saturating_sub(buffer.len());
+            buffer.extend_from_slice(&chunk[..read.min(room)]);
+        }
+    });
+    (kept, reader)
+}
+
+fn wait(child: &mut Child, timeout: Duration) -> io::Result<(Option<i32>, bool)> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status.code(), false));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok((None, true));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/**
+ * Run `hook` with `summary` on its stdin until it exits or its timeout
+ * passes. An error means it could not be started. Blocking.
+ */
+pub fn run(hook: &Hook, summary: &HookSummary) -> io::Result<HookOutcome> {
+    let Some((program, args)) = hook.command.split_first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the hook has no command",
+        ));
+    };
+    let input = serde_json::to_vec(summary)?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdin = child.stdin.take();
+    // a hook which ignores its stdin may exit before reading it, that is fine
+    thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(&input);
+        }
+    });
+    let (stdout, stdout_reader) = capture(child.stdout.take());
+    let (stderr, stderr_reader) = capture(child.stderr.take());
+
+    let (exit_code, timed_out) = wait(&mut child, Duration::from_secs(hook.timeout_secs))?;
+    let drained = Instant::now() + DRAIN_GRACE;
+    while !(stdout_reader.is_finished() && stderr_reader.is_finished()) && Instant::now() < drained
+    {
+        thread::sleep(POLL_INTERVAL);
+    }
+    let text = |buffer: &Mutex<Vec<u8>>| {
+        String::from_utf8_lossy(&buffer.lock().unwrap_or_else(PoisonError::into_inner)).into_owned()
+    };
+    Ok(HookOutcome {
+        exit_code,
+        timed_out,
+        stdout: text(&stdout),
+        stderr: text(&stderr),
+    })
+}
+
+/**
+ * Run every hook of `hooks` set for the event of `summary`, one after the
+ * other, each kept in `audit` with its output. A failing hook does not stop
+ * the next one. Blocking.
+ */
+pub fn run_all(hooks: &[Hook], summary: &HookSummary, audit: &AuditLog) {
+    let event = summary.event();
+    for hook in hooks.iter().filter(|hook| hook.event == event) {
+        let operation = format!("{} hook: {}", event.name(), hook.command.join(" "));
+        let timeout = Duration::from_secs(hook.timeout_secs);
+        let recorded = match run(hook, summary) {
+            Ok(outcome) => {
+                let error = outcome.error(timeout);
+                if let Some(error) = error.as_ref() {
+                    tracing::warn!("{} {}", operation, error);
+                }
+                let state = outcome.succeeded().then_some(TransactionState::Committed);
+                audit.record(&operation, state, Some(&outcome.output()), error.as_deref())
+            }
+            Err(err) => {
+                tracing::warn!("{} could not be started, {}", operation, err);
+                audit.record(&operation, None, None, Some(&err.to_string()))
+            }
+        };
+        if let Err(err) = recorded {
+            tracing::warn!("record {} failed, {}", operation, err);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn hook(script: &str, timeout_secs: u64) -> Hook {
+        Hook {
+            event: HookEvent::PostCleanup,
+            command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            timeout_secs,
+        }
+    }
+
+    fn freed() -> HookSummary {
+        HookSummary::PostCleanup {
+            operation: "run_cleanup".to_string(),
+            removed: 3,
+            failed: 0,
+            freed_bytes: 60_000_000_000,
+            cancelled: false,
+        }
+    }
+
+    #[test]
+    fn test_hook_reads_summary_and_is_audited() {
+        let audit = AuditLog::in_memory().unwrap();
+        let hooks = vec![
+            hook("cat; echo oops >&2", 5),
+            hook("exit 3", 5),
+            // another event, not run
+            Hook {
+                event: HookEvent::PreScan,
+                ..hook("true", 5)
+            },
+        ];
+        run_all(&hooks, &freed(), &audit);
+
+        let entries = audit.recent(10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, "postCleanup hook: sh -c exit 3");
+        assert_eq!(entries[0].state, None);
+        assert_eq!(entries[0].error.as_deref(), Some("exited with 3"));
+        assert_eq!(entries[1].state, Some(TransactionState::Committed));
+        let output = entries[1].output.as_deref().unwrap();
+        assert!(output.starts_with(r#"{"event":"postCleanup","operation":"run_cleanup""#));
+        assert!(output.contains(r#""freedBytes":60000000000"#));
+        assert!(output.ends_with("stderr:\noops\n"));
+    }
+
+    #[test]
+    fn test_hook_killed_after_timeout() {
+        let started = Instant::now();
+        let outcome = run(&hook("sleep 10", 1), &freed()).unwrap();
+        assert!(outcome.timed_out);
+        assert!(!outcome.succeeded());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
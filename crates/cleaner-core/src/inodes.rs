@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use crate::models::InodeUsage;
+
+/**
+ * the inodes of the volume mounted at `mount_point` by statvfs, none when it fails
+ */
+#[cfg(unix)]
+pub fn usage(mount_point: &Path) -> Option<InodeUsage> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(mount_point.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // 32 bit counts on macOS
+    #[allow(clippy::unnecessary_cast)]
+    let (total, free, available) = (
+        stat.f_files as u64,
+        stat.f_ffree as u64,
+        stat.f_favail as u64,
+    );
+    of_counts(total, free, available)
+}
+
+/**
+ * NTFS keeps its file records in a table which grows as needed
+ */
+#[cfg(not(unix))]
+pub fn usage(_mount_point: &Path) -> Option<InodeUsage> {
+    None
+}
+
+/**
+ * btrfs and most network filesystems report no inodes at all, they allocate them on demand
+ */
+#[cfg_attr(not(unix), allow(dead_code))]
+fn of_counts(total: u64, free: u64, available: u64) -> Option<InodeUsage> {
+    (total > 0).then(|| InodeUsage {
+        total,
+        used: total.saturating_sub(free),
+        available: available.min(free),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inodes_counted() {
+        assert_eq!(
+            of_counts(1000, 200, 150),
+            Some(InodeUsage {
+                total: 1000,
+                used: 800,
+                available: 150,
+            })
+        );
+        assert_eq!(of_counts(0, 0, 0), None);
+    }
+}
@@ -0,0 +1,264 @@
+use std::{ffi::OsStr, time::Duration};
+
+use serde::Serialize;
+use specta::Type;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+/**
+ * samples needed before a drop is judged against the peak
+ */
+const WARMUP_SAMPLES: usize = 3;
+/**
+ * a rate below this share of the peak counts as a sharp drop
+ */
+const DROP_RATIO: f64 = 0.3;
+/**
+ * bytes read or written since the previous sample to count as heavy I/O
+ */
+const HEAVY_IO_BYTES: u64 = 8 * 1024 * 1024;
+
+/**
+ * well known background scanners and indexers, matched by process name
+ */
+const KNOWN_PROCESSES: &[(&str, &str)] = &[
+    ("MsMpEng.exe", "Windows Defender"),
+    ("SearchIndexer.exe", "Windows Search"),
+    ("mds", "Spotlight"),
+    ("mds_stores", "Spotlight"),
+    ("mdworker", "Spotlight"),
+    ("mdworker_shared", "Spotlight"),
+    ("XProtect", "XProtect"),
+    ("tracker-miner-fs", "GNOME Tracker"),
+    ("tracker-miner-fs-3", "GNOME Tracker"),
+    ("baloo_file", "KDE Baloo"),
+    ("clamd", "ClamAV"),
+];
+
+/**
+ * Tracks the scan rate (directories per second) and reports when it falls
+ * sharply below the best rate seen so far.
+ */
+#[derive(Debug, Default)]
+pub struct ThroughputMonitor {
+    peak: f64,
+    samples: usize,
+}
+
+impl ThroughputMonitor {
+    pub fn observe(&mut self, rate: f64) -> bool {
+        self.samples += 1;
+        if rate > self.peak {
+            self.peak = rate;
+        }
+        self.samples > WARMUP_SAMPLES && self.peak > 0.0 && rate < self.peak * DROP_RATIO
+    }
+}
+
+pub fn friendly_name(process: &OsStr) -> Option<&'static str> {
+    KNOWN_PROCESSES
+        .iter()
+        .find(|(name, _)| OsStr::new(name) == process)
+        .map(|(_, friendly)| *friendly)
+}
+
+/**
+ * Name the processes doing heavy disk I/O since the last sample, known
+ * indexers and antivirus first.
+ */
+pub fn sample_disk_heavy_processes(system: &mut System) -> Vec<String> {
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_disk_usage(),
+    );
+
+    let mut heavy: Vec<(bool, u64, String)> = system
+        .processes()
+        .values()
+        .filter_map(|process| {
+            let usage = process.disk_usage();
+            let bytes = usage.read_bytes + usage.written_bytes;
+            if bytes < HEAVY_IO_BYTES || process.pid() == sysinfo::Pid::from_u32(std::process::id())
+            {
+                return None;
+            }
+            let known = friendly_name(process.name());
+            let name = known.map_or_else(
+                || process.name().to_string_lossy().into_owned(),
+                |name| name.to_string(),
+            );
+            Some((known.is_some(), bytes, name))
+        })
+        .collect();
+
+    heavy.sort_by(|a, b| b.cmp(a));
+    let mut names: Vec<String> = heavy.into_iter().map(|(_, _, name)| name).collect();
+    names.dedup();
+    names
+}
+
+/**
+ * the shortest and the longest window the disk writers are sampled over
+ */
+pub const WRITERS_WINDOW: (Duration, Duration) =
+    (Duration::from_millis(250), Duration::from_secs(10));
+
+/**
+ * a process and what it did to the disks during the sampled window
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskWriter {
+    pub pid: u32,
+    pub name: String,
+    /// the indexer or antivirus it is, when it is a known one
+    pub known_as: Option<String>,
+    pub written_bytes: u64,
+    pub read_bytes: u64,
+    /// bytes written per second over the window
+    pub write_rate: u64,
+}
+
+/**
+ * the processes writing the most during a window, the heaviest first
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskWriters {
+    pub window_millis: u64,
+    pub writers: Vec<DiskWriter>,
+    /// bytes written by every process, including the ones left out
+    pub total_written: u64,
+}
+
+/**
+ * Sample the I/O counters of every process twice, `window` apart, and keep
+ * the `limit` processes which wrote the most in between. The cleaner itself
+ * is left out. Blocking for the whole window.
+ */
+pub fn top_disk_writers(window: Duration, limit: usize) -> DiskWriters {
+    let window = window.clamp(WRITERS_WINDOW.0, WRITERS_WINDOW.1);
+    let refresh = |system: &mut System| {
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().with_disk_usage(),
+        );
+    };
+    let mut system = System::new();
+    // the counters are deltas since the previous refresh
+    refresh(&mut system);
+    std::thread::sleep(window);
+    refresh(&mut system);
+
+    let own = sysinfo::Pid::from_u32(std::process::id());
+    let writers = system
+        .processes()
+        .values()
+        .filter(|process| process.pid() != own)
+        .map(|process| {
+            let usage = process.disk_usage();
+            DiskWriter {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string_lossy().into_owned(),
+                known_as: friendly_name(process.name()).map(str::to_string),
+                written_bytes: usage.written_bytes,
+                read_bytes: usage.read_bytes,
+                write_rate: 0,
+            }
+        })
+        .collect();
+    rank_writers(writers, window, limit)
+}
+
+fn rank_writers(mut writers: Vec<DiskWriter>, window: Duration, limit: usize) -> DiskWriters {
+    let total_written = writers.iter().map(|writer| writer.written_bytes).sum();
+    writers.retain(|writer| writer.written_bytes > 0);
+    writers.sort_by(|a, b| {
+        b.written_bytes
+            .cmp(&a.written_bytes)
+            .then_with(|| b.read_bytes.cmp(&a.read_bytes))
+            .then_with(|| a.pid.cmp(&b.pid))
+    });
+    writers.truncate(limit);
+    let millis = window.as_millis().max(1) as u64;
+    for writer in &mut writers {
+        writer.write_rate = writer.written_bytes * 1000 / millis;
+    }
+    DiskWriters {
+        window_millis: millis,
+        writers,
+        total_written,
+    }
+}
+
+/**
+ * user facing explanation for a slowdown
+ */
+pub fn hint(processes: &[String]) -> Option<String> {
+    match processes {
+        [] => None,
+        [name] => Some(format!("{} is scanning concurrently", name)),
+        [name, rest @ ..] => Some(format!(
+            "{} and {} other process(es) are using the disk heavily",
+            name,
+            rest.len()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_sharp_drop() {
+        let mut monitor = ThroughputMonitor::default();
+        assert!(!monitor.observe(100.0));
+        assert!(!monitor.observe(120.0));
+        // still warming up
+        assert!(!monitor.observe(10.0));
+        assert!(!monitor.observe(90.0));
+        assert!(monitor.observe(20.0));
+    }
+
+    #[test]
+    fn test_hint_names_known_processes() {
+        assert_eq!(
+            friendly_name(OsStr::new("MsMpEng.exe")),
+            Some("Windows Defender")
+        );
+        assert_eq!(
+            hint(&["Windows Defender".to_string()]),
+            Some("Windows Defender is scanning concurrently".to_string())
+        );
+        assert_eq!(hint(&[]), None);
+    }
+
+    #[test]
+    fn test_writers_ranked_by_written_bytes() {
+        let writer = |pid, written_bytes| DiskWriter {
+            pid,
+            name: format!("process-{}", pid),
+            known_as: None,
+            written_bytes,
+            read_bytes: 0,
+            write_rate: 0,
+        };
+        let ranked = rank_writers(
+            vec![
+                writer(1, 0),
+                writer(2, 4096),
+                writer(3, 1 << 20),
+                writer(4, 512),
+            ],
+            Duration::from_secs(2),
+            2,
+        );
+        let pids: Vec<u32> = ranked.writers.iter().map(|writer| writer.pid).collect();
+        assert_eq!(pids, vec![3, 2]);
+        assert_eq!(ranked.writers[0].write_rate, 1 << 19);
+        assert_eq!(ranked.total_written, (1 << 20) + 4096 + 512);
+        assert_eq!(ranked.window_millis, 2000);
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    database::{Database, to_io},
+    models::{FileDetails, now_secs},
+    path_key::{self, PathKey},
+};
+
+/**
+ * longest emoji accepted, enough for a flag or a skin toned family sequence
+ */
+pub const MAX_EMOJI_CHARS: usize = 8;
+
+/**
+ * A color and an emoji the user pinned on a folder, e.g. red for "do not
+ * touch" and green for "cleaned", so a later review finds its anchors again.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderLabel {
+    /// `#rgb` or `#rrggbb`
+    pub color: Option<String>,
+    pub emoji: Option<String>,
+}
+
+impl FolderLabel {
+    pub fn is_empty(&self) -> bool {
+        self.color.is_none() && self.emoji.is_none()
+    }
+
+    /**
+     * reject what the UI could not render, a bad value would stick to the folder for good
+     */
+    pub fn validate(&self) -> io::Result<()> {
+        if let Some(color) = self.color.as_deref() {
+            let digits = color.strip_prefix('#').unwrap_or_default();
+            if !matches!(digits.len(), 3 | 6) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid(format!(
+                    "{:?} is not a #rgb or #rrggbb color",
+                    color
+                )));
+            }
+        }
+        if let Some(emoji) = self.emoji.as_deref() {
+            let chars = emoji.chars().count();
+            if chars == 0
+                || chars > MAX_EMOJI_CHARS
+                || emoji.chars().any(|c| c.is_whitespace() || c.is_control())
+            {
+                return Err(invalid(format!("{:?} is not a single emoji", emoji)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct LabeledFolder {
+    pub path: PathBuf,
+    pub label: FolderLabel,
+    /// unix seconds of the last change
+    pub updated: u64,
+}
+
+/**
+ * Folder labels persisted in SQLite, next to the hash index in the app
+ * database. They are keyed by the path with its parents resolved, so a
+ * folder reached through a symlink, or spelled in another case on macOS and
+ * Windows, keeps its label.
+ */
+#[derive(Clone)]
+pub struct LabelStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl LabelStore {
+    pub fn new(database: &Database) -> io::Result<Self> {
+        let shared = database.connection();
+        let conn = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS folder_labels (
+                key TEXT PRIMARY KEY,
+                path TEXT NOT NULL,
+                color TEXT,
+                emoji TEXT,
+                updated INTEGER NOT NULL
+            );",
+        )
+        .map_err(to_io)?;
+
+        Ok(Self {
+            conn: Arc::clone(&shared),
+        })
+    }
+
+    pub fn in_memory() -> io::Result<Self> {
+        Self::new(&Database::in_memory()?)
+    }
+
+    /**
+     * pin `label` on `path`, an empty label removes it
+     */
+    pub fn set(&self, path: &Path, label: &FolderLabel) -> io::Result<()> {
+        if label.is_empty() {
+            return self.remove(path).map(|_| ());
+        }
+        label.validate()?;
+
+        let path = PathKey::resolve(path).into_path_buf();
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "INSERT OR REPLACE INTO folder_labels (key, path, color, emoji, updated)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                key(&path),
+                path.to_string_lossy(),
+                label.color,
+                label.emoji,
+                now_secs() as i64
+            ],
+        )
+        .map_err(to_io)?;
+        Ok(())
+    }
+
+    /**
+     * drop the label of `path`, true when it had one
+     */
+    pub fn remove(&self, path: &Path) -> io::Result<bool> {
+        let path = PathKey::resolve(path).into_path_buf();
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let removed = conn
+            .execute(
+                "DELETE FROM folder_labels WHERE key = ?1",
+                params![key(&path)],
+            )
+            .map_err(to_io)?;
+        Ok(removed > 0)
+    }
+
+    pub fn get(&self, path: &Path) -> Option<FolderLabel> {
+        let path = PathKey::resolve(path).into_path_buf();
+        self.lookup(&path)
+    }
+
+    /**
+     * every labeled folder, the most recently changed first
+     */
+    pub fn list(&self) -> io::Result<Vec<LabeledFolder>> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut statement = conn
+            .prepare("SELECT path, color, emoji, updated FROM folder_labels ORDER BY updated DESC")
+            .map_err(to_io)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok(LabeledFolder {
+                    path: PathBuf::from(row.get::<_, String>(0)?),
+                    label: FolderLabel {
+                        color: row.get(1)?,
+                        emoji: row.get(2)?,
+                    },
+                    updated: row.get::<_, i64>(3)? as u64,
+                })
+            })
+            .map_err(to_io)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(to_io)
+    }
+
+    /**
+     * fill in the labels of the listed directory and of its children, which
+     * share the parent resolved once
+     */
+    pub fn label_details(&self, details: &mut FileDetails) {
+        let resolved = PathKey::resolve(&details.path).into_path_buf();
+        if details.is_directory {
+            details.label = self.lookup(&resolved);
+        }
+        for child in details.children.iter_mut().flatten() {
+            if !child.is_directory {
+                continue;
+            }
+            child.label = child
+                .path
+                .file_name()
+                .and_then(|name| self.lookup(&resolved.join(name)));
+        }
+    }
+
+    fn lookup(&self, resolved: &Path) -> Option<FolderLabel> {
+        let conn = self.conn.lock().ok()?;
+        conn.query_row(
+            "SELECT color, emoji FROM folder_labels WHERE key = ?1",
+            params![key(resolved)],
+            |row| {
+                Ok(FolderLabel {
+                    color: row.get(0)?,
+                    emoji: row.get(1)?,
+                })
+            },
+        )
+        .optional()
+        .ok()
+        .flatten()
+    }
+}
+
+/**
+ * the resolved path as stored, lowercased where the platform ignores case
+ */
+pub(crate) fn key(resolved: &Path) -> String {
+    let key = resolved.to_string_lossy();
+    if path_key::CASE_SENSITIVE {
+        key.into_owned()
+    } else {
+        key.to_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn red() -> FolderLabel {
+        FolderLabel {
+            color: Some("#e5484d".to_string()),
+            emoji: Some("⛔".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_label_set_and_removed() {
+        let store = LabelStore::in_memory().unwrap();
+        let path = Path::new("/cleaner/labels/keep");
+        store.set(path, &red()).unwrap();
+        assert_eq!(store.get(Path::new("/cleaner/labels/./keep/")), Some(red()));
+
+        let listed = store.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].path, PathBuf::from("/cleaner/labels/keep"));
+
+        store.set(path, &FolderLabel::default()).unwrap();
+        assert_eq!(store.get(path), None);
+        assert!(!store.remove(path).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_label_rejected() {
+        let store = LabelStore::in_memory().unwrap();
+        let path = Path::new("/cleaner/labels/bad");
+        let color = FolderLabel {
+            color: Some("red".to_string()),
+            ..FolderLabel::default()
+        };
+        let emoji = FolderLabel {
+            emoji: Some("do not touch".to_string()),
+            ..FolderLabel::default()
+        };
+        for label in [color, emoji] {
+            let err = store.set(path, &label).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_children_labeled() {
+        let store = LabelStore::in_memory().unwrap();
+        store.set(Path::new("/cleaner/labels/a"), &red()).unwrap();
+        let child = |name: &str, is_directory| {
+            FileDetails {
+                is_directory,
+                ..FileDetails::default()
+            }
+            .at(PathBuf::from("/cleaner/labels").join(name))
+        };
+        let mut details = FileDetails {
+            is_directory: true,
+            children: Some(vec![
+                child("a", true),
+                child("b", true),
+                child("a.txt", false),
+            ]),
+            ..FileDetails::default()
+        }
+        .at(PathBuf::from("/cleaner/labels"));
+
+        store.label_details(&mut details);
+        let labels: Vec<_> = details
+            .children
+            .unwrap()
+            .into_iter()
+            .map(|child| child.label)
+            .collect();
+        assert_eq!(labels, vec![Some(red()), None, None]);
+        assert_eq!(details.label, None);
+    }
+}
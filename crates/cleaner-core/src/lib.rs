@@ -0,0 +1,73 @@
+/*!
+ * The scanning engine behind Cleaner: the in-memory file [`tree`], the
+ * concurrent [`service::Scanner`] that fills it, and the reports computed
+ * over a scanned tree. Nothing here depends on Tauri, the plugin and any
+ * other frontend drive the engine through this API.
+ *
+ * The crate follows semver. Every `pub` item below is part of the API,
+ * breaking changes bump the minor version while the crate is below 1.0 and
+ * are listed in CHANGELOG.md.
+ */
+
+pub mod activity;
+pub mod allocation;
+pub mod analyzers;
+pub mod audit;
+pub mod backup_target;
+pub mod capabilities;
+pub mod compare;
+pub mod dashboard;
+pub mod database;
+pub mod deletion;
+pub mod delta;
+pub mod device;
+pub mod display_name;
+pub mod dumps;
+pub mod eta;
+pub mod file_id;
+pub mod filters;
+pub mod fingerprint;
+#[cfg(test)]
+mod fixture;
+pub mod hash;
+pub mod hooks;
+pub mod inodes;
+pub mod interference;
+pub mod labels;
+pub mod locks;
+pub mod long_path;
+pub mod lossless;
+pub mod manifest;
+pub mod memory;
+pub mod metrics;
+pub mod models;
+pub mod mover;
+pub mod native_dir;
+pub mod path_key;
+pub mod quarantine;
+pub mod queue;
+pub mod resume;
+pub mod retry;
+pub mod review;
+pub mod scan_diff;
+pub mod scanner_config;
+pub mod selection;
+pub mod service;
+pub mod snapshot;
+pub mod space;
+pub mod store;
+pub mod symlinks;
+pub mod throttle;
+pub mod timeline;
+pub mod timing;
+pub mod transaction;
+pub mod tree;
+pub mod watchdog;
+pub mod weekly;
+
+pub use database::Database;
+pub use models::{AgeBuckets, FileDetails, VolumeCapabilities, Volumn};
+pub use path_key::PathKey;
+pub use service::{ScanProgress, Scanner};
+pub use store::ScanStore;
+pub use tree::{Tree, node::Node};
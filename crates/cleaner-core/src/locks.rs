@@ -0,0 +1,186 @@
+use std::{
+    fmt::{self, Display},
+    fs::{self, Metadata},
+    io,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/**
+ * Flags which make a plain delete fail with a permission error however the
+ * permissions are set. Only the ones the platform has are ever set, the
+ * immutable attribute of Linux needs an ioctl and is not detected.
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LockFlags {
+    /// FILE_ATTRIBUTE_READONLY on windows
+    pub read_only: bool,
+    /// FILE_ATTRIBUTE_SYSTEM on windows
+    pub system: bool,
+    /// `uchg` on macOS, the Locked checkbox in Finder
+    pub user_immutable: bool,
+    /// `schg` on macOS, only cleared by root outside of the secure levels
+    pub system_immutable: bool,
+}
+
+#[cfg(target_os = "macos")]
+const UF_IMMUTABLE: u32 = 0x0000_0002;
+#[cfg(target_os = "macos")]
+const SF_IMMUTABLE: u32 = 0x0002_0000;
+#[cfg(windows)]
+const FILE_ATTRIBUTE_READONLY: u32 = 0x1;
+#[cfg(windows)]
+const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+
+impl LockFlags {
+    #[cfg(target_os = "macos")]
+    pub fn of(metadata: &Metadata) -> Self {
+        use std::os::macos::fs::MetadataExt;
+
+        Self::from_st_flags(metadata.st_flags())
+    }
+
+    /**
+     * the flags in `st_flags`, as a listing which reads them without a stat gets them
+     */
+    #[cfg(target_os = "macos")]
+    pub fn from_st_flags(flags: u32) -> Self {
+        Self {
+            user_immutable: flags & UF_IMMUTABLE != 0,
+            system_immutable: flags & SF_IMMUTABLE != 0,
+            ..Self::default()
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn of(metadata: &Metadata) -> Self {
+        use std::os::windows::fs::MetadataExt;
+
+        let attributes = metadata.file_attributes();
+        Self {
+            read_only: attributes & FILE_ATTRIBUTE_READONLY != 0,
+            system: attributes & FILE_ATTRIBUTE_SYSTEM != 0,
+            ..Self::default()
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub fn of(_metadata: &Metadata) -> Self {
+        Self::default()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.read_only || self.system || self.user_immutable || self.system_immutable
+    }
+}
+
+impl Display for LockFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [
+            (self.read_only, "read-only"),
+            (self.system, "system"),
+            (self.user_immutable, "uchg"),
+            (self.system_immutable, "schg"),
+        ];
+        let set: Vec<&str> = names
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| *name)
+            .collect();
+        write!(f, "{}", set.join(", "))
+    }
+}
+
+/**
+ * the lock flags of `path` itself, a symlink is not followed
+ */
+pub fn of_path(path: &Path) -> LockFlags {
+    fs::symlink_metadata(path).map_or(LockFlags::default(), |metadata| LockFlags::of(&metadata))
+}
+
+/**
+ * Clear the `flags` of `path` so it can be deleted. The platforms only offer
+ * these through their own tools, so `chflags` and `attrib` are run for them.
+ */
+pub fn clear(path: &Path, flags: LockFlags) -> io::Result<()> {
+    if flags.system_immutable {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "the system immutable flag can only be cleared by an administrator",
+        ));
+    }
+
+    if flags.user_immutable {
+        run(
+            "chflags",
+            &["-h".as_ref(), "nouchg".as_ref(), path.as_os_str()],
+        )?;
+    }
+    if flags.system {
+        run("attrib", &["-s".as_ref(), "-r".as_ref(), path.as_os_str()])?;
+    } else if flags.read_only {
+        let mut permissions = fs::symlink_metadata(path)?.permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+fn run(program: &str, args: &[&std::ffi::OsStr]) -> io::Result<()> {
+    let output = std::process::Command::new(program).args(args).output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(io::Error::other(format!(
+        "{} failed, {}",
+        program,
+        String::from_utf8_lossy(&output.stderr).trim()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_named() {
+        let flags = LockFlags {
+            read_only: true,
+            user_immutable: true,
+            ..LockFlags::default()
+        };
+        assert!(flags.is_locked());
+        assert_eq!(flags.to_string(), "read-only, uchg");
+        assert!(!LockFlags::default().is_locked());
+    }
+
+    #[test]
+    fn test_system_immutable_refused() {
+        let flags = LockFlags {
+            system_immutable: true,
+            ..LockFlags::default()
+        };
+        let err = clear(Path::new("/nonexistent"), flags).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_locked_file_cleared() {
+        let file = crate::fixture::temp_path("locks-uchg.txt");
+        let _ = clear(&file, of_path(&file));
+        let _ = fs::remove_file(&file);
+        fs::write(&file, b"locked").unwrap();
+        run("chflags", &["uchg".as_ref(), file.as_os_str()]).unwrap();
+
+        let flags = of_path(&file);
+        assert!(flags.user_immutable);
+        assert!(fs::remove_file(&file).is_err());
+        clear(&file, flags).unwrap();
+        fs::remove_file(&file).unwrap();
+    }
+}
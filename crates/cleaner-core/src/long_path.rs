@@ -0,0 +1,80 @@
+use std::{borrow::Cow, path::Path};
+
+/**
+ * `path` in the extended-length form Windows needs to reach past MAX_PATH,
+ * `C:\dir` becomes `\\?\C:\dir` and `\\server\share` becomes
+ * `\\?\UNC\server\share`. Only for handing to the OS, the tree and every
+ * report keep the plain form. Relative paths pass through untouched.
+ */
+#[cfg(windows)]
+pub fn extended(path: &Path) -> Cow<'_, Path> {
+    use std::{
+        ffi::OsString,
+        path::{Component, PathBuf, Prefix},
+    };
+
+    // the OS takes a verbatim path literally, `.` and `..` have to be gone first
+    let normalized = crate::path_key::PathKey::new(path).into_path_buf();
+    let mut components = normalized.components();
+    let mut extended = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(disk) if normalized.has_root() => {
+                PathBuf::from(format!(r"\\?\{}:\", disk as char))
+            }
+            Prefix::UNC(server, share) => {
+                let mut unc = OsString::from(r"\\?\UNC\");
+                unc.push(server);
+                unc.push(r"\");
+                unc.push(share);
+                PathBuf::from(unc)
+            }
+            _ => return Cow::Borrowed(path),
+        },
+        _ => return Cow::Borrowed(path),
+    };
+
+    for component in components {
+        if let Component::Normal(name) = component {
+            extended.push(name);
+        }
+    }
+    Cow::Owned(extended)
+}
+
+#[cfg(not(windows))]
+pub fn extended(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(windows)]
+    #[test]
+    fn test_extended_prefixes() {
+        assert_eq!(
+            extended(Path::new(r"C:\Users\me\.\cache\..\junk")),
+            Path::new(r"\\?\C:\Users\me\junk")
+        );
+        assert_eq!(
+            extended(Path::new(r"\\server\share\dir")),
+            Path::new(r"\\?\UNC\server\share\dir")
+        );
+        assert_eq!(
+            extended(Path::new(r"\\?\C:\Users")),
+            Path::new(r"\\?\C:\Users")
+        );
+        assert_eq!(
+            extended(Path::new(r"relative\dir")),
+            Path::new(r"relative\dir")
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_extended_is_a_no_op() {
+        let path = Path::new("/Users/me/../junk");
+        assert_eq!(extended(path), path);
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serializer};
+use specta::Type;
+
+/**
+ * A path argument from the webview. Plain strings cover every UTF-8 path,
+ * a name the OS stores as arbitrary bytes comes back as the `raw` form a
+ * `FileDetails` or `FoundFile` carried for it, so it can still be acted on.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Type)]
+#[serde(untagged)]
+pub enum PathArg {
+    Raw { raw: String },
+    Text(PathBuf),
+}
+
+impl PathArg {
+    pub fn into_path_buf(self) -> io::Result<PathBuf> {
+        match self {
+            PathArg::Raw { raw } => decode(&raw),
+            PathArg::Text(path) => Ok(path),
+        }
+    }
+}
+
+impl From<PathBuf> for PathArg {
+    fn from(path: PathBuf) -> Self {
+        PathArg::Text(path)
+    }
+}
+
+pub fn into_paths(args: Vec<PathArg>) -> io::Result<Vec<PathBuf>> {
+    args.into_iter().map(PathArg::into_path_buf).collect()
+}
+
+/**
+ * the exact bytes of `path` as base64, only for paths a string can't carry
+ */
+pub fn raw_if_lossy(path: &Path) -> Option<String> {
+    path.to_str().is_none().then(|| encode(path))
+}
+
+#[cfg(unix)]
+pub fn encode(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    STANDARD.encode(path.as_os_str().as_bytes())
+}
+
+#[cfg(unix)]
+pub fn decode(raw: &str) -> io::Result<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+    let bytes = STANDARD
+        .decode(raw)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    Ok(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+}
+
+/**
+ * utf-16 code units in little endian, unpaired surrogates included
+ */
+#[cfg(windows)]
+pub fn encode(path: &Path) -> String {
+    use std::os::windows::ffi::OsStrExt;
+    let bytes: Vec<u8> = path
+        .as_os_str()
+        .encode_wide()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    STANDARD.encode(bytes)
+}
+
+#[cfg(windows)]
+pub fn decode(raw: &str) -> io::Result<PathBuf> {
+    use std::os::windows::ffi::OsStringExt;
+    let bytes = STANDARD
+        .decode(raw)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    if bytes.len() % 2 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "raw path is not utf-16",
+        ));
+    }
+    let wide: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect();
+    Ok(PathBuf::from(std::ffi::OsString::from_wide(&wide)))
+}
+
+/**
+ * the exact bytes of `path`, as the database stores it
+ */
+pub(crate) fn bytes(path: &Path) -> &[u8] {
+    path.as_os_str().as_encoded_bytes()
+}
+
+/**
+ * the path `bytes` were taken from
+ */
+#[cfg(unix)]
+pub(crate) fn from_bytes(bytes: &[u8]) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(bytes)))
+}
+
+/**
+ * the path `bytes` were taken from, none for a name with an unpaired
+ * surrogate, the only one whose bytes aren't UTF-8
+ */
+#[cfg(not(unix))]
+pub(crate) fn from_bytes(bytes: &[u8]) -> Option<PathBuf> {
+    std::str::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+/**
+ * `serialize_with` for display paths, serde refuses a path which isn't
+ * valid UTF-8 and would fail the whole response over one odd name
+ */
+pub fn serialize_lossy<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&path.to_string_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_and_raw_arguments() {
+        let text: PathArg = serde_json::from_str(r#""/Users/me""#).unwrap();
+        assert_eq!(text.into_path_buf().unwrap(), PathBuf::from("/Users/me"));
+
+        let raw = encode(Path::new("/Users/me"));
+        let arg: PathArg = serde_json::from_str(&format!(r#"{{"raw":"{}"}}"#, raw)).unwrap();
+        assert_eq!(arg.into_path_buf().unwrap(), PathBuf::from("/Users/me"));
+        assert!(raw_if_lossy(Path::new("/Users/me")).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_invalid_utf8_round_trips() {
+        use std::os::unix::ffi::OsStrExt;
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"/tmp/caf\xe9.txt"));
+
+        let raw = raw_if_lossy(path).unwrap();
+        assert_eq!(PathArg::Raw { raw }.into_path_buf().unwrap(), path);
+        assert!(decode("not base64!").is_err());
+    }
+}
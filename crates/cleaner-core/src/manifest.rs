@@ -0,0 +1,180 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use zip::ZipArchive;
+
+use crate::{hash, long_path};
+
+/**
+ * what one entry of a manifest held when it was recorded
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Recorded {
+    Directory,
+    /// its length and blake3 hash
+    File {
+        size: u64,
+        hash: String,
+    },
+    /// where it leads, links are never followed
+    Link(PathBuf),
+}
+
+/**
+ * The content of a file or a folder, every entry by its path relative to
+ * the recorded one, which is the empty path. Recorded before an entry is
+ * copied, then compared with the copy so a corrupted copy is found before
+ * the original goes.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    entries: BTreeMap<PathBuf, Recorded>,
+}
+
+impl Manifest {
+    /**
+     * read and hash everything below `root`, blocking
+     */
+    pub fn record(root: &Path) -> io::Result<Manifest> {
+        let mut manifest = Manifest::default();
+        manifest.add(root, PathBuf::new())?;
+        Ok(manifest)
+    }
+
+    fn add(&mut self, path: &Path, relative: PathBuf) -> io::Result<()> {
+        let metadata = fs::symlink_metadata(long_path::extended(path))?;
+        if metadata.is_symlink() {
+            let target = fs::read_link(long_path::extended(path))?;
+            self.entries.insert(relative, Recorded::Link(target));
+        } else if metadata.is_dir() {
+            self.entries.insert(relative.clone(), Recorded::Directory);
+            for entry in fs::read_dir(long_path::extended(path))? {
+                let name = entry?.file_name();
+                self.add(&path.join(&name), relative.join(&name))?;
+            }
+        } else {
+            let hash = hash::hash_file(path)?;
+            let size = metadata.len();
+            self.entries.insert(relative, Recorded::File { size, hash });
+        }
+        Ok(())
+    }
+
+    /**
+     * Read and hash what the zip at `archive` holds, relative to its top
+     * entry the way `transaction` writes archives. Blocking.
+     */
+    pub fn of_zip(archive: &Path) -> io::Result<Manifest> {
+        let mut zip =
+            ZipArchive::new(File::open(long_path::extended(archive))?).map_err(io::Error::other)?;
+        let mut manifest = Manifest::default();
+        for index in 0..zip.len() {
+            let mut file = zip.by_index(index).map_err(io::Error::other)?;
+            let Some(name) = file.enclosed_name() else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} holds an entry outside of it", archive.display()),
+                ));
+            };
+            // the top entry is the archived file or folder itself
+            let relative: PathBuf = name
+                .components()
+                .skip(1)
+                .filter(|component| matches!(component, Component::Normal(_)))
+                .collect();
+            if file.is_dir() {
+                manifest.entries.insert(relative, Recorded::Directory);
+                continue;
+            }
+            let mut hasher = blake3::Hasher::new();
+            let size = io::copy(&mut file, &mut hasher)?;
+            let hash = hasher.finalize().to_hex().to_string();
+            manifest
+                .entries
+                .insert(relative, Recorded::File { size, hash });
+        }
+        Ok(manifest)
+    }
+
+    /**
+     * Whether `copy` holds everything this manifest recorded, unchanged.
+     * Entries only in the copy don't matter. An archive stores the file a
+     * link leads to, a link recorded as such is taken as archived.
+     */
+    pub fn verify(&self, copy: &Manifest) -> Result<(), String> {
+        for (path, recorded) in &self.entries {
+            let shown = if path.as_os_str().is_empty() {
+                "the copied entry".to_string()
+            } else {
+                path.display().to_string()
+            };
+            match (recorded, copy.entries.get(path)) {
+                (_, None) => return Err(format!("{} is missing", shown)),
+                (Recorded::Directory, Some(Recorded::Directory)) => {}
+                (Recorded::Link(_), Some(Recorded::File { .. })) => {}
+                (Recorded::Link(target), Some(Recorded::Link(copied))) if target == copied => {}
+                (Recorded::Link(_), Some(Recorded::Link(_))) => {
+                    return Err(format!("{} leads elsewhere", shown));
+                }
+                (
+                    Recorded::File { size, hash },
+                    Some(Recorded::File {
+                        size: copied_size,
+                        hash: copied_hash,
+                    }),
+                ) => {
+                    if size != copied_size {
+                        return Err(format!("{} has {} of {} bytes", shown, copied_size, size));
+                    }
+                    if hash != copied_hash {
+                        return Err(format!("{} has another content", shown));
+                    }
+                }
+                _ => return Err(format!("{} is another kind of entry", shown)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[test]
+    fn test_corrupted_copy_found() {
+        let root = temp_path("manifest");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("photos/2024")).unwrap();
+        fs::write(root.join("photos/2024/a.jpg"), "original").unwrap();
+        fs::write(root.join("photos/b.jpg"), "other").unwrap();
+        fs::create_dir_all(root.join("copy/2024")).unwrap();
+        fs::write(root.join("copy/2024/a.jpg"), "original").unwrap();
+        fs::write(root.join("copy/b.jpg"), "other").unwrap();
+
+        let recorded = Manifest::record(&root.join("photos")).unwrap();
+        assert_eq!(
+            recorded.verify(&Manifest::record(&root.join("copy")).unwrap()),
+            Ok(())
+        );
+
+        // same length, one flipped byte
+        fs::write(root.join("copy/2024/a.jpg"), "originaL").unwrap();
+        let error = recorded
+            .verify(&Manifest::record(&root.join("copy")).unwrap())
+            .unwrap_err();
+        assert!(error.contains("a.jpg"));
+        fs::write(root.join("copy/2024/a.jpg"), "original").unwrap();
+        fs::remove_file(root.join("copy/b.jpg")).unwrap();
+        let error = recorded
+            .verify(&Manifest::record(&root.join("copy")).unwrap())
+            .unwrap_err();
+        assert!(error.contains("missing"));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,166 @@
+use std::{mem::size_of, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::tree::node::{Node, NodeId};
+
+/**
+ * heap bytes of an entry name of average length
+ */
+const NAME_BYTES: usize = 32;
+
+/**
+ * what one scanned entry costs: its node and lock in a slot of the arena,
+ * the flag the slot is taken with, its id in the children of its parent
+ * with the room a growing vec keeps, and its name
+ */
+pub const ENTRY_BYTES: u64 =
+    (size_of::<RwLock<Node>>() + size_of::<u64>() + 2 * size_of::<NodeId>() + NAME_BYTES) as u64;
+
+/**
+ * share of the limit from which a scan is warned about, in percent
+ */
+pub const WARNING_PERCENT: u64 = 80;
+
+/**
+ * the smallest file keeping a node of its own once small files are folded
+ */
+pub const MITIGATION_MIN_FILE_SIZE: u64 = 1024 * 1024;
+
+/**
+ * directories deeper than this below the scan root are collapsed into the
+ * one at this depth
+ */
+pub const COLLAPSE_DEPTH: usize = 8;
+
+/**
+ * memory the tree of a scan with `entries` entries takes, an estimate
+ */
+pub fn estimate(entries: usize) -> u64 {
+    entries as u64 * ENTRY_BYTES
+}
+
+/**
+ * the limit when none is configured, a quarter of the physical memory, none
+ * when the memory is unknown
+ */
+pub fn default_limit(total_memory: u64) -> u64 {
+    match total_memory {
+        0 => u64::MAX,
+        total => total / 4,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum PressureLevel {
+    /// past `WARNING_PERCENT` of the limit
+    Warning,
+    /// past the limit
+    Critical,
+}
+
+/**
+ * how a running scan can keep its tree smaller, the entries already scanned
+ * keep counting in the sizes
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum Mitigation {
+    /// files below `MITIGATION_MIN_FILE_SIZE` only count in their directory from now on
+    FoldSmallFiles,
+    /// entries deeper than `COLLAPSE_DEPTH` are dropped and no longer listed into the tree
+    CollapseDeepLevels,
+}
+
+/**
+ * the tree of a scan grows close to or past the memory it may take
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryPressure {
+    pub level: PressureLevel,
+    pub entries: usize,
+    /// bytes the tree is estimated to take
+    pub estimated: u64,
+    pub limit: u64,
+    /// the mitigations not in effect yet, apply one with `mitigate_memory_pressure`
+    pub mitigations: Vec<Mitigation>,
+}
+
+/**
+ * Watches the size of one scan against a limit, each level is reported
+ * once as the tree grows into it.
+ */
+#[derive(Debug, Clone)]
+pub struct MemoryGuard {
+    limit: u64,
+    reported: Option<PressureLevel>,
+}
+
+impl MemoryGuard {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            reported: None,
+        }
+    }
+
+    pub fn level(&self, entries: usize) -> Option<PressureLevel> {
+        let estimated = estimate(entries);
+        if estimated >= self.limit {
+            Some(PressureLevel::Critical)
+        } else if estimated >= self.limit / 100 * WARNING_PERCENT {
+            Some(PressureLevel::Warning)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * the pressure of a tree of `entries` entries when it reached a level
+     * not reported yet, `mitigations` are only asked for then
+     */
+    pub fn check<F>(&mut self, entries: usize, mitigations: F) -> Option<MemoryPressure>
+    where
+        F: FnOnce() -> Vec<Mitigation>,
+    {
+        let level = self.level(entries)?;
+        if self.reported.is_some_and(|reported| reported >= level) {
+            return None;
+        }
+        self.reported = Some(level);
+        Some(MemoryPressure {
+            level,
+            entries,
+            estimated: estimate(entries),
+            limit: self.limit,
+            mitigations: mitigations(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_level_reported_once() {
+        let per_hundred = estimate(100);
+        let mut guard = MemoryGuard::new(per_hundred * 10);
+        assert_eq!(guard.check(500, Vec::new), None);
+
+        let warning = guard
+            .check(850, || vec![Mitigation::FoldSmallFiles])
+            .unwrap();
+        assert_eq!(warning.level, PressureLevel::Warning);
+        assert_eq!(warning.estimated, estimate(850));
+        assert_eq!(warning.mitigations, vec![Mitigation::FoldSmallFiles]);
+        assert_eq!(guard.check(900, Vec::new), None);
+
+        let critical = guard.check(1000, Vec::new).unwrap();
+        assert_eq!(critical.level, PressureLevel::Critical);
+        assert_eq!(guard.check(2000, Vec::new), None);
+    }
+}
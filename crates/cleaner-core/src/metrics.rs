@@ -0,0 +1,368 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    models::{Volumn, now_secs},
+    tree::Tree,
+};
+
+/**
+ * directories below a scan root exported, the largest ones, a Prometheus
+ * series each
+ */
+pub const DIRECTORY_LIMIT: usize = 50;
+
+/**
+ * bytes of a request read before it is answered, the metrics need no body
+ */
+const REQUEST_LIMIT: usize = 8 * 1024;
+
+/**
+ * how long a client may take to send its request
+ */
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+ * the last finished scan of one root
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanMetrics {
+    pub size: usize,
+    /// files and directories below the root
+    pub entries: usize,
+    pub duration: Duration,
+    /// unix seconds
+    pub finished_at: u64,
+    /// the `DIRECTORY_LIMIT` largest directories right below the root
+    pub directories: Vec<(PathBuf, usize)>,
+}
+
+impl ScanMetrics {
+    /**
+     * the metrics of the scan of `root` in `tree`, which just finished
+     */
+    pub fn of(tree: &Tree, root: &Path, duration: Duration) -> Option<ScanMetrics> {
+        let node = tree.get_node(root)?;
+        let children = node.children();
+        let node = node.read().ok()?;
+        let mut directories: Vec<(PathBuf, usize)> = children
+            .iter()
+            .filter_map(|child| {
+                let child = child.read().ok()?;
+                child
+                    .is_directory
+                    .then(|| (root.join(&child.path), child.size()))
+            })
+            .collect();
+        directories.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        directories.truncate(DIRECTORY_LIMIT);
+        Some(ScanMetrics {
+            size: node.size(),
+            entries: node.count(),
+            duration,
+            finished_at: now_secs(),
+            directories,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Recorded {
+    volumes: Vec<Volumn>,
+    scans: BTreeMap<PathBuf, ScanMetrics>,
+}
+
+/**
+ * The volumes and the last scan of every root, rendered in the Prometheus
+ * text format for servers graphing how directories grow. Clones share the
+ * recorded values.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    recorded: Arc<RwLock<Recorded>>,
+}
+
+/**
+ * a label value, quoted the way the text format wants it
+ */
+fn label(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn path_label(path: &Path) -> String {
+    label(&path.to_string_lossy())
+}
+
+/**
+ * the HELP and TYPE lines of a metric, then one line per sample
+ */
+fn family(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    kind: &str,
+    samples: impl Iterator<Item = (String, String)>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+impl Metrics {
+    pub fn set_volumes(&self, volumes: Vec<Volumn>) {
+        let mut recorded = self
+            .recorded
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        recorded.volumes = volumes;
+    }
+
+    /**
+     * replace the metrics of the previous scan of `root`
+     */
+    pub fn record_scan(&self, root: PathBuf, scan: ScanMetrics) {
+        let mut recorded = self
+            .recorded
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        recorded.scans.insert(root, scan);
+    }
+
+    /**
+     * everything recorded, in the Prometheus text format
+     */
+    pub fn render(&self) -> String {
+        let recorded = self.recorded.read().unwrap_or_else(PoisonError::into_inner);
+        let mut out = String::new();
+
+        let volumes = &recorded.volumes;
+        let volume = |volume: &Volumn| {
+            format!(
+                "mount={},filesystem={}",
+                path_label(&volume.path),
+                label(&volume.file_system)
+            )
+        };
+        family(
+            &mut out,
+            "cleaner_volume_size_bytes",
+            "Size of the volume.",
+            "gauge",
+            volumes
+                .iter()
+                .map(|v| (volume(v), v.total_size.to_string())),
+        );
+        family(
+            &mut out,
+            "cleaner_volume_available_bytes",
+            "Bytes still available on the volume.",
+            "gauge",
+            volumes
+                .iter()
+                .map(|v| (volume(v), v.available_size.to_string())),
+        );
+
+        let scans = &recorded.scans;
+        let root = |root: &Path| format!("root={}", path_label(root));
+        family(
+            &mut out,
+            "cleaner_scan_size_bytes",
+            "Bytes below the root at its last scan.",
+            "gauge",
+            scans
+                .iter()
+                .map(|(path, scan)| (root(path), scan.size.to_string())),
+        );
+        family(
+            &mut out,
+            "cleaner_scan_entries",
+            "Files and directories below the root at its last scan.",
+            "gauge",
+            scans
+                .iter()
+                .map(|(path, scan)| (root(path), scan.entries.to_string())),
+        );
+        family(
+            &mut out,
+            "cleaner_scan_duration_seconds",
+            "How long the last scan of the root took.",
+            "gauge",
+            scans
+                .iter()
+                .map(|(path, scan)| (root(path), format!("{:.3}", scan.duration.as_secs_f64()))),
+        );
+        family(
+            &mut out,
+            "cleaner_scan_finished_timestamp_seconds",
+            "When the last scan of the root finished.",
+            "gauge",
+            scans
+                .iter()
+                .map(|(path, scan)| (root(path), scan.finished_at.to_string())),
+        );
+        family(
+            &mut out,
+            "cleaner_directory_size_bytes",
+            "Bytes below a directory right below the root, the largest ones.",
+            "gauge",
+            scans.iter().flat_map(|(path, scan)| {
+                scan.directories.iter().map(move |(directory, size)| {
+                    let labels = format!("{},path={}", root(path), path_label(directory));
+                    (labels, size.to_string())
+                })
+            }),
+        );
+        out
+    }
+}
+
+/**
+ * answer one request, `/metrics` gets the metrics and anything else a 404
+ */
+async fn respond(mut stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 1024];
+    let read = tokio::time::timeout(REQUEST_TIMEOUT, async {
+        while !head.windows(4).any(|window| window == b"\r\n\r\n") && head.len() < REQUEST_LIMIT {
+            let read = stream.read(&mut chunk).await?;
+            if read == 0 {
+                break;
+            }
+            head.extend_from_slice(&chunk[..read]);
+        }
+        io::Result::Ok(())
+    })
+    .await;
+    if !matches!(read, Ok(Ok(()))) {
+        return Ok(());
+    }
+
+    let line = String::from_utf8_lossy(&head);
+    let mut request = line.split_whitespace();
+    let (method, target) = (request.next(), request.next());
+    let path = target.map(|target| target.split('?').next().unwrap_or_default());
+    let (status, body) = match (method, path) {
+        (Some("GET" | "HEAD"), Some("/metrics")) => ("200 OK", metrics.render()),
+        (Some("GET" | "HEAD"), _) => ("404 Not Found", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "only GET\n".to_string()),
+    };
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    if method != Some("HEAD") {
+        response.push_str(&body);
+    }
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/**
+ * Answer scrapes on `listener` until the task is aborted, each connection
+ * gets one response. Bind it to a local address, the metrics tell the
+ * directories of the user.
+ */
+pub async fn serve(listener: TcpListener, metrics: Metrics) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                tracing::warn!("accept metrics scrape failed, {}", err);
+                continue;
+            }
+        };
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(err) = respond(stream, &metrics).await {
+                tracing::debug!("answer metrics scrape failed, {}", err);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::node::Node;
+    use std::ffi::OsString;
+
+    fn metrics() -> Metrics {
+        let mut tree = Tree::from_node(Node::new(OsString::from("/srv"), true, false));
+        let root = PathBuf::from("/srv");
+        for (name, size) in [("media", 900), ("backups \"old\"", 300)] {
+            let directory = Node::new(OsString::from(name), true, false);
+            directory.set_size(size);
+            let _ = tree.insert(&root, directory);
+        }
+        let file = Node::new(OsString::from("notes.txt"), false, false);
+        file.set_size(5);
+        let _ = tree.insert(&root, file);
+
+        let metrics = Metrics::default();
+        let scan = ScanMetrics::of(&tree, &root, Duration::from_millis(1500)).unwrap();
+        metrics.record_scan(root, scan);
+        metrics
+    }
+
+    #[test]
+    fn test_render_text_format() {
+        let text = metrics().render();
+        assert!(text.contains("# TYPE cleaner_scan_size_bytes gauge\n"));
+        assert!(text.contains("cleaner_scan_duration_seconds{root=\"/srv\"} 1.500\n"));
+        // largest first, files left out, quotes escaped
+        let media = text
+            .find("cleaner_directory_size_bytes{root=\"/srv\",path=\"/srv/media\"} 900\n")
+            .unwrap();
+        let backups = text
+            .find("path=\"/srv/backups \\\"old\\\"\"} 300\n")
+            .unwrap();
+        assert!(media < backups);
+        assert!(!text.contains("notes.txt"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_serve_metrics() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(listener, metrics()));
+
+        let get = |target: &'static str| async move {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target);
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("cleaner_scan_size_bytes{root=\"/srv\"}"));
+        assert!(get("/").await.starts_with("HTTP/1.1 404"));
+        server.abort();
+    }
+}
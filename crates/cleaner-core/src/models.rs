@@ -0,0 +1,442 @@
+use std::{cmp::Ordering, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    display_name,
+    labels::FolderLabel,
+    locks::LockFlags,
+    lossless,
+    quarantine::{self, Quarantine},
+    symlinks,
+    tree::node::Node,
+};
+
+const YEAR: u64 = 365 * 24 * 60 * 60;
+
+/**
+ * a twelfth of a year, so that twelve of them make the stale bucket start a
+ * year back
+ */
+const MONTH: u64 = YEAR / 12;
+
+/**
+ * bytes of the files below a directory bucketed by last modification
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AgeBuckets {
+    /// modified within the last month
+    pub recent: usize,
+    /// one to six months ago
+    pub months: usize,
+    /// six to twelve months ago
+    pub half_year: usize,
+    /// more than a year ago, or unknown
+    pub stale: usize,
+}
+
+impl AgeBuckets {
+    pub fn add(&mut self, modified: Option<u64>, size: usize, now: u64) {
+        let age = modified.map(|modified| now.saturating_sub(modified));
+        match age {
+            Some(age) if age < MONTH => self.recent += size,
+            Some(age) if age < 6 * MONTH => self.months += size,
+            Some(age) if age < YEAR => self.half_year += size,
+            _ => self.stale += size,
+        }
+    }
+
+    pub fn merge(&mut self, other: &AgeBuckets) {
+        self.recent += other.recent;
+        self.months += other.months;
+        self.half_year += other.half_year;
+        self.stale += other.stale;
+    }
+
+    pub fn subtract(&mut self, other: &AgeBuckets) {
+        self.recent = self.recent.saturating_sub(other.recent);
+        self.months = self.months.saturating_sub(other.months);
+        self.half_year = self.half_year.saturating_sub(other.half_year);
+        self.stale = self.stale.saturating_sub(other.stale);
+    }
+}
+
+pub fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/**
+ * what the children of a folder are sorted by
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ChildSort {
+    #[default]
+    Size,
+    Name,
+    Modified,
+    /// the entries below a child, a file has none
+    Count,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/**
+ * Which children of a folder its details carry. The default keeps all of
+ * them, largest first.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ChildPage {
+    pub sort_by: ChildSort,
+    /// none for names from A to Z and anything else from the largest down
+    pub order: Option<SortOrder>,
+    pub offset: usize,
+    /// none for every child from `offset` on
+    pub limit: Option<usize>,
+}
+
+impl ChildPage {
+    fn compare(&self, a: &FileDetails, b: &FileDetails) -> Ordering {
+        let by_name = || {
+            a.name
+                .to_lowercase()
+                .cmp(&b.name.to_lowercase())
+                .then_with(|| a.name.cmp(&b.name))
+        };
+        let (ordering, natural) = match self.sort_by {
+            ChildSort::Size => (a.size.cmp(&b.size), SortOrder::Descending),
+            ChildSort::Name => (by_name(), SortOrder::Ascending),
+            ChildSort::Modified => (a.modified.cmp(&b.modified), SortOrder::Descending),
+            ChildSort::Count => (a.count.cmp(&b.count), SortOrder::Descending),
+        };
+        let ordering = match self.order.unwrap_or(natural) {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        };
+        // ties by name, so a page never shows a child the one before it had
+        ordering.then_with(by_name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDetails {
+    pub name: String,
+    /// what Finder or Explorer call a well-known location, e.g. iCloud Drive, shown instead of `name`
+    pub display_name: Option<String>,
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8, pass it back to act on the file
+    pub raw_path: Option<String>,
+    pub size: usize,
+    /// bytes taken on disk, less than `size` for sparse or compressed files
+    pub allocated_size: usize,
+    /// bytes of further hard links to files counted elsewhere, left out of `size`
+    pub hardlink_savings: usize,
+    pub is_directory: bool,
+    pub is_link: bool,
+    /// where a link leads with every link on the way resolved, only looked up on the disk of a live scan
+    pub link_target: Option<String>,
+    pub created: u64,
+    pub modified: u64,
+    pub readonly: bool,
+    /// what a cleanup has to clear before it can remove the entry
+    pub locks: LockFlags,
+    pub file_type: String,
+    pub age: AgeBuckets,
+    /// false while the scan is still adding to `size`
+    pub complete: bool,
+    /// the user skipped this directory mid-scan, its size is partial
+    pub skipped: bool,
+    /// where a downloaded file came from, only looked up on the disk of a live scan
+    pub quarantine: Option<Quarantine>,
+    /// color and emoji the user pinned on the folder, only filled in by a `LabelStore`
+    pub label: Option<FolderLabel>,
+    /// the entries below it, the subdirectories counted too
+    pub count: usize,
+    /// how many children it has, `children` may only hold a page of them
+    pub total_children: Option<usize>,
+    pub children: Option<Vec<FileDetails>>,
+}
+
+impl FileDetails {
+    pub fn from(stat: &Node) -> FileDetails {
+        let path = stat.path.clone();
+        let age = if stat.is_directory {
+            stat.age()
+        } else {
+            let mut age = AgeBuckets::default();
+            age.add(stat.modified, stat.size(), now_secs());
+            age
+        };
+
+        FileDetails {
+            name: path.to_string_lossy().into_owned(),
+            display_name: None,
+            path: PathBuf::from("/"),
+            raw_path: None,
+            size: stat.size(),
+            allocated_size: stat.allocated_size(),
+            hardlink_savings: stat.hardlink_savings(),
+            is_directory: stat.is_directory,
+            is_link: stat.is_link,
+            link_target: None,
+            created: stat.created.unwrap_or_default(),
+            modified: stat.modified.unwrap_or_default(),
+            readonly: stat.locks.read_only,
+            locks: stat.locks,
+            file_type: "file".to_string(),
+            age,
+            complete: stat.complete,
+            skipped: stat.skipped,
+            quarantine: None,
+            label: None,
+            count: stat.count(),
+            total_children: None,
+            children: None,
+        }
+    }
+
+    /**
+     * the details placed at the full `path` they were looked up at
+     */
+    pub fn at(mut self, path: PathBuf) -> FileDetails {
+        self.display_name = display_name::of(&path);
+        self.raw_path = lossless::raw_if_lossy(&path);
+        self.path = path;
+        self
+    }
+
+    /**
+     * sort `children` as `page` asks and keep only the children of the page,
+     * `total_children` still counts all of them
+     */
+    pub fn page_children(&mut self, page: &ChildPage) {
+        let Some(children) = self.children.as_mut() else {
+            return;
+        };
+        self.total_children = Some(children.len());
+        children.sort_by(|a, b| page.compare(a, b));
+        children.drain(..page.offset.min(children.len()));
+        if let Some(limit) = page.limit {
+            children.truncate(limit);
+        }
+    }
+
+    /**
+     * look up the quarantine of the file and of the files among its children
+     */
+    pub fn probe_quarantine(&mut self) {
+        if !self.is_directory {
+            self.quarantine = quarantine::read(&self.path);
+        }
+        for child in self.children.iter_mut().flatten() {
+            if !child.is_directory {
+                child.quarantine = quarantine::read(&child.path);
+            }
+        }
+    }
+
+    /**
+     * look up where the entry and the links among its children lead
+     */
+    pub fn resolve_links(&mut self) {
+        if self.is_link {
+            self.link_target = symlinks::target(&self.path).map(lossy);
+        }
+        for child in self.children.iter_mut().flatten() {
+            if child.is_link {
+                child.link_target = symlinks::target(&child.path).map(lossy);
+            }
+        }
+    }
+}
+
+fn lossy(path: PathBuf) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+impl Default for FileDetails {
+    fn default() -> Self {
+        Self {
+            name: Default::default(),
+            display_name: Default::default(),
+            path: Default::default(),
+            raw_path: Default::default(),
+            size: Default::default(),
+            allocated_size: Default::default(),
+            hardlink_savings: Default::default(),
+            is_directory: Default::default(),
+            is_link: Default::default(),
+            link_target: Default::default(),
+            created: Default::default(),
+            modified: Default::default(),
+            readonly: Default::default(),
+            locks: Default::default(),
+            file_type: Default::default(),
+            age: Default::default(),
+            complete: Default::default(),
+            skipped: Default::default(),
+            quarantine: Default::default(),
+            label: Default::default(),
+            count: Default::default(),
+            total_children: Default::default(),
+            children: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResult {
+    pub files: Vec<FileDetails>,
+    pub total_size: u64,
+    pub total_files: usize,
+    pub total_dirs: usize,
+}
+
+/**
+ * Volumn Information
+ * */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Volumn {
+    pub name: String,
+    /// the name Finder or Explorer show, e.g. `Local Disk (C:)` for an unlabeled drive
+    pub display_name: String,
+    pub path: PathBuf,
+    pub total_size: u64,
+    pub available_size: u64,
+    /// filesystem type as the OS reports it, e.g. `apfs` or `ntfs`
+    pub file_system: String,
+    /// probed on demand, none from the periodic refresh
+    pub capabilities: Option<VolumeCapabilities>,
+    /// Windows only, measured with the capabilities
+    pub shadow_storage: Option<ShadowStorage>,
+    /// Unix only, none when the filesystem has no fixed number of inodes
+    pub inodes: Option<InodeUsage>,
+}
+
+/**
+ * what the filesystem of a volume supports, probed instead of guessed from the OS
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeCapabilities {
+    pub case_sensitive: bool,
+    pub symlinks: bool,
+    pub hardlinks: bool,
+    /// copy on write clones, clonefile on APFS or FICLONE on Btrfs and XFS
+    pub reflinks: bool,
+    /// transparent per-file compression
+    pub compression: bool,
+}
+
+/**
+ * Space Windows keeps on a volume for shadow copies, which System Restore
+ * points are made of. A scan of the files never sees it.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadowStorage {
+    pub used: u64,
+    /// reserved, the used space included
+    pub allocated: u64,
+    /// the limit the copies may grow to, none when unbounded
+    pub maximum: Option<u64>,
+    /// shadow copies kept, the oldest go first when the limit is reached
+    pub copies: usize,
+}
+
+/**
+ * Files a volume can still hold. A volume runs out of inodes with bytes to
+ * spare when millions of tiny files fill it.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InodeUsage {
+    pub total: u64,
+    pub used: u64,
+    /// free for an unprivileged user, some are kept for root
+    pub available: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_age_buckets() {
+        let now = 400 * 24 * 60 * 60;
+        let mut age = AgeBuckets::default();
+        age.add(Some(now - 10), 1, now);
+        age.add(Some(now - 2 * MONTH), 2, now);
+        age.add(Some(now - 7 * MONTH), 4, now);
+        age.add(Some(now - 13 * MONTH), 8, now);
+        age.add(None, 16, now);
+        // 360 days is still within the year
+        age.add(Some(now - 360 * 24 * 60 * 60), 32, now);
+        assert_eq!(
+            age,
+            AgeBuckets {
+                recent: 1,
+                months: 2,
+                half_year: 36,
+                stale: 24,
+            }
+        );
+    }
+
+    #[test]
+    fn test_children_paged() {
+        let child = |name: &str, size, count| FileDetails {
+            name: name.to_string(),
+            size,
+            count,
+            ..FileDetails::default()
+        };
+        let mut details = FileDetails {
+            is_directory: true,
+            children: Some(vec![
+                child("b", 10, 0),
+                child("A", 30, 5),
+                child("c", 20, 2),
+                child("d", 20, 1),
+            ]),
+            ..FileDetails::default()
+        };
+        let names = |details: &FileDetails| -> Vec<String> {
+            let children = details.children.as_ref().unwrap();
+            children.iter().map(|child| child.name.clone()).collect()
+        };
+
+        details.page_children(&ChildPage::default());
+        assert_eq!(names(&details), vec!["A", "c", "d", "b"]);
+        details.page_children(&ChildPage {
+            sort_by: ChildSort::Name,
+            offset: 1,
+            limit: Some(2),
+            ..ChildPage::default()
+        });
+        assert_eq!(names(&details), vec!["b", "c"]);
+        assert_eq!(details.total_children, Some(4));
+        details.page_children(&ChildPage {
+            sort_by: ChildSort::Count,
+            order: Some(SortOrder::Ascending),
+            offset: 5,
+            ..ChildPage::default()
+        });
+        assert!(names(&details).is_empty());
+        assert_eq!(details.total_children, Some(2));
+    }
+}
@@ -0,0 +1,745 @@
+use std::{
+    fs::{self, File, FileTimes},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{long_path, manifest::Manifest, space, throttle::Throttle};
+
+/**
+ * added to the name of an entry on its way to overwrite another, until it is complete
+ */
+const INCOMING_SUFFIX: &str = ".cleaner-incoming";
+
+/**
+ * added to the name of an entry being overwritten, until its replacement is in place
+ */
+const REPLACED_SUFFIX: &str = ".cleaner-replaced";
+
+/**
+ * how often a copy across volumes reports its progress, the end of each stage is always reported
+ */
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/**
+ * bytes read and written at once while copying a file
+ */
+pub(crate) const CHUNK_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum MoveStage {
+    Copying,
+    /// the copy is compared with the source before the source goes
+    Verifying,
+    Removing,
+    Done,
+}
+
+/**
+ * how far a move across volumes got, a rename is reported once done
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveProgress {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub stage: MoveStage,
+    /// the entry is on another volume than its destination, it is copied then removed
+    pub cross_device: bool,
+    pub total_bytes: u64,
+    pub copied_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveReport {
+    pub from: PathBuf,
+    /// where the entry went, a free name next to the destination when it was renamed
+    pub to: PathBuf,
+    pub cross_device: bool,
+    /// bytes copied, zero for a rename
+    pub bytes: u64,
+    /// the sources moved or copied, the entries of merged directories one by one
+    pub transferred: Vec<PathBuf>,
+    /// sources left where they are because of a conflict
+    pub skipped: Vec<PathBuf>,
+    /// destinations the entries took the place of
+    pub replaced: Vec<PathBuf>,
+}
+
+impl MoveReport {
+    pub(crate) fn new(from: &Path, to: &Path) -> Self {
+        MoveReport {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+            cross_device: false,
+            bytes: 0,
+            transferred: vec![],
+            skipped: vec![],
+            replaced: vec![],
+        }
+    }
+}
+
+/**
+ * what to do when the destination of a move or copy exists
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CollisionPolicy {
+    /// refuse, the destination is never touched
+    #[default]
+    Fail,
+    Skip,
+    /// the entry goes next to the destination first and replaces it once complete
+    Overwrite,
+    /// a free name next to the destination, `name (2).ext`
+    Rename,
+    /// two directories are merged entry by entry, conflicts inside resolved the same way, a file is skipped
+    Merge,
+    /// every conflict is handed to the caller, who answers with a `ConflictChoice`
+    Ask,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictChoice {
+    Skip,
+    Overwrite,
+    Rename,
+    Merge,
+}
+
+/**
+ * an entry whose destination exists, as asked about by `CollisionPolicy::Ask`
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Conflict {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub source_directory: bool,
+    pub destination_directory: bool,
+    pub source_size: u64,
+    /// bytes of the destination file, zero for a directory
+    pub destination_size: u64,
+    pub source_modified: Option<u64>,
+    pub destination_modified: Option<u64>,
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> Option<u64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_secs())
+}
+
+/**
+ * The conflicts of one move or copy resolved by a `CollisionPolicy`. With
+ * `Ask`, `ask` gets each conflict and answers with a choice, and whether the
+ * choice holds for the conflicts still to come.
+ */
+pub struct Collisions<'a> {
+    policy: CollisionPolicy,
+    ask: &'a mut dyn FnMut(&Conflict) -> io::Result<(ConflictChoice, bool)>,
+    remembered: Option<ConflictChoice>,
+}
+
+impl<'a> Collisions<'a> {
+    pub fn new(
+        policy: CollisionPolicy,
+        ask: &'a mut dyn FnMut(&Conflict) -> io::Result<(ConflictChoice, bool)>,
+    ) -> Self {
+        Collisions {
+            policy,
+            ask,
+            remembered: None,
+        }
+    }
+
+    fn resolve(&mut self, conflict: &Conflict) -> io::Result<ConflictChoice> {
+        match self.policy {
+            CollisionPolicy::Fail => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", conflict.to.display()),
+            )),
+            CollisionPolicy::Skip => Ok(ConflictChoice::Skip),
+            CollisionPolicy::Overwrite => Ok(ConflictChoice::Overwrite),
+            CollisionPolicy::Rename => Ok(ConflictChoice::Rename),
+            CollisionPolicy::Merge => Ok(ConflictChoice::Merge),
+            CollisionPolicy::Ask => {
+                if let Some(choice) = self.remembered {
+                    return Ok(choice);
+                }
+                let (choice, apply_to_all) = (self.ask)(conflict)?;
+                if apply_to_all {
+                    self.remembered = Some(choice);
+                }
+                Ok(choice)
+            }
+        }
+    }
+}
+
+pub(crate) fn ensure_absent(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(long_path::extended(path)) {
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+/**
+ * refuse to put an entry onto itself, inside itself or onto one of its
+ * ancestors, overwriting an ancestor would remove the source with it
+ */
+pub(crate) fn ensure_apart(from: &Path, to: &Path) -> io::Result<()> {
+    if to.starts_with(from) || from.starts_with(to) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} and {} overlap", from.display(), to.display()),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn remove(path: &Path) -> io::Result<()> {
+    let path = long_path::extended(path);
+    if fs::symlink_metadata(&path)?.is_dir() {
+        fs::remove_dir_all(&path)
+    } else {
+        fs::remove_file(&path)
+    }
+}
+
+/**
+ * bytes of the files below `path`, links counted as links
+ */
+pub(crate) fn bytes_below(path: &Path) -> io::Result<u64> {
+    let metadata = fs::symlink_metadata(long_path::extended(path))?;
+    if !metadata.is_dir() {
+        return Ok(if metadata.is_symlink() {
+            0
+        } else {
+            metadata.len()
+        });
+    }
+    let mut size = 0;
+    for entry in fs::read_dir(long_path::extended(path))? {
+        size += bytes_below(&path.join(entry?.file_name()))?;
+    }
+    Ok(size)
+}
+
+/**
+ * Move `from` to `to`, which must not exist. A rename when both are on the
+ * same volume. Across volumes the entry is copied, after making sure the
+ * destination has room for it, the copy compared with the source, then
+ * the source removed. A copy which doesn't match is
+ * removed again, and when the source can't be removed completely the copy
+ * stays, it is the only complete one left. The copy is paced by `throttle`,
+ * `observe` gets the progress every `PROGRESS_INTERVAL`. Blocking.
+ */
+pub fn move_path(
+    from: &Path,
+    to: &Path,
+    throttle: &mut Throttle,
+    observe: &mut dyn FnMut(&MoveProgress),
+) -> io::Result<MoveReport> {
+    ensure_absent(to)?;
+    let mut progress = MoveProgress {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+        stage: MoveStage::Done,
+        cross_device: false,
+        total_bytes: 0,
+        copied_bytes: 0,
+    };
+    match fs::rename(long_path::extended(from), long_path::extended(to)) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {}
+        renamed => {
+            renamed?;
+            observe(&progress);
+            let mut report = MoveReport::new(from, to);
+            report.transferred.push(from.to_path_buf());
+            return Ok(report);
+        }
+    }
+
+    progress.cross_device = true;
+    progress.stage = MoveStage::Copying;
+    progress.total_bytes = bytes_below(from)?;
+    space::ensure(to, progress.total_bytes)?;
+    observe(&progress);
+    let recorded = Manifest::record(from)?;
+    let copied = copy_reporting(from, to, &mut progress, throttle, observe);
+    let checked = copied.and_then(|_| {
+        progress.stage = MoveStage::Verifying;
+        observe(&progress);
+        recorded.verify(&Manifest::record(to)?).map_err(|mismatch| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} doesn't match its source, {}", to.display(), mismatch),
+            )
+        })
+    });
+    if let Err(err) = checked {
+        let _ = remove(to);
+        return Err(err);
+    }
+
+    progress.stage = MoveStage::Removing;
+    observe(&progress);
+    remove(from).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("copied to {} but not removed, {}", to.display(), err),
+        )
+    })?;
+    progress.stage = MoveStage::Done;
+    observe(&progress);
+    let mut report = MoveReport::new(from, to);
+    report.cross_device = true;
+    report.bytes = progress.copied_bytes;
+    report.transferred.push(from.to_path_buf());
+    Ok(report)
+}
+
+/**
+ * copy `from` to `to` no faster than `throttle` allows, counting the bytes
+ * in `progress`, reported every `PROGRESS_INTERVAL`
+ */
+fn copy_reporting(
+    from: &Path,
+    to: &Path,
+    progress: &mut MoveProgress,
+    throttle: &mut Throttle,
+    observe: &mut dyn FnMut(&MoveProgress),
+) -> io::Result<()> {
+    let mut reported = Instant::now();
+    let mut count = |copied: u64| {
+        throttle.pace(copied);
+        progress.copied_bytes += copied;
+        if reported.elapsed() >= PROGRESS_INTERVAL {
+            observe(progress);
+            reported = Instant::now();
+        }
+    };
+    copy_tree(from, to, &mut count)
+}
+
+/**
+ * Copy `from` to `to`, which must not exist, reporting the progress like a
+ * move across volumes. Refused with `InsufficientSpace` before anything is
+ * written when the destination has no room for it. A copy which fails
+ * halfway is removed. Blocking.
+ */
+pub fn copy_path(
+    from: &Path,
+    to: &Path,
+    throttle: &mut Throttle,
+    observe: &mut dyn FnMut(&MoveProgress),
+) -> io::Result<MoveReport> {
+    ensure_absent(to)?;
+    let mut progress = MoveProgress {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+        stage: MoveStage::Copying,
+        cross_device: false,
+        total_bytes: bytes_below(from)?,
+        copied_bytes: 0,
+    };
+    space::ensure(to, progress.total_bytes)?;
+    observe(&progress);
+    if let Err(err) = copy_reporting(from, to, &mut progress, throttle, observe) {
+        let _ = remove(to);
+        return Err(err);
+    }
+    progress.stage = MoveStage::Done;
+    observe(&progress);
+    let mut report = MoveReport::new(from, to);
+    report.bytes = progress.copied_bytes;
+    report.transferred.push(from.to_path_buf());
+    Ok(report)
+}
+
+/**
+ * `path` as the `n`th of the entries sharing its name, `name (n).ext`
+ */
+pub(crate) fn numbered(path: &Path, n: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    path.with_file_name(format!("{} ({}){}", stem, n, extension))
+}
+
+/**
+ * a name next to `path` nothing has yet, `name (2).ext` and up
+ */
+fn free_name(path: &Path) -> io::Result<PathBuf> {
+    for n in 2.. {
+        let candidate = numbered(path, n);
+        match ensure_absent(&candidate) {
+            Ok(()) => return Ok(candidate),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!("the names run out before the numbers")
+}
+
+/**
+ * a name next to `path` with `suffix`, numbered when something has it already
+ */
+fn aside(path: &Path, suffix: &str) -> io::Result<PathBuf> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    let candidate = path.with_file_name(name);
+    match ensure_absent(&candidate) {
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => free_name(&candidate),
+        absent => absent.map(|()| candidate),
+    }
+}
+
+/**
+ * Put the complete entry at `incoming` in place of `to`. The old entry
+ * steps aside first and comes back when the swap fails, it is only
+ * removed once the new one is in place.
+ */
+fn replace(incoming: &Path, to: &Path) -> io::Result<()> {
+    let replaced = aside(to, REPLACED_SUFFIX)?;
+    fs::rename(long_path::extended(to), long_path::extended(&replaced))?;
+    if let Err(err) = fs::rename(long_path::extended(incoming), long_path::extended(to)) {
+        let _ = fs::rename(long_path::extended(&replaced), long_path::extended(to));
+        return Err(io::Error::new(
+            err.kind(),
+            format!("{} kept, not put in place, {}", incoming.display(), err),
+        ));
+    }
+    if let Err(err) = remove(&replaced) {
+        tracing::warn!("remove replaced {} failed, {}", replaced.display(), err);
+    }
+    Ok(())
+}
+
+/**
+ * move or copy `from` to `to`, resolving a conflict with `collisions`, and
+ * tell where the entry went
+ */
+fn transfer(
+    from: &Path,
+    to: &Path,
+    copy: bool,
+    collisions: &mut Collisions,
+    throttle: &mut Throttle,
+    observe: &mut dyn FnMut(&MoveProgress),
+    report: &mut MoveReport,
+) -> io::Result<PathBuf> {
+    let mut to = to.to_path_buf();
+    let existing = match fs::symlink_metadata(long_path::extended(&to)) {
+        Ok(metadata) => Some(metadata),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+    if let Some(existing) = existing {
+        let source = fs::symlink_metadata(long_path::extended(from))?;
+        let conflict = Conflict {
+            from: from.to_path_buf(),
+            to: to.clone(),
+            source_directory: source.is_dir(),
+            destination_directory: existing.is_dir(),
+            source_size: if source.is_dir() { 0 } else { source.len() },
+            destination_size: if existing.is_dir() { 0 } else { existing.len() },
+            source_modified: modified_secs(&source),
+            destination_modified: modified_secs(&existing),
+        };
+        match collisions.resolve(&conflict)? {
+            ConflictChoice::Skip => {
+                report.skipped.push(from.to_path_buf());
+                return Ok(to);
+            }
+            ConflictChoice::Overwrite => {
+                // the destination stays as it is until the entry arrived complete
+                let incoming = aside(&to, INCOMING_SUFFIX)?;
+                let done = if copy {
+                    copy_path(from, &incoming, throttle, observe)?
+                } else {
+                    move_path(from, &incoming, throttle, observe)?
+                };
+                replace(&incoming, &to)?;
+                report.replaced.push(to.clone());
+                report.cross_device |= done.cross_device;
+                report.bytes += done.bytes;
+                report.transferred.extend(done.transferred);
+                return Ok(to);
+            }
+            ConflictChoice::Rename => to = free_name(&to)?,
+            ConflictChoice::Merge
+                if conflict.source_directory && conflict.destination_directory =>
+            {
+                for entry in fs::read_dir(long_path::extended(from))? {
+                    let name = entry?.file_name();
+                    transfer(
+                        &from.join(&name),
+                        &to.join(&name),
+                        copy,
+                        collisions,
+                        throttle,
+                        observe,
+                        report,
+                    )?;
+                }
+                if !copy {
+                    // only gone when nothing below it was skipped
+                    let _ = fs::remove_dir(long_path::extended(from));
+                }
+                return Ok(to);
+            }
+            ConflictChoice::Merge => {
+                report.skipped.push(from.to_path_buf());
+                return Ok(to);
+            }
+        }
+    }
+
+    let done = if copy {
+        copy_path(from, &to, throttle, observe)?
+    } else {
+        move_path(from, &to, throttle, observe)?
+    };
+    report.cross_device |= done.cross_device;
+    report.bytes += done.bytes;
+    report.transferred.extend(done.transferred);
+    Ok(to)
+}
+
+/**
+ * Move or copy `from` to `to`, an existing destination resolved by
+ * `collisions`. Refuses to put an entry onto itself, inside itself or onto
+ * one of its ancestors. Blocking.
+ */
+pub fn transfer_path(
+    from: &Path,
+    to: &Path,
+    copy: bool,
+    collisions: &mut Collisions,
+    throttle: &mut Throttle,
+    observe: &mut dyn FnMut(&MoveProgress),
+) -> io::Result<MoveReport> {
+    fs::symlink_metadata(long_path::extended(from))?;
+    ensure_apart(from, to)?;
+    let mut report = MoveReport::new(from, to);
+    let placed = transfer(from, to, copy, collisions, throttle, observe, &mut report)?;
+    report.to = placed;
+    Ok(report)
+}
+
+/**
+ * Copy a file or a folder with everything below it, links as links. Files
+ * keep their permissions and modification time. `copied` gets the bytes of
+ * every chunk written.
+ */
+pub(crate) fn copy_tree(from: &Path, to: &Path, copied: &mut dyn FnMut(u64)) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(long_path::extended(from))?;
+    if metadata.is_symlink() {
+        return copy_link(from, to);
+    }
+    if !metadata.is_dir() {
+        return copy_file(from, to, &metadata, copied);
+    }
+    fs::create_dir(long_path::extended(to))?;
+    for entry in fs::read_dir(long_path::extended(from))? {
+        let name = entry?.file_name();
+        copy_tree(&from.join(&name), &to.join(&name), copied)?;
+    }
+    Ok(())
+}
+
+fn copy_file(
+    from: &Path,
+    to: &Path,
+    metadata: &fs::Metadata,
+    copied: &mut dyn FnMut(u64),
+) -> io::Result<()> {
+    let mut source = File::open(long_path::extended(from))?;
+    let mut copy = File::create_new(long_path::extended(to))?;
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        let read = source.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        copy.write_all(&chunk[..read])?;
+        copied(read as u64);
+    }
+    copy.set_permissions(metadata.permissions())?;
+    if let Ok(modified) = metadata.modified() {
+        copy.set_times(FileTimes::new().set_modified(modified))?;
+    }
+    copy.sync_all()
+}
+
+#[cfg(unix)]
+pub(crate) fn copy_link(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn copy_link(from: &Path, _to: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} is a link, it isn't copied", from.display()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[test]
+    fn test_copy_reports_progress() {
+        let root = temp_path("mover");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("album/raw")).unwrap();
+        fs::write(root.join("album/raw/a.cr2"), vec![7u8; 3 * CHUNK_SIZE + 5]).unwrap();
+        fs::write(root.join("album/cover.jpg"), "cover").unwrap();
+
+        let mut copied = 0;
+        copy_tree(&root.join("album"), &root.join("copy"), &mut |bytes| {
+            copied += bytes
+        })
+        .unwrap();
+        assert_eq!(copied, 3 * CHUNK_SIZE as u64 + 10);
+        assert_eq!(bytes_below(&root.join("copy")).unwrap(), copied);
+        let recorded = Manifest::record(&root.join("album")).unwrap();
+        assert_eq!(
+            recorded.verify(&Manifest::record(&root.join("copy")).unwrap()),
+            Ok(())
+        );
+        let modified = |path: &str| fs::metadata(root.join(path)).unwrap().modified().unwrap();
+        assert_eq!(modified("album/raw/a.cr2"), modified("copy/raw/a.cr2"));
+
+        // on the same volume it is a rename, reported once
+        let mut stages = vec![];
+        let report = move_path(
+            &root.join("copy"),
+            &root.join("moved"),
+            &mut Throttle::unlimited(),
+            &mut |progress| stages.push(progress.stage),
+        )
+        .unwrap();
+        assert!(!report.cross_device);
+        assert_eq!(stages, vec![MoveStage::Done]);
+        assert!(root.join("moved/raw/a.cr2").exists());
+        let error = move_path(
+            &root.join("album"),
+            &root.join("moved"),
+            &mut Throttle::unlimited(),
+            &mut |_| {},
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_collisions_resolved() {
+        let root = temp_path("collisions");
+        let _ = fs::remove_dir_all(&root);
+        let (src, dst) = (root.join("src"), root.join("dst"));
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::create_dir_all(dst.join("sub")).unwrap();
+        fs::write(src.join("a.txt"), "new").unwrap();
+        fs::write(src.join("sub/b.txt"), "b").unwrap();
+        fs::write(dst.join("a.txt"), "old").unwrap();
+        fs::write(dst.join("sub/c.txt"), "c").unwrap();
+
+        // directories merged, the file renamed
+        let mut asked = vec![];
+        let mut ask = |conflict: &Conflict| -> io::Result<(ConflictChoice, bool)> {
+            asked.push(conflict.to.clone());
+            let both = conflict.source_directory && conflict.destination_directory;
+            Ok(if both {
+                (ConflictChoice::Merge, false)
+            } else {
+                (ConflictChoice::Rename, false)
+            })
+        };
+        let mut collisions = Collisions::new(CollisionPolicy::Ask, &mut ask);
+        let report = transfer_path(
+            &src,
+            &dst,
+            true,
+            &mut collisions,
+            &mut Throttle::unlimited(),
+            &mut |_| {},
+        )
+        .unwrap();
+        assert_eq!(report.to, dst);
+        assert_eq!(report.transferred.len(), 2);
+        assert_eq!(asked.len(), 3);
+        let read = |path: &str| fs::read_to_string(dst.join(path)).unwrap();
+        assert_eq!(read("a.txt"), "old");
+        assert_eq!(read("a (2).txt"), "new");
+        assert_eq!(read("sub/b.txt"), "b");
+        assert_eq!(read("sub/c.txt"), "c");
+
+        let mut never = |_: &Conflict| -> io::Result<(ConflictChoice, bool)> { unreachable!() };
+        let mut skip = Collisions::new(CollisionPolicy::Skip, &mut never);
+        let report = transfer_path(
+            &src,
+            &dst,
+            false,
+            &mut skip,
+            &mut Throttle::unlimited(),
+            &mut |_| {},
+        )
+        .unwrap();
+        assert_eq!(report.skipped, vec![src.clone()]);
+        assert!(src.exists());
+
+        let mut never = |_: &Conflict| -> io::Result<(ConflictChoice, bool)> { unreachable!() };
+        let mut overwrite = Collisions::new(CollisionPolicy::Overwrite, &mut never);
+        let error = transfer_path(
+            &src,
+            &src.join("sub"),
+            false,
+            &mut overwrite,
+            &mut Throttle::unlimited(),
+            &mut |_| {},
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        let report = transfer_path(
+            &src,
+            &dst,
+            false,
+            &mut overwrite,
+            &mut Throttle::unlimited(),
+            &mut |_| {},
+        )
+        .unwrap();
+        assert_eq!(report.replaced, vec![dst.clone()]);
+        assert!(!src.exists());
+        assert_eq!(read("a.txt"), "new");
+        assert!(!dst.join("sub/c.txt").exists());
+        // nothing is left next to the destination
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 1);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
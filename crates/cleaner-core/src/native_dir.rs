@@ -0,0 +1,449 @@
+use std::{ffi::OsString, io, path::Path};
+
+use crate::{file_id::FileId, locks::LockFlags};
+
+/**
+ * whether `NativeDir` lists directories on this platform, elsewhere `open`
+ * fails as unsupported and the portable listing is used
+ */
+pub const SUPPORTED: bool = cfg!(target_os = "macos");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    /// a device, socket or pipe
+    Other,
+}
+
+/**
+ * an entry of a directory with the attributes the scanner keeps, read
+ * together with its name so it needs no stat of its own
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeEntry {
+    pub name: OsString,
+    pub kind: EntryKind,
+    /// the length of a file or a link, a directory has none
+    pub len: u64,
+    /// bytes taken on disk
+    pub allocated: u64,
+    /// unix seconds, none before the epoch
+    pub modified: Option<u64>,
+    pub created: Option<u64>,
+    /// the device and the inode, as `FileId::from_metadata` gives them
+    pub id: FileId,
+    /// hard links to a file, one for a directory
+    pub links: u32,
+    pub locks: LockFlags,
+}
+
+impl NativeEntry {
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Directory
+    }
+
+    /**
+     * what `device::hardlink` gives for the entry, the identity of a file
+     * with more than one hard link
+     */
+    pub fn hardlink(&self) -> Option<FileId> {
+        (!self.is_dir() && self.links > 1).then_some(self.id)
+    }
+}
+
+/**
+ * A directory listed with the native API of the platform, which returns the
+ * attributes of hundreds of entries per call, where `read_dir` takes a stat
+ * for every entry. Blocking.
+ */
+pub struct NativeDir {
+    #[cfg(target_os = "macos")]
+    inner: bulk::BulkDir,
+}
+
+impl NativeDir {
+    #[cfg(target_os = "macos")]
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            inner: bulk::BulkDir::open(path)?,
+        })
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn open(_path: &Path) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "no native directory listing on this platform",
+        ))
+    }
+
+    /**
+     * the next entries, none once all were read. An entry whose attributes
+     * can't be read is passed over like a failed stat, a batch may come
+     * back empty for it.
+     */
+    #[cfg(target_os = "macos")]
+    pub fn next_batch(&mut self) -> io::Result<Option<Vec<NativeEntry>>> {
+        self.inner.next_batch()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn next_batch(&mut self) -> io::Result<Option<Vec<NativeEntry>>> {
+        Ok(None)
+    }
+}
+
+/**
+ * every entry of `path`, read with `NativeDir`
+ */
+pub fn read_dir(path: &Path) -> io::Result<Vec<NativeEntry>> {
+    let mut dir = NativeDir::open(path)?;
+    let mut entries = Vec::new();
+    while let Some(batch) = dir.next_batch()? {
+        entries.extend(batch);
+    }
+    Ok(entries)
+}
+
+/**
+ * `getattrlistbulk`, which every volume format supports from macOS 10.10,
+ * the ones which can't answer it natively are served by the kernel
+ */
+#[cfg(target_os = "macos")]
+mod bulk {
+    use std::{
+        ffi::{OsStr, c_int, c_void},
+        fs::File,
+        io,
+        os::{fd::AsRawFd, unix::ffi::OsStrExt},
+        path::Path,
+    };
+
+    use super::{EntryKind, NativeEntry};
+    use crate::{file_id::FileId, locks::LockFlags};
+
+    // sys/attr.h, not all of them are in libc
+    const ATTR_BIT_MAP_COUNT: u16 = 5;
+    const ATTR_CMN_NAME: u32 = 0x0000_0001;
+    const ATTR_CMN_DEVID: u32 = 0x0000_0002;
+    const ATTR_CMN_OBJTYPE: u32 = 0x0000_0008;
+    const ATTR_CMN_CRTIME: u32 = 0x0000_0200;
+    const ATTR_CMN_MODTIME: u32 = 0x0000_0400;
+    const ATTR_CMN_FLAGS: u32 = 0x0004_0000;
+    const ATTR_CMN_FILEID: u32 = 0x0200_0000;
+    const ATTR_CMN_ERROR: u32 = 0x2000_0000;
+    const ATTR_CMN_RETURNED_ATTRS: u32 = 0x8000_0000;
+    const ATTR_FILE_LINKCOUNT: u32 = 0x0000_0001;
+    const ATTR_FILE_TOTALSIZE: u32 = 0x0000_0002;
+    const ATTR_FILE_ALLOCSIZE: u32 = 0x0000_0004;
+
+    // sys/vnode.h
+    const VREG: u32 = 1;
+    const VDIR: u32 = 2;
+    const VLNK: u32 = 5;
+
+    /**
+     * bytes read per call, a few hundred entries with typical names
+     */
+    const BUFFER_SIZE: usize = 128 * 1024;
+
+    #[repr(C)]
+    struct AttrList {
+        bitmapcount: u16,
+        reserved: u16,
+        commonattr: u32,
+        volattr: u32,
+        dirattr: u32,
+        fileattr: u32,
+        forkattr: u32,
+    }
+
+    unsafe extern "C" {
+        fn getattrlistbulk(
+            dirfd: c_int,
+            attr_list: *mut c_void,
+            attr_buf: *mut c_void,
+            attr_buf_size: usize,
+            options: u64,
+        ) -> c_int;
+    }
+
+    pub(super) struct BulkDir {
+        file: File,
+        buffer: Vec<u8>,
+    }
+
+    impl BulkDir {
+        pub(super) fn open(path: &Path) -> io::Result<Self> {
+            Ok(Self {
+                file: File::open(path)?,
+                buffer: vec![0; BUFFER_SIZE],
+            })
+        }
+
+        pub(super) fn next_batch(&mut self) -> io::Result<Option<Vec<NativeEntry>>> {
+            let mut list = AttrList {
+                bitmapcount: ATTR_BIT_MAP_COUNT,
+                reserved: 0,
+                commonattr: ATTR_CMN_RETURNED_ATTRS
+                    | ATTR_CMN_NAME
+                    | ATTR_CMN_ERROR
+                    | ATTR_CMN_DEVID
+                    | ATTR_CMN_OBJTYPE
+                    | ATTR_CMN_CRTIME
+                    | ATTR_CMN_MODTIME
+                    | ATTR_CMN_FLAGS
+                    | ATTR_CMN_FILEID,
+                volattr: 0,
+                dirattr: 0,
+                fileattr: ATTR_FILE_LINKCOUNT | ATTR_FILE_TOTALSIZE | ATTR_FILE_ALLOCSIZE,
+                forkattr: 0,
+            };
+            let count = loop {
+                // SAFETY: the fd is the open directory `self.file` owns, the attribute list is a
+                // live `attrlist` with a valid bitmap count, and the kernel writes at most
+                // `self.buffer.len()` bytes into the buffer, which outlives the call
+                let count = unsafe {
+                    getattrlistbulk(
+                        self.file.as_raw_fd(),
+                        (&mut list as *mut AttrList).cast(),
+                        self.buffer.as_mut_ptr().cast(),
+                        self.buffer.len(),
+                        0,
+                    )
+                };
+                if count == 0 {
+                    return Ok(None);
+                }
+                if count > 0 {
+                    break count as usize;
+                }
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            };
+
+            let mut entries = Vec::with_capacity(count);
+            let mut offset = 0;
+            for _ in 0..count {
+                let length = Cursor::new(&self.buffer, offset).u32()? as usize;
+                let record = self
+                    .buffer
+                    .get(offset..offset + length)
+                    .ok_or_else(truncated)?;
+                if let Some(entry) = parse(record)? {
+                    entries.push(entry);
+                }
+                offset += length;
+            }
+            Ok(Some(entries))
+        }
+    }
+
+    fn truncated() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "getattrlistbulk returned a truncated entry",
+        )
+    }
+
+    /**
+     * the attributes of a record, packed in the order of their bits and
+     * each aligned to 4 bytes
+     */
+    struct Cursor<'a> {
+        buf: &'a [u8],
+        at: usize,
+    }
+
+    impl<'a> Cursor<'a> {
+        fn new(buf: &'a [u8], at: usize) -> Self {
+            Self { buf, at }
+        }
+
+        fn take<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+            let bytes = self.buf.get(self.at..self.at + N).ok_or_else(truncated)?;
+            self.at += N;
+            Ok(bytes.try_into().expect("slice of N bytes"))
+        }
+
+        fn u32(&mut self) -> io::Result<u32> {
+            self.take().map(u32::from_ne_bytes)
+        }
+
+        fn i32(&mut self) -> io::Result<i32> {
+            self.take().map(i32::from_ne_bytes)
+        }
+
+        fn i64(&mut self) -> io::Result<i64> {
+            self.take().map(i64::from_ne_bytes)
+        }
+
+        fn u64(&mut self) -> io::Result<u64> {
+            self.take().map(u64::from_ne_bytes)
+        }
+
+        /**
+         * a `timespec` as unix seconds, none before the epoch like
+         * `SystemTime::duration_since` gives
+         */
+        fn secs(&mut self) -> io::Result<Option<u64>> {
+            let secs = self.i64()?;
+            let _nanos = self.i64()?;
+            Ok(u64::try_from(secs).ok())
+        }
+
+        /**
+         * the bytes an `attrreference_t` points at, its offset counts from
+         * the reference itself
+         */
+        fn reference(&mut self) -> io::Result<&'a [u8]> {
+            let start = self.at;
+            let offset = self.i32()?;
+            let length = self.u32()? as usize;
+            let from = start
+                .checked_add_signed(offset as isize)
+                .ok_or_else(truncated)?;
+            self.buf.get(from..from + length).ok_or_else(truncated)
+        }
+    }
+
+    fn parse(record: &[u8]) -> io::Result<Option<NativeEntry>> {
+        let mut cursor = Cursor::new(record, 4);
+        let common = cursor.u32()?;
+        let _volume = cursor.u32()?;
+        let _directory = cursor.u32()?;
+        let file = cursor.u32()?;
+        let _fork = cursor.u32()?;
+
+        // right after the returned set, ahead of the order of the bits
+        if common & ATTR_CMN_ERROR != 0 && cursor.u32()? != 0 {
+            return Ok(None);
+        }
+        if common & ATTR_CMN_NAME == 0 {
+            return Ok(None);
+        }
+        let name = cursor.reference()?;
+        let name = OsStr::from_bytes(name.strip_suffix(&[0]).unwrap_or(name)).to_os_string();
+
+        // dev_t is signed, std widens it with its sign
+        let device = if common & ATTR_CMN_DEVID != 0 {
+            cursor.i32()? as u64
+        } else {
+            0
+        };
+        let kind = if common & ATTR_CMN_OBJTYPE != 0 {
+            match cursor.u32()? {
+                VREG => EntryKind::File,
+                VDIR => EntryKind::Directory,
+                VLNK => EntryKind::Symlink,
+                _ => EntryKind::Other,
+            }
+        } else {
+            EntryKind::Other
+        };
+        let created = if common & ATTR_CMN_CRTIME != 0 {
+            cursor.secs()?
+        } else {
+            None
+        };
+        let modified = if common & ATTR_CMN_MODTIME != 0 {
+            cursor.secs()?
+        } else {
+            None
+        };
+        let flags = if common & ATTR_CMN_FLAGS != 0 {
+            cursor.u32()?
+        } else {
+            0
+        };
+        let inode = if common & ATTR_CMN_FILEID != 0 {
+            cursor.u64()?
+        } else {
+            0
+        };
+
+        // the file attributes come for everything but directories
+        let links = if file & ATTR_FILE_LINKCOUNT != 0 {
+            cursor.u32()?
+        } else {
+            1
+        };
+        let len = if file & ATTR_FILE_TOTALSIZE != 0 {
+            cursor.i64()?.max(0) as u64
+        } else {
+            0
+        };
+        let allocated = if file & ATTR_FILE_ALLOCSIZE != 0 {
+            cursor.i64()?.max(0) as u64
+        } else {
+            0
+        };
+
+        Ok(Some(NativeEntry {
+            name,
+            kind,
+            len,
+            allocated,
+            modified,
+            created,
+            id: FileId {
+                volume: device,
+                file: inode,
+            },
+            links,
+            locks: LockFlags::from_st_flags(flags),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[test]
+    fn test_native_entries_match_metadata() {
+        let dir = temp_path("native-dir");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a.bin"), vec![1u8; 5000]).unwrap();
+
+        if !SUPPORTED {
+            let err = read_dir(&dir).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+            let _ = std::fs::remove_dir_all(&dir);
+            return;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            std::fs::hard_link(dir.join("a.bin"), dir.join("b.bin")).unwrap();
+            std::os::unix::fs::symlink("a.bin", dir.join("link")).unwrap();
+
+            let mut entries = read_dir(&dir).unwrap();
+            entries.sort_by(|a, b| a.name.cmp(&b.name));
+            let names: Vec<_> = entries.iter().map(|entry| entry.name.clone()).collect();
+            assert_eq!(names, ["a.bin", "b.bin", "link", "sub"]);
+
+            let metadata = std::fs::symlink_metadata(dir.join("a.bin")).unwrap();
+            let file = &entries[0];
+            assert_eq!(file.kind, EntryKind::File);
+            assert_eq!(file.len, 5000);
+            assert_eq!(file.allocated, metadata.blocks() * 512);
+            assert_eq!(Some(file.id), FileId::from_metadata(&metadata));
+            assert_eq!(file.hardlink(), entries[1].hardlink());
+            assert!(file.hardlink().is_some());
+            assert_eq!(file.modified, Some(metadata.mtime() as u64));
+            assert_eq!(entries[2].kind, EntryKind::Symlink);
+            assert!(entries[3].is_dir());
+            assert_eq!(entries[3].hardlink(), None);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
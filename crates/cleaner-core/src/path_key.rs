@@ -0,0 +1,254 @@
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Component, Path, PathBuf, Prefix},
+};
+
+/**
+ * A path the way the tree indexes it. `.` components, trailing separators
+ * and the verbatim `\\?\` prefix Windows APIs hand out are dropped and `..`
+ * is folded lexically, so one location spelled two ways compares equal.
+ * Names compare case-insensitively where the filesystem usually does,
+ * on macOS and Windows.
+ */
+#[derive(Debug, Clone)]
+pub struct PathKey {
+    path: PathBuf,
+}
+
+impl PathKey {
+    pub fn new(path: &Path) -> Self {
+        let mut normalized = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::Prefix(prefix) => match prefix.kind() {
+                    Prefix::VerbatimDisk(disk) => {
+                        normalized.push(format!("{}:", disk as char));
+                    }
+                    Prefix::VerbatimUNC(server, share) => {
+                        let mut unc = OsString::from(r"\\");
+                        unc.push(server);
+                        unc.push(r"\");
+                        unc.push(share);
+                        normalized.push(unc);
+                    }
+                    _ => normalized.push(prefix.as_os_str()),
+                },
+                Component::RootDir => normalized.push(component),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    let folds = matches!(
+                        normalized.components().next_back(),
+                        Some(Component::Normal(_))
+                    );
+                    if folds {
+                        normalized.pop();
+                    } else if !normalized.has_root() {
+                        normalized.push(component);
+                    }
+                }
+                Component::Normal(name) => normalized.push(name),
+            }
+        }
+        Self { path: normalized }
+    }
+
+    /**
+     * like `new`, with symlinks in the parent directories resolved the way the
+     * scanner sees them, e.g. `/tmp/x` becomes `/private/tmp/x` on macOS.
+     * The last component is kept as is so a link is never swapped for its
+     * target. Falls back to the lexical form when the parent doesn't exist.
+     */
+    pub fn resolve(path: &Path) -> Self {
+        let resolved = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+                fs::canonicalize(parent).map(|parent| parent.join(name))
+            }
+            _ => fs::canonicalize(path),
+        };
+        Self::new(&resolved.unwrap_or_else(|_| path.to_path_buf()))
+    }
+
+    pub fn as_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn into_path_buf(self) -> PathBuf {
+        self.path
+    }
+
+    /**
+     * whether `base` is this path or one of its ancestors, names compared the
+     * way two keys are
+     */
+    pub fn starts_with(&self, base: &PathKey) -> bool {
+        let mut components = self.path.components();
+        base.path.components().all(|component| {
+            components
+                .next()
+                .is_some_and(|own| fold(own.as_os_str()) == fold(component.as_os_str()))
+        })
+    }
+
+    /**
+     * the names below the root, the ones matched against tree nodes
+     */
+    pub fn names(&self) -> impl Iterator<Item = &OsStr> {
+        self.path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name),
+                _ => None,
+            })
+    }
+}
+
+/**
+ * whether names differing in case are distinct files, the usual default of the
+ * platform for when the volume hasn't been probed
+ */
+pub const CASE_SENSITIVE: bool = !cfg!(any(target_os = "macos", target_os = "windows"));
+
+/**
+ * whether two file names refer to the same entry on this platform
+ */
+pub fn same_name(a: &OsStr, b: &OsStr) -> bool {
+    a == b || fold(a) == fold(b)
+}
+
+/**
+ * whether two file names refer to the same entry on a case-insensitive volume
+ */
+pub fn same_name_ignoring_case(a: &OsStr, b: &OsStr) -> bool {
+    a == b || fold_case(a) == fold_case(b)
+}
+
+fn fold(name: &OsStr) -> Cow<'_, OsStr> {
+    if CASE_SENSITIVE {
+        Cow::Borrowed(name)
+    } else {
+        fold_case(name)
+    }
+}
+
+pub(crate) fn fold_case(name: &OsStr) -> Cow<'_, OsStr> {
+    // names which aren't valid unicode only ever match byte for byte
+    match name.to_str() {
+        Some(name) if name.chars().any(char::is_uppercase) => {
+            Cow::Owned(OsString::from(name.to_lowercase()))
+        }
+        _ => Cow::Borrowed(name),
+    }
+}
+
+impl PartialEq for PathKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.path
+            .components()
+            .map(|component| fold(component.as_os_str()))
+            .eq(other
+                .path
+                .components()
+                .map(|component| fold(component.as_os_str())))
+    }
+}
+
+impl Eq for PathKey {}
+
+impl Hash for PathKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for component in self.path.components() {
+            fold(component.as_os_str()).hash(state);
+        }
+    }
+}
+
+impl From<&Path> for PathKey {
+    fn from(path: &Path) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<&PathBuf> for PathKey {
+    fn from(path: &PathBuf) -> Self {
+        Self::new(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[test]
+    fn test_spellings_share_a_key() {
+        let key = PathKey::new(Path::new("/Users/me"));
+        for spelling in [
+            "/Users/me/",
+            "/Users/./me",
+            "/Users/me/.",
+            "/Users/you/../me",
+        ] {
+            assert_eq!(PathKey::new(Path::new(spelling)), key, "{}", spelling);
+            assert_eq!(
+                PathKey::new(Path::new(spelling)).as_path(),
+                Path::new("/Users/me")
+            );
+        }
+        assert_eq!(PathKey::new(Path::new("/..")).as_path(), Path::new("/"));
+        assert_ne!(PathKey::new(Path::new("/Users/other")), key);
+    }
+
+    #[test]
+    fn test_case_follows_the_platform() {
+        let upper = PathKey::new(Path::new("/Users/ME"));
+        let lower = PathKey::new(Path::new("/users/me"));
+        assert_eq!(upper == lower, !CASE_SENSITIVE);
+        assert!(same_name(OsStr::new("me"), OsStr::new("me")));
+        assert!(same_name_ignoring_case(OsStr::new("ME"), OsStr::new("me")));
+    }
+
+    #[test]
+    fn test_starts_with_compares_keys() {
+        let base = PathKey::new(Path::new("/Users/me"));
+        assert!(PathKey::new(Path::new("/Users/me/docs")).starts_with(&base));
+        assert!(PathKey::new(Path::new("/Users/you/../me/")).starts_with(&base));
+        assert!(!PathKey::new(Path::new("/Users/meme")).starts_with(&base));
+        assert!(!PathKey::new(Path::new("/Users")).starts_with(&base));
+        assert_eq!(
+            PathKey::new(Path::new("/users/ME/docs")).starts_with(&base),
+            !CASE_SENSITIVE
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_verbatim_prefix_is_dropped() {
+        assert_eq!(
+            PathKey::new(Path::new(r"\\?\C:\Users\me\")).as_path(),
+            Path::new(r"C:\Users\me")
+        );
+        assert_eq!(
+            PathKey::new(Path::new(r"\\?\UNC\server\share\dir")).as_path(),
+            Path::new(r"\\server\share\dir")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_keeps_the_last_link() {
+        let dir = temp_path("path-key");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("real")).unwrap();
+        std::os::unix::fs::symlink(dir.join("real"), dir.join("alias")).unwrap();
+        let dir = fs::canonicalize(&dir).unwrap();
+
+        let resolved = PathKey::resolve(&dir.join("alias/file"));
+        assert_eq!(resolved.as_path(), dir.join("real/file"));
+        let link = PathKey::resolve(&dir.join("alias/"));
+        assert_eq!(link.as_path(), dir.join("alias"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
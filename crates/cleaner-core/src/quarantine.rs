@@ -0,0 +1,219 @@
+use std::{io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::long_path;
+
+/**
+ * extended attribute macOS tags every downloaded file with
+ */
+pub const QUARANTINE_ATTRIBUTE: &str = "com.apple.quarantine";
+
+/**
+ * alternate data stream holding the mark of the web on windows
+ */
+pub const ZONE_STREAM: &str = "Zone.Identifier";
+
+/**
+ * set in the quarantine flags once the user opened the file past Gatekeeper
+ */
+const USER_APPROVED: u32 = 0x0040;
+
+/**
+ * where a downloaded file came from, as recorded by the browser or the mail
+ * client which saved it
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Quarantine {
+    /// the app which downloaded it, e.g. Safari, macOS only
+    pub agent: Option<String>,
+    /// the address it was downloaded from, windows only
+    pub origin: Option<String>,
+    /// the security zone of the origin, 3 for the internet, windows only
+    pub zone: Option<u32>,
+    /// unix seconds of the download, macOS only
+    pub downloaded_at: Option<u64>,
+    /// whether it was ever opened, none where the platform doesn't record it
+    pub opened: Option<bool>,
+}
+
+impl Quarantine {
+    /**
+     * downloaded and known to have never been opened, the first candidates of a downloads triage
+     */
+    pub fn is_unopened(&self) -> bool {
+        self.opened == Some(false)
+    }
+}
+
+/**
+ * parse the macOS attribute value, `flags;timestamp;agent;event id` in hex
+ */
+pub fn parse_quarantine(value: &str) -> Option<Quarantine> {
+    let mut fields = value.trim_end_matches('\0').split(';');
+    let flags = u32::from_str_radix(fields.next()?, 16).ok()?;
+    let downloaded_at = fields
+        .next()
+        .and_then(|timestamp| u64::from_str_radix(timestamp, 16).ok());
+    let agent = fields
+        .next()
+        .filter(|agent| !agent.is_empty())
+        .map(str::to_string);
+
+    Some(Quarantine {
+        agent,
+        downloaded_at,
+        opened: Some(flags & USER_APPROVED != 0),
+        ..Quarantine::default()
+    })
+}
+
+/**
+ * parse the `[ZoneTransfer]` section of a windows zone identifier stream
+ */
+pub fn parse_zone_identifier(value: &str) -> Option<Quarantine> {
+    let mut quarantine = Quarantine::default();
+    let mut in_transfer = false;
+    for line in value.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_transfer = line.eq_ignore_ascii_case("[ZoneTransfer]");
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=').filter(|_| in_transfer) else {
+            continue;
+        };
+        match key.trim() {
+            "ZoneId" => quarantine.zone = value.trim().parse().ok(),
+            "HostUrl" => quarantine.origin = Some(value.trim().to_string()),
+            "ReferrerUrl" if quarantine.origin.is_none() => {
+                quarantine.origin = Some(value.trim().to_string())
+            }
+            _ => {}
+        }
+    }
+    quarantine.zone.map(|_| quarantine)
+}
+
+/**
+ * the quarantine of `path` itself, none when it wasn't downloaded or can't be read
+ */
+pub fn read(path: &Path) -> Option<Quarantine> {
+    read_platform(&long_path::extended(path))
+}
+
+/**
+ * remove the quarantine of a file the user keeps, true when there was one
+ */
+pub fn strip(path: &Path) -> io::Result<bool> {
+    strip_platform(&long_path::extended(path))
+}
+
+#[cfg(target_os = "macos")]
+fn read_platform(path: &Path) -> Option<Quarantine> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let name = CString::new(QUARANTINE_ATTRIBUTE).ok()?;
+    let mut value = [0u8; 256];
+    // SAFETY: both strings are nul terminated and the buffer outlives the call
+    let len = unsafe {
+        libc::getxattr(
+            path.as_ptr(),
+            name.as_ptr(),
+            value.as_mut_ptr().cast(),
+            value.len(),
+            0,
+            libc::XATTR_NOFOLLOW,
+        )
+    };
+    let len = usize::try_from(len).ok()?;
+    parse_quarantine(&String::from_utf8_lossy(&value[..len]))
+}
+
+#[cfg(target_os = "macos")]
+fn strip_platform(path: &Path) -> io::Result<bool> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    let name = CString::new(QUARANTINE_ATTRIBUTE).map_err(io::Error::other)?;
+    // SAFETY: both strings are nul terminated
+    let removed = unsafe { libc::removexattr(path.as_ptr(), name.as_ptr(), libc::XATTR_NOFOLLOW) };
+    if removed == 0 {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::ENOATTR) {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+#[cfg(windows)]
+fn zone_stream(path: &Path) -> std::path::PathBuf {
+    let mut stream = path.as_os_str().to_owned();
+    stream.push(":");
+    stream.push(ZONE_STREAM);
+    stream.into()
+}
+
+#[cfg(windows)]
+fn read_platform(path: &Path) -> Option<Quarantine> {
+    let value = std::fs::read_to_string(zone_stream(path)).ok()?;
+    parse_zone_identifier(&value)
+}
+
+#[cfg(windows)]
+fn strip_platform(path: &Path) -> io::Result<bool> {
+    match std::fs::remove_file(zone_stream(path)) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn read_platform(_path: &Path) -> Option<Quarantine> {
+    None
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn strip_platform(_path: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quarantine() {
+        let fresh =
+            parse_quarantine("0083;65a1b2c3;Safari;6F1E2D3C-0000-4000-8000-000000000000").unwrap();
+        assert_eq!(fresh.agent.as_deref(), Some("Safari"));
+        assert_eq!(fresh.downloaded_at, Some(0x65a1b2c3));
+        assert!(fresh.is_unopened());
+
+        let opened = parse_quarantine("00c1;65a1b2c3;Mail;\0").unwrap();
+        assert_eq!(opened.opened, Some(true));
+        assert!(parse_quarantine("not hex").is_none());
+    }
+
+    #[test]
+    fn test_parse_zone_identifier() {
+        let quarantine = parse_zone_identifier(
+            "[ZoneTransfer]\r\nZoneId=3\r\nReferrerUrl=https://example.com/\r\nHostUrl=https://example.com/setup.exe\r\n",
+        )
+        .unwrap();
+        assert_eq!(quarantine.zone, Some(3));
+        assert_eq!(
+            quarantine.origin.as_deref(),
+            Some("https://example.com/setup.exe")
+        );
+        assert_eq!(quarantine.opened, None);
+
+        assert!(parse_zone_identifier("[Other]\nZoneId=3\n").is_none());
+    }
+}
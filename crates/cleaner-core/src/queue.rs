@@ -0,0 +1,478 @@
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use specta::Type;
+use tokio::sync::Notify;
+
+use crate::tree::node::NodeRef;
+
+type TreeNode = NodeRef;
+
+/**
+ * a directory waiting for its listing, with the full path so workers don't walk up the tree
+ */
+pub type ScanItem = (PathBuf, TreeNode);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedDirectory {
+    pub path: PathBuf,
+    /// waiting in the on-demand lane
+    pub foreground: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerState {
+    pub worker: usize,
+    /// directory being listed, none while the worker waits for work
+    pub path: Option<PathBuf>,
+    pub millis: u64,
+}
+
+/**
+ * what the scanner is doing right now, to see which directory it is stuck on
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueSnapshot {
+    pub pending: usize,
+    pub next: Vec<QueuedDirectory>,
+    pub workers: Vec<WorkerState>,
+}
+
+/**
+ * Directories waiting to be scanned. The background lane holds the full
+ * volume scan, the foreground lane the folders the user asked for, and
+ * workers always drain the foreground first. A directory may sit in both
+ * lanes, the node `listed` flag makes sure only the first pop reads it.
+ */
+#[derive(Default)]
+pub struct ScanQueue {
+    background: VecDeque<ScanItem>,
+    foreground: VecDeque<ScanItem>,
+    /**
+     * prioritized subtrees, their ancestors and descendants go to the foreground
+     */
+    focus: Vec<PathBuf>,
+    /**
+     * subtrees the user skipped, listings still in flight below them are dropped
+     */
+    skipped: Vec<PathBuf>,
+    /**
+     * directories waiting from which the background lane is taken newest
+     * first, none to always go breadth first
+     */
+    limit: Option<usize>,
+}
+
+impl ScanQueue {
+    /**
+     * next directory to scan and whether it came from the foreground lane.
+     * Past the limit the background lane goes depth first: the subdirectories
+     * queued last are listed before their cousins, so the queue stops growing
+     * on a wide tree.
+     */
+    pub fn pop(&mut self) -> Option<(ScanItem, bool)> {
+        if let Some(item) = self.foreground.pop_front() {
+            return Some((item, true));
+        }
+        let deep = self.limit.is_some_and(|limit| self.len() >= limit);
+        let item = if deep {
+            self.background.pop_back()
+        } else {
+            self.background.pop_front()
+        };
+        item.map(|item| (item, false))
+    }
+
+    /**
+     * the directories waiting from which the scan goes depth first, applies
+     * from the next pop
+     */
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    pub fn push(&mut self, item: ScanItem) {
+        self.background.push_back(item);
+    }
+
+    /**
+     * queue the subdirectories of `parent`, a foreground directory keeps its whole
+     * subtree in the foreground and a pending target pulls its ancestors along
+     */
+    pub fn push_children(&mut self, parent: &Path, children: Vec<TreeNode>, urgent: bool) {
+        for child in children {
+            let Ok(name) = child.read().map(|node| node.path.clone()) else {
+                continue;
+            };
+
+            let path = parent.join(name);
+            if self.skipped.iter().any(|skipped| path.starts_with(skipped)) {
+                continue;
+            }
+
+            let focused = self
+                .focus
+                .iter()
+                .any(|target| target.starts_with(&path) || path.starts_with(target));
+            if urgent || focused {
+                self.foreground.push_back((path, child));
+            } else {
+                self.background.push_back((path, child));
+            }
+        }
+    }
+
+    /**
+     * Scan `target` ahead of the background work. `found` is the deepest node
+     * of the target path already in the tree, queued again in the foreground
+     * when it hasn't been listed so the path down to the target follows.
+     */
+    pub fn focus(&mut self, target: PathBuf, found: ScanItem) {
+        let (found_path, node) = found;
+        if node.read().is_ok_and(|node| !node.listed) {
+            self.foreground.push_back((found_path, node));
+        }
+        self.prioritize(target);
+    }
+
+    /**
+     * move every queued directory under `path` to the front of the queue and keep
+     * its subdirectories there, returns how many were moved
+     */
+    pub fn prioritize(&mut self, path: PathBuf) -> usize {
+        let under = |(queued, _): &ScanItem| queued.starts_with(&path);
+        let (mut front, foreground): (VecDeque<_>, VecDeque<_>) =
+            self.foreground.drain(..).partition(under);
+        let (background_under, background): (VecDeque<_>, VecDeque<_>) =
+            self.background.drain(..).partition(under);
+        front.extend(background_under);
+        let moved = front.len();
+
+        front.extend(foreground);
+        self.foreground = front;
+        self.background = background;
+
+        if !self.focus.contains(&path) {
+            self.focus.push(path);
+        }
+        moved
+    }
+
+    /**
+     * drop every queued directory under `path` and keep it from being queued again,
+     * returns how many were dropped
+     */
+    pub fn skip(&mut self, path: PathBuf) -> usize {
+        let before = self.len();
+        self.background
+            .retain(|(queued, _)| !queued.starts_with(&path));
+        self.foreground
+            .retain(|(queued, _)| !queued.starts_with(&path));
+        self.focus.retain(|target| !target.starts_with(&path));
+        if !self.skipped.contains(&path) {
+            self.skipped.push(path);
+        }
+        before - self.len()
+    }
+
+    /**
+     * the next `limit` directories in the order workers will take them
+     */
+    pub fn peek(&self, limit: usize) -> Vec<QueuedDirectory> {
+        let foreground = self.foreground.iter().map(|item| (item, true));
+        let background = self.background.iter().map(|item| (item, false));
+        let background: Vec<_> = if self.limit.is_some_and(|limit| self.len() >= limit) {
+            background.rev().take(limit).collect()
+        } else {
+            background.take(limit).collect()
+        };
+        foreground
+            .chain(background)
+            .take(limit)
+            .map(|((path, _), foreground)| QueuedDirectory {
+                path: path.clone(),
+                foreground,
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.background.len() + self.foreground.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.background.clear();
+        self.foreground.clear();
+        self.focus.clear();
+        self.skipped.clear();
+    }
+}
+
+/**
+ * the directory each worker is listing and since when
+ */
+#[derive(Debug, Default)]
+pub struct ActiveWorkers {
+    slots: Vec<Option<Listing>>,
+}
+
+#[derive(Debug, Clone)]
+struct Listing {
+    path: PathBuf,
+    started: Instant,
+    /// when the listing last got an entry
+    beat: Instant,
+    /// the watchdog already reported it
+    reported: bool,
+    abandon: Arc<Notify>,
+}
+
+/**
+ * a listing which got no entry for a while, `abandon` makes its worker give up on it
+ */
+#[derive(Debug, Clone)]
+pub struct StalledListing {
+    pub worker: usize,
+    pub path: PathBuf,
+    pub idle: Duration,
+    pub abandon: Arc<Notify>,
+}
+
+impl ActiveWorkers {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            slots: vec![None; workers],
+        }
+    }
+
+    /**
+     * record that `worker` lists `path`, the returned signal fires when the
+     * watchdog gives up on this listing
+     */
+    pub fn begin(&mut self, worker: usize, path: PathBuf) -> Arc<Notify> {
+        let abandon = Arc::new(Notify::new());
+        if let Some(slot) = self.slots.get_mut(worker) {
+            let now = Instant::now();
+            *slot = Some(Listing {
+                path,
+                started: now,
+                beat: now,
+                reported: false,
+                abandon: abandon.clone(),
+            });
+        }
+        abandon
+    }
+
+    /**
+     * the listing of `worker` made progress
+     */
+    pub fn beat(&mut self, worker: usize) {
+        if let Some(Some(listing)) = self.slots.get_mut(worker) {
+            listing.beat = Instant::now();
+            listing.reported = false;
+        }
+    }
+
+    pub fn end(&mut self, worker: usize) {
+        if let Some(slot) = self.slots.get_mut(worker) {
+            *slot = None;
+        }
+    }
+
+    /**
+     * listings without progress for `after`, each reported once until it moves again
+     */
+    pub fn stalled(&mut self, after: Duration) -> Vec<StalledListing> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(worker, slot)| {
+                let listing = slot.as_mut()?;
+                let idle = listing.beat.elapsed();
+                if listing.reported || idle < after {
+                    return None;
+                }
+                listing.reported = true;
+                Some(StalledListing {
+                    worker,
+                    path: listing.path.clone(),
+                    idle,
+                    abandon: listing.abandon.clone(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn states(&self) -> Vec<WorkerState> {
+        self.slots
+            .iter()
+            .enumerate()
+            .map(|(worker, slot)| WorkerState {
+                worker,
+                path: slot.as_ref().map(|listing| listing.path.clone()),
+                millis: slot
+                    .as_ref()
+                    .map_or(0, |listing| listing.started.elapsed().as_millis() as u64),
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::node::Node;
+    use std::ffi::OsString;
+
+    fn dir(name: &str) -> TreeNode {
+        NodeRef::new(Node::new(OsString::from(name), true, false))
+    }
+
+    fn popped(queue: &mut ScanQueue) -> Vec<(PathBuf, bool)> {
+        std::iter::from_fn(|| queue.pop())
+            .map(|((path, _), urgent)| (path, urgent))
+            .collect()
+    }
+
+    #[test]
+    fn test_focus_pulls_ancestors_forward() {
+        let mut queue = ScanQueue::default();
+        let root = dir("/");
+        root.write().unwrap().listed = true;
+        queue.focus(PathBuf::from("/home/me"), (PathBuf::from("/"), root));
+
+        queue.push_children(Path::new("/"), vec![dir("etc"), dir("home")], false);
+        assert_eq!(
+            popped(&mut queue),
+            vec![
+                (PathBuf::from("/home"), true),
+                (PathBuf::from("/etc"), false)
+            ]
+        );
+
+        queue.push_children(Path::new("/home"), vec![dir("me")], true);
+        assert_eq!(popped(&mut queue), vec![(PathBuf::from("/home/me"), true)]);
+    }
+
+    #[test]
+    fn test_peek_lists_foreground_first() {
+        let mut queue = ScanQueue::default();
+        queue.push_children(Path::new("/"), vec![dir("a")], false);
+        queue.push_children(Path::new("/"), vec![dir("b")], true);
+
+        let next = queue.peek(10);
+        assert_eq!(next.len(), 2);
+        assert_eq!(
+            next[0],
+            QueuedDirectory {
+                path: PathBuf::from("/b"),
+                foreground: true,
+            }
+        );
+        assert_eq!(queue.peek(1).len(), 1);
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_depth_first_past_limit() {
+        let mut queue = ScanQueue::default();
+        queue.set_limit(Some(3));
+        queue.push_children(Path::new("/"), vec![dir("a"), dir("b"), dir("c")], false);
+        assert_eq!(queue.peek(1)[0].path, PathBuf::from("/c"));
+        assert_eq!(queue.pop().unwrap().0.0, PathBuf::from("/c"));
+
+        // below the limit again, the oldest goes first
+        assert_eq!(
+            popped(&mut queue),
+            vec![(PathBuf::from("/a"), false), (PathBuf::from("/b"), false)]
+        );
+    }
+
+    #[test]
+    fn test_skip_drops_subtree() {
+        let mut queue = ScanQueue::default();
+        queue.push_children(Path::new("/"), vec![dir("a"), dir("b")], false);
+        queue.push_children(Path::new("/b"), vec![dir("c")], true);
+
+        assert_eq!(queue.skip(PathBuf::from("/b")), 2);
+        queue.push_children(Path::new("/b/c"), vec![dir("d")], true);
+        assert_eq!(popped(&mut queue), vec![(PathBuf::from("/a"), false)]);
+    }
+
+    #[test]
+    fn test_prioritize_moves_subtree_to_front() {
+        let mut queue = ScanQueue::default();
+        queue.push_children(Path::new("/"), vec![dir("a")], true);
+        queue.push_children(Path::new("/"), vec![dir("b"), dir("c")], false);
+        queue.push_children(Path::new("/c"), vec![dir("d")], false);
+
+        assert_eq!(queue.prioritize(PathBuf::from("/c")), 2);
+        queue.push_children(Path::new("/c/d"), vec![dir("e")], false);
+        assert_eq!(
+            popped(&mut queue),
+            vec![
+                (PathBuf::from("/c"), true),
+                (PathBuf::from("/c/d"), true),
+                (PathBuf::from("/a"), true),
+                (PathBuf::from("/c/d/e"), true),
+                (PathBuf::from("/b"), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_focus_moves_queued_descendants() {
+        let mut queue = ScanQueue::default();
+        queue.push_children(Path::new("/"), vec![dir("a"), dir("b")], false);
+        queue.push_children(Path::new("/b"), vec![dir("c")], false);
+
+        let b = dir("b");
+        b.write().unwrap().listed = true;
+        queue.focus(PathBuf::from("/b"), (PathBuf::from("/b"), b));
+        assert_eq!(
+            popped(&mut queue),
+            vec![
+                (PathBuf::from("/b"), true),
+                (PathBuf::from("/b/c"), true),
+                (PathBuf::from("/a"), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stalled_listing_reported_once() {
+        let mut active = ActiveWorkers::new(2);
+        active.begin(0, PathBuf::from("/mnt/nfs"));
+        active.begin(1, PathBuf::from("/home"));
+
+        let stalled = active.stalled(Duration::ZERO);
+        assert_eq!(stalled.len(), 2);
+        assert_eq!(stalled[0].path, PathBuf::from("/mnt/nfs"));
+        assert!(active.stalled(Duration::ZERO).is_empty());
+
+        // progress makes it reportable again, a finished listing never is
+        active.beat(0);
+        active.end(1);
+        let stalled = active.stalled(Duration::ZERO);
+        assert_eq!(stalled.len(), 1);
+        assert_eq!(stalled[0].worker, 0);
+        assert!(active.stalled(Duration::from_secs(3600)).is_empty());
+    }
+}
@@ -0,0 +1,851 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File, FileTimes, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use rusqlite::{Connection, OptionalExtension, Row, params, types::Type as SqlType};
+use serde::Serialize;
+use specta::Type;
+use tracing::debug;
+
+use crate::{
+    database::{Database, to_io},
+    long_path, lossless,
+    manifest::Manifest,
+    models::now_secs,
+    mover::{
+        CHUNK_SIZE, MoveProgress, MoveReport, MoveStage, PROGRESS_INTERVAL, bytes_below, copy_link,
+        ensure_absent, ensure_apart,
+    },
+    space,
+    throttle::Throttle,
+};
+
+/**
+ * how often a copy writes down how far it got, what it copied since is
+ * copied again after a crash
+ */
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+
+/**
+ * appended to the name of a file while it is copied, it only gets its name
+ * once it is complete
+ */
+pub const PARTIAL_SUFFIX: &str = ".cleaner-partial";
+
+/**
+ * a copy which stopped before it was done, `resume` takes it from where it got
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InterruptedCopy {
+    pub id: i64,
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub from: PathBuf,
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub to: PathBuf,
+    pub total_bytes: u64,
+    pub copied_bytes: u64,
+    /// files and links copied completely
+    pub completed: usize,
+    /// unix seconds
+    pub started: u64,
+    /// when it last wrote down how far it got
+    pub updated: u64,
+    /// why it stopped, none when the app quit in the middle of it
+    pub error: Option<String>,
+}
+
+/**
+ * how far a copy got, as written down at its last checkpoint
+ */
+struct Checkpoint {
+    id: i64,
+    from: PathBuf,
+    to: PathBuf,
+    total_bytes: u64,
+    copied_bytes: u64,
+    /// relative to `from`
+    completed: HashSet<PathBuf>,
+    /// completed since the last checkpoint
+    unsaved: Vec<PathBuf>,
+    /// the file being copied, with the bytes of it synced to the disk
+    partial: Option<(PathBuf, u64)>,
+}
+
+impl Checkpoint {
+    fn complete(&mut self, relative: &Path) {
+        self.partial = None;
+        if self.completed.insert(relative.to_path_buf()) {
+            self.unsaved.push(relative.to_path_buf());
+        }
+    }
+}
+
+/**
+ * The checkpoints of the copies not done yet, in the app database next to
+ * the audit log. A copy which finishes leaves none behind. Paths are kept
+ * as their exact bytes.
+ */
+#[derive(Clone)]
+pub struct CheckpointStore {
+    conn: Arc<Mutex<Connection>>,
+    /// the copies of this process still going, never resumed twice
+    running: Arc<Mutex<HashSet<i64>>>,
+}
+
+/**
+ * a copy marked as running until it is dropped
+ */
+struct Claim<'a> {
+    store: &'a CheckpointStore,
+    id: i64,
+}
+
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        self.store
+            .running
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.id);
+    }
+}
+
+impl CheckpointStore {
+    pub fn new(database: &Database) -> io::Result<Self> {
+        let shared = database.connection();
+        let conn = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS copy_checkpoints (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                source BLOB NOT NULL,
+                destination BLOB NOT NULL,
+                total_bytes INTEGER NOT NULL,
+                copied_bytes INTEGER NOT NULL,
+                partial BLOB,
+                partial_offset INTEGER,
+                started INTEGER NOT NULL,
+                updated INTEGER NOT NULL,
+                error TEXT
+            );
+            CREATE TABLE IF NOT EXISTS copy_checkpoint_files (
+                checkpoint INTEGER NOT NULL,
+                path BLOB NOT NULL,
+                PRIMARY KEY (checkpoint, path)
+            );",
+        )
+        .map_err(to_io)?;
+        Self::store_bytes(&conn).map_err(to_io)?;
+
+        Ok(Self {
+            conn: Arc::clone(&shared),
+            running: Arc::default(),
+        })
+    }
+
+    pub fn in_memory() -> io::Result<Self> {
+        Self::new(&Database::in_memory()?)
+    }
+
+    /**
+     * Checkpoints written before the paths were kept as bytes hold their
+     * lossy text. The text of a UTF-8 path is its bytes, a lossy one
+     * stays as wrong as it was.
+     */
+    fn store_bytes(conn: &Connection) -> rusqlite::Result<()> {
+        let text: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('copy_checkpoints') WHERE name = 'source' AND type = 'TEXT'",
+            [],
+            |row| row.get(0),
+        )?;
+        if text == 0 {
+            return Ok(());
+        }
+
+        debug!("keep the checkpointed paths as bytes");
+        conn.execute_batch(
+            "BEGIN;
+            ALTER TABLE copy_checkpoints RENAME TO copy_checkpoints_text;
+            ALTER TABLE copy_checkpoint_files RENAME TO copy_checkpoint_files_text;
+            CREATE TABLE copy_checkpoints (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                source BLOB NOT NULL,
+                destination BLOB NOT NULL,
+                total_bytes INTEGER NOT NULL,
+                copied_bytes INTEGER NOT NULL,
+                partial BLOB,
+                partial_offset INTEGER,
+                started INTEGER NOT NULL,
+                updated INTEGER NOT NULL,
+                error TEXT
+            );
+            CREATE TABLE copy_checkpoint_files (
+                checkpoint INTEGER NOT NULL,
+                path BLOB NOT NULL,
+                PRIMARY KEY (checkpoint, path)
+            );
+            INSERT INTO copy_checkpoints
+                SELECT id, CAST(source AS BLOB), CAST(destination AS BLOB), total_bytes,
+                    copied_bytes, CAST(partial AS BLOB), partial_offset, started, updated, error
+                FROM copy_checkpoints_text;
+            INSERT INTO copy_checkpoint_files
+                SELECT checkpoint, CAST(path AS BLOB) FROM copy_checkpoint_files_text;
+            DROP TABLE copy_checkpoints_text;
+            DROP TABLE copy_checkpoint_files_text;
+            COMMIT;",
+        )
+    }
+
+    fn claim(&self, id: i64) -> io::Result<Claim<'_>> {
+        let claimed = self
+            .running
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id);
+        if !claimed {
+            return Err(io::Error::new(
+                io::ErrorKind::ResourceBusy,
+                format!("operation {} is still running", id),
+            ));
+        }
+        Ok(Claim { store: self, id })
+    }
+
+    fn is_running(&self, id: i64) -> bool {
+        self.running
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(&id)
+    }
+
+    fn begin(&self, from: &Path, to: &Path, total_bytes: u64) -> io::Result<Checkpoint> {
+        let now = now_secs() as i64;
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "INSERT INTO copy_checkpoints
+                (source, destination, total_bytes, copied_bytes, started, updated)
+                VALUES (?1, ?2, ?3, 0, ?4, ?4)",
+            params![
+                lossless::bytes(from),
+                lossless::bytes(to),
+                total_bytes as i64,
+                now
+            ],
+        )
+        .map_err(to_io)?;
+        Ok(Checkpoint {
+            id: conn.last_insert_rowid(),
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+            total_bytes,
+            copied_bytes: 0,
+            completed: HashSet::new(),
+            unsaved: vec![],
+            partial: None,
+        })
+    }
+
+    fn load(&self, id: i64) -> io::Result<Checkpoint> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let row = conn
+            .query_row(
+                "SELECT source, destination, total_bytes, copied_bytes, partial, partial_offset
+                    FROM copy_checkpoints WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok((
+                        stored_path(row, 0)?,
+                        stored_path(row, 1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, i64>(3)?,
+                        match row.get_ref(4)?.as_blob_or_null()? {
+                            Some(_) => Some(stored_path(row, 4)?),
+                            None => None,
+                        },
+                        row.get::<_, Option<i64>>(5)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(to_io)?;
+        let Some((from, to, total_bytes, copied_bytes, partial, offset)) = row else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no interrupted operation {}", id),
+            ));
+        };
+        let completed = conn
+            .prepare("SELECT path FROM copy_checkpoint_files WHERE checkpoint = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![id], |row| stored_path(row, 0))?
+                    .collect::<rusqlite::Result<HashSet<PathBuf>>>()
+            })
+            .map_err(to_io)?;
+        Ok(Checkpoint {
+            id,
+            from,
+            to,
+            total_bytes: total_bytes as u64,
+            copied_bytes: copied_bytes as u64,
+            completed,
+            unsaved: vec![],
+            partial: partial
+                .zip(offset)
+                .map(|(path, offset)| (path, offset as u64)),
+        })
+    }
+
+    /**
+     * write down how far `checkpoint` got, the files completed since the
+     * last time with it
+     */
+    fn save(&self, checkpoint: &mut Checkpoint) -> io::Result<()> {
+        let mut conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let tx = conn.transaction().map_err(to_io)?;
+        for path in &checkpoint.unsaved {
+            tx.execute(
+                "INSERT OR IGNORE INTO copy_checkpoint_files (checkpoint, path) VALUES (?1, ?2)",
+                params![checkpoint.id, lossless::bytes(path)],
+            )
+            .map_err(to_io)?;
+        }
+        let (partial, offset) = checkpoint
+            .partial
+            .as_ref()
+            .map(|(path, offset)| (lossless::bytes(path), *offset as i64))
+            .unzip();
+        tx.execute(
+            "UPDATE copy_checkpoints
+                SET copied_bytes = ?2, partial = ?3, partial_offset = ?4, updated = ?5
+                WHERE id = ?1",
+            params![
+                checkpoint.id,
+                checkpoint.copied_bytes as i64,
+                partial,
+                offset,
+                now_secs() as i64
+            ],
+        )
+        .map_err(to_io)?;
+        tx.commit().map_err(to_io)?;
+        checkpoint.unsaved.clear();
+        Ok(())
+    }
+
+    fn fail(&self, id: i64, error: &str) -> io::Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "UPDATE copy_checkpoints SET error = ?2, updated = ?3 WHERE id = ?1",
+            params![id, error, now_secs() as i64],
+        )
+        .map_err(to_io)?;
+        Ok(())
+    }
+
+    /**
+     * forget the files of `id` copied so far, a resume copies all of them again
+     */
+    fn restart(&self, id: i64) -> io::Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "DELETE FROM copy_checkpoint_files WHERE checkpoint = ?1",
+            params![id],
+        )
+        .map_err(to_io)?;
+        conn.execute(
+            "UPDATE copy_checkpoints
+                SET copied_bytes = 0, partial = NULL, partial_offset = NULL
+                WHERE id = ?1",
+            params![id],
+        )
+        .map_err(to_io)?;
+        Ok(())
+    }
+
+    fn finish(&self, id: i64) -> io::Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "DELETE FROM copy_checkpoint_files WHERE checkpoint = ?1",
+            params![id],
+        )
+        .map_err(to_io)?;
+        conn.execute("DELETE FROM copy_checkpoints WHERE id = ?1", params![id])
+            .map_err(to_io)?;
+        Ok(())
+    }
+
+    /**
+     * the copies which stopped before they were done, the latest first
+     */
+    pub fn interrupted(&self) -> io::Result<Vec<InterruptedCopy>> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut statement = conn
+            .prepare(
+                "SELECT id, source, destination, total_bytes, copied_bytes, started, updated,
+                    error,
+                    (SELECT COUNT(*) FROM copy_checkpoint_files
+                        WHERE checkpoint = copy_checkpoints.id)
+                    FROM copy_checkpoints ORDER BY id DESC",
+            )
+            .map_err(to_io)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok(InterruptedCopy {
+                    id: row.get(0)?,
+                    from: stored_path(row, 1)?,
+                    to: stored_path(row, 2)?,
+                    total_bytes: row.get::<_, i64>(3)? as u64,
+                    copied_bytes: row.get::<_, i64>(4)? as u64,
+                    started: row.get::<_, i64>(5)? as u64,
+                    updated: row.get::<_, i64>(6)? as u64,
+                    error: row.get(7)?,
+                    completed: row.get::<_, i64>(8)? as usize,
+                })
+            })
+            .map_err(to_io)?;
+        let copies = rows.collect::<rusqlite::Result<Vec<_>>>().map_err(to_io)?;
+        Ok(copies
+            .into_iter()
+            .filter(|copy| !self.is_running(copy.id))
+            .collect())
+    }
+
+    /**
+     * Give up on the interrupted copy `id`. The file it was copying goes,
+     * the files it copied completely stay where they are.
+     */
+    pub fn discard(&self, id: i64) -> io::Result<()> {
+        let _claim = self.claim(id)?;
+        let checkpoint = self.load(id)?;
+        if let Some((relative, _)) = &checkpoint.partial {
+            let partial = partial_path(&under(&checkpoint.to, relative));
+            match fs::remove_file(long_path::extended(&partial)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+        self.finish(id)
+    }
+}
+
+/**
+ * the path kept as bytes in column `index` of `row`
+ */
+fn stored_path(row: &Row, index: usize) -> rusqlite::Result<PathBuf> {
+    let bytes = row.get_ref(index)?.as_blob()?;
+    lossless::from_bytes(bytes).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            index,
+            SqlType::Blob,
+            "the stored path is not a path of this system".into(),
+        )
+    })
+}
+
+fn under(base: &Path, relative: &Path) -> PathBuf {
+    if relative.as_os_str().is_empty() {
+        base.to_path_buf()
+    } else {
+        base.join(relative)
+    }
+}
+
+/**
+ * where the copy of `to` is written until it is complete
+ */
+fn partial_path(to: &Path) -> PathBuf {
+    let mut name = to.file_name().unwrap_or_default().to_os_string();
+    name.push(PARTIAL_SUFFIX);
+    to.with_file_name(name)
+}
+
+/**
+ * one run of a checkpointed copy, a fresh one or a resumed one
+ */
+struct Copier<'a> {
+    store: &'a CheckpointStore,
+    checkpoint: Checkpoint,
+    throttle: &'a mut Throttle,
+    observe: &'a mut dyn FnMut(&MoveProgress),
+    progress: MoveProgress,
+    saved: Instant,
+    reported: Instant,
+}
+
+impl Copier<'_> {
+    fn save(&mut self) -> io::Result<()> {
+        self.checkpoint.copied_bytes = self.progress.copied_bytes;
+        self.store.save(&mut self.checkpoint)?;
+        self.saved = Instant::now();
+        Ok(())
+    }
+
+    fn count(&mut self, bytes: u64) {
+        self.throttle.pace(bytes);
+        self.progress.copied_bytes += bytes;
+        if self.reported.elapsed() >= PROGRESS_INTERVAL {
+            (self.observe)(&self.progress);
+            self.reported = Instant::now();
+        }
+    }
+
+    /**
+     * copy the entry at `relative` with everything below it, what a run
+     * before copied completely is left as it is
+     */
+    fn entry(&mut self, relative: &Path) -> io::Result<()> {
+        let from = under(&self.checkpoint.from, relative);
+        let to = under(&self.checkpoint.to, relative);
+        let metadata = fs::symlink_metadata(long_path::extended(&from))?;
+        if metadata.is_dir() {
+            match fs::create_dir(long_path::extended(&to)) {
+                // made by a run before
+                Err(err)
+                    if err.kind() == io::ErrorKind::AlreadyExists
+                        && fs::metadata(long_path::extended(&to)).is_ok_and(|to| to.is_dir()) => {}
+                created => created?,
+            }
+            for entry in fs::read_dir(long_path::extended(&from))? {
+                let name = entry?.file_name();
+                self.entry(&relative.join(&name))?;
+            }
+            return Ok(());
+        }
+
+        let exists = fs::symlink_metadata(long_path::extended(&to)).is_ok();
+        if exists && self.checkpoint.completed.contains(relative) {
+            return Ok(());
+        }
+        if metadata.is_symlink() {
+            if exists {
+                // written by a run which stopped before its checkpoint
+                fs::remove_file(long_path::extended(&to))?;
+            }
+            copy_link(&from, &to)?;
+        } else {
+            self.file(relative, &from, &to, &metadata)?;
+        }
+        self.checkpoint.complete(relative);
+        if self.saved.elapsed() >= CHECKPOINT_INTERVAL {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Copy one file under its partial name, from where a run before got
+     * when it copied the same file. Only bytes synced to the disk are
+     * written down as copied. The file gets its name once it is complete.
+     */
+    fn file(
+        &mut self,
+        relative: &Path,
+        from: &Path,
+        to: &Path,
+        metadata: &fs::Metadata,
+    ) -> io::Result<()> {
+        let partial = partial_path(to);
+        let recorded = match &self.checkpoint.partial {
+            Some((path, offset)) if path == relative => Some(*offset),
+            _ => None,
+        };
+        // a partial file shorter than written down lost what wasn't synced
+        let resumed = recorded.and_then(|offset| {
+            fs::metadata(long_path::extended(&partial))
+                .ok()
+                .map(|partial| partial.len().min(offset))
+        });
+        let lost = recorded.unwrap_or(0) - resumed.unwrap_or(0);
+        self.progress.copied_bytes = self.progress.copied_bytes.saturating_sub(lost);
+
+        let mut source = File::open(long_path::extended(from))?;
+        let (mut copy, mut written) = match resumed {
+            Some(offset) => {
+                let mut copy = OpenOptions::new()
+                    .write(true)
+                    .open(long_path::extended(&partial))?;
+                copy.set_len(offset)?;
+                copy.seek(SeekFrom::Start(offset))?;
+                source.seek(SeekFrom::Start(offset))?;
+                (copy, offset)
+            }
+            None => (File::create(long_path::extended(&partial))?, 0),
+        };
+        self.checkpoint.partial = Some((relative.to_path_buf(), written));
+
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        loop {
+            let read = source.read(&mut chunk)?;
+            if read == 0 {
+                break;
+            }
+            copy.write_all(&chunk[..read])?;
+            written += read as u64;
+            self.count(read as u64);
+            if self.saved.elapsed() >= CHECKPOINT_INTERVAL {
+                copy.sync_data()?;
+                self.checkpoint.partial = Some((relative.to_path_buf(), written));
+                self.save()?;
+            }
+        }
+        copy.set_permissions(metadata.permissions())?;
+        if let Ok(modified) = metadata.modified() {
+            copy.set_times(FileTimes::new().set_modified(modified))?;
+        }
+        copy.sync_all()?;
+        drop(copy);
+        fs::rename(long_path::extended(&partial), long_path::extended(to))
+    }
+}
+
+/**
+ * Run the copy of `checkpoint` to its end, then compare the copy with the
+ * source. A copy which stops keeps its checkpoint and what it copied, the
+ * error tells the id to resume it with. One which doesn't match its source
+ * is copied all over again when resumed.
+ */
+fn run(
+    store: &CheckpointStore,
+    checkpoint: Checkpoint,
+    throttle: &mut Throttle,
+    observe: &mut dyn FnMut(&MoveProgress),
+) -> io::Result<MoveReport> {
+    let id = checkpoint.id;
+    let _claim = store.claim(id)?;
+    let progress = MoveProgress {
+        from: checkpoint.from.clone(),
+        to: checkpoint.to.clone(),
+        stage: MoveStage::Copying,
+        cross_device: false,
+        total_bytes: checkpoint.total_bytes,
+        copied_bytes: checkpoint.copied_bytes,
+    };
+    observe(&progress);
+    let mut copier = Copier {
+        store,
+        checkpoint,
+        throttle,
+        observe,
+        progress,
+        saved: Instant::now(),
+        reported: Instant::now(),
+    };
+
+    // the file a run before stopped in goes first, before another one takes its place
+    let stopped_in = copier
+        .checkpoint
+        .partial
+        .as_ref()
+        .map(|(relative, _)| relative.clone())
+        .filter(|relative| long_path::extended(&under(&copier.checkpoint.from, relative)).exists());
+    let resumed = stopped_in.map_or(Ok(()), |relative| copier.entry(&relative));
+    let copied = resumed.and_then(|()| copier.entry(Path::new("")));
+    let stopped = match copied {
+        Ok(()) => {
+            copier.progress.stage = MoveStage::Verifying;
+            (copier.observe)(&copier.progress);
+            let (from, to) = (&copier.checkpoint.from, &copier.checkpoint.to);
+            let checked = Manifest::record(from).and_then(|recorded| {
+                recorded.verify(&Manifest::record(to)?).map_err(|mismatch| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{} doesn't match its source, {}", to.display(), mismatch),
+                    )
+                })
+            });
+            if checked
+                .as_ref()
+                .is_err_and(|err| err.kind() == io::ErrorKind::InvalidData)
+            {
+                let _ = store.restart(id);
+            }
+            checked.err()
+        }
+        Err(err) => {
+            // the last bytes synced, an unplugged drive may have lost the rest
+            let _ = store.save(&mut copier.checkpoint);
+            Some(err)
+        }
+    };
+    if let Some(err) = stopped {
+        let _ = store.fail(id, &err.to_string());
+        return Err(io::Error::new(
+            err.kind(),
+            format!("{}, resume it as operation {}", err, id),
+        ));
+    }
+
+    store.finish(id)?;
+    copier.progress.stage = MoveStage::Done;
+    (copier.observe)(&copier.progress);
+    let (from, to) = (&copier.checkpoint.from, &copier.checkpoint.to);
+    let mut report = MoveReport::new(from, to);
+    report.bytes = copier.progress.copied_bytes;
+    report.transferred.push(from.clone());
+    Ok(report)
+}
+
+/**
+ * Copy `from` to `to`, which must not exist, like `mover::copy_path`, but
+ * writing down how far it got in `store` every `CHECKPOINT_INTERVAL`. A
+ * crash, a reboot or an unplugged destination leaves the copy to `resume`.
+ * Blocking.
+ */
+pub fn copy_resumable(
+    store: &CheckpointStore,
+    from: &Path,
+    to: &Path,
+    throttle: &mut Throttle,
+    observe: &mut dyn FnMut(&MoveProgress),
+) -> io::Result<MoveReport> {
+    ensure_absent(to)?;
+    ensure_apart(from, to)?;
+    let total_bytes = bytes_below(from)?;
+    space::ensure(to, total_bytes)?;
+    let checkpoint = store.begin(from, to, total_bytes)?;
+    run(store, checkpoint, throttle, observe)
+}
+
+/**
+ * continue the interrupted copy `id` from its last checkpoint, once its
+ * destination has room for the bytes still to copy. Blocking.
+ */
+pub fn resume(
+    store: &CheckpointStore,
+    id: i64,
+    throttle: &mut Throttle,
+    observe: &mut dyn FnMut(&MoveProgress),
+) -> io::Result<MoveReport> {
+    let checkpoint = store.load(id)?;
+    let remaining = checkpoint
+        .total_bytes
+        .saturating_sub(checkpoint.copied_bytes);
+    space::ensure(&checkpoint.to, remaining)?;
+    run(store, checkpoint, throttle, observe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[test]
+    fn test_interrupted_copy_resumed() {
+        let root = temp_path("resume");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("photos/2024")).unwrap();
+        fs::write(root.join("photos/2024/a.jpg"), vec![1u8; 3 * CHUNK_SIZE]).unwrap();
+        fs::write(root.join("photos/2024/b.jpg"), vec![2u8; CHUNK_SIZE + 7]).unwrap();
+        fs::write(root.join("photos/notes.txt"), "notes").unwrap();
+        let store = CheckpointStore::in_memory().unwrap();
+
+        // as left by a run which stopped halfway through a.jpg
+        let (from, to) = (root.join("photos"), root.join("backup"));
+        let mut checkpoint = store
+            .begin(&from, &to, bytes_below(&from).unwrap())
+            .unwrap();
+        fs::create_dir_all(to.join("2024")).unwrap();
+        fs::write(to.join("notes.txt"), "notes").unwrap();
+        fs::write(
+            to.join(format!("2024/a.jpg{}", PARTIAL_SUFFIX)),
+            vec![1u8; 2 * CHUNK_SIZE + 3],
+        )
+        .unwrap();
+        checkpoint.complete(Path::new("notes.txt"));
+        checkpoint.partial = Some((PathBuf::from("2024/a.jpg"), 2 * CHUNK_SIZE as u64));
+        checkpoint.copied_bytes = 5 + 2 * CHUNK_SIZE as u64;
+        store.save(&mut checkpoint).unwrap();
+        store.fail(checkpoint.id, "the drive is gone").unwrap();
+
+        let interrupted = store.interrupted().unwrap();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].completed, 1);
+        assert_eq!(interrupted[0].error.as_deref(), Some("the drive is gone"));
+
+        let mut throttle = Throttle::unlimited();
+        let report = resume(&store, checkpoint.id, &mut throttle, &mut |_| {}).unwrap();
+        assert_eq!(report.bytes, bytes_below(&from).unwrap());
+        let recorded = Manifest::record(&from).unwrap();
+        assert_eq!(recorded.verify(&Manifest::record(&to).unwrap()), Ok(()));
+        assert!(!to.join(format!("2024/a.jpg{}", PARTIAL_SUFFIX)).exists());
+        assert!(store.interrupted().unwrap().is_empty());
+        assert_eq!(
+            resume(&store, checkpoint.id, &mut throttle, &mut |_| {})
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+
+        let copied = copy_resumable(
+            &store,
+            &from,
+            &root.join("again"),
+            &mut throttle,
+            &mut |_| {},
+        );
+        assert!(copied.is_ok());
+        assert!(copy_resumable(&store, &from, &to, &mut throttle, &mut |_| {}).is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_checkpoint_keeps_path_bytes() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let store = CheckpointStore::in_memory().unwrap();
+        let from = Path::new(std::ffi::OsStr::from_bytes(b"/cleaner/caf\xe9"));
+        let to = Path::new(std::ffi::OsStr::from_bytes(b"/backup/caf\xe9"));
+        let name = Path::new(std::ffi::OsStr::from_bytes(b"\xff.jpg"));
+        let mut checkpoint = store.begin(from, to, 10).unwrap();
+        checkpoint.complete(name);
+        checkpoint.partial = Some((name.with_extension("png"), 4));
+        store.save(&mut checkpoint).unwrap();
+
+        let loaded = store.load(checkpoint.id).unwrap();
+        assert_eq!((loaded.from.as_path(), loaded.to.as_path()), (from, to));
+        assert!(loaded.completed.contains(name));
+        assert_eq!(loaded.partial, Some((name.with_extension("png"), 4)));
+        assert_eq!(store.interrupted().unwrap()[0].from, from);
+    }
+
+    #[test]
+    fn test_text_checkpoints_migrated_to_bytes() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE copy_checkpoints (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                source TEXT NOT NULL,
+                destination TEXT NOT NULL,
+                total_bytes INTEGER NOT NULL,
+                copied_bytes INTEGER NOT NULL,
+                partial TEXT,
+                partial_offset INTEGER,
+                started INTEGER NOT NULL,
+                updated INTEGER NOT NULL,
+                error TEXT
+            );
+            CREATE TABLE copy_checkpoint_files (
+                checkpoint INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                PRIMARY KEY (checkpoint, path)
+            );
+            INSERT INTO copy_checkpoints VALUES
+                (7, '/cleaner/photos', '/backup/photos', 10, 4, 'b.jpg', 2, 1, 1, NULL);
+            INSERT INTO copy_checkpoint_files VALUES (7, 'a.jpg');",
+        )
+        .unwrap();
+
+        let store = CheckpointStore::new(&Database::with_connection(conn)).unwrap();
+        let checkpoint = store.load(7).unwrap();
+        assert_eq!(checkpoint.from, PathBuf::from("/cleaner/photos"));
+        assert_eq!(checkpoint.to, PathBuf::from("/backup/photos"));
+        assert!(checkpoint.completed.contains(Path::new("a.jpg")));
+        assert_eq!(checkpoint.partial, Some((PathBuf::from("b.jpg"), 2)));
+    }
+}
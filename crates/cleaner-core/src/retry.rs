@@ -0,0 +1,196 @@
+use std::{future::Future, io, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/**
+ * how often and how patiently a listing is retried after a transient error,
+ * network shares and external drives waking from sleep fail for a moment
+ * before they answer again
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    /// attempts after the first one, zero to never retry
+    pub retries: u32,
+    /// delay before the first retry, doubled for every further one
+    pub backoff_millis: u64,
+    /// upper bound of a single delay
+    pub max_delay_millis: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            backoff_millis: 200,
+            max_delay_millis: 5_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /**
+     * the delay before retry number `attempt`, counted from zero
+     */
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let millis = self
+            .backoff_millis
+            .saturating_mul(1u64.checked_shl(attempt).unwrap_or(u64::MAX));
+        Duration::from_millis(millis.min(self.max_delay_millis))
+    }
+
+    pub fn backoff(&self) -> Backoff {
+        Backoff {
+            policy: *self,
+            attempt: 0,
+        }
+    }
+}
+
+/**
+ * the retries left for one operation
+ */
+#[derive(Debug)]
+pub struct Backoff {
+    policy: RetryPolicy,
+    attempt: u32,
+}
+
+impl Backoff {
+    /**
+     * how long to wait before trying again after `err`, none when the error is
+     * permanent or the retries are used up
+     */
+    pub fn retry(&mut self, err: &io::Error) -> Option<Duration> {
+        if self.attempt >= self.policy.retries || !is_transient(err) {
+            return None;
+        }
+        let delay = self.policy.delay(self.attempt);
+        self.attempt += 1;
+        Some(delay)
+    }
+}
+
+/**
+ * run `op` until it succeeds, fails for good or runs out of retries
+ */
+pub async fn run<T, F, Fut>(policy: &RetryPolicy, mut op: F) -> io::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    let mut backoff = policy.backoff();
+    loop {
+        match op().await {
+            Err(err) => match backoff.retry(&err) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Err(err),
+            },
+            result => return result,
+        }
+    }
+}
+
+/**
+ * Whether `err` may go away by itself: timeouts, busy devices and dropped
+ * network connections. A missing file or a denied permission stays the
+ * same however often it is asked.
+ */
+pub fn is_transient(err: &io::Error) -> bool {
+    use io::ErrorKind::*;
+
+    match err.kind() {
+        Interrupted
+        | WouldBlock
+        | TimedOut
+        | ResourceBusy
+        | StaleNetworkFileHandle
+        | NetworkDown
+        | NetworkUnreachable
+        | HostUnreachable
+        | ConnectionReset
+        | ConnectionAborted => true,
+        _ => is_transient_os_error(err),
+    }
+}
+
+/**
+ * the drive is spinning up, or the share went away for a moment
+ */
+#[cfg(windows)]
+fn is_transient_os_error(err: &io::Error) -> bool {
+    const ERROR_NOT_READY: i32 = 21;
+    const ERROR_NETNAME_DELETED: i32 = 64;
+    const ERROR_SEM_TIMEOUT: i32 = 121;
+
+    matches!(
+        err.raw_os_error(),
+        Some(ERROR_NOT_READY | ERROR_NETNAME_DELETED | ERROR_SEM_TIMEOUT)
+    )
+}
+
+#[cfg(not(windows))]
+fn is_transient_os_error(_err: &io::Error) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_delay_doubles_up_to_the_cap() {
+        let policy = RetryPolicy {
+            retries: 10,
+            backoff_millis: 100,
+            max_delay_millis: 1_000,
+        };
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+        assert_eq!(policy.delay(4), Duration::from_millis(1_000));
+        assert_eq!(policy.delay(80), Duration::from_millis(1_000));
+    }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        let mut backoff = RetryPolicy::default().backoff();
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert_eq!(backoff.retry(&denied), None);
+
+        let timeout = io::Error::from(io::ErrorKind::TimedOut);
+        for _ in 0..RetryPolicy::default().retries {
+            assert!(backoff.retry(&timeout).is_some());
+        }
+        assert_eq!(backoff.retry(&timeout), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_recovers_from_transient_errors() {
+        let policy = RetryPolicy {
+            retries: 2,
+            backoff_millis: 1,
+            max_delay_millis: 1,
+        };
+        let calls = AtomicU32::new(0);
+        let result = run(&policy, || async {
+            match calls.fetch_add(1, Ordering::Relaxed) {
+                0 | 1 => Err(io::Error::from(io::ErrorKind::TimedOut)),
+                _ => Ok("listed"),
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), "listed");
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+        calls.store(0, Ordering::Relaxed);
+        let result: io::Result<()> = run(&policy, || async {
+            calls.fetch_add(1, Ordering::Relaxed);
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,375 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    database::{Database, to_io},
+    labels,
+    models::now_secs,
+    path_key::PathKey,
+};
+
+/**
+ * where the user put an item while triaging the large ones
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ReviewState {
+    ToReview,
+    Keep,
+    DeleteLater,
+    Unsure,
+}
+
+impl ReviewState {
+    /**
+     * the queues in the order they are worked through
+     */
+    pub const ALL: [ReviewState; 4] = [
+        ReviewState::ToReview,
+        ReviewState::Unsure,
+        ReviewState::DeleteLater,
+        ReviewState::Keep,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ReviewState::ToReview => "toReview",
+            ReviewState::Keep => "keep",
+            ReviewState::DeleteLater => "deleteLater",
+            ReviewState::Unsure => "unsure",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|state| state.name() == name)
+    }
+
+    /**
+     * A kept item goes back to review before it can be deleted, a single
+     * click must not undo a "do not touch" decision. Every other move is fine.
+     */
+    pub fn can_become(self, next: ReviewState) -> bool {
+        !(self == ReviewState::Keep && next == ReviewState::DeleteLater)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewItem {
+    pub path: PathBuf,
+    pub state: ReviewState,
+    /// bytes when the decision was made, none when the item wasn't scanned
+    pub size: Option<u64>,
+    /// unix seconds of the decision
+    pub updated: u64,
+}
+
+/**
+ * the items of one state, the largest first
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewQueue {
+    pub state: ReviewState,
+    pub count: usize,
+    pub bytes: u64,
+    pub items: Vec<ReviewItem>,
+}
+
+/**
+ * what `execute_delete_later` may remove. A folder is held back while an
+ * item inside it is in another queue, deleting it would take that item along.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeletePlan {
+    pub paths: Vec<PathBuf>,
+    /// the held back folder and the first item inside it which isn't to be deleted
+    pub blocked: Vec<(PathBuf, ReviewItem)>,
+}
+
+/**
+ * The triage decisions persisted in SQLite, in the app database next to the
+ * hash index and the folder labels. Items are keyed like the labels, by the
+ * path with its parents resolved.
+ */
+#[derive(Clone)]
+pub struct ReviewStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl ReviewStore {
+    pub fn new(database: &Database) -> io::Result<Self> {
+        let shared = database.connection();
+        let conn = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS review_items (
+                key TEXT PRIMARY KEY,
+                path TEXT NOT NULL,
+                state TEXT NOT NULL,
+                size INTEGER,
+                updated INTEGER NOT NULL
+            );",
+        )
+        .map_err(to_io)?;
+
+        Ok(Self {
+            conn: Arc::clone(&shared),
+        })
+    }
+
+    pub fn in_memory() -> io::Result<Self> {
+        Self::new(&Database::in_memory()?)
+    }
+
+    /**
+     * move `path` to the queue of `state`, the previous state when it had one.
+     * A move `ReviewState::can_become` refuses fails with `InvalidInput`.
+     */
+    pub fn set(
+        &self,
+        path: &Path,
+        state: ReviewState,
+        size: Option<u64>,
+    ) -> io::Result<Option<ReviewState>> {
+        let path = PathKey::resolve(path).into_path_buf();
+        let key = labels::key(&path);
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let previous: Option<String> = conn
+            .query_row(
+                "SELECT state FROM review_items WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(to_io)?;
+        let previous = previous.as_deref().and_then(ReviewState::parse);
+        if let Some(previous) = previous.filter(|previous| !previous.can_become(state)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} is marked {}, move it back to review before deleting it",
+                    path.display(),
+                    previous.name()
+                ),
+            ));
+        }
+
+        conn.execute(
+            "INSERT OR REPLACE INTO review_items (key, path, state, size, updated)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                key,
+                path.to_string_lossy(),
+                state.name(),
+                size.map(|size| size as i64),
+                now_secs() as i64
+            ],
+        )
+        .map_err(to_io)?;
+        Ok(previous)
+    }
+
+    /**
+     * take `path` out of every queue, true when it was in one
+     */
+    pub fn remove(&self, path: &Path) -> io::Result<bool> {
+        let path = PathKey::resolve(path).into_path_buf();
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let removed = conn
+            .execute(
+                "DELETE FROM review_items WHERE key = ?1",
+                params![labels::key(&path)],
+            )
+            .map_err(to_io)?;
+        Ok(removed > 0)
+    }
+
+    pub fn items(&self) -> io::Result<Vec<ReviewItem>> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut statement = conn
+            .prepare("SELECT path, state, size, updated FROM review_items")
+            .map_err(to_io)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(to_io)?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            let (path, state, size, updated) = row.map_err(to_io)?;
+            // a state written by a newer version is left alone
+            let Some(state) = ReviewState::parse(&state) else {
+                continue;
+            };
+            items.push(ReviewItem {
+                path: PathBuf::from(path),
+                state,
+                size: size.map(|size| size as u64),
+                updated: updated as u64,
+            });
+        }
+        Ok(items)
+    }
+
+    /**
+     * every queue with its totals, empty ones included so the UI keeps its columns
+     */
+    pub fn queues(&self) -> io::Result<Vec<ReviewQueue>> {
+        let items = self.items()?;
+        Ok(ReviewState::ALL
+            .into_iter()
+            .map(|state| {
+                let mut queued: Vec<ReviewItem> = items
+                    .iter()
+                    .filter(|item| item.state == state)
+                    .cloned()
+                    .collect();
+                queued.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+                ReviewQueue {
+                    state,
+                    count: queued.len(),
+                    bytes: queued.iter().filter_map(|item| item.size).sum(),
+                    items: queued,
+                }
+            })
+            .collect())
+    }
+
+    pub fn delete_plan(&self) -> io::Result<DeletePlan> {
+        Ok(plan(&self.items()?))
+    }
+
+    /**
+     * drop the items at or below the removed `paths`, the number dropped
+     */
+    pub fn forget_removed(&self, paths: &[PathBuf]) -> io::Result<usize> {
+        let gone: Vec<PathBuf> = self
+            .items()?
+            .into_iter()
+            .map(|item| item.path)
+            .filter(|path| paths.iter().any(|removed| path.starts_with(removed)))
+            .collect();
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        for path in &gone {
+            conn.execute(
+                "DELETE FROM review_items WHERE key = ?1",
+                params![labels::key(path)],
+            )
+            .map_err(to_io)?;
+        }
+        Ok(gone.len())
+    }
+}
+
+/**
+ * the delete-later items which hold no item of another queue
+ */
+pub fn plan(items: &[ReviewItem]) -> DeletePlan {
+    let mut plan = DeletePlan::default();
+    for item in items
+        .iter()
+        .filter(|item| item.state == ReviewState::DeleteLater)
+    {
+        let spared = items.iter().find(|other| {
+            other.state != ReviewState::DeleteLater
+                && other.path != item.path
+                && other.path.starts_with(&item.path)
+        });
+        match spared {
+            Some(spared) => plan.blocked.push((item.path.clone(), spared.clone())),
+            None => plan.paths.push(item.path.clone()),
+        }
+    }
+    plan.paths.sort();
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queues_summed() {
+        let store = ReviewStore::in_memory().unwrap();
+        store
+            .set(
+                Path::new("/cleaner/review/a"),
+                ReviewState::DeleteLater,
+                Some(10),
+            )
+            .unwrap();
+        store
+            .set(
+                Path::new("/cleaner/review/b"),
+                ReviewState::DeleteLater,
+                Some(30),
+            )
+            .unwrap();
+        store
+            .set(Path::new("/cleaner/review/c"), ReviewState::Unsure, None)
+            .unwrap();
+
+        let queues = store.queues().unwrap();
+        assert_eq!(queues.len(), 4);
+        let later = queues
+            .iter()
+            .find(|queue| queue.state == ReviewState::DeleteLater)
+            .unwrap();
+        assert_eq!((later.count, later.bytes), (2, 40));
+        assert_eq!(later.items[0].path, PathBuf::from("/cleaner/review/b"));
+        assert_eq!(queues[0].count, 0);
+    }
+
+    #[test]
+    fn test_kept_item_not_deleted_directly() {
+        let store = ReviewStore::in_memory().unwrap();
+        let path = Path::new("/cleaner/review/kept");
+        assert_eq!(store.set(path, ReviewState::Keep, None).unwrap(), None);
+
+        let err = store.set(path, ReviewState::DeleteLater, None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            store.set(path, ReviewState::ToReview, None).unwrap(),
+            Some(ReviewState::Keep)
+        );
+        assert!(store.set(path, ReviewState::DeleteLater, None).is_ok());
+    }
+
+    #[test]
+    fn test_plan_spares_kept_items_inside() {
+        let store = ReviewStore::in_memory().unwrap();
+        let set = |path: &str, state| store.set(Path::new(path), state, None).unwrap();
+        set("/cleaner/review/cache", ReviewState::DeleteLater);
+        set("/cleaner/review/cache/old", ReviewState::DeleteLater);
+        set("/cleaner/review/photos", ReviewState::DeleteLater);
+        set("/cleaner/review/photos/2019", ReviewState::Keep);
+
+        let plan = store.delete_plan().unwrap();
+        assert_eq!(
+            plan.paths,
+            vec![
+                PathBuf::from("/cleaner/review/cache"),
+                PathBuf::from("/cleaner/review/cache/old"),
+            ]
+        );
+        assert_eq!(plan.blocked.len(), 1);
+        assert_eq!(plan.blocked[0].0, PathBuf::from("/cleaner/review/photos"));
+
+        let removed = [PathBuf::from("/cleaner/review/cache")];
+        assert_eq!(store.forget_removed(&removed).unwrap(), 2);
+        assert_eq!(store.items().unwrap().len(), 2);
+    }
+}
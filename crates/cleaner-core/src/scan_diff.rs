@@ -0,0 +1,214 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{lossless, snapshot::SnapshotEntry};
+
+/**
+ * a new file smaller than this is no news, 100 MB
+ */
+pub const DEFAULT_MIN_NEW_FILE_SIZE: usize = 100 * 1024 * 1024;
+
+/**
+ * a directory in both scans whose size changed
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeChange {
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8
+    pub raw_path: Option<String>,
+    pub old_size: usize,
+    pub new_size: usize,
+}
+
+/**
+ * an entry found in one scan only
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedEntry {
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8
+    pub raw_path: Option<String>,
+    pub size: usize,
+    pub is_directory: bool,
+}
+
+/**
+ * What changed between two scans of the same root. Each list holds the
+ * `limit` largest changes. A deleted directory is listed once, not every
+ * entry below it, and the directories holding a change grow or shrink with
+ * it, so the first ones tell where the space went.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanDiff {
+    pub old_size: usize,
+    pub new_size: usize,
+    /// directories in both scans which got larger, most bytes gained first
+    pub grown: Vec<SizeChange>,
+    /// directories in both scans which got smaller, most bytes lost first
+    pub shrunk: Vec<SizeChange>,
+    /// files only in the new scan of at least the minimum size, largest first
+    pub new_files: Vec<ChangedEntry>,
+    /// entries only in the old scan, largest first
+    pub deleted: Vec<ChangedEntry>,
+}
+
+fn change(path: PathBuf, old_size: usize, new_size: usize) -> SizeChange {
+    SizeChange {
+        raw_path: lossless::raw_if_lossy(&path),
+        path,
+        old_size,
+        new_size,
+    }
+}
+
+fn entry(path: PathBuf, entry: &SnapshotEntry) -> ChangedEntry {
+    ChangedEntry {
+        raw_path: lossless::raw_if_lossy(&path),
+        path,
+        size: entry.size,
+        is_directory: entry.is_directory,
+    }
+}
+
+/**
+ * the full path of every flat entry, the root entry carries the whole root path
+ */
+fn paths(entries: &[SnapshotEntry]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let path = match entry.parent.and_then(|parent| paths.get(parent)) {
+            Some(parent) => parent.join(&entry.name),
+            None => PathBuf::from(&entry.name),
+        };
+        paths.push(path);
+    }
+    paths
+}
+
+/**
+ * compare two flat scans, entries matched by their full path
+ */
+pub fn diff(
+    old: &[SnapshotEntry],
+    new: &[SnapshotEntry],
+    min_new_file_size: usize,
+    limit: usize,
+) -> ScanDiff {
+    let old_paths = paths(old);
+    let mut unmatched: HashMap<&Path, usize> = old_paths
+        .iter()
+        .enumerate()
+        .map(|(index, path)| (path.as_path(), index))
+        .collect();
+
+    let mut diff = ScanDiff {
+        old_size: old.first().map_or(0, |root| root.size),
+        new_size: new.first().map_or(0, |root| root.size),
+        ..ScanDiff::default()
+    };
+    for (path, current) in paths(new).into_iter().zip(new) {
+        let Some(index) = unmatched.remove(path.as_path()) else {
+            if !current.is_directory && current.size >= min_new_file_size {
+                diff.new_files.push(entry(path, current));
+            }
+            continue;
+        };
+        let previous = &old[index];
+        if !(previous.is_directory && current.is_directory) || previous.size == current.size {
+            continue;
+        }
+        let changed = change(path, previous.size, current.size);
+        if current.size > previous.size {
+            diff.grown.push(changed);
+        } else {
+            diff.shrunk.push(changed);
+        }
+    }
+
+    // what is left was deleted, below a deleted directory only the directory counts
+    for (path, index) in &unmatched {
+        let parent_deleted = old[*index]
+            .parent
+            .is_some_and(|parent| unmatched.contains_key(old_paths[parent].as_path()));
+        if !parent_deleted {
+            diff.deleted.push(entry(path.to_path_buf(), &old[*index]));
+        }
+    }
+
+    let changed_by = |change: &SizeChange| change.new_size.abs_diff(change.old_size);
+    for changes in [&mut diff.grown, &mut diff.shrunk] {
+        changes.sort_by(|a, b| {
+            changed_by(b)
+                .cmp(&changed_by(a))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        changes.truncate(limit);
+    }
+    for entries in [&mut diff.new_files, &mut diff.deleted] {
+        entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+        entries.truncate(limit);
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        snapshot,
+        tree::{Tree, node::Node},
+    };
+    use std::ffi::OsString;
+
+    fn tree(files: &[(&str, &str, usize)]) -> Vec<SnapshotEntry> {
+        let mut tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        for (parent, name, size) in files {
+            let node = Node::new(OsString::from(name), *size == 0, false);
+            node.set_size(*size);
+            let _ = tree.insert(&PathBuf::from(parent), node);
+        }
+        snapshot::flatten(&tree)
+    }
+
+    #[test]
+    fn test_what_ate_the_space() {
+        let old = tree(&[
+            ("/", "videos", 0),
+            ("/videos", "trip.mp4", 500),
+            ("/", "cache", 0),
+            ("/cache", "a.tmp", 300),
+            ("/", "old", 0),
+            ("/old", "b.bin", 200),
+        ]);
+        let new = tree(&[
+            ("/", "videos", 0),
+            ("/videos", "trip.mp4", 500),
+            ("/videos", "render.mov", 3000),
+            ("/", "cache", 0),
+            ("/cache", "a.tmp", 100),
+            ("/", "tiny.txt", 5),
+        ]);
+
+        let diff = diff(&old, &new, 1000, 10);
+        assert_eq!((diff.old_size, diff.new_size), (1000, 3605));
+        assert_eq!(diff.grown[0], change(PathBuf::from("/videos"), 500, 3500));
+        assert_eq!(diff.grown[1].path, PathBuf::from("/"));
+        assert_eq!(diff.shrunk, vec![change(PathBuf::from("/cache"), 300, 100)]);
+        assert_eq!(diff.new_files.len(), 1);
+        assert_eq!(diff.new_files[0].path, PathBuf::from("/videos/render.mov"));
+        // the folder, not the file in it
+        assert_eq!(diff.deleted.len(), 1);
+        assert_eq!(diff.deleted[0].path, PathBuf::from("/old"));
+        assert_eq!(diff.deleted[0].size, 200);
+    }
+}
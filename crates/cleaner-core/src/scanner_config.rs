@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/**
+ * workers a scan runs when nothing else was asked for
+ */
+pub const DEFAULT_WORKERS: usize = 20;
+
+/**
+ * workers a scan runs at the most, more only wait on the same disk
+ */
+pub const MAX_WORKERS: usize = 256;
+
+/**
+ * How hard a scan works the disk. A laptop scanning in the background runs
+ * few workers at a capped rate, a desktop on an NVMe drive runs many
+ * without a cap.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScannerConfig {
+    /// directories listed at the same time
+    pub workers: usize,
+    /// directories waiting in the queue from which the scan goes depth first, none for no bound
+    pub max_queue_depth: Option<usize>,
+    /// entries listed a second over all workers, none for no cap
+    pub entries_per_sec: Option<u64>,
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        Self::with_workers(DEFAULT_WORKERS)
+    }
+}
+
+impl ScannerConfig {
+    pub fn with_workers(workers: usize) -> Self {
+        Self {
+            workers,
+            max_queue_depth: None,
+            entries_per_sec: None,
+        }
+    }
+
+    /**
+     * the config as the scanner applies it, the workers between one and
+     * `MAX_WORKERS` and a zero bound or cap taken as none
+     */
+    pub fn normalized(self) -> Self {
+        Self {
+            workers: self.workers.clamp(1, MAX_WORKERS),
+            max_queue_depth: self.max_queue_depth.filter(|depth| *depth > 0),
+            entries_per_sec: self.entries_per_sec.filter(|rate| *rate > 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_bounds() {
+        let config = ScannerConfig {
+            workers: 0,
+            max_queue_depth: Some(0),
+            entries_per_sec: Some(0),
+        }
+        .normalized();
+        assert_eq!(config, ScannerConfig::with_workers(1));
+
+        let config = ScannerConfig {
+            workers: 10_000,
+            max_queue_depth: Some(5_000),
+            entries_per_sec: Some(200),
+        }
+        .normalized();
+        assert_eq!(config.workers, MAX_WORKERS);
+        assert_eq!(config.max_queue_depth, Some(5_000));
+        assert_eq!(config.entries_per_sec, Some(200));
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use specta::Type;
+
+use crate::{
+    hash::{HashAlgorithm, HashIndex, HashProgress},
+    tree::Tree,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionRule {
+    SameExtensionInDir,
+    OlderThanSelected,
+    LargerThanSelected,
+    SameHash,
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/**
+ * files sitting next to `path` which satisfy `matches(size, modified, name)`
+ */
+fn siblings<F>(tree: &Tree, path: &Path, matches: F) -> Vec<PathBuf>
+where
+    F: Fn(usize, Option<u64>, &Path) -> bool,
+{
+    let Some(parent) = path.parent().map(Path::to_path_buf) else {
+        return vec![];
+    };
+    let Some(node) = tree.get_node(&parent) else {
+        return vec![];
+    };
+
+    node.children()
+        .iter()
+        .filter_map(|child| {
+            let child = child.read().ok()?;
+            let name = Path::new(&child.path);
+            (!child.is_directory && matches(child.size(), child.modified, name))
+                .then(|| parent.join(name))
+        })
+        .collect()
+}
+
+/**
+ * Expand the selection with the rules that only need the scanned tree,
+ * `SameHash` returns the same-size candidates which still have to be hashed.
+ */
+pub fn expand_in_tree(tree: &Tree, paths: &[PathBuf], rule: SelectionRule) -> BTreeSet<PathBuf> {
+    let mut selection: BTreeSet<PathBuf> = paths.iter().cloned().collect();
+    // one walk of the tree finds the candidates of every selected size
+    let mut sizes = HashSet::new();
+
+    for path in paths {
+        let Some((size, modified)) = tree
+            .get_node(path)
+            .and_then(|node| node.read().map(|node| (node.size(), node.modified)).ok())
+        else {
+            continue;
+        };
+
+        match rule {
+            SelectionRule::SameExtensionInDir => {
+                let ext = extension(path);
+                selection.extend(siblings(tree, path, |_, _, name| extension(name) == ext));
+            }
+            SelectionRule::OlderThanSelected => {
+                let Some(modified) = modified else {
+                    continue;
+                };
+                selection.extend(siblings(tree, path, |_, other, _| {
+                    other.is_some_and(|other| other <= modified)
+                }));
+            }
+            SelectionRule::LargerThanSelected => {
+                selection.extend(siblings(tree, path, |other, _, _| other >= size));
+            }
+            SelectionRule::SameHash => {
+                sizes.insert(size);
+            }
+        }
+    }
+
+    if !sizes.is_empty() {
+        tree.walk(&tree.root_path().unwrap_or_default(), |candidate, node| {
+            if !node.is_directory && sizes.contains(&node.size()) {
+                selection.insert(candidate.clone());
+            }
+        });
+    }
+    selection
+}
+
+/**
+ * Keep the candidates whose content matches one of the selected files. A
+ * candidate is checked with the quick xxh3 first, only a match is confirmed
+ * with the algorithm chosen for the index. `observe` sees every file read.
+ */
+pub fn filter_same_hash(
+    index: &HashIndex,
+    selected: &[PathBuf],
+    candidates: BTreeSet<PathBuf>,
+    observe: &mut dyn FnMut(&HashProgress),
+) -> Vec<PathBuf> {
+    let algorithm = index.algorithm();
+    let mut hashes = |algorithm: HashAlgorithm| -> BTreeSet<String> {
+        selected
+            .iter()
+            .filter_map(|path| index.hash_observed(path, algorithm, observe).ok())
+            .collect()
+    };
+    let quick = hashes(HashAlgorithm::Xxh3);
+    let confirmed = hashes(algorithm);
+
+    let mut matches = |path: &Path, algorithm: HashAlgorithm, hashes: &BTreeSet<String>| {
+        index
+            .hash_observed(path, algorithm, observe)
+            .is_ok_and(|hash| hashes.contains(&hash))
+    };
+    candidates
+        .into_iter()
+        .filter(|path| {
+            selected.contains(path)
+                || (matches(path, HashAlgorithm::Xxh3, &quick)
+                    && (algorithm == HashAlgorithm::Xxh3 || matches(path, algorithm, &confirmed)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::node::Node;
+    use std::ffi::OsString;
+
+    fn file(name: &str, size: usize, modified: u64) -> Node {
+        let mut node = Node::new(OsString::from(name), false, false);
+        node.set_size(size);
+        node.modified = Some(modified);
+        node
+    }
+
+    fn build_tree() -> Tree {
+        let mut tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let root = PathBuf::from("/");
+        let _ = tree.insert(&root, Node::new(OsString::from("docs"), true, false));
+        let docs = PathBuf::from("/docs");
+        let _ = tree.insert(&docs, file("a.pdf", 10, 100));
+        let _ = tree.insert(&docs, file("b.PDF", 30, 300));
+        let _ = tree.insert(&docs, file("c.txt", 20, 200));
+        tree
+    }
+
+    #[test]
+    fn test_same_extension_in_dir() {
+        let tree = build_tree();
+        let ret = expand_in_tree(
+            &tree,
+            &[PathBuf::from("/docs/a.pdf")],
+            SelectionRule::SameExtensionInDir,
+        );
+        assert_eq!(
+            ret.into_iter().collect::<Vec<_>>(),
+            vec![PathBuf::from("/docs/a.pdf"), PathBuf::from("/docs/b.PDF")]
+        );
+    }
+
+    #[test]
+    fn test_larger_and_older_than_selected() {
+        let tree = build_tree();
+        let selected = [PathBuf::from("/docs/c.txt")];
+
+        let larger = expand_in_tree(&tree, &selected, SelectionRule::LargerThanSelected);
+        assert_eq!(
+            larger.into_iter().collect::<Vec<_>>(),
+            vec![PathBuf::from("/docs/b.PDF"), PathBuf::from("/docs/c.txt")]
+        );
+
+        let older = expand_in_tree(&tree, &selected, SelectionRule::OlderThanSelected);
+        assert_eq!(
+            older.into_iter().collect::<Vec<_>>(),
+            vec![PathBuf::from("/docs/a.pdf"), PathBuf::from("/docs/c.txt")]
+        );
+    }
+
+    #[test]
+    fn test_same_size_candidates_of_every_selected_file() {
+        let mut tree = build_tree();
+        let docs = PathBuf::from("/docs");
+        let _ = tree.insert(&docs, file("d.bin", 10, 400));
+        let _ = tree.insert(&docs, file("e.bin", 30, 500));
+
+        let selected = [PathBuf::from("/docs/a.pdf"), PathBuf::from("/docs/b.PDF")];
+        let candidates = expand_in_tree(&tree, &selected, SelectionRule::SameHash);
+        assert_eq!(
+            candidates.into_iter().collect::<Vec<_>>(),
+            vec![
+                PathBuf::from("/docs/a.pdf"),
+                PathBuf::from("/docs/b.PDF"),
+                PathBuf::from("/docs/d.bin"),
+                PathBuf::from("/docs/e.bin"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,2063 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    ffi::{OsStr, OsString},
+    fmt::Debug,
+    fs::Metadata,
+    io,
+    path::{Component, Path, PathBuf},
+    sync::{
+        Arc, Mutex, PoisonError, RwLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
+use tokio::{
+    fs,
+    sync::mpsc::{self, Sender},
+    task::JoinHandle,
+    time::{Duration, Instant},
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    allocation, dashboard,
+    delta::{self, PendingDeltas, SizeDeltaBatch},
+    device,
+    eta::{self, ScanTarget},
+    file_id::FileId,
+    filters::{FilterMatcher, ScanFilters},
+    interference::{ThroughputMonitor, hint, sample_disk_heavy_processes},
+    locks::LockFlags,
+    long_path,
+    memory::{self, Mitigation},
+    models::{AgeBuckets, ChildPage, FileDetails, VolumeCapabilities, now_secs},
+    native_dir::{self, EntryKind, NativeDir, NativeEntry},
+    path_key::PathKey,
+    queue::{ActiveWorkers, QueueSnapshot, ScanItem, ScanQueue},
+    retry::{self, RetryPolicy},
+    scanner_config::ScannerConfig,
+    snapshot::{self, ScanSource, SessionArchive, SessionInfo},
+    store::ScanStore,
+    symlinks::{LinkFollower, SymlinkPolicy},
+    throttle::Throttle,
+    timing::{DirectoryTiming, SlowDirectories},
+    tree::{
+        Growth, Tree,
+        node::{Node, NodeRef},
+    },
+    watchdog::{ScanStall, Watchdog},
+};
+use stats::BreakdownCache;
+use watch::TreeWatcher;
+
+pub mod cache;
+pub mod dedup;
+pub mod deleter;
+pub mod empty;
+pub mod stats;
+pub mod watch;
+
+#[derive(Debug, Clone)]
+pub struct FileNode {
+    pub path: OsString,
+    pub size: usize,
+    pub allocated_size: usize,
+    pub is_directory: bool,
+    pub is_link: bool,
+    pub modified: Option<u64>,
+    pub created: Option<u64>,
+}
+
+impl FileNode {
+    pub fn new(path: OsString, is_dir: bool, is_link: bool) -> FileNode {
+        FileNode {
+            path: path,
+            size: 0,
+            allocated_size: 0,
+            is_directory: is_dir,
+            is_link: is_link,
+            modified: None,
+            created: None,
+        }
+    }
+
+    fn from(node: &FileNode) -> FileNode {
+        FileNode {
+            path: node.path.clone(),
+            size: node.size,
+            allocated_size: node.allocated_size,
+            is_directory: node.is_directory,
+            is_link: node.is_link,
+            modified: node.modified,
+            created: node.created,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct ScanProgress {
+    pub scaned_files: usize,
+    pub scaned_size: usize,
+    pub current_path: Option<PathBuf>,
+    pub is_scanning: bool,
+    /// explanation when the scan slows down because of other disk activity
+    pub hint: Option<String>,
+    /// share of the estimated work done, 0 to 100, none without a target
+    pub percent: Option<f64>,
+    /// estimated seconds left, none until enough is scanned to tell
+    pub remaining_secs: Option<u64>,
+}
+impl ScanProgress {
+    fn reset(&mut self) {
+        self.scaned_files = 0;
+        self.scaned_size = 0;
+        self.current_path = None;
+        self.is_scanning = false;
+        self.hint = None;
+        self.percent = None;
+        self.remaining_secs = None;
+    }
+}
+
+/**
+ * how many of the slowest directories are remembered per scan
+ */
+const SLOW_DIRECTORY_CAPACITY: usize = 100;
+
+/**
+ * progress goes out at most this often, unless many entries were found since
+ */
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+const PROGRESS_ENTRIES: usize = 10_000;
+
+/**
+ * decides which of the progress updates of the workers reach the channel
+ */
+#[derive(Debug, Default)]
+struct ProgressThrottle {
+    sent: Option<(Instant, usize)>,
+}
+
+impl ProgressThrottle {
+    fn due(&mut self, now: Instant, entries: usize) -> bool {
+        let due = self.sent.is_none_or(|(at, sent)| {
+            now.duration_since(at) >= PROGRESS_INTERVAL
+                || entries.saturating_sub(sent) >= PROGRESS_ENTRIES
+        });
+        if due {
+            self.sent = Some((now, entries));
+        }
+        due
+    }
+}
+
+type FileTree = Arc<RwLock<Tree>>;
+type TreeNode = NodeRef;
+
+/**
+ * how a directory is listed, read for every directory so a change applies from the next one
+ */
+struct Listing {
+    retry: RetryPolicy,
+    filters: Arc<FilterMatcher>,
+    /// the filesystem of the scan root when the scan stays on it
+    device: Option<u64>,
+    /// where the directories on another filesystem are recorded
+    mounts: Arc<Mutex<Vec<PathBuf>>>,
+    /// the files with several hard links counted so far, by device and inode
+    links: Arc<Mutex<HashSet<FileId>>>,
+    /// below it the entries of a directory only count in its size
+    max_depth: Option<usize>,
+    /// the directory links to list, none to leave every link as it is
+    follower: Option<Arc<LinkFollower>>,
+    /// the cap every entry is paced through, none without one
+    pacing: Option<Arc<Mutex<Throttle>>>,
+}
+
+/**
+ * the entries of one directory being added to its node, however they were listed
+ */
+struct Fill<'a> {
+    dir_node: &'a TreeNode,
+    listing: &'a Listing,
+    /// its entries are listed for their bytes, then dropped with what they hold
+    collapsed: bool,
+    now: u64,
+    children: Vec<TreeNode>,
+    listed: Growth,
+}
+
+impl<'a> Fill<'a> {
+    fn new(dir_node: &'a TreeNode, listing: &'a Listing) -> Self {
+        Self {
+            dir_node,
+            listing,
+            collapsed: listing
+                .max_depth
+                .is_some_and(|max| Tree::ancestors(dir_node).count() >= max),
+            now: now_secs(),
+            children: Vec::new(),
+            listed: Growth::default(),
+        }
+    }
+
+    /**
+     * Add the entry at `path` to the directory. `device` is the filesystem
+     * of a directory, only needed when the scan stays on one, `link` the
+     * identity of a file with several hard links.
+     */
+    fn add(
+        &mut self,
+        path: PathBuf,
+        mut file_node: Node,
+        is_dir: bool,
+        device: Option<u64>,
+        link: Option<FileId>,
+    ) {
+        let listing = self.listing;
+        // an unknown device is no reason to leave a directory out
+        let mount = is_dir
+            && listing
+                .device
+                .is_some_and(|root| device.is_some_and(|device| device != root));
+        if mount {
+            file_node.skipped = true;
+            file_node.listed = true;
+            file_node.complete = true;
+            let _ = listing.mounts.lock().map(|mut mounts| mounts.push(path));
+        }
+        // a further hard link to a file counted already adds no bytes
+        if !is_dir
+            && let Some(link) = link
+            && !listing
+                .links
+                .lock()
+                .map_or(true, |mut links| links.insert(link))
+        {
+            file_node.set_hardlink_savings(file_node.size());
+            file_node.set_size(0);
+            file_node.set_allocated_size(0);
+        }
+
+        // below the threshold only its bytes are kept, in its directory
+        let collapsed = self.collapsed;
+        let folded = !is_dir && (collapsed || listing.filters.folds(file_node.size() as u64));
+        let mut growth = Growth::of(&file_node);
+        // only the entries kept as nodes count, however deep their directory
+        if folded || collapsed {
+            growth.entries = 0;
+        }
+        self.listed.size += growth.size;
+        self.listed.allocated_size += growth.allocated_size;
+        self.listed.hardlink_savings += growth.hardlink_savings;
+        self.listed.entries += growth.entries;
+        let mut age = AgeBuckets::default();
+        if !file_node.is_directory {
+            age.add(file_node.modified, growth.size, self.now);
+        }
+        let dir_node = self.dir_node;
+        let node = dir_node.write().map(|mut node| {
+            node.grow(growth, &age);
+
+            // below the threshold only a directory to list is worth a node
+            if folded || (collapsed && mount) {
+                return None;
+            }
+            // when collapsed it still bubbles up and settles through its parent,
+            // which it leaves once it completes
+            file_node.loose = collapsed;
+            let new_node = dir_node.alloc(file_node);
+            if !collapsed {
+                node.push_child(&new_node);
+            }
+            Some(new_node)
+        });
+
+        if let (Ok(Some(node)), true) = (node, is_dir && !mount) {
+            self.children.push(node);
+        }
+    }
+
+    /**
+     * the subdirectories to queue and the bytes the entries added
+     */
+    fn finish(self) -> (Vec<TreeNode>, Growth) {
+        (self.children, self.listed)
+    }
+}
+
+pub struct Scanner {
+    /**
+     *  waiting to scan item
+     */
+    queue: Arc<Mutex<ScanQueue>>,
+    /**
+     *  the root path
+     */
+    files: FileTree,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+    /**
+     *  workers, queue bound and entry rate, the workers above the count wait
+     */
+    config: Arc<Mutex<ScannerConfig>>,
+    /**
+     *  the entries listed against the rate of the config, shared by all workers
+     */
+    pacing: Arc<Mutex<Throttle>>,
+    progress: Arc<Mutex<ScanProgress>>,
+    /**
+     *  enumeration time of the slowest directories
+     */
+    timings: Arc<Mutex<SlowDirectories>>,
+    /**
+     *  directory each worker is currently listing
+     */
+    active: Arc<Mutex<ActiveWorkers>>,
+    /**
+     *  live disk or an imported snapshot
+     */
+    source: RwLock<ScanSource>,
+    /**
+     *  expected size of the whole scan, for the percentage and remaining time
+     */
+    target: Arc<Mutex<Option<ScanTarget>>>,
+    /**
+     *  when a listing counts as hung
+     */
+    watchdog: Arc<Mutex<Watchdog>>,
+    /**
+     *  where the watchdog reports hung listings, none until `stalls`
+     */
+    stalls: Arc<Mutex<Option<Sender<ScanStall>>>>,
+    /**
+     *  how listings recover from transient errors
+     */
+    retry: Arc<Mutex<RetryPolicy>>,
+    /**
+     *  entries left out of the tree
+     */
+    filters: Arc<RwLock<Arc<FilterMatcher>>>,
+    /**
+     *  whether the next scan stays on the filesystem of its root
+     */
+    same_filesystem: AtomicBool,
+    /**
+     *  what the next scan does with links to directories
+     */
+    symlinks: Mutex<SymlinkPolicy>,
+    /**
+     *  directories of the scan on another filesystem, left unlisted
+     */
+    mounts: Arc<Mutex<Vec<PathBuf>>>,
+    /**
+     *  files with several hard links already counted, each one counts once
+     */
+    links: Arc<Mutex<HashSet<FileId>>>,
+    /**
+     *  depth below the root from which entries get no node, applies to a running scan too
+     */
+    max_depth: Arc<Mutex<Option<usize>>>,
+    /**
+     *  type breakdowns of complete folders, dropped with the tree they describe
+     */
+    breakdowns: Mutex<BreakdownCache>,
+    /**
+     *  size changes since the last batch
+     */
+    deltas: Arc<Mutex<PendingDeltas>>,
+    /**
+     *  where the batches of size changes go, none until `size_deltas`
+     */
+    delta_sink: Arc<Mutex<Option<Sender<SizeDeltaBatch>>>>,
+    /**
+     *  keeps the finished scan in line with the disk, none until `watch`
+     */
+    watcher: Mutex<Option<TreeWatcher>>,
+}
+
+impl Scanner {
+    pub fn new(concurrency: usize) -> Self {
+        let config = ScannerConfig::with_workers(concurrency).normalized();
+        Self {
+            queue: Arc::new(Mutex::new(ScanQueue::default())),
+            files: Arc::new(RwLock::new(Self::empty_tree())),
+            workers: Mutex::new(Vec::new()),
+            config: Arc::new(Mutex::new(config)),
+            pacing: Arc::new(Mutex::new(Throttle::unlimited())),
+            progress: Arc::new(Mutex::new(ScanProgress {
+                scaned_files: 0,
+                scaned_size: 0,
+                current_path: None,
+                is_scanning: false,
+                hint: None,
+                percent: None,
+                remaining_secs: None,
+            })),
+            timings: Arc::new(Mutex::new(SlowDirectories::new(SLOW_DIRECTORY_CAPACITY))),
+            active: Arc::new(Mutex::new(ActiveWorkers::new(config.workers))),
+            source: RwLock::new(ScanSource::Live),
+            target: Arc::new(Mutex::new(None)),
+            watchdog: Arc::new(Mutex::new(Watchdog::default())),
+            stalls: Arc::new(Mutex::new(None)),
+            retry: Arc::new(Mutex::new(RetryPolicy::default())),
+            filters: Arc::new(RwLock::new(Arc::new(FilterMatcher::default()))),
+            same_filesystem: AtomicBool::new(false),
+            symlinks: Mutex::new(SymlinkPolicy::default()),
+            mounts: Arc::new(Mutex::new(Vec::new())),
+            links: Arc::new(Mutex::new(HashSet::new())),
+            max_depth: Arc::new(Mutex::new(None)),
+            breakdowns: Mutex::new(BreakdownCache::default()),
+            deltas: Arc::new(Mutex::new(PendingDeltas::default())),
+            delta_sink: Arc::new(Mutex::new(None)),
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /**
+     * what the next progress estimates are measured against, none to only count up
+     */
+    pub fn set_target(&self, target: Option<ScanTarget>) {
+        let _ = self.target.lock().map(|mut current| *current = target);
+    }
+
+    /**
+     * Keep the next scan on the filesystem of its root. Directories where
+     * another volume is mounted, a network share or an external disk, are
+     * then left unlisted and marked as skipped instead of counted twice.
+     */
+    pub fn set_same_filesystem(&self, same_filesystem: bool) {
+        self.same_filesystem
+            .store(same_filesystem, Ordering::Relaxed);
+    }
+
+    /**
+     * Whether the next scan lists links to directories like the directories
+     * they lead to. A link is never followed into the scan itself or into a
+     * directory listed before, so a cycle ends at the link closing it.
+     */
+    pub fn set_symlink_policy(&self, policy: SymlinkPolicy) {
+        let _ = self.symlinks.lock().map(|mut current| *current = policy);
+    }
+
+    pub fn symlink_policy(&self) -> SymlinkPolicy {
+        self.symlinks
+            .lock()
+            .map_or(SymlinkPolicy::default(), |policy| *policy)
+    }
+
+    /**
+     * the mount points the running or last scan didn't descend into
+     */
+    pub fn skipped_mounts(&self) -> Vec<PathBuf> {
+        self.mounts
+            .lock()
+            .map_or(Vec::new(), |mounts| mounts.clone())
+    }
+
+    /**
+     * when a listing counts as hung and whether to skip it, applies to a running scan too
+     */
+    pub fn set_watchdog(&self, watchdog: Watchdog) {
+        let _ = self.watchdog.lock().map(|mut current| *current = watchdog);
+    }
+
+    pub fn watchdog(&self) -> Watchdog {
+        self.watchdog
+            .lock()
+            .map_or(Watchdog::default(), |watchdog| *watchdog)
+    }
+
+    /**
+     * how often a listing is retried after a transient error, applies from the next directory
+     */
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        let _ = self.retry.lock().map(|mut current| *current = policy);
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry
+            .lock()
+            .map_or(RetryPolicy::default(), |policy| *policy)
+    }
+
+    /**
+     * what the scan leaves out from the next directory on, an invalid glob
+     * keeps the filters in effect
+     */
+    pub fn set_filters(&self, filters: ScanFilters) -> io::Result<()> {
+        let matcher = Arc::new(FilterMatcher::new(filters)?);
+        *self.filters.write().unwrap_or_else(PoisonError::into_inner) = matcher;
+        Ok(())
+    }
+
+    pub fn filters(&self) -> ScanFilters {
+        self.filters
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .filters()
+            .clone()
+    }
+
+    /**
+     * Keep the entries deeper than `max_depth` below the root out of the
+     * tree, their bytes still count in the directory holding them. Applies
+     * from the next listed directory on, `collapse` drops the ones already
+     * scanned.
+     */
+    pub fn set_max_depth(&self, max_depth: Option<usize>) {
+        let _ = self.max_depth.lock().map(|mut depth| *depth = max_depth);
+    }
+
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth.lock().map_or(None, |depth| *depth)
+    }
+
+    /**
+     * Change how hard the scan works the disk, returns the config as applied.
+     * The queue bound and the entry rate apply to a running scan from the
+     * next directory. Fewer workers apply to it too, the ones above the
+     * count wait, more start with the next scan.
+     */
+    pub fn set_config(&self, config: ScannerConfig) -> ScannerConfig {
+        let config = config.normalized();
+        let _ = self.config.lock().map(|mut current| *current = config);
+        let _ = self
+            .queue
+            .lock()
+            .map(|mut queue| queue.set_limit(config.max_queue_depth));
+        let _ = self
+            .pacing
+            .lock()
+            .map(|mut pacing| *pacing = Throttle::per_sec(config.entries_per_sec));
+        config
+    }
+
+    pub fn config(&self) -> ScannerConfig {
+        self.config
+            .lock()
+            .map_or(ScannerConfig::default(), |config| *config)
+    }
+
+    /**
+     * drop the entries scanned deeper than `depth`, returns how many went
+     */
+    pub fn collapse(&self, depth: usize) -> usize {
+        self.files
+            .write()
+            .map_or(0, |mut tree| tree.collapse(depth))
+    }
+
+    /**
+     * the mitigations of memory pressure not in effect yet
+     */
+    pub fn mitigations(&self) -> Vec<Mitigation> {
+        let mut mitigations = vec![];
+        let folding = self
+            .filters()
+            .min_file_size
+            .is_some_and(|min| min >= memory::MITIGATION_MIN_FILE_SIZE);
+        if !folding {
+            mitigations.push(Mitigation::FoldSmallFiles);
+        }
+        if self
+            .max_depth()
+            .is_none_or(|depth| depth > memory::COLLAPSE_DEPTH)
+        {
+            mitigations.push(Mitigation::CollapseDeepLevels);
+        }
+        mitigations
+    }
+
+    /**
+     * Keep the tree of this scanner from growing as fast. Only the scanner
+     * changes, the settings a new session starts with stay as they are.
+     */
+    pub fn mitigate(&self, mitigation: Mitigation) -> io::Result<()> {
+        match mitigation {
+            Mitigation::FoldSmallFiles => {
+                let mut filters = self.filters();
+                filters.min_file_size = Some(
+                    filters
+                        .min_file_size
+                        .unwrap_or_default()
+                        .max(memory::MITIGATION_MIN_FILE_SIZE),
+                );
+                self.set_filters(filters)
+            }
+            Mitigation::CollapseDeepLevels => {
+                self.set_max_depth(Some(memory::COLLAPSE_DEPTH));
+                let dropped = self.collapse(memory::COLLAPSE_DEPTH);
+                info!(
+                    "collapsed {} entries below depth {}",
+                    dropped,
+                    memory::COLLAPSE_DEPTH
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /**
+     * receive the hung listings the watchdog finds, a new call replaces the previous receiver
+     */
+    pub fn stalls(&self) -> mpsc::Receiver<ScanStall> {
+        let (tx, rx) = mpsc::channel(64);
+        let _ = self.stalls.lock().map(|mut stalls| *stalls = Some(tx));
+        rx
+    }
+
+    /**
+     * receive the size changes of the tree, coalesced into one batch per
+     * `DELTA_INTERVAL`, a new call replaces the previous receiver
+     */
+    pub fn size_deltas(&self) -> mpsc::Receiver<SizeDeltaBatch> {
+        let (tx, rx) = mpsc::channel(64);
+        let _ = self.delta_sink.lock().map(|mut sink| *sink = Some(tx));
+        rx
+    }
+
+    /**
+     * the tree before any scan, an unlisted filesystem root
+     */
+    fn empty_tree() -> Tree {
+        Tree::from_node(Node::new(PathBuf::from("/").into_os_string(), true, false))
+    }
+
+    /**
+     * begin scanning `target`, the tree is rebuilt to start at it so a single
+     * folder or an external drive is scanned without walking the whole disk
+     */
+    pub async fn start(&self, target: PathBuf) -> io::Result<mpsc::Receiver<ScanProgress>> {
+        let metadata = fs::metadata(long_path::extended(&target)).await?;
+        if !metadata.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("{} is not a directory", target.display()),
+            ));
+        }
+        let path = PathKey::new(&target).into_path_buf().into_os_string();
+        let root = Self::obtain_file_node(path, &target, &metadata);
+        let _ = self.files.write().map(|mut files| {
+            let capabilities = files.capabilities;
+            *files = Tree::from_node(root);
+            files.capabilities = capabilities;
+        });
+
+        let device = if self.same_filesystem.load(Ordering::Relaxed) {
+            device::id(&target, &metadata)
+        } else {
+            None
+        };
+        let follower = (self.symlink_policy() == SymlinkPolicy::Follow)
+            .then(|| Arc::new(LinkFollower::new(&target, &metadata)));
+        let _ = self.mounts.lock().map(|mut mounts| mounts.clear());
+        let _ = self.links.lock().map(|mut links| links.clear());
+        let _ = self.breakdowns.lock().map(|mut cache| cache.clear());
+        self.unwatch();
+
+        let (tx, rx) = mpsc::channel(1000);
+        // Abort the workers of a previous scan, a dropped handle leaves its task running
+        let _ = self.workers.lock().map(|mut workers| {
+            for worker in workers.drain(..) {
+                worker.abort();
+            }
+        });
+
+        // Process root node to provide initial queue items
+        if let Ok(files) = self.files.read()
+            && let Ok(mut queue) = self.queue.lock()
+        {
+            if let Some(root) = files.root.as_ref() {
+                if let Ok(node) = root.read()
+                    && let Ok(mut prog) = self.progress.lock()
+                {
+                    let path = node.get_path();
+                    prog.current_path = Some(path.clone());
+                    prog.scaned_size = node.size();
+                    prog.is_scanning = true;
+                    queue.push((path, root.clone()));
+                } else {
+                    return Ok(rx);
+                }
+            } else {
+                return Ok(rx);
+            };
+        }
+
+        // items popped and not done yet, together with the queue it tells when nothing is left
+        let busy = Arc::new(AtomicUsize::new(0));
+        let finished = Arc::new(AtomicBool::new(false));
+        let processed = Arc::new(AtomicUsize::new(0));
+        let throttle = Arc::new(Mutex::new(ProgressThrottle::default()));
+        let spawned = self.config().workers;
+        let _ = self
+            .active
+            .lock()
+            .map(|mut active| *active = ActiveWorkers::new(spawned));
+
+        for worker_id in 0..spawned {
+            let queue = Arc::clone(&self.queue);
+            let tree = self.files.clone();
+            let tx = tx.clone();
+            let busy = Arc::clone(&busy);
+            let finished = Arc::clone(&finished);
+            let timings = Arc::clone(&self.timings);
+            let active = Arc::clone(&self.active);
+            let processed = Arc::clone(&processed);
+            let retry = Arc::clone(&self.retry);
+            let filters = Arc::clone(&self.filters);
+            let mounts = Arc::clone(&self.mounts);
+            let links = Arc::clone(&self.links);
+            let max_depth = Arc::clone(&self.max_depth);
+            let follower = follower.clone();
+            let deltas = Arc::clone(&self.deltas);
+            let config = Arc::clone(&self.config);
+            let pacing = Arc::clone(&self.pacing);
+            let throttle = Arc::clone(&throttle);
+            let shared = Arc::clone(&self.progress);
+            let interval = tokio::time::Duration::from_millis(50);
+
+            let worker = tokio::spawn(async move {
+                debug!("Worker {} started", worker_id);
+                let tree = tree.clone();
+
+                loop {
+                    // lowered while scanning, the workers above the count sit it out
+                    let wanted = config.lock().map_or(spawned, |config| config.workers);
+                    if worker_id >= wanted {
+                        if finished.load(Ordering::Acquire) {
+                            break;
+                        }
+                        tokio::time::sleep(interval).await;
+                        continue;
+                    }
+
+                    // counted busy under the queue lock, so an empty queue with nobody busy means done
+                    let item = queue.lock().map_or(None, |mut queue| {
+                        let item = queue.pop();
+                        if item.is_some() {
+                            busy.fetch_add(1, Ordering::AcqRel);
+                        }
+                        item
+                    });
+                    let Some(((path, item), urgent)) = item else {
+                        let drained = queue.lock().is_ok_and(|queue| {
+                            queue.is_empty() && busy.load(Ordering::Acquire) == 0
+                        });
+                        if drained {
+                            if !finished.swap(true, Ordering::AcqRel) {
+                                Self::finish(&tree, &shared, &tx).await;
+                            }
+                            debug!("Worker {} finished", worker_id);
+                            break;
+                        }
+                        debug!("Worker try to wait next job");
+                        tokio::time::sleep(interval).await;
+                        continue;
+                    };
+
+                    let listing = Listing {
+                        retry: retry
+                            .lock()
+                            .map_or(RetryPolicy::default(), |policy| *policy),
+                        filters: filters
+                            .read()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .clone(),
+                        device,
+                        mounts: Arc::clone(&mounts),
+                        links: Arc::clone(&links),
+                        max_depth: max_depth.lock().map_or(None, |depth| *depth),
+                        follower: follower.clone(),
+                        pacing: pacing
+                            .lock()
+                            .is_ok_and(|pacing| pacing.is_limited())
+                            .then(|| Arc::clone(&pacing)),
+                    };
+                    if let Some((children, listed)) = Self::process_scan_item(
+                        worker_id, &path, &item, &timings, &active, &deltas, &listing,
+                    )
+                    .await
+                    {
+                        processed.fetch_add(1, Ordering::Relaxed);
+                        if listed.size > 0 {
+                            // a batch may have gone out while the directory was listed
+                            let _ = deltas.lock().map(|mut deltas| deltas.touch(&path, &item));
+                        }
+                        let progress = Self::update_parent_size(&tree, &item, listed).await;
+                        if let Ok(progress) = progress {
+                            Self::report_progress(&tx, &throttle, &shared, progress);
+                        } else {
+                            warn!("update parent size failed");
+                        }
+
+                        // only after the sizes went up, so a complete parent is final
+                        Tree::settle(&item, children.len());
+                        let _ = queue.lock().map(|mut queue| {
+                            queue.push_children(&path, children, urgent);
+                        });
+                    }
+                    // after the children were queued, they keep the scan going
+                    busy.fetch_sub(1, Ordering::AcqRel);
+                }
+            });
+
+            let _ = self.workers.lock().map(|mut workers| workers.push(worker));
+        }
+
+        let monitor = Self::monitor_throughput(
+            processed,
+            Arc::clone(&self.progress),
+            self.files.clone(),
+            Arc::clone(&self.target),
+            Arc::clone(&finished),
+        );
+        let _ = self.workers.lock().map(|mut workers| workers.push(monitor));
+
+        let watchdog = Self::watch_workers(
+            Arc::clone(&self.active),
+            Arc::clone(&self.queue),
+            self.files.clone(),
+            Arc::clone(&self.watchdog),
+            Arc::clone(&self.stalls),
+            Arc::clone(&finished),
+        );
+        let _ = self
+            .workers
+            .lock()
+            .map(|mut workers| workers.push(watchdog));
+
+        let reporter = Self::report_deltas(
+            Arc::clone(&self.deltas),
+            self.files.clone(),
+            Arc::clone(&self.delta_sink),
+            finished,
+        );
+        let _ = self
+            .workers
+            .lock()
+            .map(|mut workers| workers.push(reporter));
+
+        Ok(rx)
+    }
+
+    /**
+     * send the size changes once per interval, so the frontend animates growing
+     * directories instead of fetching their children again. Flushed with nobody
+     * listening too, the pending sizes must not pile up. Ends with the scan,
+     * after the last batch
+     */
+    fn report_deltas(
+        deltas: Arc<Mutex<PendingDeltas>>,
+        tree: FileTree,
+        sink: Arc<Mutex<Option<Sender<SizeDeltaBatch>>>>,
+        finished: Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(delta::DELTA_INTERVAL).await;
+                let last = finished.load(Ordering::Acquire);
+                let batch = tree.read().ok().and_then(|tree| {
+                    let mut deltas = deltas.lock().ok()?;
+                    (!deltas.is_empty()).then(|| deltas.flush(&tree))
+                });
+                if let Some(batch) = batch.filter(|batch| !batch.deltas.is_empty())
+                    && let Some(tx) = sink.lock().ok().and_then(|tx| tx.clone())
+                {
+                    let _ = tx.try_send(batch);
+                }
+                if last {
+                    break;
+                }
+            }
+        })
+    }
+
+    /**
+     * report listings which stopped getting entries, a worker stuck on a dying disk or
+     * a dead network mount would otherwise hold the scan forever. With `skip` set the
+     * directory is dropped like `skip_subtree` and its worker moves on
+     */
+    fn watch_workers(
+        active: Arc<Mutex<ActiveWorkers>>,
+        queue: Arc<Mutex<ScanQueue>>,
+        tree: FileTree,
+        watchdog: Arc<Mutex<Watchdog>>,
+        stalls: Arc<Mutex<Option<Sender<ScanStall>>>>,
+        finished: Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let interval = tokio::time::Duration::from_secs(1);
+
+            loop {
+                tokio::time::sleep(interval).await;
+                if finished.load(Ordering::Acquire) {
+                    break;
+                }
+                let watchdog = watchdog
+                    .lock()
+                    .map_or(Watchdog::default(), |watchdog| *watchdog);
+                let stalled = active
+                    .lock()
+                    .map_or(vec![], |mut active| active.stalled(watchdog.stall_after()));
+
+                for listing in stalled {
+                    let node = watchdog
+                        .skip
+                        .then(|| {
+                            tree.read()
+                                .map_or(None, |tree| tree.get_node(&listing.path))
+                        })
+                        .flatten();
+                    let skipped = node
+                        .is_some_and(|node| Self::skip_node(&queue, &node, &listing.path).is_ok());
+                    if skipped {
+                        listing.abandon.notify_one();
+                    }
+                    warn!(
+                        "worker {} stalled on {} for {:?}, skipped: {}",
+                        listing.worker,
+                        listing.path.display(),
+                        listing.idle,
+                        skipped
+                    );
+
+                    let stall = ScanStall {
+                        worker: listing.worker,
+                        path: listing.path,
+                        idle_secs: listing.idle.as_secs(),
+                        skipped,
+                    };
+                    if let Some(tx) = stalls.lock().ok().and_then(|tx| tx.clone()) {
+                        let _ = tx.try_send(stall);
+                    }
+                }
+            }
+        })
+    }
+
+    /**
+     * watch the scan rate and, when it drops sharply, name the processes competing for the disk.
+     * Every tick also refreshes the totals and the estimate against the scan target
+     */
+    fn monitor_throughput(
+        processed: Arc<AtomicUsize>,
+        progress: Arc<Mutex<ScanProgress>>,
+        tree: FileTree,
+        target: Arc<Mutex<Option<ScanTarget>>>,
+        finished: Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let interval = tokio::time::Duration::from_secs(2);
+            let mut monitor = ThroughputMonitor::default();
+            let mut system = sysinfo::System::new();
+            let mut last = 0;
+
+            loop {
+                tokio::time::sleep(interval).await;
+                if finished.load(Ordering::Acquire) {
+                    break;
+                }
+                let current = processed.load(Ordering::Relaxed);
+                let rate = (current - last) as f64 / interval.as_secs_f64();
+                last = current;
+
+                // refresh every tick, disk usage is reported as delta since the previous refresh
+                let (sampled, processes) = tokio::task::spawn_blocking(move || {
+                    let processes = sample_disk_heavy_processes(&mut system);
+                    (system, processes)
+                })
+                .await
+                .unwrap_or_default();
+                system = sampled;
+
+                let busy_hint = if monitor.observe(rate) {
+                    debug!(
+                        "scan throughput dropped to {:.1}/s, busy: {:?}",
+                        rate, processes
+                    );
+                    hint(&processes)
+                } else {
+                    None
+                };
+
+                let totals = tree.read().ok().and_then(|tree| {
+                    let root = tree.root.as_ref()?.read().ok()?;
+                    Some((root.count(), root.size(), root.complete))
+                });
+                let target = target.lock().ok().and_then(|target| *target);
+                let _ = progress.lock().map(|mut progress| {
+                    progress.hint = busy_hint;
+                    if let Some((entries, size, complete)) = totals {
+                        progress.scaned_files = entries;
+                        progress.scaned_size = size;
+                        Self::estimate(&mut progress, target, complete, started.elapsed());
+                    }
+                });
+            }
+        })
+    }
+
+    /**
+     * send the counts a worker just updated, merged into the shared progress so
+     * the hint and the estimates of the monitor go along. A full channel drops
+     * the update, a slow listener must not hold the workers up
+     */
+    fn report_progress(
+        tx: &Sender<ScanProgress>,
+        throttle: &Mutex<ProgressThrottle>,
+        shared: &Mutex<ScanProgress>,
+        progress: ScanProgress,
+    ) {
+        let due = throttle
+            .lock()
+            .is_ok_and(|mut throttle| throttle.due(Instant::now(), progress.scaned_files));
+        if !due {
+            return;
+        }
+        let merged = shared.lock().map_or(progress.clone(), |mut shared| {
+            shared.scaned_files = progress.scaned_files;
+            shared.scaned_size = progress.scaned_size;
+            shared.current_path = progress.current_path;
+            shared.clone()
+        });
+        let _ = tx.try_send(merged);
+    }
+
+    /**
+     * mark the scan done and send its totals, the channel closes once every
+     * worker returned
+     */
+    async fn finish(tree: &FileTree, progress: &Mutex<ScanProgress>, tx: &Sender<ScanProgress>) {
+        info!("scan finished");
+        let totals = tree.read().ok().and_then(|tree| {
+            let root = tree.root.as_ref()?.read().ok()?;
+            Some((root.count(), root.size()))
+        });
+        let last = progress.lock().ok().map(|mut progress| {
+            progress.is_scanning = false;
+            progress.current_path = None;
+            progress.hint = None;
+            if let Some((entries, size)) = totals {
+                progress.scaned_files = entries;
+                progress.scaned_size = size;
+            }
+            progress.percent = Some(100.0);
+            progress.remaining_secs = Some(0);
+            progress.clone()
+        });
+        if let Some(last) = last {
+            let _ = tx.send(last).await;
+        }
+    }
+
+    fn estimate(
+        progress: &mut ScanProgress,
+        target: Option<ScanTarget>,
+        complete: bool,
+        elapsed: Duration,
+    ) {
+        if complete {
+            progress.percent = Some(100.0);
+            progress.remaining_secs = Some(0);
+            return;
+        }
+
+        let fraction =
+            target.map(|target| target.fraction(progress.scaned_files, progress.scaned_size));
+        progress.percent = fraction.map(|fraction| fraction * 100.0);
+        progress.remaining_secs = fraction
+            .and_then(|fraction| eta::remaining(fraction, elapsed))
+            .map(|remaining| remaining.as_secs());
+    }
+
+    pub async fn stop_scanning(&self) {
+        info!("Stopping scan...");
+
+        // Clear the queue
+        let _ = self.queue.lock().map(|mut queue| queue.clear());
+
+        // Abort all workers
+        let _ = self.workers.lock().map(|mut workers| {
+            for worker in workers.drain(..) {
+                worker.abort();
+            }
+        });
+
+        let _ = self.active.lock().map(|mut active| active.clear());
+
+        // Reset progress
+        let _ = self.progress.lock().map(|mut prog| {
+            prog.is_scanning = false;
+            prog.current_path = None;
+        });
+    }
+
+    async fn process_scan_item(
+        worker_id: usize,
+        path: &Path,
+        item: &TreeNode,
+        timings: &Mutex<SlowDirectories>,
+        active: &Mutex<ActiveWorkers>,
+        deltas: &Mutex<PendingDeltas>,
+        listing: &Listing,
+    ) -> Option<(Vec<TreeNode>, Growth)> {
+        let inserted = item;
+
+        // claim the listing, the same directory may be queued in both lanes
+        let is_directory = inserted
+            .write()
+            .is_ok_and(|mut node| node.is_directory && !std::mem::replace(&mut node.listed, true));
+
+        if is_directory {
+            let path = path.to_path_buf();
+            let started = Instant::now();
+            let abandon = active
+                .lock()
+                .map(|mut active| active.begin(worker_id, path.clone()))
+                .ok();
+            let before = inserted
+                .read()
+                .map_or(Growth::default(), |node| Growth::of(&node));
+            let beat = || {
+                let _ = active.lock().map(|mut active| active.beat(worker_id));
+                // before the entry adds to the directory, the delta starts from the old size
+                let _ = deltas
+                    .lock()
+                    .map(|mut deltas| deltas.touch(&path, inserted));
+            };
+            let list = Self::process_directory(path.clone(), inserted, listing, beat);
+            let children = match abandon {
+                Some(abandon) => tokio::select! {
+                    children = list => children,
+                    _ = abandon.notified() => {
+                        // the watchdog skipped it, keep the entries listed before it hung
+                        let listed = inserted
+                            .read()
+                            .map_or(Growth::default(), |node| Growth::of(&node).since(before));
+                        Ok((Vec::new(), listed))
+                    }
+                },
+                None => list.await,
+            };
+            let _ = active.lock().map(|mut active| active.end(worker_id));
+            let entries = inserted.read().map_or(0, |node| node.children.len());
+            let _ = timings
+                .lock()
+                .map(|mut timings| timings.record(path, started.elapsed(), entries));
+            if children.is_err() {
+                // an unreadable directory won't get any better, don't hold its parents up
+                Tree::settle(inserted, 0);
+            }
+            children.ok()
+        } else {
+            None
+        }
+    }
+
+    async fn should_exit_scan(queue: &Arc<Mutex<ScanQueue>>) -> bool {
+        let queue_size = queue.lock().map_or(0, |queue| queue.len());
+        debug!("should exit scan size:{}", queue_size);
+        queue_size == 0
+    }
+
+    /**
+     * obtain file node from scan queue item and metadata
+     * @param item
+     * @param metadata
+     * @return
+     */
+    fn obtain_file_node(name: OsString, path: &Path, metadata: &Metadata) -> Node {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+
+        let created = metadata
+            .created()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+
+        let mut node = Node::new(name, metadata.is_dir(), metadata.is_symlink());
+        // a directory weighs what its entries do, as the native listing has it
+        if !metadata.is_dir() {
+            node.set_size(metadata.len() as usize);
+            node.set_allocated_size(allocation::size_on_disk(path, metadata) as usize);
+        }
+        node.file_id = FileId::from_metadata(metadata);
+        node.modified = modified;
+        node.created = created;
+        node.locks = LockFlags::of(metadata);
+        node
+    }
+
+    /**
+     * list a directory into its node, returns the subdirectories to queue and
+     * the bytes its entries added to the node. Transient errors are retried
+     * with the retry policy of `listing`, a permanent one ends the listing with
+     * what was read so far. Entries its filters exclude are passed over.
+     */
+    async fn process_directory(
+        dir_path: PathBuf,
+        dir_node: &TreeNode,
+        listing: &Listing,
+        beat: impl Fn(),
+    ) -> Result<(Vec<TreeNode>, Growth), String> {
+        // a followed link is judged by the metadata of its target, only std reads that
+        if native_dir::SUPPORTED && listing.follower.is_none() {
+            match Self::process_native(&dir_path, dir_node, listing, &beat).await {
+                Err(err) if err.kind() == io::ErrorKind::Unsupported => {}
+                result => return result.map_err(|e| format!("{:?}", e)),
+            }
+        }
+
+        let policy = &listing.retry;
+        let extended = long_path::extended(&dir_path);
+        let mut entries = match retry::run(policy, || fs::read_dir(&extended)).await {
+            Ok(entries) => entries,
+            Err(e) => return Err(format!("{:?}", e)),
+        };
+
+        let mut fill = Fill::new(dir_node, listing);
+        while let Some(entry) = Self::next_entry(&dir_path, &mut entries, policy).await {
+            Self::pace(listing).await;
+            beat();
+            let path = dir_path.join(entry.file_name());
+            if listing.filters.is_excluded(&path) {
+                continue;
+            }
+            let metadata = retry::run(policy, || entry.metadata()).await;
+            let file_type = retry::run(policy, || entry.file_type()).await;
+
+            if let (Ok(file_type), Ok(metadata)) = (file_type, metadata) {
+                // a followed link is listed like the directory it leads to, under its own name
+                let followed = match &listing.follower {
+                    Some(follower) if file_type.is_symlink() => follower.follow(&path),
+                    Some(follower) if file_type.is_dir() => {
+                        follower.visit(&path, &metadata);
+                        None
+                    }
+                    _ => None,
+                };
+                let is_dir = file_type.is_dir() || followed.is_some();
+                let metadata = followed.unwrap_or(metadata);
+                let mut file_node = Self::obtain_file_node(entry.file_name(), &path, &metadata);
+                file_node.is_link |= file_type.is_symlink();
+                let device = if is_dir && listing.device.is_some() {
+                    device::id(&path, &metadata)
+                } else {
+                    None
+                };
+                let link = if is_dir {
+                    None
+                } else {
+                    device::hardlink(&metadata)
+                };
+                fill.add(path, file_node, is_dir, device, link);
+            }
+        }
+
+        Ok(fill.finish())
+    }
+
+    /**
+     * `process_directory` with `NativeDir`, the attributes of a batch of
+     * entries come with their names instead of a stat for each. Unsupported
+     * when the platform or the volume has no such listing.
+     */
+    async fn process_native(
+        dir_path: &Path,
+        dir_node: &TreeNode,
+        listing: &Listing,
+        beat: &impl Fn(),
+    ) -> io::Result<(Vec<TreeNode>, Growth)> {
+        let policy = &listing.retry;
+        let extended = long_path::extended(dir_path).into_owned();
+        let dir = retry::run(policy, || {
+            let extended = extended.clone();
+            async move {
+                tokio::task::spawn_blocking(move || NativeDir::open(&extended))
+                    .await
+                    .map_err(io::Error::other)?
+            }
+        })
+        .await?;
+        let dir = Arc::new(Mutex::new(dir));
+
+        let mut fill = Fill::new(dir_node, listing);
+        let mut first = true;
+        loop {
+            let batch = retry::run(policy, || {
+                let dir = Arc::clone(&dir);
+                async move {
+                    tokio::task::spawn_blocking(move || {
+                        dir.lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .next_batch()
+                    })
+                    .await
+                    .map_err(io::Error::other)?
+                }
+            })
+            .await;
+            let batch = match batch {
+                Ok(Some(batch)) => batch,
+                Ok(None) => break,
+                // the volume can't list in bulk, nothing was added yet
+                Err(err) if err.kind() == io::ErrorKind::Unsupported && first => {
+                    return Err(err);
+                }
+                Err(err) => {
+                    warn!("listing {} cut short: {:?}", dir_path.display(), err);
+                    break;
+                }
+            };
+
+            first = false;
+            for entry in batch {
+                Self::pace(listing).await;
+                beat();
+                let path = dir_path.join(&entry.name);
+                if listing.filters.is_excluded(&path) {
+                    continue;
+                }
+                let is_dir = entry.is_dir();
+                let device = (is_dir && listing.device.is_some()).then_some(entry.id.volume);
+                let link = entry.hardlink();
+                fill.add(path, Self::native_file_node(entry), is_dir, device, link);
+            }
+        }
+
+        Ok(fill.finish())
+    }
+
+    /**
+     * the node `obtain_file_node` makes from the metadata of the entry
+     */
+    fn native_file_node(entry: NativeEntry) -> Node {
+        let is_dir = entry.is_dir();
+        let is_link = entry.kind == EntryKind::Symlink;
+        let mut node = Node::new(entry.name, is_dir, is_link);
+        node.set_size(entry.len as usize);
+        node.set_allocated_size(entry.allocated as usize);
+        node.file_id = Some(entry.id);
+        node.modified = entry.modified;
+        node.created = entry.created;
+        node.locks = entry.locks;
+        node
+    }
+
+    /**
+     * wait for the entry rate of the config, when it has one
+     */
+    async fn pace(listing: &Listing) {
+        let wait = listing
+            .pacing
+            .as_ref()
+            .and_then(|pacing| pacing.lock().map_or(None, |mut pacing| pacing.delay(1)));
+        if let Some(wait) = wait {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    async fn next_entry(
+        dir_path: &Path,
+        entries: &mut fs::ReadDir,
+        policy: &RetryPolicy,
+    ) -> Option<fs::DirEntry> {
+        let mut backoff = policy.backoff();
+        loop {
+            match entries.next_entry().await {
+                Ok(entry) => return entry,
+                Err(err) => match backoff.retry(&err) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => {
+                        warn!("listing {} cut short: {:?}", dir_path.display(), err);
+                        return None;
+                    }
+                },
+            }
+        }
+    }
+
+    /**
+     * add the `listed` bytes of a freshly listed node to its ancestors, its own
+     * size already reached the parent when the parent was listed
+     */
+    async fn update_parent_size(
+        tree: &FileTree,
+        node: &TreeNode,
+        listed: Growth,
+    ) -> Result<ScanProgress, String> {
+        let age = node
+            .read()
+            .map_or(Err("file node value fetch failed"), |node| Ok(node.age()))?;
+
+        // the totals on the way are atomic, workers bubble up side by side
+        Tree::bubble(node, listed, &age);
+
+        if let Some(root) = tree.read().map_or(None, |tree| tree.root.clone())
+            && let Ok(root) = root.read()
+            && let Ok(node) = node.read()
+        {
+            Ok(ScanProgress {
+                scaned_files: root.count(),
+                scaned_size: root.size(),
+                current_path: Some(node.get_path()),
+                is_scanning: true,
+                hint: None,
+                percent: None,
+                remaining_secs: None,
+            })
+        } else {
+            Err("Root node not found".to_string())
+        }
+    }
+
+    /**
+     * scan `path` ahead of the background scan, the results land in the same tree
+     */
+    pub fn scan_on_demand(&self, path: &Path) -> Result<(), String> {
+        if !self.progress.lock().is_ok_and(|prog| prog.is_scanning) {
+            return Err("no scan in progress".to_string());
+        }
+
+        let found = self.files.read().map_or(None, |tree| {
+            path.ancestors()
+                .find_map(|ancestor| Some((ancestor.to_path_buf(), tree.get_node(ancestor)?)))
+        });
+        let found: ScanItem =
+            found.ok_or_else(|| format!("{} is outside of the scan", path.display()))?;
+
+        self.queue
+            .lock()
+            .map_err(|err| err.to_string())?
+            .focus(path.to_path_buf(), found);
+        Ok(())
+    }
+
+    /**
+     * stop descending into `path` without cancelling the rest of the scan, returns
+     * how many queued directories were dropped
+     */
+    pub fn skip_subtree(&self, path: &Path) -> Result<usize, String> {
+        if !self.progress.lock().is_ok_and(|prog| prog.is_scanning) {
+            return Err("no scan in progress".to_string());
+        }
+
+        let node = self
+            .files
+            .read()
+            .map_or(None, |tree| tree.get_node(path))
+            .ok_or_else(|| format!("{} is not in the scan", path.display()))?;
+        Self::skip_node(&self.queue, &node, path)
+    }
+
+    fn skip_node(queue: &Mutex<ScanQueue>, node: &TreeNode, path: &Path) -> Result<usize, String> {
+        // drop the queue first so in-flight listings below can't add to it afterwards
+        let removed = queue
+            .lock()
+            .map_err(|err| err.to_string())?
+            .skip(path.to_path_buf());
+
+        let settle = node
+            .write()
+            .map(|mut node| {
+                node.skipped = true;
+                node.listed = true;
+                !node.complete
+            })
+            .map_err(|err| err.to_string())?;
+        if settle {
+            // what was counted so far is all this subtree will get
+            Tree::settle(node, 0);
+        }
+        Ok(removed)
+    }
+
+    /**
+     * finish the queued directories under `path` before anything else, returns how
+     * many were moved to the front
+     */
+    pub fn prioritize_subtree(&self, path: &Path) -> Result<usize, String> {
+        if !self.progress.lock().is_ok_and(|prog| prog.is_scanning) {
+            return Err("no scan in progress".to_string());
+        }
+
+        Ok(self
+            .queue
+            .lock()
+            .map_err(|err| err.to_string())?
+            .prioritize(path.to_path_buf()))
+    }
+
+    /**
+     * drop a deleted path from the tree and take its size off its ancestors
+     */
+    pub fn forget(&self, path: &Path) -> Option<usize> {
+        let mut tree = self.files.write().ok()?;
+        if let Some(node) = tree.get_node(path) {
+            let _ = self
+                .deltas
+                .lock()
+                .map(|mut deltas| deltas.touch(path, &node));
+        }
+        tree.remove(path).ok().map(|growth| growth.size)
+    }
+
+    /**
+     * run a read-only query against the scanned tree
+     */
+    pub fn with_tree<T, F>(&self, query: F) -> Option<T>
+    where
+        F: FnOnce(&Tree) -> T,
+    {
+        self.files.read().map(|tree| query(&tree)).ok()
+    }
+
+    /**
+     * run a query against the scan results through the backend independent store API
+     */
+    pub fn with_store<T, F>(&self, query: F) -> Option<T>
+    where
+        F: FnOnce(&dyn ScanStore) -> T,
+    {
+        self.files.read().map(|tree| query(&*tree)).ok()
+    }
+
+    /**
+     * size of a scanned node without building its children list
+     */
+    pub fn get_size(&self, path: &Path) -> Option<usize> {
+        self.with_store(|store| store.size_of(path)).flatten()
+    }
+
+    /**
+     * the details of `path` with the page of its children `page` asks for,
+     * only the children of the page are looked up on the disk
+     */
+    pub async fn get_file_node(&self, path: &Path, page: &ChildPage) -> Option<FileDetails> {
+        debug!("enter get file node for {:?}", path.display());
+        let mut details = self.with_store(|store| store.details(path)).flatten()?;
+        details.page_children(page);
+        // a snapshot describes another disk, the files here are not the ones it lists
+        if !self.is_snapshot() {
+            details.probe_quarantine();
+            details.resolve_links();
+        }
+        Some(details)
+    }
+
+    pub async fn get_progress(&self) -> Result<ScanProgress, String> {
+        self.progress
+            .lock()
+            .map_or(Err("Progress not initialized".to_string()), |prog| {
+                Ok(prog.clone())
+            })
+    }
+
+    pub async fn clear(&self) {
+        debug!("clear scaner data");
+        self.stop_scanning().await;
+        let _ = self.queue.lock().map(|mut node| node.clear());
+        let _ = self.files.write().map(|mut tree| {
+            *tree = Self::empty_tree();
+        });
+
+        let _ = self.progress.lock().map(|mut prog| prog.reset());
+        let _ = self.timings.lock().map(|mut timings| timings.clear());
+        let _ = self.deltas.lock().map(|mut deltas| deltas.clear());
+        let _ = self.breakdowns.lock().map(|mut cache| cache.clear());
+        self.unwatch();
+        let _ = self
+            .source
+            .write()
+            .map(|mut source| *source = ScanSource::Live);
+    }
+
+    pub fn source(&self) -> ScanSource {
+        self.source
+            .read()
+            .map_or(ScanSource::Live, |source| source.clone())
+    }
+
+    /**
+     * what the scanned volume supports, none until `set_capabilities`
+     */
+    pub fn capabilities(&self) -> Option<VolumeCapabilities> {
+        self.with_tree(|tree| tree.capabilities()).flatten()
+    }
+
+    /**
+     * record the probed capabilities of the scanned volume, cleared with the tree
+     */
+    pub fn set_capabilities(&self, capabilities: VolumeCapabilities) {
+        let _ = self
+            .files
+            .write()
+            .map(|mut tree| tree.set_capabilities(capabilities));
+    }
+
+    /**
+     * browsing an imported snapshot, nothing may touch the disk or the tree
+     */
+    pub fn is_snapshot(&self) -> bool {
+        self.source
+            .read()
+            .is_ok_and(|source| matches!(*source, ScanSource::Snapshot(_)))
+    }
+
+    /**
+     * the next `limit` queued directories and what every worker is listing
+     */
+    pub fn get_queue(&self, limit: usize) -> QueueSnapshot {
+        let (pending, next) = self
+            .queue
+            .lock()
+            .map_or((0, vec![]), |queue| (queue.len(), queue.peek(limit)));
+        QueueSnapshot {
+            pending,
+            next,
+            workers: self.active.lock().map_or(vec![], |active| active.states()),
+        }
+    }
+
+    /**
+     * package the scanned tree and its reports for `export_session`
+     */
+    pub fn export_archive(&self) -> Option<SessionArchive> {
+        let (entries, (_, _, categories)) = self.with_tree(|tree| {
+            (
+                snapshot::flatten(tree),
+                dashboard::summarize(tree, &tree.root_path().unwrap_or_default()),
+            )
+        })?;
+        Some(SessionArchive::new(
+            entries,
+            self.get_slow_directories(SLOW_DIRECTORY_CAPACITY),
+            categories,
+        ))
+    }
+
+    /**
+     * stop any running scan and browse an imported tree instead
+     */
+    pub async fn import_tree(
+        &self,
+        info: SessionInfo,
+        tree: Tree,
+        slow_directories: Vec<DirectoryTiming>,
+    ) {
+        self.clear().await;
+        let _ = self.files.write().map(|mut files| *files = tree);
+        let _ = self
+            .source
+            .write()
+            .map(|mut source| *source = ScanSource::Snapshot(info));
+        let _ = self.timings.lock().map(|mut timings| {
+            for timing in slow_directories {
+                timings.record(
+                    timing.path,
+                    Duration::from_millis(timing.millis),
+                    timing.entries,
+                );
+            }
+        });
+    }
+
+    /**
+     * the directories which took longest to enumerate, slowest first
+     */
+    pub fn get_slow_directories(&self, limit: usize) -> Vec<DirectoryTiming> {
+        self.timings
+            .lock()
+            .map_or(vec![], |timings| timings.slowest(limit))
+    }
+
+    pub async fn is_scanning(&self) -> bool {
+        self.progress.lock().is_ok_and(|prog| prog.is_scanning)
+    }
+}
+
+impl Drop for Scanner {
+    fn drop(&mut self) {
+        let _ = self.workers.lock().map(|workers| {
+            for worker in workers.iter() {
+                worker.abort();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::{Fixture, FixtureSpec, temp_path};
+    use std::collections::HashMap;
+
+    /**
+     * scan `root` until the whole tree is complete
+     */
+    async fn scan(root: &Path) -> Scanner {
+        let scanner = Scanner::new(4);
+        let _rx = scanner.start(root.to_path_buf()).await.unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while !scanner
+            .with_tree(|tree| tree.root.clone())
+            .flatten()
+            .is_some_and(|root| root.read().is_ok_and(|root| root.complete))
+        {
+            assert!(Instant::now() < deadline, "scan did not complete");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        scanner.stop_scanning().await;
+        scanner
+    }
+
+    /**
+     * size of every scanned directory by full path, and how many entries were found
+     */
+    fn scanned(scanner: &Scanner, root: &Path) -> (HashMap<PathBuf, usize>, usize) {
+        let mut sizes = HashMap::new();
+        let mut entries = 0;
+        let mut stack = vec![(
+            root.to_path_buf(),
+            scanner
+                .with_tree(|tree| tree.root.clone())
+                .flatten()
+                .unwrap(),
+        )];
+        while let Some((path, handle)) = stack.pop() {
+            let node = handle.read().unwrap();
+            if node.is_directory {
+                sizes.insert(path.clone(), node.size());
+            }
+            for child in node.children.iter().map(|id| handle.at(*id)) {
+                let name = child.read().unwrap().path.clone();
+                entries += 1;
+                stack.push((path.join(name), child));
+            }
+        }
+        (sizes, entries)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_totals_match_fixture() {
+        let fixture = Fixture::generate("scan-totals", FixtureSpec::default()).unwrap();
+        let scanner = scan(&fixture.root).await;
+
+        let (sizes, entries) = scanned(&scanner, &fixture.root);
+        assert_eq!(entries, fixture.entries);
+        assert_eq!(sizes.len(), fixture.sizes.len());
+        for (dir, size) in sizes {
+            assert_eq!(
+                Some(size),
+                fixture.size_of(&dir),
+                "size of {}",
+                dir.display()
+            );
+            let savings = scanner
+                .with_tree(|tree| tree.get_node(&dir))
+                .flatten()
+                .map(|node| node.read().unwrap().hardlink_savings());
+            assert_eq!(
+                savings,
+                fixture.savings.get(&dir).copied(),
+                "savings of {}",
+                dir.display()
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_single_level_fixture() {
+        let spec = FixtureSpec {
+            depth: 0,
+            seed: 42,
+            ..FixtureSpec::default()
+        };
+        let fixture = Fixture::generate("scan-flat", spec).unwrap();
+        let scanner = scan(&fixture.root).await;
+
+        let (sizes, _) = scanned(&scanner, &fixture.root);
+        assert_eq!(
+            sizes.get(&fixture.root).copied(),
+            fixture.size_of(&fixture.root)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_past_max_path() {
+        let spec = FixtureSpec {
+            depth: 4,
+            directories: 1,
+            files: 1,
+            padding: 80,
+            ..FixtureSpec::default()
+        };
+        let fixture = Fixture::generate("scan-long-paths", spec).unwrap();
+        let deepest = fixture.sizes.keys().max_by_key(|dir| dir.as_os_str().len());
+        assert!(deepest.unwrap().as_os_str().len() > 260);
+
+        let scanner = scan(&fixture.root).await;
+        let (sizes, entries) = scanned(&scanner, &fixture.root);
+        assert_eq!(entries, fixture.entries);
+        assert_eq!(
+            sizes.get(&fixture.root).copied(),
+            fixture.size_of(&fixture.root)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_reports_progress() {
+        let fixture = Fixture::generate("scan-progress", FixtureSpec::default()).unwrap();
+        let scanner = Scanner::new(4);
+        let mut rx = scanner.start(fixture.root.clone()).await.unwrap();
+
+        let progress = tokio::time::timeout(Duration::from_secs(30), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(progress.is_scanning);
+        assert!(progress.scaned_files > 0);
+        assert!(progress.current_path.unwrap().starts_with(&fixture.root));
+        scanner.stop_scanning().await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_completes_and_closes_channel() {
+        let fixture = Fixture::generate("scan-complete", FixtureSpec::default()).unwrap();
+        let scanner = Scanner::new(4);
+        let mut rx = scanner.start(fixture.root.clone()).await.unwrap();
+
+        let mut last = None;
+        let drained = tokio::time::timeout(Duration::from_secs(30), async {
+            while let Some(progress) = rx.recv().await {
+                last = Some(progress);
+            }
+        })
+        .await;
+        assert!(drained.is_ok(), "scan did not complete");
+
+        let last = last.unwrap();
+        assert!(!last.is_scanning);
+        assert_eq!(last.percent, Some(100.0));
+        assert_eq!(Some(last.scaned_size), fixture.size_of(&fixture.root));
+        assert!(!scanner.is_scanning().await);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_excluded_entries_left_out() {
+        let root = temp_path("scan-filters");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        std::fs::write(root.join("node_modules/pkg/index.js"), "0123456789").unwrap();
+        std::fs::write(root.join("ubuntu.iso"), "1234567").unwrap();
+        std::fs::write(root.join("keep.txt"), "hello").unwrap();
+
+        let scanner = Scanner::new(2);
+        let filters = ScanFilters {
+            exclude: vec!["**/node_modules".to_string(), "*.iso".to_string()],
+            ..ScanFilters::default()
+        };
+        scanner.set_filters(filters.clone()).unwrap();
+        assert_eq!(scanner.filters(), filters);
+        let mut rx = scanner.start(root.clone()).await.unwrap();
+        while rx.recv().await.is_some() {}
+
+        let (sizes, entries) = scanned(&scanner, &root);
+        assert_eq!(entries, 1);
+        assert_eq!(sizes.get(&root), Some(&5));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_collapsed_levels_leave_no_nodes() {
+        let root = temp_path("collapsed-levels");
+        let _ = std::fs::remove_dir_all(&root);
+        let mut deep = root.join("kept");
+        for level in 0..6 {
+            deep.push(format!("level{}", level));
+            std::fs::create_dir_all(deep.join("sibling")).unwrap();
+            std::fs::write(deep.join("file"), "bytes").unwrap();
+        }
+
+        let scanner = Scanner::new(2);
+        scanner.set_max_depth(Some(1));
+        let mut rx = scanner.start(root.clone()).await.unwrap();
+        while rx.recv().await.is_some() {}
+
+        // the root and the folder at the collapsed depth, nothing below lingers in the arena
+        let (sizes, entries) = scanned(&scanner, &root);
+        assert_eq!(entries, 1);
+        assert_eq!(sizes.get(&root.join("kept")), Some(&30));
+        let tree = scanner
+            .with_tree(|tree| tree.root.clone())
+            .flatten()
+            .unwrap();
+        assert!(tree.read().unwrap().complete);
+        assert_eq!(tree.live(), entries + 1);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_small_files_folded_into_directory() {
+        let root = temp_path("min-file-size");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("cache")).unwrap();
+        for index in 0..10 {
+            std::fs::write(root.join(format!("cache/{}.tmp", index)), "tiny").unwrap();
+        }
+        std::fs::write(root.join("video.mp4"), vec![0u8; 4096]).unwrap();
+
+        let scanner = Scanner::new(2);
+        scanner
+            .set_filters(ScanFilters {
+                min_file_size: Some(1024),
+                ..ScanFilters::default()
+            })
+            .unwrap();
+        let mut rx = scanner.start(root.clone()).await.unwrap();
+        while rx.recv().await.is_some() {}
+
+        let (sizes, entries) = scanned(&scanner, &root);
+        // the folder and the video, the tiny files only in their bytes
+        assert_eq!(entries, 2);
+        let cache = sizes.get(&root.join("cache")).copied().unwrap();
+        assert!(cache >= 40);
+        assert_eq!(sizes.get(&root), Some(&(cache + 4096)));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_hardlinks_counted_once() {
+        let root = temp_path("hardlinks");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("snapshot")).unwrap();
+        std::fs::write(root.join("photo.raw"), vec![0u8; 1000]).unwrap();
+        std::fs::hard_link(root.join("photo.raw"), root.join("snapshot/photo.raw")).unwrap();
+
+        let scanner = Scanner::new(2);
+        let mut rx = scanner.start(root.clone()).await.unwrap();
+        while rx.recv().await.is_some() {}
+
+        let details = scanner
+            .get_file_node(&root, &ChildPage::default())
+            .await
+            .unwrap();
+        assert_eq!(details.hardlink_savings, 1000);
+        let (sizes, entries) = scanned(&scanner, &root);
+        assert_eq!(entries, 3);
+        let snapshot = sizes[&root.join("snapshot")];
+        // the link in the root is listed first and counts, the other one adds nothing
+        assert_eq!(sizes[&root], snapshot + 1000);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_same_filesystem_keeps_local_entries() {
+        let root = temp_path("same-filesystem");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        std::fs::write(root.join("nested/a.txt"), "hello").unwrap();
+
+        let scanner = Scanner::new(2);
+        scanner.set_same_filesystem(true);
+        let mut rx = scanner.start(root.clone()).await.unwrap();
+        while rx.recv().await.is_some() {}
+
+        let (sizes, entries) = scanned(&scanner, &root);
+        assert_eq!(entries, 2);
+        let file = std::fs::symlink_metadata(root.join("nested/a.txt")).unwrap();
+        assert_eq!(sizes.get(&root), Some(&(file.len() as usize)));
+        assert!(scanner.skipped_mounts().is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_links_followed_once() {
+        let base = temp_path("follow");
+        let _ = std::fs::remove_dir_all(&base);
+        let (root, outside) = (base.join("root"), base.join("outside"));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("big.bin"), vec![0u8; 1000]).unwrap();
+        std::os::unix::fs::symlink(&outside, outside.join("loop")).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("out")).unwrap();
+
+        let length = |path: PathBuf| std::fs::symlink_metadata(path).unwrap().len() as usize;
+
+        let scanner = scan(&root).await;
+        let details = scanner
+            .get_file_node(&root, &ChildPage::default())
+            .await
+            .unwrap();
+        assert_eq!(details.size, length(root.join("out")));
+        let link = &details.children.unwrap()[0];
+        assert!(link.is_link && !link.is_directory);
+        let resolved = std::fs::canonicalize(&outside).unwrap();
+        assert_eq!(
+            link.link_target.as_deref(),
+            Some(&*resolved.to_string_lossy())
+        );
+
+        let scanner = Scanner::new(2);
+        scanner.set_symlink_policy(SymlinkPolicy::Follow);
+        let mut rx = scanner.start(root.clone()).await.unwrap();
+        while rx.recv().await.is_some() {}
+        let details = scanner
+            .get_file_node(&root, &ChildPage::default())
+            .await
+            .unwrap();
+        // the loop back into the followed directory stays a link
+        assert_eq!(
+            details.size,
+            length(outside.join("big.bin")) + length(outside.join("loop"))
+        );
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_allocated_size_reaches_root() {
+        let root = temp_path("allocated");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("vm")).unwrap();
+        let disk = std::fs::File::create(root.join("vm/disk.img")).unwrap();
+        disk.set_len(64 * 1024 * 1024).unwrap();
+
+        let scanner = Scanner::new(2);
+        let mut rx = scanner.start(root.clone()).await.unwrap();
+        while rx.recv().await.is_some() {}
+
+        let details = scanner
+            .get_file_node(&root, &ChildPage::default())
+            .await
+            .unwrap();
+        assert!(details.size >= 64 * 1024 * 1024);
+        // the sparse image takes hardly anything
+        assert!(details.allocated_size < 1024 * 1024);
+        let vm = &details.children.unwrap()[0];
+        assert_eq!(vm.allocated_size, details.allocated_size);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_progress_throttled() {
+        let mut throttle = ProgressThrottle::default();
+        let start = Instant::now();
+        assert!(throttle.due(start, 10));
+        assert!(!throttle.due(start + Duration::from_millis(50), 20));
+        assert!(throttle.due(start + PROGRESS_INTERVAL, 30));
+        // a burst of entries goes out before the interval is over
+        assert!(throttle.due(start + PROGRESS_INTERVAL, 30 + PROGRESS_ENTRIES));
+        assert!(!throttle.due(start + PROGRESS_INTERVAL, 40 + PROGRESS_ENTRIES));
+    }
+}
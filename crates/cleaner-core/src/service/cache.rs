@@ -0,0 +1,711 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::PoisonError,
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{
+    file_id::FileId,
+    filters::FilterMatcher,
+    fingerprint::mtime,
+    locks::LockFlags,
+    long_path, lossless,
+    models::{AgeBuckets, now_secs},
+    service::Scanner,
+    snapshot::{self, SnapshotEntry},
+    tree::node::Node,
+};
+
+const MAGIC: &[u8; 4] = b"CLSC";
+/**
+ * bumped whenever the cache layout changes, an older cache is rescanned
+ */
+pub const CACHE_FORMAT: u32 = 1;
+/**
+ * an absent parent, time or id, no real value gets this far
+ */
+const NONE: u64 = u64::MAX;
+/**
+ * saved scans kept of one root, the oldest go first
+ */
+pub const KEEP_PER_ROOT: usize = 5;
+
+/**
+ * A finished scan kept in the app data dir so the next start shows it
+ * without scanning. The entries are flat in depth first order like an
+ * exported session, written in a compact binary layout instead of JSON.
+ */
+#[derive(Debug, Clone)]
+pub struct ScanCache {
+    pub root: PathBuf,
+    pub saved_at: u64,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+/**
+ * a scan saved to or restored from the cache
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedScan {
+    /// names the saved scan for `diff_scans`
+    pub id: String,
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8
+    pub raw_path: Option<String>,
+    pub saved_at: u64,
+    pub entries: usize,
+    pub size: usize,
+    /// directories listed again after restoring because they changed since
+    pub refreshed: usize,
+}
+
+fn put(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn take(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn put_option(writer: &mut impl Write, value: Option<u64>) -> io::Result<()> {
+    put(writer, value.unwrap_or(NONE))
+}
+
+fn take_option(reader: &mut impl Read) -> io::Result<Option<u64>> {
+    Ok(Some(take(reader)?).filter(|value| *value != NONE))
+}
+
+/**
+ * a name as UTF-8, or tagged as the base64 of its raw bytes when it isn't
+ */
+fn put_name(writer: &mut impl Write, name: &OsStr) -> io::Result<()> {
+    let (tag, text) = match name.to_str() {
+        Some(text) => (0u8, text.to_string()),
+        None => (1u8, lossless::encode(Path::new(name))),
+    };
+    writer.write_all(&[tag])?;
+    writer.write_all(&(text.len() as u32).to_le_bytes())?;
+    writer.write_all(text.as_bytes())
+}
+
+fn take_name(reader: &mut impl Read) -> io::Result<OsString> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid name in scan cache");
+    let mut head = [0; 5];
+    reader.read_exact(&mut head)?;
+    let len = u32::from_le_bytes([head[1], head[2], head[3], head[4]]) as usize;
+    // a corrupted length ends the read instead of allocating it
+    let mut bytes = Vec::new();
+    reader.by_ref().take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(invalid());
+    }
+    let text = String::from_utf8(bytes).map_err(|_| invalid())?;
+    match head[0] {
+        0 => Ok(OsString::from(text)),
+        1 => lossless::decode(&text).map(PathBuf::into_os_string),
+        _ => Err(invalid()),
+    }
+}
+
+fn put_entry(writer: &mut impl Write, entry: &SnapshotEntry) -> io::Result<()> {
+    put_option(writer, entry.parent.map(|parent| parent as u64))?;
+    put_name(writer, &entry.name)?;
+    put(writer, entry.size as u64)?;
+    put(writer, entry.allocated_size as u64)?;
+    put(writer, entry.hardlink_savings as u64)?;
+    put_option(writer, entry.file_id.map(|id| id.volume))?;
+    put(writer, entry.file_id.map_or(0, |id| id.file))?;
+    put_option(writer, entry.modified)?;
+    put_option(writer, entry.created)?;
+    let flags = [
+        entry.is_directory,
+        entry.is_link,
+        entry.complete,
+        entry.skipped,
+        entry.locks.read_only,
+        entry.locks.system,
+        entry.locks.user_immutable,
+        entry.locks.system_immutable,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0u8, |flags, (bit, set)| flags | ((*set as u8) << bit));
+    writer.write_all(&[flags])?;
+    for bytes in [
+        entry.age.recent,
+        entry.age.months,
+        entry.age.half_year,
+        entry.age.stale,
+    ] {
+        put(writer, bytes as u64)?;
+    }
+    Ok(())
+}
+
+fn take_entry(reader: &mut impl Read) -> io::Result<SnapshotEntry> {
+    let parent = take_option(reader)?.map(|parent| parent as usize);
+    let name = take_name(reader)?;
+    let (size, allocated_size, hardlink_savings) = (
+        take(reader)? as usize,
+        take(reader)? as usize,
+        take(reader)? as usize,
+    );
+    let volume = take_option(reader)?;
+    let file = take(reader)?;
+    let (modified, created) = (take_option(reader)?, take_option(reader)?);
+    let mut flags = [0; 1];
+    reader.read_exact(&mut flags)?;
+    let flag = |bit: u8| flags[0] & (1 << bit) != 0;
+    let age = AgeBuckets {
+        recent: take(reader)? as usize,
+        months: take(reader)? as usize,
+        half_year: take(reader)? as usize,
+        stale: take(reader)? as usize,
+    };
+    Ok(SnapshotEntry {
+        parent,
+        name,
+        size,
+        allocated_size,
+        hardlink_savings,
+        file_id: volume.map(|volume| FileId { volume, file }),
+        is_directory: flag(0),
+        is_link: flag(1),
+        modified,
+        created,
+        locks: LockFlags {
+            read_only: flag(4),
+            system: flag(5),
+            user_immutable: flag(6),
+            system_immutable: flag(7),
+        },
+        age,
+        complete: flag(2),
+        skipped: flag(3),
+    })
+}
+
+impl ScanCache {
+    /**
+     * the scan root hashed, what the ids of its saved scans start with
+     */
+    fn digest(root: &Path) -> String {
+        blake3::hash(root.as_os_str().as_encoded_bytes()).to_hex()[..16].to_string()
+    }
+
+    /**
+     * the saved scan among others, its root and when it was saved
+     */
+    pub fn id(&self) -> String {
+        format!("{}-{}", Self::digest(&self.root), self.saved_at)
+    }
+
+    /**
+     * where this cache is kept inside `dir`, one file per root and save
+     */
+    pub fn file_in(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("scan-{}.bin", self.id()))
+    }
+
+    /**
+     * the file of the saved scan `id` inside `dir`, none for anything but an id
+     */
+    pub fn file_of(dir: &Path, id: &str) -> Option<PathBuf> {
+        let (digest, saved_at) = id.split_once('-')?;
+        let valid = digest.len() == 16
+            && digest.bytes().all(|byte| byte.is_ascii_hexdigit())
+            && saved_at.parse::<u64>().is_ok();
+        valid.then(|| dir.join(format!("scan-{}.bin", id)))
+    }
+
+    /**
+     * the saved scans in `dir` with when they were saved, of `root` only if
+     * given, the newest first
+     */
+    pub fn saved(dir: &Path, root: Option<&Path>) -> Vec<(PathBuf, u64)> {
+        let prefix = root.map(Self::digest);
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut saved: Vec<(PathBuf, u64)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                let id = name.to_str()?.strip_prefix("scan-")?.strip_suffix(".bin")?;
+                let (digest, saved_at) = id.split_once('-')?;
+                if prefix.as_deref().is_some_and(|prefix| prefix != digest) {
+                    return None;
+                }
+                Some((entry.path(), saved_at.parse().ok()?))
+            })
+            .collect();
+        saved.sort_by_key(|(_, saved_at)| Reverse(*saved_at));
+        saved
+    }
+
+    /**
+     * delete the saved scans of `root` past the newest `keep`, returns how many went
+     */
+    pub fn prune(dir: &Path, root: &Path, keep: usize) -> usize {
+        Self::saved(dir, Some(root))
+            .into_iter()
+            .skip(keep)
+            .filter(|(path, _)| fs::remove_file(path).is_ok())
+            .count()
+    }
+
+    /**
+     * what the saved scan at `path` holds, reading its header and root entry only
+     */
+    pub fn peek(path: &Path) -> io::Result<CachedScan> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let (saved_at, root, count) = Self::read_header(&mut reader)?;
+        let size = match count {
+            0 => 0,
+            _ => take_entry(&mut reader)?.size,
+        };
+        let cache = Self {
+            root,
+            saved_at,
+            entries: Vec::new(),
+        };
+        Ok(CachedScan {
+            entries: count,
+            size,
+            ..cache.info()
+        })
+    }
+
+    pub fn info(&self) -> CachedScan {
+        CachedScan {
+            id: self.id(),
+            raw_path: lossless::raw_if_lossy(&self.root),
+            path: self.root.clone(),
+            saved_at: self.saved_at,
+            entries: self.entries.len(),
+            size: self.entries.first().map_or(0, |root| root.size),
+            refreshed: 0,
+        }
+    }
+
+    /**
+     * write the cache next to `path` first, a crash while writing keeps the
+     * previous cache
+     */
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let partial = path.with_extension("partial");
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&CACHE_FORMAT.to_le_bytes())?;
+        put(&mut writer, self.saved_at)?;
+        put_name(&mut writer, self.root.as_os_str())?;
+        put(&mut writer, self.entries.len() as u64)?;
+        for entry in &self.entries {
+            put_entry(&mut writer, entry)?;
+        }
+        writer.into_inner()?.sync_all()?;
+        fs::rename(&partial, path)
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let (saved_at, root, count) = Self::read_header(&mut reader)?;
+        // the count may be corrupted, the entries themselves are what runs out
+        let mut entries = Vec::with_capacity(count.min(1 << 20));
+        for _ in 0..count {
+            entries.push(take_entry(&mut reader)?);
+        }
+        Ok(Self {
+            root,
+            saved_at,
+            entries,
+        })
+    }
+
+    /**
+     * when the cache was saved, its root and how many entries follow
+     */
+    fn read_header(reader: &mut impl Read) -> io::Result<(u64, PathBuf, usize)> {
+        let mut head = [0; 8];
+        reader.read_exact(&mut head)?;
+        if &head[0..4] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid scan cache",
+            ));
+        }
+        let format = u32::from_le_bytes([head[4], head[5], head[6], head[7]]);
+        if format != CACHE_FORMAT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported scan cache format {}", format),
+            ));
+        }
+
+        let saved_at = take(reader)?;
+        let root = PathBuf::from(take_name(reader)?);
+        let count = take(reader)? as usize;
+        Ok((saved_at, root, count))
+    }
+}
+
+/**
+ * whether a cached child is a directory, its size and its mtime
+ */
+type Cached = (bool, usize, Option<u64>);
+
+/**
+ * what listing a directory again found, read before the tree is locked
+ */
+struct Relisting {
+    /// the entries of the directory, new and changed ones, to replace the cached ones
+    fresh: Vec<Node>,
+    /// the cached entries gone from the disk or replaced by a fresh one
+    gone: Vec<OsString>,
+    /// everything below the new directories, each entry after its parent
+    below: Vec<(PathBuf, Node)>,
+    modified: Option<u64>,
+}
+
+impl Scanner {
+    /**
+     * The finished scan as a cache, none while a scan runs, for a snapshot
+     * session or a scan which was cut short.
+     */
+    pub fn cache(&self) -> Option<ScanCache> {
+        if self.is_snapshot() || self.progress.lock().is_ok_and(|prog| prog.is_scanning) {
+            return None;
+        }
+        self.with_tree(|tree| {
+            if !tree.root.as_ref()?.read().ok()?.complete {
+                return None;
+            }
+            Some(ScanCache {
+                root: tree.root_path()?,
+                saved_at: now_secs(),
+                entries: snapshot::flatten(tree),
+            })
+        })
+        .flatten()
+    }
+
+    /**
+     * stop any running scan and browse a cached one as if it was just
+     * scanned, it stays a live session
+     */
+    pub async fn restore(&self, cache: ScanCache) -> Result<(), String> {
+        let tree = snapshot::rebuild(cache.entries)?;
+        self.clear().await;
+        let _ = self.files.write().map(|mut files| *files = tree);
+        Ok(())
+    }
+
+    /**
+     * The directories whose mtime on the disk differs from the tree, which
+     * gained, lost or renamed an entry since they were listed. A directory
+     * gone from the disk is left to the listing of its parent. Blocking.
+     */
+    pub fn stale_directories(&self) -> Vec<PathBuf> {
+        let mut listed = Vec::new();
+        let _ = self.with_tree(|tree| {
+            let Some(root) = tree.root_path() else {
+                return;
+            };
+            tree.walk(&root, |path, node| {
+                if node.is_directory && !node.is_link && !node.skipped {
+                    listed.push((path.clone(), node.modified));
+                }
+            });
+        });
+
+        // stat outside the lock, queries keep going meanwhile
+        listed
+            .into_iter()
+            .filter(|(path, modified)| {
+                fs::symlink_metadata(long_path::extended(path))
+                    .is_ok_and(|metadata| Some(mtime(&metadata)) != *modified)
+            })
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    /**
+     * List the stale directories of a restored scan again, the rest of the
+     * tree is kept as cached. A file rewritten in place leaves the mtime of
+     * its directory alone and keeps its cached size until the next scan.
+     * Returns how many directories were listed. Blocking.
+     */
+    pub fn refresh(&self) -> usize {
+        let filters = self
+            .filters
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let mut stale = self.stale_directories();
+        // parents first, a stale child of a removed directory is gone by its turn
+        stale.sort_by_key(|path| path.components().count());
+        stale
+            .into_iter()
+            .filter(|dir| match self.relist(dir, &filters) {
+                Ok(relisted) => relisted,
+                Err(err) => {
+                    tracing::warn!("refresh {} failed, {}", dir.display(), err);
+                    false
+                }
+            })
+            .count()
+    }
+
+    /**
+     * a node for an entry found by a refresh, listed the way a scan lists it
+     */
+    pub(super) fn fresh_node(
+        name: OsString,
+        path: &Path,
+        metadata: &fs::Metadata,
+        now: u64,
+    ) -> Node {
+        let mut node = Self::obtain_file_node(name, path, metadata);
+        if node.is_directory {
+            node.listed = true;
+            node.complete = true;
+        } else {
+            let mut age = AgeBuckets::default();
+            age.add(node.modified, node.size(), now);
+            node.set_age(age);
+        }
+        node
+    }
+
+    /**
+     * the nodes below a directory the cache didn't know, parents first
+     */
+    pub(super) fn list_new(dir: &Path, filters: &FilterMatcher, now: u64) -> Vec<(PathBuf, Node)> {
+        let mut found = Vec::new();
+        let mut stack = vec![dir.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            let Ok(entries) = fs::read_dir(long_path::extended(&dir)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = dir.join(entry.file_name());
+                if filters.is_excluded(&path) {
+                    continue;
+                }
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                let node = Self::fresh_node(entry.file_name(), &path, &metadata, now);
+                if node.is_directory {
+                    stack.push(path);
+                }
+                found.push((dir.clone(), node));
+            }
+        }
+        found
+    }
+
+    pub(super) fn relist(&self, dir: &PathBuf, filters: &FilterMatcher) -> io::Result<bool> {
+        let cached: Option<HashMap<OsString, Cached>> = self
+            .with_tree(|tree| {
+                let node = tree.get_node(dir)?;
+                Some(
+                    node.children()
+                        .iter()
+                        .filter_map(|child| {
+                            let child = child.read().ok()?;
+                            let kept = (child.is_directory, child.size(), child.modified);
+                            Some((child.path.clone(), kept))
+                        })
+                        .collect(),
+                )
+            })
+            .flatten();
+        // removed along with a parent listed before it
+        let Some(mut cached) = cached else {
+            return Ok(false);
+        };
+
+        let now = now_secs();
+        let metadata = fs::symlink_metadata(long_path::extended(dir))?;
+        let mut relisting = Relisting {
+            fresh: Vec::new(),
+            gone: Vec::new(),
+            below: Vec::new(),
+            modified: Some(mtime(&metadata)),
+        };
+        for entry in fs::read_dir(long_path::extended(dir))?.flatten() {
+            let path = dir.join(entry.file_name());
+            if filters.is_excluded(&path) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let node = Self::fresh_node(entry.file_name(), &path, &metadata, now);
+            match cached.remove(&node.path) {
+                // a directory keeps its subtree, it is refreshed on its own when stale
+                Some((true, _, _)) if node.is_directory => continue,
+                Some((false, size, modified)) if !node.is_directory => {
+                    if (size, modified) == (node.size(), node.modified) {
+                        continue;
+                    }
+                    relisting.gone.push(node.path.clone());
+                }
+                Some(_) => relisting.gone.push(node.path.clone()),
+                None => {}
+            }
+            if node.is_directory && !node.is_link {
+                relisting.below.extend(Self::list_new(&path, filters, now));
+            }
+            relisting.fresh.push(node);
+        }
+        relisting.gone.extend(cached.into_keys());
+
+        let mut tree = self
+            .files
+            .write()
+            .map_err(|err| io::Error::other(err.to_string()))?;
+        for name in relisting.gone {
+            let _ = tree.remove(&dir.join(name));
+        }
+        for node in relisting.fresh {
+            let _ = tree.insert(dir, node);
+        }
+        for (parent, node) in relisting.below {
+            let _ = tree.insert(&parent, node);
+        }
+        if let Some(node) = tree.get_node(dir) {
+            let _ = node
+                .write()
+                .map(|mut node| node.modified = relisting.modified);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_cache_roundtrip() {
+        let mut file = SnapshotEntry {
+            parent: Some(0),
+            name: OsString::from("b.txt"),
+            size: 42,
+            allocated_size: 4096,
+            hardlink_savings: 0,
+            file_id: Some(FileId { volume: 7, file: 9 }),
+            is_directory: false,
+            is_link: false,
+            modified: Some(1_700_000_000),
+            created: None,
+            locks: LockFlags::default(),
+            age: AgeBuckets::default(),
+            complete: true,
+            skipped: false,
+        };
+        file.locks.user_immutable = true;
+        file.age.stale = 42;
+        let mut root = file.clone();
+        root.parent = None;
+        root.name = OsString::from("/data");
+        root.file_id = None;
+        root.is_directory = true;
+        let cache = ScanCache {
+            root: PathBuf::from("/data"),
+            saved_at: 1_700_000_100,
+            entries: vec![root, file],
+        };
+
+        let dir = temp_path("scan-cache");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = cache.file_in(&dir);
+        cache.write(&path).unwrap();
+        assert_eq!(ScanCache::file_of(&dir, &cache.id()), Some(path.clone()));
+        assert_eq!(ScanCache::file_of(&dir, "../settings.json"), None);
+        let peeked = ScanCache::peek(&path).unwrap();
+        assert_eq!((peeked.entries, peeked.size), (2, 42));
+
+        let read = ScanCache::read(&path).unwrap();
+        assert_eq!(
+            (read.root.clone(), read.saved_at),
+            (cache.root.clone(), cache.saved_at)
+        );
+        assert_eq!(
+            serde_json::to_value(&read.entries).unwrap(),
+            serde_json::to_value(&cache.entries).unwrap()
+        );
+
+        // cut short, the last entry misses its age
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 8]).unwrap();
+        assert!(ScanCache::read(&path).is_err());
+
+        let newer = ScanCache {
+            saved_at: cache.saved_at + 60,
+            ..cache.clone()
+        };
+        newer.write(&newer.file_in(&dir)).unwrap();
+        let saved = ScanCache::saved(&dir, Some(&cache.root));
+        assert_eq!(saved[0], (newer.file_in(&dir), newer.saved_at));
+        assert_eq!(ScanCache::prune(&dir, &cache.root, 1), 1);
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_refresh_lists_changed_directories() {
+        let root = temp_path("cache-refresh");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("kept")).unwrap();
+        fs::create_dir_all(root.join("changed")).unwrap();
+        fs::write(root.join("kept/a.bin"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("changed/old.bin"), vec![0u8; 200]).unwrap();
+
+        let scanner = Scanner::new(2);
+        let mut rx = scanner.start(root.clone()).await.unwrap();
+        while rx.recv().await.is_some() {}
+        let cache = scanner.cache().unwrap();
+
+        let restored = Scanner::new(2);
+        restored.restore(cache).await.unwrap();
+        let scanned = scanner.get_size(&root).unwrap();
+        assert_eq!(restored.get_size(&root), Some(scanned));
+
+        fs::remove_file(root.join("changed/old.bin")).unwrap();
+        fs::create_dir_all(root.join("changed/new")).unwrap();
+        fs::write(root.join("changed/new/c.bin"), vec![0u8; 50]).unwrap();
+        // a change within the second of the scan leaves the mtime as cached
+        let later = SystemTime::now() + Duration::from_secs(5);
+        File::open(root.join("changed"))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+
+        assert!(restored.stale_directories().contains(&root.join("changed")));
+        assert!(!restored.stale_directories().contains(&root.join("kept")));
+        assert!(restored.refresh() >= 1);
+        // directories weigh nothing of their own, only the new file adds up
+        assert_eq!(restored.get_size(&root), Some(scanned - 200 + 50));
+        assert_eq!(restored.get_size(&root.join("changed/new/c.bin")), Some(50));
+        assert!(!restored.stale_directories().contains(&root.join("changed")));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use serde::Serialize;
+use specta::Type;
+use tokio::sync::mpsc::Sender;
+
+use crate::{
+    file_id::FileId,
+    hash::HashIndex,
+    long_path,
+    models::FileDetails,
+    service::{FileTree, Scanner},
+    tree::Tree,
+};
+
+/**
+ * bytes read from the start and from the end of a file before it is hashed
+ * completely, files of the same size mostly differ within them
+ */
+pub const PARTIAL_BYTES: u64 = 8 * 1024;
+
+/**
+ * files with the same content, the first one is the one to keep
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub size: u64,
+    /// the full content hash, with the algorithm of the hash index
+    pub hash: String,
+    /// the oldest first
+    pub files: Vec<FileDetails>,
+    /// freed by keeping one of the files
+    pub reclaimable: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateReport {
+    /// the most reclaimable first
+    pub groups: Vec<DuplicateGroup>,
+    /// files sharing their size with another one
+    pub candidates: usize,
+    /// files read completely, the others differed within `PARTIAL_BYTES`
+    pub fully_hashed: usize,
+    pub reclaimable: u64,
+}
+
+/**
+ * the files below the scanned root sharing their size with another one,
+ * from `min_size` bytes on and the largest first. Links and empty files
+ * never count.
+ */
+pub fn size_groups(tree: &Tree, min_size: u64) -> Vec<(u64, Vec<PathBuf>)> {
+    let mut sizes: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    tree.walk(&tree.root_path().unwrap_or_default(), |path, node| {
+        let size = node.size() as u64;
+        if !node.is_directory && !node.is_link && size > 0 && size >= min_size {
+            sizes.entry(size).or_default().push(path.clone());
+        }
+    });
+    let mut groups: Vec<(u64, Vec<PathBuf>)> = sizes
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect();
+    groups.sort_by_key(|(size, _)| Reverse(*size));
+    groups
+}
+
+/**
+ * blake3 of the head and the tail of a file, the whole file when it is short
+ */
+fn partial_hash(path: &Path, size: u64) -> io::Result<blake3::Hash> {
+    let mut file = File::open(long_path::extended(path))?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = Vec::with_capacity(PARTIAL_BYTES as usize);
+    (&mut file).take(PARTIAL_BYTES).read_to_end(&mut buffer)?;
+    hasher.update(&buffer);
+    if size > PARTIAL_BYTES {
+        // the tail never overlaps the head
+        let tail = size.saturating_sub(PARTIAL_BYTES).max(PARTIAL_BYTES);
+        file.seek(SeekFrom::Start(tail))?;
+        buffer.clear();
+        file.take(PARTIAL_BYTES).read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+    Ok(hasher.finalize())
+}
+
+/**
+ * Split files of the same size by their partial hash, then the ones still
+ * together by their full hash. Returns the groups of two or more with the
+ * full hash, and how many files were read completely. Hard links to one
+ * file are no duplicates, only the first of them is hashed.
+ */
+pub fn confirm(
+    index: &HashIndex,
+    size: u64,
+    paths: Vec<PathBuf>,
+) -> (Vec<(String, Vec<PathBuf>)>, usize) {
+    let mut identities: HashSet<FileId> = HashSet::new();
+    let mut partial: HashMap<blake3::Hash, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        let linked = fs::metadata(long_path::extended(&path))
+            .ok()
+            .and_then(|metadata| FileId::of(&path, &metadata))
+            .is_some_and(|id| !identities.insert(id));
+        if linked {
+            continue;
+        }
+        if let Ok(hash) = partial_hash(&path, size) {
+            partial.entry(hash).or_default().push(path);
+        }
+    }
+
+    let mut fully_hashed = 0;
+    let mut full: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for path in partial
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .flatten()
+    {
+        fully_hashed += 1;
+        if let Ok(hash) = index.hash(&path) {
+            full.entry(hash).or_default().push(path);
+        }
+    }
+    let groups = full
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect();
+    (groups, fully_hashed)
+}
+
+impl Scanner {
+    /**
+     * Group the scanned files by content, on as many blocking threads as the
+     * scan has workers. A file counts once it was scanned, duplicates the
+     * disk has beyond the tree aren't found. Every group goes to `found` as
+     * soon as it is confirmed.
+     */
+    pub async fn find_duplicates(
+        &self,
+        index: HashIndex,
+        min_size: u64,
+        found: Option<Sender<DuplicateGroup>>,
+    ) -> DuplicateReport {
+        let mut groups = self
+            .with_tree(|tree| size_groups(tree, min_size))
+            .unwrap_or_default();
+        let candidates = groups.iter().map(|(_, paths)| paths.len()).sum();
+        // popped from the end, the largest sizes first as they reclaim the most
+        groups.reverse();
+        let queue = Arc::new(Mutex::new(groups));
+
+        let mut workers = Vec::new();
+        for _ in 0..self.config().workers {
+            let (queue, index, tree, found) = (
+                Arc::clone(&queue),
+                index.clone(),
+                self.files.clone(),
+                found.clone(),
+            );
+            workers.push(tokio::task::spawn_blocking(move || {
+                let mut confirmed: Vec<DuplicateGroup> = vec![];
+                let mut fully_hashed = 0;
+                loop {
+                    let next = queue.lock().unwrap_or_else(PoisonError::into_inner).pop();
+                    let Some((size, paths)) = next else {
+                        break;
+                    };
+                    let (groups, hashed) = confirm(&index, size, paths);
+                    fully_hashed += hashed;
+                    for (hash, paths) in groups {
+                        let Some(group) = group_of(&tree, size, hash, &paths) else {
+                            continue;
+                        };
+                        if let Some(found) = &found {
+                            let _ = found.blocking_send(group.clone());
+                        }
+                        confirmed.push(group);
+                    }
+                }
+                (confirmed, fully_hashed)
+            }));
+        }
+
+        let mut report = DuplicateReport {
+            candidates,
+            ..DuplicateReport::default()
+        };
+        for worker in workers {
+            if let Ok((groups, fully_hashed)) = worker.await {
+                report.fully_hashed += fully_hashed;
+                report.groups.extend(groups);
+            }
+        }
+        report.groups.sort_by(|a, b| {
+            b.reclaimable
+                .cmp(&a.reclaimable)
+                .then_with(|| a.hash.cmp(&b.hash))
+        });
+        report.reclaimable = report.groups.iter().map(|group| group.reclaimable).sum();
+        report
+    }
+}
+
+fn group_of(tree: &FileTree, size: u64, hash: String, paths: &[PathBuf]) -> Option<DuplicateGroup> {
+    let tree = tree.read().ok()?;
+    let mut files: Vec<FileDetails> = paths
+        .iter()
+        .filter_map(|path| {
+            let node = tree.get_node(path)?;
+            let node = node.read().ok()?;
+            Some(FileDetails::from(&node).at(path.clone()))
+        })
+        .collect();
+    files.sort_by(|a, b| {
+        a.modified
+            .cmp(&b.modified)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    (files.len() > 1).then(|| DuplicateGroup {
+        size,
+        hash,
+        reclaimable: size * (files.len() as u64 - 1),
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+    use std::fs;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_duplicates_confirmed_by_content() {
+        let root = temp_path("dedup");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("backup")).unwrap();
+        let big = vec![7u8; 3 * PARTIAL_BYTES as usize];
+        let mut edited = big.clone();
+        // differs only in the middle, past the partial hash
+        edited[PARTIAL_BYTES as usize + 10] = 8;
+        fs::write(root.join("photo.raw"), &big).unwrap();
+        fs::write(root.join("backup/photo.raw"), &big).unwrap();
+        fs::write(root.join("photo-edited.raw"), &edited).unwrap();
+        fs::write(root.join("a.txt"), "same").unwrap();
+        fs::write(root.join("backup/a.txt"), "same").unwrap();
+        fs::write(root.join("b.txt"), "diff").unwrap();
+
+        let scanner = Scanner::new(2);
+        let mut rx = scanner.start(root.clone()).await.unwrap();
+        while rx.recv().await.is_some() {}
+
+        let (tx, mut streamed) = tokio::sync::mpsc::channel(16);
+        let index = HashIndex::in_memory().unwrap();
+        let report = scanner.find_duplicates(index, 0, Some(tx)).await;
+        assert_eq!(report.candidates, 6);
+        // the edited copy is only told apart by the full hash
+        assert_eq!(report.fully_hashed, 3 + 2);
+        assert_eq!(report.groups.len(), 2);
+        assert_eq!(report.groups[0].size, big.len() as u64);
+        assert_eq!(report.groups[0].files.len(), 2);
+        assert_eq!(report.reclaimable, big.len() as u64 + 4);
+
+        let mut count = 0;
+        while streamed.recv().await.is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 2);
+
+        let larger = scanner
+            .find_duplicates(HashIndex::in_memory().unwrap(), 5, None)
+            .await;
+        assert_eq!(larger.groups.len(), 1);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
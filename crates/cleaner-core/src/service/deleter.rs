@@ -0,0 +1,258 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    deletion::{self, outermost},
+    long_path,
+    service::Scanner,
+};
+
+/**
+ * how often a running batch reports its progress, the last item is always reported
+ */
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum DeleteMode {
+    /// to the trash of the OS, where the user can put it back
+    Trash,
+    Permanent,
+}
+
+/**
+ * how far a batch got, counted in selected paths
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteProgress {
+    pub mode: DeleteMode,
+    pub total: usize,
+    pub done: usize,
+    pub failures: usize,
+    /// bytes of the deleted paths, as scanned
+    pub bytes: u64,
+    pub current_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteFailure {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteReport {
+    pub mode: DeleteMode,
+    pub deleted: Vec<PathBuf>,
+    pub failed: Vec<DeleteFailure>,
+    pub bytes: u64,
+}
+
+/**
+ * Delete `paths` one by one, to the trash or for good. A path inside another
+ * selected one goes with it, a permanently deleted folder is emptied by the
+ * workers of `deletion::delete`. Each deleted path leaves the tree of
+ * `scanner` right away, so the sizes of its ancestors stay correct while the
+ * batch runs. Setting `cancel` stops the batch after the entry at hand, the
+ * paths it didn't get to fail as cancelled. `observe` gets the progress
+ * every `PROGRESS_INTERVAL` and after the last path. Blocking.
+ */
+pub fn delete(
+    scanner: &Scanner,
+    paths: &[PathBuf],
+    mode: DeleteMode,
+    cancel: &AtomicBool,
+    observe: &mut dyn FnMut(&DeleteProgress),
+) -> DeleteReport {
+    let paths = outermost(paths);
+    let mut report = DeleteReport {
+        mode,
+        deleted: vec![],
+        failed: vec![],
+        bytes: 0,
+    };
+    let mut progress = DeleteProgress {
+        mode,
+        total: paths.len(),
+        done: 0,
+        failures: 0,
+        bytes: 0,
+        current_path: None,
+    };
+    let mut reported: Option<Instant> = None;
+
+    for path in paths {
+        // the tree knows the size of a folder without walking it again
+        let size = scanner.get_size(&path).map(|size| size as u64).or_else(|| {
+            fs::symlink_metadata(long_path::extended(&path))
+                .ok()
+                .map(|metadata| metadata.len())
+        });
+        let deleted = if cancel.load(Ordering::Relaxed) {
+            Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"))
+        } else {
+            delete_one(&path, mode, cancel, &mut |removed| {
+                if reported.is_none_or(|reported| reported.elapsed() >= PROGRESS_INTERVAL) {
+                    progress.bytes = report.bytes + removed;
+                    progress.current_path = Some(path.clone());
+                    observe(&progress);
+                    reported = Some(Instant::now());
+                }
+            })
+        };
+        match deleted {
+            Ok(()) => {
+                scanner.forget(&path);
+                report.bytes += size.unwrap_or(0);
+                report.deleted.push(path.clone());
+            }
+            Err(err) => report.failed.push(DeleteFailure {
+                path: path.clone(),
+                reason: err.to_string(),
+            }),
+        }
+
+        progress.done += 1;
+        progress.failures = report.failed.len();
+        progress.bytes = report.bytes;
+        progress.current_path = Some(path);
+        let last = progress.done == progress.total;
+        if last || reported.is_none_or(|reported| reported.elapsed() >= PROGRESS_INTERVAL) {
+            observe(&progress);
+            reported = Some(Instant::now());
+        }
+    }
+    report
+}
+
+/**
+ * `removed` gets the bytes of the files removed so far below a folder
+ * deleted for good
+ */
+fn delete_one(
+    path: &Path,
+    mode: DeleteMode,
+    cancel: &AtomicBool,
+    removed: &mut dyn FnMut(u64),
+) -> io::Result<()> {
+    if mode == DeleteMode::Trash {
+        return trash::delete(path).map_err(io::Error::other);
+    }
+
+    // a missing path fails as itself, not as an entry below it
+    fs::symlink_metadata(long_path::extended(path))?;
+    let subtree = deletion::delete(
+        std::slice::from_ref(&path.to_path_buf()),
+        deletion::WORKERS,
+        false,
+        cancel,
+        &mut |progress| removed(progress.bytes),
+    )
+    .remove(0);
+    if subtree.removed {
+        return Ok(());
+    }
+    Err(match subtree.failures.first() {
+        Some(first) => io::Error::other(format!(
+            "{} entries could not be removed, {}: {}",
+            subtree.failure_count,
+            first.path.display(),
+            first.reason
+        )),
+        None => io::Error::new(io::ErrorKind::Interrupted, "cancelled"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::{Fixture, FixtureSpec, temp_path};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_deleted_paths_leave_tree() {
+        let fixture = Fixture::generate("deleter", FixtureSpec::default()).unwrap();
+        let scanner = Scanner::new(4);
+        let mut rx = scanner.start(fixture.root.clone()).await.unwrap();
+        while rx.recv().await.is_some() {}
+
+        let count = |path: &PathBuf| {
+            scanner
+                .with_tree(|tree| tree.get_node(path))
+                .flatten()
+                .map(|node| node.read().unwrap().count())
+        };
+        let root_size = scanner.get_size(&fixture.root).unwrap();
+        assert_eq!(count(&fixture.root), Some(fixture.entries));
+        let folder = fs::read_dir(&fixture.root)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.is_dir())
+            .unwrap();
+        let folder_size = scanner.get_size(&folder).unwrap();
+        let folder_entries = count(&folder).unwrap();
+        let missing = fixture.root.join("missing");
+
+        let mut reports = vec![];
+        let report = delete(
+            &scanner,
+            &[folder.join("nested"), folder.clone(), missing.clone()],
+            DeleteMode::Permanent,
+            &AtomicBool::new(false),
+            &mut |progress| reports.push(progress.clone()),
+        );
+        assert_eq!(report.deleted, vec![folder.clone()]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].path, missing);
+        assert_eq!(report.bytes, folder_size as u64);
+        assert!(!folder.exists());
+        assert_eq!(
+            scanner.get_size(&fixture.root),
+            Some(root_size - folder_size)
+        );
+        // the folder leaves the counts with everything it held
+        assert_eq!(
+            count(&fixture.root),
+            Some(fixture.entries - folder_entries - 1)
+        );
+
+        let last = reports.last().unwrap();
+        assert_eq!((last.done, last.total, last.failures), (2, 2, 1));
+    }
+
+    #[test]
+    fn test_cancelled_batch_deletes_nothing() {
+        let root = temp_path("deleter-cancelled");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("folder")).unwrap();
+        fs::write(root.join("file.bin"), b"junk").unwrap();
+
+        let scanner = Scanner::new(1);
+        let report = delete(
+            &scanner,
+            &[root.join("folder"), root.join("file.bin")],
+            DeleteMode::Permanent,
+            &AtomicBool::new(true),
+            &mut |_| {},
+        );
+        assert!(report.deleted.is_empty());
+        assert_eq!(report.failed.len(), 2);
+        assert!(
+            report
+                .failed
+                .iter()
+                .all(|failure| failure.reason == "cancelled")
+        );
+        assert!(root.join("folder").exists() && root.join("file.bin").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
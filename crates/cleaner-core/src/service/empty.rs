@@ -0,0 +1,237 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{
+    deletion::outermost,
+    long_path,
+    path_key::PathKey,
+    service::{Scanner, deleter::DeleteFailure},
+    tree::{Tree, node::NodeRef},
+};
+
+/**
+ * a directory without a single file below it
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EmptyDirectory {
+    pub path: PathBuf,
+    /// the empty directories inside it, they go with it
+    pub nested: usize,
+}
+
+/**
+ * the outermost empty directories sharing a parent
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EmptyGroup {
+    pub parent: PathBuf,
+    pub directories: Vec<EmptyDirectory>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EmptyRemoval {
+    /// the selected directories gone with everything below them
+    pub removed: Vec<PathBuf>,
+    /// every directory removed, the nested ones included
+    pub directories: usize,
+    pub failed: Vec<DeleteFailure>,
+}
+
+/**
+ * Whether `node` is a directory without files below it. The outermost empty
+ * directories of the non empty ones below it go to `found`. A directory
+ * which wasn't listed or was skipped may hold anything, it is never empty.
+ */
+fn collect(path: &Path, handle: &NodeRef, found: &mut Vec<EmptyDirectory>) -> bool {
+    let Ok(node) = handle.read() else {
+        return false;
+    };
+    if !node.is_directory || node.is_link {
+        return false;
+    }
+    let mut empty = node.listed && !node.skipped;
+    let mut inner = vec![];
+    for handle in node.children.iter().map(|id| handle.at(*id)) {
+        let Ok(child) = handle.read() else {
+            empty = false;
+            continue;
+        };
+        let child_path = path.join(&child.path);
+        if collect(&child_path, &handle, found) {
+            inner.push(EmptyDirectory {
+                path: child_path,
+                nested: child.count(),
+            });
+        } else {
+            empty = false;
+        }
+    }
+    if !empty {
+        found.extend(inner);
+    }
+    empty
+}
+
+/**
+ * The empty directories below `root`, `root` itself left out. Only the
+ * outermost one of nested empty directories is listed, grouped by their
+ * parent and sorted by path.
+ */
+pub fn find(tree: &Tree, root: &Path) -> Vec<EmptyGroup> {
+    let Some(node) = tree.get_node(root) else {
+        return vec![];
+    };
+    let root = PathKey::new(root).into_path_buf();
+    let mut found = vec![];
+    for handle in node.children() {
+        let Ok(child) = handle.read() else {
+            continue;
+        };
+        let path = root.join(&child.path);
+        if collect(&path, &handle, &mut found) {
+            found.push(EmptyDirectory {
+                path,
+                nested: child.count(),
+            });
+        }
+    }
+
+    let mut groups: BTreeMap<PathBuf, Vec<EmptyDirectory>> = BTreeMap::new();
+    for directory in found {
+        let parent = directory
+            .path
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        groups.entry(parent).or_default().push(directory);
+    }
+    groups
+        .into_iter()
+        .map(|(parent, mut directories)| {
+            directories.sort_by(|a, b| a.path.cmp(&b.path));
+            EmptyGroup {
+                parent,
+                directories,
+            }
+        })
+        .collect()
+}
+
+/**
+ * `path` and the directories below it, when the tree holds no file below it
+ */
+fn empty_below(tree: &Tree, path: &Path) -> Option<Vec<PathBuf>> {
+    let node = tree.get_node(path)?;
+    if !collect(path, &node, &mut vec![]) {
+        return None;
+    }
+    let mut directories = vec![];
+    tree.walk(path, |path, _| directories.push(path.clone()));
+    Some(directories)
+}
+
+/**
+ * Remove the empty directories at `paths` and the ones inside them, the
+ * deepest first. Each one leaves the tree of `scanner` as it goes, so the
+ * counts of its ancestors stay correct. A directory is only removed while
+ * it is empty on the disk too, one which got a file since stops the removal
+ * of its ancestors. Blocking.
+ */
+pub fn remove(scanner: &Scanner, paths: &[PathBuf]) -> EmptyRemoval {
+    let mut report = EmptyRemoval::default();
+    for path in outermost(paths) {
+        let Some(mut directories) = scanner.with_tree(|tree| empty_below(tree, &path)).flatten()
+        else {
+            report.failed.push(DeleteFailure {
+                path,
+                reason: "not an empty directory of the scan".to_string(),
+            });
+            continue;
+        };
+        directories.sort_by_key(|directory| Reverse(directory.components().count()));
+
+        let mut failure = None;
+        for directory in directories {
+            // never recursive, a file which showed up since stays
+            match fs::remove_dir(long_path::extended(&directory)) {
+                Ok(()) => {
+                    scanner.forget(&directory);
+                    report.directories += 1;
+                }
+                Err(err) => {
+                    failure = Some(DeleteFailure {
+                        path: directory,
+                        reason: err.to_string(),
+                    });
+                    break;
+                }
+            }
+        }
+        match failure {
+            Some(failure) => report.failed.push(failure),
+            None => report.removed.push(path),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_empty_directories_found_and_removed() {
+        let root = temp_path("empty");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("a/b/c")).unwrap();
+        fs::create_dir_all(root.join("a/b/d")).unwrap();
+        fs::create_dir_all(root.join("kept/e")).unwrap();
+        fs::write(root.join("kept/notes.txt"), "notes").unwrap();
+        fs::create_dir_all(root.join("f")).unwrap();
+
+        let scanner = Scanner::new(2);
+        let mut progress = scanner.start(root.clone()).await.unwrap();
+        while progress.recv().await.is_some() {}
+
+        let groups = scanner.with_tree(|tree| find(tree, &root)).unwrap();
+        let empty = |path: &str, nested| EmptyDirectory {
+            path: root.join(path),
+            nested,
+        };
+        assert_eq!(
+            groups,
+            vec![
+                EmptyGroup {
+                    parent: root.clone(),
+                    directories: vec![empty("a", 3), empty("f", 0)],
+                },
+                EmptyGroup {
+                    parent: root.join("kept"),
+                    directories: vec![empty("kept/e", 0)],
+                },
+            ]
+        );
+
+        let entries = scanner.with_tree(|tree| tree.size()).unwrap();
+        let report = remove(&scanner, &[root.join("a"), root.join("kept")]);
+        assert_eq!(report.removed, vec![root.join("a")]);
+        assert_eq!(report.directories, 4);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].path, root.join("kept"));
+        assert!(!root.join("a").exists());
+        assert!(root.join("kept/notes.txt").exists());
+        assert_eq!(scanner.with_tree(|tree| tree.size()), Some(entries - 4));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::PoisonError,
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{
+    dashboard::{Category, CategoryUsage},
+    lossless,
+    service::Scanner,
+    tree::Tree,
+};
+
+/**
+ * how many extensions a breakdown lists, the smaller ones still count in
+ * their category
+ */
+pub const EXTENSION_LIMIT: usize = 50;
+
+/**
+ * the files of one extension below a folder
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionUsage {
+    /// lowercase and without the dot, empty for files without one
+    pub extension: String,
+    pub category: Category,
+    pub size: usize,
+    pub files: usize,
+}
+
+/**
+ * what kinds of files take the space below a folder, for a pie chart
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeBreakdown {
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8
+    pub raw_path: Option<String>,
+    pub size: usize,
+    pub files: usize,
+    /// largest first
+    pub categories: Vec<CategoryUsage>,
+    /// the `EXTENSION_LIMIT` largest, largest first
+    pub extensions: Vec<ExtensionUsage>,
+}
+
+/**
+ * what a cached breakdown was computed from, the subtree with the same size
+ * and entries holds the same files
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    size: usize,
+    count: usize,
+}
+
+/**
+ * breakdowns of complete folders, reused until their subtree changes
+ */
+#[derive(Debug, Default)]
+pub(crate) struct BreakdownCache {
+    breakdowns: HashMap<PathBuf, (Stamp, TypeBreakdown)>,
+}
+
+impl BreakdownCache {
+    pub(crate) fn clear(&mut self) {
+        self.breakdowns.clear();
+    }
+}
+
+fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default()
+}
+
+/**
+ * the breakdown of the files below `path`, walking its whole subtree
+ */
+pub fn breakdown(tree: &Tree, path: &Path) -> TypeBreakdown {
+    let mut categories: HashMap<Category, (usize, usize)> = HashMap::new();
+    let mut extensions: HashMap<String, ExtensionUsage> = HashMap::new();
+    tree.walk(path, |file, node| {
+        if node.is_directory {
+            return;
+        }
+        let category = Category::of(file);
+        let totals = categories.entry(category).or_default();
+        totals.0 += node.size();
+        totals.1 += 1;
+
+        let extension = extension_of(file);
+        let usage = extensions
+            .entry(extension.clone())
+            .or_insert_with(|| ExtensionUsage {
+                extension,
+                category,
+                size: 0,
+                files: 0,
+            });
+        usage.size += node.size();
+        usage.files += 1;
+    });
+
+    let mut categories: Vec<CategoryUsage> = categories
+        .into_iter()
+        .map(|(category, (size, files))| CategoryUsage {
+            category,
+            size,
+            files,
+        })
+        .collect();
+    categories.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| b.files.cmp(&a.files)));
+    let mut extensions: Vec<ExtensionUsage> = extensions.into_values().collect();
+    extensions.sort_by(|a, b| {
+        b.size
+            .cmp(&a.size)
+            .then_with(|| a.extension.cmp(&b.extension))
+    });
+    extensions.truncate(EXTENSION_LIMIT);
+
+    TypeBreakdown {
+        raw_path: lossless::raw_if_lossy(path),
+        path: path.to_path_buf(),
+        size: categories.iter().map(|usage| usage.size).sum(),
+        files: categories.iter().map(|usage| usage.files).sum(),
+        categories,
+        extensions,
+    }
+}
+
+impl Scanner {
+    /**
+     * The breakdown of the files below `path`, none when it wasn't scanned.
+     * A complete folder is walked once, later calls reuse the breakdown until
+     * its size or its entries change.
+     */
+    pub fn type_breakdown(&self, path: &PathBuf) -> Option<TypeBreakdown> {
+        let tree = self.files.read().ok()?;
+        let node = tree.get_node(path)?;
+        let (stamp, complete) = node.read().map_or((None, false), |node| {
+            let stamp = Stamp {
+                size: node.size(),
+                count: node.count(),
+            };
+            (Some(stamp), node.complete)
+        });
+        let stamp = stamp?;
+
+        let mut cache = self
+            .breakdowns
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some((cached, breakdown)) = cache.breakdowns.get(path)
+            && *cached == stamp
+        {
+            return Some(breakdown.clone());
+        }
+        let computed = breakdown(&tree, path);
+        // a folder still being scanned changes with every listing
+        if complete {
+            cache
+                .breakdowns
+                .insert(path.clone(), (stamp, computed.clone()));
+        }
+        Some(computed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::node::Node;
+    use std::ffi::OsString;
+
+    #[test]
+    fn test_breakdown_by_category_and_extension() {
+        let file = |name: &str, size: usize| {
+            let node = Node::new(OsString::from(name), false, false);
+            node.set_size(size);
+            node
+        };
+        let root = PathBuf::from("/");
+        let mut tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let _ = tree.insert(&root, Node::new(OsString::from("media"), true, false));
+        let media = PathBuf::from("/media");
+        let _ = tree.insert(&media, file("trip.MP4", 700));
+        let _ = tree.insert(&media, file("party.mkv", 200));
+        let _ = tree.insert(&media, file("cover.jpg", 50));
+        let _ = tree.insert(&root, file("README", 5));
+
+        let all = breakdown(&tree, &root);
+        assert_eq!((all.size, all.files), (955, 4));
+        assert_eq!(all.categories[0].category, Category::Video);
+        assert_eq!((all.categories[0].size, all.categories[0].files), (900, 2));
+        assert_eq!(all.extensions[0].extension, "mp4");
+        let none = all.extensions.last().unwrap();
+        assert_eq!(
+            (none.extension.as_str(), none.category),
+            ("", Category::Other)
+        );
+
+        let media = breakdown(&tree, &media);
+        assert_eq!((media.size, media.files), (950, 3));
+    }
+}
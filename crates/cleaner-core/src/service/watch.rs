@@ -0,0 +1,372 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::PoisonError,
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use specta::Type;
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    delta::SizeDelta, filters::FilterMatcher, long_path, models::now_secs, service::Scanner,
+    tree::node::Node,
+};
+
+/**
+ * how long the events of a burst are gathered before the tree is updated,
+ * an unpacked archive is one update instead of thousands
+ */
+pub const SETTLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/**
+ * what the watcher reports, a path which changed or a hint that events were lost
+ */
+#[derive(Debug)]
+enum Change {
+    Path(PathBuf),
+    Rescan,
+}
+
+/**
+ * The disk watcher of a scanned tree, dropped by the next scan or `clear`.
+ * Its events stop with it.
+ */
+pub(crate) struct TreeWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+/**
+ * the paths reported during one `SETTLE_INTERVAL`
+ */
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ChangeBatch {
+    pub paths: BTreeSet<PathBuf>,
+    /// the OS dropped events, every directory is checked against the disk
+    pub rescan: bool,
+}
+
+/**
+ * the changes of a watched tree, ends when the watcher is dropped
+ */
+pub struct TreeChanges {
+    changes: UnboundedReceiver<Change>,
+}
+
+impl TreeChanges {
+    /**
+     * wait for a change, then gather the ones following it for `SETTLE_INTERVAL`
+     */
+    pub async fn next_batch(&mut self) -> Option<ChangeBatch> {
+        let mut batch = ChangeBatch::default();
+        let add = |batch: &mut ChangeBatch, change| match change {
+            Change::Path(path) => {
+                batch.paths.insert(path);
+            }
+            Change::Rescan => batch.rescan = true,
+        };
+        let first = self.changes.recv().await?;
+        add(&mut batch, first);
+        let settled = Instant::now() + SETTLE_INTERVAL;
+        while let Ok(Some(change)) = tokio::time::timeout_at(settled, self.changes.recv()).await {
+            add(&mut batch, change);
+        }
+        Some(batch)
+    }
+}
+
+/**
+ * what applying a batch of changes did to the tree
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeUpdate {
+    /// directories which gained or lost entries, their children are worth fetching again
+    pub directories: Vec<PathBuf>,
+    /// nodes which grew or shrank, sorted by path so parents come first
+    pub deltas: Vec<SizeDelta>,
+}
+
+impl TreeUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.directories.is_empty() && self.deltas.is_empty()
+    }
+}
+
+impl Scanner {
+    /**
+     * Watch `root` on the disk, the finished scan of it stays accurate when
+     * files change outside the app. Feed each batch of the returned changes
+     * to `apply_changes`. A new scan, `clear` or another call stops the
+     * previous watcher.
+     */
+    pub fn watch(&self, root: &Path) -> io::Result<TreeChanges> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        tracing::warn!("watch tree failed, {}", err);
+                        let _ = tx.send(Change::Rescan);
+                        return;
+                    }
+                };
+                if event.need_rescan() {
+                    let _ = tx.send(Change::Rescan);
+                }
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                for path in event.paths {
+                    let _ = tx.send(Change::Path(path));
+                }
+            })
+            .map_err(io::Error::other)?;
+        // too many directories for the inotify limit ends up here
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(io::Error::other)?;
+
+        let _ = self
+            .watcher
+            .lock()
+            .map(|mut current| *current = Some(TreeWatcher { _watcher: watcher }));
+        Ok(TreeChanges { changes: rx })
+    }
+
+    /**
+     * stop watching the disk, the tree keeps what it last saw
+     */
+    pub fn unwatch(&self) {
+        let _ = self.watcher.lock().map(|mut current| *current = None);
+    }
+
+    /**
+     * Bring the entries at the paths of `batch` in line with the disk:
+     * add the new ones, drop the removed ones, list changed directories
+     * again. A path outside of the tree, excluded or below a skipped
+     * directory is left alone. Blocking.
+     */
+    pub fn apply_changes(&self, batch: &ChangeBatch) -> TreeUpdate {
+        let Some(root) = self.with_tree(|tree| tree.root_path()).flatten() else {
+            return TreeUpdate::default();
+        };
+        let filters = self
+            .filters
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let mut directories = BTreeSet::new();
+        if batch.rescan {
+            directories.extend(self.stale_directories());
+            self.refresh();
+        }
+
+        let mut paths: Vec<&PathBuf> = batch
+            .paths
+            .iter()
+            .filter(|path| path.starts_with(&root) && **path != root)
+            .filter(|path| !filters.is_excluded(path))
+            .collect();
+        // parents first, the entries of a new directory are listed with it
+        paths.sort_by_key(|path| path.components().count());
+        let now = now_secs();
+        for path in paths {
+            match self.sync_entry(path, &filters, now) {
+                Ok(Some(directory)) => {
+                    directories.insert(directory);
+                }
+                Ok(None) => {}
+                Err(err) => tracing::debug!("update {} failed, {}", path.display(), err),
+            }
+        }
+
+        let deltas = self
+            .files
+            .read()
+            .ok()
+            .and_then(|tree| {
+                let mut deltas = self.deltas.lock().ok()?;
+                Some(deltas.flush(&tree).deltas)
+            })
+            .unwrap_or_default();
+        TreeUpdate {
+            directories: directories.into_iter().collect(),
+            deltas,
+        }
+    }
+
+    /**
+     * update the node of `path` from the disk, the directory whose entries
+     * changed when any did
+     */
+    fn sync_entry(
+        &self,
+        path: &PathBuf,
+        filters: &FilterMatcher,
+        now: u64,
+    ) -> io::Result<Option<PathBuf>> {
+        let Some(parent) = path.parent().map(Path::to_path_buf) else {
+            return Ok(None);
+        };
+        let (existing, parent_node) = self
+            .with_tree(|tree| (tree.get_node(path), tree.get_node(&parent)))
+            .unwrap_or_default();
+        let Some(parent_node) = parent_node else {
+            // below a directory the tree doesn't hold, it shows up with it
+            return Ok(None);
+        };
+        if parent_node
+            .read()
+            .is_ok_and(|node| node.skipped || !node.listed)
+        {
+            return Ok(None);
+        }
+
+        let metadata = match fs::symlink_metadata(long_path::extended(path)) {
+            Ok(metadata) => Some(metadata),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        let Some(name) = path.file_name().map(|name| name.to_os_string()) else {
+            return Ok(None);
+        };
+
+        let kept = existing.as_ref().and_then(|node| {
+            node.read()
+                .ok()
+                .map(|node| (node.is_directory, node.size(), node.modified))
+        });
+        let Some(metadata) = metadata else {
+            // removed, and not recreated since
+            if existing.is_some() {
+                self.forget(path);
+                return Ok(Some(parent));
+            }
+            return Ok(None);
+        };
+        let node = Self::fresh_node(name, path, &metadata, now);
+        match kept {
+            // the entries of a directory changed, or only its own metadata
+            Some((true, _, _)) if node.is_directory => {
+                if let Some(existing) = existing.as_ref() {
+                    let _ = self
+                        .deltas
+                        .lock()
+                        .map(|mut deltas| deltas.touch(path, existing));
+                }
+                self.relist(path, filters)?;
+                Ok(Some(path.clone()))
+            }
+            Some((false, size, modified)) if !node.is_directory => {
+                if (size, modified) == (node.size(), node.modified) {
+                    return Ok(None);
+                }
+                self.replace(path, &parent, node, filters, now);
+                Ok(None)
+            }
+            // a file became a directory or the other way round
+            Some(_) => {
+                self.replace(path, &parent, node, filters, now);
+                Ok(Some(parent))
+            }
+            None => {
+                self.replace(path, &parent, node, filters, now);
+                Ok(Some(parent))
+            }
+        }
+    }
+
+    /**
+     * put `node` at `path`, in place of what the tree held there, with the
+     * entries below it when it is a directory
+     */
+    fn replace(
+        &self,
+        path: &Path,
+        parent: &PathBuf,
+        node: Node,
+        filters: &FilterMatcher,
+        now: u64,
+    ) {
+        let below = if node.is_directory && !node.is_link {
+            Self::list_new(path, filters, now)
+        } else {
+            Vec::new()
+        };
+        let Ok(mut tree) = self.files.write() else {
+            return;
+        };
+        let mut deltas = self.deltas.lock().unwrap_or_else(PoisonError::into_inner);
+        match tree.get_node(path) {
+            Some(existing) => {
+                deltas.touch(path, &existing);
+                let _ = tree.remove(path);
+            }
+            None => {
+                if let Some(parent_node) = tree.get_node(parent) {
+                    deltas.touch(parent, &parent_node);
+                }
+            }
+        }
+        let _ = tree.insert(parent, node);
+        for (parent, node) in below {
+            let _ = tree.insert(&parent, node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+    use std::fs;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_changes_applied_to_tree() {
+        let root = temp_path("watch");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("old")).unwrap();
+        fs::write(root.join("old/a.bin"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("kept.bin"), vec![0u8; 10]).unwrap();
+
+        let scanner = Scanner::new(2);
+        let mut progress = scanner.start(root.clone()).await.unwrap();
+        while progress.recv().await.is_some() {}
+        let scanned = scanner.get_size(&root).unwrap();
+
+        // changed outside the app, as the watcher would report it
+        fs::remove_dir_all(root.join("old")).unwrap();
+        fs::create_dir_all(root.join("new/deep")).unwrap();
+        fs::write(root.join("new/deep/b.bin"), vec![0u8; 300]).unwrap();
+        fs::write(root.join("kept.bin"), vec![0u8; 50]).unwrap();
+        let batch = ChangeBatch {
+            paths: [root.join("old"), root.join("old/a.bin"), root.join("new")]
+                .into_iter()
+                .chain([root.join("new/deep/b.bin"), root.join("kept.bin")])
+                .collect(),
+            rescan: false,
+        };
+        let update = scanner.apply_changes(&batch);
+
+        assert!(scanner.get_size(&root.join("old")).is_none());
+        assert_eq!(scanner.get_size(&root.join("new/deep/b.bin")), Some(300));
+        assert_eq!(scanner.get_size(&root.join("kept.bin")), Some(50));
+        assert_eq!(update.directories, vec![root.clone()]);
+        let now = scanner.get_size(&root).unwrap();
+        assert!(now > scanned);
+        let delta = update
+            .deltas
+            .iter()
+            .find(|delta| delta.path == root)
+            .unwrap();
+        assert_eq!(delta.new_size, now as u64);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
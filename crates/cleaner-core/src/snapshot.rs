@@ -0,0 +1,294 @@
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    dashboard::CategoryUsage,
+    file_id::FileId,
+    locks::LockFlags,
+    models::{AgeBuckets, now_secs},
+    timing::DirectoryTiming,
+    tree::{
+        Tree,
+        node::{Node, NodeRef},
+    },
+};
+
+/**
+ * bumped whenever the archive layout changes incompatibly
+ */
+pub const SESSION_FORMAT: u32 = 1;
+
+/**
+ * one tree node, stored flat in depth first order so deep trees don't hit
+ * the deserializer recursion limit
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotEntry {
+    /// index of the parent entry, always lower than this one; none for the root
+    pub parent: Option<usize>,
+    pub name: OsString,
+    pub size: usize,
+    /// missing from archives exported before it was measured
+    #[serde(default)]
+    pub allocated_size: usize,
+    /// missing from archives exported before hard links were told apart
+    #[serde(default)]
+    pub hardlink_savings: usize,
+    /// missing from archives exported before entries were identified
+    #[serde(default)]
+    pub file_id: Option<FileId>,
+    pub is_directory: bool,
+    pub is_link: bool,
+    pub modified: Option<u64>,
+    pub created: Option<u64>,
+    /// missing from archives exported before the flags were scanned
+    #[serde(default)]
+    pub locks: LockFlags,
+    pub age: AgeBuckets,
+    pub complete: bool,
+    pub skipped: bool,
+}
+
+/**
+ * a scan packaged into one file, to review someone else's machine on your own
+ */
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionArchive {
+    pub format: u32,
+    pub exported_at: u64,
+    pub host: Option<String>,
+    pub os: Option<String>,
+    pub entries: Vec<SnapshotEntry>,
+    pub slow_directories: Vec<DirectoryTiming>,
+    pub categories: Vec<CategoryUsage>,
+}
+
+/**
+ * what an imported archive contains, shown before the user browses it
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub path: PathBuf,
+    pub exported_at: u64,
+    pub host: Option<String>,
+    pub os: Option<String>,
+    pub entries: usize,
+    pub size: usize,
+}
+
+/**
+ * where the tree of a session comes from, a snapshot session is read-only
+ */
+#[derive(Debug, Clone, Default, Serialize, Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ScanSource {
+    #[default]
+    Live,
+    Snapshot(SessionInfo),
+}
+
+impl SessionArchive {
+    pub fn new(
+        entries: Vec<SnapshotEntry>,
+        slow_directories: Vec<DirectoryTiming>,
+        categories: Vec<CategoryUsage>,
+    ) -> Self {
+        Self {
+            format: SESSION_FORMAT,
+            exported_at: now_secs(),
+            host: sysinfo::System::host_name(),
+            os: sysinfo::System::long_os_version(),
+            entries,
+            slow_directories,
+            categories,
+        }
+    }
+
+    pub fn info(&self, path: &Path) -> SessionInfo {
+        SessionInfo {
+            path: path.to_path_buf(),
+            exported_at: self.exported_at,
+            host: self.host.clone(),
+            os: self.os.clone(),
+            entries: self.entries.len(),
+            size: self.entries.first().map_or(0, |root| root.size),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self).map_err(io::Error::other)
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let archive: SessionArchive = serde_json::from_reader(reader)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        if archive.format != SESSION_FORMAT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported session format {}", archive.format),
+            ));
+        }
+        Ok(archive)
+    }
+}
+
+/**
+ * the whole tree as flat entries, the root first
+ */
+pub fn flatten(tree: &Tree) -> Vec<SnapshotEntry> {
+    let mut entries = vec![];
+    let Some(root) = tree.root.clone() else {
+        return entries;
+    };
+
+    let mut stack: Vec<(Option<usize>, NodeRef)> = vec![(None, root)];
+    while let Some((parent, handle)) = stack.pop() {
+        let Ok(node) = handle.read() else {
+            continue;
+        };
+
+        let index = entries.len();
+        entries.push(SnapshotEntry {
+            parent,
+            name: node.path.clone(),
+            size: node.size(),
+            allocated_size: node.allocated_size(),
+            hardlink_savings: node.hardlink_savings(),
+            file_id: node.file_id,
+            is_directory: node.is_directory,
+            is_link: node.is_link,
+            modified: node.modified,
+            created: node.created,
+            locks: node.locks,
+            age: node.age(),
+            complete: node.complete,
+            skipped: node.skipped,
+        });
+        for child in node.children.iter().rev() {
+            stack.push((Some(index), handle.at(*child)));
+        }
+    }
+
+    entries
+}
+
+/**
+ * rebuild a tree from flat entries, every node counts as listed so it is never rescanned
+ */
+pub fn rebuild(entries: Vec<SnapshotEntry>) -> Result<Tree, String> {
+    let mut nodes: Vec<NodeRef> = Vec::with_capacity(entries.len());
+    let mut parents: Vec<Option<usize>> = Vec::with_capacity(entries.len());
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        if entry.parent.is_some_and(|parent| parent >= index)
+            || (index > 0) != entry.parent.is_some()
+        {
+            return Err(format!("invalid snapshot entry {}", index));
+        }
+
+        let mut node = Node::new(entry.name, entry.is_directory, entry.is_link);
+        node.set_size(entry.size);
+        node.set_allocated_size(entry.allocated_size);
+        node.set_hardlink_savings(entry.hardlink_savings);
+        node.file_id = entry.file_id;
+        node.modified = entry.modified;
+        node.created = entry.created;
+        node.locks = entry.locks;
+        node.set_age(entry.age);
+        node.complete = entry.complete;
+        node.skipped = entry.skipped;
+        node.listed = true;
+
+        let node = match entry.parent {
+            Some(parent) => {
+                let node = nodes[parent].alloc(node);
+                if let Ok(mut parent) = nodes[parent].write() {
+                    parent.push_child(&node);
+                }
+                node
+            }
+            None => NodeRef::new(node),
+        };
+        nodes.push(node);
+        parents.push(entry.parent);
+    }
+
+    // children come after their parent, so a reverse pass sees every subtree finished
+    for index in (1..nodes.len()).rev() {
+        let count = nodes[index].read().map_or(0, |node| node.total_count());
+        if let Some(parent) = parents[index]
+            && let Ok(parent) = nodes[parent].read()
+        {
+            parent.recount(count);
+        }
+    }
+
+    nodes
+        .into_iter()
+        .next()
+        .map(|root| Tree {
+            root: Some(root),
+            capabilities: None,
+        })
+        .ok_or_else(|| "empty snapshot".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_rebuild_roundtrip() {
+        let root = PathBuf::from("/");
+        let mut tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let _ = tree.insert(&root, Node::new(OsString::from("a"), true, false));
+        let _ = tree.insert(
+            &PathBuf::from("/a"),
+            Node::new(OsString::from("b.txt"), false, false),
+        );
+        let _ = tree.insert(&root, Node::new(OsString::from("c.txt"), false, false));
+
+        let entries = flatten(&tree);
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[2].name, OsString::from("b.txt"));
+        assert_eq!(entries[2].parent, Some(1));
+
+        let rebuilt = rebuild(entries).unwrap();
+        assert_eq!(rebuilt.size(), tree.size());
+        assert!(rebuilt.contains(&PathBuf::from("/a/b.txt")));
+        let node = rebuilt.get_node(&PathBuf::from("/a")).unwrap();
+        assert_eq!(Tree::path_to_root(&node).unwrap(), PathBuf::from("/a"));
+    }
+
+    #[test]
+    fn test_rebuild_rejects_forward_parent() {
+        let tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let mut entries = flatten(&tree);
+        let mut child = entries[0].clone();
+        child.parent = Some(1);
+        entries.push(child);
+        assert!(rebuild(entries).is_err());
+    }
+
+    #[test]
+    fn test_entry_without_locks_loads() {
+        let tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let mut value = serde_json::to_value(&flatten(&tree)[0]).unwrap();
+        assert!(value.as_object_mut().unwrap().remove("locks").is_some());
+
+        let entry: SnapshotEntry = serde_json::from_value(value).unwrap();
+        assert_eq!(entry.locks, LockFlags::default());
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use specta::Type;
+use tracing::warn;
+
+use crate::{device, long_path};
+
+/**
+ * bytes an operation leaves free on its destination on top of what it
+ * writes, at the least
+ */
+pub const MIN_MARGIN: u64 = 64 * 1024 * 1024;
+
+/**
+ * the share of what an operation writes it leaves free on top, in percent.
+ * Directories, metadata and an archive which hardly compresses take a bit
+ * more than the bytes of the files.
+ */
+pub const MARGIN_PERCENT: u64 = 5;
+
+/**
+ * a destination without room for what an operation is about to write,
+ * found before anything is written
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InsufficientSpace {
+    pub destination: PathBuf,
+    /// bytes the operation writes, with the safety margin
+    pub required: u64,
+    /// bytes free on the volume of the destination
+    pub available: u64,
+    /// how much more has to be freed there
+    pub missing: u64,
+}
+
+impl Display for InsufficientSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "not enough space for {}, {} more bytes are needed ({} required, {} available)",
+            self.destination.display(),
+            self.missing,
+            self.required,
+            self.available
+        )
+    }
+}
+
+impl std::error::Error for InsufficientSpace {}
+
+impl From<InsufficientSpace> for io::Error {
+    fn from(short: InsufficientSpace) -> Self {
+        io::Error::new(io::ErrorKind::StorageFull, short)
+    }
+}
+
+impl InsufficientSpace {
+    /**
+     * the shortage behind `err`, when that is why it failed
+     */
+    pub fn of(err: &io::Error) -> Option<&InsufficientSpace> {
+        err.get_ref()?.downcast_ref()
+    }
+}
+
+/**
+ * `bytes` with the safety margin
+ */
+pub fn with_margin(bytes: u64) -> u64 {
+    bytes.saturating_add((bytes / 100 * MARGIN_PERCENT).max(MIN_MARGIN))
+}
+
+/**
+ * the closest of `path` and its ancestors which exists, where a destination
+ * which is yet to be created lands
+ */
+fn existing(path: &Path) -> &Path {
+    path.ancestors()
+        .map(|ancestor| {
+            if ancestor.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                ancestor
+            }
+        })
+        .find(|ancestor| fs::symlink_metadata(long_path::extended(ancestor)).is_ok())
+        .unwrap_or(path)
+}
+
+/**
+ * bytes free to the user on the volume `path` is or would be created on
+ */
+#[cfg(unix)]
+pub fn available(path: &Path) -> io::Result<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(existing(path).as_os_str().as_bytes())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // 32 bit counts on macOS
+    #[allow(clippy::unnecessary_cast)]
+    Ok((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+}
+
+/**
+ * bytes free to the user on the volume `path` is or would be created on,
+ * quotas included
+ */
+#[cfg(windows)]
+pub fn available(path: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = long_path::extended(existing(path))
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let mut free = 0u64;
+    let read = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut free,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if read == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(free)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn available(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "free space is unknown on this platform",
+    ))
+}
+
+/**
+ * whether `from` and `to`, which need not exist yet, are on the same
+ * volume, so moving one to the other is a rename. Unknown counts as not.
+ */
+pub fn same_volume(from: &Path, to: &Path) -> bool {
+    volume(from).is_some_and(|from| Some(from) == volume(to))
+}
+
+/**
+ * the id of the volume `path` is or would be created on
+ */
+fn volume(path: &Path) -> Option<u64> {
+    let path = existing(path);
+    let metadata = fs::symlink_metadata(long_path::extended(path)).ok()?;
+    device::id(path, &metadata)
+}
+
+/**
+ * Fail with `InsufficientSpace` unless the volume of `destination` has
+ * room for `bytes` and the margin. A volume whose free space can't be
+ * read isn't held against the operation.
+ */
+pub fn ensure(destination: &Path, bytes: u64) -> io::Result<()> {
+    match available(destination) {
+        Ok(available) => check(destination, bytes, available),
+        Err(err) => {
+            warn!("free space of {} unknown, {}", destination.display(), err);
+            Ok(())
+        }
+    }
+}
+
+fn check(destination: &Path, bytes: u64, available: u64) -> io::Result<()> {
+    let required = with_margin(bytes);
+    if required <= available {
+        return Ok(());
+    }
+    Err(InsufficientSpace {
+        destination: destination.to_path_buf(),
+        required,
+        available,
+        missing: required - available,
+    }
+    .into())
+}
+
+/**
+ * `ensure` for every volume `writes` of `(destination, bytes)` go to, the
+ * bytes written to one volume add up
+ */
+pub fn ensure_all(writes: &[(PathBuf, u64)]) -> io::Result<()> {
+    let mut volumes: Vec<(Option<u64>, &Path, u64)> = vec![];
+    for (destination, bytes) in writes {
+        let id = volume(destination);
+        match volumes
+            .iter_mut()
+            .find(|(other, _, _)| id.is_some() && *other == id)
+        {
+            Some((_, _, total)) => *total = total.saturating_add(*bytes),
+            None => volumes.push((id, destination, *bytes)),
+        }
+    }
+    for (_, destination, bytes) in volumes {
+        ensure(destination, bytes)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortage_is_typed() {
+        let destination = Path::new("/backup/photos");
+        assert!(check(destination, 1000, MIN_MARGIN + 1000).is_ok());
+
+        let err = check(destination, 100 * MIN_MARGIN, 50 * MIN_MARGIN).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+        let short = InsufficientSpace::of(&err).unwrap();
+        assert_eq!(short.required, 105 * MIN_MARGIN);
+        assert_eq!(short.missing, 55 * MIN_MARGIN);
+        assert!(InsufficientSpace::of(&io::Error::other("busy")).is_none());
+
+        // a destination yet to be created is measured on the folder it goes in
+        let root = std::env::temp_dir();
+        assert_eq!(existing(&root.join("cleaner-space/a/b")), root.as_path());
+        assert!(same_volume(&root, &root.join("cleaner-space")));
+    }
+}
@@ -0,0 +1,685 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    file_id::FileId,
+    lossless,
+    models::FileDetails,
+    path_key::PathKey,
+    snapshot::SnapshotEntry,
+    tree::{Tree, node::Node},
+};
+
+/**
+ * what a query needs to know about one stored entry
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub size: usize,
+    pub is_directory: bool,
+    pub modified: Option<u64>,
+    pub created: Option<u64>,
+    pub file_id: Option<FileId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FoundFile {
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8
+    pub raw_path: Option<String>,
+    pub size: usize,
+    pub is_directory: bool,
+    pub modified: Option<u64>,
+}
+
+impl FoundFile {
+    fn new(path: &Path, entry: Entry) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            raw_path: lossless::raw_if_lossy(path),
+            size: entry.size,
+            is_directory: entry.is_directory,
+            modified: entry.modified,
+        }
+    }
+}
+
+/**
+ * The largest files offered so far, never more than `limit` of them. A walk
+ * over millions of files only holds the ones it returns, the smallest kept
+ * one is dropped first. Equal sizes are ordered by path.
+ */
+struct LargestFiles {
+    limit: usize,
+    heap: BinaryHeap<Reverse<(usize, Reverse<PathBuf>)>>,
+    entries: HashMap<PathBuf, Entry>,
+}
+
+impl LargestFiles {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::with_capacity(limit.min(4096) + 1),
+            entries: HashMap::new(),
+        }
+    }
+
+    fn offer(&mut self, path: &Path, entry: Entry) {
+        if self.limit == 0 {
+            return;
+        }
+        let ranked = Reverse((entry.size, Reverse(path.to_path_buf())));
+        if self.heap.len() == self.limit {
+            // the smallest kept one at the top, anything not larger is out
+            if self.heap.peek().is_some_and(|smallest| ranked >= *smallest) {
+                return;
+            }
+            if let Some(Reverse((_, Reverse(dropped)))) = self.heap.pop() {
+                self.entries.remove(&dropped);
+            }
+        }
+        self.entries.insert(path.to_path_buf(), entry);
+        self.heap.push(ranked);
+    }
+
+    fn into_vec(mut self) -> Vec<FoundFile> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .filter_map(|Reverse((_, Reverse(path)))| {
+                let entry = self.entries.remove(&path)?;
+                Some(FoundFile::new(&path, entry))
+            })
+            .collect()
+    }
+}
+
+/**
+ * files below it count as tiny, they cost a backup or a scan about as much
+ * time as a large one for hardly any bytes
+ */
+pub const TINY_FILE_SIZE: usize = 32 * 1024;
+
+/**
+ * a directory holding a great many tiny files of its own
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FileCountHotspot {
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8
+    pub raw_path: Option<String>,
+    /// its files below `TINY_FILE_SIZE`, the ones of its subdirectories left out
+    pub tiny_files: usize,
+    /// bytes of the tiny files together
+    pub tiny_size: usize,
+    /// its files and subdirectories
+    pub entries: usize,
+}
+
+/**
+ * a child of a folder with the entries it holds, each one takes an inode
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemCount {
+    #[serde(serialize_with = "lossless::serialize_lossy")]
+    pub path: PathBuf,
+    /// the exact bytes of `path` as base64 when it isn't valid UTF-8
+    pub raw_path: Option<String>,
+    pub size: usize,
+    pub is_directory: bool,
+    /// the entry itself and everything below it
+    pub items: usize,
+}
+
+/**
+ * how a listing of old files is sorted
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AgeOrder {
+    /// the longest untouched first
+    #[default]
+    OldestFirst,
+    NewestFirst,
+    LargestFirst,
+}
+
+/**
+ * one page of the files below a root untouched since a cutoff
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct OldFilesPage {
+    pub files: Vec<FoundFile>,
+    /// the matching files over all pages
+    pub total: usize,
+    /// bytes of the matching files over all pages
+    pub total_size: usize,
+    /// where the next page starts, none after the last one
+    pub next_offset: Option<usize>,
+}
+
+/**
+ * Where scan results are read from. Query commands are written once against
+ * this trait, a backend only has to resolve paths and walk its entries.
+ */
+pub trait ScanStore {
+    fn size_of(&self, path: &Path) -> Option<usize>;
+
+    /**
+     * `path` with its direct children, largest first
+     */
+    fn details(&self, path: &Path) -> Option<FileDetails>;
+
+    /**
+     * visit every entry below `root` (included) with its full path
+     */
+    fn walk(&self, root: &Path, visit: &mut dyn FnMut(&PathBuf, Entry));
+
+    /**
+     * the `limit` largest files below `root`, largest first
+     */
+    fn largest_files(&self, root: &Path, limit: usize) -> Vec<FoundFile> {
+        self.largest_files_above(root, 0, limit)
+    }
+
+    /**
+     * the `limit` largest files below `root` of at least `min_size` bytes,
+     * largest first
+     */
+    fn largest_files_above(&self, root: &Path, min_size: usize, limit: usize) -> Vec<FoundFile> {
+        let mut largest = LargestFiles::new(limit);
+        self.walk(root, &mut |path, entry| {
+            if !entry.is_directory && entry.size >= min_size {
+                largest.offer(path, entry);
+            }
+        });
+        largest.into_vec()
+    }
+
+    /**
+     * like `largest_files` as if the entries under `removed` were gone
+     */
+    fn largest_files_except(
+        &self,
+        root: &Path,
+        removed: &[PathBuf],
+        limit: usize,
+    ) -> Vec<FoundFile> {
+        let mut largest = LargestFiles::new(limit);
+        self.walk(root, &mut |path, entry| {
+            let kept = !removed.iter().any(|removed| path.starts_with(removed));
+            if !entry.is_directory && kept {
+                largest.offer(path, entry);
+            }
+        });
+        largest.into_vec()
+    }
+
+    /**
+     * The files below `root` of at least `min_size` bytes neither modified
+     * nor created since `before`, in unix seconds, sorted by `order`. A file
+     * copied lately keeps its old modification time, its creation time
+     * tells it was touched. Files without a modification time are left out.
+     * Returns `limit` of them from `offset` on.
+     */
+    fn old_files(
+        &self,
+        root: &Path,
+        before: u64,
+        min_size: usize,
+        order: AgeOrder,
+        offset: usize,
+        limit: usize,
+    ) -> OldFilesPage {
+        let mut old: Vec<(u64, FoundFile)> = vec![];
+        self.walk(root, &mut |path, entry| {
+            let Some(modified) = entry.modified else {
+                return;
+            };
+            let touched = modified.max(entry.created.unwrap_or(0));
+            if !entry.is_directory && entry.size >= min_size && touched < before {
+                old.push((touched, FoundFile::new(path, entry)));
+            }
+        });
+
+        let total = old.len();
+        let total_size = old.iter().map(|(_, file)| file.size).sum();
+        match order {
+            AgeOrder::OldestFirst => {
+                old.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.path.cmp(&b.1.path)))
+            }
+            AgeOrder::NewestFirst => {
+                old.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.path.cmp(&b.1.path)))
+            }
+            AgeOrder::LargestFirst => old.sort_by(|a, b| {
+                b.1.size
+                    .cmp(&a.1.size)
+                    .then_with(|| a.1.path.cmp(&b.1.path))
+            }),
+        }
+        let files: Vec<FoundFile> = old
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(_, file)| file)
+            .collect();
+        let end = offset.saturating_add(files.len());
+        OldFilesPage {
+            next_offset: (end < total && !files.is_empty()).then_some(end),
+            files,
+            total,
+            total_size,
+        }
+    }
+
+    /**
+     * where the entry `id` identifies is below `root` now, a file renamed or
+     * moved since it was last seen is still found by what it is
+     */
+    fn locate(&self, root: &Path, id: FileId) -> Option<PathBuf> {
+        let mut found = None;
+        self.walk(root, &mut |path, entry| {
+            if found.is_none() && entry.file_id == Some(id) {
+                found = Some(path.clone());
+            }
+        });
+        found
+    }
+
+    /**
+     * entries below `root` whose name contains `query`, case insensitive, largest first
+     */
+    fn search(&self, root: &Path, query: &str, limit: usize) -> Vec<FoundFile> {
+        let query = query.to_lowercase();
+        let mut found: Vec<FoundFile> = vec![];
+        self.walk(root, &mut |path, entry| {
+            let matches = path
+                .file_name()
+                .map(OsStr::to_string_lossy)
+                .is_some_and(|name| name.to_lowercase().contains(&query));
+            if matches && path != root {
+                found.push(FoundFile::new(path, entry));
+            }
+        });
+
+        found.sort_by_key(|found| Reverse(found.size));
+        found.truncate(limit);
+        found
+    }
+
+    /**
+     * The `limit` children of `path` holding the most entries, most first.
+     * Millions of tiny files exhaust the inodes of a disk while a listing by
+     * size hardly shows them.
+     */
+    fn most_files(&self, path: &Path, limit: usize) -> Vec<ItemCount> {
+        let mut children: HashMap<PathBuf, ItemCount> = HashMap::new();
+        self.walk(path, &mut |entry_path, entry| {
+            let Some(name) = entry_path
+                .strip_prefix(path)
+                .ok()
+                .and_then(|below| below.components().next())
+            else {
+                return;
+            };
+            let child = path.join(name);
+            let count = children.entry(child.clone()).or_insert_with(|| ItemCount {
+                raw_path: lossless::raw_if_lossy(&child),
+                path: child,
+                size: 0,
+                is_directory: false,
+                items: 0,
+            });
+            count.items += 1;
+            if count.path == *entry_path {
+                count.size = entry.size;
+                count.is_directory = entry.is_directory;
+            }
+        });
+
+        let mut counts: Vec<ItemCount> = children.into_values().collect();
+        counts.sort_by(|a, b| b.items.cmp(&a.items).then_with(|| a.path.cmp(&b.path)));
+        counts.truncate(limit);
+        counts
+    }
+
+    /**
+     * The directories below `root` (included) with at least `min_count` tiny
+     * files directly in them, the most first. Their bytes hardly show in a
+     * listing by size, their count slows down every backup and scan.
+     */
+    fn file_count_hotspots(&self, root: &Path, min_count: usize) -> Vec<FileCountHotspot> {
+        let mut directories: HashMap<PathBuf, FileCountHotspot> = HashMap::new();
+        self.walk(root, &mut |path, entry| {
+            let Some(parent) = path.parent().filter(|_| path != root) else {
+                return;
+            };
+            let hotspot =
+                directories
+                    .entry(parent.to_path_buf())
+                    .or_insert_with(|| FileCountHotspot {
+                        path: parent.to_path_buf(),
+                        raw_path: lossless::raw_if_lossy(parent),
+                        tiny_files: 0,
+                        tiny_size: 0,
+                        entries: 0,
+                    });
+            hotspot.entries += 1;
+            if !entry.is_directory && entry.size < TINY_FILE_SIZE {
+                hotspot.tiny_files += 1;
+                hotspot.tiny_size += entry.size;
+            }
+        });
+
+        let mut hotspots: Vec<FileCountHotspot> = directories
+            .into_values()
+            .filter(|hotspot| hotspot.tiny_files > 0 && hotspot.tiny_files >= min_count)
+            .collect();
+        hotspots.sort_by(|a, b| {
+            b.tiny_files
+                .cmp(&a.tiny_files)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        hotspots
+    }
+}
+
+impl ScanStore for Tree {
+    fn size_of(&self, path: &Path) -> Option<usize> {
+        let node = self.get_node(path)?;
+        node.read().map(|node| node.size()).ok()
+    }
+
+    fn details(&self, path: &Path) -> Option<FileDetails> {
+        let node = self.get_node(path)?;
+        let children = node.children();
+        let node = node.read().ok()?;
+
+        let mut details = FileDetails::from(&node).at(path.to_path_buf());
+        let mut children = children
+            .iter()
+            .filter_map(|child| {
+                let child = child.read().ok()?;
+                Some(FileDetails::from(&child).at(path.join(&child.path)))
+            })
+            .collect::<Vec<_>>();
+        children.sort_by_key(|child| Reverse(child.size));
+        details.children = Some(children);
+        Some(details)
+    }
+
+    fn walk(&self, root: &Path, visit: &mut dyn FnMut(&PathBuf, Entry)) {
+        Tree::walk(self, root, |path, node| {
+            visit(
+                path,
+                Entry {
+                    size: node.size(),
+                    is_directory: node.is_directory,
+                    modified: node.modified,
+                    created: node.created,
+                    file_id: node.file_id,
+                },
+            )
+        });
+    }
+}
+
+/**
+ * queries straight over the flat entries of a session archive, without
+ * rebuilding the tree
+ */
+pub struct SnapshotStore {
+    entries: Vec<SnapshotEntry>,
+    paths: Vec<PathBuf>,
+    index: HashMap<PathKey, usize>,
+    children: Vec<Vec<usize>>,
+    /// the entries below each entry
+    counts: Vec<usize>,
+}
+
+impl SnapshotStore {
+    pub fn new(entries: Vec<SnapshotEntry>) -> Self {
+        let mut paths: Vec<PathBuf> = Vec::with_capacity(entries.len());
+        let mut children: Vec<Vec<usize>> = vec![vec![]; entries.len()];
+
+        for (index, entry) in entries.iter().enumerate() {
+            // parents always come first, see snapshot::flatten
+            let path = match entry.parent.filter(|parent| *parent < index) {
+                Some(parent) => {
+                    children[parent].push(index);
+                    paths[parent].join(&entry.name)
+                }
+                None => PathBuf::from(&entry.name),
+            };
+            paths.push(path);
+        }
+        let mut counts = vec![0; entries.len()];
+        for index in (0..entries.len()).rev() {
+            if let Some(parent) = entries[index].parent.filter(|parent| *parent < index) {
+                counts[parent] += counts[index] + 1;
+            }
+        }
+
+        let index = paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| (PathKey::new(path), index))
+            .collect();
+        Self {
+            entries,
+            paths,
+            index,
+            children,
+            counts,
+        }
+    }
+
+    fn node(&self, index: usize) -> Node {
+        let entry = &self.entries[index];
+        let mut node = Node::new(entry.name.clone(), entry.is_directory, entry.is_link);
+        node.set_size(entry.size);
+        node.set_allocated_size(entry.allocated_size);
+        node.set_hardlink_savings(entry.hardlink_savings);
+        node.file_id = entry.file_id;
+        node.modified = entry.modified;
+        node.created = entry.created;
+        node.locks = entry.locks;
+        node.set_age(entry.age);
+        node.complete = entry.complete;
+        node.skipped = entry.skipped;
+        node.set_count(self.counts[index]);
+        node
+    }
+}
+
+impl ScanStore for SnapshotStore {
+    fn size_of(&self, path: &Path) -> Option<usize> {
+        self.index
+            .get(&PathKey::new(path))
+            .map(|index| self.entries[*index].size)
+    }
+
+    fn details(&self, path: &Path) -> Option<FileDetails> {
+        let index = *self.index.get(&PathKey::new(path))?;
+
+        let mut details = FileDetails::from(&self.node(index)).at(self.paths[index].clone());
+        let mut children = self.children[index]
+            .iter()
+            .map(|child| FileDetails::from(&self.node(*child)).at(self.paths[*child].clone()))
+            .collect::<Vec<_>>();
+        children.sort_by_key(|child| Reverse(child.size));
+        details.children = Some(children);
+        Some(details)
+    }
+
+    fn walk(&self, root: &Path, visit: &mut dyn FnMut(&PathBuf, Entry)) {
+        let Some(start) = self.index.get(&PathKey::new(root)) else {
+            return;
+        };
+
+        let mut stack = vec![*start];
+        while let Some(index) = stack.pop() {
+            let entry = &self.entries[index];
+            visit(
+                &self.paths[index],
+                Entry {
+                    size: entry.size,
+                    is_directory: entry.is_directory,
+                    modified: entry.modified,
+                    created: entry.created,
+                    file_id: entry.file_id,
+                },
+            );
+            stack.extend(self.children[index].iter().rev());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot;
+    use std::ffi::OsString;
+
+    const BACKUP_ID: FileId = FileId {
+        volume: 1,
+        file: 42,
+    };
+
+    fn build_tree() -> Tree {
+        let file = |name: &str, size: usize| {
+            let node = Node::new(OsString::from(name), false, false);
+            node.set_size(size);
+            node
+        };
+
+        let root = PathBuf::from("/");
+        let mut tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let _ = tree.insert(&root, Node::new(OsString::from("media"), true, false));
+        let mut movie = file("Movie.mkv", 700);
+        movie.modified = Some(1_000);
+        let _ = tree.insert(&PathBuf::from("/media"), movie);
+        let mut notes = file("movie-notes.txt", 5);
+        notes.modified = Some(5_000);
+        let _ = tree.insert(&PathBuf::from("/media"), notes);
+        let mut backup = file("backup.zip", 300);
+        backup.file_id = Some(BACKUP_ID);
+        // copied lately, an old modification time but no old file
+        backup.modified = Some(2_000);
+        backup.created = Some(9_000);
+        let _ = tree.insert(&root, backup);
+        tree
+    }
+
+    /**
+     * every backend answers the same queries the same way
+     */
+    fn check_store(store: &dyn ScanStore) {
+        let root = PathBuf::from("/");
+        assert_eq!(store.size_of(&PathBuf::from("/media")), Some(705));
+        assert_eq!(store.size_of(&PathBuf::from("/media/")), Some(705));
+
+        let largest = store.largest_files(&root, 2);
+        assert_eq!(
+            largest
+                .iter()
+                .map(|file| file.path.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                PathBuf::from("/media/Movie.mkv"),
+                PathBuf::from("/backup.zip")
+            ]
+        );
+
+        let above = store.largest_files_above(&root, 10, 10);
+        assert_eq!(above.len(), 2);
+        assert_eq!(above[1].path, PathBuf::from("/backup.zip"));
+        assert_eq!(store.largest_files_above(&root, 301, 10).len(), 1);
+        assert!(store.largest_files(&root, 0).is_empty());
+
+        let remaining = store.largest_files_except(&root, &[PathBuf::from("/media")], 2);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].path, PathBuf::from("/backup.zip"));
+
+        let found = store.search(&root, "MOVIE", 10);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].path, PathBuf::from("/media/Movie.mkv"));
+
+        let details = store.details(&PathBuf::from("/media")).unwrap();
+        assert_eq!(details.path, PathBuf::from("/media"));
+        let children = details.children.unwrap();
+        assert_eq!(children[0].name, "Movie.mkv");
+        assert_eq!(children[0].path, PathBuf::from("/media/Movie.mkv"));
+
+        assert_eq!(
+            store.locate(&root, BACKUP_ID),
+            Some(PathBuf::from("/backup.zip"))
+        );
+        let unknown = FileId {
+            file: 7,
+            ..BACKUP_ID
+        };
+        assert_eq!(store.locate(&root, unknown), None);
+
+        let most = store.most_files(&root, 10);
+        assert_eq!(most.len(), 2);
+        assert_eq!(most[0].path, PathBuf::from("/media"));
+        assert_eq!((most[0].items, most[0].size), (3, 705));
+        assert!(most[0].is_directory);
+        assert_eq!(most[1].items, 1);
+
+        let hotspots = store.file_count_hotspots(&root, 2);
+        assert_eq!(hotspots.len(), 1);
+        assert_eq!(hotspots[0].path, PathBuf::from("/media"));
+        assert_eq!((hotspots[0].tiny_files, hotspots[0].tiny_size), (2, 705));
+        // the folder itself is no tiny file of the root
+        let all = store.file_count_hotspots(&root, 0);
+        assert_eq!(all.len(), 2);
+        assert_eq!((all[1].path.clone(), all[1].tiny_files), (root.clone(), 1));
+        assert_eq!(all[1].entries, 2);
+
+        let old = store.old_files(&root, 6_000, 0, AgeOrder::OldestFirst, 0, 1);
+        assert_eq!((old.total, old.total_size), (2, 705));
+        assert_eq!(old.files[0].path, PathBuf::from("/media/Movie.mkv"));
+        assert_eq!(old.next_offset, Some(1));
+        let next = store.old_files(&root, 6_000, 0, AgeOrder::OldestFirst, 1, 1);
+        assert_eq!(next.files[0].path, PathBuf::from("/media/movie-notes.txt"));
+        assert_eq!(next.next_offset, None);
+        let newest = store.old_files(&root, 10_000, 10, AgeOrder::NewestFirst, 0, 10);
+        assert_eq!(
+            newest
+                .files
+                .iter()
+                .map(|file| file.path.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                PathBuf::from("/backup.zip"),
+                PathBuf::from("/media/Movie.mkv")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tree_store() {
+        check_store(&build_tree());
+    }
+
+    #[test]
+    fn test_snapshot_store() {
+        let entries = snapshot::flatten(&build_tree());
+        check_store(&SnapshotStore::new(entries));
+    }
+}
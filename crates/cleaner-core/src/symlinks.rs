@@ -0,0 +1,121 @@
+use std::{
+    collections::HashSet,
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{file_id::FileId, long_path, path_key::PathKey};
+
+/**
+ * what a scan does with a symbolic link to a directory
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SymlinkPolicy {
+    /// the link counts as itself, a few bytes, its target is left alone
+    #[default]
+    Never,
+    /// the link is listed like the directory it points to, unless the target
+    /// lies inside the scan or was listed already
+    Follow,
+}
+
+/**
+ * where the link at `path` leads, with every link on the way resolved. A
+ * dangling link resolves as far as its own target, none when `path` is no
+ * link at all.
+ */
+pub fn target(path: &Path) -> Option<PathBuf> {
+    let target = fs::read_link(long_path::extended(path)).ok()?;
+    let joined = match path.parent() {
+        Some(parent) => parent.join(&target),
+        None => target,
+    };
+    let resolved = fs::canonicalize(long_path::extended(&joined)).unwrap_or(joined);
+    Some(PathKey::new(&resolved).into_path_buf())
+}
+
+/**
+ * Decides which directory links a scan following them descends into. A
+ * target inside the scan is listed through its own path, one met before is
+ * a cycle or a second way to the same bytes, both are left as links.
+ */
+#[derive(Debug)]
+pub struct LinkFollower {
+    root: PathBuf,
+    visited: Mutex<HashSet<FileId>>,
+}
+
+impl LinkFollower {
+    pub fn new(root: &Path, metadata: &Metadata) -> Self {
+        let resolved = fs::canonicalize(long_path::extended(root)).unwrap_or(root.to_path_buf());
+        let follower = Self {
+            root: PathKey::new(&resolved).into_path_buf(),
+            visited: Mutex::new(HashSet::new()),
+        };
+        follower.visit(root, metadata);
+        follower
+    }
+
+    /**
+     * record a directory the scan lists, false when it was recorded already
+     */
+    pub fn visit(&self, path: &Path, metadata: &Metadata) -> bool {
+        let Some(id) = FileId::of(path, metadata) else {
+            return true;
+        };
+        self.visited
+            .lock()
+            .is_ok_and(|mut visited| visited.insert(id))
+    }
+
+    /**
+     * the metadata of the directory the link at `path` leads to when the scan
+     * should list it, none to leave the link as it is
+     */
+    pub fn follow(&self, path: &Path) -> Option<Metadata> {
+        let metadata = fs::metadata(long_path::extended(path)).ok()?;
+        if !metadata.is_dir() || target(path)?.starts_with(&self.root) {
+            return None;
+        }
+        // an unknown identity can't be told from an ancestor, it may be a cycle
+        FileId::of(path, &metadata)?;
+        self.visit(path, &metadata).then_some(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cycle_followed_once() {
+        let base = temp_path("symlinks");
+        let _ = fs::remove_dir_all(&base);
+        let (root, outside) = (base.join("root"), base.join("outside"));
+        fs::create_dir_all(root.join("inner")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("out")).unwrap();
+        std::os::unix::fs::symlink(&root, outside.join("back")).unwrap();
+        std::os::unix::fs::symlink(&outside, outside.join("loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("inner"), root.join("alias")).unwrap();
+
+        let follower = LinkFollower::new(&root, &fs::metadata(&root).unwrap());
+        assert!(follower.follow(&root.join("alias")).is_none());
+        assert!(follower.follow(&root.join("out")).is_some());
+        assert!(follower.follow(&root.join("out")).is_none());
+        assert!(follower.follow(&root.join("out/back")).is_none());
+        assert!(follower.follow(&root.join("out/loop")).is_none());
+        assert_eq!(
+            target(&root.join("out")),
+            Some(fs::canonicalize(&outside).unwrap())
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    io::{self, Read},
+    thread,
+    time::{Duration, Instant},
+};
+
+/**
+ * how far an operation may fall behind its cap and still catch up, a pause
+ * longer than it is not made up for with a burst
+ */
+const CATCH_UP: Duration = Duration::from_secs(1);
+
+/**
+ * The throughput cap of one operation, e.g. a backup to a NAS which must
+ * not saturate the network. Every loop of the operation paces the bytes it
+ * moves through the same throttle, so the cap holds for all of them.
+ */
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    /// none for no cap
+    bytes_per_sec: Option<u64>,
+    started: Instant,
+    passed: u64,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+impl Throttle {
+    pub fn unlimited() -> Self {
+        Self {
+            bytes_per_sec: None,
+            started: Instant::now(),
+            passed: 0,
+        }
+    }
+
+    /**
+     * a cap of `mb_per_sec` megabytes a second, none or zero for no cap
+     */
+    pub fn mb_per_sec(mb_per_sec: Option<u64>) -> Self {
+        Self {
+            bytes_per_sec: mb_per_sec
+                .filter(|mb| *mb > 0)
+                .map(|mb| mb.saturating_mul(1024 * 1024)),
+            ..Self::unlimited()
+        }
+    }
+
+    /**
+     * a cap of `per_sec` units a second, entries or operations rather than
+     * bytes, none or zero for no cap
+     */
+    pub fn per_sec(per_sec: Option<u64>) -> Self {
+        Self {
+            bytes_per_sec: per_sec.filter(|rate| *rate > 0),
+            ..Self::unlimited()
+        }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.bytes_per_sec.is_some()
+    }
+
+    /**
+     * count `bytes` as moved, sleeping until the average since the last
+     * pause is back at the cap. Blocking.
+     */
+    pub fn pace(&mut self, bytes: u64) {
+        if let Some(wait) = self.delay(bytes) {
+            thread::sleep(wait);
+        }
+    }
+
+    /**
+     * count `bytes` as moved, how long to wait until the average is back at
+     * the cap, for callers which can't block. None when under the cap.
+     */
+    pub fn delay(&mut self, bytes: u64) -> Option<Duration> {
+        let rate = self.bytes_per_sec?;
+        self.passed += bytes;
+        let due = Duration::from_secs_f64(self.passed as f64 / rate as f64);
+        let elapsed = self.started.elapsed();
+        if due > elapsed {
+            return Some(due - elapsed);
+        }
+        if elapsed - due > CATCH_UP {
+            self.started = Instant::now();
+            self.passed = 0;
+        }
+        None
+    }
+
+    /**
+     * `inner` read no faster than the cap
+     */
+    pub fn reader<R: Read>(&mut self, inner: R) -> Paced<'_, R> {
+        Paced {
+            inner,
+            throttle: self,
+        }
+    }
+}
+
+/**
+ * a reader paced by a `Throttle`
+ */
+pub struct Paced<'a, R> {
+    inner: R,
+    throttle: &'a mut Throttle,
+}
+
+impl<R: Read> Read for Paced<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.throttle.pace(read as u64);
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pace_holds_cap() {
+        let mut throttle = Throttle::mb_per_sec(Some(4));
+        let started = Instant::now();
+        let data = vec![0u8; 2 * 1024 * 1024];
+        let mut copied = Vec::new();
+        throttle.reader(&data[..]).read_to_end(&mut copied).unwrap();
+        assert_eq!(copied.len(), data.len());
+        // half a second for 2 MB at 4 MB/s
+        assert!(started.elapsed() >= Duration::from_millis(450));
+
+        let mut unlimited = Throttle::mb_per_sec(Some(0));
+        assert!(!unlimited.is_limited());
+        let started = Instant::now();
+        unlimited.pace(u64::MAX / 2);
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_per_sec_paces_units() {
+        let mut throttle = Throttle::per_sec(Some(5));
+        let started = Instant::now();
+        for _ in 0..6 {
+            throttle.pace(1);
+        }
+        assert!(started.elapsed() >= Duration::from_millis(900));
+    }
+}
@@ -0,0 +1,89 @@
+use std::{
+    cmp::Reverse,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::tree::Tree;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentChange {
+    pub path: PathBuf,
+    pub size: usize,
+    pub kind: ChangeKind,
+    /// seconds since epoch of the creation or last modification
+    pub changed_at: u64,
+}
+
+/**
+ * files under `root` created or modified at or after `since` (seconds since epoch),
+ * largest first
+ */
+pub fn recent_changes(tree: &Tree, root: &Path, since: u64) -> Vec<RecentChange> {
+    let mut changes: Vec<RecentChange> = vec![];
+
+    tree.walk(root, |path, node| {
+        if node.is_directory {
+            return;
+        }
+
+        let change = match (node.created, node.modified) {
+            (Some(created), _) if created >= since => Some((ChangeKind::Created, created)),
+            (_, Some(modified)) if modified >= since => Some((ChangeKind::Modified, modified)),
+            _ => None,
+        };
+
+        if let Some((kind, changed_at)) = change {
+            changes.push(RecentChange {
+                path: path.clone(),
+                size: node.size(),
+                kind,
+                changed_at,
+            });
+        }
+    });
+
+    changes.sort_by_key(|change| Reverse(change.size));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::node::Node;
+    use std::ffi::OsString;
+
+    fn file(name: &str, size: usize, created: u64, modified: u64) -> Node {
+        let mut node = Node::new(OsString::from(name), false, false);
+        node.set_size(size);
+        node.created = Some(created);
+        node.modified = Some(modified);
+        node
+    }
+
+    #[test]
+    fn test_recent_changes_sorted_by_size() {
+        let root = PathBuf::from("/");
+        let mut tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let _ = tree.insert(&root, file("old.bin", 500, 10, 20));
+        let _ = tree.insert(&root, file("edited.bin", 100, 10, 150));
+        let _ = tree.insert(&root, file("new.bin", 300, 120, 120));
+
+        let changes = recent_changes(&tree, &root, 100);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, PathBuf::from("/new.bin"));
+        assert_eq!(changes[0].kind, ChangeKind::Created);
+        assert_eq!(changes[1].path, PathBuf::from("/edited.bin"));
+        assert_eq!(changes[1].kind, ChangeKind::Modified);
+    }
+}
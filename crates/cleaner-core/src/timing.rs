@@ -0,0 +1,81 @@
+use std::{cmp::Reverse, collections::BinaryHeap, path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryTiming {
+    pub path: PathBuf,
+    /// time spent enumerating the directory
+    pub millis: u64,
+    pub entries: usize,
+}
+
+/**
+ * Keeps only the slowest directories of a scan, so recording a timing for
+ * every directory costs a bounded amount of memory.
+ */
+#[derive(Debug)]
+pub struct SlowDirectories {
+    capacity: usize,
+    heap: BinaryHeap<Reverse<(u128, usize, PathBuf)>>,
+}
+
+impl SlowDirectories {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    pub fn record(&mut self, path: PathBuf, elapsed: Duration, entries: usize) {
+        self.heap
+            .push(Reverse((elapsed.as_micros(), entries, path)));
+        if self.heap.len() > self.capacity {
+            self.heap.pop();
+        }
+    }
+
+    /**
+     * slowest directories first
+     */
+    pub fn slowest(&self, limit: usize) -> Vec<DirectoryTiming> {
+        let mut items: Vec<_> = self.heap.iter().map(|Reverse(item)| item).collect();
+        items.sort_by(|a, b| b.cmp(a));
+        items
+            .into_iter()
+            .take(limit)
+            .map(|(micros, entries, path)| DirectoryTiming {
+                path: path.clone(),
+                millis: (*micros / 1000) as u64,
+                entries: *entries,
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_slowest_directories() {
+        let mut timings = SlowDirectories::new(2);
+        timings.record(PathBuf::from("/fast"), Duration::from_millis(1), 1);
+        timings.record(PathBuf::from("/network"), Duration::from_millis(900), 10);
+        timings.record(PathBuf::from("/mail"), Duration::from_millis(300), 5000);
+
+        let slowest = timings.slowest(10);
+        assert_eq!(slowest.len(), 2);
+        assert_eq!(slowest[0].path, PathBuf::from("/network"));
+        assert_eq!(slowest[0].millis, 900);
+        assert_eq!(slowest[1].path, PathBuf::from("/mail"));
+        assert_eq!(slowest[1].entries, 5000);
+    }
+}
@@ -0,0 +1,649 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tracing::warn;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    audit::AuditLog,
+    long_path,
+    lossless::PathArg,
+    manifest::Manifest,
+    mover::{bytes_below, copy_tree, ensure_absent, move_path, remove},
+    space::{self, InsufficientSpace},
+    throttle::Throttle,
+};
+
+/**
+ * prefix of the folder a deleted entry waits in until the operation commits,
+ * created next to the entry so the move is a rename on the same volume
+ */
+pub const STAGING_PREFIX: &str = ".cleaner-rollback-";
+
+/**
+ * one step of a composite operation like backup-then-clean, none of them
+ * overwrites an existing destination
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Step {
+    /// undone by removing the copy
+    Copy { from: PathBuf, to: PathBuf },
+    /// undone by moving it back
+    Move { from: PathBuf, to: PathBuf },
+    /// a zip archive of a file or a folder, undone by removing the archive
+    Compress { from: PathBuf, to: PathBuf },
+    /// only staged while the operation runs, undone by putting it back
+    Delete { path: PathBuf },
+}
+
+/**
+ * a `Step` the way the webview sends it, every path in the lossless form
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum StepArg {
+    Copy { from: PathArg, to: PathArg },
+    Move { from: PathArg, to: PathArg },
+    Compress { from: PathArg, to: PathArg },
+    Delete { path: PathArg },
+}
+
+impl StepArg {
+    /**
+     * the step with each of its paths passed through `path`
+     */
+    pub fn into_step<E, F>(self, mut path: F) -> Result<Step, E>
+    where
+        F: FnMut(PathArg) -> Result<PathBuf, E>,
+    {
+        Ok(match self {
+            StepArg::Copy { from, to } => Step::Copy {
+                from: path(from)?,
+                to: path(to)?,
+            },
+            StepArg::Move { from, to } => Step::Move {
+                from: path(from)?,
+                to: path(to)?,
+            },
+            StepArg::Compress { from, to } => Step::Compress {
+                from: path(from)?,
+                to: path(to)?,
+            },
+            StepArg::Delete { path: deleted } => Step::Delete {
+                path: path(deleted)?,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum StepState {
+    Done,
+    Failed,
+    Undone,
+    /// it stays done, the error says what is left where
+    UndoFailed,
+    /// a step before it failed
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub step: Step,
+    pub state: StepState,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionState {
+    Committed,
+    /// a step failed and every step before it was undone
+    RolledBack,
+    /// a step failed and some of the steps before it could not be undone
+    RollbackFailed,
+}
+
+impl TransactionState {
+    pub const ALL: [TransactionState; 3] = [
+        TransactionState::Committed,
+        TransactionState::RolledBack,
+        TransactionState::RollbackFailed,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TransactionState::Committed => "committed",
+            TransactionState::RolledBack => "rolledBack",
+            TransactionState::RollbackFailed => "rollbackFailed",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|state| state.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionOutcome {
+    /// the entry of the audit log, none when it couldn't be written
+    pub audit_id: Option<i64>,
+    pub state: TransactionState,
+    /// every step in order, with what became of it
+    pub journal: Vec<JournalEntry>,
+    /// the step which failed
+    pub failed_step: Option<usize>,
+    pub error: Option<String>,
+    /// the destination without room for the steps, none of which was started
+    pub insufficient_space: Option<InsufficientSpace>,
+}
+
+/**
+ * how to take back a step which is done
+ */
+enum Undo {
+    Remove(PathBuf),
+    MoveBack { from: PathBuf, to: PathBuf },
+    Unstage { staged: PathBuf, path: PathBuf },
+}
+
+/**
+ * a copy made by a step, compared with what its source held before anything is purged
+ */
+struct Check {
+    recorded: Manifest,
+    copy: PathBuf,
+    archive: bool,
+}
+
+impl Check {
+    fn verify(&self) -> io::Result<()> {
+        let copied = if self.archive {
+            Manifest::of_zip(&self.copy)?
+        } else {
+            Manifest::record(&self.copy)?
+        };
+        self.recorded.verify(&copied).map_err(|mismatch| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} doesn't match its source, {}",
+                    self.copy.display(),
+                    mismatch
+                ),
+            )
+        })
+    }
+}
+
+/**
+ * Run `steps` as one operation named `operation`. Each step is journaled in
+ * `audit` once it is done. When one fails, the steps done before it are
+ * undone in reverse order, a delete is only final once every step
+ * succeeded. Before a staged delete is purged every copy and archive is
+ * read back and compared with what its source held, a mismatch fails its
+ * step. Copies, moves across volumes and archives are paced by `throttle`.
+ * Nothing is started, nor journaled, when `preflight` finds a destination
+ * without room for them. Blocking.
+ */
+pub fn run(
+    operation: &str,
+    steps: &[Step],
+    audit: &AuditLog,
+    throttle: &mut Throttle,
+) -> TransactionOutcome {
+    let mut journal: Vec<JournalEntry> = steps
+        .iter()
+        .map(|step| JournalEntry {
+            step: step.clone(),
+            state: StepState::Skipped,
+            error: None,
+        })
+        .collect();
+    if let Err(err) = preflight(steps) {
+        return TransactionOutcome {
+            audit_id: None,
+            state: TransactionState::RolledBack,
+            journal,
+            failed_step: None,
+            insufficient_space: InsufficientSpace::of(&err).cloned(),
+            error: Some(err.to_string()),
+        };
+    }
+
+    let id = audit
+        .begin(operation)
+        .inspect_err(|err| {
+            warn!(
+                "audit log unavailable, {} isn't recorded, {}",
+                operation, err
+            )
+        })
+        .ok();
+
+    let mut done: Vec<(usize, Undo)> = vec![];
+    let mut checks: Vec<(usize, Check)> = vec![];
+    let mut failed = None;
+    for (index, step) in steps.iter().enumerate() {
+        match apply(step, throttle) {
+            Ok((undo, check)) => {
+                journal[index].state = StepState::Done;
+                done.push((index, undo));
+                checks.extend(check.map(|check| (index, check)));
+            }
+            Err(err) => {
+                journal[index].state = StepState::Failed;
+                journal[index].error = Some(err.to_string());
+                failed = Some((index, err.to_string()));
+            }
+        }
+        if let Some(id) = id {
+            let _ = audit.journal(id, &journal[..=index]);
+        }
+        if failed.is_some() {
+            break;
+        }
+    }
+
+    // nothing is purged while a copy can't be shown to be intact
+    let purges = done
+        .iter()
+        .any(|(_, undo)| matches!(undo, Undo::Unstage { .. }));
+    if failed.is_none() && purges {
+        for (index, check) in &checks {
+            if let Err(err) = check.verify() {
+                warn!("{} stopped before purging, {}", operation, err);
+                journal[*index].state = StepState::Failed;
+                journal[*index].error = Some(err.to_string());
+                failed = Some((*index, err.to_string()));
+                break;
+            }
+        }
+    }
+
+    let (state, error) = match &failed {
+        None => (TransactionState::Committed, commit(&done).err()),
+        Some(_) => {
+            let mut state = TransactionState::RolledBack;
+            for (index, undo) in done.into_iter().rev() {
+                match revert(undo) {
+                    Ok(()) => journal[index].state = StepState::Undone,
+                    Err(err) => {
+                        journal[index].state = StepState::UndoFailed;
+                        journal[index].error = Some(err.to_string());
+                        state = TransactionState::RollbackFailed;
+                    }
+                }
+            }
+            (state, None)
+        }
+    };
+    let (failed_step, failure) = failed.unzip();
+    let error = failure.or(error.map(|err| err.to_string()));
+    if let Some(id) = id {
+        let _ = audit.finish(id, state, &journal, error.as_deref());
+    }
+
+    TransactionOutcome {
+        audit_id: id,
+        state,
+        journal,
+        failed_step,
+        error,
+        insufficient_space: None,
+    }
+}
+
+/**
+ * Make sure every destination has room for what `steps` write there, the
+ * steps writing to one volume taken together. A move within a volume
+ * writes nothing, a source which only an earlier step creates counts as
+ * empty. Blocking.
+ */
+pub fn preflight(steps: &[Step]) -> io::Result<()> {
+    let writes: Vec<(PathBuf, u64)> = steps
+        .iter()
+        .filter_map(|step| {
+            let (from, to) = match step {
+                Step::Copy { from, to } | Step::Compress { from, to } => (from, to),
+                Step::Move { from, to } if !space::same_volume(from, to) => (from, to),
+                Step::Move { .. } | Step::Delete { .. } => return None,
+            };
+            Some((to.clone(), bytes_below(from).unwrap_or(0)))
+        })
+        .collect();
+    space::ensure_all(&writes)
+}
+
+/**
+ * do one step, with how to take it back and the copy it made to check
+ */
+fn apply(step: &Step, throttle: &mut Throttle) -> io::Result<(Undo, Option<Check>)> {
+    match step {
+        Step::Copy { from, to } => {
+            ensure_absent(to)?;
+            let recorded = Manifest::record(from)?;
+            copy_tree(from, to, &mut |bytes| throttle.pace(bytes)).inspect_err(|_| {
+                let _ = remove(to);
+            })?;
+            let check = Check {
+                recorded,
+                copy: to.clone(),
+                archive: false,
+            };
+            Ok((Undo::Remove(to.clone()), Some(check)))
+        }
+        Step::Move { from, to } => {
+            ensure_absent(to)?;
+            move_path(from, to, throttle, &mut |_| {})?;
+            let undo = Undo::MoveBack {
+                from: to.clone(),
+                to: from.clone(),
+            };
+            Ok((undo, None))
+        }
+        Step::Compress { from, to } => {
+            ensure_absent(to)?;
+            let recorded = Manifest::record(from)?;
+            compress(from, to, throttle).inspect_err(|err| {
+                // never remove a file which was there before
+                if err.kind() != io::ErrorKind::AlreadyExists {
+                    let _ = remove(to);
+                }
+            })?;
+            let check = Check {
+                recorded,
+                copy: to.clone(),
+                archive: true,
+            };
+            Ok((Undo::Remove(to.clone()), Some(check)))
+        }
+        Step::Delete { path } => {
+            let staged = staged_path(path)?;
+            fs::create_dir_all(long_path::extended(staging_of(&staged)))?;
+            fs::rename(long_path::extended(path), long_path::extended(&staged))?;
+            let undo = Undo::Unstage {
+                staged,
+                path: path.clone(),
+            };
+            Ok((undo, None))
+        }
+    }
+}
+
+fn revert(undo: Undo) -> io::Result<()> {
+    match undo {
+        Undo::Remove(path) => remove(&path),
+        Undo::MoveBack { from, to } => {
+            ensure_absent(&to)?;
+            // a rollback puts things back as fast as it can
+            move_path(&from, &to, &mut Throttle::unlimited(), &mut |_| {}).map(|_| ())
+        }
+        Undo::Unstage { staged, path } => {
+            ensure_absent(&path)?;
+            fs::rename(long_path::extended(&staged), long_path::extended(&path))?;
+            // shared by the other deletes of the folder, only gone with the last one
+            let _ = fs::remove_dir(long_path::extended(staging_of(&staged)));
+            Ok(())
+        }
+    }
+}
+
+/**
+ * remove the staged entries for good, the first error after trying every one
+ */
+fn commit(done: &[(usize, Undo)]) -> io::Result<()> {
+    let mut result = Ok(());
+    for (_, undo) in done {
+        let Undo::Unstage { staged, .. } = undo else {
+            continue;
+        };
+        let removed = match fs::remove_dir_all(long_path::extended(staging_of(staged))) {
+            // the staging folder of an earlier delete in the same folder
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            removed => removed,
+        };
+        if let Err(err) = removed {
+            warn!("staged {} not removed, {}", staged.display(), err);
+            result = result.and(Err(io::Error::new(
+                err.kind(),
+                format!(
+                    "{} is left in {}, {}",
+                    staged.display(),
+                    STAGING_PREFIX,
+                    err
+                ),
+            )));
+        }
+    }
+    result
+}
+
+/**
+ * where a deleted entry waits, in a staging folder of the process inside its parent
+ */
+fn staged_path(path: &Path) -> io::Result<PathBuf> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} can't be deleted", path.display()),
+        ));
+    };
+    Ok(parent
+        .join(format!("{}{}", STAGING_PREFIX, std::process::id()))
+        .join(name))
+}
+
+fn staging_of(staged: &Path) -> &Path {
+    staged.parent().unwrap_or(staged)
+}
+
+/**
+ * a zip of `from` at `to`, a folder with its name as the top entry
+ */
+fn compress(from: &Path, to: &Path, throttle: &mut Throttle) -> io::Result<()> {
+    let archive = File::create_new(long_path::extended(to))?;
+    let mut zip = ZipWriter::new(archive);
+    let name = from.file_name().map_or_else(
+        || "archive".to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    add_to_zip(&mut zip, from, &name, throttle)?;
+    zip.finish().map_err(io::Error::other)?;
+    Ok(())
+}
+
+fn add_to_zip(
+    zip: &mut ZipWriter<File>,
+    path: &Path,
+    name: &str,
+    throttle: &mut Throttle,
+) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(long_path::extended(path))?;
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(metadata.len() >= u32::MAX as u64);
+    if metadata.is_dir() {
+        zip.add_directory(name, options).map_err(io::Error::other)?;
+        for entry in fs::read_dir(long_path::extended(path))? {
+            let child = entry?.file_name();
+            let child_name = format!("{}/{}", name, child.to_string_lossy());
+            add_to_zip(zip, &path.join(&child), &child_name, throttle)?;
+        }
+        return Ok(());
+    }
+    zip.start_file(name, options).map_err(io::Error::other)?;
+    let file = File::open(long_path::extended(path))?;
+    io::copy(&mut throttle.reader(file), zip)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    fn setup(name: &str) -> PathBuf {
+        let root = temp_path(&format!("transaction-{}", name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("project/src")).unwrap();
+        fs::write(root.join("project/src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("project/notes.txt"), "notes").unwrap();
+        root
+    }
+
+    #[test]
+    fn test_backup_then_clean_committed() {
+        let root = setup("commit");
+        let audit = AuditLog::in_memory().unwrap();
+        let steps = [
+            Step::Copy {
+                from: root.join("project"),
+                to: root.join("backup"),
+            },
+            Step::Compress {
+                from: root.join("project"),
+                to: root.join("project.zip"),
+            },
+            Step::Delete {
+                path: root.join("project"),
+            },
+        ];
+
+        let outcome = run(
+            "backup then clean",
+            &steps,
+            &audit,
+            &mut Throttle::unlimited(),
+        );
+        assert_eq!(outcome.state, TransactionState::Committed);
+        assert_eq!(outcome.error, None);
+        assert!(!root.join("project").exists());
+        assert_eq!(
+            fs::read_to_string(root.join("backup/src/main.rs")).unwrap(),
+            "fn main() {}"
+        );
+        assert!(fs::metadata(root.join("project.zip")).unwrap().len() > 0);
+        // nothing staged is left behind
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 2);
+
+        let entries = audit.recent(1).unwrap();
+        assert_eq!(entries[0].state, Some(TransactionState::Committed));
+        assert_eq!(entries[0].journal.len(), 3);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_corrupted_copy_checked() {
+        let root = setup("verify");
+        let mut throttle = Throttle::unlimited();
+        let (_, check) = apply(
+            &Step::Compress {
+                from: root.join("project"),
+                to: root.join("project.zip"),
+            },
+            &mut throttle,
+        )
+        .unwrap();
+        assert!(check.unwrap().verify().is_ok());
+
+        let (_, check) = apply(
+            &Step::Copy {
+                from: root.join("project"),
+                to: root.join("backup"),
+            },
+            &mut throttle,
+        )
+        .unwrap();
+        fs::write(root.join("backup/notes.txt"), "noted").unwrap();
+        let error = check.unwrap().verify().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("notes.txt"));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_failed_step_rolled_back() {
+        let root = setup("rollback");
+        fs::write(root.join("taken"), "in the way").unwrap();
+        let audit = AuditLog::in_memory().unwrap();
+        let steps = [
+            Step::Delete {
+                path: root.join("project/notes.txt"),
+            },
+            Step::Move {
+                from: root.join("project/src"),
+                to: root.join("moved"),
+            },
+            Step::Copy {
+                from: root.join("moved"),
+                to: root.join("taken"),
+            },
+            Step::Delete {
+                path: root.join("project"),
+            },
+        ];
+
+        let outcome = run(
+            "move then clean",
+            &steps,
+            &audit,
+            &mut Throttle::unlimited(),
+        );
+        assert_eq!(outcome.state, TransactionState::RolledBack);
+        assert_eq!(outcome.failed_step, Some(2));
+        let states: Vec<StepState> = outcome.journal.iter().map(|entry| entry.state).collect();
+        assert_eq!(
+            states,
+            vec![
+                StepState::Undone,
+                StepState::Undone,
+                StepState::Failed,
+                StepState::Skipped
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("project/notes.txt")).unwrap(),
+            "notes"
+        );
+        assert!(root.join("project/src/main.rs").exists());
+        assert!(!root.join("moved").exists());
+        assert_eq!(
+            fs::read_to_string(root.join("taken")).unwrap(),
+            "in the way"
+        );
+        assert_eq!(fs::read_dir(root.join("project")).unwrap().count(), 2);
+
+        let entries = audit.recent(1).unwrap();
+        assert_eq!(entries[0].state, Some(TransactionState::RolledBack));
+        assert!(entries[0].error.is_some());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_step_argument_decoded() {
+        let raw = crate::lossless::encode(Path::new("/data/old"));
+        let arg: StepArg = serde_json::from_str(&format!(
+            r#"{{"kind":"move","from":{{"raw":"{}"}},"to":"/archive/old"}}"#,
+            raw
+        ))
+        .unwrap();
+        let step: io::Result<Step> = arg.into_step(PathArg::into_path_buf);
+        assert_eq!(
+            step.unwrap(),
+            Step::Move {
+                from: PathBuf::from("/data/old"),
+                to: PathBuf::from("/archive/old"),
+            }
+        );
+    }
+}
@@ -0,0 +1,853 @@
+use std::{
+    ffi::OsString,
+    fmt::Debug,
+    path::{Component, Path, PathBuf},
+};
+
+use tracing::{debug, warn};
+
+use crate::{
+    models::{AgeBuckets, VolumeCapabilities},
+    path_key::{self, PathKey},
+    tree::node::{Node, NodeId, NodeRef},
+};
+
+pub mod node;
+
+/**
+ * bytes a node added to its ancestors, as counted, as allocated on disk and
+ * as left out for being a further hard link to a counted file, along with
+ * the entries it added below them
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Growth {
+    pub size: usize,
+    pub allocated_size: usize,
+    pub hardlink_savings: usize,
+    pub entries: usize,
+}
+
+impl Growth {
+    pub fn of(node: &Node) -> Self {
+        Self {
+            size: node.size(),
+            allocated_size: node.allocated_size(),
+            hardlink_savings: node.hardlink_savings(),
+            entries: node.total_count(),
+        }
+    }
+
+    pub fn since(self, before: Growth) -> Self {
+        Self {
+            size: self.size.saturating_sub(before.size),
+            allocated_size: self.allocated_size.saturating_sub(before.allocated_size),
+            hardlink_savings: self
+                .hardlink_savings
+                .saturating_sub(before.hardlink_savings),
+            entries: self.entries.saturating_sub(before.entries),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Tree {
+    pub(crate) root: Option<NodeRef>,
+    /// of the scanned volume, lookups fall back to the platform default without it
+    pub(crate) capabilities: Option<VolumeCapabilities>,
+}
+
+impl Tree {
+    pub fn from_node(node: Node) -> Tree {
+        Tree {
+            root: Some(NodeRef::new(node)),
+            capabilities: None,
+        }
+    }
+
+    /**
+     * the scanned directory, every lookup goes through it
+     */
+    pub fn root_path(&self) -> Option<PathBuf> {
+        let root = self.root.as_ref()?.read().ok()?;
+        Some(PathKey::new(Path::new(&root.path)).into_path_buf())
+    }
+
+    pub fn capabilities(&self) -> Option<VolumeCapabilities> {
+        self.capabilities
+    }
+
+    pub fn set_capabilities(&mut self, capabilities: VolumeCapabilities) {
+        self.capabilities = Some(capabilities);
+    }
+
+    fn case_sensitive(&self) -> bool {
+        self.capabilities
+            .map_or(path_key::CASE_SENSITIVE, |capabilities| {
+                capabilities.case_sensitive
+            })
+    }
+
+    pub fn insert(&mut self, parent: &PathBuf, value: Node) -> Result<NodeRef, String> {
+        self.insert_node(parent, value)
+    }
+
+    pub fn insert_node(&mut self, parent: &PathBuf, value: Node) -> Result<NodeRef, String> {
+        debug!("enter insert node, for parent");
+        let parent_node = self
+            .get_node(parent)
+            .ok_or_else(|| format!("parent not found, {}", parent.display()))?;
+
+        let new_node = parent_node.alloc(value);
+        parent_node
+            .write()
+            .map_err(|err| format!("failed to write node, {}", err))?
+            .push_child(&new_node);
+
+        // the entries and bytes it brings reach every ancestor, the parent too
+        if let Some((growth, age)) = new_node
+            .read()
+            .ok()
+            .map(|node| (Growth::of(&node), node.age()))
+        {
+            Tree::bubble(&new_node, growth, &age);
+        }
+
+        return Ok(new_node);
+    }
+
+    /**
+     * Add the bytes and entries of `growth` and their age to every ancestor of `node`,
+     * after the node itself grew. The totals are atomic, only a shared lock
+     * is taken on the way, so workers bubbling up at once don't queue on the
+     * directories close to the root.
+     */
+    pub fn bubble(node: &NodeRef, growth: Growth, age: &AgeBuckets) {
+        for parent in Tree::ancestors(node) {
+            let _ = parent.read().map(|parent| parent.grow(growth, age));
+        }
+    }
+
+    /**
+     * drop the node at `key` with everything below it, returns what left its ancestors
+     */
+    pub fn remove(&mut self, key: &Path) -> Result<Growth, String> {
+        let target = self
+            .get_node(key)
+            .ok_or_else(|| format!("key:{} not found", key.display()))?;
+
+        let (growth, age, parent, complete) = if let Ok(node) = target.read()
+            && let Some(parent) = node.parent
+            && let Ok(mut detached) = target.at(parent).write()
+        {
+            detached.detach_child(&target, &node.path);
+            (Growth::of(&node), node.age(), parent, node.complete)
+        } else {
+            return Err("remove from parent failed".to_string());
+        };
+
+        // the direct parent too, the subtree leaves every ancestor
+        for parent in Tree::ancestors(&target) {
+            let _ = parent.read().map(|parent| parent.shrink(growth, &age));
+        }
+
+        // a worker still listing below it stops climbing here instead of growing the tree again
+        let _ = target.write().map(|mut node| node.parent = None);
+        if !complete {
+            // the parent no longer waits for it
+            Tree::settle_above(&target, Some(parent));
+        }
+
+        /*
+         * remove all cache node from search map
+         */
+        target.release();
+        Ok(growth)
+    }
+
+    pub fn trace_to_root<'a, F>(&mut self, node: &NodeRef, mut modify: F)
+    where
+        F: FnMut(&NodeRef),
+    {
+        let iter = RootIter {
+            node: Some(node.clone()),
+        };
+        for node in iter {
+            if let Some(parent) = node.parent() {
+                modify(&parent)
+            } else {
+                // Handle the case when there is no parent
+                // For example, you can log a message or do nothing
+                // Here, we'll log a message
+            }
+        }
+    }
+
+    pub fn contains(&self, key: &PathBuf) -> bool {
+        return self.get_node(key).is_some();
+    }
+
+    /**
+     * the parent of `node`, its parent and so on up to the root
+     */
+    pub fn ancestors(node: &NodeRef) -> impl Iterator<Item = NodeRef> {
+        RootIter {
+            node: Some(node.clone()),
+        }
+    }
+
+    pub fn path_to_root(node: &NodeRef) -> Result<PathBuf, String> {
+        let mut path =
+            node.read().map_or(
+                Err("Node not found".to_string()),
+                |node| Ok(node.get_path()),
+            )?;
+
+        let iter = RootIter {
+            node: Some(node.clone()),
+        };
+
+        for node in iter {
+            if let Ok(parent) = node.read() {
+                let mut new_path = parent.get_path();
+                new_path.push(path);
+                path = new_path;
+            }
+        }
+
+        Ok(path)
+    }
+
+    /**
+     * record how many subdirectories of a freshly listed `node` are still to be
+     * scanned, a directory with none left is complete and may complete its parents.
+     * Nothing changes past a node which is already complete, e.g. skipped by the user.
+     */
+    pub fn settle(node: &NodeRef, pending: usize) {
+        let next = node.write().ok().and_then(|mut node| {
+            if node.complete {
+                return None;
+            }
+            node.pending = pending;
+            node.complete = pending == 0;
+            node.complete.then_some(node.parent).flatten()
+        });
+        Tree::let_go(node, next);
+        Tree::settle_above(node, next);
+    }
+
+    /**
+     * one subdirectory of `next` is done, complete it and then its ancestors
+     * for as long as that was the last one they waited for. A directory
+     * which wasn't settled yet waits for nothing so far and stays as it is.
+     */
+    fn settle_above(node: &NodeRef, mut next: Option<NodeId>) {
+        while let Some(parent) = next {
+            let parent = node.at(parent);
+            next = parent.write().ok().and_then(|mut node| {
+                if node.complete || node.pending == 0 {
+                    return None;
+                }
+                node.pending -= 1;
+                node.complete = node.pending == 0;
+                node.complete.then_some(node.parent).flatten()
+            });
+            Tree::let_go(&parent, next);
+        }
+    }
+
+    /**
+     * Give a loose directory back to the arena once it completed, `next`
+     * being the parent it completes in turn. Its bytes went up to the
+     * directory at the collapsed depth and nothing below it points up to it
+     * anymore.
+     */
+    fn let_go(node: &NodeRef, next: Option<NodeId>) {
+        if next.is_some() && node.read().is_ok_and(|node| node.loose) {
+            node.release();
+        }
+    }
+
+    /**
+     * Drop the entries deeper than `depth` below the root, their bytes stay
+     * in the directory at `depth` holding them. Returns how many were dropped.
+     */
+    pub fn collapse(&mut self, depth: usize) -> usize {
+        let Some(root) = self.root.clone() else {
+            return 0;
+        };
+        let mut level = vec![root];
+        for _ in 0..depth {
+            level = level.iter().flat_map(NodeRef::children).collect();
+        }
+
+        let mut dropped = 0;
+        for node in level {
+            let Some((children, count)) = node.write().ok().map(|mut node| {
+                let children = node.take_children();
+                let count = node.count();
+                node.set_count(0);
+                (children, count)
+            }) else {
+                continue;
+            };
+            for parent in Tree::ancestors(&node) {
+                let _ = parent.read().map(|parent| parent.uncount(count));
+            }
+            dropped += count;
+
+            for child in children {
+                node.at(child).release();
+            }
+        }
+        dropped
+    }
+
+    /***
+     * find tree node with path, spelled any way `PathKey` folds together.
+     * An exact name wins over one only equal ignoring case, which is only
+     * tried when the volume ignores case. Paths outside the root aren't in the tree.
+     */
+    pub fn get_node(&self, key: &Path) -> Option<NodeRef> {
+        let key = PathKey::new(key);
+        let root = self.root_path()?;
+        let ignore_case = !self.case_sensitive();
+
+        let mut components = key.as_path().components();
+        for expected in root.components() {
+            let same = match (components.next()?, expected) {
+                (Component::Normal(name), Component::Normal(expected)) => {
+                    name == expected
+                        || ignore_case && path_key::same_name_ignoring_case(name, expected)
+                }
+                (component, expected) => component == expected,
+            };
+            if !same {
+                return None;
+            }
+        }
+
+        let mut current = self.root.clone();
+        let names = components.filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        });
+        for name in names {
+            let Some(node) = current else {
+                break;
+            };
+            current = node.child(name).or_else(|| {
+                ignore_case
+                    .then(|| node.child_ignoring_case(name))
+                    .flatten()
+            });
+        }
+        return current;
+    }
+
+    /**
+     * visit every node below `from` (included) in depth first order with its full path
+     */
+    pub fn walk<F>(&self, from: &Path, mut visit: F)
+    where
+        F: FnMut(&PathBuf, &Node),
+    {
+        let Some(start) = self.get_node(from) else {
+            return;
+        };
+
+        let from = PathKey::new(from).into_path_buf();
+        let mut stack: Vec<(PathBuf, NodeRef)> = vec![(from, start)];
+        while let Some((path, node)) = stack.pop() {
+            if let Ok(guard) = node.read() {
+                visit(&path, &guard);
+                for child in guard.children.iter().map(|id| node.at(*id)) {
+                    if let Some(name) = child.read().ok().map(|child| child.path.clone()) {
+                        stack.push((path.join(name), child));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.root
+            .as_ref()
+            .map_or(0, |node| node.read().map_or(0, |node| node.total_count()))
+    }
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        println!("tree recycled");
+        self.root.take();
+    }
+}
+
+struct RootIter {
+    pub(crate) node: Option<NodeRef>,
+}
+
+impl Iterator for RootIter {
+    type Item = NodeRef;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.node.as_ref().and_then(NodeRef::parent);
+        self.node = next.clone();
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+    use std::ffi::OsString;
+    use std::str::FromStr;
+
+    fn build_test_tree() -> Tree {
+        // create root node
+        let root_path = PathBuf::from("/");
+        let root_file = Node::new(root_path.clone().into_os_string(), true, false);
+        let mut tree: Tree = Tree::from_node(root_file);
+        //
+        let paths: Vec<String> = (0..10).map(|i| format!("dir{}", i)).collect();
+        let mut current_path = root_path.clone();
+        for dir_name in &paths {
+            println!("current:{}, new:{}", current_path.display(), dir_name);
+            let dir_node = Node::new(OsString::from(dir_name.clone()), true, false);
+
+            for i in 0..10 {
+                let file_name = format!("file{}", i);
+                let file_node = Node::new(OsString::from(file_name), false, false);
+                let _ = tree.insert(&current_path, file_node);
+            }
+
+            let _ = tree.insert(&current_path, dir_node);
+            current_path.push(dir_name.clone());
+        }
+        tree
+    }
+
+    // 测试树的创建
+    #[test]
+    fn test_tree_creation() {
+        let path = PathBuf::from("/");
+        let file = Node::new(path.clone().into_os_string(), true, false);
+        // 从值创建树
+        let mut tree: Tree = Tree::from_node(file);
+        assert!(tree.root.is_some());
+        assert_eq!(tree.size(), 1);
+        assert!(tree.contains(&path));
+
+        // 从节点创建树
+        let file_path1 = PathBuf::from("File1.txt");
+        let file1 = Node::new(file_path1.clone().into_os_string(), false, false);
+        let _ = tree.insert(&path, file1);
+        assert_eq!(tree.size(), 2);
+        assert!(tree.contains(&PathBuf::from("/File1.txt")));
+    }
+
+    // 测试构建深度为10且节点数少于100的树
+    #[test]
+    fn test_build_deep_tree() {
+        let tree = build_test_tree();
+        assert_eq!(tree.size(), 111);
+    }
+
+    // 测试节点插入
+    #[test]
+    fn test_find_node() {
+        let tree = build_test_tree();
+        assert!(
+            tree.get_node(&PathBuf::from("/dir0/dir1/dir2/file1"))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_find_node_any_spelling() {
+        let tree = build_test_tree();
+        let node = tree.get_node(&PathBuf::from("/dir0/dir1")).unwrap();
+        for spelling in ["/dir0/dir1/", "/dir0/./dir1", "/dir0/dir1/dir2/.."] {
+            let found = tree.get_node(&PathBuf::from(spelling)).unwrap();
+            assert!(found == node, "{}", spelling);
+        }
+        assert_eq!(
+            tree.get_node(&PathBuf::from("/DIR0/dir1")).is_some(),
+            !path_key::CASE_SENSITIVE
+        );
+    }
+
+    #[test]
+    fn test_find_node_follows_volume_case() {
+        let mut tree = build_test_tree();
+        let capabilities = |case_sensitive| VolumeCapabilities {
+            case_sensitive,
+            symlinks: true,
+            hardlinks: true,
+            reflinks: false,
+            compression: false,
+        };
+
+        tree.set_capabilities(capabilities(true));
+        assert!(tree.get_node(&PathBuf::from("/DIR0/dir1")).is_none());
+        tree.set_capabilities(capabilities(false));
+        assert!(tree.get_node(&PathBuf::from("/DIR0/dir1")).is_some());
+    }
+
+    #[test]
+    fn test_find_node_below_scanned_folder() {
+        let root = PathBuf::from("/data/scan");
+        let mut tree = Tree::from_node(Node::new(root.clone().into_os_string(), true, false));
+        let _ = tree.insert(&root, Node::new(OsString::from("a"), true, false));
+        assert_eq!(tree.root_path(), Some(root));
+
+        assert!(tree.get_node(&PathBuf::from("/data/scan/")).is_some());
+        assert!(tree.get_node(&PathBuf::from("/data/scan/a")).is_some());
+        assert!(tree.get_node(&PathBuf::from("/data/other/a")).is_none());
+        assert!(tree.get_node(&PathBuf::from("/a")).is_none());
+        assert!(tree.get_node(&PathBuf::from("/data")).is_none());
+    }
+
+    #[test]
+    fn test_find_node_in_wide_folder() {
+        let root = PathBuf::from("/wide");
+        let mut tree = Tree::from_node(Node::new(root.clone().into_os_string(), true, false));
+        for i in 0..100 {
+            let name = OsString::from(format!("file{}", i));
+            let _ = tree.insert(&root, Node::new(name, false, false));
+        }
+        // a sibling sharing a name only shows up once the first one is gone
+        let first = tree.get_node(&root.join("file7")).unwrap();
+        let twin = tree
+            .insert(&root, Node::new(OsString::from("file7"), false, false))
+            .unwrap();
+        assert_eq!(tree.get_node(&root.join("file7")).unwrap(), first);
+        assert!(tree.get_node(&root.join("file99")).is_some());
+        assert!(tree.get_node(&root.join("file100")).is_none());
+
+        let _ = tree.remove(&root.join("file7"));
+        assert_eq!(tree.get_node(&root.join("file7")).unwrap(), twin);
+        let _ = tree.remove(&root.join("file7"));
+        assert!(tree.get_node(&root.join("file7")).is_none());
+        assert!(tree.get_node(&root.join("file8")).is_some());
+        assert_eq!(tree.size(), 100);
+    }
+
+    // 测试节点插入
+    #[test]
+    fn test_insert_node() {
+        let mut tree = build_test_tree();
+
+        let before_count = tree.size();
+        // 插入子节点
+        let file1_path = PathBuf::from("File2.txt");
+        let file1 = Node::new(file1_path.clone().into_os_string(), false, false);
+        let result = tree.insert(&PathBuf::from("/"), file1);
+        assert!(result.is_ok());
+        assert_eq!(tree.size(), before_count + 1);
+        assert!(tree.contains(&PathBuf::from("/File2.txt")));
+
+        let before_count = tree.size();
+        // 插入到不存在的父节点
+        let file2_path = PathBuf::from("File3.txt");
+        let file2 = Node::new(file2_path.clone().into_os_string(), false, false);
+        let result = tree.insert(&PathBuf::from("nofile"), file2);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), "parent not found, nofile".to_string());
+        assert_eq!(tree.size(), before_count);
+    }
+
+    ///测试节点删除
+    #[test]
+    fn test_remove_node() {
+        let path = PathBuf::from("/");
+        // 从值创建树
+        let mut tree: Tree = build_test_tree();
+        let before_size = tree.size();
+        // 删除不存在的节点
+        let result = tree.remove(&PathBuf::from("non_existent"));
+        assert!(result.is_err());
+        assert_eq!(tree.size(), before_size);
+
+        let before_size = tree.size();
+        // 删除叶节点
+        let target_path = PathBuf::from("/dir0/dir1/dir2/file1");
+        let result = tree.remove(&target_path);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().entries, before_size - tree.size());
+        assert!(!tree.contains(&target_path));
+
+        // 删除带有子节点的节点
+        let before_size = tree.size();
+        let target_path = PathBuf::from("/dir0/dir1/dir2/dir3");
+        let result = tree.remove(&target_path);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().entries, before_size - tree.size());
+        assert!(!tree.contains(&target_path));
+    }
+
+    #[test]
+    fn test_walk_subtree() {
+        let tree = build_test_tree();
+        let mut paths = vec![];
+        tree.walk(
+            &PathBuf::from("/dir0/dir1/dir2/dir3/dir4/dir5/dir6/dir7/dir8"),
+            |path, _| paths.push(path.clone()),
+        );
+        // dir8 itself, its 10 files and dir9
+        assert_eq!(paths.len(), 12);
+        assert!(paths.contains(&PathBuf::from(
+            "/dir0/dir1/dir2/dir3/dir4/dir5/dir6/dir7/dir8/dir9"
+        )));
+    }
+
+    #[test]
+    fn test_settle_completes_parents() {
+        let tree = build_test_tree();
+        let root = tree.root.clone().unwrap();
+        let dir0 = tree.get_node(&PathBuf::from("/dir0")).unwrap();
+        let dir1 = tree.get_node(&PathBuf::from("/dir0/dir1")).unwrap();
+
+        Tree::settle(&root, 1);
+        Tree::settle(&dir0, 1);
+        assert!(!root.read().unwrap().complete);
+
+        Tree::settle(&dir1, 0);
+        assert!(dir1.read().unwrap().complete);
+        assert!(dir0.read().unwrap().complete);
+        assert!(root.read().unwrap().complete);
+    }
+
+    #[test]
+    fn test_removed_folder_keeps_its_worker_out() {
+        let root_path = PathBuf::from("/");
+        let mut tree = Tree::from_node(Node::new(root_path.clone().into_os_string(), true, false));
+        let _ = tree.insert(&root_path, Node::new(OsString::from("a"), true, false));
+        let a = PathBuf::from("/a");
+        let b = tree
+            .insert(&a, Node::new(OsString::from("b"), true, false))
+            .unwrap();
+        let root = tree.root.clone().unwrap();
+        Tree::settle(&root, 1);
+        Tree::settle(&tree.get_node(&a).unwrap(), 1);
+
+        tree.remove(&a).unwrap();
+        assert!(root.read().unwrap().complete);
+
+        // the worker listing `b` finishes after `a` was deleted
+        let growth = Growth {
+            size: 1000,
+            ..Growth::default()
+        };
+        Tree::bubble(&b, growth, &AgeBuckets::default());
+        Tree::settle(&b, 0);
+        assert_eq!(root.read().unwrap().size(), 0);
+        assert!(tree.get_node(&a).is_none());
+    }
+
+    #[derive(Debug, Clone)]
+    enum Operation {
+        Insert {
+            parent: usize,
+            is_dir: bool,
+            size: usize,
+        },
+        Remove {
+            target: usize,
+        },
+        Grow {
+            target: usize,
+            delta: usize,
+        },
+    }
+
+    fn operation() -> impl Strategy<Value = Operation> {
+        prop_oneof![
+            3 => (any::<usize>(), any::<bool>(), 0..1000usize).prop_map(|(parent, is_dir, size)| {
+                Operation::Insert {
+                    parent,
+                    is_dir,
+                    size,
+                }
+            }),
+            1 => any::<usize>().prop_map(|target| Operation::Remove { target }),
+            1 => (any::<usize>(), 1..1000usize)
+                .prop_map(|(target, delta)| Operation::Grow { target, delta }),
+        ]
+    }
+
+    /**
+     * compare the tree against a flat model of `path -> (is_dir, own size)`
+     */
+    fn check_invariants(tree: &Tree, model: &BTreeMap<PathBuf, (bool, usize)>) {
+        assert_eq!(tree.size(), model.len());
+
+        for (path, _) in model.iter() {
+            let node = tree.get_node(path).expect("modelled path is missing");
+            assert_eq!(&Tree::path_to_root(&node).unwrap(), path);
+
+            let below: Vec<_> = model
+                .iter()
+                .filter(|(other, _)| other.starts_with(path))
+                .collect();
+            let node = node.read().unwrap();
+            assert_eq!(node.count(), below.len() - 1, "count of {}", path.display());
+            assert_eq!(
+                node.size(),
+                below.iter().map(|(_, (_, size))| size).sum::<usize>(),
+                "size of {}",
+                path.display()
+            );
+        }
+
+        // every child points back to the node holding it
+        let mut stack = vec![tree.root.clone().unwrap()];
+        let mut visited = 0;
+        while let Some(node) = stack.pop() {
+            visited += 1;
+            for child in node.children() {
+                let parent = child.parent().expect("orphan node");
+                assert_eq!(parent, node);
+                stack.push(child);
+            }
+        }
+        assert_eq!(visited, model.len());
+    }
+
+    proptest! {
+        #[test]
+        fn test_random_operations_keep_invariants(operations in prop::collection::vec(operation(), 1..64)) {
+            let root = PathBuf::from("/");
+            let mut tree = Tree::from_node(Node::new(root.clone().into_os_string(), true, false));
+            let mut model = BTreeMap::from([(root.clone(), (true, 0))]);
+
+            for (index, operation) in operations.into_iter().enumerate() {
+                match operation {
+                    Operation::Insert { parent, is_dir, size } => {
+                        let dirs: Vec<_> = model.iter().filter(|(_, (is_dir, _))| *is_dir).map(|(path, _)| path.clone()).collect();
+                        let parent = &dirs[parent % dirs.len()];
+                        let name = format!("node{}", index);
+                        let node = Node::new(OsString::from(&name), is_dir, false);
+                        node.set_size(size);
+
+                        prop_assert!(tree.insert(parent, node).is_ok());
+                        model.insert(parent.join(name), (is_dir, size));
+                    }
+                    Operation::Remove { target } => {
+                        let candidates: Vec<_> = model.keys().filter(|path| **path != root).cloned().collect();
+                        if candidates.is_empty() {
+                            continue;
+                        }
+                        let target = &candidates[target % candidates.len()];
+
+                        prop_assert!(tree.remove(target).is_ok());
+                        model.retain(|path, _| !path.starts_with(target));
+                    }
+                    Operation::Grow { target, delta } => {
+                        let paths: Vec<_> = model.keys().cloned().collect();
+                        let target = &paths[target % paths.len()];
+                        let node = tree.get_node(target).unwrap();
+
+                        let own = node.read().unwrap().size();
+                        node.read().unwrap().set_size(own + delta);
+                        let growth = Growth {
+                            size: delta,
+                            ..Growth::default()
+                        };
+                        Tree::bubble(&node, growth, &AgeBuckets::default());
+                        model.get_mut(target).unwrap().1 += delta;
+                    }
+                }
+                check_invariants(&tree, &model);
+            }
+        }
+    }
+
+    #[test]
+    fn test_collapse_keeps_sizes() {
+        let mut tree = build_test_tree();
+        let deep = PathBuf::from("/dir0/dir1/dir2/file0");
+        let node = tree.get_node(&deep).unwrap();
+        node.read().unwrap().set_size(100);
+        let growth = Growth {
+            size: 100,
+            ..Growth::default()
+        };
+        Tree::bubble(&node, growth, &AgeBuckets::default());
+
+        assert_eq!(tree.collapse(2), 88);
+        assert_eq!(tree.size(), 23);
+        assert!(tree.get_node(&deep).is_none());
+        {
+            let collapsed = tree.get_node(&PathBuf::from("/dir0/dir1")).unwrap();
+            let collapsed = collapsed.read().unwrap();
+            assert!(collapsed.children.is_empty());
+            assert_eq!(collapsed.size(), 100);
+        }
+        assert_eq!(tree.collapse(2), 0);
+    }
+
+    #[test]
+    fn test_released_slots_reused() {
+        let mut tree = build_test_tree();
+        let root = tree.root.clone().unwrap();
+        let dir = PathBuf::from("/dir0");
+        let watched = |tree: &mut Tree| {
+            let _ = tree.insert(&dir, Node::new(OsString::from("watched"), false, false));
+            assert!(tree.remove(&dir.join("watched")).is_ok());
+        };
+        // the first one takes a new slot, the others the one it left
+        watched(&mut tree);
+        let slots = root.slots();
+        for _ in 0..50 {
+            watched(&mut tree);
+        }
+        assert_eq!(root.slots(), slots);
+
+        // the directories below are still being listed and keep their slots, the files go
+        assert_eq!(tree.collapse(2), 88);
+        for i in 0..80 {
+            let name = OsString::from(format!("new{}", i));
+            let _ = tree.insert(&dir, Node::new(name, false, false));
+        }
+        assert_eq!(root.slots(), slots);
+        assert_eq!(tree.size(), 23 + 80);
+    }
+
+    ///测试路径获取
+    #[test]
+    fn test_path_to_root() {
+        let tree = build_test_tree();
+
+        let target_path = PathBuf::from("/dir0/dir1/dir2/dir3/file3");
+        // 获取从叶节点到根的路径
+        let node = tree.get_node(&target_path);
+        assert!(node.is_some());
+
+        let path = Tree::path_to_root(&node.unwrap()).unwrap();
+        assert_eq!(path, target_path);
+    }
+
+    // // 测试节点值的访问
+    // #[test]
+    // fn test_node_value_access() {
+    //     let node = Node::new("test".to_string(), 42);
+    //     assert_eq!(node.get_value(), &42);
+    // }
+
+    // // 测试节点更新
+    // #[test]
+    // fn test_node_update() {
+    //     let node = Node::new("test".to_string(), 42);
+    //     let node_ref = Arc::new(RwLock::new(node));
+
+    //     {
+    //         let mut node = node_ref.write().unwrap();
+    //         node.update(|value| *value = 100);
+    //     }
+
+    //     assert_eq!(node_ref.read().unwrap().get_value(), &100);
+    // }
+}
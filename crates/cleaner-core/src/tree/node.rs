@@ -0,0 +1,763 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet, VecDeque},
+    ffi::{OsStr, OsString},
+    fmt::{Debug, Display},
+    path::{Path, PathBuf},
+    ptr::eq,
+    sync::{
+        Arc, LockResult, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::{
+    file_id::FileId,
+    locks::LockFlags,
+    models::AgeBuckets,
+    path_key::{self, fold_case},
+    tree::Growth,
+};
+
+/**
+ * children a directory holds before they are indexed by name, looking
+ * through a few is faster than hashing and saves the index on most nodes
+ */
+const INDEXED_FANOUT: usize = 32;
+
+/**
+ * The children of a wide directory by name, the first of siblings sharing
+ * one. Where the platform ignores case the names holding upper case letters
+ * are kept folded too, a lower case name is its own folded key.
+ */
+#[derive(Debug, Default)]
+struct NameIndex {
+    exact: HashMap<OsString, NodeId>,
+    folded: HashMap<OsString, NodeId>,
+}
+
+impl NameIndex {
+    fn insert(&mut self, name: &OsStr, id: NodeId) {
+        self.exact.entry(name.to_os_string()).or_insert(id);
+        if !path_key::CASE_SENSITIVE
+            && let Cow::Owned(folded) = fold_case(name)
+        {
+            self.folded.entry(folded).or_insert(id);
+        }
+    }
+
+    /**
+     * forget `id` under `name`, the first sibling named alike takes its
+     * place, `sibling` finds the first one whose name passes a test
+     */
+    fn remove<F>(&mut self, name: &OsStr, id: NodeId, sibling: F)
+    where
+        F: Fn(&dyn Fn(&OsStr) -> bool) -> Option<NodeId>,
+    {
+        if self.exact.get(name) == Some(&id) {
+            self.exact.remove(name);
+            if let Some(sibling) = sibling(&|other| other == name) {
+                self.exact.insert(name.to_os_string(), sibling);
+            }
+        }
+        if path_key::CASE_SENSITIVE {
+            return;
+        }
+        let Cow::Owned(folded) = fold_case(name) else {
+            return;
+        };
+        if self.folded.get(&folded) == Some(&id) {
+            self.folded.remove(&folded);
+            let alike = |other: &OsStr| other != folded && fold_case(other) == folded.as_os_str();
+            if let Some(sibling) = sibling(&alike) {
+                self.folded.insert(folded, sibling);
+            }
+        }
+    }
+}
+
+/**
+ * where a node sits in the arena of its tree
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(u32);
+
+/**
+ * The nodes of one tree, each behind its own lock so scan workers fill
+ * directories side by side. Nodes refer to each other by their `NodeId`,
+ * which takes a few bytes where a pointer to a shared node took an
+ * allocation with its counts. The slots of nodes dropped from the tree are
+ * released and taken again before the arena grows.
+ */
+struct Arena {
+    nodes: boxcar::Vec<RwLock<Node>>,
+    /// released slots, their nodes emptied
+    free: Mutex<Vec<NodeId>>,
+}
+
+impl Arena {
+    fn alloc(&self, node: Node) -> NodeId {
+        let reused = self
+            .free
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+        if let Some(id) = reused {
+            *self.nodes[id.0 as usize]
+                .write()
+                .unwrap_or_else(PoisonError::into_inner) = node;
+            return id;
+        }
+        let index = self.nodes.push(RwLock::new(node));
+        NodeId(u32::try_from(index).expect("more nodes than a tree can address"))
+    }
+
+    /**
+     * empty the node `id` and hand its slot to the next allocation
+     */
+    fn release(&self, id: NodeId) {
+        *self.nodes[id.0 as usize]
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Node::new(OsString::new(), false, false);
+        self.free
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(id);
+    }
+}
+
+/**
+ * a node of a tree, cheap to clone, it keeps the arena of the tree alive
+ */
+#[derive(Clone)]
+pub struct NodeRef {
+    arena: Arc<Arena>,
+    id: NodeId,
+}
+
+#[derive(Debug)]
+pub struct Node {
+    pub path: OsString,
+    size: AtomicU64, //the totals of the subtree grow from below under a shared lock
+    allocated_size: AtomicU64, //bytes taken on disk by the blocks of all sub files
+    hardlink_savings: AtomicU64, //bytes of further hard links to a file counted elsewhere, left out of size
+    pub file_id: Option<FileId>, //what the entry is whatever its path, where the listing tells it
+    pub is_directory: bool,
+    pub is_link: bool,
+    pub modified: Option<u64>,
+    pub created: Option<u64>,
+    pub locks: LockFlags,
+    age: AtomicAge,                   //bytes of all sub files by modification age
+    pub(crate) listed: bool, //directory entries have been read, by whichever queue got it first
+    pub complete: bool,      //size is final, the whole subtree has been listed
+    pub(crate) pending: usize, //subdirectories still waiting to complete
+    pub skipped: bool,       //the user stopped the scan from descending here
+    pub(crate) loose: bool,  //listed below the collapsed depth, no parent holds it
+    count: AtomicU64,        //total count of all sub nodes
+    pub(crate) children: Vec<NodeId>, //all files and dirs in this node
+    #[allow(clippy::box_collection)] //a pointer on every node instead of a whole empty map
+    index: Option<Box<NameIndex>>, //children by name once there are many
+    pub(crate) parent: Option<NodeId>, //parent node in the same arena
+}
+
+#[derive(Debug)]
+pub enum UpdateMode {
+    Child,
+    Root,
+}
+
+impl NodeRef {
+    /**
+     * `node` as the root of a new arena
+     */
+    pub fn new(node: Node) -> NodeRef {
+        let arena = Arc::new(Arena {
+            nodes: boxcar::Vec::new(),
+            free: Mutex::new(Vec::new()),
+        });
+        let id = arena.alloc(node);
+        NodeRef { arena, id }
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /**
+     * slots the arena of the node has, released ones included
+     */
+    #[cfg(test)]
+    pub(crate) fn slots(&self) -> usize {
+        self.arena.nodes.count()
+    }
+
+    /**
+     * nodes the arena holds, its slots less the released ones
+     */
+    #[cfg(test)]
+    pub(crate) fn live(&self) -> usize {
+        let free = self.arena.free.lock().map_or(0, |free| free.len());
+        self.arena.nodes.count() - free
+    }
+
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, Node>> {
+        self.arena.nodes[self.id.0 as usize].read()
+    }
+
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, Node>> {
+        self.arena.nodes[self.id.0 as usize].write()
+    }
+
+    /**
+     * the node `id` of the same tree
+     */
+    pub fn at(&self, id: NodeId) -> NodeRef {
+        NodeRef {
+            arena: self.arena.clone(),
+            id,
+        }
+    }
+
+    /**
+     * Put `child` in the arena below this node, without adding it to the
+     * children yet. Its ancestors are reachable from it, it isn't found
+     * from them until it is added.
+     */
+    pub fn alloc(&self, mut child: Node) -> NodeRef {
+        child.parent = Some(self.id);
+        self.at(self.arena.alloc(child))
+    }
+
+    /**
+     * put `child` in the arena and add it to the children, counted in this
+     * node only
+     */
+    pub fn insert(&self, child: Node) -> NodeRef {
+        let child = self.alloc(child);
+        if let Ok(mut node) = self.write() {
+            node.add_child(&child);
+        }
+        child
+    }
+
+    pub fn parent(&self) -> Option<NodeRef> {
+        let parent = self.read().ok()?.parent?;
+        Some(self.at(parent))
+    }
+
+    pub fn children(&self) -> Vec<NodeRef> {
+        self.read().map_or(vec![], |node| {
+            node.children.iter().map(|id| self.at(*id)).collect()
+        })
+    }
+
+    /**
+     * the child called exactly `name`
+     */
+    pub fn child(&self, name: &OsStr) -> Option<NodeRef> {
+        let node = self.read().ok()?;
+        if let Some(index) = node.index.as_ref() {
+            return index.exact.get(name).map(|id| self.at(*id));
+        }
+        node.children
+            .iter()
+            .map(|id| self.at(*id))
+            .find(|child| child.read().is_ok_and(|child| child.path == name))
+    }
+
+    /**
+     * a child called `name` ignoring case, from the index where the
+     * platform keeps names folded, else by looking through the children
+     */
+    pub fn child_ignoring_case(&self, name: &OsStr) -> Option<NodeRef> {
+        let node = self.read().ok()?;
+        if let Some(index) = node.index.as_ref().filter(|_| !path_key::CASE_SENSITIVE) {
+            let folded = fold_case(name);
+            return index
+                .folded
+                .get(&*folded)
+                .or_else(|| index.exact.get(&*folded))
+                .map(|id| self.at(*id));
+        }
+        node.children.iter().map(|id| self.at(*id)).find(|child| {
+            child
+                .read()
+                .is_ok_and(|child| path_key::same_name_ignoring_case(&child.path, name))
+        })
+    }
+
+    /**
+     * Give the slots of this node, dropped from the tree, and of everything
+     * below it back to the arena, returns how many went. A directory still
+     * being listed stays with its ancestors, its scan worker bubbles up
+     * through them and stops at this node, which has no parent anymore.
+     */
+    pub(crate) fn release(&self) -> usize {
+        let mut nodes = vec![];
+        let mut stack = vec![self.id];
+        while let Some(id) = stack.pop() {
+            let node = self.at(id);
+            if let Ok(mut node) = node.write() {
+                stack.extend(node.take_children());
+            }
+            nodes.push(node);
+        }
+
+        let mut kept = HashSet::new();
+        for node in nodes
+            .iter()
+            .filter(|node| node.read().is_ok_and(|node| !node.complete))
+        {
+            let mut next = Some(node.clone());
+            while let Some(node) = next.filter(|node| kept.insert(node.id)) {
+                next = (node.id != self.id).then(|| node.parent()).flatten();
+            }
+        }
+
+        let released = nodes.len() - kept.len();
+        for node in nodes.into_iter().filter(|node| !kept.contains(&node.id)) {
+            self.arena.release(node.id);
+        }
+        released
+    }
+
+    /**
+     *  remove all children from this node and return all nodes contains in this subtree
+     */
+    pub fn remove_child(&self, key: &Path) -> Vec<NodeRef> {
+        let Some(elem) = self.child(key.as_os_str()) else {
+            return vec![];
+        };
+        if let Ok(mut node) = self.write() {
+            node.detach_child(&elem, key.as_os_str());
+            node.uncount(elem.read().map_or(0, |node| node.total_count()));
+        }
+
+        let mut elems: Vec<NodeRef> = vec![];
+        let mut queue: VecDeque<NodeRef> = VecDeque::new();
+        queue.push_back(elem);
+        while let Some(node) = queue.pop_front() {
+            let children = node.write().map_or(vec![], |mut node| node.take_children());
+            queue.extend(children.into_iter().map(|id| node.at(id)));
+            elems.push(node);
+        }
+        elems
+    }
+}
+
+impl PartialEq for NodeRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && Arc::ptr_eq(&self.arena, &other.arena)
+    }
+}
+
+impl Eq for NodeRef {}
+
+impl Debug for NodeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("NodeRef").field(&self.id.0).finish()
+    }
+}
+
+impl Node {
+    pub fn new(path: OsString, is_dir: bool, is_link: bool) -> Node {
+        Node {
+            path: path,
+            size: AtomicU64::new(0),
+            allocated_size: AtomicU64::new(0),
+            hardlink_savings: AtomicU64::new(0),
+            file_id: None,
+            is_directory: is_dir,
+            is_link: is_link,
+            modified: None,
+            created: None,
+            locks: LockFlags::default(),
+            age: AtomicAge::default(),
+            listed: false,
+            complete: !is_dir,
+            pending: 0,
+            skipped: false,
+            loose: false,
+            count: AtomicU64::new(0), //self is the first one
+            children: Vec::new(),
+            index: None,
+            parent: None,
+        }
+    }
+
+    fn from(node: &Node) -> Node {
+        Node {
+            path: node.path.clone(),
+            size: AtomicU64::new(node.size() as u64),
+            allocated_size: AtomicU64::new(node.allocated_size() as u64),
+            hardlink_savings: AtomicU64::new(node.hardlink_savings() as u64),
+            file_id: node.file_id,
+            is_directory: node.is_directory,
+            is_link: node.is_link,
+            modified: node.modified,
+            created: node.created,
+            locks: node.locks,
+            age: AtomicAge::new(node.age()),
+            listed: node.listed,
+            complete: node.complete,
+            pending: node.pending,
+            skipped: node.skipped,
+            loose: node.loose,
+            count: AtomicU64::new(0), //self is the first one
+            children: Vec::new(),
+            index: None,
+            parent: None,
+        }
+    }
+
+    /**
+     * add `child`, allocated below this node, to the children and count it
+     * with what it holds
+     */
+    pub fn add_child(&mut self, child: &NodeRef) {
+        self.recount(child.read().map_or(0, |child| child.total_count())); //include the inserted node
+        self.push_child(child);
+    }
+
+    /**
+     * add `child` to the children, leaving the counts alone
+     */
+    pub(crate) fn push_child(&mut self, child: &NodeRef) {
+        self.children.push(child.id);
+        if let Some(index) = self.index.as_mut() {
+            if let Ok(name) = child.read().map(|child| child.path.clone()) {
+                index.insert(&name, child.id);
+            }
+        } else if self.children.len() > INDEXED_FANOUT {
+            // the first of siblings sharing a name wins, as a scan would find it
+            let mut index = NameIndex::default();
+            for id in &self.children {
+                if let Ok(name) = child.at(*id).read().map(|child| child.path.clone()) {
+                    index.insert(&name, *id);
+                }
+            }
+            self.index = Some(Box::new(index));
+        }
+    }
+
+    /**
+     * drop `child` named `name` from the children by identity, a sibling may
+     * carry the same name. The counts are left alone.
+     */
+    pub(crate) fn detach_child(&mut self, child: &NodeRef, name: &OsStr) {
+        self.children.retain(|other| *other != child.id);
+        if let Some(index) = self.index.as_mut() {
+            let children = &self.children;
+            index.remove(name, child.id, |passes| {
+                children.iter().copied().find(|other| {
+                    child
+                        .at(*other)
+                        .read()
+                        .is_ok_and(|other| passes(other.path.as_os_str()))
+                })
+            });
+        }
+    }
+
+    /**
+     * all children, leaving none behind
+     */
+    pub(crate) fn take_children(&mut self) -> Vec<NodeId> {
+        self.index = None;
+        std::mem::take(&mut self.children)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size.load(Ordering::Relaxed) as usize
+    }
+
+    pub fn allocated_size(&self) -> usize {
+        self.allocated_size.load(Ordering::Relaxed) as usize
+    }
+
+    pub fn hardlink_savings(&self) -> usize {
+        self.hardlink_savings.load(Ordering::Relaxed) as usize
+    }
+
+    pub fn age(&self) -> AgeBuckets {
+        self.age.load()
+    }
+
+    /**
+     * the entries below the node
+     */
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed) as usize
+    }
+
+    pub fn total_count(&self) -> usize {
+        self.count() + 1
+    }
+
+    pub fn set_size(&self, size: usize) {
+        self.size.store(size as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_allocated_size(&self, allocated_size: usize) {
+        self.allocated_size
+            .store(allocated_size as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_hardlink_savings(&self, hardlink_savings: usize) {
+        self.hardlink_savings
+            .store(hardlink_savings as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_age(&self, age: AgeBuckets) {
+        self.age.store(age);
+    }
+
+    pub(crate) fn set_count(&self, count: usize) {
+        self.count.store(count as u64, Ordering::Relaxed);
+    }
+
+    /**
+     * add the bytes and entries of `growth` and their `age` to the totals,
+     * which only takes a shared lock so workers growing the same ancestors
+     * don't wait on each other
+     */
+    pub fn grow(&self, growth: Growth, age: &AgeBuckets) {
+        add(&self.size, growth.size);
+        add(&self.allocated_size, growth.allocated_size);
+        add(&self.hardlink_savings, growth.hardlink_savings);
+        add(&self.count, growth.entries);
+        self.age.merge(age);
+    }
+
+    /**
+     * take the bytes and entries of `growth` and their `age` off the totals,
+     * none going below zero
+     */
+    pub fn shrink(&self, growth: Growth, age: &AgeBuckets) {
+        subtract(&self.size, growth.size);
+        subtract(&self.allocated_size, growth.allocated_size);
+        subtract(&self.hardlink_savings, growth.hardlink_savings);
+        subtract(&self.count, growth.entries);
+        self.age.subtract(age);
+    }
+
+    /**
+     * `entries` more below the node
+     */
+    pub(crate) fn recount(&self, entries: usize) {
+        add(&self.count, entries);
+    }
+
+    /**
+     * `entries` fewer below the node
+     */
+    pub(crate) fn uncount(&self, entries: usize) {
+        subtract(&self.count, entries);
+    }
+
+    pub fn get_name(&self) -> Result<String, String> {
+        self.path
+            .clone()
+            .into_string()
+            .map_err(|err| err.to_string_lossy().into_owned())
+    }
+
+    pub fn get_path(&self) -> PathBuf {
+        PathBuf::from(self.path.clone())
+    }
+
+    pub fn clear(&mut self) {
+        self.take_children();
+    }
+}
+
+fn add(total: &AtomicU64, value: usize) {
+    total.fetch_add(value as u64, Ordering::Relaxed);
+}
+
+fn subtract(total: &AtomicU64, value: usize) {
+    let _ = total.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |total| {
+        Some(total.saturating_sub(value as u64))
+    });
+}
+
+/**
+ * `AgeBuckets` grown and shrunk in place by the nodes below
+ */
+#[derive(Debug, Default)]
+struct AtomicAge {
+    recent: AtomicU64,
+    months: AtomicU64,
+    half_year: AtomicU64,
+    stale: AtomicU64,
+}
+
+impl AtomicAge {
+    fn new(age: AgeBuckets) -> Self {
+        let atomic = AtomicAge::default();
+        atomic.store(age);
+        atomic
+    }
+
+    fn load(&self) -> AgeBuckets {
+        AgeBuckets {
+            recent: self.recent.load(Ordering::Relaxed) as usize,
+            months: self.months.load(Ordering::Relaxed) as usize,
+            half_year: self.half_year.load(Ordering::Relaxed) as usize,
+            stale: self.stale.load(Ordering::Relaxed) as usize,
+        }
+    }
+
+    fn store(&self, age: AgeBuckets) {
+        self.recent.store(age.recent as u64, Ordering::Relaxed);
+        self.months.store(age.months as u64, Ordering::Relaxed);
+        self.half_year
+            .store(age.half_year as u64, Ordering::Relaxed);
+        self.stale.store(age.stale as u64, Ordering::Relaxed);
+    }
+
+    fn merge(&self, age: &AgeBuckets) {
+        add(&self.recent, age.recent);
+        add(&self.months, age.months);
+        add(&self.half_year, age.half_year);
+        add(&self.stale, age.stale);
+    }
+
+    fn subtract(&self, age: &AgeBuckets) {
+        subtract(&self.recent, age.recent);
+        subtract(&self.months, age.months);
+        subtract(&self.half_year, age.half_year);
+        subtract(&self.stale, age.stale);
+    }
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+
+    fn ne(&self, other: &Self) -> bool {
+        return !eq(self, other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::NAN;
+    use std::ffi::OsString;
+    use std::path;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_node_creation() {
+        let path = PathBuf::from("/test");
+        let node = Node::new(path.clone().into_os_string(), false, false);
+        assert_eq!(node.path, path);
+        assert_eq!(node.get_name(), Ok(String::from("/test")));
+        assert_eq!(node.count(), 0);
+    }
+
+    #[test]
+    fn test_node_insert() {
+        let path = PathBuf::from("/root");
+        let fileNode = NodeRef::new(Node::new(path.clone().into_os_string(), false, false));
+
+        let child_path = PathBuf::from("/root/child");
+        let child_node = Node::new(child_path.clone().into_os_string(), false, false);
+        let child = fileNode.insert(child_node);
+
+        assert_eq!(fileNode.read().unwrap().count(), 1);
+        assert_eq!(child.parent(), Some(fileNode));
+    }
+
+    //text multi nodes insert
+    #[test]
+    fn test_nodes_insert() {
+        let path = PathBuf::from("/root");
+        let node = NodeRef::new(Node::new(path.clone().into_os_string(), false, false));
+
+        let files = vec!["file1.txt", "file2.txt", "file3.txt"];
+        for name in files {
+            let child_path = path.clone().join(name);
+            let child_node = Node::new(child_path.clone().into_os_string(), false, false);
+            node.insert(child_node);
+        }
+
+        assert_eq!(node.read().unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_node_remove() {
+        let path = PathBuf::from("/root");
+        let node = create_nodes(path.clone());
+        assert_eq!(node.read().unwrap().count(), 7);
+
+        let mut exist_path = path.clone();
+        exist_path.push("file1.txt");
+        let ret = node.remove_child(&exist_path);
+        assert_eq!(ret.len(), 1);
+        assert_eq!(node.read().unwrap().count(), 6);
+    }
+
+    #[test]
+    fn test_wide_node_indexed() {
+        let node = NodeRef::new(Node::new(OsString::from("/root"), true, false));
+        for i in 0..=INDEXED_FANOUT {
+            node.insert(Node::new(
+                OsString::from(format!("file{}", i)),
+                false,
+                false,
+            ));
+        }
+        assert!(node.read().unwrap().index.is_some());
+        let child = node.child(OsStr::new("file3")).unwrap();
+        assert_eq!(child.read().unwrap().path, "file3");
+        assert!(node.child(OsStr::new("missing")).is_none());
+    }
+
+    #[test]
+    fn test_wide_node_found_ignoring_case() {
+        let node = NodeRef::new(Node::new(OsString::from("/root"), true, false));
+        for i in 0..=INDEXED_FANOUT {
+            node.insert(Node::new(
+                OsString::from(format!("File{}", i)),
+                false,
+                false,
+            ));
+        }
+        let first = node.child(OsStr::new("File3")).unwrap();
+        let twin = node.insert(Node::new(OsString::from("FILE3"), false, false));
+        {
+            let node = node.read().unwrap();
+            let index = node.index.as_ref().unwrap();
+            assert_eq!(index.folded.is_empty(), path_key::CASE_SENSITIVE);
+        }
+
+        assert_eq!(node.child_ignoring_case(OsStr::new("file3")), Some(first));
+        assert!(node.child_ignoring_case(OsStr::new("file99")).is_none());
+        node.remove_child(Path::new("File3"));
+        assert_eq!(node.child_ignoring_case(OsStr::new("file3")), Some(twin));
+    }
+
+    fn create_nodes(mut root: PathBuf) -> NodeRef {
+        let node = NodeRef::new(Node::new(root.clone().into_os_string(), false, false));
+        let files = vec!["file1.txt", "file2.txt", "file3.txt"];
+        for name in files {
+            let child_path = root.clone().join(name);
+            let child_node = Node::new(child_path.clone().into_os_string(), false, false);
+            node.insert(child_node);
+        }
+
+        root.push("dir1");
+        let sub_node = node.alloc(Node::new(root.clone().into_os_string(), false, false));
+        let files = vec!["file1.bin", "file2.bin", "file3.bin"];
+        for name in files {
+            let child_path = root.clone().join(name);
+            let child_node = Node::new(child_path.clone().into_os_string(), false, false);
+            sub_node.insert(child_node);
+        }
+        node.write().unwrap().add_child(&sub_node);
+        node
+    }
+}
@@ -0,0 +1,51 @@
+use std::{path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/**
+ * how long a directory listing may go without a new entry before the
+ * watchdog reports it, e.g. a dying disk or an unreachable network mount
+ */
+pub const DEFAULT_STALL_SECS: u64 = 30;
+
+/**
+ * when a scan worker counts as hung and what to do about it
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Watchdog {
+    /// seconds without a new entry before a listing counts as stalled
+    pub stall_secs: u64,
+    /// give up on a stalled directory and carry on with the rest of the scan
+    pub skip: bool,
+}
+
+impl Watchdog {
+    pub fn stall_after(&self) -> Duration {
+        Duration::from_secs(self.stall_secs.max(1))
+    }
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self {
+            stall_secs: DEFAULT_STALL_SECS,
+            skip: false,
+        }
+    }
+}
+
+/**
+ * a worker found stuck on a directory, reported once until the listing moves again
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanStall {
+    pub worker: usize,
+    pub path: PathBuf,
+    /// seconds since the listing last got an entry
+    pub idle_secs: u64,
+    /// the directory was skipped, its subtree keeps what was listed so far
+    pub skipped: bool,
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    activity::{ActivityEntry, ActivityKind},
+    database::{Database, to_io},
+    review::{ReviewQueue, ReviewState},
+};
+
+pub const WEEK_SECS: u64 = 7 * 24 * 60 * 60;
+
+/**
+ * the first Monday after the epoch, weeks start on Mondays at midnight UTC
+ */
+const FIRST_MONDAY: u64 = 4 * 24 * 60 * 60;
+
+/**
+ * how often the scheduler looks whether a week ended without a summary
+ */
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/**
+ * summaries kept, a year of weeks
+ */
+pub const KEEP_SUMMARIES: i64 = 52;
+
+/**
+ * paths listed as new hotspots in a summary at the most
+ */
+pub const MAX_HOTSPOTS: usize = 5;
+
+/**
+ * the start of the week `secs` fall in, in unix seconds
+ */
+pub fn week_start(secs: u64) -> u64 {
+    if secs < FIRST_MONDAY {
+        return 0;
+    }
+    (secs - FIRST_MONDAY) / WEEK_SECS * WEEK_SECS + FIRST_MONDAY
+}
+
+/**
+ * the start of the last week which is over at `now`
+ */
+pub fn last_week(now: u64) -> u64 {
+    week_start(now).saturating_sub(WEEK_SECS)
+}
+
+/**
+ * a path which took noticeably more space over the week
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Hotspot {
+    pub path: PathBuf,
+    /// bytes it grew by over the week
+    pub grown: u64,
+}
+
+/**
+ * what happened to the disks over one week, from the activity feed and the
+ * review queues
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklySummary {
+    /// unix seconds of the Monday it starts on
+    pub week_start: u64,
+    pub created_at: u64,
+    /// bytes the cleanups of the week removed or moved away
+    pub space_freed: u64,
+    pub cleanups: usize,
+    /// how much more the scanned roots took at the end of the week, below zero when they shrank
+    pub space_grown: i64,
+    /// the paths which grew the most and weren't a hotspot the week before, largest first
+    pub new_hotspots: Vec<Hotspot>,
+    /// items waiting in the review queues for a decision or for their deletion
+    pub pending_recommendations: usize,
+    pub pending_bytes: u64,
+}
+
+impl WeeklySummary {
+    /**
+     * nothing was cleaned, grew or waits for a decision, not worth a notification
+     */
+    pub fn is_quiet(&self) -> bool {
+        self.cleanups == 0
+            && self.space_grown == 0
+            && self.new_hotspots.is_empty()
+            && self.pending_recommendations == 0
+    }
+}
+
+/**
+ * the summary of the week from `week_start`, out of the `activity` recorded
+ * during it, the `previous` summary and the review `queues` as they are now
+ */
+pub fn compile(
+    week_start: u64,
+    activity: &[ActivityEntry],
+    previous: Option<&WeeklySummary>,
+    queues: &[ReviewQueue],
+    now: u64,
+) -> WeeklySummary {
+    let mut summary = WeeklySummary {
+        week_start,
+        created_at: now,
+        ..WeeklySummary::default()
+    };
+
+    let mut growth: HashMap<&Path, i64> = HashMap::new();
+    for entry in activity {
+        match entry.kind {
+            ActivityKind::Cleanup => {
+                summary.space_freed += entry.bytes.unwrap_or(0).max(0) as u64;
+                summary.cleanups += 1;
+            }
+            ActivityKind::SpaceTrend => {
+                let bytes = entry.bytes.unwrap_or(0);
+                summary.space_grown += bytes;
+                if let Some(path) = &entry.path {
+                    *growth.entry(path.as_path()).or_default() += bytes;
+                }
+            }
+            ActivityKind::Scan | ActivityKind::Alert => {}
+        }
+    }
+
+    let known = |path: &Path| {
+        previous.is_some_and(|previous| {
+            previous
+                .new_hotspots
+                .iter()
+                .any(|hotspot| hotspot.path == path)
+        })
+    };
+    let mut hotspots: Vec<Hotspot> = growth
+        .into_iter()
+        .filter(|(path, grown)| *grown > 0 && !known(path))
+        .map(|(path, grown)| Hotspot {
+            path: path.to_path_buf(),
+            grown: grown as u64,
+        })
+        .collect();
+    hotspots.sort_by(|a, b| b.grown.cmp(&a.grown).then_with(|| a.path.cmp(&b.path)));
+    hotspots.truncate(MAX_HOTSPOTS);
+    summary.new_hotspots = hotspots;
+
+    for queue in queues
+        .iter()
+        .filter(|queue| queue.state != ReviewState::Keep)
+    {
+        summary.pending_recommendations += queue.count;
+        summary.pending_bytes += queue.bytes;
+    }
+    summary
+}
+
+/**
+ * The weekly summaries, in the app database next to the activity feed they
+ * are compiled from. One per week, the latest `KEEP_SUMMARIES` are kept.
+ */
+#[derive(Clone)]
+pub struct WeeklySummaries {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl WeeklySummaries {
+    pub fn new(database: &Database) -> io::Result<Self> {
+        let shared = database.connection();
+        let conn = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS weekly_summary (
+                week_start INTEGER PRIMARY KEY,
+                summary TEXT NOT NULL
+            );",
+        )
+        .map_err(to_io)?;
+
+        Ok(Self {
+            conn: Arc::clone(&shared),
+        })
+    }
+
+    pub fn in_memory() -> io::Result<Self> {
+        Self::new(&Database::in_memory()?)
+    }
+
+    /**
+     * keep `summary`, in place of one compiled for the same week before
+     */
+    pub fn store(&self, summary: &WeeklySummary) -> io::Result<()> {
+        let json = serde_json::to_string(summary)?;
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        conn.execute(
+            "INSERT OR REPLACE INTO weekly_summary (week_start, summary) VALUES (?1, ?2)",
+            params![summary.week_start as i64, json],
+        )
+        .map_err(to_io)?;
+        conn.execute(
+            "DELETE FROM weekly_summary WHERE week_start NOT IN
+                (SELECT week_start FROM weekly_summary ORDER BY week_start DESC LIMIT ?1)",
+            params![KEEP_SUMMARIES],
+        )
+        .map_err(to_io)?;
+        Ok(())
+    }
+
+    /**
+     * the summary of the week from `week_start`, none until it was compiled
+     */
+    pub fn get(&self, week_start: u64) -> io::Result<Option<WeeklySummary>> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let json = conn
+            .query_row(
+                "SELECT summary FROM weekly_summary WHERE week_start = ?1",
+                params![week_start as i64],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(to_io)?;
+        json.map(|json| serde_json::from_str(&json).map_err(io::Error::from))
+            .transpose()
+    }
+
+    /**
+     * the `limit` latest summaries, the latest first
+     */
+    pub fn latest(&self, limit: usize) -> io::Result<Vec<WeeklySummary>> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut statement = conn
+            .prepare("SELECT summary FROM weekly_summary ORDER BY week_start DESC LIMIT ?1")
+            .map_err(to_io)?;
+        let rows = statement
+            .query_map(params![limit as i64], |row| row.get::<_, String>(0))
+            .map_err(to_io)?;
+        let mut summaries = Vec::new();
+        for json in rows {
+            summaries.push(serde_json::from_str(&json.map_err(to_io)?)?);
+        }
+        Ok(summaries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: ActivityKind, path: &str, bytes: i64) -> ActivityEntry {
+        ActivityEntry {
+            id: 0,
+            kind,
+            at: 0,
+            path: Some(PathBuf::from(path)),
+            summary: String::new(),
+            bytes: Some(bytes),
+            count: None,
+        }
+    }
+
+    #[test]
+    fn test_summary_compiled_and_kept() {
+        // Monday 2025-10-06
+        let monday = 1_759_708_800;
+        assert_eq!(week_start(monday + 3 * 86_400 + 5), monday);
+        assert_eq!(last_week(monday + WEEK_SECS + 60), monday);
+
+        let previous = WeeklySummary {
+            week_start: monday - WEEK_SECS,
+            new_hotspots: vec![Hotspot {
+                path: PathBuf::from("/data/cache"),
+                grown: 10,
+            }],
+            ..WeeklySummary::default()
+        };
+        let activity = [
+            entry(ActivityKind::Cleanup, "/data/tmp", 300),
+            entry(ActivityKind::SpaceTrend, "/data/cache", 500),
+            entry(ActivityKind::SpaceTrend, "/data/photos", 200),
+            entry(ActivityKind::SpaceTrend, "/data/photos", 100),
+            entry(ActivityKind::SpaceTrend, "/data/tmp", -300),
+            entry(ActivityKind::Scan, "/data", 1_000),
+        ];
+        let queues = [
+            ReviewQueue {
+                state: ReviewState::DeleteLater,
+                count: 2,
+                bytes: 40,
+                items: vec![],
+            },
+            ReviewQueue {
+                state: ReviewState::Keep,
+                count: 7,
+                bytes: 90,
+                items: vec![],
+            },
+        ];
+        let summary = compile(
+            monday,
+            &activity,
+            Some(&previous),
+            &queues,
+            monday + WEEK_SECS,
+        );
+        assert_eq!((summary.space_freed, summary.cleanups), (300, 1));
+        assert_eq!(summary.space_grown, 500);
+        assert_eq!(
+            summary.new_hotspots,
+            vec![Hotspot {
+                path: PathBuf::from("/data/photos"),
+                grown: 300,
+            }]
+        );
+        assert_eq!(
+            (summary.pending_recommendations, summary.pending_bytes),
+            (2, 40)
+        );
+
+        let summaries = WeeklySummaries::in_memory().unwrap();
+        summaries.store(&previous).unwrap();
+        summaries.store(&summary).unwrap();
+        assert_eq!(summaries.get(monday).unwrap(), Some(summary.clone()));
+        assert_eq!(summaries.get(monday + WEEK_SECS).unwrap(), None);
+        let latest = summaries.latest(10).unwrap();
+        assert_eq!(latest, vec![summary, previous]);
+    }
+}
@@ -1,4 +1,92 @@
-const COMMANDS: &[&str] = &["ping"];
+const COMMANDS: &[&str] = &[
+    "ping",
+    "start_scan",
+    "get_folder_stats",
+    "get_scan_progress",
+    "stop_folder_scan",
+    "is_scanning",
+    "clear_folder_scan",
+    "get_available_drivers",
+    "prepare_scan_targets",
+    "copy_paths_to_clipboard",
+    "get_actions_for",
+    "expand_selection",
+    "get_recent_changes",
+    "get_slow_directories",
+    "check_freshness",
+    "scan_on_demand",
+    "get_scan_queue",
+    "skip_subtree",
+    "prioritize_subtree",
+    "get_dashboard",
+    "estimate_cleanup",
+    "export_session",
+    "import_session",
+    "get_scan_source",
+    "run_cleanup",
+    "get_largest_files",
+    "find_old_files",
+    "search_files",
+    "run_maintenance",
+    "get_maintenance_stats",
+    "get_scan_watchdog",
+    "set_scan_watchdog",
+    "get_retry_policy",
+    "set_retry_policy",
+    "get_scanner_config",
+    "configure_scanner",
+    "get_request_trace",
+    "simulate_cleanup",
+    "get_settings",
+    "set_settings",
+    "cancel_cleanup",
+    "fix_permissions_then_delete",
+    "strip_quarantine",
+    "set_folder_label",
+    "list_folder_labels",
+    "set_review_state",
+    "get_review_queues",
+    "execute_delete_later",
+    "get_breadcrumbs",
+    "get_icon_hint",
+    "get_icon_hints",
+    "get_thumbnail",
+    "diff_paths",
+    "backup_targets",
+    "run_operation",
+    "get_audit_log",
+    "get_activity_feed",
+    "get_weekly_summaries",
+    "trash_paths",
+    "delete_paths",
+    "move_file",
+    "copy_file",
+    "resolve_conflict",
+    "list_interrupted_operations",
+    "resume_operation",
+    "discard_operation",
+    "find_duplicates",
+    "get_top_disk_writers",
+    "resize_shadow_storage",
+    "delete_oldest_restore_point",
+    "get_scan_filters",
+    "set_scan_filters",
+    "find_system_dumps",
+    "delete_system_dumps",
+    "find_empty_directories",
+    "remove_empty_directories",
+    "get_skipped_mounts",
+    "get_most_files",
+    "find_file_count_hotspots",
+    "mitigate_memory_pressure",
+    "get_type_breakdown",
+    "save_scan_snapshot",
+    "load_scan_snapshot",
+    "list_analyzers",
+    "run_analyzers",
+    "list_scan_snapshots",
+    "diff_scans",
+];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS)
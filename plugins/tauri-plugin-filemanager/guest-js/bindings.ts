@@ -0,0 +1,1520 @@
+// This file was generated by [tauri-specta](https://github.com/oscartbeaumont/tauri-specta). Do not edit this file manually.
+
+/** user-defined commands **/
+
+
+export const commands = {
+async ping(payload: PingRequest) : Promise<Result<PingResponse, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|ping", { payload }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * scan from scratch, `estimate_by` picks what the progress percentage and
+ * remaining time are measured against, the used bytes of the volume by default.
+ * With `same_filesystem` the scan doesn't descend into other mounted volumes,
+ * `get_skipped_mounts` lists the ones it left out. Links to directories are
+ * only listed with `symlinks` set to follow them. The pre-scan hooks of the
+ * settings run first, the scan waits for them.
+ */
+async startScan(path: PathArg, estimateBy: EstimateBasis | null, sameFilesystem: boolean | null, symlinks: SymlinkPolicy | null) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|start_scan", { path, estimateBy, sameFilesystem, symlinks }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Read-only queries never take the control guards, so they can't hold up
+ * stop/clear. Without `limit` every child comes back, a folder of 100k
+ * entries is better asked for a page at a time.
+ */
+async getFolderStats(path: PathArg, sortBy: ChildSort | null, order: SortOrder | null, offset: number | null, limit: number | null) : Promise<Result<FileDetails | null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_folder_stats", { path, sortBy, order, offset, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getScanProgress() : Promise<Result<ScanProgress, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_scan_progress") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async stopFolderScan() : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|stop_folder_scan") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async isScanning() : Promise<Result<boolean, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|is_scanning") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async clearFolderScan() : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|clear_folder_scan") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Get the list of drivers provided by the operation
+ */
+async getAvailableDrivers() : Promise<Result<Volumn[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_available_drivers") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * validate paths picked or dropped by the user into a deduplicated scan target list
+ */
+async prepareScanTargets(paths: PathArg[]) : Promise<Result<ScanTargets, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|prepare_scan_targets", { paths }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * copy the selection to the clipboard without round-tripping it through the webview
+ */
+async copyPathsToClipboard(paths: PathArg[], format: ClipboardFormat) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|copy_paths_to_clipboard", { paths, format }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * context menu actions for a path, driven by what the backend can actually do with it.
+ * Compress and hash carry how long they would take at the measured throughput
+ */
+async getActionsFor(path: PathArg) : Promise<Result<Action[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_actions_for", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * "select all like this", computed over the scanned tree instead of the rendered list
+ */
+async expandSelection(paths: PathArg[], rule: SelectionRule) : Promise<Result<string[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|expand_selection", { paths, rule }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * what appeared or changed under `root` since the given unix timestamp, largest first
+ */
+async getRecentChanges(root: PathArg, since: number) : Promise<Result<RecentChange[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_recent_changes", { root, since }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the slowest directories of the current scan, to spot network mounts or failing disks
+ */
+async getSlowDirectories(limit: number | null) : Promise<Result<DirectoryTiming[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_slow_directories", { limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * directories under `root` which changed since its last scan, decided from mtimes
+ * alone without loading the previous tree
+ */
+async checkFreshness(root: PathArg, limit: number | null) : Promise<Result<Freshness, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|check_freshness", { root, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * scan the folder the user clicked at high priority while the full scan keeps going
+ */
+async scanOnDemand(path: PathArg) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|scan_on_demand", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the directories waiting to be scanned and what each worker is stuck on
+ */
+async getScanQueue(limit: number | null) : Promise<Result<QueueSnapshot, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_scan_queue", { limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * stop descending into a directory the user doesn't care about, the rest of the scan goes on
+ */
+async skipSubtree(path: PathArg) : Promise<Result<number, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|skip_subtree", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * make the scanner finish the folder the user is looking at first
+ */
+async prioritizeSubtree(path: PathArg) : Promise<Result<number, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|prioritize_subtree", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * home screen payload, served from the cache so it never triggers a scan
+ */
+async getDashboard() : Promise<Result<Dashboard, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_dashboard") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * bytes a cleanup of the selection frees, without counting what already sits in the trash twice
+ */
+async estimateCleanup(paths: PathArg[]) : Promise<Result<CleanupEstimate, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|estimate_cleanup", { paths }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * write the current scan with its reports into one file to review elsewhere
+ */
+async exportSession(path: PathArg) : Promise<Result<SessionInfo, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|export_session", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * load an exported session into this window in place of its scan
+ */
+async importSession(path: PathArg) : Promise<Result<SessionInfo, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|import_session", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * whether this window browses the live disk or an imported snapshot
+ */
+async getScanSource() : Promise<Result<ScanSource, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_scan_source") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * permanently delete the selection, then check each touched volume really
+ * gained the space the removed files occupied. `reclaim_purgeable` also asks
+ * macOS to release purgeable space so the result matches Finder. The removal
+ * reports `cleanup-progress` every second and stops on `cancel_cleanup`.
+ * `unlock` clears the locked, read-only and system flags in its way, without
+ * it a locked entry fails with the flags it carries
+ */
+async runCleanup(paths: PathArg[], reclaimPurgeable: boolean | null, unlock: boolean | null) : Promise<Result<CleanupReport, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|run_cleanup", { paths, reclaimPurgeable, unlock }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the largest files under `root`, from whichever store backs the session,
+ * leaving out the ones below `min_size` bytes
+ */
+async getLargestFiles(root: PathArg, limit: number | null, minSize: number | null) : Promise<Result<FoundFile[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_largest_files", { root, limit, minSize }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Files under `root` neither modified nor created in the last
+ * `older_than_days` days, of at least `min_size` bytes, a page of `limit`
+ * from `offset` on. Oldest first unless `order` tells otherwise. Ask for
+ * the next page with its `nextOffset`.
+ */
+async findOldFiles(root: PathArg, olderThanDays: number, minSize: number | null, order: AgeOrder | null, offset: number | null, limit: number | null) : Promise<Result<OldFilesPage, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|find_old_files", { root, olderThanDays, minSize, order, offset, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * entries under `root` whose name contains `query`
+ */
+async searchFiles(root: PathArg, query: string, limit: number | null) : Promise<Result<FoundFile[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|search_files", { root, query, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * compact the hash index and prune old fingerprint files now instead of waiting for the scheduler
+ */
+async runMaintenance() : Promise<Result<MaintenanceReport, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|run_maintenance") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the last maintenance run, none before the first one finished
+ */
+async getMaintenanceStats() : Promise<Result<MaintenanceReport | null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_maintenance_stats") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * when a scan worker counts as hung and whether the scan skips its directory
+ */
+async getScanWatchdog() : Promise<Result<Watchdog, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_scan_watchdog") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * change the watchdog settings, a running scan picks them up on its next check
+ */
+async setScanWatchdog(watchdog: Watchdog) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|set_scan_watchdog", { watchdog }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * how often a scan retries a directory after a transient error
+ */
+async getRetryPolicy() : Promise<Result<RetryPolicy, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_retry_policy") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * change the retry policy, a running scan uses it from the next directory on
+ */
+async setRetryPolicy(policy: RetryPolicy) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|set_retry_policy", { policy }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the workers, queue bound and entry rate the scans of the window run with
+ */
+async getScannerConfig() : Promise<Result<ScannerConfig, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_scanner_config") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Change how hard the scans of the window work the disk: `workers` listing
+ * at the same time, depth first past `max_queue_depth` waiting directories
+ * and at most `entries_per_sec` entries a second. A running scan takes the
+ * bound, the rate and fewer workers from its next directory, more workers
+ * start with the next scan. Returns the config as applied.
+ */
+async configureScanner(workers: number, maxQueueDepth: number | null, entriesPerSec: number | null) : Promise<Result<ScannerConfig, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|configure_scanner", { workers, maxQueueDepth, entriesPerSec }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * timing of a recent command by the `requestId` it answered or failed with,
+ * to tie a frontend bug report to the backend log
+ */
+async getRequestTrace(id: number) : Promise<Result<RequestTrace | null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_request_trace", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * preview a cleanup: what it would free per volume, which directories would shrink,
+ * how the largest files would change and how long it would take, without touching the disk
+ */
+async simulateCleanup(plan: CleanupPlan) : Promise<Result<CleanupSimulation, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|simulate_cleanup", { plan }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the settings in effect, including edits made to the file by hand
+ */
+async getSettings() : Promise<Result<Settings, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * save the settings, every window gets them with `settings-changed` and the
+ * scanner, protected paths and scheduler apply them without a restart
+ */
+async setSettings(settings: Settings) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|set_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * stop the running cleanup or batch deletion of this window, the entries
+ * removed so far stay removed and whatever is left is reported by the
+ * command which started it
+ */
+async cancelCleanup() : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|cancel_cleanup") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete `path`, and when a plain deletion leaves entries behind, reset their
+ * owner and permissions recursively and try again. Opt-in only, the changes
+ * are permanent on whatever still can't be removed, so the repair lists every
+ * entry which needed fixing along with the warnings to show the user
+ */
+async fixPermissionsThenDelete(path: PathArg) : Promise<Result<PermissionRepair, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|fix_permissions_then_delete", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * drop the quarantine of a downloaded file the user keeps, so it opens without
+ * the downloaded from the internet warning. False when it carried none
+ */
+async stripQuarantine(path: PathArg) : Promise<Result<boolean, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|strip_quarantine", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * pin a color and an emoji on a folder, kept across sessions and returned with
+ * its details. An empty label removes it
+ */
+async setFolderLabel(path: PathArg, label: FolderLabel) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|set_folder_label", { path, label }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * every labeled folder, the most recently changed first
+ */
+async listFolderLabels() : Promise<Result<LabeledFolder[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|list_folder_labels") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * triage `path` into the queue of `state`, none takes it out of every queue.
+ * A kept item has to go back to review before it can be deleted later
+ */
+async setReviewState(path: PathArg, state: ReviewState | null) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|set_review_state", { path, state }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * every review queue with its item count and bytes, the largest items first
+ */
+async getReviewQueues() : Promise<Result<ReviewQueue[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_review_queues") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete everything in the delete-later queue the way `run_cleanup` does.
+ * A folder holding an item of another queue is reported as failed and left
+ * in place. Removed items leave the queue, failed ones stay for another try
+ */
+async executeDeleteLater() : Promise<Result<CleanupReport, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|execute_delete_later") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the path bar of `path` with the names Finder or Explorer show, starting at
+ * the innermost well-known location above it, e.g. iCloud Drive
+ */
+async getBreadcrumbs(path: PathArg) : Promise<Result<Breadcrumb[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_breadcrumbs", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * which icon to draw for `path`, see `get_icon_hints` for a whole listing
+ */
+async getIconHint(path: PathArg) : Promise<Result<IconHint, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_icon_hint", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the icon hints of many entries in their order, none for an entry which is
+ * gone. Entries of one type share a single platform lookup
+ */
+async getIconHints(paths: PathArg[]) : Promise<Result<(IconHint | null)[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_icon_hints", { paths }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * a thumbnail of an image, a video or a PDF fitting in `size` pixels, read
+ * from the disk cache once it was rendered
+ */
+async getThumbnail(path: PathArg, size: number) : Promise<Result<Thumbnail, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_thumbnail", { path, size }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * compare two live folders, e.g. a folder and its backup, by relative path and
+ * size, and by content hash of the files of the same size with `compare_content`
+ */
+async diffPaths(a: PathArg, b: PathArg, compareContent: boolean | null) : Promise<Result<FolderDiff, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|diff_paths", { a, b, compareContent }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Where each of `sources` goes when it is backed up below `destination`,
+ * following `template`, the `backup_template` of the settings by default.
+ * Targets which exist already, or which an earlier source got, are
+ * numbered, with `archive` they are zip archives. They are the `to` of the
+ * copy or compress steps of `run_operation`, nothing is created yet.
+ */
+async backupTargets(destination: PathArg, sources: PathArg[], template: string | null, archive: boolean) : Promise<Result<BackupTarget[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|backup_targets", { destination, sources, template, archive }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Run a composite operation like backup-then-clean as one transaction. A
+ * failing step undoes the ones before it, deletes only become final once
+ * every step succeeded. The outcome is kept in the audit log and the
+ * activity feed. Copies and archives go no faster than
+ * `io_limit_mb_per_sec`, the one of the settings by default. Rejected with
+ * `insufficientSpace` before any step starts when a destination has no
+ * room for what the steps write. Every path is resolved like the tree
+ * keys it before the protected locations are checked.
+ */
+async runOperation(operation: string, steps: StepArg[], ioLimitMbPerSec: number | null) : Promise<Result<TransactionOutcome, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|run_operation", { operation, steps, ioLimitMbPerSec }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the last operations which changed files, the latest first
+ */
+async getAuditLog(limit: number | null) : Promise<Result<AuditEntry[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_audit_log", { limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * One page of the history of scans, cleanups, alerts and space trends, the
+ * latest first. `limit` is 50 by default, `kinds` leaves out the others.
+ */
+async getActivityFeed(offset: number | null, limit: number | null, kinds: ActivityKind[] | null) : Promise<Result<ActivityPage, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_activity_feed", { offset, limit, kinds }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The weekly summaries of space freed and grown, new hotspots and pending
+ * recommendations, the latest first. `limit` is a year of weeks by default.
+ */
+async getWeeklySummaries(limit: number | null) : Promise<Result<WeeklySummary[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_weekly_summaries", { limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * move `paths` to the trash of the OS, reporting `delete-progress` while the
+ * batch runs and `delete-complete` at the end
+ */
+async trashPaths(paths: PathArg[]) : Promise<Result<DeleteReport, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|trash_paths", { paths }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * delete `paths` for good, reporting `delete-progress` while the batch runs
+ * and `delete-complete` at the end
+ */
+async deletePaths(paths: PathArg[]) : Promise<Result<DeleteReport, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|delete_paths", { paths }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Move `from` to `to`. An existing destination is handled by `on_conflict`,
+ * refused by default. On another volume the entry is copied, checked
+ * against the original and only then removed, reporting `move-progress`
+ * while it copies no faster than `io_limit_mb_per_sec`, the one of the
+ * settings by default. The moved entries leave the scanned tree.
+ */
+async moveFile(from: PathArg, to: PathArg, onConflict: CollisionPolicy | null, ioLimitMbPerSec: number | null) : Promise<Result<MoveReport, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|move_file", { from, to, onConflict, ioLimitMbPerSec }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Copy `from` to `to`, an existing destination handled by `on_conflict`,
+ * refused by default. Reports `move-progress` while it copies no faster
+ * than `io_limit_mb_per_sec`, the one of the settings by default. A copy
+ * to a destination which doesn't exist yet keeps checkpoints, when it
+ * stops its error tells the id to pass to `resume_operation`. Rejected
+ * with `insufficientSpace` before anything is written when the destination
+ * has no room for it.
+ */
+async copyFile(from: PathArg, to: PathArg, onConflict: CollisionPolicy | null, ioLimitMbPerSec: number | null) : Promise<Result<MoveReport, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|copy_file", { from, to, onConflict, ioLimitMbPerSec }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Answer the `conflict` a move or copy with `on_conflict: "ask"` paused on.
+ * With `apply_to_all` the choice holds for the rest of the operation.
+ */
+async resolveConflict(operationId: number, choice: ConflictChoice, applyToAll: boolean) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|resolve_conflict", { operationId, choice, applyToAll }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the copies which stopped before they were done, the latest first
+ */
+async listInterruptedOperations() : Promise<Result<InterruptedCopy[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|list_interrupted_operations") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Continue the interrupted copy `id` from its last checkpoint, reporting
+ * `move-progress` like `copy_file`. The files it copied completely are
+ * kept, the one it was copying goes on from the last bytes synced.
+ */
+async resumeOperation(id: number, ioLimitMbPerSec: number | null) : Promise<Result<MoveReport, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|resume_operation", { id, ioLimitMbPerSec }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Give up on the interrupted copy `id`. The file it was copying is removed,
+ * the files it copied completely stay.
+ */
+async discardOperation(id: number) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|discard_operation", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Files of the scanned tree with the same content, from `min_size` bytes on.
+ * Every group is emitted as `duplicate-found` once it is confirmed, the
+ * report holds them all sorted by the bytes they reclaim.
+ */
+async findDuplicates(minSize: number | null) : Promise<Result<DuplicateReport, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|find_duplicates", { minSize }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The processes which wrote the most to disk during the next `window_millis`,
+ * two seconds by default, to tell who fills a disk up while it happens.
+ */
+async getTopDiskWriters(windowMillis: number | null, limit: number | null) : Promise<Result<DiskWriters, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_top_disk_writers", { windowMillis, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Limit the space Windows keeps for restore points on `volume` to `max_size`
+ * bytes, the oldest points are dropped to fit. Asks for an elevated app.
+ */
+async resizeShadowStorage(volume: PathArg, maxSize: number) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|resize_shadow_storage", { volume, maxSize }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * delete the oldest restore point of `volume`, asks for an elevated app
+ */
+async deleteOldestRestorePoint(volume: PathArg) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|delete_oldest_restore_point", { volume }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * what the scans of the window leave out
+ */
+async getScanFilters() : Promise<Result<ScanFilters, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_scan_filters") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Exclude rules for the scans of the window, globs matched against the whole
+ * path like `*.iso`, and the size below which files only count in their
+ * directory. They apply from the next listed directory on, an invalid glob is
+ * rejected and the rules in effect stay.
+ */
+async setScanFilters(filters: ScanFilters) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|set_scan_filters", { filters }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Memory dumps, crash reports and swapfiles of the scanned tree, the largest
+ * first. A full memory dump is as large as the RAM and often forgotten.
+ */
+async findSystemDumps() : Promise<Result<SystemDump[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|find_system_dumps") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete memory dumps, crash reports and unused swapfiles, inside protected
+ * system locations too. Any other path is refused, as is a swapfile the
+ * system still uses.
+ */
+async deleteSystemDumps(paths: PathArg[], mode: DeleteMode) : Promise<Result<DeleteReport, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|delete_system_dumps", { paths, mode }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the directories below `root` without a single file, grouped by their
+ * parent, the nested ones folded into the outermost
+ */
+async findEmptyDirectories(root: PathArg) : Promise<Result<EmptyGroup[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|find_empty_directories", { root }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Remove the empty directories at `paths` with the empty ones inside them,
+ * the deepest first. A directory which isn't empty anymore is left, with
+ * its ancestors.
+ */
+async removeEmptyDirectories(paths: PathArg[]) : Promise<Result<EmptyRemoval, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|remove_empty_directories", { paths }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the mount points a scan with `same_filesystem` didn't descend into
+ */
+async getSkippedMounts() : Promise<Result<string[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_skipped_mounts") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the children of `path` holding the most entries, to find what exhausts the inodes of a disk
+ */
+async getMostFiles(path: PathArg, limit: number | null) : Promise<Result<ItemCount[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_most_files", { path, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the directories under `root` with at least `min_count` tiny files of their
+ * own, 1000 by default, the most files first
+ */
+async findFileCountHotspots(root: PathArg, minCount: number | null) : Promise<Result<FileCountHotspot[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|find_file_count_hotspots", { root, minCount }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * keep the tree of the scan from outgrowing the memory, with one of the
+ * mitigations a `memory-pressure` event offered
+ */
+async mitigateMemoryPressure(mitigation: Mitigation) : Promise<Result<null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|mitigate_memory_pressure", { mitigation }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * what kinds of files take the space below `path`, by category and by
+ * extension, none when it wasn't scanned
+ */
+async getTypeBreakdown(path: PathArg) : Promise<Result<TypeBreakdown | null, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|get_type_breakdown", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * keep the finished scan of this window in the app data dir, so the next
+ * start can restore it with `load_scan_snapshot` instead of scanning again.
+ * The last few scans of a root are kept for `diff_scans`.
+ */
+async saveScanSnapshot() : Promise<Result<CachedScan, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|save_scan_snapshot") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * restore the last saved scan of `root` into this window in place of its scan,
+ * with `refresh` the directories changed since are listed again
+ */
+async loadScanSnapshot(root: PathArg, refresh: boolean | null) : Promise<Result<CachedScan, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|load_scan_snapshot", { root, refresh }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the analyzer modules installed in the app data dir, experimental
+ */
+async listAnalyzers() : Promise<Result<AnalyzerInfo[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|list_analyzers") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * run the installed analyzers, or the ones in `names`, through the scanned
+ * entries below `path`, experimental
+ */
+async runAnalyzers(path: PathArg, names: string[] | null) : Promise<Result<AnalyzerReport[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|run_analyzers", { path, names }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * the saved scans, of `root` only when given, the newest first
+ */
+async listScanSnapshots(root: PathArg | null) : Promise<Result<CachedScan[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|list_scan_snapshots", { root }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * what changed between two saved scans of the same root: the directories
+ * which grew or shrank, the new files of at least `min_size` bytes, 100 MB
+ * by default, and what was deleted, the `limit` largest of each
+ */
+async diffScans(oldId: string, newId: string, minSize: number | null, limit: number | null) : Promise<Result<ScanDiff, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("plugin:filemanager|diff_scans", { oldId, newId, minSize, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+}
+}
+
+/** user-defined events **/
+
+
+
+/** user-defined constants **/
+
+
+
+/** user-defined types **/
+
+export type AbortReason = "volumeRemoved"
+/**
+ * a context menu entry, unavailable actions carry the reason so the menu can explain it
+ */
+export type Action = { kind: ActionKind; available: boolean; reason: string | null; risk: ActionRisk; estimate: OperationEstimate | null }
+export type ActionKind = "reveal" | "trash" | "compress" | "hash" | "analyzeGit" | "explainSize" | "stripQuarantine"
+export type ActionRisk = "safe" | "moderate" | "destructive"
+/**
+ * one thing which happened, as the history screen lists it
+ */
+export type ActivityEntry = { id: number; kind: ActivityKind; at: number; path: string | null; summary: string; bytes: number | null; count: number | null }
+export type ActivityKind = "scan" | "cleanup" | "alert" | "spaceTrend"
+/**
+ * one page of the feed, the latest first
+ */
+export type ActivityPage = { entries: ActivityEntry[]; total: number; nextOffset: number | null }
+/**
+ * bytes of the files below a directory bucketed by last modification
+ */
+export type AgeBuckets = { recent: number; months: number; halfYear: number; stale: number }
+/**
+ * how a listing of old files is sorted
+ */
+export type AgeOrder = "oldestFirst" | "newestFirst" | "largestFirst"
+export type Alert = { kind: AlertKind; path: string; message: string }
+export type AlertKind = "lowSpace"
+/**
+ * an analyzer module found on disk, with why it can't run if it can't
+ */
+export type AnalyzerInfo = { name: string; path: string; error: string | null }
+/**
+ * what one analyzer reported on a folder, or why it stopped
+ */
+export type AnalyzerReport = { name: string; findings: Finding[]; error: string | null }
+export type AppEvent = ({ kind: "folder-scan-progress" } & ScanProgress) | { kind: "folder-scan-complete"; message: string } | ({ kind: "scan-targets-dropped" } & ScanTargets) | ({ kind: "scan-stalled" } & ScanStall) | ({ kind: "scan-aborted" } & ScanAborted) | ({ kind: "settings-changed" } & Settings) | ({ kind: "node-size-delta" } & SizeDeltaBatch) | ({ kind: "hash-progress" } & HashProgress) | ({ kind: "cleanup-progress" } & CleanupProgress) | ({ kind: "delete-progress" } & DeleteProgress) | ({ kind: "delete-complete" } & DeleteReport) | ({ kind: "duplicate-found" } & DuplicateGroup) | ({ kind: "memory-pressure" } & MemoryPressure) | ({ kind: "tree-updated" } & TreeUpdate) | ({ kind: "move-progress" } & MoveProgress) | ({ kind: "conflict" } & PendingConflict) | ({ kind: "weekly-summary" } & WeeklySummary)
+/**
+ * one operation that changed files, as it ended or as far as it got
+ */
+export type AuditEntry = { id: number; operation: string; started: number; finished: number | null; state: TransactionState | null; journal: JournalEntry[]; error: string | null; output: string | null }
+/**
+ * where one source is backed up, as the webview gets it
+ */
+export type BackupTarget = { path: string; rawPath: string | null }
+/**
+ * one step of the path bar, from the outermost shown location down
+ */
+export type Breadcrumb = { path: string; rawPath: string | null; name: string }
+/**
+ * a scan saved to or restored from the cache
+ */
+export type CachedScan = { id: string; path: string; rawPath: string | null; savedAt: number; entries: number; size: number; refreshed: number }
+export type Category = "video" | "image" | "audio" | "archive" | "document" | "code" | "systemDump" | "other"
+export type CategoryUsage = { category: Category; size: number; files: number }
+export type ChangeKind = "created" | "modified"
+/**
+ * an entry found in one scan only
+ */
+export type ChangedEntry = { path: string; rawPath: string | null; size: number; isDirectory: boolean }
+/**
+ * what the children of a folder are sorted by
+ */
+export type ChildSort = "size" | "name" | "modified" | "count"
+/**
+ * what deleting a selection frees, with bytes already in the trash counted apart
+ */
+export type CleanupEstimate = { items: number; inPlace: number; inTrash: number; unknown: string[] }
+export type CleanupFailure = { path: string; reason: string }
+/**
+ * what a cleanup would remove, `root` bounds the top lists and defaults to the
+ * root of the scan
+ */
+export type CleanupPlan = { paths: PathArg[]; root: PathArg | null }
+/**
+ * what a running deletion got through so far, over all selected paths
+ */
+/**
+ * what a running deletion got through so far, over all selected paths
+ */
+export type CleanupProgress = { files: number; directories: number; bytes: number; failures: number; elapsedMillis: number; done: boolean }
+export type CleanupReport = { removed: string[]; failed: CleanupFailure[]; expectedFreed: number; volumes: FreeSpaceCheck[]; purgeable: PurgeableSpace[]; reclaimError: string | null; subtrees: SubtreeDeletion[]; cancelled: boolean }
+/**
+ * The cleanup of a plan replayed against the scanned tree, nothing on the
+ * disk is touched. The same selections are removed or refused as `run_cleanup`
+ * would, except that a path outside the scan is refused instead of measured.
+ */
+export type CleanupSimulation = { removed: string[]; refused: CleanupFailure[]; freed: number; volumes: VolumeDiff[]; directories: DirectoryDiff[]; largestBefore: FoundFile[]; largestAfter: FoundFile[]; estimate: OperationEstimate | null }
+export type ClipboardFormat = "plain" | "shell" | "markdown"
+/**
+ * what to do when the destination of a move or copy exists
+ */
+export type CollisionPolicy = "fail" | "skip" | "overwrite" | "rename" | "merge" | "ask"
+/**
+ * what a command rejects with, the error and the id of the request which raised it
+ * to look up with `get_request_trace`
+ */
+export type CommandError = ({ requestId: number }) & (ErrorKind)
+/**
+ * an entry whose destination exists, as asked about by `CollisionPolicy::Ask`
+ */
+export type Conflict = { from: string; to: string; sourceDirectory: boolean; destinationDirectory: boolean; sourceSize: number; destinationSize: number; sourceModified: number | null; destinationModified: number | null }
+export type ConflictChoice = "skip" | "overwrite" | "rename" | "merge"
+/**
+ * everything the home screen shows, kept up to date in the background
+ */
+export type Dashboard = { volumes: Volumn[]; scans: ScanDelta[]; categories: CategoryUsage[]; alerts: Alert[]; refreshedAt: number }
+export type DeleteFailure = { path: string; reason: string }
+export type DeleteMode = "trash" | "permanent"
+/**
+ * how far a batch got, counted in selected paths
+ */
+export type DeleteProgress = { mode: DeleteMode; total: number; done: number; failures: number; bytes: number; currentPath: string | null }
+export type DeleteReport = { mode: DeleteMode; deleted: string[]; failed: DeleteFailure[]; bytes: number }
+/**
+ * one entry of a comparison, `path` relative to both compared folders
+ */
+export type DiffEntry = { path: string; rawPath: string | null; isDirectory: boolean; sizeA: number | null; sizeB: number | null }
+/**
+ * a directory which would shrink, with its size on both sides
+ */
+export type DirectoryDiff = { path: string; sizeBefore: number; sizeAfter: number }
+export type DirectoryTiming = { path: string; millis: number; entries: number }
+/**
+ * a process and what it did to the disks during the sampled window
+ */
+export type DiskWriter = { pid: number; name: string; knownAs: string | null; writtenBytes: number; readBytes: number; writeRate: number }
+/**
+ * the processes writing the most during a window, the heaviest first
+ */
+export type DiskWriters = { windowMillis: number; writers: DiskWriter[]; totalWritten: number }
+export type DumpKind = "fullMemoryDump" | "kernelDump" | "minidump" | "crashReport" | "swapfile"
+/**
+ * files with the same content, the first one is the one to keep
+ */
+export type DuplicateGroup = { size: number; hash: string; files: FileDetails[]; reclaimable: number }
+export type DuplicateReport = { groups: DuplicateGroup[]; candidates: number; fullyHashed: number; reclaimable: number }
+/**
+ * a directory without a single file below it
+ */
+export type EmptyDirectory = { path: string; nested: number }
+/**
+ * the outermost empty directories sharing a parent
+ */
+export type EmptyGroup = { parent: string; directories: EmptyDirectory[] }
+export type EmptyRemoval = { removed: string[]; directories: number; failed: DeleteFailure[] }
+export type ErrorKind = { kind: "io"; message: string } | { kind: "busy"; message: string } | { kind: "scanner"; message: string } | { kind: "readOnly"; message: string } | { kind: "clipboard"; message: string } | { kind: "insufficientSpace"; message: InsufficientSpace }
+/**
+ * what the progress of a scan is measured against
+ */
+export type EstimateBasis = "size" | "entries"
+export type EventEnvelope = ({ version: number }) & (AppEvent)
+/**
+ * the files of one extension below a folder
+ */
+export type ExtensionUsage = { extension: string; category: Category; size: number; files: number }
+/**
+ * a directory holding a great many tiny files of its own
+ */
+export type FileCountHotspot = { path: string; rawPath: string | null; tinyFiles: number; tinySize: number; entries: number }
+export type FileDetails = { name: string; displayName: string | null; path: string; rawPath: string | null; size: number; allocatedSize: number; hardlinkSavings: number; isDirectory: boolean; isLink: boolean; linkTarget: string | null; created: number; modified: number; readonly: boolean; locks: LockFlags; fileType: string; age: AgeBuckets; complete: boolean; skipped: boolean; quarantine: Quarantine | null; label: FolderLabel | null; count: number; totalChildren: number | null; children: FileDetails[] | null }
+/**
+ * something an analyzer found, for the user to review
+ */
+export type Finding = { analyzer: string; path: string; message: string; size: number | null }
+/**
+ * what was changed on an entry so it could be removed
+ */
+export type FixKind = "mode" | "owner" | "readOnly"
+/**
+ * Two live folders side by side. A directory found on one side only is
+ * listed once, not every entry below it. Directories on both sides are
+ * compared by their contents and never listed themselves.
+ */
+export type FolderDiff = { onlyInA: DiffEntry[]; onlyInB: DiffEntry[]; different: DiffEntry[]; same: DiffEntry[]; unreadable: string[]; comparedContent: boolean }
+/**
+ * A color and an emoji the user pinned on a folder, e.g. red for "do not
+ * touch" and green for "cleaned", so a later review finds its anchors again.
+ */
+export type FolderLabel = { color: string | null; emoji: string | null }
+export type FoundFile = { path: string; rawPath: string | null; size: number; isDirectory: boolean; modified: number | null }
+/**
+ * free space of a volume around a cleanup, compared with what the removed files occupied
+ */
+export type FreeSpaceCheck = { volume: string; expected: number; before: number; after: number; discrepancy: string | null }
+export type Freshness = { checked: number; changed: string[]; changedCount: number }
+/**
+ * how file contents are hashed, every stored hash records its algorithm
+ */
+export type HashAlgorithm = "blake3" | "xxh3" | "sha256"
+/**
+ * progress of hashing one file, reported after every chunk
+ */
+export type HashProgress = { path: string; algorithm: HashAlgorithm; hashed: number; size: number; bytesPerSec: number; mapped: boolean }
+/**
+ * A command the user wants run on `event`. The program and its arguments
+ * are given apart and no shell is involved, `["sh", "-c", "..."]` gets one.
+ * The summary of the event is written to its stdin as JSON.
+ */
+export type Hook = { event: HookEvent; command: string[]; timeoutSecs: number }
+/**
+ * when a hook runs
+ */
+export type HookEvent = "preScan" | "postCleanup"
+/**
+ * a path which took noticeably more space over the week
+ */
+export type Hotspot = { path: string; grown: number }
+/**
+ * Which icon to draw for an entry. Entries drawn with the same icon get the
+ * same `id`, so the frontend fetches each icon once instead of once per row.
+ * Only the per file ones are fetched one by one.
+ */
+export type IconHint = { id: string; perFile: boolean }
+/**
+ * Files a volume can still hold. A volume runs out of inodes with bytes to
+ * spare when millions of tiny files fill it.
+ */
+export type InodeUsage = { total: number; used: number; available: number }
+/**
+ * a destination without room for what an operation is about to write,
+ * found before anything is written
+ */
+export type InsufficientSpace = { destination: string; required: number; available: number; missing: number }
+/**
+ * a copy which stopped before it was done, `resume` takes it from where it got
+ */
+export type InterruptedCopy = { id: number; from: string; to: string; totalBytes: number; copiedBytes: number; completed: number; started: number; updated: number; error: string | null }
+/**
+ * a child of a folder with the entries it holds, each one takes an inode
+ */
+export type ItemCount = { path: string; rawPath: string | null; size: number; isDirectory: boolean; items: number }
+export type JournalEntry = { step: Step; state: StepState; error: string | null }
+export type LabeledFolder = { path: string; label: FolderLabel; updated: number }
+/**
+ * Flags which make a plain delete fail with a permission error however the
+ * permissions are set. Only the ones the platform has are ever set, the
+ * immutable attribute of Linux needs an ioctl and is not detected.
+ */
+export type LockFlags = { readOnly: boolean; system: boolean; userImmutable: boolean; systemImmutable: boolean }
+export type MaintenanceReport = { finishedAt: number; hashesPruned: number; indexBytesBefore: number; indexBytesAfter: number; fingerprintsPruned: number; fingerprintBytesFreed: number; thumbnailsEvicted: number; thumbnailBytesFreed: number; errors: string[] }
+/**
+ * the tree of a scan grows close to or past the memory it may take
+ */
+export type MemoryPressure = { level: PressureLevel; entries: number; estimated: number; limit: number; mitigations: Mitigation[] }
+/**
+ * how a running scan can keep its tree smaller, the entries already scanned
+ * keep counting in the sizes
+ */
+export type Mitigation = "foldSmallFiles" | "collapseDeepLevels"
+/**
+ * how far a move across volumes got, a rename is reported once done
+ */
+export type MoveProgress = { from: string; to: string; stage: MoveStage; crossDevice: boolean; totalBytes: number; copiedBytes: number }
+export type MoveReport = { from: string; to: string; crossDevice: boolean; bytes: number; transferred: string[]; skipped: string[]; replaced: string[] }
+export type MoveStage = "copying" | "verifying" | "removing" | "done"
+/**
+ * one page of the files below a root untouched since a cutoff
+ */
+export type OldFilesPage = { files: FoundFile[]; total: number; totalSize: number; nextOffset: number | null }
+export type Operation = "cleanup" | "compress" | "hash"
+/**
+ * how long an operation would take on this device, so the user can decide
+ * to run it now or later
+ */
+export type OperationEstimate = { operation: Operation; bytes: number; entries: number; seconds: number; energyWh: number; measured: boolean; summary: string }
+/**
+ * A path argument from the webview. Plain strings cover every UTF-8 path,
+ * a name the OS stores as arbitrary bytes comes back as the `raw` form a
+ * `FileDetails` or `FoundFile` carried for it, so it can still be acted on.
+ */
+export type PathArg = { raw: string } | string
+/**
+ * a conflict of a running move or copy, answered with `resolve_conflict`
+ */
+export type PendingConflict = ({ operationId: number }) & (Conflict)
+export type PermissionFix = { path: string; kind: FixKind; before: string; after: string }
+/**
+ * The outcome of `fix_permissions_then_delete`. Only what a plain deletion
+ * left behind is repaired, so `fixed` lists exactly the entries which stood
+ * in the way.
+ */
+export type PermissionRepair = { path: string; fixed: PermissionFix[]; unfixable: CleanupFailure[]; warnings: string[]; deletion: SubtreeDeletion }
+export type PingRequest = { value: string | null }
+export type PingResponse = { value: string | null }
+export type PressureLevel = "warning" | "critical"
+/**
+ * free space split the way Finder shows it, APFS keeps local snapshots and caches
+ * as purgeable so deleted bytes may not show up as free right away
+ */
+export type PurgeableSpace = { volume: string; free: number; purgeable: number }
+/**
+ * what the scanner is doing right now, to see which directory it is stuck on
+ */
+/**
+ * where a downloaded file came from, as recorded by the browser or the mail
+ * client which saved it
+ */
+export type Quarantine = { agent: string | null; origin: string | null; zone: number | null; downloadedAt: number | null; opened: boolean | null }
+export type QueueSnapshot = { pending: number; next: QueuedDirectory[]; workers: WorkerState[] }
+export type QueuedDirectory = { path: string; foreground: boolean }
+export type RecentChange = { path: string; size: number; kind: ChangeKind; changedAt: number }
+export type RejectedTarget = { path: string; reason: TargetRejection }
+/**
+ * what the backend did for one invoked command, found by the `requestId`
+ * the frontend got with the response or the error
+ */
+export type RequestTrace = { id: number; command: string; startedAt: number; millis: number | null; error: string | null; spans: SpanTiming[] }
+/**
+ * how often and how patiently a listing is retried after a transient error,
+ * network shares and external drives waking from sleep fail for a moment
+ * before they answer again
+ */
+export type RetryPolicy = { retries: number; backoffMillis: number; maxDelayMillis: number }
+export type ReviewItem = { path: string; state: ReviewState; size: number | null; updated: number }
+/**
+ * the items of one state, the largest first
+ */
+export type ReviewQueue = { state: ReviewState; count: number; bytes: number; items: ReviewItem[] }
+/**
+ * where the user put an item while triaging the large ones
+ */
+export type ReviewState = "toReview" | "keep" | "deleteLater" | "unsure"
+/**
+ * a scan the app cancelled on its own, what was scanned so far is kept as a snapshot
+ */
+export type ScanAborted = { reason: AbortReason; volume: string; snapshot: SessionInfo | null }
+export type ScanDelta = { root: string; size: number; files: number; previousSize: number | null; finishedAt: number }
+/**
+ * What changed between two scans of the same root. Each list holds the
+ * `limit` largest changes. A deleted directory is listed once, not every
+ * entry below it, and the directories holding a change grow or shrink with
+ * it, so the first ones tell where the space went.
+ */
+export type ScanDiff = { oldSize: number; newSize: number; grown: SizeChange[]; shrunk: SizeChange[]; newFiles: ChangedEntry[]; deleted: ChangedEntry[] }
+/**
+ * what a scan leaves out, as the user configured it
+ */
+export type ScanFilters = { exclude: string[]; minFileSize: number | null }
+export type ScanProgress = { scaned_files: number; scaned_size: number; current_path: string | null; is_scanning: boolean; hint: string | null; percent: number | null; remaining_secs: number | null }
+/**
+ * where the tree of a session comes from, a snapshot session is read-only
+ */
+export type ScanSource = { kind: "live" } | ({ kind: "snapshot" } & SessionInfo)
+/**
+ * a worker found stuck on a directory, reported once until the listing moves again
+ */
+export type ScanStall = { worker: number; path: string; idleSecs: number; skipped: boolean }
+/**
+ * normalized scan targets built from dropped or picked paths
+ */
+export type ScanTargets = { targets: string[]; rejected: RejectedTarget[] }
+/**
+ * How hard a scan works the disk. A laptop scanning in the background runs
+ * few workers at a capped rate, a desktop on an NVMe drive runs many
+ * without a cap.
+ */
+export type ScannerConfig = { workers: number; maxQueueDepth: number | null; entriesPerSec: number | null }
+export type SelectionRule = "same_extension_in_dir" | "older_than_selected" | "larger_than_selected" | "same_hash"
+/**
+ * what an imported archive contains, shown before the user browses it
+ */
+export type SessionInfo = { path: string; exportedAt: number; host: string | null; os: string | null; entries: number; size: number }
+/**
+ * Everything a user may tune, read from `settings.json`. Missing fields keep
+ * their default, so an older or hand written file still loads.
+ */
+export type Settings = { scanConcurrency: number; watchdog: Watchdog; retry: RetryPolicy; scanFilters: ScanFilters; protectedPaths: string[]; maintenanceIntervalHours: number; hashAlgorithm: HashAlgorithm | null; memoryLimitMb: number | null; hooks: Hook[]; metricsPort: number | null; ioLimitMbPerSec: number | null; backupTemplate: string | null; weeklySummaryNotification: boolean }
+/**
+ * Space Windows keeps on a volume for shadow copies, which System Restore
+ * points are made of. A scan of the files never sees it.
+ */
+export type ShadowStorage = { used: number; allocated: number; maximum: number | null; copies: number }
+/**
+ * a directory in both scans whose size changed
+ */
+export type SizeChange = { path: string; rawPath: string | null; oldSize: number; newSize: number }
+/**
+ * a node which grew or shrank since the previous batch
+ */
+export type SizeDelta = { path: string; oldSize: number; newSize: number }
+/**
+ * the size changes of one interval, sorted by path so parents come first
+ */
+export type SizeDeltaBatch = { deltas: SizeDelta[] }
+export type SortOrder = "ascending" | "descending"
+/**
+ * one timed step inside a request, e.g. listing the volumes for `start_scan`
+ */
+export type SpanTiming = { name: string; offsetMillis: number; millis: number }
+/**
+ * one step of a composite operation like backup-then-clean, none of them
+ * overwrites an existing destination
+ */
+export type Step = { kind: "copy"; from: string; to: string } | { kind: "move"; from: string; to: string } | { kind: "compress"; from: string; to: string } | { kind: "delete"; path: string }
+/**
+ * a `Step` the way the webview sends it, every path in the lossless form
+ */
+export type StepArg = { kind: "copy"; from: PathArg; to: PathArg } | { kind: "move"; from: PathArg; to: PathArg } | { kind: "compress"; from: PathArg; to: PathArg } | { kind: "delete"; path: PathArg }
+export type StepState = "done" | "failed" | "undone" | "undoFailed" | "skipped"
+/**
+ * the outcome of deleting one selected path
+ */
+export type SubtreeDeletion = { path: string; removed: boolean; files: number; directories: number; bytes: number; failures: CleanupFailure[]; failureCount: number; unlocked: number }
+/**
+ * what a scan does with a symbolic link to a directory
+ */
+export type SymlinkPolicy = "never" | "follow"
+/**
+ * a memory dump, crash report or swapfile found in the scanned tree
+ */
+export type SystemDump = { path: string; kind: DumpKind; size: number; modified: number | null; deletable: boolean; reason: string | null }
+export type TargetRejection = "notFound" | "unreadable" | "protected" | "nested"
+export type Thumbnail = { kind: ThumbnailKind; size: number; data: string; cached: boolean }
+export type ThumbnailKind = "image" | "video" | "pdf"
+export type TransactionOutcome = { auditId: number | null; state: TransactionState; journal: JournalEntry[]; failedStep: number | null; error: string | null; insufficientSpace: InsufficientSpace | null }
+export type TransactionState = "committed" | "rolledBack" | "rollbackFailed"
+/**
+ * what applying a batch of changes did to the tree
+ */
+export type TreeUpdate = { directories: string[]; deltas: SizeDelta[] }
+/**
+ * what kinds of files take the space below a folder, for a pie chart
+ */
+export type TypeBreakdown = { path: string; rawPath: string | null; size: number; files: number; categories: CategoryUsage[]; extensions: ExtensionUsage[] }
+/**
+ * Volumn Information
+ */
+/**
+ * what the filesystem of a volume supports, probed instead of guessed from the OS
+ */
+export type VolumeCapabilities = { caseSensitive: boolean; symlinks: boolean; hardlinks: boolean; reflinks: boolean; compression: boolean }
+/**
+ * free space of a volume before and after the simulated cleanup
+ */
+export type VolumeDiff = { volume: string; freed: number; availableBefore: number; availableAfter: number }
+export type Volumn = { name: string; displayName: string; path: string; totalSize: number; availableSize: number; fileSystem: string; capabilities: VolumeCapabilities | null; shadowStorage: ShadowStorage | null; inodes: InodeUsage | null }
+/**
+ * when a scan worker counts as hung and what to do about it
+ */
+export type Watchdog = { stallSecs: number; skip: boolean }
+/**
+ * what happened to the disks over one week, from the activity feed and the
+ * review queues
+ */
+export type WeeklySummary = { weekStart: number; createdAt: number; spaceFreed: number; cleanups: number; spaceGrown: number; newHotspots: Hotspot[]; pendingRecommendations: number; pendingBytes: number }
+export type WorkerState = { worker: number; path: string | null; millis: number }
+
+/** tauri-specta globals **/
+
+import {
+	invoke as TAURI_INVOKE,
+	Channel as TAURI_CHANNEL,
+} from "@tauri-apps/api/core";
+import * as TAURI_API_EVENT from "@tauri-apps/api/event";
+import { type WebviewWindow as __WebviewWindow__ } from "@tauri-apps/api/webviewWindow";
+
+type __EventObj__<T> = {
+	listen: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.listen<T>>;
+	once: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.once<T>>;
+	emit: null extends T
+		? (payload?: T) => ReturnType<typeof TAURI_API_EVENT.emit>
+		: (payload: T) => ReturnType<typeof TAURI_API_EVENT.emit>;
+};
+
+export type Result<T, E> =
+	| { status: "ok"; data: T }
+	| { status: "error"; error: E };
@@ -1,3 +1,4 @@
+export * from './bindings'
 import { invoke } from '@tauri-apps/api/core'
 
 export async function ping(value: string): Promise<string | null> {
@@ -0,0 +1,324 @@
+use std::path::Path;
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{
+    cost::{Operation, OperationEstimate, Throughput},
+    long_path, quarantine,
+    targets::is_protected,
+};
+
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    "zip", "rar", "7z", "tar", "gz", "bz2", "xz", "zst", "dmg", "iso",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ActionKind {
+    Reveal,
+    Trash,
+    Compress,
+    Hash,
+    AnalyzeGit,
+    ExplainSize,
+    StripQuarantine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ActionRisk {
+    /// read only
+    Safe,
+    /// writes new data next to the original
+    Moderate,
+    /// removes or changes user data
+    Destructive,
+}
+
+/**
+ * a context menu entry, unavailable actions carry the reason so the menu can explain it
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Action {
+    pub kind: ActionKind,
+    pub available: bool,
+    pub reason: Option<String>,
+    pub risk: ActionRisk,
+    /// how long a compress or a hash would take on this device
+    pub estimate: Option<OperationEstimate>,
+}
+
+/**
+ * what the registry needs to know about the selected path
+ */
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Subject {
+    pub exists: bool,
+    pub is_directory: bool,
+    pub is_link: bool,
+    pub scanned: bool,
+    pub protected: bool,
+    pub is_archive: bool,
+    pub is_repository: bool,
+    /// the path comes from an imported snapshot, not from this disk
+    pub historical: bool,
+    /// a download still carrying the quarantine of its origin
+    pub quarantined: bool,
+}
+
+impl Subject {
+    pub fn probe(path: &Path, scanned: bool) -> Subject {
+        let Ok(metadata) = std::fs::symlink_metadata(long_path::extended(path)) else {
+            return Subject::default();
+        };
+
+        let is_archive = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ARCHIVE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+
+        Subject {
+            exists: true,
+            is_directory: metadata.is_dir(),
+            is_link: metadata.is_symlink(),
+            scanned,
+            protected: is_protected(path),
+            is_archive,
+            is_repository: metadata.is_dir() && long_path::extended(&path.join(".git")).exists(),
+            historical: false,
+            quarantined: !metadata.is_dir() && quarantine::read(path).is_some(),
+        }
+    }
+
+    /**
+     * a node of an imported snapshot, only what the tree recorded is known
+     */
+    pub fn historical(is_directory: bool) -> Subject {
+        Subject {
+            exists: true,
+            is_directory,
+            scanned: true,
+            historical: true,
+            ..Default::default()
+        }
+    }
+}
+
+fn action(kind: ActionKind, risk: ActionRisk, unavailable: Option<&str>) -> Action {
+    Action {
+        kind,
+        available: unavailable.is_none(),
+        reason: unavailable.map(|reason| reason.to_string()),
+        risk,
+        estimate: None,
+    }
+}
+
+/**
+ * every registered action with its availability for the subject
+ */
+pub fn actions_for(subject: &Subject) -> Vec<Action> {
+    if subject.historical {
+        let historical = Some("browsing a historical snapshot");
+        let explain_size = if subject.is_directory {
+            None
+        } else {
+            Some("only directories have a size breakdown")
+        };
+        return vec![
+            action(ActionKind::Reveal, ActionRisk::Safe, historical),
+            action(ActionKind::Trash, ActionRisk::Destructive, historical),
+            action(ActionKind::Compress, ActionRisk::Moderate, historical),
+            action(ActionKind::Hash, ActionRisk::Safe, historical),
+            action(ActionKind::AnalyzeGit, ActionRisk::Safe, historical),
+            action(ActionKind::ExplainSize, ActionRisk::Safe, explain_size),
+            action(
+                ActionKind::StripQuarantine,
+                ActionRisk::Destructive,
+                historical,
+            ),
+        ];
+    }
+
+    if !subject.exists {
+        return vec![action(
+            ActionKind::Reveal,
+            ActionRisk::Safe,
+            Some("path no longer exists"),
+        )];
+    }
+
+    let trash = if subject.protected {
+        Some("protected system location")
+    } else {
+        None
+    };
+
+    let compress = if subject.protected {
+        Some("protected system location")
+    } else if subject.is_archive {
+        Some("already an archive")
+    } else if subject.is_link {
+        Some("symbolic link")
+    } else {
+        None
+    };
+
+    let hash = if subject.is_directory || subject.is_link {
+        Some("only regular files can be hashed")
+    } else {
+        None
+    };
+
+    let analyze_git = if subject.is_repository {
+        None
+    } else {
+        Some("not a git repository")
+    };
+
+    let explain_size = if !subject.is_directory {
+        Some("only directories have a size breakdown")
+    } else if !subject.scanned {
+        Some("directory has not been scanned")
+    } else {
+        None
+    };
+
+    // the system stops warning before the file is opened, so it counts as destructive
+    let strip_quarantine = if subject.quarantined {
+        None
+    } else {
+        Some("not downloaded from the internet")
+    };
+
+    vec![
+        action(ActionKind::Reveal, ActionRisk::Safe, None),
+        action(ActionKind::Trash, ActionRisk::Destructive, trash),
+        action(ActionKind::Compress, ActionRisk::Moderate, compress),
+        action(ActionKind::Hash, ActionRisk::Safe, hash),
+        action(ActionKind::AnalyzeGit, ActionRisk::Safe, analyze_git),
+        action(ActionKind::ExplainSize, ActionRisk::Safe, explain_size),
+        action(
+            ActionKind::StripQuarantine,
+            ActionRisk::Destructive,
+            strip_quarantine,
+        ),
+    ]
+}
+
+/**
+ * attach to the available compress and hash actions how long they would take over `bytes`
+ */
+pub fn estimate(actions: &mut [Action], throughput: &Throughput, bytes: u64) {
+    for action in actions.iter_mut().filter(|action| action.available) {
+        let operation = match action.kind {
+            ActionKind::Compress => Operation::Compress,
+            ActionKind::Hash => Operation::Hash,
+            _ => continue,
+        };
+        action.estimate = Some(throughput.estimate(operation, bytes, 0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn available(actions: &[Action]) -> Vec<ActionKind> {
+        actions
+            .iter()
+            .filter(|action| action.available)
+            .map(|action| action.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_actions_for_file() {
+        let subject = Subject {
+            exists: true,
+            scanned: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            available(&actions_for(&subject)),
+            vec![
+                ActionKind::Reveal,
+                ActionKind::Trash,
+                ActionKind::Compress,
+                ActionKind::Hash
+            ]
+        );
+    }
+
+    #[test]
+    fn test_actions_for_snapshot_directory() {
+        let actions = actions_for(&Subject::historical(true));
+        assert_eq!(available(&actions), vec![ActionKind::ExplainSize]);
+        assert!(actions[1].reason.is_some());
+    }
+
+    #[test]
+    fn test_actions_for_protected_repository() {
+        let subject = Subject {
+            exists: true,
+            is_directory: true,
+            scanned: true,
+            protected: true,
+            is_repository: true,
+            ..Default::default()
+        };
+        let actions = actions_for(&subject);
+        assert_eq!(
+            available(&actions),
+            vec![
+                ActionKind::Reveal,
+                ActionKind::AnalyzeGit,
+                ActionKind::ExplainSize
+            ]
+        );
+        assert_eq!(actions[1].risk, ActionRisk::Destructive);
+        assert!(actions[1].reason.is_some());
+    }
+
+    #[test]
+    fn test_strip_quarantine_only_for_downloads() {
+        let mut subject = Subject {
+            exists: true,
+            scanned: true,
+            ..Default::default()
+        };
+        let strip = |subject: &Subject| {
+            actions_for(subject)
+                .into_iter()
+                .find(|action| action.kind == ActionKind::StripQuarantine)
+                .unwrap()
+        };
+        assert!(!strip(&subject).available);
+
+        subject.quarantined = true;
+        let action = strip(&subject);
+        assert!(action.available);
+        assert_eq!(action.risk, ActionRisk::Destructive);
+    }
+
+    #[test]
+    fn test_estimates_only_for_available_work() {
+        let subject = Subject {
+            exists: true,
+            scanned: true,
+            is_archive: true,
+            ..Default::default()
+        };
+        let mut actions = actions_for(&subject);
+        estimate(&mut actions, &Throughput::default(), 1024 * 1024 * 1024);
+        let estimated: Vec<ActionKind> = actions
+            .iter()
+            .filter(|action| action.estimate.is_some())
+            .map(|action| action.kind)
+            .collect();
+        // an archive is not compressed again
+        assert_eq!(estimated, vec![ActionKind::Hash]);
+    }
+}
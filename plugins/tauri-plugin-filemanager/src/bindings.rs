@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use specta_typescript::{BigIntExportBehavior, Typescript};
+use tauri::Runtime;
+
+/**
+ * typescript definitions for commands, DTOs and events, shipped with the guest-js api
+ */
+pub const BINDINGS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/guest-js/bindings.ts");
+
+fn typescript() -> Typescript {
+    Typescript::default().bigint(BigIntExportBehavior::Number)
+}
+
+/**
+ * export the bindings so the webview calls are typed from the same source
+ */
+pub fn export<R: Runtime>(builder: &tauri_specta::Builder<R>, path: &Path) -> Result<(), String> {
+    builder
+        .export(typescript(), path)
+        .map_err(|err| format!("export bindings failed, {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    /// fails when the committed bindings drift from the rust definitions,
+    /// run the app in debug once to regenerate them
+    #[test]
+    fn test_bindings_up_to_date() {
+        let generated = temp_path("bindings.ts");
+        export(&crate::specta_builder::<tauri::Wry>(), &generated).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(generated).unwrap(),
+            std::fs::read_to_string(BINDINGS).unwrap(),
+            "bindings are out of date"
+        );
+    }
+}
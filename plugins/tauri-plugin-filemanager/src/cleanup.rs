@@ -0,0 +1,258 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+pub use crate::deletion::{CleanupFailure, outermost};
+use crate::{deletion::SubtreeDeletion, models::Volumn, purgeable::PurgeableSpace};
+
+/**
+ * per-volume trash folders, recognized anywhere in a path
+ */
+const TRASH_COMPONENTS: &[&str] = &[".Trashes", "$Recycle.Bin"];
+
+/**
+ * freed space may fall short of the expectation by this much before it is reported,
+ * filesystems round to blocks and other processes write meanwhile
+ */
+const FREED_TOLERANCE: u64 = 1024 * 1024;
+
+/**
+ * the trash folders of the current user
+ */
+#[cfg(target_os = "macos")]
+pub fn trash_roots() -> Vec<PathBuf> {
+    std::env::var_os("HOME")
+        .map(|home| vec![PathBuf::from(home).join(".Trash")])
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
+pub fn trash_roots() -> Vec<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .map(|data| vec![data.join("Trash")])
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+pub fn trash_roots() -> Vec<PathBuf> {
+    vec![PathBuf::from("C:\\$Recycle.Bin")]
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+pub fn trash_roots() -> Vec<PathBuf> {
+    vec![]
+}
+
+pub fn is_in_trash(path: &Path, roots: &[PathBuf]) -> bool {
+    roots.iter().any(|root| path.starts_with(root))
+        || path.components().any(|component| match component {
+            Component::Normal(name) => is_trash_name(name),
+            _ => false,
+        })
+}
+
+fn is_trash_name(name: &OsStr) -> bool {
+    let name = name.to_string_lossy();
+    TRASH_COMPONENTS.contains(&name.as_ref()) || name.starts_with(".Trash-")
+}
+
+/**
+ * what deleting a selection frees, with bytes already in the trash counted apart
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupEstimate {
+    /// selected paths left after dropping the ones nested in another selection
+    pub items: usize,
+    /// bytes still in place
+    pub in_place: usize,
+    /// bytes the user already moved to the trash
+    pub in_trash: usize,
+    /// selected paths which are not in the scanned tree
+    pub unknown: Vec<PathBuf>,
+}
+
+/**
+ * split the size of a selection between in-place and trashed bytes, a
+ * trash folder inside a selected directory is moved to the trashed side
+ */
+pub fn estimate<F>(paths: &[PathBuf], roots: &[PathBuf], size_of: F) -> CleanupEstimate
+where
+    F: Fn(&PathBuf) -> Option<usize>,
+{
+    let mut estimate = CleanupEstimate::default();
+    for path in outermost(paths) {
+        let Some(size) = size_of(&path) else {
+            estimate.unknown.push(path);
+            continue;
+        };
+
+        estimate.items += 1;
+        if is_in_trash(&path, roots) {
+            estimate.in_trash += size;
+            continue;
+        }
+
+        let nested_trash: usize = roots
+            .iter()
+            .filter(|root| root.starts_with(&path))
+            .filter_map(|root| size_of(root))
+            .sum();
+        estimate.in_trash += nested_trash;
+        estimate.in_place += size.saturating_sub(nested_trash);
+    }
+
+    estimate
+}
+
+/**
+ * free space of a volume around a cleanup, compared with what the removed files occupied
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FreeSpaceCheck {
+    pub volume: PathBuf,
+    pub expected: u64,
+    pub before: u64,
+    pub after: u64,
+    /// why the free space didn't grow as much as expected
+    pub discrepancy: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    pub removed: Vec<PathBuf>,
+    pub failed: Vec<CleanupFailure>,
+    pub expected_freed: u64,
+    pub volumes: Vec<FreeSpaceCheck>,
+    /// purgeable space of the touched volumes, empty where the system doesn't report it
+    pub purgeable: Vec<PurgeableSpace>,
+    /// why the purgeable space could not be reclaimed when it was asked for
+    pub reclaim_error: Option<String>,
+    /// what was removed below every selected path, with the entries left behind
+    pub subtrees: Vec<SubtreeDeletion>,
+    /// `cancel_cleanup` stopped the removal before it got through the selection
+    pub cancelled: bool,
+}
+
+/**
+ * the volume holding `path`, the one with the longest matching mount point
+ */
+pub fn volume_of<'a>(path: &Path, volumes: &'a [Volumn]) -> Option<&'a Volumn> {
+    volumes
+        .iter()
+        .filter(|volume| path.starts_with(&volume.path))
+        .max_by_key(|volume| volume.path.components().count())
+}
+
+/**
+ * compare the free space per volume before and after removing `freed` bytes from it
+ */
+pub fn verify_free_space(
+    freed: &HashMap<PathBuf, u64>,
+    before: &[Volumn],
+    after: &[Volumn],
+) -> Vec<FreeSpaceCheck> {
+    let mut checks: Vec<FreeSpaceCheck> = freed
+        .iter()
+        .filter_map(|(mount, expected)| {
+            let before = before.iter().find(|volume| volume.path == *mount)?;
+            let after = after.iter().find(|volume| volume.path == *mount)?;
+            let grown = after.available_size.saturating_sub(before.available_size);
+            let missing = expected.saturating_sub(grown);
+            let discrepancy = (missing > FREED_TOLERANCE).then(|| {
+                format!(
+                    "{} MB not released yet, removed files may still be open in another process or kept by a snapshot",
+                    missing / 1024 / 1024
+                )
+            });
+
+            Some(FreeSpaceCheck {
+                volume: mount.clone(),
+                expected: *expected,
+                before: before.available_size,
+                after: after.available_size,
+                discrepancy,
+            })
+        })
+        .collect();
+    checks.sort_by(|a, b| a.volume.cmp(&b.volume));
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(path: &str, available_size: u64) -> Volumn {
+        Volumn {
+            name: path.to_string(),
+            display_name: path.to_string(),
+            path: PathBuf::from(path),
+            total_size: 1 << 40,
+            available_size,
+            file_system: "apfs".to_string(),
+            capabilities: None,
+            shadow_storage: None,
+            inodes: None,
+        }
+    }
+
+    #[test]
+    fn test_volume_of_longest_mount() {
+        let volumes = [volume("/", 0), volume("/media/usb", 0)];
+        let found = volume_of(Path::new("/media/usb/a.iso"), &volumes).unwrap();
+        assert_eq!(found.path, PathBuf::from("/media/usb"));
+    }
+
+    #[test]
+    fn test_verify_reports_unreleased_space() {
+        let gb = 1 << 30;
+        let freed = HashMap::from([
+            (PathBuf::from("/"), 2 * gb),
+            (PathBuf::from("/media/usb"), gb),
+        ]);
+        let before = [volume("/", 10 * gb), volume("/media/usb", gb)];
+        let after = [volume("/", 10 * gb + gb / 2), volume("/media/usb", 2 * gb)];
+
+        let checks = verify_free_space(&freed, &before, &after);
+        assert_eq!(checks.len(), 2);
+        assert!(checks[0].discrepancy.is_some());
+        assert!(checks[1].discrepancy.is_none());
+    }
+
+    #[test]
+    fn test_estimate_separates_trash() {
+        let roots = vec![PathBuf::from("/home/me/.local/share/Trash")];
+        let size_of = |path: &PathBuf| match path.to_str()? {
+            "/home/me" => Some(1000),
+            "/home/me/docs" => Some(200),
+            "/home/me/.local/share/Trash" => Some(300),
+            "/media/usb/.Trash-1000/old.iso" => Some(50),
+            _ => None,
+        };
+
+        let estimate = estimate(
+            &[
+                PathBuf::from("/home/me/docs"),
+                PathBuf::from("/home/me"),
+                PathBuf::from("/media/usb/.Trash-1000/old.iso"),
+                PathBuf::from("/missing"),
+            ],
+            &roots,
+            size_of,
+        );
+        assert_eq!(estimate.items, 2);
+        assert_eq!(estimate.in_place, 700);
+        assert_eq!(estimate.in_trash, 350);
+        assert_eq!(estimate.unknown, vec![PathBuf::from("/missing")]);
+    }
+}
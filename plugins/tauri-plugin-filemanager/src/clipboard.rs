@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use specta::Type;
+
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Copy, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ClipboardFormat {
+    /// one path per line
+    Plain,
+    /// space separated, quoted for the platform shell
+    Shell,
+    /// table of name and size
+    Markdown,
+}
+
+pub(crate) fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.2} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(not(windows))]
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"))
+}
+
+#[cfg(windows)]
+fn shell_quote(path: &Path) -> String {
+    format!("\"{}\"", path.to_string_lossy().replace('"', "\\\""))
+}
+
+/**
+ * render the selection as text, `size_of` resolves the size shown in the markdown table
+ */
+pub fn format_paths<F>(paths: &[PathBuf], format: ClipboardFormat, size_of: F) -> String
+where
+    F: Fn(&Path) -> Option<usize>,
+{
+    match format {
+        ClipboardFormat::Plain => paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ClipboardFormat::Shell => paths
+            .iter()
+            .map(|path| shell_quote(path))
+            .collect::<Vec<_>>()
+            .join(" "),
+        ClipboardFormat::Markdown => {
+            let mut table = String::from("| Name | Size |\n| --- | ---: |\n");
+            for path in paths {
+                let name = path
+                    .file_name()
+                    .unwrap_or(path.as_os_str())
+                    .to_string_lossy()
+                    .replace('|', "\\|");
+                let size = size_of(path).map_or("-".to_string(), format_size);
+                table.push_str(&format!("| {} | {} |\n", name, size));
+            }
+            table
+        }
+    }
+}
+
+pub fn write_text(text: String) -> Result<()> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|err| Error::Clipboard(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths() -> Vec<PathBuf> {
+        vec![
+            PathBuf::from("/data/a b.txt"),
+            PathBuf::from("/data/it's|odd"),
+        ]
+    }
+
+    #[test]
+    fn test_plain_format() {
+        let text = format_paths(&paths(), ClipboardFormat::Plain, |_| None);
+        assert_eq!(text, "/data/a b.txt\n/data/it's|odd");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_shell_format() {
+        let text = format_paths(&paths(), ClipboardFormat::Shell, |_| None);
+        assert_eq!(text, "'/data/a b.txt' '/data/it'\\''s|odd'");
+    }
+
+    #[test]
+    fn test_markdown_format() {
+        let text = format_paths(&paths(), ClipboardFormat::Markdown, |path| {
+            (path == Path::new("/data/a b.txt")).then_some(2048)
+        });
+        assert_eq!(
+            text,
+            "| Name | Size |\n| --- | ---: |\n| a b.txt | 2.00 KB |\n| it's\\|odd | - |\n"
+        );
+    }
+}
@@ -1,13 +1,3203 @@
-use tauri::{command, AppHandle, Runtime};
+use std::{
+    collections::HashMap,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+    sync::{Arc, atomic::Ordering},
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager, Runtime, State, command};
+use tracing::debug;
 
-use crate::models::*;
 use crate::FilemanagerExt;
-use crate::Result;
+use crate::actions::{self, Action, Subject};
+use crate::activity::{ActivityFeed, ActivityKind, ActivityPage};
+use crate::analyzers::{self, AnalyzerInfo, AnalyzerReport};
+use crate::audit::{AuditEntry, AuditLog};
+use crate::backup_target::{self, BackupTarget};
+use crate::capabilities;
+use crate::cleanup::{self, CleanupEstimate, CleanupFailure, CleanupReport};
+use crate::clipboard::{self, ClipboardFormat};
+use crate::compare::{self, FolderDiff};
+use crate::conflicts::{Conflicts, PendingConflict};
+use crate::cost::ThroughputMeter;
+use crate::dashboard::{self, Dashboard, DashboardCache};
+use crate::deletion;
+use crate::display_name::{self, Breadcrumb};
+use crate::driver;
+use crate::dumps::{self, SystemDump};
+use crate::eta::{EstimateBasis, ScanTarget};
+use crate::events::{self, AppEvent};
+use crate::filters::ScanFilters;
+use crate::fingerprint::{self, DirectoryFingerprints, Freshness};
+use crate::hash::{HashIndex, HashProgress};
+use crate::hooks::{self, HookSummary};
+use crate::icons::{IconCache, IconHint};
+use crate::interference::{self, DiskWriters};
+use crate::labels::{FolderLabel, LabelStore, LabeledFolder};
+use crate::long_path;
+use crate::lossless::{self, PathArg};
+use crate::maintenance::{self, MaintenanceLog, MaintenanceReport, Retention};
+use crate::memory::{MemoryGuard, Mitigation};
+use crate::metrics::{self, Metrics, ScanMetrics};
+use crate::models::*;
+use crate::mover::{
+    self, CollisionPolicy, Collisions, Conflict, ConflictChoice, MoveProgress, MoveReport,
+};
+use crate::path_key::PathKey;
+use crate::purgeable;
+use crate::quarantine;
+use crate::queue::QueueSnapshot;
+use crate::repair::{self, PermissionRepair};
+use crate::request::{self, RequestTrace, Requests};
+use crate::resume::{self, CheckpointStore, InterruptedCopy};
+use crate::retry::RetryPolicy;
+use crate::review::{ReviewQueue, ReviewState, ReviewStore};
+use crate::scan_diff::{self, ScanDiff};
+use crate::scanner_config::ScannerConfig;
+use crate::selection::{self, SelectionRule};
+use crate::service::{
+    ScanProgress,
+    cache::{self, CachedScan, ScanCache},
+    dedup::DuplicateReport,
+    deleter::{self, DeleteFailure, DeleteMode, DeleteReport},
+    empty::{self, EmptyGroup, EmptyRemoval},
+    stats::TypeBreakdown,
+};
+use crate::session::{Session, Sessions};
+use crate::settings::{Settings, SettingsStore};
+use crate::simulation::{self, CleanupPlan, CleanupSimulation};
+use crate::snapshot::{self, ScanSource, SessionArchive, SessionInfo};
+use crate::store::{AgeOrder, FileCountHotspot, FoundFile, ItemCount, OldFilesPage};
+use crate::symlinks::SymlinkPolicy;
+use crate::targets;
+use crate::throttle::Throttle;
+use crate::thumbnails::{Thumbnail, Thumbnailer};
+use crate::timeline::{self, RecentChange};
+use crate::timing::DirectoryTiming;
+use crate::transaction::{self, Step, StepArg, StepState, TransactionOutcome};
+use crate::watchdog::Watchdog;
+use crate::weekly::{self, WeeklySummaries, WeeklySummary};
+use crate::{CommandResult, Error, Result};
 
 #[command]
+#[specta::specta]
 pub(crate) async fn ping<R: Runtime>(
     app: AppHandle<R>,
     payload: PingRequest,
-) -> Result<PingResponse> {
-    app.filemanager().ping(payload)
+    requests: State<'_, Requests>,
+) -> CommandResult<PingResponse> {
+    requests
+        .run("ping", async { app.filemanager().ping(payload) })
+        .await
+}
+
+/**
+ * scan from scratch, `estimate_by` picks what the progress percentage and
+ * remaining time are measured against, the used bytes of the volume by default.
+ * With `same_filesystem` the scan doesn't descend into other mounted volumes,
+ * `get_skipped_mounts` lists the ones it left out. Links to directories are
+ * only listed with `symlinks` set to follow them. The pre-scan hooks of the
+ * settings run first, the scan waits for them.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn start_scan<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    path: PathArg,
+    estimate_by: Option<EstimateBasis>,
+    same_filesystem: Option<bool>,
+    symlinks: Option<SymlinkPolicy>,
+    app_handle: AppHandle<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("start_scan", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "start_scan")?;
+            let _guard = session.guards.start_scan.try_enter()?;
+            let path = tree_path(&session, path)?;
+            debug!(
+                "start_folder_scan called with path: {:?}",
+                path.to_string_lossy()
+            );
+
+            // e.g. to mount the share about to be scanned, the scan waits for them
+            let summary = HookSummary::PreScan { path: path.clone() };
+            request::step("hooks", run_hooks(&app_handle, summary)).await;
+
+            // the previous scan is the best guess of the entries to come, read it before it is cleared
+            let previous_entries = session
+                .scanner
+                .with_tree(|tree| tree.size())
+                .filter(|entries| *entries > 1);
+
+            // Clear previous scan data
+            request::step("clear", session.scanner.clear()).await;
+
+            // lookups consult what the volume really does instead of guessing by OS
+            let root = path.clone();
+            let probed = request::step(
+                "probe_volume",
+                tokio::task::spawn_blocking(move || {
+                    let volumes = driver::list_volumes();
+                    cleanup::volume_of(&root, &volumes).map(|volume| {
+                        let used = volume.total_size.saturating_sub(volume.available_size);
+                        (capabilities::probe(volume), used, volume.path.clone())
+                    })
+                }),
+            )
+            .await;
+
+            let mut used_bytes = None;
+            let mut mount_point = None;
+            if let Ok(Some((capabilities, used, volume))) = probed {
+                session.scanner.set_capabilities(capabilities);
+                used_bytes = Some(used);
+                mount_point = Some(volume);
+            }
+            // the drive monitor cancels the scan if this volume goes away
+            let _ = session
+                .volume
+                .lock()
+                .map(|mut volume| *volume = mount_point);
+            session.aborted.store(false, Ordering::Relaxed);
+            let by_entries = previous_entries
+                .filter(|_| estimate_by == Some(EstimateBasis::Entries))
+                .and_then(|entries| ScanTarget::new(EstimateBasis::Entries, entries as u64));
+            let target = by_entries
+                .or_else(|| used_bytes.and_then(|used| ScanTarget::new(EstimateBasis::Size, used)));
+            session.scanner.set_target(target);
+            session
+                .scanner
+                .set_same_filesystem(same_filesystem.unwrap_or(false));
+            session
+                .scanner
+                .set_symlink_policy(symlinks.unwrap_or_default());
+
+            // Start scanning and get receiver
+            let started = Instant::now();
+            let mut rx = session.scanner.start(path.clone()).await?;
+            let label = window.label().to_string();
+
+            // the watchdog keeps reporting for this scan until the next one subscribes
+            let mut stalls = session.scanner.stalls();
+            let (stall_handle, stall_label) = (app_handle.clone(), label.clone());
+            tokio::spawn(async move {
+                while let Some(stall) = stalls.recv().await {
+                    let _ =
+                        events::emit_to(&stall_handle, &stall_label, AppEvent::ScanStalled(stall));
+                }
+            });
+            // once a second at most, the frontend animates the sizes instead of refetching
+            let mut deltas = session.scanner.size_deltas();
+            let (delta_handle, delta_label) = (app_handle.clone(), label.clone());
+            tokio::spawn(async move {
+                while let Some(batch) = deltas.recv().await {
+                    let _ = events::emit_to(
+                        &delta_handle,
+                        &delta_label,
+                        AppEvent::NodeSizeDelta(batch),
+                    );
+                }
+            });
+            let fingerprints = fingerprints_dir(&app_handle)
+                .map(|dir| DirectoryFingerprints::file_for(&dir, &path));
+            let scanned = session.clone();
+            // the tree of a whole disk can outgrow the memory of the webview host, warn before it does
+            let mut system = sysinfo::System::new();
+            system.refresh_memory();
+            let limit = app_handle
+                .state::<SettingsStore>()
+                .current()
+                .memory_limit(system.total_memory());
+            let mut guard = MemoryGuard::new(limit);
+
+            // Spawn task to handle file updates
+            tokio::spawn(async move {
+                while let Some(stats) = rx.recv().await {
+                    let pressure =
+                        guard.check(stats.scaned_files + 1, || scanned.scanner.mitigations());
+                    // Emit update event to frontend
+
+                    let _ =
+                        events::emit_to(&app_handle, &label, AppEvent::FolderScanProgress(stats));
+                    if let Some(pressure) = pressure {
+                        let _ = events::emit_to(
+                            &app_handle,
+                            &label,
+                            AppEvent::MemoryPressure(pressure),
+                        );
+                    }
+                }
+
+                debug!("all scan job finished");
+                if scanned.aborted.load(Ordering::Relaxed) {
+                    // the partial tree is no baseline for freshness checks or the dashboard
+                    return;
+                }
+                watch_tree(&app_handle, &label, &scanned, &path);
+                let entries = scanned.scanner.with_tree(|tree| tree.size());
+                app_handle
+                    .state::<ThroughputMeter>()
+                    .record_scan(entries.unwrap_or_default() as u64, started.elapsed());
+                if let Ok(file) = fingerprints {
+                    save_fingerprints(&scanned, &path, &file).await;
+                }
+                record_dashboard(&app_handle, scanned, path, started.elapsed()).await;
+
+                // Emit completion event
+                let _ = events::emit_to(
+                    &app_handle,
+                    &label,
+                    AppEvent::FolderScanComplete {
+                        message: "Scan completed".to_string(),
+                    },
+                );
+            });
+
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * keep the tree of the scan from outgrowing the memory, with one of the
+ * mitigations a `memory-pressure` event offered
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn mitigate_memory_pressure<R: Runtime>(
+    mitigation: Mitigation,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("mitigate_memory_pressure", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "mitigate_memory_pressure")?;
+            // collapsing walks the tree under its lock
+            let mitigated =
+                tokio::task::spawn_blocking(move || session.scanner.mitigate(mitigation));
+            request::step("mitigate", mitigated)
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))??;
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * the mount points a scan with `same_filesystem` didn't descend into
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_skipped_mounts<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<PathBuf>> {
+    requests
+        .run("get_skipped_mounts", async {
+            let session = sessions.get_or_create(window.label());
+            Ok(session.scanner.skipped_mounts())
+        })
+        .await
+}
+
+/**
+ * scan the folder the user clicked at high priority while the full scan keeps going
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn scan_on_demand<R: Runtime>(
+    path: PathArg,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("scan_on_demand", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "scan_on_demand")?;
+            Ok(session
+                .scanner
+                .scan_on_demand(&tree_path(&session, path)?)?)
+        })
+        .await
+}
+
+/**
+ * the directories waiting to be scanned and what each worker is stuck on
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_scan_queue<R: Runtime>(
+    limit: Option<usize>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<QueueSnapshot> {
+    requests
+        .run("get_scan_queue", async {
+            let session = sessions.get_or_create(window.label());
+            Ok(session.scanner.get_queue(limit.unwrap_or(50)))
+        })
+        .await
+}
+
+/**
+ * stop descending into a directory the user doesn't care about, the rest of the scan goes on
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn skip_subtree<R: Runtime>(
+    path: PathArg,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<usize> {
+    requests
+        .run("skip_subtree", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "skip_subtree")?;
+            Ok(session.scanner.skip_subtree(&tree_path(&session, path)?)?)
+        })
+        .await
+}
+
+/**
+ * make the scanner finish the folder the user is looking at first
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn prioritize_subtree<R: Runtime>(
+    path: PathArg,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<usize> {
+    requests
+        .run("prioritize_subtree", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "prioritize_subtree")?;
+            Ok(session
+                .scanner
+                .prioritize_subtree(&tree_path(&session, path)?)?)
+        })
+        .await
+}
+
+/**
+ * Read-only queries never take the control guards, so they can't hold up
+ * stop/clear. Without `limit` every child comes back, a folder of 100k
+ * entries is better asked for a page at a time.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_folder_stats<R: Runtime>(
+    path: PathArg,
+    sort_by: Option<ChildSort>,
+    order: Option<SortOrder>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    labels: State<'_, LabelStore>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Option<FileDetails>> {
+    requests
+        .run("get_folder_stats", async {
+            let session = sessions.get_or_create(window.label());
+            let path = tree_path(&session, path)?;
+            let page = ChildPage {
+                sort_by: sort_by.unwrap_or_default(),
+                order,
+                offset: offset.unwrap_or_default(),
+                limit,
+            };
+            let mut stats = session.scanner.get_file_node(&path, &page).await;
+            if let Some(details) = stats.as_mut() {
+                labels.label_details(details);
+            }
+            Ok(stats)
+        })
+        .await
+}
+
+/**
+ * the largest files under `root`, from whichever store backs the session,
+ * leaving out the ones below `min_size` bytes
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_largest_files<R: Runtime>(
+    root: PathArg,
+    limit: Option<usize>,
+    min_size: Option<u64>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<FoundFile>> {
+    requests
+        .run("get_largest_files", async {
+            let session = sessions.get_or_create(window.label());
+            let root = tree_path(&session, root)?;
+            Ok(session
+                .scanner
+                .with_store(|store| {
+                    let min_size = min_size.unwrap_or_default() as usize;
+                    store.largest_files_above(&root, min_size, limit.unwrap_or(100))
+                })
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/**
+ * Files under `root` neither modified nor created in the last
+ * `older_than_days` days, of at least `min_size` bytes, a page of `limit`
+ * from `offset` on. Oldest first unless `order` tells otherwise. Ask for
+ * the next page with its `nextOffset`.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn find_old_files<R: Runtime>(
+    root: PathArg,
+    older_than_days: u64,
+    min_size: Option<u64>,
+    order: Option<AgeOrder>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<OldFilesPage> {
+    requests
+        .run("find_old_files", async {
+            let session = sessions.get_or_create(window.label());
+            let root = tree_path(&session, root)?;
+            let before = now_secs().saturating_sub(older_than_days.saturating_mul(24 * 60 * 60));
+            let scanned = session.clone();
+            let found = tokio::task::spawn_blocking(move || {
+                scanned
+                    .scanner
+                    .with_store(|store| {
+                        store.old_files(
+                            &root,
+                            before,
+                            min_size.unwrap_or_default() as usize,
+                            order.unwrap_or_default(),
+                            offset.unwrap_or_default(),
+                            limit.unwrap_or(100),
+                        )
+                    })
+                    .unwrap_or_default()
+            });
+            let page = request::step("find", found)
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))?;
+            Ok(page)
+        })
+        .await
+}
+
+/**
+ * the children of `path` holding the most entries, to find what exhausts the inodes of a disk
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_most_files<R: Runtime>(
+    path: PathArg,
+    limit: Option<usize>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<ItemCount>> {
+    requests
+        .run("get_most_files", async {
+            let session = sessions.get_or_create(window.label());
+            let path = tree_path(&session, path)?;
+            Ok(session
+                .scanner
+                .with_store(|store| store.most_files(&path, limit.unwrap_or(100)))
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/**
+ * what kinds of files take the space below `path`, by category and by
+ * extension, none when it wasn't scanned
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_type_breakdown<R: Runtime>(
+    path: PathArg,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Option<TypeBreakdown>> {
+    requests
+        .run("get_type_breakdown", async {
+            let session = sessions.get_or_create(window.label());
+            let path = tree_path(&session, path)?;
+            Ok(session.scanner.type_breakdown(&path))
+        })
+        .await
+}
+
+fn analyzers_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf> {
+    Ok(fingerprints_dir(app)?.join("analyzers"))
+}
+
+/**
+ * the analyzer modules installed in the app data dir, experimental
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn list_analyzers<R: Runtime>(
+    app_handle: AppHandle<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<AnalyzerInfo>> {
+    requests
+        .run("list_analyzers", async {
+            let dir = analyzers_dir(&app_handle)?;
+            tokio::task::spawn_blocking(move || {
+                analyzers::discover(&dir)
+                    .into_iter()
+                    .map(|(info, _)| info)
+                    .collect()
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))
+        })
+        .await
+}
+
+/**
+ * run the installed analyzers, or the ones in `names`, through the scanned
+ * entries below `path`, experimental
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn run_analyzers<R: Runtime>(
+    path: PathArg,
+    names: Option<Vec<String>>,
+    app_handle: AppHandle<R>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<AnalyzerReport>> {
+    requests
+        .run("run_analyzers", async {
+            let session = sessions.get_or_create(window.label());
+            let path = tree_path(&session, path)?;
+            let dir = analyzers_dir(&app_handle)?;
+            tokio::task::spawn_blocking(move || {
+                let loaded: Vec<_> = analyzers::discover(&dir)
+                    .into_iter()
+                    .filter_map(|(_, analyzer)| analyzer)
+                    .filter(|analyzer| {
+                        names
+                            .as_ref()
+                            .is_none_or(|names| names.iter().any(|name| name == analyzer.name()))
+                    })
+                    .collect();
+                session
+                    .scanner
+                    .with_tree(|tree| analyzers::run_all(&loaded, tree, &path))
+                    .unwrap_or_default()
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))
+        })
+        .await
+}
+
+/**
+ * the directories under `root` with at least `min_count` tiny files of their
+ * own, 1000 by default, the most files first
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn find_file_count_hotspots<R: Runtime>(
+    root: PathArg,
+    min_count: Option<usize>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<FileCountHotspot>> {
+    requests
+        .run("find_file_count_hotspots", async {
+            let session = sessions.get_or_create(window.label());
+            let root = tree_path(&session, root)?;
+            Ok(session
+                .scanner
+                .with_store(|store| store.file_count_hotspots(&root, min_count.unwrap_or(1000)))
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/**
+ * entries under `root` whose name contains `query`
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn search_files<R: Runtime>(
+    root: PathArg,
+    query: String,
+    limit: Option<usize>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<FoundFile>> {
+    requests
+        .run("search_files", async {
+            let session = sessions.get_or_create(window.label());
+            let root = tree_path(&session, root)?;
+            Ok(session
+                .scanner
+                .with_store(|store| store.search(&root, &query, limit.unwrap_or(100)))
+                .unwrap_or_default())
+        })
+        .await
+}
+
+#[command]
+#[specta::specta]
+pub(crate) async fn get_scan_progress<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<ScanProgress> {
+    requests
+        .run("get_scan_progress", async {
+            let session = sessions.get_or_create(window.label());
+            Ok(session.scanner.get_progress().await?)
+        })
+        .await
+}
+
+#[command]
+#[specta::specta]
+pub(crate) async fn stop_folder_scan<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("stop_folder_scan", async {
+            if let Some(session) = sessions.get(window.label()) {
+                session.scanner.stop_scanning().await;
+            }
+            Ok(())
+        })
+        .await
+}
+
+#[command]
+#[specta::specta]
+pub(crate) async fn is_scanning<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<bool> {
+    requests
+        .run("is_scanning", async {
+            if let Some(session) = sessions.get(window.label()) {
+                Ok(session.scanner.is_scanning().await)
+            } else {
+                Ok(false)
+            }
+        })
+        .await
+}
+
+#[command]
+#[specta::specta]
+pub(crate) async fn clear_folder_scan<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("clear_folder_scan", async {
+            let session = sessions.get_or_create(window.label());
+            let _guard = session.guards.clear_scan.try_enter()?;
+            debug!("clear folder scan has been called");
+            session.scanner.clear().await;
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * validate paths picked or dropped by the user into a deduplicated scan target list
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn prepare_scan_targets(
+    paths: Vec<PathArg>,
+    requests: State<'_, Requests>,
+) -> CommandResult<ScanTargets> {
+    requests
+        .run("prepare_scan_targets", async {
+            Ok(targets::prepare_scan_targets(&lossless::into_paths(paths)?))
+        })
+        .await
+}
+
+/**
+ * copy the selection to the clipboard without round-tripping it through the webview
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn copy_paths_to_clipboard<R: Runtime>(
+    paths: Vec<PathArg>,
+    format: ClipboardFormat,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("copy_paths_to_clipboard", async {
+            let session = sessions.get_or_create(window.label());
+            let paths = lossless::into_paths(paths)?;
+            let text =
+                clipboard::format_paths(&paths, format, |path| session.scanner.get_size(path));
+            clipboard::write_text(text)
+        })
+        .await
+}
+
+/**
+ * bytes a cleanup of the selection frees, without counting what already sits in the trash twice
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn estimate_cleanup<R: Runtime>(
+    paths: Vec<PathArg>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<CleanupEstimate> {
+    requests
+        .run("estimate_cleanup", async {
+            let session = sessions.get_or_create(window.label());
+            let paths = tree_paths(&session, paths)?;
+            Ok(cleanup::estimate(&paths, &cleanup::trash_roots(), |path| {
+                session.scanner.get_size(path)
+            }))
+        })
+        .await
+}
+
+/**
+ * permanently delete the selection, then check each touched volume really
+ * gained the space the removed files occupied. `reclaim_purgeable` also asks
+ * macOS to release purgeable space so the result matches Finder. The removal
+ * reports `cleanup-progress` every second and stops on `cancel_cleanup`.
+ * `unlock` clears the locked, read-only and system flags in its way, without
+ * it a locked entry fails with the flags it carries
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn run_cleanup<R: Runtime>(
+    paths: Vec<PathArg>,
+    reclaim_purgeable: Option<bool>,
+    unlock: Option<bool>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<CleanupReport> {
+    requests
+        .run("run_cleanup", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "run_cleanup")?;
+            let _guard = session.guards.cleanup.try_enter()?;
+            let paths = tree_paths(&session, paths)?;
+            clean(
+                &session,
+                &window,
+                paths,
+                reclaim_purgeable.unwrap_or(false),
+                unlock.unwrap_or(false),
+                "run_cleanup",
+            )
+            .await
+        })
+        .await
+}
+
+/**
+ * move `paths` to the trash of the OS, reporting `delete-progress` while the
+ * batch runs and `delete-complete` at the end
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn trash_paths<R: Runtime>(
+    paths: Vec<PathArg>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<DeleteReport> {
+    requests
+        .run("trash_paths", async {
+            delete_batch(
+                &sessions,
+                &window,
+                paths,
+                DeleteMode::Trash,
+                "trash_paths",
+                protected_location,
+            )
+            .await
+        })
+        .await
+}
+
+/**
+ * delete `paths` for good, reporting `delete-progress` while the batch runs
+ * and `delete-complete` at the end
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn delete_paths<R: Runtime>(
+    paths: Vec<PathArg>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<DeleteReport> {
+    requests
+        .run("delete_paths", async {
+            delete_batch(
+                &sessions,
+                &window,
+                paths,
+                DeleteMode::Permanent,
+                "delete_paths",
+                protected_location,
+            )
+            .await
+        })
+        .await
+}
+
+/**
+ * Move `from` to `to`. An existing destination is handled by `on_conflict`,
+ * refused by default. On another volume the entry is copied, checked
+ * against the original and only then removed, reporting `move-progress`
+ * while it copies no faster than `io_limit_mb_per_sec`, the one of the
+ * settings by default. The moved entries leave the scanned tree.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn move_file<R: Runtime>(
+    from: PathArg,
+    to: PathArg,
+    on_conflict: Option<CollisionPolicy>,
+    io_limit_mb_per_sec: Option<u64>,
+    sessions: State<'_, Sessions>,
+    conflicts: State<'_, Conflicts>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<MoveReport> {
+    requests
+        .run("move_file", async {
+            transfer(
+                &sessions,
+                conflicts.inner(),
+                &window,
+                (from, to),
+                on_conflict.unwrap_or_default(),
+                io_limit_mb_per_sec,
+                false,
+            )
+            .await
+        })
+        .await
+}
+
+/**
+ * Copy `from` to `to`, an existing destination handled by `on_conflict`,
+ * refused by default. Reports `move-progress` while it copies no faster
+ * than `io_limit_mb_per_sec`, the one of the settings by default. A copy
+ * to a destination which doesn't exist yet keeps checkpoints, when it
+ * stops its error tells the id to pass to `resume_operation`. Rejected
+ * with `insufficientSpace` before anything is written when the destination
+ * has no room for it.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn copy_file<R: Runtime>(
+    from: PathArg,
+    to: PathArg,
+    on_conflict: Option<CollisionPolicy>,
+    io_limit_mb_per_sec: Option<u64>,
+    sessions: State<'_, Sessions>,
+    conflicts: State<'_, Conflicts>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<MoveReport> {
+    requests
+        .run("copy_file", async {
+            transfer(
+                &sessions,
+                conflicts.inner(),
+                &window,
+                (from, to),
+                on_conflict.unwrap_or_default(),
+                io_limit_mb_per_sec,
+                true,
+            )
+            .await
+        })
+        .await
+}
+
+/**
+ * Answer the `conflict` a move or copy with `on_conflict: "ask"` paused on.
+ * With `apply_to_all` the choice holds for the rest of the operation.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn resolve_conflict(
+    operation_id: u64,
+    choice: ConflictChoice,
+    apply_to_all: bool,
+    conflicts: State<'_, Conflicts>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("resolve_conflict", async {
+            if conflicts.resolve(operation_id, choice, apply_to_all) {
+                Ok(())
+            } else {
+                Err(Error::Scanner(format!(
+                    "operation {} waits on no conflict",
+                    operation_id
+                )))
+            }
+        })
+        .await
+}
+
+/**
+ * the copies which stopped before they were done, the latest first
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn list_interrupted_operations(
+    checkpoints: State<'_, CheckpointStore>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<InterruptedCopy>> {
+    requests
+        .run("list_interrupted_operations", async {
+            Ok(checkpoints.interrupted()?)
+        })
+        .await
+}
+
+/**
+ * Continue the interrupted copy `id` from its last checkpoint, reporting
+ * `move-progress` like `copy_file`. The files it copied completely are
+ * kept, the one it was copying goes on from the last bytes synced.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn resume_operation<R: Runtime>(
+    id: i64,
+    io_limit_mb_per_sec: Option<u64>,
+    checkpoints: State<'_, CheckpointStore>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<MoveReport> {
+    requests
+        .run("resume_operation", async {
+            let checkpoints = checkpoints.inner().clone();
+            let mut throttle = throttle(window.app_handle(), io_limit_mb_per_sec);
+            let (app, label) = (window.app_handle().clone(), window.label().to_string());
+            let resumed = tokio::task::spawn_blocking(move || {
+                resume::resume(&checkpoints, id, &mut throttle, &mut |progress| {
+                    let event = AppEvent::MoveProgress(progress.clone());
+                    let _ = events::emit_to(&app, &label, event);
+                })
+            });
+            let copied = request::step("copy", resumed)
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))?
+                .map_err(|err| Error::of_io(err, |err| format!("resume {} failed, {}", id, err)))?;
+            Ok(copied)
+        })
+        .await
+}
+
+/**
+ * Give up on the interrupted copy `id`. The file it was copying is removed,
+ * the files it copied completely stay.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn discard_operation(
+    id: i64,
+    checkpoints: State<'_, CheckpointStore>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("discard_operation", async { Ok(checkpoints.discard(id)?) })
+        .await
+}
+
+/**
+ * the throughput cap of an operation, the one of the call over the one of the settings
+ */
+fn throttle<R: Runtime>(app: &AppHandle<R>, io_limit: Option<u64>) -> Throttle {
+    let settings = app.state::<SettingsStore>().current();
+    Throttle::mb_per_sec(io_limit.or(settings.io_limit_mb_per_sec))
+}
+
+/**
+ * `move_file` and `copy_file`, each conflict asked about with a `conflict`
+ * event when `policy` is `Ask`
+ */
+async fn transfer<R: Runtime>(
+    sessions: &Sessions,
+    conflicts: &Conflicts,
+    window: &WebviewWindow<R>,
+    (from, to): (PathArg, PathArg),
+    policy: CollisionPolicy,
+    io_limit: Option<u64>,
+    copy: bool,
+) -> Result<MoveReport> {
+    let (command, step) = if copy {
+        ("copy_file", "copy")
+    } else {
+        ("move_file", "move")
+    };
+    let session = sessions.get_or_create(window.label());
+    ensure_live(&session, command)?;
+    let _guard = session.guards.cleanup.try_enter()?;
+    let from = tree_path(&session, from)?;
+    let to = to.into_path_buf()?;
+    if !copy && targets::is_protected(&from) {
+        return Err(Error::Scanner(format!(
+            "{} is a protected system location",
+            from.display()
+        )));
+    }
+    // only an overwrite removes anything at the destination
+    let overwrites = matches!(policy, CollisionPolicy::Overwrite | CollisionPolicy::Ask);
+    if overwrites && targets::is_protected(&to) {
+        return Err(Error::Scanner(format!(
+            "{} is a protected system location, it is never overwritten",
+            to.display()
+        )));
+    }
+
+    let operation = conflicts.begin();
+    let mut throttle = throttle(window.app_handle(), io_limit);
+    let (app, label) = (window.app_handle().clone(), window.label().to_string());
+    let (source, destination) = (from.clone(), to.clone());
+    let moving = tokio::task::spawn_blocking(move || {
+        let conflicts = app.state::<Conflicts>();
+        let mut ask = |conflict: &Conflict| {
+            conflicts.wait(operation, || {
+                let pending = PendingConflict {
+                    operation_id: operation,
+                    conflict: conflict.clone(),
+                };
+                let _ = events::emit_to(&app, &label, AppEvent::Conflict(pending));
+            })
+        };
+        let mut observe = |progress: &MoveProgress| {
+            let event = AppEvent::MoveProgress(progress.clone());
+            let _ = events::emit_to(&app, &label, event);
+        };
+        // only a fresh copy can be resumed, one into an existing destination resolves conflicts
+        if copy && std::fs::symlink_metadata(&destination).is_err() {
+            let checkpoints = app.state::<CheckpointStore>();
+            return resume::copy_resumable(
+                &checkpoints,
+                &source,
+                &destination,
+                &mut throttle,
+                &mut observe,
+            );
+        }
+        let mut collisions = Collisions::new(policy, &mut ask);
+        mover::transfer_path(
+            &source,
+            &destination,
+            copy,
+            &mut collisions,
+            &mut throttle,
+            &mut observe,
+        )
+    });
+    let moved = request::step(step, moving)
+        .await
+        .map_err(|err| Error::Scanner(err.to_string()))?
+        .map_err(|err| {
+            Error::of_io(err, |err| {
+                format!(
+                    "{} {} to {} failed, {}",
+                    step,
+                    from.display(),
+                    to.display(),
+                    err
+                )
+            })
+        })?;
+    for replaced in &moved.replaced {
+        session.scanner.forget(replaced);
+    }
+    if !copy {
+        for path in &moved.transferred {
+            session.scanner.forget(path);
+        }
+    }
+    Ok(moved)
+}
+
+/**
+ * Memory dumps, crash reports and swapfiles of the scanned tree, the largest
+ * first. A full memory dump is as large as the RAM and often forgotten.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn find_system_dumps<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<SystemDump>> {
+    requests
+        .run("find_system_dumps", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "find_system_dumps")?;
+            let scanned = session.clone();
+            let found = tokio::task::spawn_blocking(move || {
+                scanned
+                    .scanner
+                    .with_tree(|tree| {
+                        let root = tree.root_path()?;
+                        Some(dumps::find(tree, &root))
+                    })
+                    .flatten()
+                    .unwrap_or_default()
+            });
+            let dumps = request::step("find", found)
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))?;
+            Ok(dumps)
+        })
+        .await
+}
+
+/**
+ * Delete memory dumps, crash reports and unused swapfiles, inside protected
+ * system locations too. Any other path is refused, as is a swapfile the
+ * system still uses.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn delete_system_dumps<R: Runtime>(
+    paths: Vec<PathArg>,
+    mode: DeleteMode,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<DeleteReport> {
+    requests
+        .run("delete_system_dumps", async {
+            delete_batch(
+                &sessions,
+                &window,
+                paths,
+                mode,
+                "delete_system_dumps",
+                |path| dumps::check(path).err(),
+            )
+            .await
+        })
+        .await
+}
+
+/**
+ * the directories below `root` without a single file, grouped by their
+ * parent, the nested ones folded into the outermost
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn find_empty_directories<R: Runtime>(
+    root: PathArg,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<EmptyGroup>> {
+    requests
+        .run("find_empty_directories", async {
+            let session = sessions.get_or_create(window.label());
+            let root = tree_path(&session, root)?;
+            let scanned = session.clone();
+            let found = tokio::task::spawn_blocking(move || {
+                scanned
+                    .scanner
+                    .with_tree(|tree| empty::find(tree, &root))
+                    .unwrap_or_default()
+            });
+            let groups = request::step("find", found)
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))?;
+            Ok(groups)
+        })
+        .await
+}
+
+/**
+ * Remove the empty directories at `paths` with the empty ones inside them,
+ * the deepest first. A directory which isn't empty anymore is left, with
+ * its ancestors.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn remove_empty_directories<R: Runtime>(
+    paths: Vec<PathArg>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<EmptyRemoval> {
+    requests
+        .run("remove_empty_directories", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "remove_empty_directories")?;
+            let _guard = session.guards.cleanup.try_enter()?;
+            let mut refused = vec![];
+            let paths: Vec<PathBuf> = tree_paths(&session, paths)?
+                .into_iter()
+                .filter(|path| match protected_location(path) {
+                    Some(reason) => {
+                        refused.push(DeleteFailure {
+                            path: path.clone(),
+                            reason,
+                        });
+                        false
+                    }
+                    None => true,
+                })
+                .collect();
+
+            let removing = session.clone();
+            let removal =
+                tokio::task::spawn_blocking(move || empty::remove(&removing.scanner, &paths));
+            let mut report = request::step("remove", removal)
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))?;
+            report.failed.extend(refused);
+
+            let summary = HookSummary::PostCleanup {
+                operation: "remove_empty_directories".to_string(),
+                removed: report.directories,
+                failed: report.failed.len(),
+                freed_bytes: 0,
+                cancelled: false,
+            };
+            cleaned(window.app_handle(), summary);
+            Ok(report)
+        })
+        .await
+}
+
+fn protected_location(path: &Path) -> Option<String> {
+    targets::is_protected(path).then(|| "protected system location".to_string())
+}
+
+/**
+ * the batch deleters, the paths `refuse` gives a reason for are refused one by one
+ */
+async fn delete_batch<R: Runtime>(
+    sessions: &Sessions,
+    window: &WebviewWindow<R>,
+    paths: Vec<PathArg>,
+    mode: DeleteMode,
+    command: &'static str,
+    refuse: fn(&Path) -> Option<String>,
+) -> Result<DeleteReport> {
+    let session = sessions.get_or_create(window.label());
+    ensure_live(&session, command)?;
+    let _guard = session.guards.cleanup.try_enter()?;
+    let mut refused = vec![];
+    let paths: Vec<PathBuf> = tree_paths(&session, paths)?
+        .into_iter()
+        .filter(|path| match refuse(path) {
+            Some(reason) => {
+                refused.push(DeleteFailure {
+                    path: path.clone(),
+                    reason,
+                });
+                false
+            }
+            None => true,
+        })
+        .collect();
+
+    session.cleanup_cancelled.store(false, Ordering::Relaxed);
+    let (app, label) = (window.app_handle().clone(), window.label().to_string());
+    let deleting = session.clone();
+    let removal = tokio::task::spawn_blocking(move || {
+        let cancel = &deleting.cleanup_cancelled;
+        deleter::delete(&deleting.scanner, &paths, mode, cancel, &mut |progress| {
+            let event = AppEvent::DeleteProgress(progress.clone());
+            let _ = events::emit_to(&app, &label, event);
+        })
+    });
+    let mut report = request::step("delete", removal)
+        .await
+        .map_err(|err| Error::Scanner(err.to_string()))?;
+    report.failed.extend(refused);
+
+    let event = AppEvent::DeleteComplete(report.clone());
+    let _ = events::emit_to(window.app_handle(), window.label(), event);
+    let summary = HookSummary::PostCleanup {
+        operation: command.to_string(),
+        removed: report.deleted.len(),
+        failed: report.failed.len(),
+        freed_bytes: report.bytes,
+        cancelled: session.cleanup_cancelled.load(Ordering::Relaxed),
+    };
+    cleaned(window.app_handle(), summary);
+    Ok(report)
+}
+
+/**
+ * remove `paths` for `run_cleanup` and `execute_delete_later`, the caller
+ * holds the cleanup guard of the session
+ */
+async fn clean<R: Runtime>(
+    session: &Arc<Session>,
+    window: &WebviewWindow<R>,
+    paths: Vec<PathBuf>,
+    reclaim_purgeable: bool,
+    unlock: bool,
+    command: &'static str,
+) -> Result<CleanupReport> {
+    let mut report = CleanupReport::default();
+    let mut selected: Vec<(PathBuf, u64)> = vec![];
+    for path in cleanup::outermost(&paths) {
+        if targets::is_protected(&path) {
+            report.failed.push(CleanupFailure {
+                path,
+                reason: "protected system location".to_string(),
+            });
+            continue;
+        }
+
+        let size = session
+            .scanner
+            .get_size(&path)
+            .map(|size| size as u64)
+            .or_else(|| {
+                std::fs::symlink_metadata(long_path::extended(&path))
+                    .ok()
+                    .map(|meta| meta.len())
+            });
+        selected.push((path, size.unwrap_or(0)));
+    }
+
+    session.cleanup_cancelled.store(false, Ordering::Relaxed);
+    let (app, label) = (window.app_handle().clone(), window.label().to_string());
+    let deleting = session.clone();
+    let removal = tokio::task::spawn_blocking(move || {
+        let before = driver::list_volumes();
+        let roots: Vec<PathBuf> = selected.iter().map(|(path, _)| path.clone()).collect();
+        let subtrees = deletion::delete(
+            &roots,
+            deletion::WORKERS,
+            unlock,
+            &deleting.cleanup_cancelled,
+            &mut |progress| {
+                if progress.done {
+                    app.state::<ThroughputMeter>().record_deletion(
+                        progress.files + progress.directories,
+                        Duration::from_millis(progress.elapsed_millis),
+                    );
+                }
+                let event = AppEvent::CleanupProgress(progress.clone());
+                let _ = events::emit_to(&app, &label, event);
+            },
+        );
+        report.cancelled = deleting.cleanup_cancelled.load(Ordering::Relaxed);
+
+        let mut freed: HashMap<PathBuf, u64> = HashMap::new();
+        for ((path, size), subtree) in selected.into_iter().zip(&subtrees) {
+            // a partly removed directory freed what its workers got through
+            let size = if subtree.removed { size } else { subtree.bytes };
+            if let Some(volume) = cleanup::volume_of(&path, &before) {
+                *freed.entry(volume.path.clone()).or_default() += size;
+            }
+            report.expected_freed += size;
+
+            if subtree.removed {
+                report.removed.push(path);
+            } else {
+                let reason = match subtree.failures.first() {
+                    Some(first) => format!(
+                        "{} entries could not be removed, {}: {}",
+                        subtree.failure_count,
+                        first.path.display(),
+                        first.reason
+                    ),
+                    None => "cancelled".to_string(),
+                };
+                report.failed.push(CleanupFailure { path, reason });
+            }
+        }
+        report.subtrees = subtrees;
+
+        if reclaim_purgeable {
+            let errors: Vec<String> = freed
+                .iter()
+                .filter_map(|(mount, size)| purgeable::reclaim(mount, *size).err())
+                .map(|err| err.to_string())
+                .collect();
+            report.reclaim_error = (!errors.is_empty()).then(|| errors.join(", "));
+        }
+
+        let after = driver::list_volumes();
+        report.purgeable = after
+            .iter()
+            .filter(|volume| freed.contains_key(&volume.path))
+            .filter_map(purgeable::measure)
+            .collect();
+        (report, freed, before, after)
+    });
+    let (mut report, freed, before, after) = request::step("remove", removal)
+        .await
+        .map_err(|err| Error::Scanner(err.to_string()))?;
+
+    for path in &report.removed {
+        session.scanner.forget(path);
+    }
+    report.volumes = cleanup::verify_free_space(&freed, &before, &after);
+
+    let summary = HookSummary::PostCleanup {
+        operation: command.to_string(),
+        removed: report.removed.len(),
+        failed: report.failed.len(),
+        freed_bytes: report.expected_freed,
+        cancelled: report.cancelled,
+    };
+    cleaned(window.app_handle(), summary);
+    Ok(report)
+}
+
+/**
+ * context menu actions for a path, driven by what the backend can actually do with it.
+ * Compress and hash carry how long they would take at the measured throughput
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_actions_for<R: Runtime>(
+    path: PathArg,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    meter: State<'_, ThroughputMeter>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<Action>> {
+    requests
+        .run("get_actions_for", async {
+            let session = sessions.get_or_create(window.label());
+            let path = tree_path(&session, path)?;
+            if session.scanner.is_snapshot() {
+                let is_directory = session
+                    .scanner
+                    .with_tree(|tree| tree.get_node(&path))
+                    .flatten()
+                    .is_some_and(|node| node.read().is_ok_and(|node| node.is_directory));
+                return Ok(actions::actions_for(&Subject::historical(is_directory)));
+            }
+
+            let size = session.scanner.get_size(&path);
+            let mut actions = actions::actions_for(&Subject::probe(&path, size.is_some()));
+            let bytes = size.map(|size| size as u64).or_else(|| {
+                std::fs::symlink_metadata(long_path::extended(&path))
+                    .ok()
+                    .map(|meta| meta.len())
+            });
+            actions::estimate(&mut actions, &meter.current(), bytes.unwrap_or(0));
+            Ok(actions)
+        })
+        .await
+}
+
+/**
+ * how often the progress of a file being hashed is emitted, its last chunk always is
+ */
+const HASH_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/**
+ * "select all like this", computed over the scanned tree instead of the rendered list
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn expand_selection<R: Runtime>(
+    paths: Vec<PathArg>,
+    rule: SelectionRule,
+    sessions: State<'_, Sessions>,
+    index: State<'_, HashIndex>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<PathBuf>> {
+    requests
+        .run("expand_selection", async {
+            let session = sessions.get_or_create(window.label());
+            let paths = tree_paths(&session, paths)?;
+            let expanded = session
+                .scanner
+                .with_tree(|tree| selection::expand_in_tree(tree, &paths, rule))
+                .ok_or_else(|| Error::Scanner("scan tree unavailable".to_string()))?;
+
+            if rule == SelectionRule::SameHash {
+                // hash outside of the tree lock, the candidates are owned paths
+                let index = index.inner().clone();
+                let (app, label) = (window.app_handle().clone(), window.label().to_string());
+                tokio::task::spawn_blocking(move || {
+                    let mut reported: Option<Instant> = None;
+                    let mut observe = |progress: &HashProgress| {
+                        let finished = progress.hashed == progress.size;
+                        if finished && progress.bytes_per_sec > 0 {
+                            let elapsed = progress.size as f64 / progress.bytes_per_sec as f64;
+                            app.state::<ThroughputMeter>()
+                                .record_read(progress.size, Duration::from_secs_f64(elapsed));
+                        }
+                        if !finished
+                            && reported.is_some_and(|at| at.elapsed() < HASH_PROGRESS_INTERVAL)
+                        {
+                            return;
+                        }
+                        reported = Some(Instant::now());
+                        let event = AppEvent::HashProgress(progress.clone());
+                        let _ = events::emit_to(&app, &label, event);
+                    };
+                    selection::filter_same_hash(&index, &paths, expanded, &mut observe)
+                })
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))
+            } else {
+                Ok(expanded.into_iter().collect())
+            }
+        })
+        .await
+}
+
+/**
+ * Files of the scanned tree with the same content, from `min_size` bytes on.
+ * Every group is emitted as `duplicate-found` once it is confirmed, the
+ * report holds them all sorted by the bytes they reclaim.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn find_duplicates<R: Runtime>(
+    min_size: Option<u64>,
+    sessions: State<'_, Sessions>,
+    index: State<'_, HashIndex>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<DuplicateReport> {
+    requests
+        .run("find_duplicates", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "find_duplicates")?;
+
+            let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+            let (app, label) = (window.app_handle().clone(), window.label().to_string());
+            let forward = tokio::spawn(async move {
+                while let Some(group) = rx.recv().await {
+                    let _ = events::emit_to(&app, &label, AppEvent::DuplicateFound(group));
+                }
+            });
+            let report = request::step(
+                "hash",
+                session.scanner.find_duplicates(
+                    index.inner().clone(),
+                    min_size.unwrap_or(0),
+                    Some(tx),
+                ),
+            )
+            .await;
+            let _ = forward.await;
+            Ok(report)
+        })
+        .await
+}
+
+/**
+ * what appeared or changed under `root` since the given unix timestamp, largest first
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_recent_changes<R: Runtime>(
+    root: PathArg,
+    since: u64,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<RecentChange>> {
+    requests
+        .run("get_recent_changes", async {
+            let session = sessions.get_or_create(window.label());
+            let root = tree_path(&session, root)?;
+            session
+                .scanner
+                .with_tree(|tree| timeline::recent_changes(tree, &root, since))
+                .ok_or_else(|| Error::Scanner("scan tree unavailable".to_string()))
+        })
+        .await
+}
+
+/**
+ * the slowest directories of the current scan, to spot network mounts or failing disks
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_slow_directories<R: Runtime>(
+    limit: Option<usize>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<DirectoryTiming>> {
+    requests
+        .run("get_slow_directories", async {
+            let session = sessions.get_or_create(window.label());
+            Ok(session.scanner.get_slow_directories(limit.unwrap_or(20)))
+        })
+        .await
+}
+
+fn fingerprints_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf> {
+    Ok(app.path().app_data_dir().map_err(std::io::Error::other)?)
+}
+
+/**
+ * remember the directory mtimes of the finished scan for the next freshness check
+ */
+async fn save_fingerprints(session: &Session, root: &PathBuf, file: &PathBuf) {
+    let Some(fingerprints) = session
+        .scanner
+        .with_tree(|tree| DirectoryFingerprints::from_tree(tree, root))
+        .flatten()
+    else {
+        return;
+    };
+
+    let file = file.clone();
+    let saved = tokio::task::spawn_blocking(move || {
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        fingerprints.save(&file)
+    })
+    .await;
+    if let Ok(Err(err)) = saved {
+        tracing::warn!("save directory fingerprints failed, {}", err);
+    }
+}
+
+/**
+ * Keep the finished scan of `root` in line with the disk, each batch of
+ * changes is applied to the tree and reported with `tree-updated`. Ends
+ * with the watcher, which the next scan drops, and never keeps the session
+ * of a closed window alive.
+ */
+fn watch_tree<R: Runtime>(app: &AppHandle<R>, label: &str, session: &Arc<Session>, root: &Path) {
+    let mut changes = match session.scanner.watch(root) {
+        Ok(changes) => changes,
+        Err(err) => {
+            tracing::warn!(
+                "watch {} failed, sizes won't follow later changes, {}",
+                root.display(),
+                err
+            );
+            return;
+        }
+    };
+    let (app, label, session) = (app.clone(), label.to_string(), Arc::downgrade(session));
+    tokio::spawn(async move {
+        while let Some(batch) = changes.next_batch().await {
+            let Some(session) = session.upgrade() else {
+                break;
+            };
+            let update =
+                tokio::task::spawn_blocking(move || session.scanner.apply_changes(&batch)).await;
+            if let Ok(update) = update
+                && !update.is_empty()
+            {
+                let _ = events::emit_to(&app, &label, AppEvent::TreeUpdated(update));
+            }
+        }
+    });
+}
+
+/**
+ * feed the size and categories of a finished scan to the dashboard cache,
+ * and its largest directories to the metrics
+ */
+async fn record_dashboard<R: Runtime>(
+    app: &AppHandle<R>,
+    session: Arc<Session>,
+    root: PathBuf,
+    took: Duration,
+) {
+    let summary = tokio::task::spawn_blocking(move || {
+        session
+            .scanner
+            .with_tree(|tree| {
+                let scan = ScanMetrics::of(tree, &root, took);
+                (dashboard::summarize(tree, &root), scan)
+            })
+            .map(|summary| (root, summary))
+    })
+    .await;
+
+    if let Ok(Some((root, ((size, files, categories), scan)))) = summary
+        && files > 0
+    {
+        if let Some(scan) = scan {
+            app.state::<Metrics>().record_scan(root.clone(), scan);
+        }
+        let scan = app
+            .state::<DashboardCache>()
+            .record_scan(root, size, files, categories);
+        record_activity(
+            app,
+            ActivityKind::Scan,
+            Some(&scan.root),
+            &format!("scanned {}", scan.root.display()),
+            Some(scan.size as i64),
+            Some(scan.files as u64),
+        );
+        if let Some(previous) = scan.previous_size
+            && previous != scan.size
+        {
+            let growth = scan.size as i64 - previous as i64;
+            let trend = format!(
+                "{} {} by {} MB since its scan before",
+                scan.root.display(),
+                if growth > 0 { "grew" } else { "shrank" },
+                growth.unsigned_abs() / 1024 / 1024
+            );
+            record_activity(
+                app,
+                ActivityKind::SpaceTrend,
+                Some(&scan.root),
+                &trend,
+                Some(growth),
+                None,
+            );
+        }
+    }
+}
+
+/**
+ * add what just happened to the activity feed, a failure is only logged
+ */
+pub(crate) fn record_activity<R: Runtime>(
+    app: &AppHandle<R>,
+    kind: ActivityKind,
+    path: Option<&Path>,
+    summary: &str,
+    bytes: Option<i64>,
+    count: Option<u64>,
+) {
+    let recorded = app
+        .state::<ActivityFeed>()
+        .record(kind, path, summary, bytes, count);
+    if let Err(err) = recorded {
+        tracing::warn!("record activity failed, {}", err);
+    }
+}
+
+/**
+ * keep a finished cleanup in the activity feed and run the hooks set for it
+ */
+fn cleaned<R: Runtime>(app: &AppHandle<R>, summary: HookSummary) {
+    if let HookSummary::PostCleanup {
+        operation,
+        removed,
+        failed,
+        freed_bytes,
+        cancelled,
+    } = &summary
+    {
+        let mut text = format!("{} removed {} entries", operation, removed);
+        if *failed > 0 {
+            text.push_str(&format!(", {} failed", failed));
+        }
+        if *cancelled {
+            text.push_str(", cancelled");
+        }
+        record_activity(
+            app,
+            ActivityKind::Cleanup,
+            None,
+            &text,
+            Some(*freed_bytes as i64),
+            Some(*removed as u64),
+        );
+    }
+    let app = app.clone();
+    tokio::spawn(async move { run_hooks(&app, summary).await });
+}
+
+/**
+ * serve the metrics on `port` of the loopback interface, none when it is
+ * off or taken
+ */
+pub(crate) async fn export_metrics<R: Runtime>(
+    app: &AppHandle<R>,
+    port: Option<u16>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let port = port?;
+    let listener = match tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            tracing::warn!("serve metrics on port {} failed, {}", port, err);
+            return None;
+        }
+    };
+    tracing::info!("metrics served on http://127.0.0.1:{}/metrics", port);
+    let metrics = app.state::<Metrics>().inner().clone();
+    Some(tokio::spawn(metrics::serve(listener, metrics)))
+}
+
+/**
+ * run the hooks of the settings set for the event of `summary`, each run is
+ * kept in the audit log
+ */
+async fn run_hooks<R: Runtime>(app: &AppHandle<R>, summary: HookSummary) {
+    let settings = app.state::<SettingsStore>().current();
+    if !settings
+        .hooks
+        .iter()
+        .any(|hook| hook.event == summary.event())
+    {
+        return;
+    }
+    let audit = app.state::<AuditLog>().inner().clone();
+    let ran =
+        tokio::task::spawn_blocking(move || hooks::run_all(&settings.hooks, &summary, &audit))
+            .await;
+    if let Err(err) = ran {
+        tracing::warn!("run hooks failed, {}", err);
+    }
+}
+
+/**
+ * home screen payload, served from the cache so it never triggers a scan
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_dashboard(
+    cache: State<'_, DashboardCache>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Dashboard> {
+    requests
+        .run("get_dashboard", async { Ok(cache.snapshot()) })
+        .await
+}
+
+/**
+ * write the current scan with its reports into one file to review elsewhere
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn export_session<R: Runtime>(
+    path: PathArg,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<SessionInfo> {
+    requests
+        .run("export_session", async {
+            let session = sessions.get_or_create(window.label());
+            let path = path.into_path_buf()?;
+            let archive = session
+                .scanner
+                .export_archive()
+                .ok_or_else(|| Error::Scanner("scan tree unavailable".to_string()))?;
+
+            tokio::task::spawn_blocking(move || -> Result<SessionInfo> {
+                archive.write(&path)?;
+                Ok(archive.info(&path))
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))?
+        })
+        .await
+}
+
+/**
+ * load an exported session into this window in place of its scan
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn import_session<R: Runtime>(
+    path: PathArg,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<SessionInfo> {
+    requests
+        .run("import_session", async {
+            let session = sessions.get_or_create(window.label());
+            let _guard = session.guards.start_scan.try_enter()?;
+            let path = path.into_path_buf()?;
+
+            let (info, tree, slow_directories) =
+                tokio::task::spawn_blocking(move || -> Result<_> {
+                    let archive = SessionArchive::read(&path)?;
+                    let info = archive.info(&path);
+                    let tree = snapshot::rebuild(archive.entries)?;
+                    Ok((info, tree, archive.slow_directories))
+                })
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))??;
+
+            session
+                .scanner
+                .import_tree(info.clone(), tree, slow_directories)
+                .await;
+            Ok(info)
+        })
+        .await
+}
+
+fn scans_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf> {
+    Ok(fingerprints_dir(app)?.join("scans"))
+}
+
+/**
+ * keep the finished scan of this window in the app data dir, so the next
+ * start can restore it with `load_scan_snapshot` instead of scanning again.
+ * The last few scans of a root are kept for `diff_scans`.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn save_scan_snapshot<R: Runtime>(
+    app_handle: AppHandle<R>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<CachedScan> {
+    requests
+        .run("save_scan_snapshot", async {
+            let session = sessions.get_or_create(window.label());
+            let dir = scans_dir(&app_handle)?;
+            // flattening walks the whole tree under its lock
+            let scanned = session.clone();
+            let cache = tokio::task::spawn_blocking(move || scanned.scanner.cache())
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))?
+                .ok_or_else(|| Error::Scanner("no finished scan to save".to_string()))?;
+
+            tokio::task::spawn_blocking(move || -> Result<CachedScan> {
+                std::fs::create_dir_all(&dir)?;
+                cache.write(&cache.file_in(&dir))?;
+                ScanCache::prune(&dir, &cache.root, cache::KEEP_PER_ROOT);
+                Ok(cache.info())
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))?
+        })
+        .await
+}
+
+/**
+ * restore the last saved scan of `root` into this window in place of its scan,
+ * with `refresh` the directories changed since are listed again
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn load_scan_snapshot<R: Runtime>(
+    root: PathArg,
+    refresh: Option<bool>,
+    app_handle: AppHandle<R>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<CachedScan> {
+    requests
+        .run("load_scan_snapshot", async {
+            let session = sessions.get_or_create(window.label());
+            let _guard = session.guards.start_scan.try_enter()?;
+            let root = PathKey::resolve(&root.into_path_buf()?).into_path_buf();
+            let dir = scans_dir(&app_handle)?;
+
+            let wanted = root.clone();
+            let cache = tokio::task::spawn_blocking(move || {
+                ScanCache::saved(&dir, Some(&wanted))
+                    .first()
+                    .map(|(file, _)| ScanCache::read(file))
+                    .transpose()
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))??
+            .filter(|cache| cache.root == root)
+            .ok_or_else(|| Error::Scanner(format!("no saved scan of {}", root.display())))?;
+            let mut info = cache.info();
+            session.scanner.restore(cache).await?;
+
+            if refresh.unwrap_or(false) {
+                let refreshed = session.clone();
+                info.refreshed = tokio::task::spawn_blocking(move || refreshed.scanner.refresh())
+                    .await
+                    .map_err(|err| Error::Scanner(err.to_string()))?;
+            }
+            watch_tree(&app_handle, window.label(), &session, &root);
+            Ok(info)
+        })
+        .await
+}
+
+/**
+ * the saved scans, of `root` only when given, the newest first
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn list_scan_snapshots<R: Runtime>(
+    root: Option<PathArg>,
+    app_handle: AppHandle<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<CachedScan>> {
+    requests
+        .run("list_scan_snapshots", async {
+            let root = match root {
+                Some(root) => Some(PathKey::resolve(&root.into_path_buf()?).into_path_buf()),
+                None => None,
+            };
+            let dir = scans_dir(&app_handle)?;
+            tokio::task::spawn_blocking(move || {
+                ScanCache::saved(&dir, root.as_deref())
+                    .into_iter()
+                    .filter_map(|(file, _)| ScanCache::peek(&file).ok())
+                    .collect()
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))
+        })
+        .await
+}
+
+/**
+ * what changed between two saved scans of the same root: the directories
+ * which grew or shrank, the new files of at least `min_size` bytes, 100 MB
+ * by default, and what was deleted, the `limit` largest of each
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn diff_scans<R: Runtime>(
+    old_id: String,
+    new_id: String,
+    min_size: Option<u64>,
+    limit: Option<usize>,
+    app_handle: AppHandle<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<ScanDiff> {
+    requests
+        .run("diff_scans", async {
+            let dir = scans_dir(&app_handle)?;
+            let file = |id: &str| {
+                ScanCache::file_of(&dir, id)
+                    .ok_or_else(|| Error::Scanner(format!("invalid scan id {}", id)))
+            };
+            let (old_file, new_file) = (file(&old_id)?, file(&new_id)?);
+            tokio::task::spawn_blocking(move || -> Result<ScanDiff> {
+                let (old, new) = (ScanCache::read(&old_file)?, ScanCache::read(&new_file)?);
+                if old.root != new.root {
+                    return Err(Error::Scanner(format!(
+                        "{} and {} are scans of different folders",
+                        old.root.display(),
+                        new.root.display()
+                    )));
+                }
+                Ok(scan_diff::diff(
+                    &old.entries,
+                    &new.entries,
+                    min_size.map_or(scan_diff::DEFAULT_MIN_NEW_FILE_SIZE, |size| size as usize),
+                    limit.unwrap_or(100),
+                ))
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))?
+        })
+        .await
+}
+
+/**
+ * whether this window browses the live disk or an imported snapshot
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_scan_source<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<ScanSource> {
+    requests
+        .run("get_scan_source", async {
+            let session = sessions.get_or_create(window.label());
+            Ok(session.scanner.source())
+        })
+        .await
+}
+
+/**
+ * a path from the webview spelled the way the tree indexes it, so `/Users/me/`
+ * and `/Users/me` reach the same node. Symlinks are only resolved on the live
+ * disk, a snapshot may come from another machine
+ */
+fn tree_path(session: &Session, path: PathArg) -> Result<PathBuf> {
+    let path = path.into_path_buf()?;
+    let key = if session.scanner.is_snapshot() {
+        PathKey::new(&path)
+    } else {
+        PathKey::resolve(&path)
+    };
+    Ok(key.into_path_buf())
+}
+
+fn tree_paths(session: &Session, paths: Vec<PathArg>) -> Result<Vec<PathBuf>> {
+    paths
+        .into_iter()
+        .map(|path| tree_path(session, path))
+        .collect()
+}
+
+/**
+ * commands which scan or change the tree are refused on a snapshot session
+ */
+fn ensure_live(session: &Session, command: &'static str) -> Result<()> {
+    if session.scanner.is_snapshot() {
+        Err(Error::ReadOnly(command))
+    } else {
+        Ok(())
+    }
+}
+
+/**
+ * directories under `root` which changed since its last scan, decided from mtimes
+ * alone without loading the previous tree
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn check_freshness<R: Runtime>(
+    root: PathArg,
+    limit: Option<usize>,
+    app_handle: AppHandle<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Freshness> {
+    requests
+        .run("check_freshness", async {
+            let root = PathKey::resolve(&root.into_path_buf()?).into_path_buf();
+            let file = DirectoryFingerprints::file_for(&fingerprints_dir(&app_handle)?, &root);
+            tokio::task::spawn_blocking(move || -> Result<Freshness> {
+                let fingerprints = DirectoryFingerprints::load(&file)?;
+                Ok(fingerprint::check_freshness(
+                    &fingerprints,
+                    &root,
+                    limit.unwrap_or(100),
+                ))
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))?
+        })
+        .await
+}
+
+/**
+ * compact the hash index and prune old fingerprint files now instead of waiting for the scheduler
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn run_maintenance<R: Runtime>(
+    app_handle: AppHandle<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<MaintenanceReport> {
+    requests
+        .run("run_maintenance", async { maintain(&app_handle).await })
+        .await
+}
+
+/**
+ * the last maintenance run, none before the first one finished
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_maintenance_stats(
+    log: State<'_, MaintenanceLog>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Option<MaintenanceReport>> {
+    requests
+        .run("get_maintenance_stats", async { Ok(log.last()) })
+        .await
+}
+
+/**
+ * one maintenance run, shared by the command and the scheduler
+ */
+pub(crate) async fn maintain<R: Runtime>(app: &AppHandle<R>) -> Result<MaintenanceReport> {
+    let log = app.state::<MaintenanceLog>();
+    let _guard = log.guard.try_enter()?;
+
+    let index = app.state::<HashIndex>().inner().clone();
+    let thumbnails = app.state::<Thumbnailer>().inner().clone();
+    let data_dir = fingerprints_dir(app)?;
+    let report = tokio::task::spawn_blocking(move || {
+        maintenance::run(&index, &data_dir, &thumbnails, Retention::default())
+    })
+    .await
+    .map_err(|err| Error::Scanner(err.to_string()))?;
+
+    log.record(report.clone());
+    Ok(report)
+}
+
+/**
+ * when a scan worker counts as hung and whether the scan skips its directory
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_scan_watchdog<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Watchdog> {
+    requests
+        .run("get_scan_watchdog", async {
+            let session = sessions.get_or_create(window.label());
+            Ok(session.scanner.watchdog())
+        })
+        .await
+}
+
+/**
+ * change the watchdog settings, a running scan picks them up on its next check
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn set_scan_watchdog<R: Runtime>(
+    watchdog: Watchdog,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("set_scan_watchdog", async {
+            let session = sessions.get_or_create(window.label());
+            session.scanner.set_watchdog(watchdog);
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * how often a scan retries a directory after a transient error
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_retry_policy<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<RetryPolicy> {
+    requests
+        .run("get_retry_policy", async {
+            let session = sessions.get_or_create(window.label());
+            Ok(session.scanner.retry_policy())
+        })
+        .await
+}
+
+/**
+ * change the retry policy, a running scan uses it from the next directory on
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn set_retry_policy<R: Runtime>(
+    policy: RetryPolicy,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("set_retry_policy", async {
+            let session = sessions.get_or_create(window.label());
+            session.scanner.set_retry_policy(policy);
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * the workers, queue bound and entry rate the scans of the window run with
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_scanner_config<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<ScannerConfig> {
+    requests
+        .run("get_scanner_config", async {
+            let session = sessions.get_or_create(window.label());
+            Ok(session.scanner.config())
+        })
+        .await
+}
+
+/**
+ * Change how hard the scans of the window work the disk: `workers` listing
+ * at the same time, depth first past `max_queue_depth` waiting directories
+ * and at most `entries_per_sec` entries a second. A running scan takes the
+ * bound, the rate and fewer workers from its next directory, more workers
+ * start with the next scan. Returns the config as applied.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn configure_scanner<R: Runtime>(
+    workers: usize,
+    max_queue_depth: Option<usize>,
+    entries_per_sec: Option<u64>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<ScannerConfig> {
+    requests
+        .run("configure_scanner", async {
+            let session = sessions.get_or_create(window.label());
+            Ok(session.scanner.set_config(ScannerConfig {
+                workers,
+                max_queue_depth,
+                entries_per_sec,
+            }))
+        })
+        .await
+}
+
+/**
+ * what the scans of the window leave out
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_scan_filters<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<ScanFilters> {
+    requests
+        .run("get_scan_filters", async {
+            let session = sessions.get_or_create(window.label());
+            Ok(session.scanner.filters())
+        })
+        .await
+}
+
+/**
+ * Exclude rules for the scans of the window, globs matched against the whole
+ * path like `*.iso`, and the size below which files only count in their
+ * directory. They apply from the next listed directory on, an invalid glob is
+ * rejected and the rules in effect stay.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn set_scan_filters<R: Runtime>(
+    filters: ScanFilters,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("set_scan_filters", async {
+            let session = sessions.get_or_create(window.label());
+            session.scanner.set_filters(filters)?;
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * timing of a recent command by the `requestId` it answered or failed with,
+ * to tie a frontend bug report to the backend log
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_request_trace(
+    id: u64,
+    requests: State<'_, Requests>,
+) -> CommandResult<Option<RequestTrace>> {
+    requests
+        .run("get_request_trace", async { Ok(requests.trace(id)) })
+        .await
+}
+
+/**
+ * preview a cleanup: what it would free per volume, which directories would shrink,
+ * how the largest files would change and how long it would take, without touching the disk
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn simulate_cleanup<R: Runtime>(
+    plan: CleanupPlan,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    meter: State<'_, ThroughputMeter>,
+    requests: State<'_, Requests>,
+) -> CommandResult<CleanupSimulation> {
+    requests
+        .run("simulate_cleanup", async {
+            let session = sessions.get_or_create(window.label());
+            let paths = tree_paths(&session, plan.paths)?;
+            let root = match plan.root {
+                Some(root) => tree_path(&session, root)?,
+                None => session
+                    .scanner
+                    .with_tree(|tree| tree.root_path())
+                    .flatten()
+                    .unwrap_or_else(|| PathBuf::from("/")),
+            };
+
+            let volumes = request::step(
+                "list_volumes",
+                tokio::task::spawn_blocking(driver::list_volumes),
+            )
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))?;
+            let throughput = meter.current();
+            Ok(session
+                .scanner
+                .with_store(|store| {
+                    simulation::simulate(store, &root, &paths, &volumes, &throughput)
+                })
+                .unwrap_or_default())
+        })
+        .await
+}
+
+/**
+ * the settings in effect, including edits made to the file by hand
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_settings(
+    settings: State<'_, SettingsStore>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Settings> {
+    requests
+        .run("get_settings", async { Ok((*settings.current()).clone()) })
+        .await
+}
+
+/**
+ * save the settings, every window gets them with `settings-changed` and the
+ * scanner, protected paths and scheduler apply them without a restart
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn set_settings<R: Runtime>(
+    settings: Settings,
+    app: AppHandle<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("set_settings", async {
+            tokio::task::spawn_blocking(move || app.state::<SettingsStore>().save(settings))
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))??;
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * stop the running cleanup or batch deletion of this window, the entries
+ * removed so far stay removed and whatever is left is reported by the
+ * command which started it
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn cancel_cleanup<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("cancel_cleanup", async {
+            if let Some(session) = sessions.get(window.label()) {
+                session.cleanup_cancelled.store(true, Ordering::Relaxed);
+            }
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * Delete `path`, and when a plain deletion leaves entries behind, reset their
+ * owner and permissions recursively and try again. Opt-in only, the changes
+ * are permanent on whatever still can't be removed, so the repair lists every
+ * entry which needed fixing along with the warnings to show the user
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn fix_permissions_then_delete<R: Runtime>(
+    path: PathArg,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<PermissionRepair> {
+    requests
+        .run("fix_permissions_then_delete", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "fix_permissions_then_delete")?;
+            let _guard = session.guards.cleanup.try_enter()?;
+            let path = tree_path(&session, path)?;
+            if targets::is_protected(&path) {
+                return Err(Error::Scanner(format!(
+                    "{} is a protected system location",
+                    path.display()
+                )));
+            }
+
+            session.cleanup_cancelled.store(false, Ordering::Relaxed);
+            let (app, label) = (window.app_handle().clone(), window.label().to_string());
+            let deleting = session.clone();
+            let removal = tokio::task::spawn_blocking(move || {
+                repair::repair_and_delete(&path, &deleting.cleanup_cancelled, &mut |progress| {
+                    let event = AppEvent::CleanupProgress(progress.clone());
+                    let _ = events::emit_to(&app, &label, event);
+                })
+            });
+            let repair = request::step("repair", removal)
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))?;
+
+            if repair.deletion.removed {
+                session.scanner.forget(&repair.path);
+            }
+            Ok(repair)
+        })
+        .await
+}
+
+/**
+ * drop the quarantine of a downloaded file the user keeps, so it opens without
+ * the downloaded from the internet warning. False when it carried none
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn strip_quarantine<R: Runtime>(
+    path: PathArg,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<bool> {
+    requests
+        .run("strip_quarantine", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "strip_quarantine")?;
+            let path = tree_path(&session, path)?;
+            Ok(quarantine::strip(&path)?)
+        })
+        .await
+}
+
+/**
+ * pin a color and an emoji on a folder, kept across sessions and returned with
+ * its details. An empty label removes it
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn set_folder_label(
+    path: PathArg,
+    label: FolderLabel,
+    labels: State<'_, LabelStore>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("set_folder_label", async {
+            let path = path.into_path_buf()?;
+            Ok(labels.set(&path, &label)?)
+        })
+        .await
+}
+
+/**
+ * every labeled folder, the most recently changed first
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn list_folder_labels(
+    labels: State<'_, LabelStore>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<LabeledFolder>> {
+    requests
+        .run("list_folder_labels", async { Ok(labels.list()?) })
+        .await
+}
+
+/**
+ * triage `path` into the queue of `state`, none takes it out of every queue.
+ * A kept item has to go back to review before it can be deleted later
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn set_review_state<R: Runtime>(
+    path: PathArg,
+    state: Option<ReviewState>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    review: State<'_, ReviewStore>,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("set_review_state", async {
+            let session = sessions.get_or_create(window.label());
+            let path = tree_path(&session, path)?;
+            match state {
+                Some(state) => {
+                    let size = session.scanner.get_size(&path).map(|size| size as u64);
+                    review.set(&path, state, size)?;
+                }
+                None => {
+                    review.remove(&path)?;
+                }
+            }
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * every review queue with its item count and bytes, the largest items first
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_review_queues(
+    review: State<'_, ReviewStore>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<ReviewQueue>> {
+    requests
+        .run("get_review_queues", async { Ok(review.queues()?) })
+        .await
+}
+
+/**
+ * Delete everything in the delete-later queue the way `run_cleanup` does.
+ * A folder holding an item of another queue is reported as failed and left
+ * in place. Removed items leave the queue, failed ones stay for another try
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn execute_delete_later<R: Runtime>(
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    review: State<'_, ReviewStore>,
+    requests: State<'_, Requests>,
+) -> CommandResult<CleanupReport> {
+    requests
+        .run("execute_delete_later", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "execute_delete_later")?;
+            let _guard = session.guards.cleanup.try_enter()?;
+            let plan = review.delete_plan()?;
+
+            let mut report = clean(
+                &session,
+                &window,
+                plan.paths,
+                false,
+                false,
+                "execute_delete_later",
+            )
+            .await?;
+            for (path, spared) in plan.blocked {
+                report.failed.push(CleanupFailure {
+                    path,
+                    reason: format!(
+                        "holds {}, which is marked {}",
+                        spared.path.display(),
+                        spared.state.name()
+                    ),
+                });
+            }
+            review.forget_removed(&report.removed)?;
+            Ok(report)
+        })
+        .await
+}
+
+/**
+ * the path bar of `path` with the names Finder or Explorer show, starting at
+ * the innermost well-known location above it, e.g. iCloud Drive
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_breadcrumbs(
+    path: PathArg,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<Breadcrumb>> {
+    requests
+        .run("get_breadcrumbs", async {
+            Ok(display_name::breadcrumbs(&path.into_path_buf()?))
+        })
+        .await
+}
+
+/**
+ * which icon to draw for `path`, see `get_icon_hints` for a whole listing
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_icon_hint(
+    path: PathArg,
+    icons: State<'_, IconCache>,
+    requests: State<'_, Requests>,
+) -> CommandResult<IconHint> {
+    requests
+        .run("get_icon_hint", async {
+            let path = path.into_path_buf()?;
+            let icons = icons.inner().clone();
+            tokio::task::spawn_blocking(move || -> Result<IconHint> {
+                let metadata = std::fs::symlink_metadata(long_path::extended(&path))?;
+                Ok(icons.hint(&path, &metadata))
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))?
+        })
+        .await
+}
+
+/**
+ * the icon hints of many entries in their order, none for an entry which is
+ * gone. Entries of one type share a single platform lookup
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_icon_hints(
+    paths: Vec<PathArg>,
+    icons: State<'_, IconCache>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<Option<IconHint>>> {
+    requests
+        .run("get_icon_hints", async {
+            let paths = paths
+                .into_iter()
+                .map(PathArg::into_path_buf)
+                .collect::<std::io::Result<Vec<_>>>()?;
+            let icons = icons.inner().clone();
+            tokio::task::spawn_blocking(move || -> Vec<Option<IconHint>> {
+                paths
+                    .iter()
+                    .map(|path| {
+                        let metadata = std::fs::symlink_metadata(long_path::extended(path)).ok()?;
+                        Some(icons.hint(path, &metadata))
+                    })
+                    .collect()
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))
+        })
+        .await
+}
+
+/**
+ * a thumbnail of an image, a video or a PDF fitting in `size` pixels, read
+ * from the disk cache once it was rendered
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_thumbnail(
+    path: PathArg,
+    size: u32,
+    thumbnails: State<'_, Thumbnailer>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Thumbnail> {
+    requests
+        .run("get_thumbnail", async {
+            let path = path.into_path_buf()?;
+            let thumbnails = thumbnails.inner().clone();
+            let thumbnail = request::step(
+                "render",
+                tokio::task::spawn_blocking(move || thumbnails.get(&path, size)),
+            )
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))??;
+            Ok(thumbnail)
+        })
+        .await
+}
+
+/**
+ * compare two live folders, e.g. a folder and its backup, by relative path and
+ * size, and by content hash of the files of the same size with `compare_content`
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn diff_paths(
+    a: PathArg,
+    b: PathArg,
+    compare_content: Option<bool>,
+    requests: State<'_, Requests>,
+) -> CommandResult<FolderDiff> {
+    requests
+        .run("diff_paths", async {
+            let (a, b) = (a.into_path_buf()?, b.into_path_buf()?);
+            let compare_content = compare_content.unwrap_or(false);
+            let diff = request::step(
+                "compare",
+                tokio::task::spawn_blocking(move || compare::diff_paths(&a, &b, compare_content)),
+            )
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))??;
+            Ok(diff)
+        })
+        .await
+}
+
+/**
+ * Where each of `sources` goes when it is backed up below `destination`,
+ * following `template`, the `backup_template` of the settings by default.
+ * Targets which exist already, or which an earlier source got, are
+ * numbered, with `archive` they are zip archives. They are the `to` of the
+ * copy or compress steps of `run_operation`, nothing is created yet.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn backup_targets<R: Runtime>(
+    destination: PathArg,
+    sources: Vec<PathArg>,
+    template: Option<String>,
+    archive: bool,
+    app: AppHandle<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<BackupTarget>> {
+    requests
+        .run("backup_targets", async {
+            let destination = destination.into_path_buf()?;
+            let sources = lossless::into_paths(sources)?;
+            let template = template
+                .or_else(|| {
+                    app.state::<SettingsStore>()
+                        .current()
+                        .backup_template
+                        .clone()
+                })
+                .unwrap_or_else(|| backup_target::DEFAULT_TEMPLATE.to_string());
+            let targets = tokio::task::spawn_blocking(move || {
+                backup_target::resolve(&destination, &template, &sources, archive, now_secs())
+            })
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))??;
+            Ok(targets.into_iter().map(BackupTarget::from).collect())
+        })
+        .await
+}
+
+/**
+ * Run a composite operation like backup-then-clean as one transaction. A
+ * failing step undoes the ones before it, deletes only become final once
+ * every step succeeded. The outcome is kept in the audit log and the
+ * activity feed. Copies and archives go no faster than
+ * `io_limit_mb_per_sec`, the one of the settings by default. Rejected with
+ * `insufficientSpace` before any step starts when a destination has no
+ * room for what the steps write. Every path is resolved like the tree
+ * keys it before the protected locations are checked.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn run_operation<R: Runtime>(
+    operation: String,
+    steps: Vec<StepArg>,
+    io_limit_mb_per_sec: Option<u64>,
+    audit: State<'_, AuditLog>,
+    sessions: State<'_, Sessions>,
+    window: WebviewWindow<R>,
+    requests: State<'_, Requests>,
+) -> CommandResult<TransactionOutcome> {
+    requests
+        .run("run_operation", async {
+            let session = sessions.get_or_create(window.label());
+            ensure_live(&session, "run_operation")?;
+            let _guard = session.guards.cleanup.try_enter()?;
+            // resolved first, `..`, a linked parent or another case can't hide a protected path
+            let steps = steps
+                .into_iter()
+                .map(|step| step.into_step(|path| tree_path(&session, path)))
+                .collect::<Result<Vec<Step>>>()?;
+            for step in &steps {
+                let removed = match step {
+                    Step::Move { from, .. } => Some(from),
+                    Step::Delete { path } => Some(path),
+                    Step::Copy { .. } | Step::Compress { .. } => None,
+                };
+                if let Some(path) = removed.filter(|path| targets::is_protected(path)) {
+                    return Err(Error::Scanner(format!(
+                        "{} is a protected system location",
+                        path.display()
+                    )));
+                }
+            }
+
+            let audit = audit.inner().clone();
+            let mut throttle = throttle(window.app_handle(), io_limit_mb_per_sec);
+            let name = operation.clone();
+            let outcome = request::step(
+                "transaction",
+                tokio::task::spawn_blocking(move || {
+                    transaction::run(&name, &steps, &audit, &mut throttle)
+                }),
+            )
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))?;
+            if let Some(short) = outcome.insufficient_space {
+                return Err(Error::InsufficientSpace(short));
+            }
+
+            // whatever stayed moved or deleted is gone from the scanned tree
+            for entry in &outcome.journal {
+                match (&entry.step, entry.state) {
+                    (Step::Move { from: path, .. } | Step::Delete { path }, StepState::Done) => {
+                        session.scanner.forget(path);
+                    }
+                    _ => {}
+                }
+            }
+            let done = outcome
+                .journal
+                .iter()
+                .filter(|entry| entry.state == StepState::Done)
+                .count();
+            record_activity(
+                window.app_handle(),
+                ActivityKind::Cleanup,
+                None,
+                &format!("{} {}", operation, outcome.state.name()),
+                None,
+                Some(done as u64),
+            );
+            Ok(outcome)
+        })
+        .await
+}
+
+/**
+ * the last operations which changed files, the latest first
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_audit_log(
+    limit: Option<usize>,
+    audit: State<'_, AuditLog>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<AuditEntry>> {
+    requests
+        .run("get_audit_log", async {
+            Ok(audit.recent(limit.unwrap_or(100))?)
+        })
+        .await
+}
+
+/**
+ * One page of the history of scans, cleanups, alerts and space trends, the
+ * latest first. `limit` is 50 by default, `kinds` leaves out the others.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_activity_feed(
+    offset: Option<usize>,
+    limit: Option<usize>,
+    kinds: Option<Vec<ActivityKind>>,
+    feed: State<'_, ActivityFeed>,
+    requests: State<'_, Requests>,
+) -> CommandResult<ActivityPage> {
+    requests
+        .run("get_activity_feed", async {
+            Ok(feed.page(
+                offset.unwrap_or(0),
+                limit.unwrap_or(50),
+                &kinds.unwrap_or_default(),
+            )?)
+        })
+        .await
+}
+
+/**
+ * Compile the summary of the last week which is over, unless it was already.
+ * The windows get it as an event when something happened that week and the
+ * settings ask for it. Returns the summary when it was compiled now.
+ */
+pub(crate) async fn summarize_week<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<Option<WeeklySummary>> {
+    let summaries = app.state::<WeeklySummaries>().inner().clone();
+    let feed = app.state::<ActivityFeed>().inner().clone();
+    let review = app.state::<ReviewStore>().inner().clone();
+    let summary = tokio::task::spawn_blocking(move || -> std::io::Result<Option<WeeklySummary>> {
+        let now = now_secs();
+        let week = weekly::last_week(now);
+        if summaries.get(week)?.is_some() {
+            return Ok(None);
+        }
+        let activity = feed.between(
+            week,
+            week + weekly::WEEK_SECS,
+            &[ActivityKind::Cleanup, ActivityKind::SpaceTrend],
+        )?;
+        let previous = summaries.get(week.saturating_sub(weekly::WEEK_SECS))?;
+        let summary = weekly::compile(week, &activity, previous.as_ref(), &review.queues()?, now);
+        summaries.store(&summary)?;
+        Ok(Some(summary))
+    })
+    .await
+    .map_err(|err| Error::Scanner(err.to_string()))??;
+
+    if let Some(summary) = &summary
+        && !summary.is_quiet()
+        && app
+            .state::<SettingsStore>()
+            .current()
+            .weekly_summary_notification
+        && let Err(err) = events::emit_all(app, AppEvent::WeeklySummary(summary.clone()))
+    {
+        tracing::warn!("weekly summary not sent, {}", err);
+    }
+    Ok(summary)
+}
+
+/**
+ * The weekly summaries of space freed and grown, new hotspots and pending
+ * recommendations, the latest first. `limit` is a year of weeks by default.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_weekly_summaries(
+    limit: Option<usize>,
+    summaries: State<'_, WeeklySummaries>,
+    requests: State<'_, Requests>,
+) -> CommandResult<Vec<WeeklySummary>> {
+    requests
+        .run("get_weekly_summaries", async {
+            Ok(summaries.latest(limit.unwrap_or(weekly::KEEP_SUMMARIES as usize))?)
+        })
+        .await
+}
+
+/**
+ * The processes which wrote the most to disk during the next `window_millis`,
+ * two seconds by default, to tell who fills a disk up while it happens.
+ */
+#[command]
+#[specta::specta]
+pub(crate) async fn get_top_disk_writers(
+    window_millis: Option<u64>,
+    limit: Option<usize>,
+    requests: State<'_, Requests>,
+) -> CommandResult<DiskWriters> {
+    requests
+        .run("get_top_disk_writers", async {
+            let window = Duration::from_millis(window_millis.unwrap_or(2000));
+            let writers = request::step(
+                "sample",
+                tokio::task::spawn_blocking(move || {
+                    interference::top_disk_writers(window, limit.unwrap_or(10))
+                }),
+            )
+            .await
+            .map_err(|err| Error::Scanner(err.to_string()))?;
+            Ok(writers)
+        })
+        .await
+}
+
+/**
+ * Cancel the scans reading a volume which disappeared, e.g. an unplugged
+ * USB drive, instead of letting the workers spin on I/O errors. What was
+ * scanned so far is saved as a snapshot which `import_session` can open.
+ */
+pub(crate) async fn check_removed_volumes<R: Runtime>(app: &AppHandle<R>) {
+    let mut watched = Vec::new();
+    for (label, session) in app.state::<Sessions>().all() {
+        let volume = session.volume.lock().ok().and_then(|volume| volume.clone());
+        if let Some(volume) = volume
+            && session
+                .scanner
+                .get_progress()
+                .await
+                .is_ok_and(|progress| progress.is_scanning)
+        {
+            watched.push((label, session, volume));
+        }
+    }
+    if watched.is_empty() {
+        return;
+    }
+
+    let Ok(volumes) = tokio::task::spawn_blocking(driver::list_volumes).await else {
+        return;
+    };
+    for (label, session, volume) in watched {
+        if driver::is_removed(&volume, &volumes) {
+            abort_scan(app, &label, &session, volume).await;
+        }
+    }
+}
+
+async fn abort_scan<R: Runtime>(
+    app: &AppHandle<R>,
+    label: &str,
+    session: &Session,
+    volume: PathBuf,
+) {
+    tracing::warn!(
+        "volume {} removed, aborting the scan of {}",
+        volume.display(),
+        label
+    );
+    session.aborted.store(true, Ordering::Relaxed);
+    let _ = session.volume.lock().map(|mut volume| volume.take());
+    session.scanner.stop_scanning().await;
+
+    let archive = session.scanner.export_archive();
+    let file = fingerprints_dir(app).map(|dir| {
+        dir.join("partial")
+            .join(format!("{}-{}.json", label, now_secs()))
+    });
+    let snapshot = match (archive, file) {
+        (Some(archive), Ok(file)) => tokio::task::spawn_blocking(move || {
+            if let Some(dir) = file.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            archive.write(&file)?;
+            Ok::<_, std::io::Error>(archive.info(&file))
+        })
+        .await
+        .map_err(std::io::Error::other)
+        .and_then(|saved| saved)
+        .inspect_err(|err| tracing::warn!("save partial scan failed, {}", err))
+        .ok(),
+        _ => None,
+    };
+
+    let _ = events::emit_to(
+        app,
+        label,
+        AppEvent::ScanAborted(ScanAborted {
+            reason: AbortReason::VolumeRemoved,
+            volume,
+            snapshot,
+        }),
+    );
 }
@@ -0,0 +1,115 @@
+use std::{
+    collections::HashMap,
+    io,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, SyncSender},
+    },
+    time::Duration,
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::mover::{Conflict, ConflictChoice};
+
+/**
+ * how long a move or copy waits for the answer to a conflict, a window
+ * closed meanwhile never answers
+ */
+pub const ANSWER_TIMEOUT: Duration = Duration::from_secs(600);
+
+/**
+ * a conflict of a running move or copy, answered with `resolve_conflict`
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingConflict {
+    pub operation_id: u64,
+    #[serde(flatten)]
+    pub conflict: Conflict,
+}
+
+/**
+ * The moves and copies paused on a conflict, each waiting for the answer
+ * of the user.
+ */
+#[derive(Default)]
+pub struct Conflicts {
+    next: AtomicU64,
+    waiting: Mutex<HashMap<u64, SyncSender<(ConflictChoice, bool)>>>,
+}
+
+impl Conflicts {
+    /**
+     * the id of a new operation, its conflicts carry it
+     */
+    pub fn begin(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /**
+     * Announce a conflict of `operation` with `announce`, then wait for the
+     * answer, or for `ANSWER_TIMEOUT`. Blocking.
+     */
+    pub fn wait(
+        &self,
+        operation: u64,
+        announce: impl FnOnce(),
+    ) -> io::Result<(ConflictChoice, bool)> {
+        let (tx, rx) = mpsc::sync_channel(1);
+        self.waiting
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(operation, tx);
+        announce();
+        let answer = rx.recv_timeout(ANSWER_TIMEOUT);
+        self.waiting
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&operation);
+        answer.map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the conflict was not answered, the operation stopped",
+            )
+        })
+    }
+
+    /**
+     * answer the conflict `operation` waits on, false when it waits on none
+     */
+    pub fn resolve(&self, operation: u64, choice: ConflictChoice, apply_to_all: bool) -> bool {
+        let waiting = self
+            .waiting
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&operation);
+        waiting.is_some_and(|tx| tx.send((choice, apply_to_all)).is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, thread};
+
+    #[test]
+    fn test_answer_reaches_waiting_operation() {
+        let conflicts = Arc::new(Conflicts::default());
+        let operation = conflicts.begin();
+        assert!(!conflicts.resolve(operation, ConflictChoice::Skip, false));
+
+        let (announced, asked) = mpsc::channel();
+        let waiting = conflicts.clone();
+        let answer = thread::spawn(move || waiting.wait(operation, || announced.send(()).unwrap()));
+        asked.recv().unwrap();
+        assert!(!conflicts.resolve(operation + 1, ConflictChoice::Skip, false));
+        assert!(conflicts.resolve(operation, ConflictChoice::Rename, true));
+        assert_eq!(
+            answer.join().unwrap().unwrap(),
+            (ConflictChoice::Rename, true)
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::clipboard::format_size;
+
+/**
+ * a run shorter than this says more about caches than about the device
+ */
+const MIN_SAMPLE: Duration = Duration::from_secs(1);
+
+/**
+ * rates assumed until the device was measured, a mid-range SSD
+ */
+const DEFAULT_DELETES_PER_SEC: f64 = 2_000.0;
+const DEFAULT_READ_BYTES_PER_SEC: f64 = 200.0 * 1024.0 * 1024.0;
+
+/**
+ * what one core compresses per second, compression waits on the CPU before the disk
+ */
+const COMPRESS_BYTES_PER_SEC: f64 = 100.0 * 1024.0 * 1024.0;
+
+/**
+ * Extra power drawn while an operation runs, a laptop busy with the disk
+ * and one core. Only meant to compare operations, nothing is measured.
+ */
+const DELETE_WATTS: f64 = 4.0;
+const HASH_WATTS: f64 = 8.0;
+const COMPRESS_WATTS: f64 = 15.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum Operation {
+    Cleanup,
+    Compress,
+    Hash,
+}
+
+impl Operation {
+    fn verb(self) -> &'static str {
+        match self {
+            Operation::Cleanup => "deleting",
+            Operation::Compress => "compressing",
+            Operation::Hash => "hashing",
+        }
+    }
+
+    fn watts(self) -> f64 {
+        match self {
+            Operation::Cleanup => DELETE_WATTS,
+            Operation::Compress => COMPRESS_WATTS,
+            Operation::Hash => HASH_WATTS,
+        }
+    }
+}
+
+/**
+ * how long an operation would take on this device, so the user can decide
+ * to run it now or later
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationEstimate {
+    pub operation: Operation,
+    pub bytes: u64,
+    /// entries the operation goes through, only counted for a cleanup
+    pub entries: u64,
+    pub seconds: u64,
+    /// rough watt hours drawn on top of idle
+    pub energy_wh: f64,
+    /// false while the rate is a default, nothing like it ran yet
+    pub measured: bool,
+    /// e.g. "compressing 80.00 GB ≈ 14 min"
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Throughput {
+    /// entries listed per second by the last scans
+    pub scanned_per_sec: Option<f64>,
+    /// entries removed per second by the last cleanups
+    pub deleted_per_sec: Option<f64>,
+    /// bytes read per second by the last hashes
+    pub read_bytes_per_sec: Option<f64>,
+}
+
+impl Throughput {
+    /**
+     * Time `operation` would take over `bytes` in `entries` entries. A cleanup
+     * is bound by the entries, the others by the bytes.
+     */
+    pub fn estimate(&self, operation: Operation, bytes: u64, entries: u64) -> OperationEstimate {
+        // removing an entry costs about as much as listing it
+        let deleted = self.deleted_per_sec.or(self.scanned_per_sec);
+        let (rate, measured) = match operation {
+            Operation::Cleanup => (
+                deleted.unwrap_or(DEFAULT_DELETES_PER_SEC),
+                deleted.is_some(),
+            ),
+            Operation::Hash => (
+                self.read_bytes_per_sec
+                    .unwrap_or(DEFAULT_READ_BYTES_PER_SEC),
+                self.read_bytes_per_sec.is_some(),
+            ),
+            Operation::Compress => (
+                self.read_bytes_per_sec
+                    .unwrap_or(DEFAULT_READ_BYTES_PER_SEC)
+                    .min(COMPRESS_BYTES_PER_SEC),
+                self.read_bytes_per_sec.is_some(),
+            ),
+        };
+        let work = match operation {
+            Operation::Cleanup => entries,
+            Operation::Compress | Operation::Hash => bytes,
+        };
+        let seconds = (work as f64 / rate).ceil() as u64;
+
+        OperationEstimate {
+            operation,
+            bytes,
+            entries,
+            seconds,
+            energy_wh: operation.watts() * seconds as f64 / 3600.0,
+            measured,
+            summary: format!(
+                "{} {} ≈ {}",
+                operation.verb(),
+                format_size(bytes as usize),
+                format_duration(seconds)
+            ),
+        }
+    }
+}
+
+/**
+ * The rates measured on this device while the app runs. Every sample is
+ * averaged with the previous ones, so one slow run doesn't swing the estimates.
+ */
+#[derive(Debug, Default)]
+pub struct ThroughputMeter {
+    current: Mutex<Throughput>,
+}
+
+impl ThroughputMeter {
+    pub fn current(&self) -> Throughput {
+        *self.current.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn record_scan(&self, entries: u64, elapsed: Duration) {
+        self.record(entries, elapsed, |throughput| {
+            &mut throughput.scanned_per_sec
+        });
+    }
+
+    pub fn record_deletion(&self, entries: u64, elapsed: Duration) {
+        self.record(entries, elapsed, |throughput| {
+            &mut throughput.deleted_per_sec
+        });
+    }
+
+    pub fn record_read(&self, bytes: u64, elapsed: Duration) {
+        self.record(bytes, elapsed, |throughput| {
+            &mut throughput.read_bytes_per_sec
+        });
+    }
+
+    fn record(&self, units: u64, elapsed: Duration, rate: fn(&mut Throughput) -> &mut Option<f64>) {
+        if units == 0 || elapsed < MIN_SAMPLE {
+            return;
+        }
+        let sample = units as f64 / elapsed.as_secs_f64();
+        let mut current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        let rate = rate(&mut current);
+        *rate = Some(rate.map_or(sample, |previous| (previous + sample) / 2.0));
+    }
+}
+
+fn format_duration(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{} s", seconds.max(1)),
+        60..3600 => format!("{} min", seconds.div_ceil(60)),
+        _ => {
+            let minutes = seconds.div_ceil(60);
+            format!("{} h {} min", minutes / 60, minutes % 60)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn test_compress_bound_by_cpu() {
+        let throughput = Throughput {
+            read_bytes_per_sec: Some(1_000.0 * 1024.0 * 1024.0),
+            ..Throughput::default()
+        };
+        let estimate = throughput.estimate(Operation::Compress, 80 * GB, 1);
+        assert_eq!(estimate.seconds, 820);
+        assert!(estimate.measured);
+        assert_eq!(estimate.summary, "compressing 80.00 GB ≈ 14 min");
+    }
+
+    #[test]
+    fn test_cleanup_falls_back_to_scan_rate() {
+        let meter = ThroughputMeter::default();
+        let estimate = meter.current().estimate(Operation::Cleanup, GB, 10_000);
+        assert!(!estimate.measured);
+        assert_eq!(estimate.seconds, 5);
+
+        meter.record_scan(50_000, Duration::from_secs(10));
+        // too short to tell
+        meter.record_deletion(1_000, Duration::from_millis(10));
+        let estimate = meter.current().estimate(Operation::Cleanup, GB, 10_000);
+        assert!(estimate.measured);
+        assert_eq!(estimate.seconds, 2);
+
+        meter.record_deletion(3_000, Duration::from_secs(1));
+        meter.record_deletion(1_000, Duration::from_secs(1));
+        assert_eq!(meter.current().deleted_per_sec, Some(2_000.0));
+    }
+
+    #[test]
+    fn test_durations_rounded_up() {
+        assert_eq!(format_duration(0), "1 s");
+        assert_eq!(format_duration(61), "2 min");
+        assert_eq!(format_duration(2 * 3600 + 5 * 60), "2 h 5 min");
+    }
+}
@@ -0,0 +1,176 @@
+use std::{path::Path, time::Duration};
+
+use crate::{
+    CommandResult, Error, capabilities, display_name, inodes,
+    lossless::PathArg,
+    models::Volumn,
+    request::{self, Requests},
+    shadow,
+};
+use sysinfo::{Disks, System};
+use tauri::{State, command};
+use tracing::debug;
+
+#[command]
+#[specta::specta]
+/**
+ * Get the list of drivers provided by the operation
+ */
+pub async fn get_available_drivers(requests: State<'_, Requests>) -> CommandResult<Vec<Volumn>> {
+    requests
+        .run("get_available_drivers", async {
+            // Please note that we use "new_all" to ensure that all lists of
+            // CPUs and processes are filled!
+            let mut sys = System::new_all();
+
+            // First we update all information of our `System` struct.
+            sys.refresh_all();
+
+            debug!("=> system:");
+            // RAM and swap information:
+            debug!("total memory: {} bytes", sys.total_memory());
+            debug!("used memory : {} bytes", sys.used_memory());
+            debug!("total swap  : {} bytes", sys.total_swap());
+            debug!("used swap   : {} bytes", sys.used_swap());
+
+            // Display system information:
+            debug!("System name:             {:?}", System::name());
+            debug!("System kernel version:   {:?}", System::kernel_version());
+            debug!("System OS version:       {:?}", System::os_version());
+            debug!("System host name:        {:?}", System::host_name());
+
+            // the user asked for the list, a look at each mount point is affordable here
+            let probed = tokio::task::spawn_blocking(|| {
+                let mut volumes = list_volumes();
+                let shadow_storage = shadow::measure_all().unwrap_or_else(|err| {
+                    debug!("shadow storage unavailable: {}", err);
+                    Default::default()
+                });
+                for volume in volumes.iter_mut() {
+                    volume.capabilities = Some(capabilities::probe(volume));
+                    volume.shadow_storage = shadow_storage.get(&volume.path).copied();
+                }
+                volumes
+            });
+            request::step("probe_volumes", probed)
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))
+        })
+        .await
+}
+
+/**
+ * Limit the space Windows keeps for restore points on `volume` to `max_size`
+ * bytes, the oldest points are dropped to fit. Asks for an elevated app.
+ */
+#[command]
+#[specta::specta]
+pub async fn resize_shadow_storage(
+    volume: PathArg,
+    max_size: u64,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("resize_shadow_storage", async {
+            let volume = volume.into_path_buf()?;
+            let resized = tokio::task::spawn_blocking(move || shadow::resize(&volume, max_size));
+            request::step("vssadmin", resized)
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))??;
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * delete the oldest restore point of `volume`, asks for an elevated app
+ */
+#[command]
+#[specta::specta]
+pub async fn delete_oldest_restore_point(
+    volume: PathArg,
+    requests: State<'_, Requests>,
+) -> CommandResult<()> {
+    requests
+        .run("delete_oldest_restore_point", async {
+            let volume = volume.into_path_buf()?;
+            let deleted = tokio::task::spawn_blocking(move || shadow::delete_oldest(&volume));
+            request::step("vssadmin", deleted)
+                .await
+                .map_err(|err| Error::Scanner(err.to_string()))??;
+            Ok(())
+        })
+        .await
+}
+
+/**
+ * mounted volumes with their capacity and inodes, cheap enough for a periodic refresh
+ */
+pub fn list_volumes() -> Vec<Volumn> {
+    // We display all disks' information:
+    let disks = Disks::new_with_refreshed_list();
+    debug!("System disks size: {:?}", disks.list().len());
+    let mut volumns: Vec<Volumn> = vec![];
+    for disk in &disks {
+        let full_path = disk.mount_point();
+
+        let name = disk.name().to_string_lossy().into_owned();
+        let volumn = Volumn {
+            display_name: display_name::of_volume(&name, full_path),
+            name,
+            path: full_path.to_path_buf(),
+            total_size: disk.total_space(),
+            available_size: disk.available_space(),
+            file_system: disk.file_system().to_string_lossy().into_owned(),
+            capabilities: None,
+            shadow_storage: None,
+            inodes: inodes::usage(full_path),
+        };
+
+        debug!("full path {:?}, info:{:?}", full_path, volumn);
+
+        volumns.push(volumn);
+    }
+    volumns
+}
+
+/**
+ * how often the drive monitor looks for volumes removed under a running scan
+ */
+pub const MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+
+/**
+ * whether the volume mounted at `mount_point` is gone, an empty list says
+ * more about the listing than about the drive
+ */
+pub fn is_removed(mount_point: &Path, volumes: &[Volumn]) -> bool {
+    !volumes.is_empty() && !volumes.iter().any(|volume| volume.path == mount_point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn volume(path: &str) -> Volumn {
+        Volumn {
+            name: path.to_string(),
+            display_name: path.to_string(),
+            path: PathBuf::from(path),
+            total_size: 0,
+            available_size: 0,
+            file_system: "exfat".to_string(),
+            capabilities: None,
+            shadow_storage: None,
+            inodes: None,
+        }
+    }
+
+    #[test]
+    fn test_removed_volume() {
+        let volumes = vec![volume("/"), volume("/Volumes/USB")];
+        assert!(!is_removed(Path::new("/Volumes/USB"), &volumes));
+        assert!(is_removed(Path::new("/Volumes/Backup"), &volumes));
+        assert!(!is_removed(Path::new("/Volumes/Backup"), &[]));
+    }
+}
@@ -1,21 +1,100 @@
-use serde::{ser::Serializer, Serialize};
+use serde::{Serialize, Serializer};
+use specta::Type;
+
+use crate::space::InsufficientSpace;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Debug, thiserror::Error)]
+/**
+ * Errors of the plugin, serialized as `{ kind, message }` so the frontend
+ * can tell a busy rejection apart from a real failure.
+ */
+#[derive(Debug, thiserror::Error, Serialize, Type)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+#[specta(rename = "ErrorKind")]
 pub enum Error {
-  #[error(transparent)]
-  Io(#[from] std::io::Error),
-  #[cfg(mobile)]
-  #[error(transparent)]
-  PluginInvoke(#[from] tauri::plugin::mobile::PluginInvokeError),
+    #[error(transparent)]
+    Io(
+        #[serde(serialize_with = "serialize_display")]
+        #[specta(type = String)]
+        std::io::Error,
+    ),
+    #[cfg(mobile)]
+    #[error(transparent)]
+    PluginInvoke(
+        #[from]
+        #[serde(serialize_with = "serialize_display")]
+        #[specta(type = String)]
+        tauri::plugin::mobile::PluginInvokeError,
+    ),
+    /// the named command is already running
+    #[error("{0} is already running")]
+    Busy(&'static str),
+    #[error("{0}")]
+    Scanner(String),
+    /// the named command would change a session which browses a snapshot
+    #[error("{0} is unavailable while browsing a snapshot")]
+    ReadOnly(&'static str),
+    #[error("clipboard unavailable, {0}")]
+    Clipboard(String),
+    /// the destination of a copy, move or archive has no room for it, nothing was written
+    #[error(transparent)]
+    InsufficientSpace(InsufficientSpace),
+}
+
+impl Error {
+    /**
+     * a failed file operation, told apart when its destination is short of
+     * space, otherwise described by `describe`
+     */
+    pub fn of_io(err: std::io::Error, describe: impl FnOnce(std::io::Error) -> String) -> Error {
+        match InsufficientSpace::of(&err) {
+            Some(short) => Error::InsufficientSpace(short.clone()),
+            None => Error::Scanner(describe(err)),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        match InsufficientSpace::of(&err) {
+            Some(short) => Error::InsufficientSpace(short.clone()),
+            None => Error::Io(err),
+        }
+    }
+}
+
+/**
+ * what a command rejects with, the error and the id of the request which raised it
+ * to look up with `get_request_trace`
+ */
+#[derive(Debug, thiserror::Error, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+#[error("{error} (request {request_id})")]
+pub struct CommandError {
+    #[serde(flatten)]
+    pub error: Error,
+    pub request_id: u64,
+}
+
+impl CommandError {
+    pub fn new(error: Error, request_id: u64) -> Self {
+        Self { error, request_id }
+    }
+}
+
+pub type CommandResult<T> = std::result::Result<T, CommandError>;
+
+impl From<String> for Error {
+    fn from(value: String) -> Self {
+        Error::Scanner(value)
+    }
 }
 
-impl Serialize for Error {
-  fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
-  where
+fn serialize_display<T, S>(value: &T, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    T: std::fmt::Display,
     S: Serializer,
-  {
-    serializer.serialize_str(self.to_string().as_ref())
-  }
+{
+    serializer.collect_str(value)
 }
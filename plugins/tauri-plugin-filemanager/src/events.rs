@@ -0,0 +1,135 @@
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::{
+    conflicts::PendingConflict,
+    deletion::CleanupProgress,
+    delta::SizeDeltaBatch,
+    hash::HashProgress,
+    memory::MemoryPressure,
+    models::{ScanAborted, ScanTargets},
+    mover::MoveProgress,
+    service::{
+        ScanProgress,
+        dedup::DuplicateGroup,
+        deleter::{DeleteProgress, DeleteReport},
+        watch::TreeUpdate,
+    },
+    settings::Settings,
+    watchdog::ScanStall,
+    weekly::WeeklySummary,
+};
+
+/**
+ * bump whenever the payload of any event changes shape
+ */
+pub const EVENT_VERSION: u32 = 28;
+
+/**
+ * Every event the backend emits to the webview, the `kind` tag matches the
+ * tauri event name so listeners can check what they received.
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum AppEvent {
+    FolderScanProgress(ScanProgress),
+    FolderScanComplete { message: String },
+    ScanTargetsDropped(ScanTargets),
+    ScanStalled(ScanStall),
+    ScanAborted(ScanAborted),
+    SettingsChanged(Settings),
+    NodeSizeDelta(SizeDeltaBatch),
+    HashProgress(HashProgress),
+    CleanupProgress(CleanupProgress),
+    DeleteProgress(DeleteProgress),
+    DeleteComplete(DeleteReport),
+    DuplicateFound(DuplicateGroup),
+    MemoryPressure(MemoryPressure),
+    TreeUpdated(TreeUpdate),
+    MoveProgress(MoveProgress),
+    Conflict(PendingConflict),
+    WeeklySummary(WeeklySummary),
+}
+
+impl AppEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::FolderScanProgress(_) => "folder-scan-progress",
+            AppEvent::FolderScanComplete { .. } => "folder-scan-complete",
+            AppEvent::ScanTargetsDropped(_) => "scan-targets-dropped",
+            AppEvent::ScanStalled(_) => "scan-stalled",
+            AppEvent::ScanAborted(_) => "scan-aborted",
+            AppEvent::SettingsChanged(_) => "settings-changed",
+            AppEvent::NodeSizeDelta(_) => "node-size-delta",
+            AppEvent::HashProgress(_) => "hash-progress",
+            AppEvent::CleanupProgress(_) => "cleanup-progress",
+            AppEvent::DeleteProgress(_) => "delete-progress",
+            AppEvent::DeleteComplete(_) => "delete-complete",
+            AppEvent::DuplicateFound(_) => "duplicate-found",
+            AppEvent::MemoryPressure(_) => "memory-pressure",
+            AppEvent::TreeUpdated(_) => "tree-updated",
+            AppEvent::MoveProgress(_) => "move-progress",
+            AppEvent::Conflict(_) => "conflict",
+            AppEvent::WeeklySummary(_) => "weekly-summary",
+        }
+    }
+}
+
+/**
+ * the payload actually sent over the wire
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct EventEnvelope {
+    pub version: u32,
+    #[serde(flatten)]
+    pub event: AppEvent,
+}
+
+/**
+ * emit an event, wrapped in its versioned envelope, only to the window owning the scan session
+ */
+pub fn emit_to<R: Runtime>(app: &AppHandle<R>, label: &str, event: AppEvent) -> tauri::Result<()> {
+    let name = event.name();
+    app.emit_to(
+        label,
+        name,
+        EventEnvelope {
+            version: EVENT_VERSION,
+            event,
+        },
+    )
+}
+
+/**
+ * emit an event to every window, for state they all share such as the settings
+ */
+pub fn emit_all<R: Runtime>(app: &AppHandle<R>, event: AppEvent) -> tauri::Result<()> {
+    let name = event.name();
+    app.emit(
+        name,
+        EventEnvelope {
+            version: EVENT_VERSION,
+            event,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialized_with_version_and_kind() {
+        let event = EventEnvelope {
+            version: EVENT_VERSION,
+            event: AppEvent::FolderScanComplete {
+                message: "Scan completed".to_string(),
+            },
+        };
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["version"], EVENT_VERSION);
+        assert_eq!(value["kind"], "folder-scan-complete");
+        assert_eq!(value["message"], "Scan completed");
+    }
+}
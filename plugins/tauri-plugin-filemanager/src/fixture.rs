@@ -0,0 +1,9 @@
+use std::path::PathBuf;
+
+/**
+ * a path under the temp dir only this test run uses, so concurrent runs of
+ * the suite don't trip over each other's files
+ */
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("cleaner-{}-{}", std::process::id(), name))
+}
@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::{Error, Result};
+
+/**
+ * Reentrancy flag for a single command, a second caller is rejected with
+ * `Error::Busy` instead of queueing behind the first one.
+ */
+pub struct Reentrancy {
+    name: &'static str,
+    busy: AtomicBool,
+}
+
+impl Reentrancy {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    pub fn try_enter(&self) -> Result<ReentrancyGuard<'_>> {
+        self.busy
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .map(|_| ReentrancyGuard { flag: &self.busy })
+            .map_err(|_| Error::Busy(self.name))
+    }
+}
+
+pub struct ReentrancyGuard<'a> {
+    flag: &'a AtomicBool,
+}
+
+impl Drop for ReentrancyGuard<'_> {
+    fn drop(&mut self) {
+        self.flag.store(false, Ordering::Release);
+    }
+}
+
+/**
+ * Guards for the control commands which must not overlap with themselves.
+ */
+pub struct CommandGuards {
+    pub start_scan: Reentrancy,
+    pub clear_scan: Reentrancy,
+    pub cleanup: Reentrancy,
+}
+
+impl Default for CommandGuards {
+    fn default() -> Self {
+        Self {
+            start_scan: Reentrancy::new("start_scan"),
+            clear_scan: Reentrancy::new("clear_folder_scan"),
+            cleanup: Reentrancy::new("run_cleanup"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reject_reentrant_call() {
+        let guard = Reentrancy::new("start_scan");
+        let first = guard.try_enter();
+        assert!(first.is_ok());
+        assert!(matches!(guard.try_enter(), Err(Error::Busy("start_scan"))));
+
+        drop(first);
+        assert!(guard.try_enter().is_ok());
+    }
+}
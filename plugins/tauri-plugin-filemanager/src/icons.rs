@@ -0,0 +1,301 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::Metadata,
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+/**
+ * icons remembered, far more than the extensions on a disk so the per file
+ * ones of a large app folder don't push them out
+ */
+pub const CACHE_CAPACITY: usize = 4096;
+
+/**
+ * extensions whose icon is stored in the file itself, apps, shortcuts and icons
+ */
+const PER_FILE_EXTENSIONS: [&str; 8] =
+    ["app", "exe", "ico", "icns", "lnk", "prefpane", "scr", "url"];
+
+/**
+ * Which icon to draw for an entry. Entries drawn with the same icon get the
+ * same `id`, so the frontend fetches each icon once instead of once per row.
+ * Only the per file ones are fetched one by one.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct IconHint {
+    /// e.g. `folder`, `ext:pdf`, `uti:com.adobe.pdf` on macOS or `sys:42` on windows
+    pub id: String,
+    /// the icon belongs to this entry alone, an app or a shortcut, fetch it by path
+    pub per_file: bool,
+}
+
+/**
+ * The hints looked up recently, keyed by extension or by path for the per
+ * file ones. The platform lookups are slow, a list of 50k files shares a few
+ * dozen of them.
+ */
+#[derive(Clone)]
+pub struct IconCache {
+    entries: Arc<Mutex<Lru>>,
+}
+
+impl Default for IconCache {
+    fn default() -> Self {
+        Self::new(CACHE_CAPACITY)
+    }
+}
+
+impl IconCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(Lru::new(capacity))),
+        }
+    }
+
+    /**
+     * the hint of `path`, `metadata` without following a link
+     */
+    pub fn hint(&self, path: &Path, metadata: &Metadata) -> IconHint {
+        self.hint_with(path, metadata.is_dir(), metadata.is_symlink(), platform_id)
+    }
+
+    fn hint_with(
+        &self,
+        path: &Path,
+        is_directory: bool,
+        is_link: bool,
+        platform: fn(&Path, bool, bool) -> Option<String>,
+    ) -> IconHint {
+        let (key, generic) = generic_hint(path, is_directory, is_link);
+        if let Some(hint) = self.lookup(&key) {
+            return hint;
+        }
+        // the first entry of a type decides for all the others
+        let platform_id = (!is_link)
+            .then(|| platform(path, is_directory, generic.per_file))
+            .flatten();
+        let hint = match platform_id {
+            Some(id) => IconHint { id, ..generic },
+            None => generic,
+        };
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, hint.clone());
+        hint
+    }
+
+    fn lookup(&self, key: &str) -> Option<IconHint> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key)
+    }
+}
+
+/**
+ * the cache key and the hint known without asking the platform
+ */
+fn generic_hint(path: &Path, is_directory: bool, is_link: bool) -> (String, IconHint) {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    let per_file = !is_link
+        && extension
+            .as_deref()
+            .is_some_and(|extension| PER_FILE_EXTENSIONS.contains(&extension));
+
+    let id = match (is_link, is_directory, &extension) {
+        (true, _, _) => "symlink".to_string(),
+        // an app bundle is drawn like a file of its type
+        (false, true, Some(extension)) if per_file => format!("ext:{}", extension),
+        (false, true, _) => "folder".to_string(),
+        (false, false, Some(extension)) => format!("ext:{}", extension),
+        (false, false, None) => "file".to_string(),
+    };
+    let key = match (per_file, is_directory, extension) {
+        (true, _, _) => format!("path:{}", path.display()),
+        // a package like a photos library has its own icon, plain folders share one
+        (false, true, Some(extension)) if !is_link => format!("folder:{}", extension),
+        _ => id.clone(),
+    };
+    (key, IconHint { id, per_file })
+}
+
+/**
+ * the uniform type identifier, an app keeps the one of its path as every app
+ * shares the type
+ */
+#[cfg(target_os = "macos")]
+fn platform_id(path: &Path, _is_directory: bool, per_file: bool) -> Option<String> {
+    use objc2_foundation::{NSArray, NSString, NSURL, NSURLTypeIdentifierKey};
+
+    if per_file {
+        return None;
+    }
+    let path = NSString::from_str(path.to_str()?);
+    let url = NSURL::fileURLWithPath(&path);
+    let key = unsafe { NSURLTypeIdentifierKey };
+    let values = url
+        .resourceValuesForKeys_error(&NSArray::from_slice(&[key]))
+        .ok()?;
+    let identifier = values.objectForKey(key)?.downcast::<NSString>().ok()?;
+    Some(format!("uti:{}", identifier))
+}
+
+/**
+ * the index of the icon in the system image list, shared by every file drawn
+ * alike. Only a per file icon needs the file to be read
+ */
+#[cfg(windows)]
+fn platform_id(path: &Path, is_directory: bool, per_file: bool) -> Option<String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::{
+        Storage::FileSystem::{FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_NORMAL},
+        UI::Shell::{SHFILEINFOW, SHGFI_SYSICONINDEX, SHGFI_USEFILEATTRIBUTES, SHGetFileInfoW},
+    };
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let attributes = if is_directory {
+        FILE_ATTRIBUTE_DIRECTORY
+    } else {
+        FILE_ATTRIBUTE_NORMAL
+    };
+    // SAFETY: an all zero SHFILEINFOW is valid, it is only written to
+    let mut info: SHFILEINFOW = unsafe { std::mem::zeroed() };
+    let flags = if per_file {
+        SHGFI_SYSICONINDEX
+    } else {
+        // by name and attributes alone, the file isn't opened
+        SHGFI_SYSICONINDEX | SHGFI_USEFILEATTRIBUTES
+    };
+    // SAFETY: the path is nul terminated and `info` outlives the call
+    let list = unsafe {
+        SHGetFileInfoW(
+            wide.as_ptr(),
+            attributes,
+            &mut info,
+            std::mem::size_of::<SHFILEINFOW>() as u32,
+            flags,
+        )
+    };
+    (list != 0).then(|| format!("sys:{}", info.iIcon))
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn platform_id(_path: &Path, _is_directory: bool, _per_file: bool) -> Option<String> {
+    None
+}
+
+/**
+ * least recently used eviction, `order` maps the tick of the last use to its key
+ */
+struct Lru {
+    capacity: usize,
+    tick: u64,
+    values: HashMap<String, (IconHint, u64)>,
+    order: BTreeMap<u64, String>,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            tick: 0,
+            values: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<IconHint> {
+        self.tick += 1;
+        let (hint, used) = self.values.get_mut(key)?;
+        let key = self.order.remove(used)?;
+        *used = self.tick;
+        self.order.insert(self.tick, key);
+        Some(hint.clone())
+    }
+
+    fn insert(&mut self, key: String, hint: IconHint) {
+        self.tick += 1;
+        if let Some((_, used)) = self.values.remove(&key) {
+            self.order.remove(&used);
+        }
+        while self.values.len() >= self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.values.remove(&oldest);
+        }
+        self.order.insert(self.tick, key.clone());
+        self.values.insert(key, (hint, self.tick));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+
+    fn counted(path: &Path, _is_directory: bool, _per_file: bool) -> Option<String> {
+        LOOKUPS.fetch_add(1, Ordering::Relaxed);
+        path.extension()
+            .map(|extension| format!("sys:{}", extension.len()))
+    }
+
+    #[test]
+    fn test_one_lookup_per_extension() {
+        let cache = IconCache::default();
+        for index in 0..1000 {
+            let hint = cache.hint_with(
+                &PathBuf::from(format!("/photos/{}.JPG", index)),
+                false,
+                false,
+                counted,
+            );
+            assert_eq!(hint.id, "sys:3");
+            assert!(!hint.per_file);
+        }
+        let app = cache.hint_with(Path::new("/Applications/Safari.app"), true, false, counted);
+        assert!(app.per_file);
+        cache.hint_with(Path::new("/Applications/Notes.app"), true, false, counted);
+        assert_eq!(LOOKUPS.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_generic_hints() {
+        let id = |path: &str, is_directory, is_link| {
+            generic_hint(Path::new(path), is_directory, is_link).1.id
+        };
+        assert_eq!(id("/data/Report.PDF", false, false), "ext:pdf");
+        assert_eq!(id("/data/Makefile", false, false), "file");
+        assert_eq!(id("/data/photos.v2", true, false), "folder");
+        assert_eq!(id("/data/latest", true, true), "symlink");
+    }
+
+    #[test]
+    fn test_least_recently_used_evicted() {
+        let hint = |id: &str| IconHint {
+            id: id.to_string(),
+            per_file: false,
+        };
+        let mut lru = Lru::new(2);
+        lru.insert("a".to_string(), hint("a"));
+        lru.insert("b".to_string(), hint("b"));
+        assert!(lru.get("a").is_some());
+        lru.insert("c".to_string(), hint("c"));
+        assert_eq!(lru.get("b"), None);
+        assert_eq!(lru.get("a"), Some(hint("a")));
+        assert_eq!(lru.get("c"), Some(hint("c")));
+    }
+}
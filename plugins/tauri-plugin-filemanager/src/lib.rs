@@ -1,7 +1,8 @@
 use tauri::{
+    DragDropEvent, Manager, RunEvent, Runtime, WindowEvent,
     plugin::{Builder, TauriPlugin},
-    Manager, Runtime,
 };
+use tokio::sync::broadcast::error::RecvError;
 
 pub use models::*;
 
@@ -10,16 +11,67 @@ mod desktop;
 #[cfg(mobile)]
 mod mobile;
 
+mod actions;
+mod bindings;
+mod cleanup;
+mod clipboard;
 mod commands;
+mod conflicts;
+mod cost;
+mod driver;
 mod error;
+mod events;
+#[cfg(test)]
+mod fixture;
+mod guard;
+mod icons;
+mod maintenance;
 mod models;
+mod purgeable;
+mod repair;
+mod request;
+mod session;
+mod settings;
+mod shadow;
+mod simulation;
+mod targets;
+mod thumbnails;
 
-pub use error::{Error, Result};
+// the engine lives in cleaner-core, aliased so `crate::tree` and friends keep resolving
+use cleaner_core::{
+    activity, analyzers, audit, backup_target, capabilities, compare, dashboard, database,
+    deletion, delta, display_name, dumps, eta, filters, fingerprint, hash, hooks, inodes,
+    interference, labels, locks, long_path, lossless, memory, metrics, mover, path_key, quarantine,
+    queue, resume, retry, review, scan_diff, scanner_config, selection, service, snapshot, space,
+    store, symlinks, throttle, timeline, timing, transaction, tree, watchdog, weekly,
+};
+
+pub use error::{CommandError, CommandResult, Error, Result};
+pub use events::{AppEvent, EVENT_VERSION, EventEnvelope};
+pub use service::{ScanProgress, Scanner};
 
+use activity::{ActivityFeed, ActivityKind};
+use audit::AuditLog;
+use conflicts::Conflicts;
+use cost::ThroughputMeter;
+use dashboard::DashboardCache;
+use database::Database;
 #[cfg(desktop)]
 use desktop::Filemanager;
+use hash::HashIndex;
+use icons::IconCache;
+use labels::LabelStore;
+use maintenance::MaintenanceLog;
+use metrics::Metrics;
 #[cfg(mobile)]
 use mobile::Filemanager;
+use request::Requests;
+use resume::CheckpointStore;
+use review::ReviewStore;
+use session::Sessions;
+use settings::SettingsStore;
+use thumbnails::Thumbnailer;
+use weekly::WeeklySummaries;
 
 /// Extensions to [`tauri::App`], [`tauri::AppHandle`] and [`tauri::Window`] to access the filemanager APIs.
 pub trait FilemanagerExt<R: Runtime> {
@@ -32,17 +84,329 @@ impl<R: Runtime, T: Manager<R>> crate::FilemanagerExt<R> for T {
     }
 }
 
+/// Every command exposed by the plugin, also the source of the generated bindings.
+pub(crate) fn specta_builder<R: Runtime>() -> tauri_specta::Builder<R> {
+    tauri_specta::Builder::<R>::new()
+        .plugin_name("filemanager")
+        .commands(tauri_specta::collect_commands![
+            commands::ping::<R>,
+            commands::start_scan::<R>,
+            commands::get_folder_stats::<R>,
+            commands::get_scan_progress::<R>,
+            commands::stop_folder_scan::<R>,
+            commands::is_scanning::<R>,
+            commands::clear_folder_scan::<R>,
+            driver::get_available_drivers,
+            commands::prepare_scan_targets,
+            commands::copy_paths_to_clipboard::<R>,
+            commands::get_actions_for::<R>,
+            commands::expand_selection::<R>,
+            commands::get_recent_changes::<R>,
+            commands::get_slow_directories::<R>,
+            commands::check_freshness::<R>,
+            commands::scan_on_demand::<R>,
+            commands::get_scan_queue::<R>,
+            commands::skip_subtree::<R>,
+            commands::prioritize_subtree::<R>,
+            commands::get_dashboard,
+            commands::estimate_cleanup::<R>,
+            commands::export_session::<R>,
+            commands::import_session::<R>,
+            commands::get_scan_source::<R>,
+            commands::run_cleanup::<R>,
+            commands::get_largest_files::<R>,
+            commands::find_old_files::<R>,
+            commands::search_files::<R>,
+            commands::run_maintenance::<R>,
+            commands::get_maintenance_stats,
+            commands::get_scan_watchdog::<R>,
+            commands::set_scan_watchdog::<R>,
+            commands::get_retry_policy::<R>,
+            commands::set_retry_policy::<R>,
+            commands::get_scanner_config::<R>,
+            commands::configure_scanner::<R>,
+            commands::get_request_trace,
+            commands::simulate_cleanup::<R>,
+            commands::get_settings,
+            commands::set_settings::<R>,
+            commands::cancel_cleanup::<R>,
+            commands::fix_permissions_then_delete::<R>,
+            commands::strip_quarantine::<R>,
+            commands::set_folder_label,
+            commands::list_folder_labels,
+            commands::set_review_state::<R>,
+            commands::get_review_queues,
+            commands::execute_delete_later::<R>,
+            commands::get_breadcrumbs,
+            commands::get_icon_hint,
+            commands::get_icon_hints,
+            commands::get_thumbnail,
+            commands::diff_paths,
+            commands::backup_targets::<R>,
+            commands::run_operation::<R>,
+            commands::get_audit_log,
+            commands::get_activity_feed,
+            commands::get_weekly_summaries,
+            commands::trash_paths::<R>,
+            commands::delete_paths::<R>,
+            commands::move_file::<R>,
+            commands::copy_file::<R>,
+            commands::resolve_conflict,
+            commands::list_interrupted_operations,
+            commands::resume_operation::<R>,
+            commands::discard_operation,
+            commands::find_duplicates::<R>,
+            commands::get_top_disk_writers,
+            driver::resize_shadow_storage,
+            driver::delete_oldest_restore_point,
+            commands::get_scan_filters::<R>,
+            commands::set_scan_filters::<R>,
+            commands::find_system_dumps::<R>,
+            commands::delete_system_dumps::<R>,
+            commands::find_empty_directories::<R>,
+            commands::remove_empty_directories::<R>,
+            commands::get_skipped_mounts::<R>,
+            commands::get_most_files::<R>,
+            commands::find_file_count_hotspots::<R>,
+            commands::mitigate_memory_pressure::<R>,
+            commands::get_type_breakdown::<R>,
+            commands::save_scan_snapshot::<R>,
+            commands::load_scan_snapshot::<R>,
+            commands::list_analyzers::<R>,
+            commands::run_analyzers::<R>,
+            commands::list_scan_snapshots::<R>,
+            commands::diff_scans::<R>
+        ])
+        .typ::<EventEnvelope>()
+}
+
 /// Initializes the plugin.
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    let builder = specta_builder::<R>();
+
+    #[cfg(debug_assertions)]
+    if let Err(err) = bindings::export(&builder, std::path::Path::new(bindings::BINDINGS)) {
+        tracing::warn!("{}", err);
+    }
+
     Builder::new("filemanager")
-        .invoke_handler(tauri::generate_handler![commands::ping])
+        .invoke_handler(builder.invoke_handler())
         .setup(|app, api| {
             #[cfg(mobile)]
             let filemanager = mobile::init(app, api)?;
             #[cfg(desktop)]
             let filemanager = desktop::init(app, api)?;
             app.manage(filemanager);
+
+            let settings = app
+                .path()
+                .app_config_dir()
+                .map(|dir| SettingsStore::open(dir.join(settings::SETTINGS_FILE)))
+                .unwrap_or_else(|err| {
+                    tracing::warn!("no config directory, settings won't be kept, {}", err);
+                    SettingsStore::in_memory()
+                });
+            let current = settings.current();
+            targets::set_user_protected(current.protected_paths.clone());
+            app.manage(Sessions::new(current.clone()));
+            let mut changes = settings.subscribe();
+            let mut maintenance_changes = settings.subscribe();
+            let mut metrics_changes = settings.subscribe();
+            let watch = settings.watch();
+            app.manage(settings);
+
+            // a hand edit, or one from another window, applies without a restart
+            match watch {
+                Ok(Some(mut watch)) => {
+                    let handle = app.app_handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        while watch.next_edit().await.is_some() {
+                            let reader = handle.clone();
+                            let reloaded = tokio::task::spawn_blocking(move || {
+                                reader.state::<SettingsStore>().reload()
+                            })
+                            .await;
+                            if let Ok(Err(err)) = reloaded {
+                                tracing::warn!(
+                                    "reload settings failed, the previous ones stay, {}",
+                                    err
+                                );
+                            }
+                        }
+                    });
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    tracing::warn!(
+                        "watch settings failed, edits apply after a restart, {}",
+                        err
+                    )
+                }
+            }
+            let handle = app.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let change = match changes.recv().await {
+                        Ok(change) => change,
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    };
+                    handle.state::<Sessions>().apply_settings(&change);
+                    targets::set_user_protected(change.current.protected_paths.clone());
+                    if change.previous.hash_algorithm != change.current.hash_algorithm {
+                        let algorithm = change.current.content_hash();
+                        handle.state::<HashIndex>().set_algorithm(algorithm);
+                    }
+                    let settings = (*change.current).clone();
+                    let _ = events::emit_all(&handle, AppEvent::SettingsChanged(settings));
+                }
+            });
+            app.manage(Requests::default());
+
+            // every store keeps its tables in the one app database
+            let database = app
+                .path()
+                .app_data_dir()
+                .map_err(std::io::Error::other)
+                .and_then(|dir| {
+                    std::fs::create_dir_all(&dir)?;
+                    Database::open(&dir.join("index.sqlite"))
+                })
+                .or_else(|err| {
+                    tracing::warn!("open app database failed, nothing will be kept, {}", err);
+                    Database::in_memory()
+                })?;
+
+            let index = HashIndex::new(&database)?;
+            let algorithm = current.content_hash();
+            tracing::info!("hash with {}, {:?}", algorithm.name(), hash::acceleration());
+            index.set_algorithm(algorithm);
+            app.manage(index);
+            app.manage(LabelStore::new(&database)?);
+            app.manage(ReviewStore::new(&database)?);
+            app.manage(AuditLog::new(&database)?);
+            app.manage(ActivityFeed::new(&database)?);
+            app.manage(WeeklySummaries::new(&database)?);
+            app.manage(CheckpointStore::new(&database)?);
+
+            app.manage(Conflicts::default());
+            app.manage(DashboardCache::default());
+            app.manage(Metrics::default());
+            // opt-in, servers graph the growth of their directories from it
+            let handle = app.app_handle().clone();
+            let port = current.metrics_port;
+            tauri::async_runtime::spawn(async move {
+                let mut exporter = commands::export_metrics(&handle, port).await;
+                loop {
+                    let change = match metrics_changes.recv().await {
+                        Ok(change) => change,
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    };
+                    if change.previous.metrics_port == change.current.metrics_port {
+                        continue;
+                    }
+                    if let Some(exporter) = exporter.take() {
+                        exporter.abort();
+                    }
+                    exporter = commands::export_metrics(&handle, change.current.metrics_port).await;
+                }
+            });
+            app.manage(ThroughputMeter::default());
+            app.manage(IconCache::default());
+            let thumbnails = app
+                .path()
+                .app_cache_dir()
+                .map(|dir| dir.join("thumbnails"))
+                .unwrap_or_else(|err| {
+                    tracing::warn!("no app cache dir, thumbnails go to the temp dir, {}", err);
+                    std::env::temp_dir().join("cleaner-thumbnails")
+                });
+            app.manage(Thumbnailer::new(thumbnails));
+            let handle = app.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    if let Ok(volumes) = tokio::task::spawn_blocking(driver::list_volumes).await {
+                        // the index maps large files only on volumes it knows to be local
+                        handle.state::<HashIndex>().set_volumes(volumes.clone());
+                        handle.state::<Metrics>().set_volumes(volumes.clone());
+                        let raised = handle.state::<DashboardCache>().refresh_volumes(volumes);
+                        for alert in raised {
+                            commands::record_activity(
+                                &handle,
+                                ActivityKind::Alert,
+                                Some(&alert.path),
+                                &alert.message,
+                                None,
+                                None,
+                            );
+                        }
+                    }
+                    tokio::time::sleep(dashboard::REFRESH_INTERVAL).await;
+                }
+            });
+
+            app.manage(MaintenanceLog::default());
+            let handle = app.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut last = tokio::time::Instant::now();
+                loop {
+                    let interval = handle
+                        .state::<SettingsStore>()
+                        .current()
+                        .maintenance_interval();
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(last + interval) => {
+                            last = tokio::time::Instant::now();
+                            if let Err(err) = commands::maintain(&handle).await {
+                                tracing::warn!("scheduled maintenance failed, {}", err);
+                            }
+                        }
+                        // a changed interval counts from the last run, not from the change
+                        Ok(_) = maintenance_changes.recv() => {}
+                    }
+                }
+            });
+
+            // the summary of a week is compiled once it is over, or at the next start
+            let handle = app.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    if let Err(err) = commands::summarize_week(&handle).await {
+                        tracing::warn!("weekly summary failed, {}", err);
+                    }
+                    tokio::time::sleep(weekly::CHECK_INTERVAL).await;
+                }
+            });
+
+            // drive monitor, a scan of an unplugged drive is cancelled instead of left spinning
+            let handle = app.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    tokio::time::sleep(driver::MONITOR_INTERVAL).await;
+                    commands::check_removed_volumes(&handle).await;
+                }
+            });
             Ok(())
         })
+        .on_event(|app, event| match event {
+            // a closed window takes its scan session with it
+            RunEvent::WindowEvent {
+                label,
+                event: WindowEvent::Destroyed,
+                ..
+            } => {
+                app.state::<Sessions>().remove(label);
+            }
+            RunEvent::WindowEvent {
+                label,
+                event: WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }),
+                ..
+            } => {
+                let targets = targets::prepare_scan_targets(paths);
+                let _ = events::emit_to(app, label, AppEvent::ScanTargetsDropped(targets));
+            }
+            _ => {}
+        })
         .build()
 }
@@ -0,0 +1,156 @@
+use std::{
+    path::Path,
+    sync::{PoisonError, RwLock},
+    time::Duration,
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{
+    fingerprint::DirectoryFingerprints,
+    guard::Reentrancy,
+    hash::HashIndex,
+    models::now_secs,
+    thumbnails::{self, Thumbnailer},
+};
+
+/**
+ * hours between two runs of the scheduler compacting the index and pruning
+ * old files, unless the settings say otherwise
+ */
+pub const DEFAULT_INTERVAL_HOURS: u64 = 24;
+
+/**
+ * how long the files written by past scans are kept
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Retention {
+    /// fingerprints of roots not scanned for this long are dropped
+    pub max_age: Duration,
+    /// fingerprint files kept at most, the most recently written ones
+    pub max_fingerprint_files: usize,
+    /// disk space of the thumbnails kept, the most recently used ones
+    pub max_thumbnail_bytes: u64,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            max_age: Duration::from_secs(90 * 24 * 60 * 60),
+            max_fingerprint_files: 50,
+            max_thumbnail_bytes: thumbnails::DEFAULT_CACHE_BYTES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceReport {
+    pub finished_at: u64,
+    /// hashes dropped because their file is gone
+    pub hashes_pruned: usize,
+    pub index_bytes_before: u64,
+    pub index_bytes_after: u64,
+    pub fingerprints_pruned: usize,
+    pub fingerprint_bytes_freed: u64,
+    pub thumbnails_evicted: usize,
+    pub thumbnail_bytes_freed: u64,
+    /// steps which failed, the others still ran
+    pub errors: Vec<String>,
+}
+
+/**
+ * Outcome of the last maintenance run. The scheduler and `run_maintenance`
+ * share the guard, so the index is never vacuumed twice at once.
+ */
+pub struct MaintenanceLog {
+    pub guard: Reentrancy,
+    last: RwLock<Option<MaintenanceReport>>,
+}
+
+impl Default for MaintenanceLog {
+    fn default() -> Self {
+        Self {
+            guard: Reentrancy::new("run_maintenance"),
+            last: RwLock::new(None),
+        }
+    }
+}
+
+impl MaintenanceLog {
+    pub fn last(&self) -> Option<MaintenanceReport> {
+        self.last
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn record(&self, report: MaintenanceReport) {
+        *self.last.write().unwrap_or_else(PoisonError::into_inner) = Some(report);
+    }
+}
+
+/**
+ * prune the hash index and compact it, then drop the fingerprint files and
+ * the thumbnails past `retention`, blocking
+ */
+pub fn run(
+    index: &HashIndex,
+    data_dir: &Path,
+    thumbnails: &Thumbnailer,
+    retention: Retention,
+) -> MaintenanceReport {
+    let mut report = MaintenanceReport::default();
+
+    match index.prune_missing() {
+        Ok(pruned) => report.hashes_pruned = pruned,
+        Err(err) => report.errors.push(format!("prune hash index, {}", err)),
+    }
+    match index.vacuum() {
+        Ok((before, after)) => {
+            report.index_bytes_before = before;
+            report.index_bytes_after = after;
+        }
+        Err(err) => report.errors.push(format!("vacuum hash index, {}", err)),
+    }
+    match DirectoryFingerprints::prune(data_dir, retention.max_age, retention.max_fingerprint_files)
+    {
+        Ok((pruned, freed)) => {
+            report.fingerprints_pruned = pruned;
+            report.fingerprint_bytes_freed = freed;
+        }
+        Err(err) => report.errors.push(format!("prune fingerprints, {}", err)),
+    }
+    match thumbnails.evict(retention.max_thumbnail_bytes) {
+        Ok((evicted, freed)) => {
+            report.thumbnails_evicted = evicted;
+            report.thumbnail_bytes_freed = freed;
+        }
+        Err(err) => report.errors.push(format!("evict thumbnails, {}", err)),
+    }
+
+    report.finished_at = now_secs();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_run_reports_every_step() {
+        let index = HashIndex::in_memory().unwrap();
+        let report = run(
+            &index,
+            Path::new("/cleaner/missing-dir"),
+            &Thumbnailer::new(PathBuf::from("/cleaner/missing-dir/thumbnails")),
+            Retention::default(),
+        );
+        // only the fingerprint directory is missing, no thumbnail was rendered yet
+        assert_eq!(report.hashes_pruned, 0);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.finished_at > 0);
+    }
+}
@@ -1,13 +1,69 @@
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
+use specta::Type;
+
+pub use cleaner_core::models::{
+    AgeBuckets, ChildPage, ChildSort, FileDetails, ScanResult, ShadowStorage, SortOrder,
+    VolumeCapabilities, Volumn, now_secs,
+};
+use cleaner_core::snapshot::SessionInfo;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct PingRequest {
     pub value: Option<String>,
 }
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct PingResponse {
     pub value: Option<String>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TargetRejection {
+    NotFound,
+    Unreadable,
+    Protected,
+    /// already covered by a selected parent directory
+    Nested,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedTarget {
+    pub path: PathBuf,
+    pub reason: TargetRejection,
+}
+
+/**
+ * normalized scan targets built from dropped or picked paths
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanTargets {
+    pub targets: Vec<PathBuf>,
+    pub rejected: Vec<RejectedTarget>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AbortReason {
+    /// the scanned volume was unplugged or unmounted
+    VolumeRemoved,
+}
+
+/**
+ * a scan the app cancelled on its own, what was scanned so far is kept as a snapshot
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanAborted {
+    pub reason: AbortReason,
+    /// mount point of the volume the scan read
+    pub volume: PathBuf,
+    /// the partial scan, none when it couldn't be saved
+    pub snapshot: Option<SessionInfo>,
+}
@@ -0,0 +1,111 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::models::Volumn;
+
+/**
+ * free space split the way Finder shows it, APFS keeps local snapshots and caches
+ * as purgeable so deleted bytes may not show up as free right away
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeableSpace {
+    pub volume: PathBuf,
+    /// space free right now
+    pub free: u64,
+    /// space the system releases on demand, Finder counts it as available
+    pub purgeable: u64,
+}
+
+impl PurgeableSpace {
+    /**
+     * `available` is the capacity for important usage, free plus purgeable
+     */
+    pub fn new(volume: PathBuf, free: u64, available: u64) -> Self {
+        Self {
+            volume,
+            free,
+            purgeable: available.saturating_sub(free),
+        }
+    }
+}
+
+/**
+ * the purgeable space of a volume, none where the system doesn't report it
+ */
+#[cfg(target_os = "macos")]
+pub fn measure(volume: &Volumn) -> Option<PurgeableSpace> {
+    use objc2_foundation::{
+        NSArray, NSNumber, NSString, NSURL, NSURLVolumeAvailableCapacityForImportantUsageKey,
+    };
+
+    let path = NSString::from_str(volume.path.to_str()?);
+    let url = NSURL::fileURLWithPath(&path);
+    let key = unsafe { NSURLVolumeAvailableCapacityForImportantUsageKey };
+    let values = url
+        .resourceValuesForKeys_error(&NSArray::from_slice(&[key]))
+        .ok()?;
+    let available = values.objectForKey(key)?.downcast::<NSNumber>().ok()?;
+    let available = u64::try_from(available.longLongValue()).ok()?;
+
+    Some(PurgeableSpace::new(
+        volume.path.clone(),
+        volume.available_size,
+        available,
+    ))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn measure(_volume: &Volumn) -> Option<PurgeableSpace> {
+    None
+}
+
+/**
+ * ask the system to release up to `bytes` of purgeable space on the volume
+ * mounted at `mount` by thinning its local snapshots
+ */
+#[cfg(target_os = "macos")]
+pub fn reclaim(mount: &Path, bytes: u64) -> io::Result<()> {
+    // urgency 4 is the highest tmutil accepts
+    let output = std::process::Command::new("tmutil")
+        .arg("thinlocalsnapshots")
+        .arg(mount)
+        .arg(bytes.to_string())
+        .arg("4")
+        .output()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn reclaim(_mount: &Path, _bytes: u64) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "purgeable space is only reclaimed on macOS",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purgeable_is_available_beyond_free() {
+        let space = PurgeableSpace::new(PathBuf::from("/"), 100, 250);
+        assert_eq!(space.purgeable, 150);
+
+        let space = PurgeableSpace::new(PathBuf::from("/"), 100, 80);
+        assert_eq!(space.purgeable, 0);
+    }
+}
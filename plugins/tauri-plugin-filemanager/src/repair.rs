@@ -0,0 +1,352 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{
+    cleanup::CleanupFailure,
+    deletion::{self, CleanupProgress, SubtreeDeletion},
+    long_path,
+    throttle::Throttle,
+};
+
+/**
+ * permission and owner changes made per second at most, a tree of millions of
+ * entries must not saturate the disk or a network share while it is repaired
+ */
+pub const FIXES_PER_SECOND: u64 = 1_000;
+
+/**
+ * what was changed on an entry so it could be removed
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+// every platform only makes some of them
+#[allow(dead_code)]
+pub enum FixKind {
+    /// the owner got read, write and search access, unix only
+    Mode,
+    /// the entry was handed over to the current user, unix only
+    Owner,
+    /// the read-only attribute was cleared, windows only
+    ReadOnly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionFix {
+    pub path: PathBuf,
+    pub kind: FixKind,
+    pub before: String,
+    pub after: String,
+}
+
+/**
+ * The outcome of `fix_permissions_then_delete`. Only what a plain deletion
+ * left behind is repaired, so `fixed` lists exactly the entries which stood
+ * in the way.
+ */
+#[derive(Debug, Clone, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionRepair {
+    pub path: PathBuf,
+    pub fixed: Vec<PermissionFix>,
+    /// entries whose permissions could not be changed either
+    pub unfixable: Vec<CleanupFailure>,
+    /// what the user should know about the changes and what is left
+    pub warnings: Vec<String>,
+    /// both attempts together, `removed` once the path is gone
+    pub deletion: SubtreeDeletion,
+}
+
+/**
+ * Delete `path`, and when entries are left reset their permissions and owner
+ * recursively and delete again, clearing the lock flags in the way. The changes stay on whatever still can't be
+ * removed, they are all listed in the repair.
+ */
+pub fn repair_and_delete(
+    path: &Path,
+    cancel: &AtomicBool,
+    report: &mut dyn FnMut(&CleanupProgress),
+) -> PermissionRepair {
+    let paths = [path.to_path_buf()];
+    let mut repair = PermissionRepair {
+        path: path.to_path_buf(),
+        ..PermissionRepair::default()
+    };
+    let first = deletion::delete(&paths, deletion::WORKERS, false, cancel, report).remove(0);
+    if first.removed || cancel.load(Ordering::Relaxed) {
+        repair.deletion = first;
+        return repair;
+    }
+
+    let mut fixer = Fixer {
+        selected: path,
+        accessed: long_path::extended(path).into_owned(),
+        owner: current_owner(),
+        throttle: Throttle::per_sec(Some(FIXES_PER_SECOND)),
+        repair: &mut repair,
+    };
+    let accessed = fixer.accessed.clone();
+    fixer.walk(&accessed);
+    platform_warnings(&mut repair);
+
+    let mut second = deletion::delete(&paths, deletion::WORKERS, true, cancel, report).remove(0);
+    second.files += first.files;
+    second.directories += first.directories;
+    second.bytes += first.bytes;
+    if second.unlocked > 0 {
+        repair.warnings.push(format!(
+            "{} locked entries were unlocked to remove them",
+            second.unlocked
+        ));
+    }
+    if !second.removed {
+        repair.warnings.push(format!(
+            "{} entries could still not be removed, the permissions changed on them are kept",
+            second.failure_count
+        ));
+    }
+    if second.failures.iter().any(|failure| failure.path == path) {
+        repair.warnings.push(format!(
+            "{} is in a directory you can't write to, its parent is left unchanged",
+            path.display()
+        ));
+    }
+    repair.deletion = second;
+    repair
+}
+
+struct Fixer<'a> {
+    selected: &'a Path,
+    accessed: PathBuf,
+    /// user and group the entries are handed over to, only root can
+    owner: Option<(u32, u32)>,
+    throttle: Throttle,
+    repair: &'a mut PermissionRepair,
+}
+
+impl Fixer<'_> {
+    /**
+     * repair `path` before its entries, a directory has to be readable to be listed
+     */
+    fn walk(&mut self, path: &Path) {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return,
+            Err(err) => return self.unfixable(path, err.to_string()),
+        };
+        // a symlink is removed as it is, its target is never touched
+        if metadata.is_symlink() {
+            return;
+        }
+        self.fix(path, &metadata);
+        if !metadata.is_dir() {
+            return;
+        }
+
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    self.walk(&entry.path());
+                }
+            }
+            Err(err) => self.unfixable(path, err.to_string()),
+        }
+    }
+
+    #[cfg(unix)]
+    fn fix(&mut self, path: &Path, metadata: &fs::Metadata) {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        // why the entry still belongs to another user, it only matters when
+        // its mode can't be fixed either
+        let mut foreign = None;
+        if let Some((uid, gid)) = self.owner.filter(|(uid, _)| *uid != metadata.uid()) {
+            if uid != 0 {
+                foreign = Some(format!(
+                    "owned by user {}, changing it needs administrator rights",
+                    metadata.uid()
+                ));
+            } else {
+                self.throttle.pace(1);
+                match std::os::unix::fs::lchown(path, Some(uid), Some(gid)) {
+                    Ok(()) => self.fixed(path, FixKind::Owner, metadata.uid(), uid),
+                    Err(err) => {
+                        foreign = Some(format!("owned by user {}, {}", metadata.uid(), err))
+                    }
+                }
+            }
+        }
+
+        // deleting an entry needs write and search access to its directory
+        let mode = metadata.mode() & 0o7777;
+        if metadata.is_dir() && mode & 0o700 != 0o700 {
+            self.throttle.pace(1);
+            let fixed = mode | 0o700;
+            match fs::set_permissions(path, fs::Permissions::from_mode(fixed)) {
+                Ok(()) => self.fixed(
+                    path,
+                    FixKind::Mode,
+                    format!("{:o}", mode),
+                    format!("{:o}", fixed),
+                ),
+                Err(err) => {
+                    let reason = match foreign {
+                        Some(foreign) => format!("{}, {}", foreign, err),
+                        None => err.to_string(),
+                    };
+                    self.unfixable(path, reason)
+                }
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn fix(&mut self, path: &Path, metadata: &fs::Metadata) {
+        let mut permissions = metadata.permissions();
+        if permissions.readonly() {
+            self.throttle.pace(1);
+            permissions.set_readonly(false);
+            match fs::set_permissions(path, permissions) {
+                Ok(()) => self.fixed(path, FixKind::ReadOnly, "read-only", "writable"),
+                Err(err) => self.unfixable(path, err.to_string()),
+            }
+        }
+    }
+
+    fn fixed(&mut self, path: &Path, kind: FixKind, before: impl ToString, after: impl ToString) {
+        self.repair.fixed.push(PermissionFix {
+            path: self.reported(path),
+            kind,
+            before: before.to_string(),
+            after: after.to_string(),
+        });
+    }
+
+    fn unfixable(&mut self, path: &Path, reason: String) {
+        let path = self.reported(path);
+        self.repair.unfixable.push(CleanupFailure { path, reason });
+    }
+
+    /**
+     * the path the way it was selected, without the extended length prefix
+     */
+    fn reported(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.accessed)
+            .map_or_else(|_| path.to_path_buf(), |rest| self.selected.join(rest))
+    }
+}
+
+/**
+ * the effective user and group of the app
+ */
+#[cfg(unix)]
+fn current_owner() -> Option<(u32, u32)> {
+    // SAFETY: both calls only read the credentials of the process and can't fail
+    Some(unsafe { (libc::geteuid(), libc::getegid()) })
+}
+
+#[cfg(not(unix))]
+fn current_owner() -> Option<(u32, u32)> {
+    None
+}
+
+#[cfg(unix)]
+fn platform_warnings(repair: &mut PermissionRepair) {
+    if repair.fixed.iter().any(|fix| fix.kind == FixKind::Owner) {
+        repair
+            .warnings
+            .push("entries owned by another user were taken over by you".to_string());
+    }
+}
+
+#[cfg(not(unix))]
+fn platform_warnings(repair: &mut PermissionRepair) {
+    repair.warnings.push(
+        "only read-only attributes are cleared, owners and access lists are left unchanged"
+            .to_string(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_locked_directory_fixed_then_deleted() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = temp_path("repair-locked");
+        let _ = fs::remove_dir_all(&root);
+        let locked = root.join("locked");
+        fs::create_dir_all(locked.join("inner")).unwrap();
+        fs::write(locked.join("inner/junk.bin"), b"junk").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o500)).unwrap();
+        // root ignores the permission bits, the first attempt removes everything then
+        let privileged = fs::write(locked.join("probe"), b"").is_ok();
+
+        let repair = repair_and_delete(&root, &AtomicBool::new(false), &mut |_| {});
+        assert!(repair.deletion.removed);
+        assert_eq!(repair.deletion.files, 1 + privileged as u64);
+        assert!(!root.exists());
+        if privileged {
+            assert!(repair.fixed.is_empty());
+        } else {
+            assert_eq!(
+                repair.fixed,
+                vec![PermissionFix {
+                    path: locked,
+                    kind: FixKind::Mode,
+                    before: "500".to_string(),
+                    after: "700".to_string(),
+                }]
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_foreign_owner_kept_mode_fixed() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let root = temp_path("repair-foreign");
+        let _ = fs::remove_dir_all(&root);
+        let locked = root.join("locked");
+        fs::create_dir_all(&locked).unwrap();
+        fs::write(locked.join("junk.bin"), b"junk").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o500)).unwrap();
+        // as seen by an unprivileged user other than the owner of the entries
+        let owner = fs::metadata(&root).unwrap().uid();
+        let user = if owner == 1000 { 1001 } else { 1000 };
+
+        let mut repair = PermissionRepair::default();
+        let mut fixer = Fixer {
+            selected: &root,
+            accessed: root.clone(),
+            owner: Some((user, user)),
+            throttle: Throttle::unlimited(),
+            repair: &mut repair,
+        };
+        fixer.walk(&root);
+
+        assert_eq!(
+            repair.fixed,
+            vec![PermissionFix {
+                path: locked.clone(),
+                kind: FixKind::Mode,
+                before: "500".to_string(),
+                after: "700".to_string(),
+            }]
+        );
+        assert!(repair.unfixable.is_empty());
+        assert_eq!(fs::metadata(&locked).unwrap().uid(), owner);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use specta::Type;
+use tracing::Instrument;
+
+use crate::error::{CommandError, CommandResult, Result};
+
+/**
+ * how many finished requests are kept for `get_request_trace`
+ */
+pub const TRACE_CAPACITY: usize = 500;
+
+/**
+ * one timed step inside a request, e.g. listing the volumes for `start_scan`
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SpanTiming {
+    pub name: String,
+    /// milliseconds from the start of the request
+    pub offset_millis: u64,
+    pub millis: u64,
+}
+
+/**
+ * what the backend did for one invoked command, found by the `requestId`
+ * the frontend got with the response or the error
+ */
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestTrace {
+    pub id: u64,
+    pub command: String,
+    /// milliseconds since the epoch
+    pub started_at: u64,
+    /// none while the command is still running
+    pub millis: Option<u64>,
+    pub error: Option<String>,
+    pub spans: Vec<SpanTiming>,
+}
+
+tokio::task_local! {
+    static CURRENT: (Instant, Arc<Mutex<Vec<SpanTiming>>>);
+}
+
+/**
+ * Ids for the invoked commands and the traces of the recent ones. Every log
+ * line written while a command runs carries its id in the `request` span.
+ */
+#[derive(Default)]
+pub struct Requests {
+    next: AtomicU64,
+    traces: Mutex<VecDeque<RequestTrace>>,
+}
+
+impl Requests {
+    /**
+     * run the body of `command` as one request, its error comes back tagged with the id
+     */
+    pub async fn run<T, F>(&self, command: &'static str, body: F) -> CommandResult<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let id = self.next.fetch_add(1, Ordering::Relaxed) + 1;
+        let started = Instant::now();
+        let spans = Arc::new(Mutex::new(Vec::new()));
+        self.push(RequestTrace {
+            id,
+            command: command.to_string(),
+            started_at: epoch_millis(),
+            millis: None,
+            error: None,
+            spans: Vec::new(),
+        });
+
+        let span = tracing::info_span!("request", id, command);
+        let result = CURRENT
+            .scope((started, spans.clone()), body.instrument(span))
+            .await;
+
+        let millis = started.elapsed().as_millis() as u64;
+        let error = result.as_ref().err().map(ToString::to_string);
+        if let Some(error) = error.as_ref() {
+            tracing::warn!(
+                "request {} {} failed after {}ms, {}",
+                id,
+                command,
+                millis,
+                error
+            );
+        }
+        self.update(id, |trace| {
+            trace.millis = Some(millis);
+            trace.error = error;
+            trace.spans = spans.lock().map_or(Vec::new(), |spans| spans.clone());
+        });
+        result.map_err(|error| CommandError::new(error, id))
+    }
+
+    /**
+     * the trace of a recent request, the steps so far while it still runs
+     */
+    pub fn trace(&self, id: u64) -> Option<RequestTrace> {
+        self.traces
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .find(|trace| trace.id == id)
+            .cloned()
+    }
+
+    fn push(&self, trace: RequestTrace) {
+        let mut traces = self.traces.lock().unwrap_or_else(PoisonError::into_inner);
+        if traces.len() == TRACE_CAPACITY {
+            traces.pop_front();
+        }
+        traces.push_back(trace);
+    }
+
+    fn update(&self, id: u64, update: impl FnOnce(&mut RequestTrace)) {
+        let mut traces = self.traces.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(trace) = traces.iter_mut().find(|trace| trace.id == id) {
+            update(trace);
+        }
+    }
+}
+
+/**
+ * time `step` as a span of the running request, outside of a request it only runs
+ */
+pub async fn step<T>(name: &str, step: impl Future<Output = T>) -> T {
+    let Ok((request_started, spans)) = CURRENT.try_with(|current| current.clone()) else {
+        return step.await;
+    };
+
+    let started = Instant::now();
+    let output = step.instrument(tracing::debug_span!("step", name)).await;
+    let _ = spans.lock().map(|mut spans| {
+        spans.push(SpanTiming {
+            name: name.to_string(),
+            offset_millis: started.duration_since(request_started).as_millis() as u64,
+            millis: started.elapsed().as_millis() as u64,
+        })
+    });
+    output
+}
+
+fn epoch_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[tokio::test]
+    async fn test_request_traced_with_steps() {
+        let requests = Requests::default();
+        let listed = requests
+            .run("list", async { Ok(step("read", async { 3 }).await) })
+            .await
+            .unwrap();
+        assert_eq!(listed, 3);
+
+        let trace = requests.trace(1).unwrap();
+        assert_eq!(trace.command, "list");
+        assert!(trace.millis.is_some());
+        assert_eq!(trace.spans.len(), 1);
+        assert_eq!(trace.spans[0].name, "read");
+    }
+
+    #[tokio::test]
+    async fn test_error_carries_request_id() {
+        let requests = Requests::default();
+        let _ = requests.run("first", async { Ok(()) }).await;
+        let failed = requests
+            .run("second", async { Err::<(), _>(Error::Busy("second")) })
+            .await
+            .unwrap_err();
+
+        assert_eq!(failed.request_id, 2);
+        let value = serde_json::to_value(&failed).unwrap();
+        assert_eq!(value["kind"], "busy");
+        assert_eq!(value["requestId"], 2);
+        assert_eq!(
+            requests.trace(2).unwrap().error.as_deref(),
+            Some("second is already running")
+        );
+        assert!(requests.trace(3).is_none());
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError, RwLock, atomic::AtomicBool},
+};
+
+use tracing::{debug, warn};
+
+use crate::{
+    filters::ScanFilters,
+    guard::CommandGuards,
+    service::Scanner,
+    settings::{Settings, SettingsChanged},
+};
+
+/**
+ * scan state owned by a single window
+ */
+pub struct Session {
+    pub scanner: Scanner,
+    pub guards: CommandGuards,
+    /// mount point of the volume the last scan reads, watched by the drive monitor
+    pub volume: Mutex<Option<PathBuf>>,
+    /// the drive monitor cancelled the scan, it must not be reported as complete
+    pub aborted: AtomicBool,
+    /// set by `cancel_cleanup`, the running cleanup stops removing entries
+    pub cleanup_cancelled: AtomicBool,
+}
+
+/**
+ * Scan sessions keyed by window label, so two windows analysing different
+ * drives don't share (and clobber) one scanner.
+ */
+pub struct Sessions {
+    /// what a new session starts with
+    defaults: RwLock<Arc<Settings>>,
+    sessions: RwLock<HashMap<String, Arc<Session>>>,
+}
+
+impl Sessions {
+    pub fn new(defaults: Arc<Settings>) -> Self {
+        Self {
+            defaults: RwLock::new(defaults),
+            sessions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /**
+     * Pick up reloaded settings. The open sessions only take the parts which
+     * changed, so a window tuned with `set_scan_watchdog` keeps its own values
+     * otherwise. The worker count of a live scanner is fixed, it applies to
+     * the sessions created from now on.
+     */
+    pub fn apply_settings(&self, change: &SettingsChanged) {
+        let SettingsChanged { previous, current } = change;
+        *self
+            .defaults
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = current.clone();
+
+        for (_, session) in self.all() {
+            if previous.watchdog != current.watchdog {
+                session.scanner.set_watchdog(current.watchdog);
+            }
+            if previous.retry != current.retry {
+                session.scanner.set_retry_policy(current.retry);
+            }
+            if previous.scan_filters != current.scan_filters {
+                apply_filters(&session.scanner, &current.scan_filters);
+            }
+        }
+    }
+
+    pub fn get_or_create(&self, label: &str) -> Arc<Session> {
+        if let Some(session) = self.get(label) {
+            return session;
+        }
+
+        let mut sessions = self
+            .sessions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        sessions
+            .entry(label.to_string())
+            .or_insert_with(|| {
+                debug!("create scan session for window {}", label);
+                let defaults = self
+                    .defaults
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone();
+                let scanner = Scanner::new(defaults.scan_concurrency.max(1));
+                scanner.set_watchdog(defaults.watchdog);
+                scanner.set_retry_policy(defaults.retry);
+                apply_filters(&scanner, &defaults.scan_filters);
+                Arc::new(Session {
+                    scanner,
+                    guards: CommandGuards::default(),
+                    volume: Mutex::new(None),
+                    aborted: AtomicBool::new(false),
+                    cleanup_cancelled: AtomicBool::new(false),
+                })
+            })
+            .clone()
+    }
+
+    pub fn get(&self, label: &str) -> Option<Arc<Session>> {
+        self.sessions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(label)
+            .cloned()
+    }
+
+    /**
+     * every open session with the label of its window
+     */
+    pub fn all(&self) -> Vec<(String, Arc<Session>)> {
+        self.sessions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(label, session)| (label.clone(), session.clone()))
+            .collect()
+    }
+
+    /**
+     * drop the session of a closed window, its workers are aborted with the scanner
+     */
+    pub fn remove(&self, label: &str) -> Option<Arc<Session>> {
+        debug!("remove scan session for window {}", label);
+        self.sessions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(label)
+    }
+}
+
+/**
+ * the exclude rules of the settings, rules which don't compile are logged and
+ * the ones in effect stay
+ */
+fn apply_filters(scanner: &Scanner, filters: &ScanFilters) {
+    if let Err(err) = scanner.set_filters(filters.clone()) {
+        warn!("scan filters of the settings ignored, {}", err);
+    }
+}
@@ -0,0 +1,365 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::Duration,
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::sync::{
+    broadcast,
+    mpsc::{self, UnboundedReceiver},
+};
+
+use crate::{
+    filters::ScanFilters,
+    hash::{self, HashAlgorithm},
+    hooks::Hook,
+    maintenance, memory,
+    retry::RetryPolicy,
+    watchdog::Watchdog,
+};
+
+/**
+ * name of the settings file inside the app config directory
+ */
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/**
+ * how long the settings file has to stay quiet before it is read again, an
+ * editor may write it in several steps
+ */
+pub const SETTLE_INTERVAL: Duration = Duration::from_millis(300);
+
+/**
+ * Everything a user may tune, read from `settings.json`. Missing fields keep
+ * their default, so an older or hand written file still loads.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    /// workers of a scan, the windows opening a session afterwards get it
+    pub scan_concurrency: usize,
+    /// watchdog a new scan session starts with
+    pub watchdog: Watchdog,
+    /// retry policy a new scan session starts with
+    pub retry: RetryPolicy,
+    /// exclude rules a new scan session starts with
+    pub scan_filters: ScanFilters,
+    /// locations never scanned nor cleaned, on top of the system ones
+    pub protected_paths: Vec<PathBuf>,
+    /// hours between two scheduled maintenance runs
+    pub maintenance_interval_hours: u64,
+    /// how duplicates are confirmed, none for the fastest one on this CPU
+    pub hash_algorithm: Option<HashAlgorithm>,
+    /// megabytes the tree of a scan may take before the user is warned, none for a quarter of the RAM
+    pub memory_limit_mb: Option<u64>,
+    /// commands run before a scan and after a cleanup, in this order
+    pub hooks: Vec<Hook>,
+    /// local port serving the Prometheus metrics at `/metrics`, none keeps them off
+    pub metrics_port: Option<u16>,
+    /// megabytes a second a copy, move or archive may read and write, none for no cap
+    pub io_limit_mb_per_sec: Option<u64>,
+    /// folders a backup goes in below its destination, none for `{hostname}/{date}/{source-name}`
+    pub backup_template: Option<String>,
+    /// send the windows the summary of a week once it is over
+    pub weekly_summary_notification: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            scan_concurrency: 20,
+            watchdog: Watchdog::default(),
+            retry: RetryPolicy::default(),
+            scan_filters: ScanFilters::default(),
+            protected_paths: Vec::new(),
+            maintenance_interval_hours: maintenance::DEFAULT_INTERVAL_HOURS,
+            hash_algorithm: None,
+            memory_limit_mb: None,
+            hooks: Vec::new(),
+            metrics_port: None,
+            io_limit_mb_per_sec: None,
+            backup_template: None,
+            weekly_summary_notification: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn maintenance_interval(&self) -> Duration {
+        Duration::from_secs(self.maintenance_interval_hours.max(1) * 60 * 60)
+    }
+
+    /**
+     * bytes the tree of a scan may take on a machine with `total_memory` bytes of RAM
+     */
+    pub fn memory_limit(&self, total_memory: u64) -> u64 {
+        self.memory_limit_mb.map_or_else(
+            || memory::default_limit(total_memory),
+            |mb| mb * 1024 * 1024,
+        )
+    }
+
+    /**
+     * the algorithm hashes are confirmed with, picked by the detected CPU features unless chosen
+     */
+    pub fn content_hash(&self) -> HashAlgorithm {
+        self.hash_algorithm
+            .unwrap_or_else(|| HashAlgorithm::preferred(hash::acceleration()))
+    }
+}
+
+/**
+ * the settings before and after a reload, subscribers compare the part they own
+ */
+#[derive(Debug, Clone)]
+pub struct SettingsChanged {
+    pub previous: Arc<Settings>,
+    pub current: Arc<Settings>,
+}
+
+/**
+ * The settings in effect and the file they come from. A reload and a save
+ * are serialized, subscribers get every change exactly once whoever made it.
+ */
+pub struct SettingsStore {
+    /// none when the config directory is unavailable, settings then only live in memory
+    file: Option<PathBuf>,
+    current: RwLock<Arc<Settings>>,
+    /// contents of the file as last read or written, an unchanged file is not parsed again
+    seen: Mutex<Option<String>>,
+    changes: broadcast::Sender<SettingsChanged>,
+}
+
+impl SettingsStore {
+    /**
+     * load the settings of `file`, the defaults when it is missing or malformed
+     */
+    pub fn open(file: PathBuf) -> Self {
+        let store = Self::new(Some(file));
+        if let Err(err) = store.reload() {
+            tracing::warn!("load settings failed, defaults are used, {}", err);
+        }
+        store
+    }
+
+    pub fn in_memory() -> Self {
+        Self::new(None)
+    }
+
+    fn new(file: Option<PathBuf>) -> Self {
+        Self {
+            file,
+            current: RwLock::new(Arc::new(Settings::default())),
+            seen: Mutex::new(None),
+            changes: broadcast::channel(16).0,
+        }
+    }
+
+    pub fn current(&self) -> Arc<Settings> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SettingsChanged> {
+        self.changes.subscribe()
+    }
+
+    /**
+     * read the file again and apply it when it changed, true when the settings did.
+     * A malformed file keeps the settings in effect, the edit may still be under way.
+     */
+    pub fn reload(&self) -> io::Result<bool> {
+        let Some(file) = self.file.as_ref() else {
+            return Ok(false);
+        };
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        let contents = match fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        if seen.as_ref() == Some(&contents) {
+            return Ok(false);
+        }
+        *seen = Some(contents.clone());
+
+        let settings = if contents.trim().is_empty() {
+            Settings::default()
+        } else {
+            serde_json::from_str(&contents).map_err(io::Error::other)?
+        };
+        Ok(self.apply(settings))
+    }
+
+    /**
+     * write `settings` to the file and apply them, true when they changed
+     */
+    pub fn save(&self, settings: Settings) -> io::Result<bool> {
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(file) = self.file.as_ref() {
+            let contents = serde_json::to_string_pretty(&settings).map_err(io::Error::other)?;
+            if let Some(dir) = file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            // renamed into place, a reader never sees half of the file
+            let partial = file.with_extension("json.partial");
+            fs::write(&partial, &contents)?;
+            fs::rename(&partial, file)?;
+            *seen = Some(contents);
+        }
+        Ok(self.apply(settings))
+    }
+
+    /**
+     * Watch the file for edits made outside of `set_settings`, none when the
+     * settings only live in memory. Its directory is watched rather than the
+     * file, an editor or `save` replaces the file by renaming another onto it.
+     */
+    pub fn watch(&self) -> io::Result<Option<SettingsWatch>> {
+        let Some(file) = self.file.clone() else {
+            return Ok(None);
+        };
+        let Some(dir) = file.parent().map(PathBuf::from) else {
+            return Ok(None);
+        };
+        fs::create_dir_all(&dir)?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let name = file.file_name().map(ToOwned::to_owned);
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let edited = match event {
+                    // a lost event may have been an edit
+                    Err(_) => true,
+                    Ok(event) => {
+                        event.need_rescan()
+                            || (!matches!(event.kind, EventKind::Access(_))
+                                && event
+                                    .paths
+                                    .iter()
+                                    .any(|path| path.file_name() == name.as_deref()))
+                    }
+                };
+                if edited {
+                    let _ = tx.send(());
+                }
+            })
+            .map_err(io::Error::other)?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(io::Error::other)?;
+        Ok(Some(SettingsWatch {
+            _watcher: watcher,
+            edits: rx,
+        }))
+    }
+
+    fn apply(&self, settings: Settings) -> bool {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        if **current == settings {
+            return false;
+        }
+
+        let change = SettingsChanged {
+            previous: current.clone(),
+            current: Arc::new(settings),
+        };
+        *current = change.current.clone();
+        // nobody listening is fine, e.g. while the app starts
+        let _ = self.changes.send(change);
+        true
+    }
+}
+
+/**
+ * the edits of the settings file, they stop when it is dropped
+ */
+pub struct SettingsWatch {
+    _watcher: RecommendedWatcher,
+    edits: UnboundedReceiver<()>,
+}
+
+impl SettingsWatch {
+    /**
+     * wait for an edit, then until the file stayed untouched for `SETTLE_INTERVAL`
+     */
+    pub async fn next_edit(&mut self) -> Option<()> {
+        self.edits.recv().await?;
+        loop {
+            match tokio::time::timeout(SETTLE_INTERVAL, self.edits.recv()).await {
+                Ok(Some(())) => continue,
+                Ok(None) => return None,
+                Err(_) => return Some(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[test]
+    fn test_external_edit_is_broadcast() {
+        let file = temp_path("settings-edit.json");
+        let _ = fs::remove_file(&file);
+        let store = SettingsStore::open(file.clone());
+        let mut changes = store.subscribe();
+        assert_eq!(*store.current(), Settings::default());
+
+        fs::write(
+            &file,
+            r#"{ "scanConcurrency": 4, "protectedPaths": ["/srv"] }"#,
+        )
+        .unwrap();
+        assert!(store.reload().unwrap());
+        assert!(!store.reload().unwrap());
+
+        let change = changes.try_recv().unwrap();
+        assert_eq!(change.previous.scan_concurrency, 20);
+        assert_eq!(change.current.scan_concurrency, 4);
+        assert_eq!(change.current.protected_paths, vec![PathBuf::from("/srv")]);
+        assert_eq!(change.current.maintenance_interval_hours, 24);
+        assert!(changes.try_recv().is_err());
+        let _ = fs::remove_file(&file);
+    }
+
+    #[test]
+    fn test_malformed_file_keeps_settings() {
+        let file = temp_path("settings-malformed.json");
+        let _ = fs::remove_file(&file);
+        let store = SettingsStore::open(file.clone());
+        let mut settings = Settings::default();
+        settings.maintenance_interval_hours = 6;
+        assert!(store.save(settings.clone()).unwrap());
+        assert!(!store.reload().unwrap());
+
+        fs::write(&file, r#"{ "scanConcurrency": "#).unwrap();
+        assert!(store.reload().is_err());
+        assert_eq!(*store.current(), settings);
+        let _ = fs::remove_file(&file);
+    }
+
+    #[tokio::test]
+    async fn test_edit_seen_by_watch() {
+        let file = temp_path("settings-watched.json");
+        let _ = fs::remove_file(&file);
+        let store = SettingsStore::open(file.clone());
+        let mut watch = store.watch().unwrap().unwrap();
+
+        fs::write(&file, r#"{ "scanConcurrency": 3 }"#).unwrap();
+        let edit = tokio::time::timeout(Duration::from_secs(5), watch.next_edit()).await;
+        assert_eq!(edit, Ok(Some(())));
+        assert!(store.reload().unwrap());
+        assert_eq!(store.current().scan_concurrency, 3);
+        assert!(SettingsStore::in_memory().watch().unwrap().is_none());
+        let _ = fs::remove_file(&file);
+    }
+}
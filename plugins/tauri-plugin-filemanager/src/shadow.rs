@@ -0,0 +1,181 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::models::ShadowStorage;
+
+/**
+ * WMI reports an unbounded limit as the largest 64 bit value
+ */
+#[cfg_attr(not(windows), allow(dead_code))]
+const UNBOUNDED: u64 = u64::MAX;
+
+/**
+ * one line per volume with shadow storage: its mount path, the used, allocated
+ * and maximum bytes, and how many shadow copies it holds
+ */
+#[cfg(windows)]
+const QUERY: &str = r#"
+$copies = @(Get-CimInstance Win32_ShadowCopy)
+$volumes = @(Get-CimInstance Win32_Volume)
+Get-CimInstance Win32_ShadowStorage | ForEach-Object {
+    $id = $_.Volume.DeviceID
+    $volume = $volumes | Where-Object DeviceID -eq $id | Select-Object -First 1
+    $count = @($copies | Where-Object VolumeName -eq $id).Count
+    "{0}`t{1}`t{2}`t{3}`t{4}" -f $volume.Name, $_.UsedSpace, $_.AllocatedSpace, $_.MaxSpace, $count
+}
+"#;
+
+/**
+ * the shadow storage of every volume which has some, by mount path
+ */
+#[cfg(windows)]
+pub fn measure_all() -> io::Result<HashMap<PathBuf, ShadowStorage>> {
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", QUERY])
+        .output()?;
+
+    if output.status.success() {
+        Ok(parse(&String::from_utf8_lossy(&output.stdout)))
+    } else {
+        Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+#[cfg(not(windows))]
+pub fn measure_all() -> io::Result<HashMap<PathBuf, ShadowStorage>> {
+    Ok(HashMap::new())
+}
+
+// only measured on windows, tested everywhere
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse(output: &str) -> HashMap<PathBuf, ShadowStorage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let volume = fields.next().filter(|volume| !volume.is_empty())?;
+            let mut number = || fields.next()?.trim().parse::<u64>().ok();
+            let (used, allocated, maximum, copies) = (number()?, number()?, number()?, number()?);
+            Some((
+                PathBuf::from(volume),
+                ShadowStorage {
+                    used,
+                    allocated,
+                    maximum: (maximum != UNBOUNDED).then_some(maximum),
+                    copies: copies as usize,
+                },
+            ))
+        })
+        .collect()
+}
+
+/**
+ * `C:` for the volume mounted at `C:\`, the way vssadmin names it
+ */
+fn volume_arg(volume: &Path) -> io::Result<String> {
+    let name = volume.to_string_lossy();
+    let name = name.trim_end_matches('\\');
+    if name.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a volume", volume.display()),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/**
+ * Limit the shadow storage of `volume` to `max_size` bytes, Windows drops the
+ * oldest restore points to fit. Needs an elevated process.
+ */
+#[cfg(windows)]
+pub fn resize(volume: &Path, max_size: u64) -> io::Result<()> {
+    let volume = volume_arg(volume)?;
+    vssadmin(&[
+        "resize",
+        "shadowstorage",
+        &format!("/For={}", volume),
+        &format!("/On={}", volume),
+        &format!("/MaxSize={}", max_size),
+    ])
+}
+
+/**
+ * delete the oldest shadow copy of `volume`, needs an elevated process
+ */
+#[cfg(windows)]
+pub fn delete_oldest(volume: &Path) -> io::Result<()> {
+    let volume = volume_arg(volume)?;
+    vssadmin(&[
+        "delete",
+        "shadows",
+        &format!("/For={}", volume),
+        "/Oldest",
+        "/Quiet",
+    ])
+}
+
+#[cfg(windows)]
+fn vssadmin(args: &[&str]) -> io::Result<()> {
+    let output = std::process::Command::new("vssadmin").args(args).output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        // vssadmin explains itself on stdout
+        let message = String::from_utf8_lossy(&output.stdout);
+        let message = message.lines().last().unwrap_or_default().trim();
+        Err(io::Error::other(message.to_string()))
+    }
+}
+
+#[cfg(not(windows))]
+pub fn resize(volume: &Path, _max_size: u64) -> io::Result<()> {
+    volume_arg(volume)?;
+    Err(unsupported())
+}
+
+#[cfg(not(windows))]
+pub fn delete_oldest(volume: &Path) -> io::Result<()> {
+    volume_arg(volume)?;
+    Err(unsupported())
+}
+
+#[cfg(not(windows))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "shadow copies only exist on Windows",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shadow_storage_parsed() {
+        let output = "C:\\\t2201170739\t2673868800\t10000000000\t3\r\n\
+            D:\\\t0\t0\t18446744073709551615\t0\r\n\
+            \t1\t2\t3\t4\r\n";
+        let storage = parse(output);
+        assert_eq!(storage.len(), 2);
+        assert_eq!(
+            storage[Path::new("C:\\")],
+            ShadowStorage {
+                used: 2201170739,
+                allocated: 2673868800,
+                maximum: Some(10000000000),
+                copies: 3,
+            }
+        );
+        assert_eq!(storage[Path::new("D:\\")].maximum, None);
+
+        assert_eq!(volume_arg(Path::new("C:\\")).unwrap(), "C:");
+        assert!(volume_arg(Path::new("")).is_err());
+    }
+}
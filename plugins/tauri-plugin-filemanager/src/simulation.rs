@@ -0,0 +1,234 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    cleanup::{self, CleanupFailure},
+    cost::{Operation, OperationEstimate, Throughput},
+    lossless::PathArg,
+    models::Volumn,
+    store::{FoundFile, ScanStore},
+    targets,
+};
+
+/**
+ * entries of the before and after top lists
+ */
+pub const TOP_LIMIT: usize = 10;
+
+/**
+ * what a cleanup would remove, `root` bounds the top lists and defaults to the
+ * root of the scan
+ */
+#[derive(Debug, Clone, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupPlan {
+    pub paths: Vec<PathArg>,
+    pub root: Option<PathArg>,
+}
+
+/**
+ * free space of a volume before and after the simulated cleanup
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeDiff {
+    pub volume: PathBuf,
+    pub freed: u64,
+    pub available_before: u64,
+    pub available_after: u64,
+}
+
+/**
+ * a directory which would shrink, with its size on both sides
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryDiff {
+    pub path: PathBuf,
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+/**
+ * The cleanup of a plan replayed against the scanned tree, nothing on the
+ * disk is touched. The same selections are removed or refused as `run_cleanup`
+ * would, except that a path outside the scan is refused instead of measured.
+ */
+#[derive(Debug, Clone, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupSimulation {
+    pub removed: Vec<PathBuf>,
+    pub refused: Vec<CleanupFailure>,
+    pub freed: u64,
+    pub volumes: Vec<VolumeDiff>,
+    /// every ancestor of a removed path, the most shrunk first
+    pub directories: Vec<DirectoryDiff>,
+    pub largest_before: Vec<FoundFile>,
+    pub largest_after: Vec<FoundFile>,
+    /// how long the removal would take on this device, none when nothing is removed
+    pub estimate: Option<OperationEstimate>,
+}
+
+pub fn simulate(
+    store: &dyn ScanStore,
+    root: &PathBuf,
+    paths: &[PathBuf],
+    volumes: &[Volumn],
+    throughput: &Throughput,
+) -> CleanupSimulation {
+    let mut simulation = CleanupSimulation::default();
+    let mut freed_in: HashMap<PathBuf, u64> = HashMap::new();
+    let mut freed_on: BTreeMap<PathBuf, u64> = BTreeMap::new();
+
+    for path in cleanup::outermost(paths) {
+        if targets::is_protected(&path) {
+            simulation.refused.push(CleanupFailure {
+                path,
+                reason: "protected system location".to_string(),
+            });
+            continue;
+        }
+        let Some(size) = store.size_of(&path).map(|size| size as u64) else {
+            simulation.refused.push(CleanupFailure {
+                path,
+                reason: "not in the scan".to_string(),
+            });
+            continue;
+        };
+
+        for ancestor in path.ancestors().skip(1) {
+            *freed_in.entry(ancestor.to_path_buf()).or_default() += size;
+        }
+        if let Some(volume) = cleanup::volume_of(&path, volumes) {
+            *freed_on.entry(volume.path.clone()).or_default() += size;
+        }
+        simulation.freed += size;
+        simulation.removed.push(path);
+    }
+
+    simulation.volumes = volumes
+        .iter()
+        .filter_map(|volume| {
+            let freed = *freed_on.get(&volume.path)?;
+            Some(VolumeDiff {
+                volume: volume.path.clone(),
+                freed,
+                available_before: volume.available_size,
+                available_after: volume.available_size.saturating_add(freed),
+            })
+        })
+        .collect();
+
+    simulation.directories = freed_in
+        .into_iter()
+        .filter_map(|(path, freed)| {
+            let size_before = store.size_of(&path)? as u64;
+            Some(DirectoryDiff {
+                path,
+                size_before,
+                size_after: size_before.saturating_sub(freed),
+            })
+        })
+        .collect();
+    simulation.directories.sort_by(|a, b| {
+        (b.size_before - b.size_after)
+            .cmp(&(a.size_before - a.size_after))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    simulation.largest_before = store.largest_files(root, TOP_LIMIT);
+    simulation.largest_after = store.largest_files_except(root, &simulation.removed, TOP_LIMIT);
+
+    if !simulation.removed.is_empty() {
+        let mut entries = 0;
+        for path in &simulation.removed {
+            store.walk(path, &mut |_, _| entries += 1);
+        }
+        simulation.estimate =
+            Some(throughput.estimate(Operation::Cleanup, simulation.freed, entries));
+    }
+    simulation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{Tree, node::Node};
+    use std::ffi::OsString;
+
+    fn file(name: &str, size: usize) -> Node {
+        let node = Node::new(OsString::from(name), false, false);
+        node.set_size(size);
+        node
+    }
+
+    fn volume(path: &str, available_size: u64) -> Volumn {
+        Volumn {
+            name: path.to_string(),
+            display_name: path.to_string(),
+            path: PathBuf::from(path),
+            total_size: 10_000,
+            available_size,
+            file_system: "apfs".to_string(),
+            capabilities: None,
+            shadow_storage: None,
+            inodes: None,
+        }
+    }
+
+    #[test]
+    fn test_simulated_cleanup() {
+        let root = PathBuf::from("/");
+        let mut tree = Tree::from_node(Node::new(OsString::from("/"), true, false));
+        let _ = tree.insert(&root, Node::new(OsString::from("cache"), true, false));
+        let _ = tree.insert(&PathBuf::from("/cache"), file("big.bin", 700));
+        let _ = tree.insert(&PathBuf::from("/cache"), file("small.bin", 20));
+        let _ = tree.insert(&root, file("keep.zip", 300));
+
+        let simulation = simulate(
+            &tree,
+            &root,
+            &[
+                PathBuf::from("/cache"),
+                PathBuf::from("/cache/big.bin"),
+                PathBuf::from("/missing"),
+            ],
+            &[volume("/", 1_000)],
+            &Throughput::default(),
+        );
+
+        assert_eq!(simulation.removed, vec![PathBuf::from("/cache")]);
+        assert_eq!(simulation.refused[0].path, PathBuf::from("/missing"));
+        assert_eq!(simulation.freed, 720);
+        assert_eq!(
+            simulation.volumes,
+            vec![VolumeDiff {
+                volume: PathBuf::from("/"),
+                freed: 720,
+                available_before: 1_000,
+                available_after: 1_720,
+            }]
+        );
+        assert_eq!(
+            simulation.directories,
+            vec![DirectoryDiff {
+                path: root.clone(),
+                size_before: 1_020,
+                size_after: 300,
+            }]
+        );
+        assert_eq!(
+            simulation.largest_before[0].path,
+            PathBuf::from("/cache/big.bin")
+        );
+        assert_eq!(simulation.largest_after.len(), 1);
+        assert_eq!(simulation.largest_after[0].path, PathBuf::from("/keep.zip"));
+        // the directory and its two files
+        assert_eq!(simulation.estimate.unwrap().entries, 3);
+    }
+}
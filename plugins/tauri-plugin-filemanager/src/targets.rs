@@ -0,0 +1,176 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{PoisonError, RwLock},
+};
+
+use crate::{
+    models::{RejectedTarget, ScanTargets, TargetRejection},
+    path_key::PathKey,
+};
+
+/**
+ * system locations which are never offered as scan targets
+ */
+#[cfg(target_os = "macos")]
+const PROTECTED_PATHS: &[&str] = &["/System", "/dev", "/private/var/vm"];
+#[cfg(target_os = "linux")]
+const PROTECTED_PATHS: &[&str] = &["/proc", "/sys", "/dev", "/run"];
+#[cfg(target_os = "windows")]
+const PROTECTED_PATHS: &[&str] = &["C:\\Windows", "C:\\System Volume Information"];
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+const PROTECTED_PATHS: &[&str] = &[];
+
+/**
+ * locations the user protected in the settings, replaced on every reload
+ */
+static USER_PROTECTED: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+
+pub fn set_user_protected(paths: Vec<PathBuf>) {
+    *USER_PROTECTED
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = paths;
+}
+
+/**
+ * whether `path` is at or below a protected location. Both sides go through
+ * `PathKey`, so the `\\?\` form `fs::canonicalize` returns on Windows and a
+ * different case on case-insensitive platforms still match.
+ */
+pub fn is_protected(path: &Path) -> bool {
+    let key = PathKey::new(path);
+    PROTECTED_PATHS
+        .iter()
+        .any(|protected| key.starts_with(&PathKey::new(Path::new(protected))))
+        || USER_PROTECTED
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .any(|protected| key.starts_with(&PathKey::new(protected)))
+}
+
+fn validate(path: &Path) -> Result<PathBuf, TargetRejection> {
+    let path = fs::canonicalize(path).map_err(|_| TargetRejection::NotFound)?;
+    if is_protected(&path) {
+        return Err(TargetRejection::Protected);
+    }
+
+    let readable = if path.is_dir() {
+        fs::read_dir(&path).is_ok()
+    } else {
+        fs::File::open(&path).is_ok()
+    };
+
+    if readable {
+        Ok(path)
+    } else {
+        Err(TargetRejection::Unreadable)
+    }
+}
+
+/**
+ * validate user supplied paths (dropped or picked) and reduce them to a
+ * normalized list without entries nested inside another selected directory
+ */
+pub fn prepare_scan_targets(paths: &[PathBuf]) -> ScanTargets {
+    let mut valid: Vec<PathBuf> = vec![];
+    let mut rejected: Vec<RejectedTarget> = vec![];
+
+    for path in paths {
+        match validate(path) {
+            Ok(path) => valid.push(path),
+            Err(reason) => rejected.push(RejectedTarget {
+                path: path.clone(),
+                reason,
+            }),
+        }
+    }
+
+    // components sort parents right before their descendants
+    valid.sort();
+    valid.dedup();
+
+    let mut targets: Vec<PathBuf> = vec![];
+    for path in valid {
+        if targets
+            .last()
+            .is_some_and(|parent| path.starts_with(parent))
+        {
+            rejected.push(RejectedTarget {
+                path,
+                reason: TargetRejection::Nested,
+            });
+        } else {
+            targets.push(path);
+        }
+    }
+
+    ScanTargets { targets, rejected }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+
+    #[test]
+    fn test_nested_targets_deduplicated() {
+        let root = temp_path("targets-nested");
+        let child = root.join("child");
+        let sibling = temp_path("targets-sibling");
+        fs::create_dir_all(&child).unwrap();
+        fs::create_dir_all(&sibling).unwrap();
+
+        let ret =
+            prepare_scan_targets(&[child.clone(), root.clone(), sibling.clone(), root.clone()]);
+        assert_eq!(
+            ret.targets,
+            vec![
+                fs::canonicalize(&root).unwrap(),
+                fs::canonicalize(&sibling).unwrap()
+            ]
+        );
+        assert_eq!(ret.rejected.len(), 2);
+        assert!(
+            ret.rejected
+                .iter()
+                .all(|item| item.reason == TargetRejection::Nested)
+        );
+    }
+
+    #[test]
+    fn test_missing_target_rejected() {
+        let missing = PathBuf::from("/no/such/cleaner/target");
+        let ret = prepare_scan_targets(&[missing.clone()]);
+        assert!(ret.targets.is_empty());
+        assert_eq!(ret.rejected[0].path, missing);
+        assert_eq!(ret.rejected[0].reason, TargetRejection::NotFound);
+    }
+
+    #[test]
+    fn test_user_protected_paths() {
+        let protected = PathBuf::from("/cleaner-user-protected");
+        assert!(!is_protected(&protected.join("photos")));
+        set_user_protected(vec![protected.clone()]);
+        assert!(is_protected(&protected.join("photos")));
+        assert!(is_protected(Path::new(
+            "/cleaner-user-protected/../cleaner-user-protected/x"
+        )));
+        set_user_protected(Vec::new());
+        assert!(!is_protected(&protected));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_verbatim_system_path_protected() {
+        assert!(is_protected(Path::new(r"\\?\C:\Windows\System32")));
+        assert!(is_protected(Path::new(r"c:\windows")));
+        assert!(!is_protected(Path::new(r"\\?\C:\Users")));
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_system_path_protected_in_any_case() {
+        assert!(is_protected(Path::new("/system/Library")));
+    }
+}
@@ -0,0 +1,339 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(unix)]
+use std::process::Command;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::Serialize;
+use specta::Type;
+
+use crate::{dashboard::Category, long_path};
+
+/**
+ * disk space the thumbnails may take before maintenance evicts the least recently used
+ */
+pub const DEFAULT_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+/**
+ * edge lengths a thumbnail is rendered at, a request is rounded up to the next
+ * one so a list and a grid share the cached files
+ */
+const SIZES: [u32; 5] = [64, 128, 256, 512, 1024];
+
+/**
+ * renders in flight, for unique scratch directories
+ */
+static RENDERS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ThumbnailKind {
+    Image,
+    Video,
+    Pdf,
+}
+
+impl ThumbnailKind {
+    /**
+     * by extension, none for a file no thumbnail is made of
+     */
+    pub fn of(path: &Path) -> Option<ThumbnailKind> {
+        let is_pdf = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"));
+        match Category::of(path) {
+            Category::Image => Some(ThumbnailKind::Image),
+            Category::Video => Some(ThumbnailKind::Video),
+            _ if is_pdf => Some(ThumbnailKind::Pdf),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Thumbnail {
+    pub kind: ThumbnailKind,
+    /// the edge length it was rendered at, the longer side fits in it
+    pub size: u32,
+    /// a PNG as base64, ready for a `data:image/png;base64,` url
+    pub data: String,
+    /// served from the disk cache without rendering
+    pub cached: bool,
+}
+
+/**
+ * Renders thumbnails with the tools of the platform and keeps them as PNG
+ * files in the app cache. A file is keyed by the path, its size and mtime,
+ * so an edited file gets a new thumbnail and the old one ages out.
+ */
+#[derive(Debug, Clone)]
+pub struct Thumbnailer {
+    dir: PathBuf,
+}
+
+impl Thumbnailer {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /**
+     * the thumbnail of `path` fitting in `size`, rendered on the first request
+     * and read from the cache after that, blocking
+     */
+    pub fn get(&self, path: &Path, size: u32) -> io::Result<Thumbnail> {
+        let kind = ThumbnailKind::of(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("no thumbnail for {}", path.display()),
+            )
+        })?;
+        let size = bucket(size);
+        let metadata = fs::metadata(long_path::extended(path))?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |modified| modified.as_secs());
+        let file = self.file_for(path, size, metadata.len(), modified);
+
+        let cached = file.is_file();
+        if cached {
+            // maintenance evicts by mtime, a thumbnail in use stays
+            let _ = fs::File::options()
+                .append(true)
+                .open(&file)
+                .and_then(|opened| opened.set_modified(SystemTime::now()));
+        } else {
+            fs::create_dir_all(&self.dir)?;
+            self.render(path, kind, size, &file)?;
+        }
+
+        Ok(Thumbnail {
+            kind,
+            size,
+            data: STANDARD.encode(fs::read(&file)?),
+            cached,
+        })
+    }
+
+    fn file_for(&self, path: &Path, size: u32, len: u64, modified: u64) -> PathBuf {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(path.as_os_str().as_encoded_bytes());
+        hasher.update(&len.to_le_bytes());
+        hasher.update(&modified.to_le_bytes());
+        let digest = hasher.finalize();
+        self.dir
+            .join(format!("{}-{}.png", &digest.to_hex()[..32], size))
+    }
+
+    /**
+     * render into a scratch directory first, a half written file must never
+     * be served from the cache
+     */
+    fn render(&self, path: &Path, kind: ThumbnailKind, size: u32, file: &Path) -> io::Result<()> {
+        let scratch = self.dir.join(format!(
+            "render-{}-{}",
+            std::process::id(),
+            RENDERS.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&scratch)?;
+        let rendered =
+            render_platform(path, kind, size, &scratch).and_then(|png| fs::rename(png, file));
+        let _ = fs::remove_dir_all(&scratch);
+        rendered
+    }
+
+    /**
+     * Delete the least recently used thumbnails until the rest fit in
+     * `max_bytes`, and scratch files left by a crash. Returns the files and
+     * bytes removed.
+     */
+    pub fn evict(&self, max_bytes: u64) -> io::Result<(usize, u64)> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            // nothing rendered yet
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((0, 0)),
+            Err(err) => return Err(err),
+        };
+
+        let (mut removed, mut bytes) = (0, 0);
+        let mut files: Vec<(PathBuf, SystemTime, u64)> = vec![];
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with("render-") {
+                let _ = fs::remove_dir_all(entry.path());
+                continue;
+            }
+            if !name.ends_with(".png") {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let used = metadata.modified().unwrap_or(UNIX_EPOCH);
+            files.push((entry.path(), used, metadata.len()));
+        }
+        // most recently used first, whatever doesn't fit after them goes
+        files.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let mut total = 0;
+        for (path, _, size) in files {
+            total += size;
+            if total > max_bytes && fs::remove_file(&path).is_ok() {
+                removed += 1;
+                bytes += size;
+            }
+        }
+        Ok((removed, bytes))
+    }
+}
+
+fn bucket(size: u32) -> u32 {
+    SIZES
+        .into_iter()
+        .find(|bucket| *bucket >= size)
+        .unwrap_or(SIZES[SIZES.len() - 1])
+}
+
+#[cfg(unix)]
+fn run(command: &mut Command) -> io::Result<()> {
+    let output = command.output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/**
+ * Quick Look renders images, videos and PDFs alike, it names the PNG after the file
+ */
+#[cfg(target_os = "macos")]
+fn render_platform(
+    path: &Path,
+    _kind: ThumbnailKind,
+    size: u32,
+    scratch: &Path,
+) -> io::Result<PathBuf> {
+    run(Command::new("qlmanage")
+        .arg("-t")
+        .arg("-s")
+        .arg(size.to_string())
+        .arg("-o")
+        .arg(scratch)
+        .arg(path))?;
+    fs::read_dir(scratch)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|png| png.extension().is_some_and(|extension| extension == "png"))
+        .ok_or_else(|| io::Error::other(format!("no thumbnail rendered for {}", path.display())))
+}
+
+/**
+ * ImageMagick, ffmpegthumbnailer and poppler, whichever the distribution has installed
+ */
+#[cfg(all(unix, not(target_os = "macos")))]
+fn render_platform(
+    path: &Path,
+    kind: ThumbnailKind,
+    size: u32,
+    scratch: &Path,
+) -> io::Result<PathBuf> {
+    let png = scratch.join("thumbnail.png");
+    match kind {
+        ThumbnailKind::Image => {
+            // the first frame of an animation or the first page of a tiff
+            let mut source = path.as_os_str().to_owned();
+            source.push("[0]");
+            run(Command::new("convert")
+                .arg(source)
+                .arg("-auto-orient")
+                .arg("-thumbnail")
+                .arg(format!("{0}x{0}", size))
+                .arg(&png))?
+        }
+        ThumbnailKind::Video => run(Command::new("ffmpegthumbnailer")
+            .arg("-i")
+            .arg(path)
+            .arg("-o")
+            .arg(&png)
+            .arg("-s")
+            .arg(size.to_string()))?,
+        // pdftoppm appends the extension itself
+        ThumbnailKind::Pdf => run(Command::new("pdftoppm")
+            .args(["-png", "-singlefile", "-f", "1", "-scale-to"])
+            .arg(size.to_string())
+            .arg(path)
+            .arg(scratch.join("thumbnail")))?,
+    }
+    Ok(png)
+}
+
+#[cfg(not(unix))]
+fn render_platform(
+    _path: &Path,
+    _kind: ThumbnailKind,
+    _size: u32,
+    _scratch: &Path,
+) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "thumbnails are only rendered on macOS and Linux",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::temp_path;
+    use std::time::Duration;
+
+    #[test]
+    fn test_sizes_bucketed() {
+        assert_eq!(bucket(0), 64);
+        assert_eq!(bucket(100), 128);
+        assert_eq!(bucket(256), 256);
+        assert_eq!(bucket(4000), 1024);
+        assert_eq!(
+            ThumbnailKind::of(Path::new("/a/Scan.PDF")),
+            Some(ThumbnailKind::Pdf)
+        );
+        assert_eq!(
+            ThumbnailKind::of(Path::new("/a/clip.mov")),
+            Some(ThumbnailKind::Video)
+        );
+        assert_eq!(ThumbnailKind::of(Path::new("/a/notes.txt")), None);
+    }
+
+    #[test]
+    fn test_least_recently_used_evicted() {
+        let dir = temp_path("thumbnails-evict");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("render-1-0")).unwrap();
+        let now = SystemTime::now();
+        for (name, age) in [("old.png", 30), ("recent.png", 10), ("newest.png", 0)] {
+            let file = fs::File::create(dir.join(name)).unwrap();
+            file.set_len(100).unwrap();
+            file.set_modified(now - Duration::from_secs(age)).unwrap();
+        }
+
+        let thumbnails = Thumbnailer::new(dir.clone());
+        assert_eq!(thumbnails.evict(250).unwrap(), (1, 100));
+        assert!(!dir.join("old.png").exists());
+        assert!(dir.join("recent.png").exists());
+        assert!(!dir.join("render-1-0").exists());
+
+        let missing = Thumbnailer::new(dir.join("missing"));
+        assert_eq!(missing.evict(0).unwrap(), (0, 0));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
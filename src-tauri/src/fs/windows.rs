@@ -1,6 +0,0 @@
-use std::{fs::DirEntry, path::Path};
-
-pub(super) fn read_dir(path: &Path) -> Option<Vec<DirEntry>> {
-    let mut vec = vec![];
-    Some(vec)
-}
@@ -31,7 +31,7 @@ const fs = {
 
         var typedResult: ResultItem[] = [];
         try {
-            typedResult = await invoke('get_available_drivers', {});
+            typedResult = await invoke('plugin:filemanager|get_available_drivers', {});
             console.log('fs.getAvailableDrivers result:', { typedResult });
         } catch (error) {
             console.error('getAvailableDrivers failed:', error);
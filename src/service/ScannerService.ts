@@ -1,3 +1,4 @@
+import { EventEnvelope } from 'tauri-plugin-filemanager-api';
 import { FileInfo } from '@/types/fs';
 import { invoke } from '@tauri-apps/api/core';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
@@ -67,14 +68,16 @@ export class ScannerService {
 
             // 设置完成监听器
             if (onComplete) {
-                const unlistenComplete = await listen<string>('folder-scan-complete', (event) => {
-                    onComplete(event.payload);
+                const unlistenComplete = await listen<EventEnvelope>('folder-scan-complete', (event) => {
+                    if (event.payload.kind === 'folder-scan-complete') {
+                        onComplete(event.payload.message);
+                    }
                 });
                 this.completeListeners.push(unlistenComplete);
             }
 
             // 开始扫描
-            await invoke('start_scan', { path: '/' });
+            await invoke('plugin:filemanager|start_scan', { path: '/' });
             return true;
         } catch (error) {
             console.error('启动文件夹扫描失败:', error);
@@ -88,7 +91,7 @@ export class ScannerService {
     static async stopScan(): Promise<boolean> {
         try {
             console.log('call stop scan from vue');
-            await invoke('stop_folder_scan');
+            await invoke('plugin:filemanager|stop_folder_scan');
             return await this.cleanup();
         } catch (error) {
             console.error('停止扫描失败:', error);
@@ -101,7 +104,7 @@ export class ScannerService {
      */
     static async getProgress(): Promise<ScanProgress> {
         try {
-            return await invoke<ScanProgress>('get_scan_progress');
+            return await invoke<ScanProgress>('plugin:filemanager|get_scan_progress');
         } catch (error) {
             console.error('获取扫描进度失败:', error);
             throw error;
@@ -113,7 +116,7 @@ export class ScannerService {
      */
     static async isScanning(): Promise<boolean> {
         try {
-            return await invoke<boolean>('is_scanning');
+            return await invoke<boolean>('plugin:filemanager|is_scanning');
         } catch (error) {
             console.error('检查扫描状态失败:', error);
             return false;
@@ -125,7 +128,7 @@ export class ScannerService {
      */
     static async getFileStats(path: string): Promise<FileInfo | null> {
         try {
-            const stats = await invoke<FileInfo | null>('get_folder_stats', { path });
+            const stats = await invoke<FileInfo | null>('plugin:filemanager|get_folder_stats', { path });
             return stats;
         } catch (error) {
             console.error('获取文件统计信息失败:', error);
@@ -199,7 +202,7 @@ export class ScannerService {
      */
     static async clearScanData(): Promise<void> {
         try {
-            await invoke('clear_folder_scan');
+            await invoke('plugin:filemanager|clear_folder_scan');
             await this.cleanup();
         } catch (error) {
             console.error('清理扫描数据失败:', error);
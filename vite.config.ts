@@ -20,7 +20,8 @@ export default defineConfig(async () => ({
     ],
     resolve: {
         alias: {
-            '@': fileURLToPath(new URL('./src', import.meta.url))
+            '@': fileURLToPath(new URL('./src', import.meta.url)),
+            'tauri-plugin-filemanager-api': fileURLToPath(new URL('./plugins/tauri-plugin-filemanager/guest-js/index.ts', import.meta.url))
         }
     },
 